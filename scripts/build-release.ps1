@@ -17,6 +17,8 @@ try {
         & (Join-Path $PSScriptRoot "fetch-pdfium.ps1") -Destination $pdfiumPath
     }
 
+    & (Join-Path $PSScriptRoot "generate-licenses.ps1")
+
     Write-Host "Running cargo build --release"
     cargo build --release
 
@@ -0,0 +1,73 @@
+param(
+    [string]$Target = "x86_64-pc-windows-msvc"
+)
+
+Set-StrictMode -Version Latest
+$ErrorActionPreference = "Stop"
+
+$repoRoot = [System.IO.Path]::GetFullPath((Join-Path $PSScriptRoot ".."))
+$outputPath = Join-Path $repoRoot "resources\licenses.tsv"
+
+Push-Location $repoRoot
+try {
+    Write-Host "Collecting crate licenses for $Target"
+    $metadata = cargo metadata --format-version 1 --locked --filter-platform $Target | ConvertFrom-Json
+
+    $packages = @{}
+    foreach ($package in $metadata.packages) {
+        $packages[$package.id] = $package
+    }
+    $nodes = @{}
+    foreach ($node in $metadata.resolve.nodes) {
+        $nodes[$node.id] = $node
+    }
+
+    # Walk the default-feature build graph from the root, skipping dev-dependencies.
+    $rootId = $metadata.resolve.root
+    $seen = @{ $rootId = $true }
+    $queue = New-Object System.Collections.Generic.Queue[string]
+    $queue.Enqueue($rootId)
+    while ($queue.Count -gt 0) {
+        $node = $nodes[$queue.Dequeue()]
+        foreach ($dep in $node.deps) {
+            $runtime = @($dep.dep_kinds | Where-Object { $_.kind -ne "dev" })
+            if ($runtime.Count -eq 0 -or $seen.ContainsKey($dep.pkg)) {
+                continue
+            }
+            $seen[$dep.pkg] = $true
+            $queue.Enqueue($dep.pkg)
+        }
+    }
+
+    $lines = @(
+        "# Third-party crates bundled into doco.exe: name, version, SPDX license, repository.",
+        "# Generated by scripts/generate-licenses.ps1 from ``cargo metadata``; do not edit by hand."
+    )
+    $rows = foreach ($id in $seen.Keys) {
+        if ($id -eq $rootId) {
+            continue
+        }
+        $package = $packages[$id]
+        $license = $package.license
+        if (-not $license) {
+            if ($package.license_file) {
+                $license = "See $($package.license_file | Split-Path -Leaf)"
+            }
+            else {
+                throw "$($package.name) $($package.version) declares no license."
+            }
+        }
+        $repository = if ($package.repository) { $package.repository } elseif ($package.homepage) { $package.homepage } else { "" }
+        [pscustomobject]@{
+            Name = $package.name
+            Line = (@($package.name, $package.version, ($license -replace "/", " OR "), $repository) -join "`t").TrimEnd("`t")
+        }
+    }
+    $lines += $rows | Sort-Object Name, Line | ForEach-Object { $_.Line }
+
+    [System.IO.File]::WriteAllLines($outputPath, $lines)
+    Write-Host ("Wrote {0} crates to {1}" -f ($lines.Count - 2), $outputPath)
+}
+finally {
+    Pop-Location
+}
@@ -6,7 +6,10 @@ use crate::{
     document::export::AutoSaveManager,
     render::perf::emit_startup_marker,
     document::model::DocumentModel,
-    settings::{SettingsStore, schema::Settings},
+    settings::{
+        SettingsStore,
+        schema::{AutoSaveInterval, Settings},
+    },
     theme::ThemeManager,
     window::AppWindow,
 };
@@ -45,7 +48,7 @@ impl Default for AppState {
             show_debug_panel: false,
             status_text: "Ready".to_string(),
             document: DocumentModel::default(),
-            autosave: AutoSaveManager::new(60),
+            autosave: AutoSaveManager::new(AutoSaveInterval::default()),
             settings: Settings::default(),
         }
     }
@@ -140,14 +143,7 @@ impl App {
         state.show_sidebar = state.settings.appearance.show_sidebar;
         state.show_statusbar = state.settings.appearance.show_status_bar;
         state.show_tabs = state.settings.appearance.show_tab_bar;
-        state.autosave = AutoSaveManager::new(
-            state
-                .settings
-                .files
-                .auto_save_interval
-                .as_seconds()
-                .unwrap_or(60),
-        );
+        state.autosave = AutoSaveManager::new(state.settings.files.auto_save_interval);
 
         let total_ms = startup_begin.elapsed().as_millis() as u32;
         startup.finish_startup(total_ms);
@@ -0,0 +1,298 @@
+use std::{fs, path::Path};
+
+use encoding_rs::Encoding;
+
+use crate::document::{
+    DocumentFormat,
+    model::{
+        Block, BlockId, DocumentModel, Indent, LineEnding, Paragraph, ParagraphAlignment,
+        ParagraphSpacing, Run, RunStyle, Table, TableBorders, TableCell, TableRow,
+        TableStylePreset, TextEncoding,
+    },
+    txt::{decode_text, encode_text},
+};
+
+/// Whether `path` names a comma- or tab-separated file.
+pub fn is_delimited_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("csv") || ext.eq_ignore_ascii_case("tsv"))
+}
+
+/// The field separator for `path`: a tab for `.tsv`. A `.csv` uses whichever of comma,
+/// semicolon or tab appears most in its first line, since spreadsheets in locales with a
+/// decimal comma write semicolons.
+pub fn detect_delimiter(path: &Path, text: &str) -> char {
+    let is_tsv = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tsv"));
+    if is_tsv {
+        return '\t';
+    }
+    let first_line = text.lines().next().unwrap_or_default();
+    // Only delimiters outside quoted fields count.
+    let count = |delimiter: char| {
+        let mut in_quotes = false;
+        first_line
+            .chars()
+            .filter(|&ch| {
+                if ch == '"' {
+                    in_quotes = !in_quotes;
+                }
+                ch == delimiter && !in_quotes
+            })
+            .count()
+    };
+    [';', '\t']
+        .into_iter()
+        .filter(|&delimiter| count(delimiter) > count(','))
+        .max_by_key(|&delimiter| count(delimiter))
+        .unwrap_or(',')
+}
+
+/// Splits delimited text into rows of fields following RFC 4180: fields in double quotes
+/// may hold the delimiter, line breaks, and `""` for a literal quote. Rows end at LF or
+/// CRLF, and a final line break doesn't start another row.
+pub fn parse_delimited(text: &str, delimiter: char) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut row_started = false;
+    let mut chars = text
+        .strip_prefix('\u{feff}')
+        .unwrap_or(text)
+        .chars()
+        .peekable();
+    while let Some(ch) = chars.next() {
+        row_started = true;
+        if in_quotes {
+            match ch {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' => in_quotes = false,
+                ch => field.push(ch),
+            }
+            continue;
+        }
+        match ch {
+            '"' if field.is_empty() => in_quotes = true,
+            ch if ch == delimiter => row.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+                row_started = false;
+            }
+            ch => field.push(ch),
+        }
+    }
+    if row_started {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+/// Writes rows as delimited text, quoting only the fields that need it, with CRLF line
+/// breaks as RFC 4180 specifies.
+pub fn write_delimited(rows: &[Vec<String>], delimiter: char) -> String {
+    let mut out = String::new();
+    for row in rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|field| {
+                if field.contains([delimiter, '"', '\r', '\n']) {
+                    format!("\"{}\"", field.replace('"', "\"\""))
+                } else {
+                    field.clone()
+                }
+            })
+            .collect();
+        out.push_str(&fields.join(&delimiter.to_string()));
+        out.push_str("\r\n");
+    }
+    out
+}
+
+/// A document holding `rows` as its one table, first row as the header. Short rows are
+/// padded so every row has a cell per column.
+pub fn table_document(rows: &[Vec<String>]) -> DocumentModel {
+    let columns = rows.iter().map(Vec::len).max().unwrap_or(0).max(1);
+    let mut next_id = 2;
+    let mut cell_paragraph = |text: &str| {
+        let id = BlockId(next_id);
+        next_id += 1;
+        Block::Paragraph(Paragraph {
+            id,
+            runs: vec![Run {
+                text: text.to_string(),
+                style: RunStyle::default(),
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    };
+    let table_rows: Vec<TableRow> = rows
+        .iter()
+        .map(|row| TableRow {
+            cells: (0..columns)
+                .map(|column| TableCell {
+                    blocks: vec![cell_paragraph(row.get(column).map_or("", String::as_str))],
+                    rowspan: 1,
+                    colspan: 1,
+                    background: None,
+                })
+                .collect(),
+        })
+        .collect();
+
+    let mut model = DocumentModel::default();
+    model.content.push(Block::Table(Table {
+        id: BlockId(1),
+        row_heights: vec![28.0; table_rows.len()],
+        rows: table_rows,
+        column_widths: vec![1.0 / columns as f32; columns],
+        borders: TableBorders::default(),
+        style: TableStylePreset::Grid,
+        cell_padding: 8.0,
+        header_row: true,
+        alternating_rows: true,
+        header_repeat: true,
+        caption: None,
+    }));
+    model.metadata.format = DocumentFormat::Text;
+    model
+}
+
+/// The cell text of `model` when it is a single table, as delimited files open; blank
+/// paragraphs around the table are ignored. Paragraphs within a cell join with line breaks.
+pub fn table_rows(model: &DocumentModel) -> Option<Vec<Vec<String>>> {
+    let mut tables = model.content.iter().filter(|block| {
+        !matches!(block, Block::Paragraph(p) if p.runs.iter().all(|run| run.text.trim().is_empty()))
+    });
+    let (Some(Block::Table(table)), None) = (tables.next(), tables.next()) else {
+        return None;
+    };
+    let cell_text = |cell: &TableCell| {
+        cell.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Paragraph(p) => Some(p.runs.iter().map(|run| run.text.as_str()).collect()),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    };
+    Some(
+        table
+            .rows
+            .iter()
+            .map(|row| row.cells.iter().map(cell_text).collect())
+            .collect(),
+    )
+}
+
+/// Opens a CSV or TSV file as a one-table document, remembering the encoding it was read
+/// with so saving writes it back the same way.
+pub fn load_delimited(path: &Path) -> std::io::Result<DocumentModel> {
+    let bytes = fs::read(path)?;
+    let (text, encoding_name) = decode_text(&bytes);
+    let rows = parse_delimited(&text, detect_delimiter(path, &text));
+    let mut model = table_document(&rows);
+    model.metadata.file_path = Some(path.to_path_buf());
+    model.metadata.text_encoding = Some(TextEncoding {
+        encoding_name,
+        // Rows are always written with CRLF, as RFC 4180 specifies.
+        line_ending: LineEnding::CrLf,
+        bom: Encoding::for_bom(&bytes).is_some(),
+    });
+    Ok(model)
+}
+
+/// Saves a single-table document back to delimited text, keeping the delimiter the file
+/// already uses and the encoding it was opened with; other documents save as UTF-8.
+pub fn export_delimited(path: &Path, model: &DocumentModel) -> std::io::Result<()> {
+    let rows = table_rows(model).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "only a document holding a single table can be saved as CSV or TSV",
+        )
+    })?;
+    let (existing, _) = decode_text(&fs::read(path).unwrap_or_default());
+    let text = write_delimited(&rows, detect_delimiter(path, &existing));
+    let bytes = match &model.metadata.text_encoding {
+        Some(encoding) => encode_text(&text, &encoding.encoding_name, encoding.bom),
+        None => text.into_bytes(),
+    };
+    fs::write(path, bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::{
+        detect_delimiter, export_delimited, load_delimited, parse_delimited, table_document,
+        table_rows, write_delimited,
+    };
+
+    #[test]
+    fn quoted_fields_keep_delimiters_quotes_and_line_breaks() {
+        let text = "name,notes\r\n\"Smith, J\",\"said \"\"hi\"\"\r\nthen left\"\r\nplain,\r\n";
+        assert_eq!(
+            parse_delimited(text, ','),
+            vec![
+                vec!["name", "notes"],
+                vec!["Smith, J", "said \"hi\"\r\nthen left"],
+                vec!["plain", ""],
+            ]
+        );
+        assert_eq!(
+            parse_delimited("a\tb\nc\td", '\t'),
+            vec![vec!["a", "b"], vec!["c", "d"]]
+        );
+    }
+
+    #[test]
+    fn delimiter_follows_extension_and_first_line() {
+        assert_eq!(detect_delimiter(Path::new("a.tsv"), "x,y,z"), '\t');
+        assert_eq!(detect_delimiter(Path::new("a.csv"), "x,y;z"), ',');
+        assert_eq!(detect_delimiter(Path::new("a.csv"), "x;y;\"1,5\""), ';');
+    }
+
+    #[test]
+    fn table_documents_save_back_to_the_same_text() {
+        let text =
+            "id,name,notes\r\n1,\"Smith, J\",\"two\nlines\"\r\n2,Lee,\"a \"\"quote\"\"\"\r\n";
+        let model = table_document(&parse_delimited(text, ','));
+        let rows = table_rows(&model).expect("single table");
+        assert_eq!(write_delimited(&rows, ','), text);
+
+        let tsv = "a\tb\r\nc\td e\r\n";
+        let model = table_document(&parse_delimited(tsv, '\t'));
+        assert_eq!(write_delimited(&table_rows(&model).unwrap(), '\t'), tsv);
+    }
+
+    #[test]
+    fn files_save_in_the_encoding_they_were_read_with() {
+        let path = std::env::temp_dir().join(format!("doco-delimited-{}.csv", std::process::id()));
+        // "café;1" in windows-1252, which isn't valid UTF-8.
+        let bytes = b"caf\xe9;1\r\n".to_vec();
+        std::fs::write(&path, &bytes).expect("write csv");
+
+        let model = load_delimited(&path).expect("load csv");
+        assert_eq!(table_rows(&model).unwrap(), vec![vec!["café", "1"]]);
+        export_delimited(&path, &model).expect("save csv");
+        let saved = std::fs::read(&path).expect("read csv");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(saved, bytes);
+    }
+}
@@ -13,9 +13,14 @@ use quick_xml::{
 use zip::ZipArchive;
 
 use crate::document::model::{
+    Anchor,
     Block,
     BlockId,
+    CaptionKind,
+    CrossReference,
+    CrossReferenceDisplay,
     DocumentModel,
+    Footnote,
     Heading,
     ImageAlignment,
     ImageBlock,
@@ -30,15 +35,19 @@ use crate::document::model::{
     PageSize,
     Paragraph,
     ParagraphAlignment,
+    ParagraphBorder,
+    ParagraphFrame,
     ParagraphStyle,
     ParagraphSpacing,
     Run,
     RunStyle,
+    SectionColumns,
     StyleSheet,
     Table,
     TableBorders,
     TableCell,
     TableRow,
+    TextDirection,
 };
 use crate::document::DocumentFormat;
 
@@ -49,6 +58,8 @@ struct ParagraphBuilder {
     alignment: ParagraphAlignment,
     spacing: ParagraphSpacing,
     indent: crate::document::model::Indent,
+    direction: TextDirection,
+    frame: Option<ParagraphFrame>,
     list_type: Option<ListType>,
 }
 
@@ -58,6 +69,8 @@ struct TableBuilder {
     current_row: Vec<String>,
     current_cell_text: String,
     in_cell: bool,
+    /// The first row is marked `w:tblHeader`, so it repeats on continued pages.
+    header_repeat: bool,
 }
 
 #[derive(Debug, Default)]
@@ -162,6 +175,11 @@ pub fn parse_docx(path: &Path) -> std::io::Result<DocumentModel> {
         ));
     }
 
+    if let Some(footnotes_xml) = entries.get("word/footnotes.xml") {
+        document.footnotes = parse_footnotes(footnotes_xml);
+    }
+    document.renumber_footnotes();
+
     extract_images(&entries, &rels, &content_types, &mut document);
     apply_embedded_image_dimensions(&mut document);
     if !header_footer_fragments.is_empty() && document.metadata.title.is_empty() {
@@ -176,35 +194,126 @@ pub fn parse_docx(path: &Path) -> std::io::Result<DocumentModel> {
 
 fn parse_core_metadata(xml: &[u8], doc: &mut DocumentModel) {
     let mut reader = Reader::from_reader(Cursor::new(xml));
-    reader.config_mut().trim_text(true);
+    // Untrimmed, so the spaces around an entity such as `&amp;` survive; the whole value is
+    // trimmed once it ends.
+    reader.config_mut().trim_text(false);
     let mut buf = Vec::new();
     let mut current_tag: Option<String> = None;
+    let mut value = String::new();
 
     loop {
         match reader.read_event_into(&mut buf) {
             Ok(Event::Start(e)) => {
                 current_tag = Some(local_name(e.local_name().as_ref()));
+                value.clear();
             }
             Ok(Event::Text(t)) => {
-                if let Some(tag) = &current_tag {
-                    if let Ok(value) = t.decode() {
-                        match tag.as_str() {
-                            "title" => doc.metadata.title = value.to_string(),
-                            "creator" => doc.metadata.author = value.to_string(),
-                            "created" => doc.metadata.created = parse_datetime(value.as_ref()),
-                            "modified" => doc.metadata.modified = parse_datetime(value.as_ref()),
-                            _ => {}
-                        }
+                if current_tag.is_some()
+                    && let Ok(text) = t.decode()
+                {
+                    value.push_str(&text);
+                }
+            }
+            // Entities such as `&amp;` arrive separately from the text around them.
+            Ok(Event::GeneralRef(r)) => {
+                if current_tag.is_some() {
+                    if let Ok(Some(ch)) = r.resolve_char_ref() {
+                        value.push(ch);
+                    } else if let Ok(name) = r.decode()
+                        && let Some(resolved) = quick_xml::escape::resolve_predefined_entity(&name)
+                    {
+                        value.push_str(resolved);
+                    }
+                }
+            }
+            Ok(Event::End(_)) => {
+                if let Some(tag) = current_tag.take() {
+                    let text = std::mem::take(&mut value).trim().to_string();
+                    match tag.as_str() {
+                        "title" => doc.metadata.title = text,
+                        "creator" => doc.metadata.author = text,
+                        "subject" => doc.metadata.subject = text,
+                        "keywords" => doc.metadata.keywords = text,
+                        "language" if !text.is_empty() => doc.metadata.language = Some(text),
+                        "created" => doc.metadata.created = parse_datetime(&text),
+                        "modified" => doc.metadata.modified = parse_datetime(&text),
+                        _ => {}
+                    }
+                }
+            }
+            Ok(Event::Eof) => break,
+            Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Reads the bodies of `word/footnotes.xml`, keyed by note id. The separator notes Word keeps
+/// in the part carry a `w:type` and are skipped.
+fn parse_footnotes(xml: &[u8]) -> Vec<Footnote> {
+    let mut reader = Reader::from_reader(Cursor::new(xml));
+    reader.config_mut().trim_text(false);
+    let mut buf = Vec::new();
+    let mut notes = Vec::new();
+    let mut current: Option<Footnote> = None;
+    let mut in_text = false;
+
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => match local_name(e.local_name().as_ref()).as_str() {
+                "footnote" if attr_value(&e, "type", reader.decoder()).is_none() => {
+                    current = attr_value(&e, "id", reader.decoder()).map(|label| Footnote {
+                        label,
+                        runs: Vec::new(),
+                    });
+                }
+                "p" => {
+                    if let Some(note) = &mut current
+                        && !note.runs.is_empty()
+                    {
+                        note.runs.push(Run {
+                            text: "\n".to_string(),
+                            style: RunStyle::default(),
+                        });
                     }
                 }
+                "t" => in_text = true,
+                _ => {}
+            },
+            Ok(Event::Text(t)) if in_text => {
+                if let (Some(note), Ok(text)) = (&mut current, t.decode()) {
+                    note.runs.push(Run {
+                        text: text.into_owned(),
+                        style: RunStyle::default(),
+                    });
+                }
             }
-            Ok(Event::End(_)) => current_tag = None,
+            Ok(Event::End(e)) => match local_name(e.local_name().as_ref()).as_str() {
+                "t" => in_text = false,
+                "footnote" => {
+                    if let Some(mut note) = current.take() {
+                        // Word separates the note mark from the body with a leading space.
+                        while let Some(first) = note.runs.first_mut() {
+                            first.text = first.text.trim_start().to_string();
+                            if !first.text.is_empty() {
+                                break;
+                            }
+                            note.runs.remove(0);
+                        }
+                        notes.push(note);
+                    }
+                }
+                _ => {}
+            },
             Ok(Event::Eof) => break,
             Err(_) => break,
             _ => {}
         }
         buf.clear();
     }
+
+    notes
 }
 
 fn parse_styles(xml: &[u8]) -> StyleSheet {
@@ -558,9 +667,15 @@ fn parse_document_xml(
     let mut in_text = false;
     let mut in_run_props = false;
     let mut in_paragraph_props = false;
+    let mut in_paragraph_borders = false;
     let mut in_hyperlink = false;
     let mut current_table: Option<TableBuilder> = None;
     let mut pending_image_size_points: Option<(f32, f32)> = None;
+    // Bookmarks waiting for the paragraph they mark, and the REF field runs are inside.
+    let mut pending_anchors: Vec<String> = Vec::new();
+    let mut field_reference: Option<CrossReference> = None;
+    let mut field_instruction = String::new();
+    let mut in_field_instruction = false;
 
     loop {
         match reader.read_event_into(&mut buf) {
@@ -571,13 +686,20 @@ fn parse_document_xml(
                         paragraph = Some(ParagraphBuilder::default());
                     }
                     "pPr" => in_paragraph_props = true,
+                    "pBdr" if in_paragraph_props => in_paragraph_borders = true,
                     "hyperlink" => in_hyperlink = true,
+                    "fldSimple" => {
+                        field_reference = attr_value(&e, "instr", reader.decoder())
+                            .and_then(|instr| parse_ref_field(&instr));
+                    }
+                    "instrText" => in_field_instruction = true,
                     "r" => {
                         let mut next_run = Run::default();
                         if in_hyperlink {
                             next_run.style.underline = true;
                             next_run.style.color = Some(crate::ui::Color::rgb(0.12, 0.39, 0.91));
                         }
+                        next_run.style.cross_reference = field_reference.clone();
                         run = Some(next_run);
                     }
                     "rPr" => in_run_props = true,
@@ -695,6 +817,13 @@ fn parse_document_xml(
                             }
                         }
                     }
+                    "highlight" if in_run_props => {
+                        if let Some(r) = &mut run {
+                            if let Some(v) = attr_value(&e, "val", reader.decoder()) {
+                                r.style.background = parse_named_highlight(&v);
+                            }
+                        }
+                    }
                     "vertAlign" if in_run_props => {
                         if let Some(r) = &mut run {
                             if let Some(v) = attr_value(&e, "val", reader.decoder()) {
@@ -759,6 +888,7 @@ fn parse_document_xml(
                             left: twips_to_points(attr_value(&e, "left", reader.decoder())),
                         };
                     }
+                    "cols" => doc.metadata.columns = section_columns(&e, reader.decoder()),
                     _ => {}
                 }
             }
@@ -782,6 +912,49 @@ fn parse_document_xml(
                             }
                         }
                     }
+                    "bidi" if in_paragraph_props => {
+                        let rtl = attr_value(&e, "val", reader.decoder())
+                            .is_none_or(|v| !matches!(v.as_str(), "0" | "false" | "off"));
+                        if let Some(p) = &mut paragraph {
+                            p.direction = if rtl {
+                                TextDirection::Rtl
+                            } else {
+                                TextDirection::Ltr
+                            };
+                        }
+                    }
+                    // One border is kept for the whole box; the first side that draws a line
+                    // decides it.
+                    "top" | "left" | "bottom" | "right" | "start" | "end"
+                        if in_paragraph_borders =>
+                    {
+                        let style = attr_value(&e, "val", reader.decoder()).unwrap_or_default();
+                        if let Some(p) = &mut paragraph
+                            && !matches!(style.as_str(), "" | "nil" | "none")
+                        {
+                            let frame = p.frame.get_or_insert_with(ParagraphFrame::default);
+                            if frame.border.is_none() {
+                                let width = attr_value(&e, "sz", reader.decoder())
+                                    .and_then(|v| v.parse::<f32>().ok())
+                                    .map(|eighths| eighths / 8.0)
+                                    .unwrap_or(0.5);
+                                let color = attr_value(&e, "color", reader.decoder())
+                                    .and_then(|v| parse_hex_color(&v))
+                                    .unwrap_or(crate::ui::Color::rgb(0.0, 0.0, 0.0));
+                                frame.border = Some(ParagraphBorder { color, width });
+                            }
+                        }
+                    }
+                    "shd" if in_paragraph_props && !in_run_props => {
+                        let fill = attr_value(&e, "fill", reader.decoder())
+                            .and_then(|v| parse_hex_color(&v));
+                        if let Some(p) = &mut paragraph
+                            && let Some(fill) = fill
+                        {
+                            let frame = p.frame.get_or_insert_with(ParagraphFrame::default);
+                            frame.shading = Some(fill);
+                        }
+                    }
                     "numId" if in_paragraph_props => {
                         if let Some(p) = &mut paragraph {
                             if let Some(num_id) = attr_value(&e, "val", reader.decoder()) {
@@ -829,6 +1002,44 @@ fn parse_document_xml(
                             doc.content.push(Block::PageBreak);
                         }
                     }
+                    "tab" if !in_paragraph_props => {
+                        if let Some(r) = &mut run {
+                            r.text.push('\t');
+                        }
+                    }
+                    "cols" => doc.metadata.columns = section_columns(&e, reader.decoder()),
+                    "tblHeader" => {
+                        let enabled = attr_value(&e, "val", reader.decoder())
+                            .is_none_or(|v| !matches!(v.as_str(), "0" | "false" | "off"));
+                        if let Some(tbl) = &mut current_table
+                            && tbl.rows.is_empty()
+                            && enabled
+                        {
+                            tbl.header_repeat = true;
+                        }
+                    }
+                    // Names starting with "_" are Word's own, such as "_Toc12" and "_GoBack".
+                    "bookmarkStart" => {
+                        if let Some(name) = attr_value(&e, "name", reader.decoder())
+                            && !name.is_empty()
+                            && !name.starts_with('_')
+                        {
+                            pending_anchors.push(name);
+                        }
+                    }
+                    "fldChar" => match attr_value(&e, "fldCharType", reader.decoder()).as_deref() {
+                        Some("begin") => field_instruction.clear(),
+                        Some("separate") => field_reference = parse_ref_field(&field_instruction),
+                        Some("end") => field_reference = None,
+                        _ => {}
+                    },
+                    "footnoteReference" => {
+                        if let (Some(r), Some(id)) = (&mut run, attr_value(&e, "id", reader.decoder())) {
+                            r.text = id.clone();
+                            r.style.superscript = true;
+                            r.style.footnote = Some(id);
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -843,6 +1054,9 @@ fn parse_document_xml(
                         r.text.push_str(&text);
                     }
                 }
+                if in_field_instruction {
+                    field_instruction.push_str(&text);
+                }
                 if let Some(tbl) = &mut current_table {
                     if tbl.in_cell {
                         tbl.current_cell_text.push_str(&text);
@@ -853,9 +1067,12 @@ fn parse_document_xml(
                 let name = local_name(e.local_name().as_ref());
                 match name.as_str() {
                     "pPr" => in_paragraph_props = false,
+                    "pBdr" => in_paragraph_borders = false,
                     "rPr" => in_run_props = false,
                     "t" => in_text = false,
                     "hyperlink" => in_hyperlink = false,
+                    "fldSimple" => field_reference = None,
+                    "instrText" => in_field_instruction = false,
                     "r" => {
                         if let (Some(p), Some(r)) = (&mut paragraph, run.take()) {
                             if !r.text.is_empty() {
@@ -868,8 +1085,28 @@ fn parse_document_xml(
                             if p.runs.is_empty() {
                                 continue;
                             }
+                            // "Figure N" below an image and "Table N" below a table caption
+                            // them; a table caption above its table is claimed at "tbl".
+                            if let Some((kind, caption)) =
+                                split_caption(p.style_id.as_deref(), &p.runs)
+                                && let Some((target, slot)) =
+                                    empty_caption_slot(doc.content.last_mut(), kind)
+                            {
+                                *slot = Some(caption);
+                                doc.anchors
+                                    .extend(pending_anchors.drain(..).map(|name| Anchor {
+                                        name,
+                                        block_id: target,
+                                    }));
+                                continue;
+                            }
 
                             let block_id_now = next_block_id(&mut block_id);
+                            doc.anchors
+                                .extend(pending_anchors.drain(..).map(|name| Anchor {
+                                    name,
+                                    block_id: block_id_now,
+                                }));
                             let is_heading = p
                                 .style_id
                                 .as_ref()
@@ -882,6 +1119,8 @@ fn parse_document_xml(
                                 spacing: p.spacing,
                                 indent: p.indent,
                                 style_id: p.style_id,
+                                direction: p.direction,
+                                frame: p.frame,
                             };
                             apply_resolved_style_to_paragraph(&mut paragraph_block, &doc.styles);
 
@@ -944,6 +1183,8 @@ fn parse_document_xml(
                                                     spacing: ParagraphSpacing::default(),
                                                     indent: Indent::default(),
                                                     style_id: None,
+                                                    direction: Default::default(),
+                                                    frame: None,
                                                 })]
                                             },
                                             rowspan: 1,
@@ -956,16 +1197,36 @@ fn parse_document_xml(
 
                             let col_count = rows.first().map(|r| r.cells.len()).unwrap_or(1);
                             let row_count = rows.len();
+                            let table_id = next_block_id(&mut block_id);
+                            let caption = match doc.content.last() {
+                                Some(Block::Paragraph(p)) => {
+                                    split_caption(p.style_id.as_deref(), &p.runs)
+                                        .filter(|(kind, _)| *kind == CaptionKind::Table)
+                                        .map(|(_, caption)| (p.id, caption))
+                                }
+                                _ => None,
+                            };
+                            let caption = caption.map(|(caption_id, caption)| {
+                                doc.content.pop();
+                                for anchor in &mut doc.anchors {
+                                    if anchor.block_id == caption_id {
+                                        anchor.block_id = table_id;
+                                    }
+                                }
+                                caption
+                            });
                             doc.content.push(Block::Table(Table {
-                                id: next_block_id(&mut block_id),
+                                id: table_id,
                                 rows,
                                 column_widths: vec![120.0; col_count],
                                 row_heights: vec![28.0; row_count],
                                 borders: TableBorders::default(),
                                 style: crate::document::model::TableStylePreset::Grid,
                                 cell_padding: 4.0,
-                                header_row: false,
+                                header_row: tbl.header_repeat,
                                 alternating_rows: false,
+                                header_repeat: tbl.header_repeat,
+                                caption,
                             }));
                         }
                     }
@@ -995,6 +1256,8 @@ fn parse_document_xml(
                 spacing: ParagraphSpacing::default(),
                 indent: Indent::default(),
                 style_id: None,
+                direction: Default::default(),
+                frame: None,
             }));
         }
     }
@@ -1197,7 +1460,7 @@ fn parse_named_highlight(name: &str) -> Option<crate::ui::Color> {
     }
 }
 
-fn decode_image_dimensions(bytes: &[u8], ext: &str) -> (u32, u32) {
+pub(crate) fn decode_image_dimensions(bytes: &[u8], ext: &str) -> (u32, u32) {
     if matches!(ext, "emf" | "wmf") {
         return (0, 0);
     }
@@ -1315,6 +1578,97 @@ fn parse_hex_color(value: &str) -> Option<crate::ui::Color> {
     ))
 }
 
+/// Splits a paragraph in Word's "Caption" style, "Figure 2: A cat", into the kind its label
+/// names and the runs after the label and number, which is renumbered on load.
+fn split_caption(style_id: Option<&str>, runs: &[Run]) -> Option<(CaptionKind, Vec<Run>)> {
+    if !style_id?.eq_ignore_ascii_case("Caption") {
+        return None;
+    }
+    let text = runs.iter().map(|run| run.text.as_str()).collect::<String>();
+    let start = text.trim_start();
+    let kind = [CaptionKind::Figure, CaptionKind::Table]
+        .into_iter()
+        .find(|kind| {
+            start
+                .get(..kind.label().len())
+                .is_some_and(|word| word.eq_ignore_ascii_case(kind.label()))
+        })?;
+    let rest = start[kind.label().len()..].trim_start();
+    if !rest.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    // Chapter-numbered captions read "Figure 2-1" or "Figure 2.1".
+    let rest = rest
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c == '.' || c == '-')
+        .trim_start();
+    let rest = rest
+        .strip_prefix([':', '.', '-', '\u{2013}', '\u{2014}'])
+        .unwrap_or(rest)
+        .trim_start();
+    let mut cut = text.len() - rest.len();
+    let mut caption = Vec::new();
+    for run in runs {
+        if cut >= run.text.len() {
+            cut -= run.text.len();
+            continue;
+        }
+        caption.push(Run {
+            text: run.text[cut..].to_string(),
+            style: run.style.clone(),
+        });
+        cut = 0;
+    }
+    Some((kind, caption))
+}
+
+/// The caption of `block` and its id, when it's an uncaptioned image for a figure caption or
+/// an uncaptioned table for a table caption.
+fn empty_caption_slot(
+    block: Option<&mut Block>,
+    kind: CaptionKind,
+) -> Option<(BlockId, &mut Option<Vec<Run>>)> {
+    match (block?, kind) {
+        (Block::Image(image), CaptionKind::Figure) if image.caption.is_none() => {
+            Some((image.id, &mut image.caption))
+        }
+        (Block::Table(table), CaptionKind::Table) if table.caption.is_none() => {
+            Some((table.id, &mut table.caption))
+        }
+        _ => None,
+    }
+}
+
+/// Reads a ` REF name \h ` field instruction as a cross-reference to bookmark `name`; the
+/// `\r`, `\n` and `\w` switches show its paragraph number instead of its text.
+fn parse_ref_field(instruction: &str) -> Option<CrossReference> {
+    let mut words = instruction.split_whitespace();
+    if !words.next()?.eq_ignore_ascii_case("REF") {
+        return None;
+    }
+    let anchor = words.next()?.to_string();
+    let display = if words.any(|switch| matches!(switch, "\\r" | "\\n" | "\\w")) {
+        CrossReferenceDisplay::Number
+    } else {
+        CrossReferenceDisplay::Text
+    };
+    Some(CrossReference { anchor, display })
+}
+
+/// A section's `w:cols`, with Word's defaults of one column half an inch apart filling in.
+fn section_columns(
+    event: &BytesStart<'_>,
+    decoder: quick_xml::encoding::Decoder,
+) -> SectionColumns {
+    let count = attr_value(event, "num", decoder)
+        .and_then(|v| v.parse::<u8>().ok())
+        .unwrap_or(1);
+    let mut columns = SectionColumns::new(count);
+    if let Some(space) = attr_value(event, "space", decoder) {
+        columns.spacing = twips_to_points(Some(space));
+    }
+    columns
+}
+
 fn twips_to_points(value: Option<String>) -> f32 {
     value
         .and_then(|v| v.parse::<f32>().ok())
@@ -1353,7 +1707,7 @@ fn next_block_id(counter: &mut u64) -> BlockId {
     BlockId(id)
 }
 
-fn default_mime_for_ext(ext: &str) -> &'static str {
+pub(crate) fn default_mime_for_ext(ext: &str) -> &'static str {
     match ext {
         "png" => "image/png",
         "jpg" | "jpeg" => "image/jpeg",
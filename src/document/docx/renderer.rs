@@ -299,6 +299,8 @@ impl DocxRenderEngine {
                     },
                     indent: crate::document::model::Indent::default(),
                     style_id: Some(format!("Heading{}", h.level)),
+                    direction: Default::default(),
+                    frame: None,
                 };
                 self.layout_paragraph(&fake, cfg, width, 14.0 + ((6 - h.level.min(6)) as f32))
             }
@@ -434,9 +436,10 @@ impl DocxRenderEngine {
         let mut max_line_height = (cfg.default_font_size.max(fallback_size) * 1.35).max(8.0);
 
         for run in merge_similar_runs(&paragraph.runs) {
-            let size = run.style.font_size.unwrap_or(fallback_size).max(8.0);
-            let line_h = size * 1.35;
+            let base_size = run.style.font_size.unwrap_or(fallback_size).max(8.0);
+            let line_h = base_size * 1.35;
             max_line_height = max_line_height.max(line_h);
+            let (size, baseline_shift) = vertical_align_metrics(&run.style, base_size);
             for (idx, part) in run.text.split('\n').enumerate() {
                 if idx > 0 {
                     x = paragraph.indent.left;
@@ -456,11 +459,18 @@ impl DocxRenderEngine {
 
                 let rect = Rect {
                     x,
-                    y,
+                    y: y + baseline_shift,
                     width: token_w.min(max_width),
-                    height: line_h,
+                    height: size * 1.35,
                 };
                 let format_id = self.resolve_text_format(style_key(&run, paragraph).as_str());
+                if let Some(background) = run.style.background {
+                    draw.push(DrawCommand::Rect {
+                        rect,
+                        fill: Some(background),
+                        stroke: None,
+                    });
+                }
                 draw.push(DrawCommand::Text {
                     text: part.to_string(),
                     rect,
@@ -469,7 +479,7 @@ impl DocxRenderEngine {
                     italic: run.style.italic,
                     underline: run.style.underline,
                     color: run.style.color,
-                    alignment: paragraph.alignment.clone(),
+                    alignment: paragraph.alignment.visual(paragraph.direction),
                     format_id,
                 });
 
@@ -513,7 +523,7 @@ impl DocxRenderEngine {
         for (offset, row) in table.rows[row_start..row_start + row_count].iter().enumerate() {
             let idx = row_start + offset;
             let row_h = row_heights[idx];
-            if table.header_row && idx == 0 {
+            if let Some(fill) = table.row_fill(idx) {
                 draw.push(DrawCommand::Rect {
                     rect: Rect {
                         x: 0.0,
@@ -521,18 +531,7 @@ impl DocxRenderEngine {
                         width,
                         height: row_h,
                     },
-                    fill: Some(Color::rgb(0.84, 0.9, 0.98)),
-                    stroke: None,
-                });
-            } else if table.alternating_rows && idx % 2 == 1 {
-                draw.push(DrawCommand::Rect {
-                    rect: Rect {
-                        x: 0.0,
-                        y,
-                        width,
-                        height: row_h,
-                    },
-                    fill: Some(Color::rgba(0.7, 0.74, 0.8, 0.14)),
+                    fill: Some(fill),
                     stroke: None,
                 });
             }
@@ -725,17 +724,30 @@ pub fn draw_docx(model: &DocumentModel) -> Vec<LaidOutPage> {
 
 fn style_key(run: &Run, paragraph: &Paragraph) -> String {
     format!(
-        "{}|{}|{}|{}|{}|{:?}|{:?}",
+        "{}|{}|{}|{}|{}|{}|{}|{:?}|{:?}",
         run.style.font_family.clone().unwrap_or_else(|| "Segoe UI".to_string()),
         run.style.font_size.unwrap_or(12.0),
         run.style.bold,
         run.style.italic,
         run.style.underline,
+        run.style.superscript,
+        run.style.subscript,
         run.style.color,
         paragraph.style_id
     )
 }
 
+// Superscript/subscript runs draw at a reduced size, shifted off the line's baseline.
+fn vertical_align_metrics(style: &RunStyle, size: f32) -> (f32, f32) {
+    if style.superscript {
+        (size * 0.65, -size * 0.1)
+    } else if style.subscript {
+        (size * 0.65, size * 0.4)
+    } else {
+        (size, 0.0)
+    }
+}
+
 fn effective_margins(doc: &DocumentModel, cfg: &RenderConfig) -> (f32, f32, f32, f32) {
     let fallback = cfg.margin.max(8.0);
     let top = if doc.metadata.margins.top > 0.0 {
@@ -923,6 +935,9 @@ fn run_style_eq(a: &RunStyle, b: &RunStyle) -> bool {
         && a.background == b.background
         && a.superscript == b.superscript
         && a.subscript == b.subscript
+        && a.math == b.math
+        && a.wiki_link == b.wiki_link
+        && a.cross_reference == b.cross_reference
 }
 
 fn is_hyperlink_style(style: &RunStyle) -> bool {
@@ -1245,6 +1260,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn shrinks_and_offsets_script_runs() {
+        let mut doc = DocumentModel::default();
+        let mut block = simple_paragraph(1, "E = mc", false);
+        if let Block::Paragraph(p) = &mut block {
+            p.runs.push(Run {
+                text: "2".to_string(),
+                style: RunStyle {
+                    superscript: true,
+                    ..RunStyle::default()
+                },
+            });
+            p.runs.push(Run {
+                text: "n".to_string(),
+                style: RunStyle {
+                    subscript: true,
+                    ..RunStyle::default()
+                },
+            });
+        }
+        doc.content.push(block);
+
+        let mut engine = DocxRenderEngine::default();
+        let pages = engine.paginate(&doc, &RenderConfig::default());
+        let texts = pages[0].blocks[0]
+            .draw
+            .iter()
+            .filter_map(|cmd| match cmd {
+                DrawCommand::Text { text, rect, size, .. } => Some((text.clone(), rect.y, *size)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let base = texts.iter().find(|(t, _, _)| t == "E = mc").expect("base run");
+        let sup = texts.iter().find(|(t, _, _)| t == "2").expect("superscript run");
+        let sub = texts.iter().find(|(t, _, _)| t == "n").expect("subscript run");
+        assert!(sup.2 < base.2 && sub.2 < base.2);
+        assert!(sup.1 < base.1);
+        assert!(sub.1 > base.1);
+    }
+
+    #[test]
+    fn highlight_draws_behind_text() {
+        let mut doc = DocumentModel::default();
+        let mut block = simple_paragraph(1, "marked", false);
+        if let Block::Paragraph(p) = &mut block {
+            p.runs[0].style.background = Some(Color::rgb(1.0, 0.95, 0.36));
+        }
+        doc.content.push(block);
+
+        let mut engine = DocxRenderEngine::default();
+        let pages = engine.paginate(&doc, &RenderConfig::default());
+        let draw = &pages[0].blocks[0].draw;
+        let fill_idx = draw
+            .iter()
+            .position(|cmd| matches!(cmd, DrawCommand::Rect { fill: Some(_), .. }))
+            .expect("highlight rect");
+        let text_idx = draw
+            .iter()
+            .position(|cmd| matches!(cmd, DrawCommand::Text { .. }))
+            .expect("text command");
+        assert!(fill_idx < text_idx);
+    }
+
     fn simple_paragraph(id: u64, text: &str, link_style: bool) -> Block {
         Block::Paragraph(Paragraph {
             id: BlockId(id),
@@ -1268,6 +1347,8 @@ mod tests {
             },
             indent: Default::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         })
     }
 }
@@ -5,13 +5,26 @@ use std::{
     path::Path,
 };
 
+use chrono::{DateTime, SecondsFormat, Utc};
 use regex::Regex;
 use zip::{CompressionMethod, ZipArchive, ZipWriter, write::SimpleFileOptions};
 
-use crate::document::model::{
-    Block, DocumentModel, ImageData, ListType, Paragraph, Run, TableStylePreset,
+use crate::{
+    document::model::{
+        Block, BlockId, CrossReferenceDisplay, DocumentModel, ImageData, ListType, Paragraph,
+        ParagraphFrame, Run, RunStyle, SectionColumns, TableStylePreset, TextDirection,
+    },
+    editor::{
+        caption::{CaptionNumber, caption_number_of, caption_of, number_captions},
+        outline::toc_range,
+    },
 };
 
+/// Runs opening a Word TOC field over heading levels 1-6 with hyperlinked entries. The TOC
+/// paragraphs after it are the field's cached result, shown until Word updates the field.
+const TOC_FIELD_BEGIN: &str = "<w:r><w:fldChar w:fldCharType=\"begin\"/></w:r><w:r><w:instrText xml:space=\"preserve\"> TOC \\o \"1-6\" \\h \\z \\u </w:instrText></w:r><w:r><w:fldChar w:fldCharType=\"separate\"/></w:r>";
+const TOC_FIELD_END: &str = "<w:r><w:fldChar w:fldCharType=\"end\"/></w:r>";
+
 #[derive(Debug, Clone)]
 struct ImageAsset {
     key: String,
@@ -26,6 +39,7 @@ struct PackageSnapshot {
     preserved: Vec<(String, Vec<u8>)>,
     content_types: Option<String>,
     document_rels: Option<String>,
+    root_rels: Option<String>,
 }
 
 pub fn write_docx(path: &Path, model: &DocumentModel) -> io::Result<()> {
@@ -83,6 +97,9 @@ fn read_package_snapshot(path: &Path) -> io::Result<PackageSnapshot> {
             "word/_rels/document.xml.rels" => {
                 snapshot.document_rels = String::from_utf8(bytes).ok();
             }
+            "_rels/.rels" => {
+                snapshot.root_rels = String::from_utf8(bytes).ok();
+            }
             _ => {
                 if should_preserve_entry(name.as_str()) {
                     snapshot.preserved.push((name, bytes));
@@ -98,7 +115,12 @@ fn read_package_snapshot(path: &Path) -> io::Result<PackageSnapshot> {
 fn should_preserve_entry(name: &str) -> bool {
     !matches!(
         name,
-        "[Content_Types].xml" | "word/document.xml" | "word/_rels/document.xml.rels"
+        "[Content_Types].xml"
+            | "word/document.xml"
+            | "word/_rels/document.xml.rels"
+            | "_rels/.rels"
+            | "docProps/core.xml"
+            | "word/footnotes.xml"
     ) && !name.starts_with("word/media/")
 }
 
@@ -132,8 +154,9 @@ fn write_package(
         .map(|asset| (asset.key.clone(), asset.rel_id.clone()))
         .collect::<HashMap<_, _>>();
     let document_xml = document_xml(model, &image_rel_map);
-    let content_types = content_types_xml(snapshot.content_types.as_deref(), images);
-    let doc_rels = document_rels_xml(snapshot.document_rels.as_deref(), images);
+    let has_footnotes = !model.footnotes.is_empty();
+    let content_types = content_types_xml(snapshot.content_types.as_deref(), images, has_footnotes);
+    let doc_rels = document_rels_xml(snapshot.document_rels.as_deref(), images, has_footnotes);
 
     let file = File::create(output_path)?;
     let mut zip = ZipWriter::new(file);
@@ -146,14 +169,11 @@ fn write_package(
         zip.write_all(bytes)?;
     }
 
-    if !snapshot
-        .preserved
-        .iter()
-        .any(|(name, _)| name == "_rels/.rels")
-    {
-        zip.start_file("_rels/.rels", options)?;
-        zip.write_all(root_rels_xml().as_bytes())?;
-    }
+    zip.start_file("_rels/.rels", options)?;
+    zip.write_all(root_rels_xml_with_core(snapshot.root_rels.as_deref()).as_bytes())?;
+
+    zip.start_file("docProps/core.xml", options)?;
+    zip.write_all(core_properties_xml(model).as_bytes())?;
 
     if !snapshot
         .preserved
@@ -173,6 +193,11 @@ fn write_package(
     zip.start_file("word/_rels/document.xml.rels", options)?;
     zip.write_all(doc_rels.as_bytes())?;
 
+    if has_footnotes {
+        zip.start_file("word/footnotes.xml", options)?;
+        zip.write_all(footnotes_xml(model).as_bytes())?;
+    }
+
     for image in images {
         let entry = format!("word/media/{}", image.file_name);
         zip.start_file(entry, options)?;
@@ -183,14 +208,24 @@ fn write_package(
     Ok(())
 }
 
-fn content_types_xml(existing: Option<&str>, images: &[ImageAsset]) -> String {
+const FOOTNOTES_CONTENT_TYPE_OVERRIDE: &str = "<Override PartName=\"/word/footnotes.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.footnotes+xml\"/>";
+const FOOTNOTES_REL_TYPE: &str = "http://schemas.openxmlformats.org/officeDocument/2006/relationships/footnotes";
+
+fn content_types_xml(existing: Option<&str>, images: &[ImageAsset], has_footnotes: bool) -> String {
     let image_exts = images
         .iter()
         .map(|img| ext_from_mime(img.mime.as_str()).to_string())
         .collect::<BTreeSet<_>>();
 
     if let Some(existing_xml) = existing {
-        let mut out = existing_xml.to_string();
+        // The footnotes part is rewritten from the model, so its override follows the model too.
+        let mut out = match Regex::new(r#"<Override\b[^>]*PartName="/word/footnotes\.xml"[^>]*/>"#) {
+            Ok(re) => re.replace_all(existing_xml, "").into_owned(),
+            Err(_) => existing_xml.to_string(),
+        };
+        if has_footnotes {
+            out = insert_before_types_end(out, FOOTNOTES_CONTENT_TYPE_OVERRIDE);
+        }
         for ext in image_exts {
             let probe = format!("Extension=\"{ext}\"");
             if !out.contains(probe.as_str()) {
@@ -213,6 +248,12 @@ fn content_types_xml(existing: Option<&str>, images: &[ImageAsset]) -> String {
                 "<Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>",
             );
         }
+        if !out.contains("PartName=\"/docProps/core.xml\"") {
+            out = insert_before_types_end(
+                out,
+                "<Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>",
+            );
+        }
         return out;
     }
 
@@ -227,8 +268,12 @@ fn content_types_xml(existing: Option<&str>, images: &[ImageAsset]) -> String {
         ));
     }
 
+    if has_footnotes {
+        defaults.push(FOOTNOTES_CONTENT_TYPE_OVERRIDE.to_string());
+    }
+
     format!(
-        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\n{}\n<Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\n<Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>\n</Types>",
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<Types xmlns=\"http://schemas.openxmlformats.org/package/2006/content-types\">\n{}\n<Override PartName=\"/word/document.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.document.main+xml\"/>\n<Override PartName=\"/word/styles.xml\" ContentType=\"application/vnd.openxmlformats-officedocument.wordprocessingml.styles+xml\"/>\n<Override PartName=\"/docProps/core.xml\" ContentType=\"application/vnd.openxmlformats-package.core-properties+xml\"/>\n</Types>",
         defaults.join("\n")
     )
 }
@@ -241,14 +286,14 @@ fn insert_before_types_end(mut xml: String, snippet: &str) -> String {
     xml
 }
 
-fn document_rels_xml(existing: Option<&str>, images: &[ImageAsset]) -> String {
+fn document_rels_xml(existing: Option<&str>, images: &[ImageAsset], has_footnotes: bool) -> String {
     let mut kept = Vec::new();
     if let Some(existing_xml) = existing
         && let Ok(re) = Regex::new(r#"<Relationship\b[^>]*/>"#)
     {
         for m in re.find_iter(existing_xml) {
             let rel = m.as_str();
-            if rel.contains("/relationships/image\"") {
+            if rel.contains("/relationships/image\"") || rel.contains(FOOTNOTES_REL_TYPE) {
                 continue;
             }
             kept.push(rel.to_string());
@@ -262,6 +307,11 @@ fn document_rels_xml(existing: Option<&str>, images: &[ImageAsset]) -> String {
             image.rel_id, image.file_name
         ));
     }
+    if has_footnotes {
+        kept.push(format!(
+            "<Relationship Id=\"rIdDocoFootnotes\" Type=\"{FOOTNOTES_REL_TYPE}\" Target=\"footnotes.xml\"/>"
+        ));
+    }
 
     format!(
         "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>\n<Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">\n{}\n</Relationships>",
@@ -269,6 +319,60 @@ fn document_rels_xml(existing: Option<&str>, images: &[ImageAsset]) -> String {
     )
 }
 
+const CORE_PROPERTIES_REL_TYPE: &str =
+    "http://schemas.openxmlformats.org/package/2006/relationships/metadata/core-properties";
+
+/// Root relationships, keeping an existing package's entries and adding the core properties
+/// part when it is missing.
+fn root_rels_xml_with_core(existing: Option<&str>) -> String {
+    let xml = existing.unwrap_or(root_rels_xml()).to_string();
+    if xml.contains(CORE_PROPERTIES_REL_TYPE) {
+        return xml;
+    }
+    let relationship = format!(
+        "  <Relationship Id=\"rIdDocoCore\" Type=\"{CORE_PROPERTIES_REL_TYPE}\" Target=\"docProps/core.xml\"/>\n"
+    );
+    match xml.rfind("</Relationships>") {
+        Some(idx) => {
+            let mut out = xml;
+            out.insert_str(idx, &relationship);
+            out
+        }
+        None => xml,
+    }
+}
+
+fn core_properties_xml(model: &DocumentModel) -> String {
+    let now = Utc::now();
+    let meta = &model.metadata;
+    let mut fields = String::new();
+    let language = meta.language.clone().unwrap_or_default();
+    for (tag, value) in [
+        ("dc:title", &meta.title),
+        ("dc:subject", &meta.subject),
+        ("dc:creator", &meta.author),
+        ("cp:keywords", &meta.keywords),
+        ("dc:language", &language),
+    ] {
+        if !value.trim().is_empty() {
+            fields.push_str(&format!("  <{tag}>{}</{tag}>\n", escape_xml(value.trim())));
+        }
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>
+<cp:coreProperties xmlns:cp=\"http://schemas.openxmlformats.org/package/2006/metadata/core-properties\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:dcterms=\"http://purl.org/dc/terms/\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\">
+{fields}  <dcterms:created xsi:type=\"dcterms:W3CDTF\">{}</dcterms:created>
+  <dcterms:modified xsi:type=\"dcterms:W3CDTF\">{}</dcterms:modified>
+</cp:coreProperties>",
+        w3c_datetime(meta.created.unwrap_or(now)),
+        w3c_datetime(now)
+    )
+}
+
+fn w3c_datetime(value: DateTime<Utc>) -> String {
+    value.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
 fn root_rels_xml() -> &'static str {
     "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>
 <Relationships xmlns=\"http://schemas.openxmlformats.org/package/2006/relationships\">
@@ -285,21 +389,134 @@ fn default_styles_xml() -> &'static str {
   <w:style w:type=\"paragraph\" w:styleId=\"Heading1\"><w:name w:val=\"heading 1\"/></w:style>
   <w:style w:type=\"paragraph\" w:styleId=\"Heading2\"><w:name w:val=\"heading 2\"/></w:style>
   <w:style w:type=\"paragraph\" w:styleId=\"Heading3\"><w:name w:val=\"heading 3\"/></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TOC1\"><w:name w:val=\"toc 1\"/><w:pPr><w:tabs><w:tab w:val=\"right\" w:leader=\"dot\" w:pos=\"9350\"/></w:tabs></w:pPr></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TOC2\"><w:name w:val=\"toc 2\"/><w:basedOn w:val=\"TOC1\"/></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TOC3\"><w:name w:val=\"toc 3\"/><w:basedOn w:val=\"TOC1\"/></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TOC4\"><w:name w:val=\"toc 4\"/><w:basedOn w:val=\"TOC1\"/></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TOC5\"><w:name w:val=\"toc 5\"/><w:basedOn w:val=\"TOC1\"/></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TOC6\"><w:name w:val=\"toc 6\"/><w:basedOn w:val=\"TOC1\"/></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"Caption\"><w:name w:val=\"caption\"/><w:basedOn w:val=\"Normal\"/><w:rPr><w:i/><w:sz w:val=\"18\"/></w:rPr></w:style>
+  <w:style w:type=\"paragraph\" w:styleId=\"TableofFigures\"><w:name w:val=\"table of figures\"/><w:pPr><w:tabs><w:tab w:val=\"right\" w:leader=\"dot\" w:pos=\"9350\"/></w:tabs></w:pPr></w:style>
 </w:styles>"
 }
 
 fn document_xml(model: &DocumentModel, image_rel_map: &HashMap<String, String>) -> String {
+    // Blocks a run links to get a bookmark for the hyperlink to anchor on.
+    let link_targets = model
+        .content
+        .iter()
+        .flat_map(|block| match block {
+            Block::Paragraph(p) => p.runs.as_slice(),
+            Block::Heading(h) => h.runs.as_slice(),
+            _ => &[],
+        })
+        .filter_map(|run| run.style.internal_link.map(|id| id.0))
+        .collect::<BTreeSet<_>>();
+    let toc = toc_range(&model.content);
+    // Named anchors get bookmarks of their own, numbered past the block ids link bookmarks use.
+    let mut anchor_bookmark_id = model.next_block_id().0;
+    let captions = number_captions(&model.content);
+
     let mut body = String::new();
-    for block in &model.content {
-        body.push_str(block_xml(block, image_rel_map).as_str());
+    for (index, block) in model.content.iter().enumerate() {
+        let mut xml = block_xml(block, image_rel_map);
+        if let Some(caption) = caption_xml(&captions, block) {
+            // Word puts table captions above the table and figure captions below.
+            if matches!(block, Block::Table(_)) {
+                xml.insert_str(0, &caption);
+            } else {
+                xml.push_str(&caption);
+            }
+        }
+        if let Some(toc) = &toc {
+            if index == toc.start {
+                xml = xml.replacen("</w:pPr>", &format!("</w:pPr>{TOC_FIELD_BEGIN}"), 1);
+            }
+            if index + 1 == toc.end
+                && let Some(close) = xml.rfind("</w:p>")
+            {
+                xml.insert_str(close, TOC_FIELD_END);
+            }
+        }
+        let block_id = match block {
+            Block::Paragraph(p) => Some(p.id),
+            Block::Heading(h) => Some(h.id),
+            Block::Image(img) => Some(img.id),
+            Block::Table(table) => Some(table.id),
+            _ => None,
+        };
+        for anchor in model
+            .anchors
+            .iter()
+            .filter(|anchor| Some(anchor.block_id) == block_id)
+        {
+            let id = anchor_bookmark_id;
+            anchor_bookmark_id += 1;
+            xml = format!(
+                "<w:bookmarkStart w:id=\"{id}\" w:name=\"{}\"/>{xml}<w:bookmarkEnd w:id=\"{id}\"/>",
+                escape_xml(&anchor.name)
+            );
+        }
+        let target = block_id
+            .map(|id| id.0)
+            .filter(|id| link_targets.contains(id));
+        if let Some(id) = target {
+            let name = bookmark_name(BlockId(id));
+            body.push_str(format!("<w:bookmarkStart w:id=\"{id}\" w:name=\"{name}\"/>").as_str());
+            body.push_str(xml.as_str());
+            body.push_str(format!("<w:bookmarkEnd w:id=\"{id}\"/>").as_str());
+        } else {
+            body.push_str(xml.as_str());
+        }
     }
 
     format!(
         "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>
 <w:document xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\" xmlns:r=\"http://schemas.openxmlformats.org/officeDocument/2006/relationships\" xmlns:wp=\"http://schemas.openxmlformats.org/drawingml/2006/wordprocessingDrawing\" xmlns:a=\"http://schemas.openxmlformats.org/drawingml/2006/main\" xmlns:pic=\"http://schemas.openxmlformats.org/drawingml/2006/picture\">
-  <w:body>{}<w:sectPr/></w:body>
+  <w:body>{}{}</w:body>
 </w:document>",
-        body
+        body,
+        section_properties_xml(&model.metadata.columns)
+    )
+}
+
+/// The body's closing `w:sectPr`, holding its columns when there's more than one.
+fn section_properties_xml(columns: &SectionColumns) -> String {
+    if columns.count() < 2 {
+        return "<w:sectPr/>".to_string();
+    }
+    format!(
+        "<w:sectPr><w:cols w:num=\"{}\" w:space=\"{}\"/></w:sectPr>",
+        columns.count(),
+        (columns.spacing * 20.0).round() as i32
+    )
+}
+
+/// `word/footnotes.xml` with Word's two separator notes followed by the model's notes, whose
+/// ids are their 1-based numbers.
+fn footnotes_xml(model: &DocumentModel) -> String {
+    let mut notes = String::new();
+    for (index, note) in model.footnotes.iter().enumerate() {
+        notes.push_str(format!("<w:footnote w:id=\"{}\"><w:p>", index + 1).as_str());
+        notes.push_str("<w:r><w:rPr><w:vertAlign w:val=\"superscript\"/></w:rPr><w:footnoteRef/></w:r>");
+        notes.push_str("<w:r><w:t xml:space=\"preserve\"> </w:t></w:r>");
+        for run in &note.runs {
+            if run.text == "\n" {
+                notes.push_str("</w:p><w:p>");
+            } else {
+                notes.push_str(run_xml(run).as_str());
+            }
+        }
+        notes.push_str("</w:p></w:footnote>");
+    }
+
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"yes\"?>
+<w:footnotes xmlns:w=\"http://schemas.openxmlformats.org/wordprocessingml/2006/main\">
+  <w:footnote w:type=\"separator\" w:id=\"-1\"><w:p><w:r><w:separator/></w:r></w:p></w:footnote>
+  <w:footnote w:type=\"continuationSeparator\" w:id=\"0\"><w:p><w:r><w:continuationSeparator/></w:r></w:p></w:footnote>
+  {notes}
+</w:footnotes>"
     )
 }
 
@@ -314,6 +531,8 @@ fn block_xml(block: &Block, image_rel_map: &HashMap<String, String>) -> String {
                 spacing: crate::document::model::ParagraphSpacing::default(),
                 indent: crate::document::model::Indent::default(),
                 style_id: Some(format!("Heading{}", h.level.clamp(1, 6))),
+                direction: Default::default(),
+                frame: None,
             };
             paragraph_xml(&paragraph)
         }
@@ -331,6 +550,8 @@ fn block_xml(block: &Block, image_rel_map: &HashMap<String, String>) -> String {
                 spacing: crate::document::model::ParagraphSpacing::default(),
                 indent: crate::document::model::Indent::default(),
                 style_id: None,
+                direction: Default::default(),
+                frame: None,
             };
             paragraph_xml(&paragraph)
         }
@@ -364,6 +585,8 @@ fn block_xml(block: &Block, image_rel_map: &HashMap<String, String>) -> String {
                     spacing: crate::document::model::ParagraphSpacing::default(),
                     indent: crate::document::model::Indent::default(),
                     style_id: None,
+                    direction: Default::default(),
+                    frame: None,
                 };
                 out.push_str(paragraph_xml(&paragraph).as_str());
             }
@@ -388,9 +611,14 @@ fn block_xml(block: &Block, image_rel_map: &HashMap<String, String>) -> String {
                     .copied()
                     .unwrap_or(28.0)
                     .max(18.0);
+                let header = if row_idx == 0 && table.header_repeat {
+                    "<w:tblHeader/>"
+                } else {
+                    ""
+                };
                 out.push_str(
                     format!(
-                        "<w:tr><w:trPr><w:trHeight w:val=\"{}\" w:hRule=\"atLeast\"/></w:trPr>",
+                        "<w:tr><w:trPr><w:trHeight w:val=\"{}\" w:hRule=\"atLeast\"/>{header}</w:trPr>",
                         (row_h * 20.0).round() as i32
                     )
                     .as_str(),
@@ -465,6 +693,8 @@ fn paragraph_xml(p: &Paragraph) -> String {
     out.push_str("<w:p>");
 
     let mut has_ppr = p.style_id.is_some()
+        || p.frame.is_some()
+        || p.direction == TextDirection::Rtl
         || !matches!(
             p.alignment,
             crate::document::model::ParagraphAlignment::Left
@@ -480,6 +710,12 @@ fn paragraph_xml(p: &Paragraph) -> String {
         if let Some(style) = &p.style_id {
             out.push_str(format!("<w:pStyle w:val=\"{}\"/>", escape_xml(style)).as_str());
         }
+        if let Some(frame) = &p.frame {
+            out.push_str(paragraph_frame_xml(frame).as_str());
+        }
+        if p.direction == TextDirection::Rtl {
+            out.push_str("<w:bidi/>");
+        }
         let align = match p.alignment {
             crate::document::model::ParagraphAlignment::Left => None,
             crate::document::model::ParagraphAlignment::Center => Some("center"),
@@ -525,8 +761,84 @@ fn paragraph_xml(p: &Paragraph) -> String {
     out
 }
 
+/// A "Caption" paragraph for `block`: its label, the number as a SEQ field Word keeps
+/// counting, then the caption text.
+fn caption_xml(captions: &[CaptionNumber], block: &Block) -> Option<String> {
+    let number = caption_number_of(captions, block)?;
+    let (_, runs) = caption_of(block)?;
+    let label = number.kind.label();
+    let mut out = format!(
+        "<w:p><w:pPr><w:pStyle w:val=\"Caption\"/></w:pPr><w:r><w:t xml:space=\"preserve\">{label} </w:t></w:r><w:fldSimple w:instr=\" SEQ {label} \\* ARABIC \"><w:r><w:t>{}</w:t></w:r></w:fldSimple>",
+        number.number
+    );
+    if runs.iter().any(|run| !run.text.trim().is_empty()) {
+        out.push_str("<w:r><w:t xml:space=\"preserve\">: </w:t></w:r>");
+        for run in runs {
+            out.push_str(run_xml(run).as_str());
+        }
+    }
+    out.push_str("</w:p>");
+    Some(out)
+}
+
+/// Bookmark a hyperlink to `id` anchors on, named the way Word names its TOC targets.
+fn bookmark_name(id: BlockId) -> String {
+    format!("_Toc{}", id.0)
+}
+
 fn run_xml(run: &Run) -> String {
     let mut out = String::new();
+    if let Some(reference) = &run.style.cross_reference {
+        let plain = Run {
+            text: run.text.clone(),
+            style: RunStyle {
+                cross_reference: None,
+                ..run.style.clone()
+            },
+        };
+        // `\r` asks Word for the target's paragraph number instead of its text.
+        let switches = match reference.display {
+            CrossReferenceDisplay::Text => "\\h",
+            CrossReferenceDisplay::Number => "\\r \\h",
+        };
+        out.push_str(
+            format!(
+                "<w:fldSimple w:instr=\" REF {} {switches} \">",
+                escape_xml(&reference.anchor)
+            )
+            .as_str(),
+        );
+        out.push_str(run_xml(&plain).as_str());
+        out.push_str("</w:fldSimple>");
+        return out;
+    }
+    if let Some(target) = run.style.internal_link {
+        let plain = Run {
+            text: run.text.clone(),
+            style: RunStyle {
+                internal_link: None,
+                ..run.style.clone()
+            },
+        };
+        out.push_str(
+            format!(
+                "<w:hyperlink w:anchor=\"{}\" w:history=\"1\">",
+                bookmark_name(target)
+            )
+            .as_str(),
+        );
+        out.push_str(run_xml(&plain).as_str());
+        out.push_str("</w:hyperlink>");
+        return out;
+    }
+    // Reference runs carry their note's number, which is also its id in footnotes.xml.
+    if run.style.footnote.is_some()
+        && let Ok(id) = run.text.trim().parse::<u32>()
+    {
+        out.push_str("<w:r><w:rPr><w:vertAlign w:val=\"superscript\"/></w:rPr>");
+        out.push_str(format!("<w:footnoteReference w:id=\"{id}\"/></w:r>").as_str());
+        return out;
+    }
     out.push_str("<w:r>");
     if has_run_props(run) {
         out.push_str("<w:rPr>");
@@ -569,13 +881,17 @@ fn run_xml(run: &Run) -> String {
         }
         out.push_str("</w:rPr>");
     }
-    out.push_str(
-        format!(
-            "<w:t xml:space=\"preserve\">{}</w:t>",
-            escape_xml(run.text.as_str())
-        )
-        .as_str(),
-    );
+    // Tabs are their own element; a literal tab inside <w:t> reads back as a space.
+    for (index, segment) in run.text.split('\t').enumerate() {
+        if index > 0 {
+            out.push_str("<w:tab/>");
+        }
+        if !segment.is_empty() || run.text.is_empty() {
+            out.push_str(
+                format!("<w:t xml:space=\"preserve\">{}</w:t>", escape_xml(segment)).as_str(),
+            );
+        }
+    }
     out.push_str("</w:r>");
     out
 }
@@ -601,6 +917,36 @@ fn escape_xml(text: &str) -> String {
         .replace('\'', "&apos;")
 }
 
+/// `w:pBdr` and `w:shd`, which sit between `w:pStyle` and `w:bidi` in `w:pPr`.
+fn paragraph_frame_xml(frame: &ParagraphFrame) -> String {
+    let mut out = String::new();
+    if let Some(border) = frame.border {
+        // Border widths are eighths of a point, from 2 to 96.
+        let size = (border.width * 8.0).round().clamp(2.0, 96.0) as i32;
+        let color = to_hex(border.color);
+        out.push_str("<w:pBdr>");
+        for side in ["top", "left", "bottom", "right"] {
+            out.push_str(
+                format!(
+                    "<w:{side} w:val=\"single\" w:sz=\"{size}\" w:space=\"4\" w:color=\"{color}\"/>"
+                )
+                .as_str(),
+            );
+        }
+        out.push_str("</w:pBdr>");
+    }
+    if let Some(fill) = frame.shading {
+        out.push_str(
+            format!(
+                "<w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"{}\"/>",
+                to_hex(fill)
+            )
+            .as_str(),
+        );
+    }
+    out
+}
+
 fn to_hex(c: crate::ui::Color) -> String {
     format!(
         "{:02X}{:02X}{:02X}",
@@ -611,20 +957,27 @@ fn to_hex(c: crate::ui::Color) -> String {
 }
 
 fn highlight_name(c: crate::ui::Color) -> &'static str {
-    let r = (c.r.clamp(0.0, 1.0) * 255.0) as i32;
-    let g = (c.g.clamp(0.0, 1.0) * 255.0) as i32;
-    let b = (c.b.clamp(0.0, 1.0) * 255.0) as i32;
-    if r > 220 && g > 220 && b < 120 {
-        "yellow"
-    } else if r > 220 && g < 120 && b < 120 {
-        "red"
-    } else if r < 120 && g > 220 && b < 120 {
-        "green"
-    } else if r < 120 && g < 120 && b > 220 {
-        "blue"
-    } else {
-        "none"
-    }
+    // w:highlight only accepts named colors, so snap to the closest one.
+    const NAMED: [(&str, (f32, f32, f32)); 12] = [
+        ("yellow", (1.0, 0.95, 0.36)),
+        ("green", (0.63, 0.91, 0.67)),
+        ("cyan", (0.58, 0.89, 0.96)),
+        ("magenta", (0.96, 0.69, 0.93)),
+        ("blue", (0.62, 0.74, 0.97)),
+        ("red", (0.95, 0.58, 0.58)),
+        ("darkYellow", (0.84, 0.71, 0.33)),
+        ("darkGreen", (0.35, 0.64, 0.38)),
+        ("darkCyan", (0.31, 0.63, 0.66)),
+        ("darkMagenta", (0.62, 0.42, 0.62)),
+        ("darkBlue", (0.38, 0.44, 0.74)),
+        ("darkRed", (0.73, 0.34, 0.34)),
+    ];
+    let distance = |(r, g, b): (f32, f32, f32)| (c.r - r).powi(2) + (c.g - g).powi(2) + (c.b - b).powi(2);
+    NAMED
+        .iter()
+        .min_by(|a, b| distance(a.1).total_cmp(&distance(b.1)))
+        .map(|(name, _)| *name)
+        .unwrap_or("yellow")
 }
 
 fn ext_from_mime(mime: &str) -> &'static str {
@@ -742,6 +1095,8 @@ mod tests {
             spacing: ParagraphSpacing::default(),
             indent: Default::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         }));
         doc.images.insert(
             "img1".to_string(),
@@ -790,6 +1145,8 @@ mod tests {
             spacing: ParagraphSpacing::default(),
             indent: Default::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         }));
 
         write_docx(&output, &doc).expect("write fresh docx");
@@ -800,4 +1157,428 @@ mod tests {
 
         let _ = fs::remove_file(output);
     }
+
+    #[test]
+    fn core_properties_survive_save_and_reload() {
+        let source = unique_temp("props-source");
+        let output = unique_temp("props-out");
+        write_seed_docx(&source);
+
+        let mut doc = DocumentModel::default();
+        doc.metadata.file_path = Some(source.clone());
+        doc.metadata.title = "Q3 Plan".to_string();
+        doc.metadata.author = "Smith & Jones".to_string();
+        doc.metadata.subject = "Planning".to_string();
+        doc.metadata.keywords = "budget, roadmap".to_string();
+        doc.metadata.language = Some("fr-FR".to_string());
+
+        write_docx(&output, &doc).expect("write docx");
+
+        let core = String::from_utf8_lossy(&read_entry(&output, "docProps/core.xml")).to_string();
+        assert!(core.contains("<dc:title>Q3 Plan</dc:title>"));
+        assert!(core.contains("<dc:creator>Smith &amp; Jones</dc:creator>"));
+        // The seed package had no core part, so it is registered on save.
+        let rels = String::from_utf8_lossy(&read_entry(&output, "_rels/.rels")).to_string();
+        assert!(rels.contains(CORE_PROPERTIES_REL_TYPE));
+        assert!(rels.contains("officeDocument"));
+        let content_types =
+            String::from_utf8_lossy(&read_entry(&output, "[Content_Types].xml")).to_string();
+        assert!(content_types.contains("/docProps/core.xml"));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        assert_eq!(reloaded.metadata.title, "Q3 Plan");
+        assert_eq!(reloaded.metadata.author, "Smith & Jones");
+        assert_eq!(reloaded.metadata.subject, "Planning");
+        assert_eq!(reloaded.metadata.keywords, "budget, roadmap");
+        assert_eq!(reloaded.metadata.language.as_deref(), Some("fr-FR"));
+        assert!(reloaded.metadata.created.is_some());
+
+        let _ = fs::remove_file(source);
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn footnotes_survive_save_and_reload() {
+        let output = unique_temp("footnotes");
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Paragraph(Paragraph {
+            id: crate::document::model::BlockId(1),
+            runs: vec![
+                Run {
+                    text: "Claim".to_string(),
+                    style: RunStyle::default(),
+                },
+                Run {
+                    text: "1".to_string(),
+                    style: RunStyle {
+                        superscript: true,
+                        footnote: Some("src".to_string()),
+                        ..RunStyle::default()
+                    },
+                },
+            ],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        }));
+        doc.footnotes.push(crate::document::model::Footnote {
+            label: "src".to_string(),
+            runs: vec![Run {
+                text: "See the appendix.".to_string(),
+                style: RunStyle::default(),
+            }],
+        });
+
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:footnoteReference w:id=\"1\"/>"));
+        let rels = String::from_utf8_lossy(&read_entry(&output, "word/_rels/document.xml.rels"))
+            .to_string();
+        assert!(rels.contains(FOOTNOTES_REL_TYPE));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        assert_eq!(reloaded.footnotes.len(), 1);
+        let note = &reloaded.footnotes[0];
+        assert_eq!(note.runs.iter().map(|r| r.text.as_str()).collect::<String>(), "See the appendix.");
+        let Some(Block::Paragraph(paragraph)) = reloaded.content.first() else {
+            panic!("expected a paragraph");
+        };
+        let reference = paragraph.runs.iter().find(|r| r.style.footnote.is_some()).expect("reference kept");
+        assert_eq!(reference.style.footnote.as_deref(), Some(note.label.as_str()));
+        assert_eq!(reference.text, "1");
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn right_to_left_paragraphs_survive_save_and_reload() {
+        let output = unique_temp("bidi");
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Paragraph(Paragraph {
+            id: crate::document::model::BlockId(1),
+            runs: vec![Run {
+                text: "שלום עולם".to_string(),
+                style: RunStyle::default(),
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: TextDirection::Rtl,
+            frame: None,
+        }));
+
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:pPr><w:bidi/></w:pPr>"));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        let Some(Block::Paragraph(paragraph)) = reloaded.content.first() else {
+            panic!("expected a paragraph");
+        };
+        assert_eq!(paragraph.direction, TextDirection::Rtl);
+        assert!(matches!(paragraph.alignment, ParagraphAlignment::Left));
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn framed_paragraphs_write_borders_and_shading() {
+        let output = unique_temp("frame");
+        let frame = crate::document::model::ParagraphFramePreset::Warning.frame();
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Paragraph(Paragraph {
+            id: crate::document::model::BlockId(1),
+            runs: vec![Run {
+                text: "Back up first.".to_string(),
+                style: RunStyle::default(),
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: Some(frame.clone()),
+        }));
+
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        let border = frame.border.expect("preset border");
+        let shading = frame.shading.expect("preset shading");
+        assert!(xml.contains(&format!(
+            "<w:pPr><w:pBdr><w:top w:val=\"single\" w:sz=\"12\" w:space=\"4\" w:color=\"{}\"/>",
+            to_hex(border.color)
+        )));
+        assert!(xml.contains(&format!(
+            "</w:pBdr><w:shd w:val=\"clear\" w:color=\"auto\" w:fill=\"{}\"/></w:pPr>",
+            to_hex(shading)
+        )));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        let Some(Block::Paragraph(paragraph)) = reloaded.content.first() else {
+            panic!("expected a paragraph");
+        };
+        let reloaded_frame = paragraph.frame.as_ref().expect("frame kept");
+        let reloaded_border = reloaded_frame.border.expect("border kept");
+        assert_eq!(to_hex(reloaded_border.color), to_hex(border.color));
+        assert_eq!(reloaded_border.width, 1.5);
+        assert_eq!(reloaded_frame.shading.map(to_hex), Some(to_hex(shading)));
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn cross_references_write_bookmarks_and_ref_fields() {
+        use crate::document::model::{Anchor, CrossReference, CrossReferenceDisplay, Heading};
+
+        let output = unique_temp("xref");
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Heading(Heading {
+            id: BlockId(1),
+            level: 1,
+            runs: vec![Run {
+                text: "Setup".to_string(),
+                style: RunStyle::default(),
+            }],
+        }));
+        let reference = |display| Run {
+            text: "Setup".to_string(),
+            style: RunStyle {
+                cross_reference: Some(CrossReference {
+                    anchor: "Setup".to_string(),
+                    display,
+                }),
+                ..RunStyle::default()
+            },
+        };
+        doc.content.push(Block::Paragraph(Paragraph {
+            id: BlockId(2),
+            runs: vec![
+                Run {
+                    text: "See ".to_string(),
+                    style: RunStyle::default(),
+                },
+                reference(CrossReferenceDisplay::Text),
+                reference(CrossReferenceDisplay::Number),
+            ],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        }));
+        doc.anchors.push(Anchor {
+            name: "Setup".to_string(),
+            block_id: BlockId(1),
+        });
+
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:bookmarkStart w:id=\"3\" w:name=\"Setup\"/><w:p>"));
+        assert!(xml.contains("<w:fldSimple w:instr=\" REF Setup \\h \"><w:r><w:t"));
+        assert!(xml.contains("<w:fldSimple w:instr=\" REF Setup \\r \\h \">"));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        let heading_id = match reloaded.content.first() {
+            Some(Block::Heading(heading)) => heading.id,
+            _ => panic!("expected a heading"),
+        };
+        assert_eq!(
+            reloaded.anchors,
+            vec![Anchor {
+                name: "Setup".to_string(),
+                block_id: heading_id,
+            }]
+        );
+        let Some(Block::Paragraph(paragraph)) = reloaded.content.get(1) else {
+            panic!("expected a paragraph");
+        };
+        let displays = paragraph
+            .runs
+            .iter()
+            .filter_map(|run| run.style.cross_reference.as_ref())
+            .map(|reference| (reference.anchor.as_str(), reference.display))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            displays,
+            [
+                ("Setup", CrossReferenceDisplay::Text),
+                ("Setup", CrossReferenceDisplay::Number)
+            ]
+        );
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn captions_write_seq_fields_and_reattach_on_load() {
+        use crate::document::model::{Anchor, ImageBlock, ImageData, Table, TableCell, TableRow};
+
+        let output = unique_temp("captions");
+        let caption = |text: &str| {
+            Some(vec![Run {
+                text: text.to_string(),
+                style: RunStyle::default(),
+            }])
+        };
+        let mut doc = DocumentModel::default();
+        doc.images.insert(
+            "img".to_string(),
+            ImageData {
+                bytes: vec![0x89, b'P', b'N', b'G'],
+                mime: "image/png".to_string(),
+                width: 4,
+                height: 4,
+            },
+        );
+        doc.content.push(Block::Image(ImageBlock {
+            id: BlockId(1),
+            key: "img".to_string(),
+            caption: caption("Overview"),
+            width: 40.0,
+            height: 40.0,
+            ..ImageBlock::default()
+        }));
+        doc.content.push(Block::Table(Table {
+            id: BlockId(2),
+            rows: vec![TableRow {
+                cells: vec![TableCell {
+                    blocks: vec![Block::Paragraph(Paragraph {
+                        id: BlockId(3),
+                        runs: vec![Run {
+                            text: "42".to_string(),
+                            style: RunStyle::default(),
+                        }],
+                        alignment: ParagraphAlignment::Left,
+                        spacing: ParagraphSpacing::default(),
+                        indent: Default::default(),
+                        style_id: None,
+                        direction: Default::default(),
+                        frame: None,
+                    })],
+                    ..TableCell::default()
+                }],
+            }],
+            column_widths: vec![120.0],
+            caption: caption("Totals"),
+            ..Table::default()
+        }));
+        doc.anchors.push(Anchor {
+            name: "Table_1_Totals".to_string(),
+            block_id: BlockId(2),
+        });
+
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:fldSimple w:instr=\" SEQ Figure \\* ARABIC \"><w:r><w:t>1</w:t>"));
+        // The table's caption comes first, inside the table's bookmark.
+        let table_caption = xml.find("SEQ Table").expect("table caption");
+        assert!(xml[..table_caption].contains("w:name=\"Table_1_Totals\"/><w:p>"));
+        assert!(table_caption < xml.find("<w:tbl>").expect("table"));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        let captions = reloaded
+            .content
+            .iter()
+            .map(|block| match block {
+                Block::Image(image) => ("image", image.caption.clone()),
+                Block::Table(table) => ("table", table.caption.clone()),
+                _ => ("other", None),
+            })
+            .map(|(kind, runs)| {
+                let text = runs.map(|runs| runs.iter().map(|r| r.text.clone()).collect::<String>());
+                (kind, text)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            captions,
+            [
+                ("image", Some("Overview".to_string())),
+                ("table", Some("Totals".to_string()))
+            ]
+        );
+        let Some(Block::Table(table)) = reloaded.content.get(1) else {
+            panic!("expected a table");
+        };
+        assert_eq!(
+            reloaded.anchors,
+            vec![Anchor {
+                name: "Table_1_Totals".to_string(),
+                block_id: table.id,
+            }]
+        );
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn table_of_contents_is_written_as_a_linked_toc_field() {
+        let output = unique_temp("toc");
+        let mut doc = DocumentModel::default();
+        let heading = Block::Heading(crate::document::model::Heading {
+            id: BlockId(1),
+            level: 1,
+            runs: vec![Run {
+                text: "Intro".to_string(),
+                style: RunStyle::default(),
+            }],
+        });
+        doc.content.push(heading);
+        let entries = crate::editor::outline::toc_entries(&doc.content);
+        let toc = crate::editor::outline::toc_paragraphs(&entries, 10, |_| Some(2));
+        doc.content.splice(0..0, toc);
+
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:pStyle w:val=\"TOC1\"/></w:pPr><w:r><w:fldChar w:fldCharType=\"begin\"/>"));
+        assert!(xml.contains("TOC \\o \"1-6\" \\h"));
+        assert!(xml.contains("<w:hyperlink w:anchor=\"_Toc1\" w:history=\"1\">"));
+        assert!(xml.contains("<w:tab/><w:t xml:space=\"preserve\">2</w:t>"));
+        let end = xml.find("w:fldCharType=\"end\"").expect("field closed");
+        let bookmark = xml.find("<w:bookmarkStart w:id=\"1\" w:name=\"_Toc1\"/>").expect("heading bookmarked");
+        assert!(end < bookmark);
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        assert_eq!(crate::editor::outline::toc_range(&reloaded.content), Some(0..1));
+        let Some(Block::Paragraph(line)) = reloaded.content.first() else {
+            panic!("expected the TOC line");
+        };
+        assert_eq!(line.runs.iter().map(|r| r.text.as_str()).collect::<String>(), "Intro\t2");
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn section_columns_are_written_as_w_cols_and_read_back() {
+        let output = unique_temp("columns");
+        let mut doc = DocumentModel::default();
+        doc.metadata.columns = SectionColumns {
+            count: 3,
+            spacing: 18.0,
+        };
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:sectPr><w:cols w:num=\"3\" w:space=\"360\"/></w:sectPr>"));
+
+        let reloaded = crate::document::docx::parser::parse_docx(&output).expect("reload docx");
+        assert_eq!(reloaded.metadata.columns, doc.metadata.columns);
+
+        doc.metadata.columns = SectionColumns::default();
+        write_docx(&output, &doc).expect("write docx");
+        let xml = String::from_utf8_lossy(&read_entry(&output, "word/document.xml")).to_string();
+        assert!(xml.contains("<w:sectPr/>"));
+
+        let _ = fs::remove_file(output);
+    }
+
+    #[test]
+    fn highlight_snaps_to_named_word_colors() {
+        assert_eq!(highlight_name(crate::ui::Color::rgb(1.0, 1.0, 0.0)), "yellow");
+        assert_eq!(highlight_name(crate::ui::Color::rgb(0.63, 0.91, 0.67)), "green");
+        assert_eq!(highlight_name(crate::ui::Color::rgb(0.96, 0.69, 0.93)), "magenta");
+    }
 }
@@ -0,0 +1,548 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Error, ErrorKind, Read, Seek},
+    path::Path,
+};
+
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+use zip::ZipArchive;
+
+use crate::document::{
+    DocumentFormat,
+    docx::parser::{decode_image_dimensions, default_mime_for_ext},
+    html::{EmbeddedImage, html_to_blocks},
+    model::{Block, BlockId, DocumentModel, Heading, ImageData, Run},
+};
+
+/// Largest archive entry read, whatever size its header claims; bigger ones are skipped.
+const MAX_ENTRY_BYTES: u64 = 64 * 1024 * 1024;
+
+/// What the OPF package document says about the book.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct EpubPackage {
+    pub title: String,
+    pub author: String,
+    pub language: Option<String>,
+    /// Chapter paths inside the archive, in reading order.
+    pub spine: Vec<String>,
+    /// The EPUB 3 navigation document, if the manifest marks one.
+    pub nav: Option<String>,
+    /// The EPUB 2 `toc.ncx`, used when there is no navigation document.
+    pub ncx: Option<String>,
+    /// Manifest media types by archive path.
+    pub media_types: HashMap<String, String>,
+}
+
+/// One table of contents entry: its label and the chapter it opens, without any `#anchor`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub title: String,
+    pub href: String,
+}
+
+/// Opens an EPUB as a read-only document: its spine chapters in order, separated by page
+/// breaks, with images embedded and each chapter headed by its table of contents title.
+pub fn parse_epub(path: &Path) -> std::io::Result<DocumentModel> {
+    let file = File::open(path)?;
+    let mut archive = ZipArchive::new(file)?;
+
+    // Only the entries the book refers to are read: the container, the package document,
+    // the table of contents, the spine chapters and the images they show.
+    let package_path = read_entry(&mut archive, "META-INF/container.xml", MAX_ENTRY_BYTES)
+        .and_then(|xml| parse_container(&xml))
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing EPUB package document"))?;
+    let package_xml = read_entry(&mut archive, &package_path, MAX_ENTRY_BYTES)
+        .ok_or_else(|| Error::new(ErrorKind::InvalidData, "missing EPUB package document"))?;
+    let package = parse_opf(&package_xml, parent_dir(&package_path));
+    if package.spine.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidData, "EPUB spine is empty"));
+    }
+
+    let toc = if let Some(nav) = &package.nav
+        && let Some(xml) = read_entry(&mut archive, nav, MAX_ENTRY_BYTES)
+    {
+        parse_nav(&xml, parent_dir(nav))
+    } else if let Some(ncx) = &package.ncx
+        && let Some(xml) = read_entry(&mut archive, ncx, MAX_ENTRY_BYTES)
+    {
+        parse_ncx(&xml, parent_dir(ncx))
+    } else {
+        Vec::new()
+    };
+
+    let mut document = DocumentModel::default();
+    let mut images = HashMap::<String, ImageData>::new();
+    let mut next_id = 1;
+    for chapter in &package.spine {
+        let Some(bytes) = read_entry(&mut archive, chapter, MAX_ENTRY_BYTES) else {
+            continue;
+        };
+        let html = String::from_utf8_lossy(&bytes);
+        let chapter_dir = parent_dir(chapter);
+        let mut embed_image = |source: &str| {
+            let key = resolve_href(chapter_dir, source);
+            if !images.contains_key(&key) {
+                let bytes = read_entry(&mut archive, &key, MAX_ENTRY_BYTES)?;
+                let ext = Path::new(&key)
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .unwrap_or_default()
+                    .to_ascii_lowercase();
+                let (width, height) = decode_image_dimensions(&bytes, &ext);
+                if width == 0 || height == 0 {
+                    return None;
+                }
+                let mime = package
+                    .media_types
+                    .get(&key)
+                    .cloned()
+                    .unwrap_or_else(|| default_mime_for_ext(&ext).to_string());
+                images.insert(
+                    key.clone(),
+                    ImageData {
+                        bytes,
+                        mime,
+                        width,
+                        height,
+                    },
+                );
+            }
+            let data = &images[&key];
+            Some(EmbeddedImage {
+                width: data.width,
+                height: data.height,
+                key,
+            })
+        };
+        let mut blocks = html_to_blocks(&html, &mut next_id, &mut embed_image);
+        if blocks.is_empty() {
+            continue;
+        }
+        // The outline comes from headings, so chapters that don't open with one get their
+        // table of contents title as a heading.
+        let toc_title = toc.iter().find(|entry| &entry.href == chapter);
+        if let Some(entry) = toc_title
+            && !matches!(blocks.first(), Some(Block::Heading(_)))
+        {
+            let heading = Heading {
+                level: 1,
+                runs: vec![Run {
+                    text: entry.title.clone(),
+                    style: Default::default(),
+                }],
+                id: BlockId(next_id),
+            };
+            next_id += 1;
+            blocks.insert(0, Block::Heading(heading));
+        }
+        if !document.content.is_empty() {
+            document.content.push(Block::PageBreak);
+        }
+        document.content.extend(blocks);
+    }
+
+    document.images = images;
+    document.metadata.title = package.title;
+    document.metadata.author = package.author;
+    document.metadata.language = package.language;
+    document.metadata.format = DocumentFormat::Epub;
+    document.metadata.file_path = Some(path.to_path_buf());
+    document.dirty = false;
+
+    Ok(document)
+}
+
+/// The archive entry `name`, or None when it is missing, unreadable or turns out larger than
+/// `max_bytes` once read.
+fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+    max_bytes: u64,
+) -> Option<Vec<u8>> {
+    let entry = archive.by_name(name).ok()?;
+    let mut bytes = Vec::new();
+    entry.take(max_bytes + 1).read_to_end(&mut bytes).ok()?;
+    (bytes.len() as u64 <= max_bytes).then_some(bytes)
+}
+
+/// The package document path `META-INF/container.xml` points at.
+fn parse_container(xml: &[u8]) -> Option<String> {
+    let mut reader = Reader::from_reader(xml);
+    let mut buf = Vec::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if e.local_name().as_ref() == b"rootfile" => {
+                return attribute(&e, "full-path");
+            }
+            Ok(Event::Eof) | Err(_) => return None,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Reads the OPF package document at `base`: metadata, the manifest, and the spine in
+/// `itemref` order, with every path resolved to its place in the archive. Items marked
+/// `linear="no"` are left out of the reading order.
+pub fn parse_opf(xml: &[u8], base: &str) -> EpubPackage {
+    let mut package = EpubPackage::default();
+    let mut manifest = HashMap::<String, String>::new();
+    let mut spine_ids = Vec::new();
+    let mut ncx_id = None;
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut current = String::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                current = local_name(&e);
+                if current == "spine" {
+                    ncx_id = attribute(&e, "toc");
+                }
+            }
+            Ok(Event::Empty(e)) => match local_name(&e).as_str() {
+                "item" => {
+                    if let (Some(id), Some(href)) = (attribute(&e, "id"), attribute(&e, "href")) {
+                        let path = resolve_href(base, &href);
+                        if let Some(media_type) = attribute(&e, "media-type") {
+                            package.media_types.insert(path.clone(), media_type);
+                        }
+                        let properties = attribute(&e, "properties").unwrap_or_default();
+                        if properties.split_whitespace().any(|p| p == "nav") {
+                            package.nav = Some(path.clone());
+                        }
+                        manifest.insert(id, path);
+                    }
+                }
+                "itemref" => {
+                    let linear = attribute(&e, "linear").is_none_or(|linear| linear != "no");
+                    if let Some(idref) = attribute(&e, "idref")
+                        && linear
+                    {
+                        spine_ids.push(idref);
+                    }
+                }
+                _ => {}
+            },
+            Ok(Event::Text(t)) => {
+                let text = t.decode().map(|t| t.into_owned()).unwrap_or_default();
+                match current.as_str() {
+                    "title" if package.title.is_empty() => package.title = text,
+                    "creator" if package.author.is_empty() => package.author = text,
+                    "language" if package.language.is_none() => package.language = Some(text),
+                    _ => {}
+                }
+            }
+            Ok(Event::End(_)) => current.clear(),
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    package.ncx = ncx_id.and_then(|id| manifest.get(&id).cloned());
+    package.spine = spine_ids
+        .iter()
+        .filter_map(|id| manifest.get(id).cloned())
+        .collect();
+    package
+}
+
+/// The entries of an EPUB 3 navigation document's `<nav epub:type="toc">`, in order.
+pub fn parse_nav(xml: &[u8], base: &str) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().check_end_names = false;
+    let mut buf = Vec::new();
+    // Depth inside the toc `<nav>`, and the link being read with its text so far.
+    let mut nav_depth = 0usize;
+    let mut link: Option<(String, String)> = None;
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) => {
+                let name = local_name(&e);
+                if nav_depth > 0 {
+                    nav_depth += 1;
+                    if name == "a" {
+                        link = attribute(&e, "href").map(|href| (href, String::new()));
+                    }
+                } else if name == "nav" && attribute(&e, "type").as_deref() == Some("toc") {
+                    nav_depth = 1;
+                }
+            }
+            Ok(Event::Text(t)) => {
+                if let Some((_, title)) = &mut link
+                    && let Ok(text) = t.decode()
+                {
+                    title.push_str(&text);
+                }
+            }
+            Ok(Event::GeneralRef(r)) => {
+                if let Some((_, title)) = &mut link
+                    && let Ok(name) = r.decode()
+                    && let Some(resolved) = quick_xml::escape::resolve_predefined_entity(&name)
+                {
+                    title.push_str(resolved);
+                }
+            }
+            Ok(Event::End(e)) if nav_depth > 0 => {
+                nav_depth -= 1;
+                if e.local_name().as_ref() == b"a"
+                    && let Some((href, title)) = link.take()
+                {
+                    push_toc_entry(&mut entries, &title, base, &href);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    entries
+}
+
+/// The `navPoint`s of an EPUB 2 `toc.ncx`, in document order.
+pub fn parse_ncx(xml: &[u8], base: &str) -> Vec<TocEntry> {
+    let mut entries = Vec::new();
+    let mut reader = Reader::from_reader(xml);
+    reader.config_mut().trim_text(true);
+    let mut buf = Vec::new();
+    let mut in_label = false;
+    let mut title = String::new();
+    loop {
+        match reader.read_event_into(&mut buf) {
+            Ok(Event::Start(e)) if local_name(&e) == "navLabel" => {
+                in_label = true;
+                title.clear();
+            }
+            Ok(Event::Text(t)) if in_label => {
+                if let Ok(text) = t.decode() {
+                    title.push_str(&text);
+                }
+            }
+            Ok(Event::End(e)) if e.local_name().as_ref() == b"navLabel" => in_label = false,
+            Ok(Event::Start(e)) | Ok(Event::Empty(e)) if local_name(&e) == "content" => {
+                if let Some(src) = attribute(&e, "src") {
+                    push_toc_entry(&mut entries, &title, base, &src);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+    entries
+}
+
+fn push_toc_entry(entries: &mut Vec<TocEntry>, title: &str, base: &str, href: &str) {
+    let title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+    if title.is_empty() {
+        return;
+    }
+    entries.push(TocEntry {
+        title,
+        href: resolve_href(base, href),
+    });
+}
+
+/// The archive path `href` names when written in a file under `base`: percent-escapes
+/// decoded, `#anchor` dropped, and `.`/`..` segments applied.
+pub fn resolve_href(base: &str, href: &str) -> String {
+    let href = href.split('#').next().unwrap_or_default();
+    let href = percent_decode(href);
+    let mut segments: Vec<&str> = if href.starts_with('/') {
+        Vec::new()
+    } else {
+        base.split('/')
+            .filter(|segment| !segment.is_empty())
+            .collect()
+    };
+    for segment in href.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%')
+            .then(|| text.get(i + 1..i + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The folder part of an archive path, with its trailing slash; empty at the root.
+fn parent_dir(path: &str) -> &str {
+    path.rfind('/').map_or("", |slash| &path[..=slash])
+}
+
+fn local_name(e: &BytesStart<'_>) -> String {
+    String::from_utf8_lossy(e.local_name().as_ref()).into_owned()
+}
+
+fn attribute(e: &BytesStart<'_>, name: &str) -> Option<String> {
+    e.attributes().flatten().find_map(|attr| {
+        (attr.key.local_name().as_ref() == name.as_bytes())
+            .then(|| attr.unescape_value().ok().map(|value| value.into_owned()))
+            .flatten()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Write};
+
+    use zip::{ZipArchive, ZipWriter, write::SimpleFileOptions};
+
+    use super::{TocEntry, parse_epub, parse_nav, parse_opf, read_entry, resolve_href};
+    use crate::document::model::Block;
+
+    fn zip_of(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, bytes) in files {
+            zip.start_file(*name, SimpleFileOptions::default())
+                .expect("start entry");
+            zip.write_all(bytes).expect("write entry");
+        }
+        zip.finish().expect("finish zip").into_inner()
+    }
+
+    #[test]
+    fn entries_past_the_size_cap_are_skipped() {
+        let zip = zip_of(&[("small.xml", b"<a/>"), ("big.bin", &[0u8; 4096])]);
+        let mut archive = ZipArchive::new(Cursor::new(zip)).expect("open zip");
+
+        assert_eq!(
+            read_entry(&mut archive, "small.xml", 1024).as_deref(),
+            Some(&b"<a/>"[..])
+        );
+        assert_eq!(read_entry(&mut archive, "big.bin", 1024), None);
+        assert_eq!(
+            read_entry(&mut archive, "big.bin", 4096).map(|bytes| bytes.len()),
+            Some(4096)
+        );
+        assert_eq!(read_entry(&mut archive, "missing.xml", 1024), None);
+    }
+
+    #[test]
+    fn books_open_from_the_entries_the_package_names() {
+        let container = br#"<container><rootfiles><rootfile full-path="OEBPS/content.opf"/></rootfiles></container>"#;
+        let opf = br#"<package><metadata xmlns:dc="http://purl.org/dc/elements/1.1/"><dc:title>Tiny</dc:title></metadata>
+<manifest><item id="c1" href="one.xhtml" media-type="application/xhtml+xml"/></manifest>
+<spine><itemref idref="c1"/></spine></package>"#;
+        let chapter = b"<html><body><h1>One</h1><p>Hello there</p></body></html>";
+        let path = std::env::temp_dir().join(format!("doco-epub-{}.epub", std::process::id()));
+        let zip = zip_of(&[
+            ("META-INF/container.xml", container),
+            ("OEBPS/content.opf", opf),
+            ("OEBPS/one.xhtml", chapter),
+        ]);
+        std::fs::write(&path, zip).expect("write epub");
+
+        let document = parse_epub(&path).expect("parse epub");
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(document.metadata.title, "Tiny");
+        assert!(matches!(document.content.first(), Some(Block::Heading(_))));
+        assert_eq!(document.content.len(), 2);
+    }
+
+    #[test]
+    fn spine_follows_itemref_order_and_resolves_paths() {
+        let opf = br#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:title>A Book</dc:title>
+    <dc:creator>Some Author</dc:creator>
+    <dc:language>en</dc:language>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="c1" href="text/one.xhtml" media-type="application/xhtml+xml"/>
+    <item id="c2" href="text/two%20parts.xhtml" media-type="application/xhtml+xml"/>
+    <item id="cover" href="cover.xhtml" media-type="application/xhtml+xml"/>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+    <item id="img" href="../images/fig.png" media-type="image/png"/>
+  </manifest>
+  <spine toc="ncx">
+    <itemref idref="c2"/>
+    <itemref idref="cover" linear="no"/>
+    <itemref idref="c1"/>
+    <itemref idref="missing"/>
+  </spine>
+</package>"#;
+        let package = parse_opf(opf, "OEBPS/");
+
+        assert_eq!(package.title, "A Book");
+        assert_eq!(package.author, "Some Author");
+        assert_eq!(package.language.as_deref(), Some("en"));
+        assert_eq!(
+            package.spine,
+            vec!["OEBPS/text/two parts.xhtml", "OEBPS/text/one.xhtml"]
+        );
+        assert_eq!(package.nav.as_deref(), Some("OEBPS/nav.xhtml"));
+        assert_eq!(package.ncx.as_deref(), Some("OEBPS/toc.ncx"));
+        assert_eq!(
+            package
+                .media_types
+                .get("images/fig.png")
+                .map(String::as_str),
+            Some("image/png")
+        );
+    }
+
+    #[test]
+    fn nav_toc_entries_point_at_chapters() {
+        let nav = br#"<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops">
+<body>
+  <nav epub:type="landmarks"><ol><li><a href="cover.xhtml">Cover</a></li></ol></nav>
+  <nav epub:type="toc"><ol>
+    <li><a href="text/one.xhtml">Chapter   One</a></li>
+    <li><a href="text/two.xhtml#part">Chapter Two &amp; More</a></li>
+  </ol></nav>
+</body>
+</html>"#;
+
+        assert_eq!(
+            parse_nav(nav, "OEBPS/"),
+            vec![
+                TocEntry {
+                    title: "Chapter One".to_string(),
+                    href: "OEBPS/text/one.xhtml".to_string(),
+                },
+                TocEntry {
+                    title: "Chapter Two & More".to_string(),
+                    href: "OEBPS/text/two.xhtml".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            resolve_href("OEBPS/text/", "../images/a.png"),
+            "OEBPS/images/a.png"
+        );
+    }
+}
@@ -6,16 +6,30 @@ use std::{
 
 use chrono::Utc;
 
-use crate::document::{
-    docx::writer,
-    model::{Block, DocumentModel, ListType},
+use crate::{
+    document::{
+        delimited::{export_delimited, table_rows},
+        docx::writer,
+        model::{
+            Block, BlockId, DocumentModel, ListType, MathMode, ParagraphFrame, Run, TextDirection,
+        },
+        txt::TextDocument,
+    },
+    editor::caption::{CaptionNumber, number_captions, numbered_caption},
+    settings::schema::AutoSaveInterval,
+    ui::Color,
 };
 
 pub fn save_docx(path: &Path, model: &DocumentModel) -> std::io::Result<()> {
     writer::write_docx(path, model)
 }
 
+/// Documents read from a text file save with the encoding and line endings they were read
+/// with; others as UTF-8 with one line per block.
 pub fn export_txt(path: &Path, model: &DocumentModel) -> std::io::Result<()> {
+    if model.metadata.text_encoding.is_some() {
+        return TextDocument::from_document_model(model).save_to_path(path);
+    }
     fs::write(path, to_plain_text(model))
 }
 
@@ -38,9 +52,10 @@ pub fn export_pdf(path: &Path, model: &DocumentModel) -> std::io::Result<()> {
     let escaped = text.replace('(', "\\(").replace(')', "\\)");
 
     let body = format!(
-        "%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n3 0 obj << /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >> endobj\n4 0 obj << /Length {len} >> stream\nBT /F1 12 Tf 48 800 Td ({text}) Tj ET\nendstream endobj\n5 0 obj << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> endobj\n",
+        "%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n2 0 obj << /Type /Pages /Kids [3 0 R] /Count 1 >> endobj\n3 0 obj << /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] /Contents 4 0 R /Resources << /Font << /F1 5 0 R >> >> >> endobj\n4 0 obj << /Length {len} >> stream\nBT /F1 12 Tf 48 800 Td ({text}) Tj ET\nendstream endobj\n5 0 obj << /Type /Font /Subtype /Type1 /BaseFont /Helvetica >> endobj\n6 0 obj {info} endobj\n",
         len = escaped.len() + 29,
-        text = escaped
+        text = escaped,
+        info = pdf_info_dictionary(model)
     );
 
     let mut offsets = Vec::new();
@@ -64,7 +79,7 @@ pub fn export_pdf(path: &Path, model: &DocumentModel) -> std::io::Result<()> {
     }
     out.extend_from_slice(
         format!(
-            "trailer << /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            "trailer << /Size {} /Root 1 0 R /Info 6 0 R >>\nstartxref\n{}\n%%EOF\n",
             offsets.len() + 1,
             xref_pos
         )
@@ -74,9 +89,59 @@ pub fn export_pdf(path: &Path, model: &DocumentModel) -> std::io::Result<()> {
     fs::write(path, out)
 }
 
+/// The PDF document information dictionary built from the document properties.
+fn pdf_info_dictionary(model: &DocumentModel) -> String {
+    let meta = &model.metadata;
+    let mut entries = Vec::new();
+    for (key, value) in [
+        ("Title", &meta.title),
+        ("Author", &meta.author),
+        ("Subject", &meta.subject),
+        ("Keywords", &meta.keywords),
+    ] {
+        if !value.trim().is_empty() {
+            entries.push(format!("/{key} {}", pdf_text_string(value.trim())));
+        }
+    }
+    entries.push("/Producer (Doco)".to_string());
+    entries.push(format!("/ModDate (D:{})", Utc::now().format("%Y%m%d%H%M%SZ")));
+    format!("<< {} >>", entries.join(" "))
+}
+
+/// A PDF text string: literal for ASCII, UTF-16BE hex with a byte order mark otherwise.
+fn pdf_text_string(value: &str) -> String {
+    if value.is_ascii() {
+        let escaped = value
+            .replace('\\', "\\\\")
+            .replace('(', "\\(")
+            .replace(')', "\\)");
+        format!("({escaped})")
+    } else {
+        let hex = value
+            .encode_utf16()
+            .map(|unit| format!("{unit:04X}"))
+            .collect::<String>();
+        format!("<FEFF{hex}>")
+    }
+}
+
 pub fn to_plain_text(model: &DocumentModel) -> String {
+    let captions = number_captions(&model.content);
     let mut out = String::new();
-    for block in &model.content {
+    plain_text_blocks(&model.content, &captions, &mut out);
+    out
+}
+
+/// Appends `blocks` as plain text, a line per paragraph. A quote's contents get a `> `
+/// prefix per level.
+fn plain_text_blocks(blocks: &[Block], captions: &[CaptionNumber], out: &mut String) {
+    for block in blocks {
+        // Tables are captioned above, figures below, as Word places them.
+        let caption = numbered_caption(captions, block);
+        if let (Block::Table(_), Some(caption)) = (block, &caption) {
+            out.push_str(caption);
+            out.push('\n');
+        }
         match block {
             Block::Paragraph(p) => {
                 out.push_str(p.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str());
@@ -143,33 +208,47 @@ pub fn to_plain_text(model: &DocumentModel) -> String {
             }
             Block::Image(img) => {
                 out.push_str(format!("[Image: {}]\n", img.alt_text).as_str());
+                if let Some(caption) = &caption {
+                    out.push_str(caption);
+                    out.push('\n');
+                }
             }
             Block::BlockQuote(q) => {
-                for block in &q.blocks {
-                    if let Block::Paragraph(p) = block {
-                        out.push_str("> ");
-                        out.push_str(p.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str());
-                        out.push('\n');
-                    }
+                let mut inner = String::new();
+                plain_text_blocks(&q.blocks, captions, &mut inner);
+                for line in inner.lines() {
+                    out.push_str("> ");
+                    out.push_str(line);
+                    out.push('\n');
                 }
             }
         }
     }
-    out
 }
 
 pub fn to_markdown(model: &DocumentModel) -> String {
     let mut out = String::new();
-    for block in &model.content {
+    markdown_blocks(&model.content, &mut out);
+    for note in &model.footnotes {
+        let text = markdown_runs(&note.runs).replace('\n', "\n    ");
+        out.push_str(format!("[^{}]: {text}\n\n", note.label).as_str());
+    }
+    out
+}
+
+/// Appends `blocks` as Markdown, each followed by a blank line. A quote's contents get a
+/// `> ` prefix per level, and a callout opens with its `[!KIND]` marker line.
+fn markdown_blocks(blocks: &[Block], out: &mut String) {
+    for block in blocks {
         match block {
             Block::Heading(h) => {
                 out.push_str("#".repeat(h.level.clamp(1, 6) as usize).as_str());
                 out.push(' ');
-                out.push_str(h.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str());
+                out.push_str(markdown_runs(&h.runs).as_str());
                 out.push_str("\n\n");
             }
             Block::Paragraph(p) => {
-                out.push_str(p.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str());
+                out.push_str(markdown_runs(&p.runs).as_str());
                 out.push_str("\n\n");
             }
             Block::CodeBlock(c) => {
@@ -194,7 +273,7 @@ pub fn to_markdown(model: &DocumentModel) -> String {
                     out.push_str(marker.as_str());
                     for block in &item.content {
                         if let Block::Paragraph(p) = block {
-                            out.push_str(p.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str());
+                            out.push_str(markdown_runs(&p.runs).as_str());
                         }
                     }
                     out.push('\n');
@@ -235,47 +314,131 @@ pub fn to_markdown(model: &DocumentModel) -> String {
                 out.push('\n');
             }
             Block::Image(img) => {
-                out.push_str(format!("![{}]({})\n\n", img.alt_text, img.key).as_str());
+                // The caption text rides in the title; its number is worked out on load.
+                let title = img
+                    .caption
+                    .as_ref()
+                    .map(|runs| runs.iter().map(|r| r.text.as_str()).collect::<String>())
+                    .filter(|text| !text.trim().is_empty())
+                    .map(|text| format!(" \"{}\"", text.replace('"', "\\\"")))
+                    .unwrap_or_default();
+                out.push_str(format!("![{}]({}{title})\n\n", img.alt_text, img.key).as_str());
             }
-            Block::PageBreak | Block::BlockQuote(_) => {}
+            Block::BlockQuote(quote) => {
+                let mut inner = String::new();
+                if let Some(kind) = quote.callout {
+                    inner.push_str(format!("[!{}]\n", kind.alert_marker()).as_str());
+                }
+                markdown_blocks(&quote.blocks, &mut inner);
+                for line in inner.trim_end_matches('\n').lines() {
+                    if line.is_empty() {
+                        out.push_str(">\n");
+                    } else {
+                        out.push_str("> ");
+                        out.push_str(line);
+                        out.push('\n');
+                    }
+                }
+                out.push('\n');
+            }
+            Block::PageBreak => {}
         }
     }
-    out
+}
+
+/// Run text for Markdown, with footnote references written back as `[^label]`, math
+/// between its dollar delimiters and wiki links in double brackets.
+fn markdown_runs(runs: &[Run]) -> String {
+    runs.iter()
+        .map(|run| {
+            let style = &run.style;
+            match (&style.footnote, style.math, &style.wiki_link) {
+                (Some(label), _, _) => format!("[^{label}]"),
+                (None, Some(MathMode::Inline), _) => format!("${}$", run.text),
+                (None, Some(MathMode::Display), _) => format!("$${}$$", run.text),
+                (None, None, Some(target)) if run.text == *target => format!("[[{target}]]"),
+                (None, None, Some(target)) => format!("[[{target}|{}]]", run.text),
+                (None, None, None) => run.text.clone(),
+            }
+        })
+        .collect()
 }
 
 pub fn to_html(model: &DocumentModel) -> String {
+    let captions = number_captions(&model.content);
     let mut body = String::new();
-    for block in &model.content {
+    html_blocks(model, &model.content, &captions, &mut body);
+
+    format!(
+        "<!doctype html><html><head><meta charset=\"utf-8\"><style>body{{font-family:Segoe UI,Arial,sans-serif;max-width:840px;margin:24px auto;line-height:1.4}}table{{border-collapse:collapse}}td{{border:1px solid #ccc;padding:6px}}</style></head><body>{}</body></html>",
+        body
+    )
+}
+
+/// Appends `blocks` of `model` as HTML; quotes become `<blockquote>` around their contents.
+fn html_blocks(
+    model: &DocumentModel,
+    blocks: &[Block],
+    captions: &[CaptionNumber],
+    body: &mut String,
+) {
+    for block in blocks {
         match block {
             Block::Heading(h) => body.push_str(
                 format!(
-                    "<h{lvl}>{text}</h{lvl}>",
+                    "<h{lvl}{id}>{text}</h{lvl}>",
                     lvl = h.level.clamp(1, 6),
-                    text = escape_html(h.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str())
-                )
-                .as_str(),
-            ),
-            Block::Paragraph(p) => body.push_str(
-                format!(
-                    "<p>{}</p>",
-                    escape_html(p.runs.iter().map(|r| r.text.as_str()).collect::<String>().as_str())
+                    id = anchor_id_attr(model, h.id),
+                    text = runs_to_html(&h.runs)
                 )
                 .as_str(),
             ),
+            Block::Paragraph(p) => {
+                let dir = if p.direction == TextDirection::Rtl {
+                    " dir=\"rtl\""
+                } else {
+                    ""
+                };
+                let (style, icon) = match &p.frame {
+                    Some(frame) => (
+                        paragraph_frame_css(frame),
+                        frame
+                            .icon
+                            .as_deref()
+                            .map(|icon| format!("{} ", escape_html(icon)))
+                            .unwrap_or_default(),
+                    ),
+                    None => (String::new(), String::new()),
+                };
+                let id = anchor_id_attr(model, p.id);
+                body.push_str(
+                    format!("<p{id}{dir}{style}>{icon}{}</p>", runs_to_html(&p.runs)).as_str(),
+                );
+            }
             Block::CodeBlock(c) => body.push_str(
                 format!("<pre><code>{}</code></pre>", escape_html(c.code.as_str())).as_str(),
             ),
             Block::HorizontalRule => body.push_str("<hr/>"),
             Block::Image(img) => body.push_str(
                 format!(
-                    "<figure><img alt=\"{}\" src=\"{}\"/></figure>",
+                    "<figure{}><img alt=\"{}\" src=\"{}\"/>{}</figure>",
+                    anchor_id_attr(model, img.id),
                     escape_html(img.alt_text.as_str()),
-                    escape_html(img.key.as_str())
+                    escape_html(img.key.as_str()),
+                    numbered_caption(captions, block)
+                        .map(|caption| format!(
+                            "<figcaption>{}</figcaption>",
+                            escape_html(&caption)
+                        ))
+                        .unwrap_or_default()
                 )
                 .as_str(),
             ),
             Block::Table(table) => {
-                body.push_str("<table>");
+                body.push_str(format!("<table{}>", anchor_id_attr(model, table.id)).as_str());
+                if let Some(caption) = numbered_caption(captions, block) {
+                    body.push_str(format!("<caption>{}</caption>", escape_html(&caption)).as_str());
+                }
                 for row in &table.rows {
                     body.push_str("<tr>");
                     for cell in &row.cells {
@@ -299,26 +462,37 @@ pub fn to_html(model: &DocumentModel) -> String {
                 }
                 body.push_str("</table>");
             }
-            Block::PageBreak | Block::List(_) | Block::BlockQuote(_) => {}
+            Block::BlockQuote(q) => {
+                body.push_str(format!("<blockquote{}>", anchor_id_attr(model, q.id)).as_str());
+                html_blocks(model, &q.blocks, captions, body);
+                body.push_str("</blockquote>");
+            }
+            Block::PageBreak | Block::List(_) => {}
         }
     }
+}
 
-    format!(
-        "<!doctype html><html><head><meta charset=\"utf-8\"><style>body{{font-family:Segoe UI,Arial,sans-serif;max-width:840px;margin:24px auto;line-height:1.4}}table{{border-collapse:collapse}}td{{border:1px solid #ccc;padding:6px}}</style></head><body>{}</body></html>",
-        body
-    )
+/// Left indent, in twips, a quote adds to its contents in RTF.
+const RTF_QUOTE_INDENT: u32 = 360;
+
+fn escape_rtf(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('{', "\\{")
+        .replace('}', "\\}")
+        .replace('\n', "\\line ")
 }
 
 pub fn to_rtf(model: &DocumentModel) -> String {
-    fn escape_rtf(text: &str) -> String {
-        text.replace('\\', "\\\\")
-            .replace('{', "\\{")
-            .replace('}', "\\}")
-            .replace('\n', "\\line ")
-    }
-
     let mut out = String::from("{\\rtf1\\ansi\\deff0{\\fonttbl{\\f0 Segoe UI;}}\\viewkind4\\uc1\\pard");
-    for block in &model.content {
+    rtf_blocks(&model.content, 0, &mut out);
+    out.push('}');
+    out
+}
+
+/// Appends `blocks` as RTF paragraphs. A quote's contents are indented a further step per
+/// level, `depth` being the level `blocks` sit at.
+fn rtf_blocks(blocks: &[Block], depth: u32, out: &mut String) {
+    for block in blocks {
         match block {
             Block::Heading(h) => {
                 let text = h.runs.iter().map(|r| r.text.as_str()).collect::<String>();
@@ -328,8 +502,15 @@ pub fn to_rtf(model: &DocumentModel) -> String {
             }
             Block::Paragraph(p) => {
                 let text = p.runs.iter().map(|r| r.text.as_str()).collect::<String>();
+                let rtl = p.direction == TextDirection::Rtl;
+                if rtl {
+                    out.push_str("\\rtlpar ");
+                }
                 out.push_str(escape_rtf(text.as_str()).as_str());
                 out.push_str("\\par ");
+                if rtl {
+                    out.push_str("\\ltrpar ");
+                }
             }
             Block::CodeBlock(c) => {
                 out.push_str("\\f0 ");
@@ -392,21 +573,76 @@ pub fn to_rtf(model: &DocumentModel) -> String {
             Block::HorizontalRule => out.push_str("---\\par "),
             Block::PageBreak => out.push_str("\\page "),
             Block::BlockQuote(q) => {
-                for nested in &q.blocks {
-                    if let Block::Paragraph(p) = nested {
-                        let text = p.runs.iter().map(|r| r.text.as_str()).collect::<String>();
-                        out.push_str("> ");
-                        out.push_str(escape_rtf(text.as_str()).as_str());
-                        out.push_str("\\par ");
-                    }
-                }
+                out.push_str(format!("\\li{} ", RTF_QUOTE_INDENT * (depth + 1)).as_str());
+                rtf_blocks(&q.blocks, depth + 1, out);
+                out.push_str(format!("\\li{} ", RTF_QUOTE_INDENT * depth).as_str());
             }
         }
     }
-    out.push('}');
+}
+
+fn runs_to_html(runs: &[Run]) -> String {
+    let mut out = String::new();
+    for run in runs {
+        let mut text = escape_html(run.text.as_str());
+        if let Some(reference) = &run.style.cross_reference {
+            text = format!("<a href=\"#{}\">{text}</a>", escape_html(&reference.anchor));
+        }
+        let color = run
+            .style
+            .color
+            .map(|c| format!("color:{}", css_hex(c)))
+            .unwrap_or_default();
+        match run.style.background {
+            Some(bg) => {
+                let separator = if color.is_empty() { "" } else { ";" };
+                out.push_str(
+                    format!(
+                        "<mark style=\"background-color:{}{separator}{color}\">{}</mark>",
+                        css_hex(bg),
+                        text
+                    )
+                    .as_str(),
+                )
+            }
+            None if !color.is_empty() => {
+                out.push_str(format!("<span style=\"{color}\">{}</span>", text).as_str())
+            }
+            None => out.push_str(text.as_str()),
+        }
+    }
     out
 }
 
+/// ` id="…"` naming a block after its anchor, for cross-references to link to.
+fn anchor_id_attr(model: &DocumentModel, block_id: BlockId) -> String {
+    model
+        .anchors
+        .iter()
+        .find(|anchor| anchor.block_id == block_id)
+        .map(|anchor| format!(" id=\"{}\"", escape_html(&anchor.name)))
+        .unwrap_or_default()
+}
+
+/// ` style="…"` drawing a paragraph frame as a padded box.
+fn paragraph_frame_css(frame: &ParagraphFrame) -> String {
+    let mut style = String::new();
+    if let Some(border) = frame.border {
+        style.push_str(
+            format!("border:{}pt solid {};", border.width, css_hex(border.color)).as_str(),
+        );
+    }
+    if let Some(fill) = frame.shading {
+        style.push_str(format!("background-color:{};", css_hex(fill)).as_str());
+    }
+    format!(" style=\"{style}padding:6px 10px\"")
+}
+
+fn css_hex(color: Color) -> String {
+    let channel = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02X}{:02X}{:02X}", channel(color.r), channel(color.g), channel(color.b))
+}
+
 fn escape_html(text: &str) -> String {
     text.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -415,13 +651,14 @@ fn escape_html(text: &str) -> String {
 }
 
 pub struct AutoSaveManager {
-    pub interval: Duration,
+    /// Time between recovery snapshots; `None` when autosave is off.
+    pub interval: Option<Duration>,
     pub recovery_dir: PathBuf,
     last_save: Instant,
 }
 
 impl AutoSaveManager {
-    pub fn new(interval_seconds: u64) -> Self {
+    pub fn new(interval: AutoSaveInterval) -> Self {
         let recovery_dir = if let Some(portable) = crate::settings::portable_root() {
             portable.join("recovery")
         } else {
@@ -433,14 +670,22 @@ impl AutoSaveManager {
         let _ = fs::create_dir_all(&recovery_dir);
 
         Self {
-            interval: Duration::from_secs(interval_seconds.max(5)),
+            interval: interval.as_seconds().map(Duration::from_secs),
             recovery_dir,
             last_save: Instant::now(),
         }
     }
 
+    /// Switches to `interval` without resetting the time since the last snapshot.
+    pub fn set_interval(&mut self, interval: AutoSaveInterval) {
+        self.interval = interval.as_seconds().map(Duration::from_secs);
+    }
+
     pub fn tick(&mut self, model: &DocumentModel) -> std::io::Result<Option<PathBuf>> {
-        if self.last_save.elapsed() < self.interval || !model.dirty {
+        let Some(interval) = self.interval else {
+            return Ok(None);
+        };
+        if self.last_save.elapsed() < interval || !model.dirty {
             return Ok(None);
         }
 
@@ -488,16 +733,43 @@ pub fn save_with_format(path: &Path, model: &DocumentModel) -> std::io::Result<(
         "md" | "markdown" => export_markdown(path, model),
         "html" | "htm" => export_html(path, model),
         "rtf" => export_rtf(path, model),
+        // Documents opened as a table save back as rows; others stay plain text.
+        "csv" | "tsv" if table_rows(model).is_some() => export_delimited(path, model),
         _ => export_txt(path, model),
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::save_with_format;
+    use super::{AutoSaveManager, export_pdf, save_with_format, to_html, to_plain_text, to_rtf};
     use crate::document::model::{
-        Block, BlockId, DocumentModel, Indent, Paragraph, ParagraphAlignment, ParagraphSpacing, Run, RunStyle,
+        Anchor, Block, BlockId, BlockQuote, CodeBlock, CrossReference, DocumentModel, Heading,
+        Indent, Paragraph, ParagraphAlignment, ParagraphBorder, ParagraphFrame, ParagraphSpacing,
+        Run, RunStyle,
     };
+    use crate::settings::schema::AutoSaveInterval;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn autosave_off_never_writes_a_snapshot() {
+        let dir = std::env::temp_dir().join(format!("doco-autosave-{}", std::process::id()));
+        let mut model = DocumentModel::default();
+        model.dirty = true;
+
+        let mut manager = AutoSaveManager::new(AutoSaveInterval::Off);
+        assert_eq!(manager.interval, None);
+        manager.recovery_dir = dir.clone();
+        manager.last_save = Instant::now() - Duration::from_secs(3600);
+        assert!(manager.tick(&model).expect("tick").is_none());
+
+        // Turning it back on snapshots the overdue document.
+        std::fs::create_dir_all(&dir).expect("create recovery dir");
+        manager.set_interval(AutoSaveInterval::Seconds(30));
+        assert_eq!(manager.interval, Some(Duration::from_secs(30)));
+        let written = manager.tick(&model).expect("tick");
+        let _ = std::fs::remove_dir_all(&dir);
+        assert!(written.is_some_and(|path| path.starts_with(&dir)));
+    }
 
     #[test]
     fn save_unknown_extension_falls_back_to_plain_text() {
@@ -512,6 +784,8 @@ mod tests {
             spacing: ParagraphSpacing::default(),
             indent: Indent::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         }));
 
         let path = std::env::temp_dir().join(format!(
@@ -529,5 +803,169 @@ mod tests {
 
         assert_eq!(written, "hello\n");
     }
-}
 
+    #[test]
+    fn html_export_carries_run_colors() {
+        let mut model = DocumentModel::default();
+        model.content.push(Block::Paragraph(Paragraph {
+            id: BlockId(1),
+            runs: vec![
+                Run {
+                    text: "plain ".to_string(),
+                    style: RunStyle::default(),
+                },
+                Run {
+                    text: "marked".to_string(),
+                    style: RunStyle {
+                        background: Some(crate::ui::Color::rgb(1.0, 1.0, 0.0)),
+                        ..RunStyle::default()
+                    },
+                },
+                Run {
+                    text: " red".to_string(),
+                    style: RunStyle {
+                        color: Some(crate::ui::Color::rgb(1.0, 0.0, 0.0)),
+                        ..RunStyle::default()
+                    },
+                },
+            ],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        }));
+
+        let html = to_html(&model);
+        assert!(html.contains(
+            "<p>plain <mark style=\"background-color:#FFFF00\">marked</mark><span style=\"color:#FF0000\"> red</span></p>"
+        ));
+    }
+
+    #[test]
+    fn html_export_draws_paragraph_frames() {
+        let mut model = DocumentModel::default();
+        model.content.push(Block::Paragraph(Paragraph {
+            id: BlockId(1),
+            runs: vec![Run {
+                text: "Try the shortcut.".to_string(),
+                style: RunStyle::default(),
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: Some(ParagraphFrame {
+                border: Some(ParagraphBorder {
+                    color: crate::ui::Color::rgb(0.0, 0.5, 0.0),
+                    width: 1.5,
+                }),
+                shading: Some(crate::ui::Color::rgb(0.8, 1.0, 0.8)),
+                icon: Some("\u{1F4A1}".to_string()),
+            }),
+        }));
+
+        let html = to_html(&model);
+        assert!(html.contains(
+            "<p style=\"border:1.5pt solid #008000;background-color:#CCFFCC;padding:6px 10px\">\u{1F4A1} Try the shortcut.</p>"
+        ));
+    }
+
+    #[test]
+    fn html_export_links_cross_references_to_anchors() {
+        let mut model = DocumentModel::default();
+        model.content.push(Block::Heading(Heading {
+            id: BlockId(1),
+            level: 2,
+            runs: vec![Run {
+                text: "Q&A".to_string(),
+                style: RunStyle::default(),
+            }],
+        }));
+        model.content.push(Block::Paragraph(Paragraph {
+            id: BlockId(2),
+            runs: vec![Run {
+                text: "Q&A".to_string(),
+                style: RunStyle {
+                    cross_reference: Some(CrossReference {
+                        anchor: "Q_A".to_string(),
+                        display: Default::default(),
+                    }),
+                    ..RunStyle::default()
+                },
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        }));
+        model.anchors.push(Anchor {
+            name: "Q_A".to_string(),
+            block_id: BlockId(1),
+        });
+
+        let html = to_html(&model);
+        assert!(html.contains("<h2 id=\"Q_A\">Q&amp;A</h2>"));
+        assert!(html.contains("<p><a href=\"#Q_A\">Q&amp;A</a></p>"));
+    }
+
+    #[test]
+    fn exports_keep_every_block_inside_a_quote() {
+        let mut model = DocumentModel::default();
+        model.content.push(Block::BlockQuote(BlockQuote {
+            id: BlockId(1),
+            blocks: vec![
+                Block::Heading(Heading {
+                    id: BlockId(2),
+                    level: 3,
+                    runs: vec![Run {
+                        text: "Quoted heading".to_string(),
+                        style: RunStyle::default(),
+                    }],
+                }),
+                Block::CodeBlock(CodeBlock {
+                    id: BlockId(3),
+                    language: None,
+                    code: "let x = 1;".to_string(),
+                }),
+            ],
+            callout: None,
+        }));
+
+        assert_eq!(to_plain_text(&model), "> Quoted heading\n> let x = 1;\n");
+        let html = to_html(&model);
+        assert!(html.contains("<blockquote><h3>Quoted heading</h3>"));
+        assert!(html.contains("let x = 1;</code></pre></blockquote>"));
+        let rtf = to_rtf(&model);
+        assert!(rtf.contains("\\li360 \\b Quoted heading\\b0\\par \\f0 let x = 1;\\par \\li0 "));
+    }
+
+    #[test]
+    fn pdf_export_embeds_title_and_author() {
+        let mut model = DocumentModel::default();
+        model.metadata.title = "Annual (draft) report".to_string();
+        model.metadata.author = "Zoë".to_string();
+
+        let path = std::env::temp_dir().join(format!(
+            "doco-pdf-info-{}-{}.pdf",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_nanos())
+                .unwrap_or(0)
+        ));
+        export_pdf(path.as_path(), &model).expect("export should succeed");
+        let written = std::fs::read(path.as_path()).expect("read should succeed");
+        let _ = std::fs::remove_file(path.as_path());
+        let written = String::from_utf8_lossy(&written);
+
+        assert!(written.contains("/Info 6 0 R"));
+        assert!(written.contains("/Title (Annual \\(draft\\) report)"));
+        // Non-ASCII text is written as UTF-16BE: "Zoë".
+        assert!(written.contains("/Author <FEFF005A006F00EB>"));
+    }
+}
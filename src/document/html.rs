@@ -0,0 +1,667 @@
+use quick_xml::{
+    Reader,
+    events::{BytesStart, Event},
+};
+
+use crate::document::model::{
+    Block, BlockId, BlockQuote, CodeBlock, Heading, ImageAlignment, ImageBlock, ImageDataRef,
+    Indent, List, ListItem, ListType, Paragraph, ParagraphAlignment, ParagraphSpacing, Run,
+    RunStyle, Table, TableBorders, TableCell, TableRow, TableStylePreset,
+};
+use crate::ui::Color;
+
+/// Widest an imported image is laid out, in points; larger ones scale down to fit.
+const MAX_IMAGE_WIDTH: f32 = 450.0;
+
+/// An image an `<img>` resolved to: the key its bytes are stored under in the document's
+/// images, and its size in pixels.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmbeddedImage {
+    pub key: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// A block element still open while its content is read.
+enum Container {
+    Quote(Vec<Block>),
+    List(List),
+    Item(Vec<Block>),
+    Table(Table),
+    Row(TableRow),
+    Cell(Vec<Block>),
+}
+
+struct HtmlConverter<'a> {
+    next_id: &'a mut u64,
+    embed_image: &'a mut dyn FnMut(&str) -> Option<EmbeddedImage>,
+    blocks: Vec<Block>,
+    containers: Vec<Container>,
+    runs: Vec<Run>,
+    /// Inline elements open around the text, with the style each one gives it.
+    styles: Vec<(String, RunStyle)>,
+    heading: Option<u8>,
+    code: Option<String>,
+    /// Depth inside elements whose text isn't content, like `<head>` or `<script>`.
+    skipped: usize,
+}
+
+/// Converts (X)HTML into document blocks, numbering them from `*next_id` and leaving it past
+/// the last id used. Each `<img>` source goes through `embed_image`, which stores the image
+/// and says where; images it can't resolve are dropped.
+pub fn html_to_blocks(
+    html: &str,
+    next_id: &mut u64,
+    embed_image: &mut dyn FnMut(&str) -> Option<EmbeddedImage>,
+) -> Vec<Block> {
+    let mut converter = HtmlConverter {
+        next_id,
+        embed_image,
+        blocks: Vec::new(),
+        containers: Vec::new(),
+        runs: Vec::new(),
+        styles: Vec::new(),
+        heading: None,
+        code: None,
+        skipped: 0,
+    };
+    let mut reader = Reader::from_str(html);
+    reader.config_mut().check_end_names = false;
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(e)) => {
+                let tag = tag_name(&e);
+                if is_void(&tag) {
+                    converter.empty(&tag, &e);
+                } else {
+                    converter.start(&tag, &e);
+                }
+            }
+            Ok(Event::Empty(e)) => converter.empty(&tag_name(&e), &e),
+            Ok(Event::End(e)) => {
+                let tag = String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase();
+                converter.end(&tag);
+            }
+            Ok(Event::Text(t)) => {
+                if let Ok(text) = t.decode() {
+                    converter.text(&text);
+                }
+            }
+            Ok(Event::CData(t)) => {
+                if let Ok(text) = t.decode() {
+                    converter.text(&text);
+                }
+            }
+            // Entities such as `&amp;` arrive separately from the text around them.
+            Ok(Event::GeneralRef(r)) => {
+                if let Ok(Some(ch)) = r.resolve_char_ref() {
+                    converter.text(&ch.to_string());
+                } else if let Ok(name) = r.decode()
+                    && let Some(resolved) = resolve_entity(&name)
+                {
+                    converter.text(resolved);
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+    converter.finish()
+}
+
+impl HtmlConverter<'_> {
+    fn next_block_id(&mut self) -> BlockId {
+        let id = BlockId(*self.next_id);
+        *self.next_id += 1;
+        id
+    }
+
+    fn start(&mut self, tag: &str, e: &BytesStart<'_>) {
+        if self.skipped > 0 || matches!(tag, "head" | "script" | "style" | "title") {
+            self.skipped += 1;
+            return;
+        }
+        if is_block(tag) {
+            self.flush_text();
+        }
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                self.heading = tag[1..].parse().ok();
+            }
+            "pre" => self.code = Some(String::new()),
+            "blockquote" => self.containers.push(Container::Quote(Vec::new())),
+            "ul" | "ol" => {
+                let start_number = attribute(e, "start")
+                    .and_then(|start| start.parse().ok())
+                    .unwrap_or(1);
+                self.containers.push(Container::List(List {
+                    items: Vec::new(),
+                    list_type: if tag == "ol" {
+                        ListType::Numbered
+                    } else {
+                        ListType::Bullet
+                    },
+                    start_number,
+                }));
+            }
+            "li" => {
+                if matches!(self.containers.last(), Some(Container::Item(_))) {
+                    self.end("li");
+                }
+                self.containers.push(Container::Item(Vec::new()));
+            }
+            "table" => {
+                let id = self.next_block_id();
+                self.containers.push(Container::Table(Table {
+                    id,
+                    borders: TableBorders::default(),
+                    style: TableStylePreset::Grid,
+                    cell_padding: 8.0,
+                    header_row: true,
+                    alternating_rows: true,
+                    header_repeat: true,
+                    caption: None,
+                    ..Table::default()
+                }));
+            }
+            "tr" => {
+                if matches!(
+                    self.containers.last(),
+                    Some(Container::Row(_) | Container::Cell(_))
+                ) {
+                    self.end("tr");
+                }
+                self.containers.push(Container::Row(TableRow::default()));
+            }
+            "td" | "th" => {
+                if matches!(self.containers.last(), Some(Container::Cell(_))) {
+                    self.end("td");
+                }
+                self.containers.push(Container::Cell(Vec::new()));
+            }
+            _ => {
+                if let Some(style) = self.inline_style(tag) {
+                    self.styles.push((tag.to_string(), style));
+                }
+            }
+        }
+    }
+
+    fn empty(&mut self, tag: &str, e: &BytesStart<'_>) {
+        if self.skipped > 0 {
+            return;
+        }
+        match tag {
+            "br" => self.text_raw("\n"),
+            "hr" => {
+                self.flush_text();
+                self.push_block(Block::HorizontalRule);
+            }
+            "img" | "image" => {
+                let Some(source) = attribute(e, "src").or_else(|| attribute(e, "href")) else {
+                    return;
+                };
+                let Some(image) = (self.embed_image)(&source) else {
+                    return;
+                };
+                self.flush_text();
+                let alt_text = attribute(e, "alt").unwrap_or_default();
+                let block = image_block(self.next_block_id(), image, alt_text);
+                self.push_block(Block::Image(block));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: &str) {
+        if self.skipped > 0 {
+            self.skipped -= 1;
+            return;
+        }
+        if is_block(tag) {
+            self.flush_text();
+        }
+        match tag {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => self.heading = None,
+            "pre" => {
+                if let Some(code) = self.code.take() {
+                    let id = self.next_block_id();
+                    self.push_block(Block::CodeBlock(CodeBlock {
+                        id,
+                        language: None,
+                        code: code.trim_end_matches('\n').to_string(),
+                    }));
+                }
+            }
+            "blockquote" => {
+                if let Some(Container::Quote(blocks)) = self.pop_container(tag) {
+                    let id = self.next_block_id();
+                    self.push_block(Block::BlockQuote(BlockQuote {
+                        id,
+                        blocks,
+                        callout: None,
+                    }));
+                }
+            }
+            "li" => {
+                if let Some(Container::Item(content)) = self.pop_container(tag) {
+                    let id = self.next_block_id();
+                    if let Some(Container::List(list)) = self.containers.last_mut() {
+                        list.items.push(ListItem {
+                            id,
+                            content,
+                            checked: None,
+                            children: Vec::new(),
+                        });
+                    }
+                }
+            }
+            "ul" | "ol" => {
+                if let Some(Container::List(list)) = self.pop_container(tag)
+                    && !list.items.is_empty()
+                {
+                    self.push_block(Block::List(list));
+                }
+            }
+            "td" | "th" => {
+                if let Some(Container::Cell(blocks)) = self.pop_container(tag)
+                    && let Some(Container::Row(row)) = self.containers.last_mut()
+                {
+                    row.cells.push(TableCell {
+                        blocks,
+                        rowspan: 1,
+                        colspan: 1,
+                        background: None,
+                    });
+                }
+            }
+            "tr" => {
+                if let Some(Container::Row(row)) = self.pop_container(tag)
+                    && let Some(Container::Table(table)) = self.containers.last_mut()
+                {
+                    table.rows.push(row);
+                }
+            }
+            "table" => {
+                if let Some(Container::Table(mut table)) = self.pop_container(tag)
+                    && !table.rows.is_empty()
+                {
+                    let columns = table
+                        .rows
+                        .iter()
+                        .map(|row| row.cells.len())
+                        .max()
+                        .unwrap_or(0);
+                    table.column_widths = vec![1.0 / columns.max(1) as f32; columns];
+                    table.row_heights = vec![28.0; table.rows.len()];
+                    self.push_block(Block::Table(table));
+                }
+            }
+            _ => {
+                if let Some(position) = self.styles.iter().rposition(|(open, _)| open == tag) {
+                    self.styles.truncate(position);
+                }
+            }
+        }
+    }
+
+    /// Text as HTML renders it: runs of whitespace collapse to one space, except in `<pre>`.
+    fn text(&mut self, text: &str) {
+        if self.skipped > 0 {
+            return;
+        }
+        if let Some(code) = &mut self.code {
+            code.push_str(text);
+            return;
+        }
+        let mut collapsed = String::with_capacity(text.len());
+        let mut last_space = self
+            .runs
+            .last()
+            .is_none_or(|run| run.text.ends_with([' ', '\n']));
+        for ch in text.chars() {
+            if ch.is_whitespace() && ch != '\u{a0}' {
+                if !last_space {
+                    collapsed.push(' ');
+                }
+                last_space = true;
+            } else {
+                collapsed.push(if ch == '\u{a0}' { ' ' } else { ch });
+                last_space = false;
+            }
+        }
+        self.text_raw(&collapsed);
+    }
+
+    fn text_raw(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(code) = &mut self.code {
+            code.push_str(text);
+            return;
+        }
+        let style = self
+            .styles
+            .last()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default();
+        match self.runs.last_mut() {
+            Some(run) if run.style == style => run.text.push_str(text),
+            _ => self.runs.push(Run {
+                text: text.to_string(),
+                style,
+            }),
+        }
+    }
+
+    /// The style text inside `tag` takes, on top of the elements already open around it.
+    fn inline_style(&self, tag: &str) -> Option<RunStyle> {
+        let mut style = self
+            .styles
+            .last()
+            .map(|(_, style)| style.clone())
+            .unwrap_or_default();
+        match tag {
+            "b" | "strong" => style.bold = true,
+            "i" | "em" | "cite" | "dfn" => style.italic = true,
+            "u" | "ins" => style.underline = true,
+            "s" | "del" | "strike" => style.strikethrough = true,
+            "sup" => style.superscript = true,
+            "sub" => style.subscript = true,
+            "code" | "kbd" | "samp" | "tt" => {
+                style.font_family = Some("Cascadia Mono".to_string());
+            }
+            "a" => {
+                style.underline = true;
+                style.color = Some(Color::rgb(0.34, 0.55, 0.95));
+            }
+            "span" | "small" | "mark" | "abbr" | "q" | "time" => {}
+            _ => return None,
+        }
+        Some(style)
+    }
+
+    /// Ends the paragraph or heading being collected, dropping it if it holds only spaces.
+    fn flush_text(&mut self) {
+        let mut runs = std::mem::take(&mut self.runs);
+        if let Some(last) = runs.last_mut() {
+            let trimmed = last.text.trim_end().len();
+            last.text.truncate(trimmed);
+        }
+        if let Some(first) = runs.first_mut() {
+            first.text = first.text.trim_start().to_string();
+        }
+        runs.retain(|run| !run.text.is_empty());
+        if runs.is_empty() {
+            return;
+        }
+        let id = self.next_block_id();
+        let block = match self.heading {
+            Some(level) => Block::Heading(Heading { level, runs, id }),
+            None => Block::Paragraph(Paragraph {
+                id,
+                runs,
+                alignment: ParagraphAlignment::Left,
+                spacing: ParagraphSpacing::default(),
+                indent: Indent::default(),
+                style_id: None,
+                direction: Default::default(),
+                frame: None,
+            }),
+        };
+        self.push_block(block);
+    }
+
+    /// Adds `block` to the innermost open element that holds blocks, or the document.
+    fn push_block(&mut self, block: Block) {
+        let target = self
+            .containers
+            .iter_mut()
+            .rev()
+            .find_map(|container| match container {
+                Container::Quote(blocks) | Container::Item(blocks) | Container::Cell(blocks) => {
+                    Some(blocks)
+                }
+                _ => None,
+            });
+        target.unwrap_or(&mut self.blocks).push(block);
+    }
+
+    /// Closes the innermost container `tag` opened. Any left unclosed inside it, as HTML
+    /// allows for `<li>` or `<td>`, are ended first as if their end tags were there.
+    fn pop_container(&mut self, tag: &str) -> Option<Container> {
+        let position = self
+            .containers
+            .iter()
+            .rposition(|container| end_tag(container) == end_tag_for(tag))?;
+        while self.containers.len() > position + 1 {
+            let inner = end_tag(self.containers.last()?);
+            self.end(inner);
+        }
+        self.containers.pop()
+    }
+
+    fn finish(mut self) -> Vec<Block> {
+        self.flush_text();
+        while let Some(container) = self.containers.last() {
+            let tag = end_tag(container);
+            self.end(tag);
+        }
+        self.blocks
+    }
+}
+
+/// The end tag that closes `container`.
+fn end_tag(container: &Container) -> &'static str {
+    match container {
+        Container::Quote(_) => "blockquote",
+        Container::List(_) => "ul",
+        Container::Item(_) => "li",
+        Container::Table(_) => "table",
+        Container::Row(_) => "tr",
+        Container::Cell(_) => "td",
+    }
+}
+
+/// `tag` as [`end_tag`] spells the element it closes.
+fn end_tag_for(tag: &str) -> &str {
+    match tag {
+        "ol" => "ul",
+        "th" => "td",
+        tag => tag,
+    }
+}
+
+fn image_block(id: BlockId, image: EmbeddedImage, alt_text: String) -> ImageBlock {
+    // Pixels at 96 DPI to points, scaled down to the page when wider.
+    let width = image.width.max(1) as f32 * 0.75;
+    let height = image.height.max(1) as f32 * 0.75;
+    let scale = (MAX_IMAGE_WIDTH / width).min(1.0);
+    ImageBlock {
+        id,
+        data: ImageDataRef::Key(image.key.clone()),
+        original_width: image.width,
+        original_height: image.height,
+        caption: None,
+        border: None,
+        crop: None,
+        key: image.key,
+        alt_text,
+        source_path: None,
+        width: width * scale,
+        height: height * scale,
+        alignment: ImageAlignment::Inline,
+    }
+}
+
+fn tag_name(e: &BytesStart<'_>) -> String {
+    String::from_utf8_lossy(e.local_name().as_ref()).to_ascii_lowercase()
+}
+
+fn attribute(e: &BytesStart<'_>, name: &str) -> Option<String> {
+    e.attributes().flatten().find_map(|attr| {
+        let key = std::str::from_utf8(attr.key.local_name().into_inner()).ok()?;
+        key.eq_ignore_ascii_case(name)
+            .then(|| attr.unescape_value().ok().map(|value| value.into_owned()))
+            .flatten()
+    })
+}
+
+/// Elements HTML never closes, which a lenient page may write without the XHTML slash.
+fn is_void(tag: &str) -> bool {
+    matches!(
+        tag,
+        "br" | "hr" | "img" | "meta" | "link" | "input" | "col" | "wbr"
+    )
+}
+
+fn is_block(tag: &str) -> bool {
+    matches!(
+        tag,
+        "p" | "div"
+            | "section"
+            | "article"
+            | "header"
+            | "footer"
+            | "aside"
+            | "nav"
+            | "main"
+            | "figure"
+            | "figcaption"
+            | "body"
+            | "h1"
+            | "h2"
+            | "h3"
+            | "h4"
+            | "h5"
+            | "h6"
+            | "pre"
+            | "blockquote"
+            | "ul"
+            | "ol"
+            | "li"
+            | "dl"
+            | "dt"
+            | "dd"
+            | "table"
+            | "tr"
+            | "td"
+            | "th"
+    )
+}
+
+/// XML's predefined entities plus the HTML ones books commonly use.
+fn resolve_entity(name: &str) -> Option<&'static str> {
+    if let Some(resolved) = quick_xml::escape::resolve_predefined_entity(name) {
+        return Some(resolved);
+    }
+    Some(match name {
+        "nbsp" => "\u{a0}",
+        "mdash" => "\u{2014}",
+        "ndash" => "\u{2013}",
+        "hellip" => "\u{2026}",
+        "lsquo" => "\u{2018}",
+        "rsquo" => "\u{2019}",
+        "ldquo" => "\u{201c}",
+        "rdquo" => "\u{201d}",
+        "copy" => "\u{a9}",
+        "reg" => "\u{ae}",
+        "trade" => "\u{2122}",
+        "shy" => "",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EmbeddedImage, html_to_blocks};
+    use crate::document::model::{Block, ListType};
+
+    fn text_of(block: &Block) -> String {
+        match block {
+            Block::Paragraph(p) => p.runs.iter().map(|run| run.text.as_str()).collect(),
+            Block::Heading(h) => h.runs.iter().map(|run| run.text.as_str()).collect(),
+            _ => String::new(),
+        }
+    }
+
+    #[test]
+    fn xhtml_chapter_becomes_headings_paragraphs_and_lists() {
+        let chapter = r#"<?xml version="1.0" encoding="utf-8"?>
+<html xmlns="http://www.w3.org/1999/xhtml">
+<head><title>Ignored</title><style>p { color: red }</style></head>
+<body>
+  <h1>Chapter  One</h1>
+  <p>It was a <em>dark</em> and
+     <strong>stormy</strong> night&nbsp;&mdash; really.</p>
+  <ol><li>First</li><li>Second</li></ol>
+  <blockquote><p>Quoted</p></blockquote>
+  <p>Look:<br/>here <img src="images/fig1.png" alt="Figure"/></p>
+  <pre>let x = 1;
+let y = 2;</pre>
+</body>
+</html>"#;
+        let mut next_id = 10;
+        let mut sources = Vec::new();
+        let blocks = html_to_blocks(chapter, &mut next_id, &mut |source| {
+            sources.push(source.to_string());
+            Some(EmbeddedImage {
+                key: "fig1".to_string(),
+                width: 200,
+                height: 100,
+            })
+        });
+
+        let Block::Heading(heading) = &blocks[0] else {
+            panic!("expected a heading, got {:?}", blocks[0]);
+        };
+        assert_eq!(heading.level, 1);
+        assert_eq!(text_of(&blocks[0]), "Chapter One");
+
+        let Block::Paragraph(paragraph) = &blocks[1] else {
+            panic!("expected a paragraph");
+        };
+        assert_eq!(
+            text_of(&blocks[1]),
+            "It was a dark and stormy night \u{2014} really."
+        );
+        assert!(
+            paragraph
+                .runs
+                .iter()
+                .any(|run| run.text == "dark" && run.style.italic)
+        );
+        assert!(
+            paragraph
+                .runs
+                .iter()
+                .any(|run| run.text == "stormy" && run.style.bold)
+        );
+
+        let Block::List(list) = &blocks[2] else {
+            panic!("expected a list");
+        };
+        assert!(matches!(list.list_type, ListType::Numbered));
+        assert_eq!(list.items.len(), 2);
+        assert_eq!(text_of(&list.items[1].content[0]), "Second");
+
+        assert!(
+            matches!(&blocks[3], Block::BlockQuote(quote) if text_of(&quote.blocks[0]) == "Quoted")
+        );
+        assert_eq!(text_of(&blocks[4]), "Look:\nhere");
+        let Block::Image(image) = &blocks[5] else {
+            panic!("expected an image");
+        };
+        assert_eq!(
+            (image.key.as_str(), image.alt_text.as_str()),
+            ("fig1", "Figure")
+        );
+        assert_eq!(sources, vec!["images/fig1.png"]);
+        assert!(
+            matches!(&blocks[6], Block::CodeBlock(code) if code.code == "let x = 1;\nlet y = 2;")
+        );
+        assert_eq!(blocks.len(), 7);
+        assert!(next_id > 10);
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    document::model::{Block, DocumentModel},
+    editor::words::DEFAULT_LOCALE,
+};
+
+/// Bytes of document text the auto-detection looks at.
+const SAMPLE_CHARS: usize = 4000;
+
+/// Languages offered by the status bar picker, as BCP 47 tags and display names.
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en-US", "English (United States)"),
+    ("en-GB", "English (United Kingdom)"),
+    ("fr-FR", "French"),
+    ("de-DE", "German"),
+    ("es-ES", "Spanish"),
+    ("it-IT", "Italian"),
+    ("ru-RU", "Russian"),
+    ("el-GR", "Greek"),
+    ("ar-SA", "Arabic"),
+    ("he-IL", "Hebrew"),
+    ("hi-IN", "Hindi"),
+    ("th-TH", "Thai"),
+    ("zh-CN", "Chinese (Simplified)"),
+    ("ja-JP", "Japanese"),
+    ("ko-KR", "Korean"),
+];
+
+/// Frequent short words that tell Latin-script languages apart.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en-US", &["the", "and", "of", "to", "is", "that", "with", "it"]),
+    ("fr-FR", &["le", "la", "les", "et", "des", "est", "une", "dans"]),
+    ("de-DE", &["der", "die", "und", "das", "ist", "nicht", "mit", "ein"]),
+    ("es-ES", &["el", "los", "y", "que", "es", "una", "del", "por"]),
+    ("it-IT", &["il", "di", "che", "e", "non", "sono", "gli", "della"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Han,
+    Kana,
+    Hangul,
+}
+
+/// Every script, in declaration order so `Script as usize` indexes it.
+const SCRIPTS: [Script; 10] = [
+    Script::Latin,
+    Script::Cyrillic,
+    Script::Greek,
+    Script::Arabic,
+    Script::Hebrew,
+    Script::Devanagari,
+    Script::Thai,
+    Script::Han,
+    Script::Kana,
+    Script::Hangul,
+];
+
+fn script_of(ch: char) -> Option<Script> {
+    Some(match ch as u32 {
+        0x0041..=0x024F if ch.is_alphabetic() => Script::Latin,
+        0x0370..=0x03FF => Script::Greek,
+        0x0400..=0x04FF => Script::Cyrillic,
+        0x0590..=0x05FF => Script::Hebrew,
+        0x0600..=0x06FF | 0x0750..=0x077F => Script::Arabic,
+        0x0900..=0x097F => Script::Devanagari,
+        0x0E00..=0x0E7F => Script::Thai,
+        0x3040..=0x30FF => Script::Kana,
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF => Script::Han,
+        0xAC00..=0xD7AF | 0x1100..=0x11FF => Script::Hangul,
+        _ => return None,
+    })
+}
+
+/// Guesses the language of `text` from its dominant script, and for Latin text from which
+/// language's common words it uses most. Falls back to English.
+pub fn detect_language(text: &str) -> &'static str {
+    let mut counts = [0usize; SCRIPTS.len()];
+    for script in text.chars().filter_map(script_of) {
+        counts[script as usize] += 1;
+    }
+    // Japanese mixes kana with Han characters; any real share of kana decides it.
+    let (kana, han) = (counts[Script::Kana as usize], counts[Script::Han as usize]);
+    if kana > 0 && kana * 5 >= han {
+        return "ja-JP";
+    }
+    let Some((dominant, _)) = SCRIPTS
+        .iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .max_by_key(|(_, count)| *count)
+    else {
+        return DEFAULT_LOCALE;
+    };
+    match dominant {
+        Script::Latin => detect_latin_language(text),
+        Script::Cyrillic => "ru-RU",
+        Script::Greek => "el-GR",
+        Script::Arabic => "ar-SA",
+        Script::Hebrew => "he-IL",
+        Script::Devanagari => "hi-IN",
+        Script::Thai => "th-TH",
+        Script::Han => "zh-CN",
+        Script::Kana => "ja-JP",
+        Script::Hangul => "ko-KR",
+    }
+}
+
+fn detect_latin_language(text: &str) -> &'static str {
+    let words: Vec<String> = text
+        .split(|ch: char| !ch.is_alphabetic())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    // Reversed so that on a tie the earlier, more common language wins.
+    STOPWORDS
+        .iter()
+        .rev()
+        .map(|(tag, stopwords)| {
+            let hits = words
+                .iter()
+                .filter(|word| stopwords.contains(&word.as_str()))
+                .count();
+            (*tag, hits)
+        })
+        .filter(|(_, hits)| *hits > 0)
+        .max_by_key(|(_, hits)| *hits)
+        .map_or(DEFAULT_LOCALE, |(tag, _)| tag)
+}
+
+/// The document's language: the one chosen for it, or else one detected from its text.
+pub fn document_language(document: &DocumentModel) -> &str {
+    match document.metadata.language.as_deref() {
+        Some(language) if !language.is_empty() => language,
+        _ => detected_language(document),
+    }
+}
+
+/// The language detected from the document's text, whatever was chosen for it.
+pub fn detected_language(document: &DocumentModel) -> &'static str {
+    detect_language(&language_sample(document))
+}
+
+/// Whether text in `language` reads right to left.
+pub fn is_rtl_language(language: &str) -> bool {
+    let primary = language.split(['-', '_']).next().unwrap_or_default();
+    ["ar", "he", "fa", "ur", "yi"]
+        .iter()
+        .any(|rtl| primary.eq_ignore_ascii_case(rtl))
+}
+
+/// Display name for `language`, or the tag itself when the picker doesn't list it.
+pub fn language_name(language: &str) -> &str {
+    LANGUAGES
+        .iter()
+        .find(|(tag, _)| tag.eq_ignore_ascii_case(language))
+        .map_or(language, |(_, name)| name)
+}
+
+/// Leading prose of the document; code blocks say nothing about its language.
+fn language_sample(document: &DocumentModel) -> String {
+    let mut sample = String::new();
+    for block in &document.content {
+        if sample.len() >= SAMPLE_CHARS {
+            break;
+        }
+        push_block_sample(block, &mut sample);
+    }
+    sample
+}
+
+fn push_block_sample(block: &Block, sample: &mut String) {
+    if sample.len() >= SAMPLE_CHARS {
+        return;
+    }
+    match block {
+        Block::Paragraph(p) => p.runs.iter().for_each(|run| sample.push_str(&run.text)),
+        Block::Heading(h) => h.runs.iter().for_each(|run| sample.push_str(&run.text)),
+        Block::List(list) => list
+            .items
+            .iter()
+            .flat_map(|item| &item.content)
+            .for_each(|nested| push_block_sample(nested, sample)),
+        Block::BlockQuote(quote) => quote
+            .blocks
+            .iter()
+            .for_each(|nested| push_block_sample(nested, sample)),
+        Block::Table(table) => table
+            .rows
+            .iter()
+            .flat_map(|row| &row.cells)
+            .flat_map(|cell| &cell.blocks)
+            .for_each(|nested| push_block_sample(nested, sample)),
+        _ => return,
+    }
+    sample.push(' ');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{detect_language, document_language, is_rtl_language};
+    use crate::{document::txt::TextDocument, editor::words::word_range_at};
+
+    #[test]
+    fn detection_tells_english_from_non_latin_scripts() {
+        assert_eq!(
+            detect_language("The cat sat on the mat and it was happy."),
+            "en-US"
+        );
+        assert_eq!(
+            detect_language("Le chat est dans la maison et les enfants jouent."),
+            "fr-FR"
+        );
+        assert_eq!(detect_language("Привет, как дела?"), "ru-RU");
+        assert_eq!(detect_language("مرحبا بالعالم"), "ar-SA");
+        assert_eq!(detect_language("東京は日本の首都です"), "ja-JP");
+        assert_eq!(detect_language("我们今天去北京"), "zh-CN");
+        assert_eq!(detect_language("안녕하세요 세계"), "ko-KR");
+        assert_eq!(detect_language("1234 !!"), "en-US", "no letters at all");
+        assert!(is_rtl_language("he-IL") && !is_rtl_language("en-US"));
+    }
+
+    #[test]
+    fn chosen_language_reaches_word_ranges() {
+        let text = "l'homme et la femme";
+        let mut doc = TextDocument::from_text(text).to_document_model();
+        assert_eq!(document_language(&doc), "fr-FR");
+        assert_eq!(word_range_at(text, 4, document_language(&doc)), 2..7);
+
+        doc.metadata.language = Some("en-US".to_string());
+        assert_eq!(word_range_at(text, 4, document_language(&doc)), 0..7);
+    }
+}
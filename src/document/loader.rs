@@ -0,0 +1,335 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver, TryRecvError},
+    },
+    thread,
+};
+
+use super::model::{Block, DocumentModel};
+use super::txt::TextDocument;
+
+/// Blocks handed to the window per message while a large document streams in.
+const CHUNK_BLOCKS: usize = 256;
+
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+#[derive(Debug)]
+enum LoadMessage {
+    Chunk { index: usize, blocks: Vec<Block> },
+    Finished {
+        metadata_only: Box<DocumentModel>,
+        chunks: usize,
+    },
+    Text(TextDocument),
+}
+
+/// Reassembles content chunks in index order, whatever order they arrive in.
+#[derive(Debug, Default)]
+pub struct ChunkAssembler {
+    next_index: usize,
+    pending: BTreeMap<usize, Vec<Block>>,
+    ready: Vec<Block>,
+}
+
+impl ChunkAssembler {
+    /// Accepts chunk `index` and returns how many blocks became contiguous as a result.
+    pub fn push(&mut self, index: usize, blocks: Vec<Block>) -> usize {
+        if index < self.next_index || self.pending.contains_key(&index) {
+            return 0;
+        }
+        self.pending.insert(index, blocks);
+
+        let before = self.ready.len();
+        while let Some(next) = self.pending.remove(&self.next_index) {
+            self.ready.extend(next);
+            self.next_index += 1;
+        }
+        self.ready.len() - before
+    }
+
+    /// Number of leading chunks that have been assembled so far.
+    pub fn chunks_assembled(&self) -> usize {
+        self.next_index
+    }
+
+    /// Hands out the in-order blocks assembled since the last call.
+    pub fn take_ready(&mut self) -> Vec<Block> {
+        std::mem::take(&mut self.ready)
+    }
+}
+
+#[derive(Debug)]
+pub enum LoadProgress {
+    Pending,
+    /// The next blocks of content, to append after everything delivered before.
+    Blocks(Vec<Block>),
+    /// Parsing finished; the model carries metadata, styles and images but no content,
+    /// which has already been delivered through `Blocks`.
+    Finished(Box<DocumentModel>),
+    /// A large text file read into its rope, shown without converting it to blocks.
+    Text(TextDocument),
+    Failed,
+}
+
+/// A document being parsed on a worker thread for the tab `tab_id`.
+#[derive(Debug)]
+pub struct DocumentLoadJob {
+    pub tab_id: u64,
+    pub path: PathBuf,
+    cancel: CancelToken,
+    rx: Receiver<LoadMessage>,
+    assembler: ChunkAssembler,
+    delivered: usize,
+    finished: Option<(Box<DocumentModel>, usize)>,
+}
+
+impl DocumentLoadJob {
+    pub fn spawn(tab_id: u64, path: PathBuf, parse: fn(&Path) -> DocumentModel) -> Self {
+        let cancel = CancelToken::default();
+        let (tx, rx) = mpsc::channel();
+        let worker_cancel = cancel.clone();
+        let worker_path = path.clone();
+        thread::spawn(move || {
+            let mut model = parse(worker_path.as_path());
+            if worker_cancel.is_cancelled() {
+                return;
+            }
+
+            let content = std::mem::take(&mut model.content);
+            let mut chunks = 0usize;
+            let mut iter = content.into_iter().peekable();
+            while iter.peek().is_some() {
+                if worker_cancel.is_cancelled() {
+                    return;
+                }
+                let blocks = iter.by_ref().take(CHUNK_BLOCKS).collect::<Vec<_>>();
+                if tx.send(LoadMessage::Chunk { index: chunks, blocks }).is_err() {
+                    return;
+                }
+                chunks += 1;
+            }
+            let _ = tx.send(LoadMessage::Finished {
+                metadata_only: Box::new(model),
+                chunks,
+            });
+        });
+
+        Self {
+            tab_id,
+            path,
+            cancel,
+            rx,
+            assembler: ChunkAssembler::default(),
+            delivered: 0,
+            finished: None,
+        }
+    }
+
+    /// Reads a large text file into a rope on a worker thread, for a tab that shows it
+    /// through a `TextView` rather than as blocks.
+    pub fn spawn_text(tab_id: u64, path: PathBuf) -> Self {
+        let cancel = CancelToken::default();
+        let (tx, rx) = mpsc::channel();
+        let worker_cancel = cancel.clone();
+        let worker_path = path.clone();
+        thread::spawn(move || {
+            // Dropping `tx` without sending reports the failure.
+            let Ok(document) = TextDocument::load_from_path(worker_path.as_path()) else {
+                return;
+            };
+            if !worker_cancel.is_cancelled() {
+                let _ = tx.send(LoadMessage::Text(document));
+            }
+        });
+
+        Self {
+            tab_id,
+            path,
+            cancel,
+            rx,
+            assembler: ChunkAssembler::default(),
+            delivered: 0,
+            finished: None,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    pub fn blocks_delivered(&self) -> usize {
+        self.delivered
+    }
+
+    /// Drains everything the worker has sent so far without blocking.
+    pub fn poll(&mut self) -> LoadProgress {
+        loop {
+            match self.rx.try_recv() {
+                Ok(LoadMessage::Chunk { index, blocks }) => {
+                    let _ = self.assembler.push(index, blocks);
+                }
+                Ok(LoadMessage::Finished {
+                    metadata_only,
+                    chunks,
+                }) => {
+                    self.finished = Some((metadata_only, chunks));
+                }
+                Ok(LoadMessage::Text(document)) => return LoadProgress::Text(document),
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    if self.finished.is_none() {
+                        return LoadProgress::Failed;
+                    }
+                    break;
+                }
+            }
+        }
+
+        let ready = self.assembler.take_ready();
+        if !ready.is_empty() {
+            self.delivered += ready.len();
+            return LoadProgress::Blocks(ready);
+        }
+
+        match self.finished.take() {
+            Some((model, chunks)) if self.assembler.chunks_assembled() >= chunks => LoadProgress::Finished(model),
+            other => {
+                self.finished = other;
+                LoadProgress::Pending
+            }
+        }
+    }
+}
+
+impl Drop for DocumentLoadJob {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::model::{BlockId, Indent, Paragraph, ParagraphAlignment, ParagraphSpacing, Run};
+    use std::time::{Duration, Instant};
+
+    fn paragraph(id: u64) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: format!("p{id}"),
+                ..Run::default()
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    fn ids(blocks: &[Block]) -> Vec<u64> {
+        blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Paragraph(p) => Some(p.id.0),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn assembler_orders_chunks_by_index() {
+        let mut assembler = ChunkAssembler::default();
+        assert_eq!(assembler.push(1, vec![paragraph(3), paragraph(4)]), 0);
+        assert_eq!(assembler.push(2, vec![paragraph(5)]), 0);
+        assert_eq!(assembler.push(0, vec![paragraph(1), paragraph(2)]), 5);
+        assert_eq!(assembler.chunks_assembled(), 3);
+        // Late duplicates of already-assembled chunks are ignored.
+        assert_eq!(assembler.push(1, vec![paragraph(99)]), 0);
+        assert_eq!(ids(&assembler.take_ready()), vec![1, 2, 3, 4, 5]);
+
+        assert_eq!(assembler.push(4, vec![paragraph(7)]), 0);
+        assert!(assembler.take_ready().is_empty());
+        assert_eq!(assembler.push(3, vec![paragraph(6)]), 2);
+        assert_eq!(ids(&assembler.take_ready()), vec![6, 7]);
+    }
+
+    #[test]
+    fn cancel_token_is_shared_between_clones() {
+        let token = CancelToken::default();
+        let worker = token.clone();
+        assert!(!worker.is_cancelled());
+        token.cancel();
+        assert!(worker.is_cancelled());
+    }
+
+    fn slow_parse(_: &Path) -> DocumentModel {
+        thread::sleep(Duration::from_millis(30));
+        many_paragraphs(Path::new("slow.md"))
+    }
+
+    #[test]
+    fn cancelled_job_never_delivers_content() {
+        let mut job = DocumentLoadJob::spawn(3, PathBuf::from("slow.md"), slow_parse);
+        job.cancel();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        loop {
+            match job.poll() {
+                LoadProgress::Failed => break,
+                LoadProgress::Pending => {
+                    assert!(Instant::now() < deadline, "worker did not stop");
+                    thread::sleep(Duration::from_millis(1));
+                }
+                other => panic!("cancelled job produced {other:?}"),
+            }
+        }
+        assert_eq!(job.blocks_delivered(), 0);
+    }
+
+    fn many_paragraphs(_: &Path) -> DocumentModel {
+        let mut model = DocumentModel::default();
+        model.metadata.title = "Big".to_string();
+        model.content = (1..=(CHUNK_BLOCKS as u64 * 2 + 7)).map(paragraph).collect();
+        model
+    }
+
+    #[test]
+    fn job_streams_chunks_and_finishes_in_order() {
+        let mut job = DocumentLoadJob::spawn(7, PathBuf::from("big.md"), many_paragraphs);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut content = Vec::new();
+        let model = loop {
+            match job.poll() {
+                LoadProgress::Blocks(blocks) => content.extend(blocks),
+                LoadProgress::Finished(model) => break model,
+                LoadProgress::Failed => panic!("worker failed"),
+                _ => {
+                    assert!(Instant::now() < deadline, "load timed out");
+                    thread::sleep(Duration::from_millis(1));
+                }
+            }
+        };
+        assert_eq!(model.metadata.title, "Big");
+        assert!(model.content.is_empty());
+        let expected = (1..=(CHUNK_BLOCKS as u64 * 2 + 7)).collect::<Vec<_>>();
+        assert_eq!(ids(&content), expected);
+        assert_eq!(job.blocks_delivered(), expected.len());
+    }
+}
@@ -8,6 +8,7 @@ use crate::document::{
 };
 
 pub mod renderer;
+pub mod wiki;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MarkdownViewMode {
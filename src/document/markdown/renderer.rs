@@ -5,15 +5,17 @@ use std::sync::mpsc::{self, Receiver, TryRecvError};
 use std::thread;
 
 use image::GenericImageView;
-use pulldown_cmark::{Alignment, CodeBlockKind, Event, HeadingLevel, Tag, TagEnd};
+use pulldown_cmark::{
+    Alignment, BlockQuoteKind, CodeBlockKind, Event, HeadingLevel, LinkType, Tag, TagEnd,
+};
 use regex::Regex;
 
 use crate::document::{
     markdown::{MarkdownDocument, MarkdownViewMode},
     model::{
-        Block, BlockId, BlockQuote, CodeBlock, DocumentModel, Heading, ImageAlignment, ImageBlock,
-        ImageDataRef, List, ListItem, ListType, Paragraph, ParagraphAlignment, ParagraphSpacing,
-        Run, RunStyle, Table, TableCell, TableRow,
+        Block, BlockId, BlockQuote, CalloutKind, CodeBlock, DocumentModel, Footnote, Heading, ImageAlignment,
+        ImageBlock, ImageDataRef, List, ListItem, ListType, MathMode, Paragraph, ParagraphAlignment,
+        ParagraphSpacing, Run, RunStyle, Table, TableCell, TableRow,
     },
 };
 use crate::ui::Color;
@@ -211,21 +213,24 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
     let mut code_lang: Option<String> = None;
     let mut code_text = String::new();
 
-    let mut in_block_quote = false;
-    let mut quote_runs: Vec<Run> = Vec::new();
+    // Open block quotes, innermost last; `> >` nests one inside the other.
+    let mut quote_stack: Vec<BlockQuote> = Vec::new();
 
     let mut table_builder: Option<TableBuilder> = None;
     let mut table_in_cell = false;
 
-    let mut image_capture: Option<(String, String)> = None;
+    // Source, title and alt text; a title becomes the image's caption.
+    let mut image_capture: Option<(String, String, String)> = None;
     let mut footnote_label: Option<String> = None;
+    let mut footnote_runs: Vec<Run> = Vec::new();
 
     let mut emphasis_depth = 0usize;
     let mut strong_depth = 0usize;
     let mut strike_depth = 0usize;
     let mut superscript_depth = 0usize;
     let mut subscript_depth = 0usize;
-    let mut link_stack: Vec<String> = Vec::new();
+    // Each open link's destination, and whether it is a `[[wiki link]]`.
+    let mut link_stack: Vec<(String, bool)> = Vec::new();
 
     for event in doc.parser() {
         match event {
@@ -263,9 +268,13 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                         CodeBlockKind::Fenced(lang) => Some(lang.to_string()),
                     };
                 }
-                Tag::BlockQuote(_) => {
-                    in_block_quote = true;
-                    quote_runs.clear();
+                Tag::BlockQuote(kind) => {
+                    quote_stack.push(BlockQuote {
+                        id: BlockId(next_id),
+                        blocks: Vec::new(),
+                        callout: kind.map(callout_kind),
+                    });
+                    next_id += 1;
                 }
                 Tag::Table(alignments) => {
                     table_builder = Some(TableBuilder {
@@ -289,12 +298,22 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                 Tag::Strikethrough => strike_depth += 1,
                 Tag::Superscript => superscript_depth += 1,
                 Tag::Subscript => subscript_depth += 1,
-                Tag::Link { dest_url, .. } => link_stack.push(dest_url.to_string()),
-                Tag::Image { dest_url, .. } => {
-                    image_capture = Some((dest_url.to_string(), String::new()));
+                Tag::Link {
+                    link_type,
+                    dest_url,
+                    ..
+                } => link_stack.push((
+                    dest_url.to_string(),
+                    matches!(link_type, LinkType::WikiLink { .. }),
+                )),
+                Tag::Image {
+                    dest_url, title, ..
+                } => {
+                    image_capture = Some((dest_url.to_string(), title.to_string(), String::new()));
                 }
                 Tag::FootnoteDefinition(label) => {
                     footnote_label = Some(label.to_string());
+                    footnote_runs.clear();
                 }
                 _ => {}
             },
@@ -305,10 +324,16 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                         let block = Block::Paragraph(Paragraph {
                             id: BlockId(next_id),
                             runs: current_runs.clone(),
-                            alignment: ParagraphAlignment::Left,
+                            alignment: if is_display_math(&current_runs) {
+                                ParagraphAlignment::Center
+                            } else {
+                                ParagraphAlignment::Left
+                            },
                             spacing: ParagraphSpacing::default(),
                             indent: crate::document::model::Indent::default(),
                             style_id: None,
+                            direction: Default::default(),
+                            frame: None,
                         });
                         next_id += 1;
 
@@ -318,8 +343,16 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                             }
                         } else if let Some(list) = list_stack.last_mut() {
                             list.current_item_runs.extend(extract_runs_from_block(&block));
-                        } else if in_block_quote {
-                            quote_runs.extend(extract_runs_from_block(&block));
+                        } else if let Some(quote) = quote_stack.last_mut() {
+                            quote.blocks.push(block);
+                        } else if footnote_label.is_some() {
+                            if !footnote_runs.is_empty() {
+                                footnote_runs.push(Run {
+                                    text: "\n".to_string(),
+                                    style: RunStyle::default(),
+                                });
+                            }
+                            footnote_runs.extend(extract_runs_from_block(&block));
                         } else {
                             model.content.push(block);
                         }
@@ -344,6 +377,8 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                             spacing: ParagraphSpacing::default(),
                             indent: crate::document::model::Indent::default(),
                             style_id: None,
+                            direction: Default::default(),
+                            frame: None,
                         })];
                         list.items.push(ListItem {
                             id: BlockId(next_id),
@@ -377,20 +412,12 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                     }
                 }
                 TagEnd::BlockQuote(_) => {
-                    if in_block_quote {
-                        in_block_quote = false;
-                        model.content.push(Block::BlockQuote(BlockQuote {
-                            id: BlockId(next_id),
-                            blocks: vec![Block::Paragraph(Paragraph {
-                                id: BlockId(next_id + 1),
-                                runs: quote_runs.clone(),
-                                alignment: ParagraphAlignment::Left,
-                                spacing: ParagraphSpacing::default(),
-                                indent: crate::document::model::Indent::default(),
-                                style_id: None,
-                            })],
-                        }));
-                        next_id += 2;
+                    if let Some(quote) = quote_stack.pop() {
+                        let block = Block::BlockQuote(quote);
+                        match quote_stack.last_mut() {
+                            Some(outer) => outer.blocks.push(block),
+                            None => model.content.push(block),
+                        }
                     }
                 }
                 TagEnd::TableCell => {
@@ -404,6 +431,8 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                                 spacing: ParagraphSpacing::default(),
                                 indent: crate::document::model::Indent::default(),
                                 style_id: None,
+                                direction: Default::default(),
+                                frame: None,
                             })],
                             rowspan: 1,
                             colspan: 1,
@@ -440,6 +469,8 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                             cell_padding: 8.0,
                             header_row: true,
                             alternating_rows: true,
+                            header_repeat: true,
+                            caption: None,
                         }));
                         next_id += 1;
                     }
@@ -453,37 +484,52 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                     let _ = link_stack.pop();
                 }
                 TagEnd::Image => {
-                    if let Some((src, alt_text)) = image_capture.take() {
-                        model.content.push(Block::Image(build_image_block(
+                    if let Some((src, title, alt_text)) = image_capture.take() {
+                        let mut image = build_image_block(
                             src.as_str(),
                             alt_text.as_str(),
                             base_path,
                             BlockId(next_id),
-                        )));
+                        );
+                        if !title.trim().is_empty() {
+                            image.caption = Some(vec![Run {
+                                text: title,
+                                style: RunStyle::default(),
+                            }]);
+                        }
+                        model.content.push(Block::Image(image));
                         next_id += 1;
                     }
                 }
                 TagEnd::FootnoteDefinition => {
-                    footnote_label = None;
+                    if let Some(label) = footnote_label.take() {
+                        model.footnotes.push(Footnote {
+                            label,
+                            runs: std::mem::take(&mut footnote_runs),
+                        });
+                    }
                 }
                 _ => {}
             },
             Event::Text(text) => {
-                if let Some((_, alt_text)) = image_capture.as_mut() {
+                if let Some((_, _, alt_text)) = image_capture.as_mut() {
                     alt_text.push_str(text.as_ref());
                     continue;
                 }
 
-                let run = styled_run(
+                let mut run = styled_run(
                     text.as_ref(),
                     strong_depth > 0,
                     emphasis_depth > 0,
                     strike_depth > 0,
                     superscript_depth > 0,
                     subscript_depth > 0,
-                    link_stack.last().map(|s| s.as_str()),
+                    link_stack.last().map(|(url, _)| url.as_str()),
                     false,
                 );
+                if let Some((target, true)) = link_stack.last() {
+                    run.style.wiki_link = Some(target.clone());
+                }
 
                 if in_code_block {
                     code_text.push_str(run.text.as_str());
@@ -491,8 +537,6 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                     heading_runs.push(run);
                 } else if in_paragraph {
                     current_runs.push(run);
-                } else if in_block_quote {
-                    quote_runs.push(run);
                 }
             }
             Event::Code(text) => {
@@ -503,7 +547,7 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                     strike_depth > 0,
                     superscript_depth > 0,
                     subscript_depth > 0,
-                    link_stack.last().map(|s| s.as_str()),
+                    link_stack.last().map(|(url, _)| url.as_str()),
                     true,
                 );
                 if in_heading.is_some() {
@@ -512,6 +556,22 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                     current_runs.push(run);
                 }
             }
+            Event::InlineMath(source) => {
+                let run = math_run(source.as_ref(), MathMode::Inline);
+                if in_heading.is_some() {
+                    heading_runs.push(run);
+                } else if in_paragraph {
+                    current_runs.push(run);
+                }
+            }
+            Event::DisplayMath(source) => {
+                let run = math_run(source.as_ref(), MathMode::Display);
+                if in_heading.is_some() {
+                    heading_runs.push(run);
+                } else if in_paragraph {
+                    current_runs.push(run);
+                }
+            }
             Event::TaskListMarker(checked) => {
                 if let Some(list) = list_stack.last_mut() {
                     list.list_type = ListType::Checkbox;
@@ -519,14 +579,19 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
                 }
             }
             Event::FootnoteReference(label) => {
-                if footnote_label.is_none() {
-                    current_runs.push(Run {
-                        text: format!("[{label}]"),
-                        style: RunStyle {
-                            superscript: true,
-                            ..RunStyle::default()
-                        },
-                    });
+                // The number is filled in by `renumber_footnotes` once every definition is known.
+                let run = Run {
+                    text: label.to_string(),
+                    style: RunStyle {
+                        superscript: true,
+                        footnote: Some(label.to_string()),
+                        ..RunStyle::default()
+                    },
+                };
+                if in_heading.is_some() {
+                    heading_runs.push(run);
+                } else if in_paragraph {
+                    current_runs.push(run);
                 }
             }
             Event::Rule => model.content.push(Block::HorizontalRule),
@@ -547,6 +612,7 @@ pub fn markdown_to_model(doc: &MarkdownDocument, base_path: Option<&Path>) -> Do
         }
     }
 
+    model.renumber_footnotes();
     model
 }
 
@@ -649,6 +715,16 @@ fn heading_level_to_u8(level: HeadingLevel) -> u8 {
     }
 }
 
+fn callout_kind(kind: BlockQuoteKind) -> CalloutKind {
+    match kind {
+        BlockQuoteKind::Note => CalloutKind::Note,
+        BlockQuoteKind::Tip => CalloutKind::Tip,
+        BlockQuoteKind::Important => CalloutKind::Important,
+        BlockQuoteKind::Warning => CalloutKind::Warning,
+        BlockQuoteKind::Caution => CalloutKind::Danger,
+    }
+}
+
 fn extract_runs_from_block(block: &Block) -> Vec<Run> {
     match block {
         Block::Paragraph(p) => p.runs.clone(),
@@ -689,6 +765,25 @@ fn styled_run(
     }
 }
 
+fn math_run(source: &str, mode: MathMode) -> Run {
+    Run {
+        text: source.to_string(),
+        style: RunStyle {
+            math: Some(mode),
+            ..RunStyle::default()
+        },
+    }
+}
+
+/// Whether a paragraph holds nothing but display math, which sits centered on its own.
+fn is_display_math(runs: &[Run]) -> bool {
+    let display = |run: &Run| run.style.math == Some(MathMode::Display);
+    runs.iter().any(display)
+        && runs
+            .iter()
+            .all(|run| display(run) || run.text.trim().is_empty())
+}
+
 fn build_image_block(source: &str, alt_text: &str, base_path: Option<&Path>, id: BlockId) -> ImageBlock {
     let resolved = resolve_local_image_path(source, base_path);
     let (width, height) = load_image_dimensions(source, base_path).unwrap_or((320, 180));
@@ -811,6 +906,100 @@ fn main() {}
         assert!(model.content.iter().any(|b| matches!(b, Block::Image(_))));
     }
 
+    #[test]
+    fn nested_block_quotes_keep_their_levels() {
+        let md = "> outer\n>\n> > inner\n>\n> back out\n\nafter\n";
+        let model = markdown_to_model(&MarkdownDocument::from_source(md), None);
+
+        assert_eq!(model.content.len(), 2);
+        let Block::BlockQuote(outer) = &model.content[0] else {
+            panic!("expected a block quote");
+        };
+        let text = |block: &Block| {
+            extract_runs_from_block(block)
+                .iter()
+                .map(|r| r.text.clone())
+                .collect::<String>()
+        };
+        assert_eq!(outer.blocks.len(), 3);
+        assert_eq!(text(&outer.blocks[0]), "outer");
+        let Block::BlockQuote(inner) = &outer.blocks[1] else {
+            panic!("expected a nested block quote");
+        };
+        assert_eq!(inner.blocks.iter().map(text).collect::<Vec<_>>(), ["inner"]);
+        assert_eq!(text(&outer.blocks[2]), "back out");
+        assert_eq!(text(&model.content[1]), "after");
+    }
+
+    #[test]
+    fn alert_markers_become_callouts_and_round_trip() {
+        let md = "> [!WARNING]\n> Mind the gap.\n\n> [!caution]\n> Hot.\n>\n> > [!TIP]\n> > Nested.\n\n> Plain.\n";
+        let model = markdown_to_model(&MarkdownDocument::from_source(md), None);
+
+        let callouts = |blocks: &[Block]| {
+            blocks
+                .iter()
+                .filter_map(|block| match block {
+                    Block::BlockQuote(quote) => Some(quote.callout),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            callouts(&model.content),
+            [Some(CalloutKind::Warning), Some(CalloutKind::Danger), None]
+        );
+        // The marker line is not part of the callout's text.
+        let Block::BlockQuote(warning) = &model.content[0] else {
+            panic!("expected a callout");
+        };
+        assert_eq!(extract_runs_from_block(&warning.blocks[0])[0].text, "Mind the gap.");
+        let Block::BlockQuote(danger) = &model.content[1] else {
+            panic!("expected a callout");
+        };
+        assert_eq!(callouts(&danger.blocks), [Some(CalloutKind::Tip)]);
+
+        let exported = crate::document::export::to_markdown(&model);
+        assert!(exported.starts_with("> [!WARNING]\n> Mind the gap.\n\n"), "{exported}");
+        assert!(exported.contains("> [!CAUTION]\n> Hot.\n>\n> > [!TIP]\n> > Nested.\n"), "{exported}");
+        assert!(exported.contains("> Plain.\n"), "{exported}");
+        let reparsed = markdown_to_model(&MarkdownDocument::from_source(&exported), None);
+        assert_eq!(callouts(&reparsed.content), callouts(&model.content));
+        assert_eq!(CalloutKind::from_alert_marker("danger"), Some(CalloutKind::Danger));
+        assert_eq!(CalloutKind::from_alert_marker("Important"), Some(CalloutKind::Important));
+        assert_eq!(CalloutKind::from_alert_marker("aside"), None);
+    }
+
+    #[test]
+    fn image_titles_become_numbered_captions() {
+        let md = "![a cat](cat.png \"Sleeping \\\"Tom\\\"\")\n\n![logo](logo.png)\n\n![a dog](dog.png 'Awake')\n";
+        let model = markdown_to_model(&MarkdownDocument::from_source(md), None);
+        let captions = model
+            .content
+            .iter()
+            .filter_map(|block| match block {
+                Block::Image(image) => {
+                    Some(image.caption.as_ref().map(|runs| runs[0].text.clone()))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            captions,
+            [Some("Sleeping \"Tom\"".to_string()), None, Some("Awake".to_string())]
+        );
+        let text = crate::document::export::to_plain_text(&model);
+        assert!(text.contains("Figure 1: Sleeping \"Tom\"\n"), "{text}");
+        assert!(text.contains("Figure 2: Awake\n"), "{text}");
+
+        let exported = crate::document::export::to_markdown(&model);
+        assert!(exported.contains("![a cat](cat.png \"Sleeping \\\"Tom\\\"\")"), "{exported}");
+        assert!(exported.contains("![logo](logo.png)\n"), "{exported}");
+        let reparsed = markdown_to_model(&MarkdownDocument::from_source(&exported), None);
+        let html = crate::document::export::to_html(&reparsed);
+        assert!(html.contains("<figcaption>Figure 2: Awake</figcaption>"), "{html}");
+    }
+
     #[test]
     fn builds_outline_and_highlights() {
         let doc = MarkdownDocument::from_source("# H1\n## H2\n- item\n[link](a)");
@@ -831,6 +1020,89 @@ fn main() {}
         assert!(tokens.iter().any(|t| t.kind == MarkdownCodeTokenKind::String));
     }
 
+    #[test]
+    fn footnotes_round_trip_through_markdown() {
+        let md = "Second[^b] then first[^a], again[^b].\n\n[^a]: Note A.\n\n[^b]: Note B.\n";
+        let model = markdown_to_model(&MarkdownDocument::from_source(md), None);
+
+        assert_eq!(model.content.len(), 1, "definitions must not become body paragraphs");
+        let labels = model.footnotes.iter().map(|note| note.label.as_str()).collect::<Vec<_>>();
+        assert_eq!(labels, ["b", "a"]);
+        let Block::Paragraph(paragraph) = &model.content[0] else {
+            panic!("expected a paragraph");
+        };
+        let references = paragraph
+            .runs
+            .iter()
+            .filter_map(|run| run.style.footnote.as_deref().map(|label| (label, run.text.as_str())))
+            .collect::<Vec<_>>();
+        assert_eq!(references, [("b", "1"), ("a", "2"), ("b", "1")]);
+        assert!(paragraph.runs.iter().filter(|run| run.style.footnote.is_some()).all(|run| run.style.superscript));
+
+        let exported = crate::document::export::to_markdown(&model);
+        let reparsed = markdown_to_model(&MarkdownDocument::from_source(&exported), None);
+        for note in &reparsed.footnotes {
+            let original = model.footnotes.iter().find(|n| n.label == note.label).expect("label kept");
+            let text = |runs: &[Run]| runs.iter().map(|r| r.text.clone()).collect::<String>();
+            assert_eq!(text(&note.runs), text(&original.runs));
+        }
+        assert_eq!(reparsed.footnotes.len(), 2);
+        assert_eq!(reparsed.footnote_number("b"), Some(1));
+        assert_eq!(reparsed.footnote_number("a"), Some(2));
+    }
+
+    #[test]
+    fn math_round_trips_through_markdown() {
+        let md = "Euler: $e^{i\\pi} + 1 = 0$ holds.\n\n$$\\frac{a}{b}$$\n";
+        let model = markdown_to_model(&MarkdownDocument::from_source(md), None);
+
+        let Block::Paragraph(inline) = &model.content[0] else {
+            panic!("expected a paragraph");
+        };
+        let math = inline
+            .runs
+            .iter()
+            .find(|run| run.style.math.is_some())
+            .expect("math run");
+        assert_eq!(math.text, "e^{i\\pi} + 1 = 0");
+        assert_eq!(math.style.math, Some(MathMode::Inline));
+        let Block::Paragraph(display) = &model.content[1] else {
+            panic!("expected a paragraph");
+        };
+        assert!(matches!(display.alignment, ParagraphAlignment::Center));
+        assert_eq!(display.runs[0].style.math, Some(MathMode::Display));
+
+        let exported = crate::document::export::to_markdown(&model);
+        assert!(exported.contains("$e^{i\\pi} + 1 = 0$"));
+        assert!(exported.contains("$$\\frac{a}{b}$$"));
+    }
+
+    #[test]
+    fn wiki_links_keep_their_page_through_markdown() {
+        let md = "See [[My Note]] and [[Other Page|the other one]].\n";
+        let model = markdown_to_model(&MarkdownDocument::from_source(md), None);
+
+        let Block::Paragraph(p) = &model.content[0] else {
+            panic!("expected a paragraph");
+        };
+        let links: Vec<(&str, Option<&str>)> = p
+            .runs
+            .iter()
+            .filter(|run| run.style.wiki_link.is_some())
+            .map(|run| (run.text.as_str(), run.style.wiki_link.as_deref()))
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("My Note", Some("My Note")),
+                ("the other one", Some("Other Page"))
+            ]
+        );
+
+        let exported = crate::document::export::to_markdown(&model);
+        assert!(exported.contains("See [[My Note]] and [[Other Page|the other one]]."));
+    }
+
     #[test]
     fn image_loader_is_async() {
         let temp_dir = std::env::temp_dir().join("doco-md-loader-tests");
@@ -0,0 +1,129 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// How many folders deep the resolver looks below the workspace root.
+const MAX_DEPTH: usize = 8;
+
+/// The page name a `[[target]]` refers to, without any `#heading` anchor.
+pub fn wiki_page_name(target: &str) -> &str {
+    target.split('#').next().unwrap_or_default().trim()
+}
+
+/// Finds the Markdown file `[[target]]` points at: the `.md` file under `root` whose name
+/// without the extension matches the page name, ignoring case.
+pub fn resolve_wiki_link(root: &Path, target: &str) -> Option<PathBuf> {
+    find_wiki_page(&wiki_pages(root), target).map(Path::to_path_buf)
+}
+
+/// The page `[[target]]` names among `pages`, as listed by [`wiki_pages`].
+pub fn find_wiki_page<'a>(pages: &'a [PathBuf], target: &str) -> Option<&'a Path> {
+    let page = wiki_page_name(target).to_lowercase();
+    if page.is_empty() {
+        return None;
+    }
+    pages
+        .iter()
+        .find(|path| {
+            path.file_stem()
+                .and_then(|stem| stem.to_str())
+                .is_some_and(|stem| stem.to_lowercase() == page)
+        })
+        .map(PathBuf::as_path)
+}
+
+/// Every `.md` file under `root`, shallowest first so a note next to the root beats a
+/// namesake in a subfolder.
+pub fn wiki_pages(root: &Path) -> Vec<PathBuf> {
+    let mut pages = Vec::new();
+    let mut folders = vec![root.to_path_buf()];
+    for _ in 0..=MAX_DEPTH {
+        let mut subfolders = Vec::new();
+        for folder in folders {
+            let Ok(entries) = fs::read_dir(&folder) else {
+                continue;
+            };
+            let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+            paths.sort();
+            for path in paths {
+                let hidden = path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with('.'));
+                if hidden {
+                    continue;
+                }
+                if path.is_dir() {
+                    subfolders.push(path);
+                } else if is_markdown(&path) {
+                    pages.push(path);
+                }
+            }
+        }
+        if subfolders.is_empty() {
+            break;
+        }
+        folders = subfolders;
+    }
+    pages
+}
+
+/// Where a new page for an unresolved `[[target]]` is created: `<root>/<page>.md`.
+pub fn new_wiki_page_path(root: &Path, target: &str) -> PathBuf {
+    let page: String = wiki_page_name(target)
+        .chars()
+        .map(|ch| match ch {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '-',
+            ch => ch,
+        })
+        .collect();
+    root.join(format!("{page}.md"))
+}
+
+fn is_markdown(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("md"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{new_wiki_page_path, resolve_wiki_link};
+
+    fn workspace(name: &str) -> std::path::PathBuf {
+        let root = std::env::temp_dir().join(format!("doco-wiki-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(root.join("notes")).expect("create workspace");
+        root
+    }
+
+    #[test]
+    fn wiki_links_resolve_by_file_stem_ignoring_case() {
+        let root = workspace("resolve");
+        fs::write(root.join("notes").join("My Note.md"), "# My Note").expect("write note");
+        fs::write(root.join("My Note.txt"), "not markdown").expect("write text");
+
+        let expected = root.join("notes").join("My Note.md");
+        assert_eq!(resolve_wiki_link(&root, "My Note"), Some(expected.clone()));
+        assert_eq!(resolve_wiki_link(&root, "my note"), Some(expected.clone()));
+        assert_eq!(resolve_wiki_link(&root, "My Note#Intro"), Some(expected));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn missing_pages_stay_unresolved() {
+        let root = workspace("missing");
+        fs::write(root.join("Other.md"), "").expect("write note");
+
+        assert_eq!(resolve_wiki_link(&root, "My Note"), None);
+        assert_eq!(resolve_wiki_link(&root, ""), None);
+        assert_eq!(
+            new_wiki_page_path(&root, "My Note"),
+            root.join("My Note.md")
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,420 @@
+use std::ops::Range;
+
+/// A lexical piece of TeX math source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathToken {
+    Symbol(char),
+    /// A control sequence without its backslash, e.g. `frac` or `,`.
+    Command(String),
+    Superscript,
+    Subscript,
+    GroupStart,
+    GroupEnd,
+}
+
+/// Typeset structure of a formula, built from its tokens.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MathNode {
+    Text(String),
+    Group(Vec<MathNode>),
+    Fraction {
+        numerator: Vec<MathNode>,
+        denominator: Vec<MathNode>,
+    },
+    Root(Vec<MathNode>),
+    Scripts {
+        base: Box<MathNode>,
+        superscript: Option<Vec<MathNode>>,
+        subscript: Option<Vec<MathNode>>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MathScript {
+    Superscript,
+    Subscript,
+}
+
+/// A formula laid out on one line: its text and the byte ranges raised or lowered in it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MathLayout {
+    pub text: String,
+    pub scripts: Vec<(Range<usize>, MathScript)>,
+}
+
+/// Commands that stand for a single character.
+const SYMBOLS: &[(&str, &str)] = &[
+    ("alpha", "α"),
+    ("beta", "β"),
+    ("gamma", "γ"),
+    ("delta", "δ"),
+    ("epsilon", "ε"),
+    ("zeta", "ζ"),
+    ("eta", "η"),
+    ("theta", "θ"),
+    ("iota", "ι"),
+    ("kappa", "κ"),
+    ("lambda", "λ"),
+    ("mu", "μ"),
+    ("nu", "ν"),
+    ("xi", "ξ"),
+    ("pi", "π"),
+    ("rho", "ρ"),
+    ("sigma", "σ"),
+    ("tau", "τ"),
+    ("upsilon", "υ"),
+    ("phi", "φ"),
+    ("chi", "χ"),
+    ("psi", "ψ"),
+    ("omega", "ω"),
+    ("Gamma", "Γ"),
+    ("Delta", "Δ"),
+    ("Theta", "Θ"),
+    ("Lambda", "Λ"),
+    ("Xi", "Ξ"),
+    ("Pi", "Π"),
+    ("Sigma", "Σ"),
+    ("Phi", "Φ"),
+    ("Psi", "Ψ"),
+    ("Omega", "Ω"),
+    ("cdot", "·"),
+    ("times", "×"),
+    ("div", "÷"),
+    ("pm", "±"),
+    ("mp", "∓"),
+    ("leq", "≤"),
+    ("le", "≤"),
+    ("geq", "≥"),
+    ("ge", "≥"),
+    ("neq", "≠"),
+    ("ne", "≠"),
+    ("approx", "≈"),
+    ("equiv", "≡"),
+    ("infty", "∞"),
+    ("partial", "∂"),
+    ("nabla", "∇"),
+    ("sum", "∑"),
+    ("prod", "∏"),
+    ("int", "∫"),
+    ("to", "→"),
+    ("rightarrow", "→"),
+    ("leftarrow", "←"),
+    ("in", "∈"),
+    ("forall", "∀"),
+    ("exists", "∃"),
+    ("ldots", "…"),
+    ("cdots", "⋯"),
+    (",", " "),
+    (";", " "),
+    (" ", " "),
+    ("quad", " "),
+    ("{", "{"),
+    ("}", "}"),
+];
+
+/// Splits TeX math into tokens. Spaces are dropped, as TeX drops them in math.
+pub fn tokenize_math(source: &str) -> Vec<MathToken> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => {
+                let mut name = String::new();
+                while let Some(&next) = chars.peek() {
+                    if !next.is_ascii_alphabetic() {
+                        break;
+                    }
+                    name.push(next);
+                    chars.next();
+                }
+                if name.is_empty()
+                    && let Some(other) = chars.next()
+                {
+                    name.push(other);
+                }
+                tokens.push(MathToken::Command(name));
+            }
+            '^' => tokens.push(MathToken::Superscript),
+            '_' => tokens.push(MathToken::Subscript),
+            '{' => tokens.push(MathToken::GroupStart),
+            '}' => tokens.push(MathToken::GroupEnd),
+            _ if ch.is_whitespace() => {}
+            _ => tokens.push(MathToken::Symbol(ch)),
+        }
+    }
+    tokens
+}
+
+/// Parses TeX math into layout nodes. Unknown commands are kept as written.
+pub fn parse_math(source: &str) -> Vec<MathNode> {
+    let tokens = tokenize_math(source);
+    let mut index = 0;
+    let mut nodes = Vec::new();
+    // A stray closing brace ends the inner list early; keep going past it.
+    while index < tokens.len() {
+        nodes.extend(parse_list(&tokens, &mut index));
+        index += 1;
+    }
+    nodes
+}
+
+fn parse_list(tokens: &[MathToken], index: &mut usize) -> Vec<MathNode> {
+    let mut nodes: Vec<MathNode> = Vec::new();
+    while let Some(token) = tokens.get(*index) {
+        match token {
+            MathToken::GroupEnd => return nodes,
+            MathToken::Superscript | MathToken::Subscript => {
+                let superscript = *token == MathToken::Superscript;
+                *index += 1;
+                let argument = parse_argument(tokens, index);
+                let node = nodes.pop().unwrap_or(MathNode::Text(String::new()));
+                nodes.push(attach_script(node, superscript, argument));
+            }
+            _ => {
+                if let Some(node) = parse_atom(tokens, index) {
+                    nodes.push(node);
+                }
+            }
+        }
+    }
+    nodes
+}
+
+/// Parses one atom at `index` and moves past it: a group, a symbol or a command.
+fn parse_atom(tokens: &[MathToken], index: &mut usize) -> Option<MathNode> {
+    let token = tokens.get(*index)?;
+    *index += 1;
+    match token {
+        MathToken::Symbol(ch) => Some(MathNode::Text(ch.to_string())),
+        MathToken::GroupStart => {
+            let nodes = parse_list(tokens, index);
+            *index += 1;
+            Some(MathNode::Group(nodes))
+        }
+        MathToken::Command(name) => match name.as_str() {
+            "frac" | "dfrac" | "tfrac" => {
+                let numerator = parse_argument(tokens, index);
+                let denominator = parse_argument(tokens, index);
+                Some(MathNode::Fraction {
+                    numerator,
+                    denominator,
+                })
+            }
+            "sqrt" => Some(MathNode::Root(parse_argument(tokens, index))),
+            // Sized delimiters: the delimiter that follows is drawn as is.
+            "left" | "right" => None,
+            _ => Some(MathNode::Text(
+                SYMBOLS
+                    .iter()
+                    .find(|(command, _)| command == name)
+                    .map_or_else(|| format!("\\{name}"), |(_, symbol)| symbol.to_string()),
+            )),
+        },
+        MathToken::Superscript | MathToken::Subscript | MathToken::GroupEnd => None,
+    }
+}
+
+/// The argument of a command or script: a braced group's contents, or a single atom.
+fn parse_argument(tokens: &[MathToken], index: &mut usize) -> Vec<MathNode> {
+    match parse_atom(tokens, index) {
+        Some(MathNode::Group(nodes)) => nodes,
+        Some(node) => vec![node],
+        None => Vec::new(),
+    }
+}
+
+fn attach_script(node: MathNode, raise: bool, argument: Vec<MathNode>) -> MathNode {
+    match node {
+        MathNode::Scripts {
+            base,
+            superscript: None,
+            subscript,
+        } if raise => MathNode::Scripts {
+            base,
+            superscript: Some(argument),
+            subscript,
+        },
+        MathNode::Scripts {
+            base,
+            superscript,
+            subscript: None,
+        } if !raise => MathNode::Scripts {
+            base,
+            superscript,
+            subscript: Some(argument),
+        },
+        base => {
+            let (superscript, subscript) = if raise {
+                (Some(argument), None)
+            } else {
+                (None, Some(argument))
+            };
+            MathNode::Scripts {
+                base: Box::new(base),
+                superscript,
+                subscript,
+            }
+        }
+    }
+}
+
+/// Lays `source` out on one line: Greek letters and operators as their characters,
+/// scripts as raised or lowered ranges, and fractions as a raised numerator over a
+/// fraction slash and a lowered denominator.
+pub fn layout_math(source: &str) -> MathLayout {
+    let mut layout = MathLayout::default();
+    push_nodes(&parse_math(source), None, &mut layout);
+    layout
+}
+
+fn push_nodes(nodes: &[MathNode], script: Option<MathScript>, layout: &mut MathLayout) {
+    for node in nodes {
+        push_node(node, script, layout);
+    }
+}
+
+fn push_node(node: &MathNode, script: Option<MathScript>, layout: &mut MathLayout) {
+    match node {
+        MathNode::Text(text) => push_text(text, script, layout),
+        MathNode::Group(nodes) => push_nodes(nodes, script, layout),
+        MathNode::Fraction {
+            numerator,
+            denominator,
+        } => {
+            if script.is_some() {
+                // Already raised or lowered: keep it readable on the same level.
+                push_parenthesized(numerator, script, layout);
+                push_text("/", script, layout);
+                push_parenthesized(denominator, script, layout);
+            } else {
+                push_nodes(numerator, Some(MathScript::Superscript), layout);
+                push_text("\u{2044}", None, layout);
+                push_nodes(denominator, Some(MathScript::Subscript), layout);
+            }
+        }
+        MathNode::Root(radicand) => {
+            push_text("√", script, layout);
+            push_parenthesized(radicand, script, layout);
+        }
+        MathNode::Scripts {
+            base,
+            superscript,
+            subscript,
+        } => {
+            push_node(base, script, layout);
+            if let Some(nodes) = subscript {
+                push_nodes(nodes, script.or(Some(MathScript::Subscript)), layout);
+            }
+            if let Some(nodes) = superscript {
+                push_nodes(nodes, script.or(Some(MathScript::Superscript)), layout);
+            }
+        }
+    }
+}
+
+/// Pushes `nodes`, in parentheses unless they are a single character.
+fn push_parenthesized(nodes: &[MathNode], script: Option<MathScript>, layout: &mut MathLayout) {
+    let single = matches!(nodes, [MathNode::Text(text)] if text.chars().count() == 1);
+    if !single {
+        push_text("(", script, layout);
+    }
+    push_nodes(nodes, script, layout);
+    if !single {
+        push_text(")", script, layout);
+    }
+}
+
+fn push_text(text: &str, script: Option<MathScript>, layout: &mut MathLayout) {
+    let start = layout.text.len();
+    layout.text.push_str(text);
+    let Some(script) = script else {
+        return;
+    };
+    match layout.scripts.last_mut() {
+        Some((range, last)) if *last == script && range.end == start => {
+            range.end = layout.text.len();
+        }
+        _ => layout.scripts.push((start..layout.text.len(), script)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        MathLayout, MathNode, MathScript, MathToken, layout_math, parse_math, tokenize_math,
+    };
+
+    fn text(value: &str) -> MathNode {
+        MathNode::Text(value.to_string())
+    }
+
+    #[test]
+    fn fraction_tokenizes_into_a_fraction_node() {
+        assert_eq!(
+            tokenize_math(r"\frac{a}{b}"),
+            [
+                MathToken::Command("frac".to_string()),
+                MathToken::GroupStart,
+                MathToken::Symbol('a'),
+                MathToken::GroupEnd,
+                MathToken::GroupStart,
+                MathToken::Symbol('b'),
+                MathToken::GroupEnd,
+            ]
+        );
+        assert_eq!(
+            parse_math(r"\frac{a}{b}"),
+            [MathNode::Fraction {
+                numerator: vec![text("a")],
+                denominator: vec![text("b")],
+            }]
+        );
+        assert_eq!(
+            layout_math(r"\frac{a}{b}"),
+            MathLayout {
+                text: "a\u{2044}b".to_string(),
+                scripts: vec![
+                    (0..1, MathScript::Superscript),
+                    (4..5, MathScript::Subscript)
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn scripts_attach_to_the_atom_before_them() {
+        assert_eq!(
+            tokenize_math("x^2"),
+            [
+                MathToken::Symbol('x'),
+                MathToken::Superscript,
+                MathToken::Symbol('2'),
+            ]
+        );
+        assert_eq!(
+            parse_math("x^2"),
+            [MathNode::Scripts {
+                base: Box::new(text("x")),
+                superscript: Some(vec![text("2")]),
+                subscript: None,
+            }]
+        );
+        assert_eq!(
+            parse_math("a_i^{10}"),
+            [MathNode::Scripts {
+                base: Box::new(text("a")),
+                superscript: Some(vec![text("1"), text("0")]),
+                subscript: Some(vec![text("i")]),
+            }]
+        );
+        let layout = layout_math("x^{n+1}");
+        assert_eq!(layout.text, "xn+1");
+        assert_eq!(layout.scripts, [(1..4, MathScript::Superscript)]);
+    }
+
+    #[test]
+    fn greek_letters_and_operators_become_characters() {
+        assert_eq!(layout_math(r"\alpha \cdot \beta \leq \pi").text, "α·β≤π");
+        assert_eq!(layout_math(r"\sqrt{x+1}").text, "√(x+1)");
+    }
+}
@@ -1,11 +1,17 @@
+pub mod delimited;
 pub mod docx;
+pub mod epub;
 pub mod export;
+pub mod html;
+pub mod language;
+pub mod loader;
 pub mod markdown;
+pub mod math;
 pub mod model;
 pub mod pdf;
 pub mod txt;
 
-use std::path::Path;
+use std::{path::Path, time::SystemTime};
 
 use model::DocumentModel;
 use serde::{Deserialize, Serialize};
@@ -20,6 +26,7 @@ pub enum DocumentFormat {
     Docx,
     Pdf,
     Markdown,
+    Epub,
     Text,
     Unknown,
 }
@@ -29,6 +36,7 @@ pub fn detect_format(path: &Path) -> DocumentFormat {
         Some(ext) if ext == "docx" => DocumentFormat::Docx,
         Some(ext) if ext == "pdf" => DocumentFormat::Pdf,
         Some(ext) if ext == "md" || ext == "markdown" => DocumentFormat::Markdown,
+        Some(ext) if ext == "epub" => DocumentFormat::Epub,
         Some(ext)
             if matches!(
                 ext.as_str(),
@@ -84,10 +92,40 @@ pub fn detect_format(path: &Path) -> DocumentFormat {
     }
 }
 
+/// When `path` was last written, or None when its metadata can't be read.
+pub fn modified_time(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+}
+
+/// Whether a file last seen with modified time `recorded` has since been rewritten. Files
+/// never recorded, or no longer readable, don't count: there is nothing to reload from.
+pub fn changed_externally(recorded: Option<SystemTime>, current: Option<SystemTime>) -> bool {
+    matches!((recorded, current), (Some(recorded), Some(current)) if current != recorded)
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{DocumentFormat, detect_format};
-    use std::path::Path;
+    use super::{DocumentFormat, changed_externally, detect_format};
+    use std::{
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    #[test]
+    fn only_a_different_modified_time_counts_as_an_external_change() {
+        let loaded = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let later = loaded + Duration::from_secs(5);
+        let earlier = loaded - Duration::from_secs(5);
+
+        assert!(!changed_externally(Some(loaded), Some(loaded)));
+        assert!(changed_externally(Some(loaded), Some(later)));
+        // Restoring an older copy over the file still replaces what was loaded.
+        assert!(changed_externally(Some(loaded), Some(earlier)));
+        assert!(!changed_externally(None, Some(later)));
+        assert!(!changed_externally(Some(loaded), None));
+    }
 
     #[test]
     fn detects_common_text_extensions() {
@@ -96,5 +134,6 @@ mod tests {
         assert_eq!(detect_format(Path::new("c.rs")), DocumentFormat::Text);
         assert_eq!(detect_format(Path::new("d.md")), DocumentFormat::Markdown);
         assert_eq!(detect_format(Path::new("e.docx")), DocumentFormat::Docx);
+        assert_eq!(detect_format(Path::new("f.EPUB")), DocumentFormat::Epub);
     }
 }
@@ -1,4 +1,8 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -14,19 +18,99 @@ pub struct Document {
     pub content: Vec<Block>,
     pub styles: StyleSheet,
     pub images: HashMap<String, ImageData>,
+    /// Note bodies in reference order; runs point at them through `RunStyle::footnote`.
+    #[serde(default)]
+    pub footnotes: Vec<Footnote>,
+    /// Named places cross-references point at; runs name them through
+    /// `RunStyle::cross_reference`.
+    #[serde(default)]
+    pub anchors: Vec<Anchor>,
     pub dirty: bool,
+    /// Which state of the content this is; caches built from the document compare it to
+    /// tell whether they are still current.
+    #[serde(skip)]
+    pub revision: Revision,
+}
+
+/// Names one state of a document's content. Every edit takes a new value and values are
+/// never handed out twice, even across documents, so a reloaded or replaced document can't
+/// pass for the one a cache was built from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Revision(u64);
+
+impl Revision {
+    pub fn next() -> Self {
+        static NEXT: AtomicU64 = AtomicU64::new(1);
+        Self(NEXT.fetch_add(1, Ordering::Relaxed))
+    }
+}
+
+impl Default for Revision {
+    fn default() -> Self {
+        Self::next()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DocumentMetadata {
     pub title: String,
     pub author: String,
+    pub subject: String,
+    /// Comma- or semicolon-separated, as stored in DOCX `cp:keywords`.
+    pub keywords: String,
     pub created: Option<DateTime<Utc>>,
     pub modified: Option<DateTime<Utc>>,
     pub file_path: Option<PathBuf>,
     pub format: DocumentFormat,
     pub page_size: PageSize,
     pub margins: Margins,
+    /// Columns the text flows through, saved as the DOCX section's `w:cols`.
+    #[serde(default)]
+    pub columns: SectionColumns,
+    /// BCP 47 tag chosen for the document, stored as DOCX `dc:language`; `None` detects it
+    /// from the text.
+    #[serde(default)]
+    pub language: Option<String>,
+    /// How the plain-text file the document was read from is stored, so saving it as text
+    /// writes it back the same way.
+    #[serde(default)]
+    pub text_encoding: Option<TextEncoding>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextEncoding {
+    /// Encoding label as `encoding_rs` names it, e.g. `UTF-8` or `windows-1252`.
+    pub encoding_name: String,
+    pub line_ending: LineEnding,
+    /// Whether the file starts with a byte order mark.
+    pub bom: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+}
+
+impl LineEnding {
+    /// The line ending of the first line break in `text`, or LF when there is none.
+    pub fn detect(text: &str) -> Self {
+        match text.find(['\r', '\n']) {
+            Some(index) if text[index..].starts_with("\r\n") => Self::CrLf,
+            Some(index) if text[index..].starts_with('\r') => Self::Cr,
+            _ => Self::Lf,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::CrLf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
 }
 
 impl Default for DocumentMetadata {
@@ -34,12 +118,17 @@ impl Default for DocumentMetadata {
         Self {
             title: String::new(),
             author: String::new(),
+            subject: String::new(),
+            keywords: String::new(),
             created: None,
             modified: None,
             file_path: None,
             format: DocumentFormat::Unknown,
             page_size: PageSize::Letter,
             margins: Margins::default(),
+            columns: SectionColumns::default(),
+            language: None,
+            text_encoding: None,
         }
     }
 }
@@ -76,6 +165,12 @@ pub struct Paragraph {
     pub spacing: ParagraphSpacing,
     pub indent: Indent,
     pub style_id: Option<String>,
+    /// Reading direction; `alignment` is relative to it, so `Left` means the line's start.
+    #[serde(default)]
+    pub direction: TextDirection,
+    /// Border and shading drawn around the paragraph, as callouts have.
+    #[serde(default)]
+    pub frame: Option<ParagraphFrame>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -96,6 +191,60 @@ pub struct RunStyle {
     pub background: Option<Color>,
     pub superscript: bool,
     pub subscript: bool,
+    /// Label of the footnote this run references; the run text is the note's number.
+    #[serde(default)]
+    pub footnote: Option<String>,
+    /// Block a click on this run jumps to, e.g. a Table of Contents line's heading.
+    #[serde(default)]
+    pub internal_link: Option<BlockId>,
+    /// Marks the run text as TeX math source, typeset when shown.
+    #[serde(default)]
+    pub math: Option<MathMode>,
+    /// Page name of a `[[wiki link]]`, resolved against the workspace when followed.
+    #[serde(default)]
+    pub wiki_link: Option<String>,
+    /// Anchor this run refers to; the run text is kept in step with the anchored block.
+    #[serde(default)]
+    pub cross_reference: Option<CrossReference>,
+}
+
+/// A heading or paragraph given a name, kept as a DOCX bookmark and an HTML `id`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Anchor {
+    pub name: String,
+    pub block_id: BlockId,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CrossReference {
+    pub anchor: String,
+    #[serde(default)]
+    pub display: CrossReferenceDisplay,
+}
+
+/// What a cross-reference shows of its target.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum CrossReferenceDisplay {
+    /// The anchored block's text.
+    #[default]
+    Text,
+    /// The outline number of the anchored heading, or of the section the block is in,
+    /// e.g. "2.1".
+    Number,
+}
+
+/// How a math run sits in its paragraph: `$inline$` or `$$display$$` in Markdown.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum MathMode {
+    Inline,
+    Display,
+}
+
+/// A footnote body, keyed by the label used in Markdown (`[^label]`) or the DOCX note id.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct Footnote {
+    pub label: String,
+    pub runs: Vec<Run>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -110,6 +259,30 @@ pub struct Table {
     pub cell_padding: f32,
     pub header_row: bool,
     pub alternating_rows: bool,
+    /// Repeat the first row at the top of every page the table continues onto.
+    #[serde(default)]
+    pub header_repeat: bool,
+    /// Text after the "Table N" label, which is numbered from document order when drawn.
+    #[serde(default)]
+    pub caption: Option<Vec<Run>>,
+}
+
+impl Table {
+    pub const HEADER_FILL: Color = Color::rgb(0.84, 0.9, 0.98);
+    pub const BAND_FILL: Color = Color::rgba(0.7, 0.74, 0.8, 0.14);
+
+    /// The fill drawn behind row `row`: the header tint on the first row of a table with a
+    /// header row, and the band on odd rows when `alternating_rows` is on. Worked out from the
+    /// row's current index when drawn, so inserting or deleting rows rebands the rest.
+    pub fn row_fill(&self, row: usize) -> Option<Color> {
+        if self.header_row && row == 0 {
+            Some(Self::HEADER_FILL)
+        } else if self.alternating_rows && row % 2 == 1 {
+            Some(Self::BAND_FILL)
+        } else {
+            None
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -140,6 +313,7 @@ pub struct ImageBlock {
     pub data: ImageDataRef,
     pub original_width: u32,
     pub original_height: u32,
+    /// Text after the "Figure N" label, which is numbered from document order when drawn.
     pub caption: Option<Vec<Run>>,
     pub border: Option<ImageBorder>,
     pub crop: Option<CropRect>,
@@ -155,6 +329,9 @@ pub struct ImageBlock {
 pub struct BlockQuote {
     pub id: BlockId,
     pub blocks: Vec<Block>,
+    /// Set when the quote is a GitHub-style alert (`> [!NOTE]`), drawn as a callout.
+    #[serde(default)]
+    pub callout: Option<CalloutKind>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -208,6 +385,25 @@ impl Default for ParagraphAlignment {
     }
 }
 
+impl ParagraphAlignment {
+    /// The side of the page lines actually sit on: a right-to-left paragraph starts at the
+    /// right, so its `Left` and `Right` swap.
+    pub fn visual(&self, direction: TextDirection) -> Self {
+        match (self, direction) {
+            (Self::Left, TextDirection::Rtl) => Self::Right,
+            (Self::Right, TextDirection::Rtl) => Self::Left,
+            (alignment, _) => alignment.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+pub enum TextDirection {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ParagraphSpacing {
     pub before: f32,
@@ -230,6 +426,39 @@ pub struct Margins {
     pub left: f32,
 }
 
+/// Newspaper-style columns: text fills the first column of a page before moving to the next.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SectionColumns {
+    /// 1 for ordinary single-column text.
+    pub count: u8,
+    /// Gap between neighbouring columns, in points.
+    pub spacing: f32,
+}
+
+impl SectionColumns {
+    pub const MAX_COUNT: u8 = 3;
+    /// Word's default gap of half an inch.
+    pub const DEFAULT_SPACING: f32 = 36.0;
+
+    pub fn new(count: u8) -> Self {
+        Self {
+            count: count.clamp(1, Self::MAX_COUNT),
+            spacing: Self::DEFAULT_SPACING,
+        }
+    }
+
+    /// The column count, kept within 1..=`MAX_COUNT` whatever a file asked for.
+    pub fn count(&self) -> usize {
+        usize::from(self.count.clamp(1, Self::MAX_COUNT))
+    }
+}
+
+impl Default for SectionColumns {
+    fn default() -> Self {
+        Self::new(1)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct StyleSheet {
     pub styles: HashMap<String, NamedStyle>,
@@ -295,6 +524,175 @@ impl Default for ImageBorderStyle {
     }
 }
 
+/// A box around one paragraph: a border, background shading, or both.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct ParagraphFrame {
+    pub border: Option<ParagraphBorder>,
+    pub shading: Option<Color>,
+    /// Symbol drawn at the start of the box, e.g. the note preset's "ℹ". Only the canvas and
+    /// HTML show it; DOCX has nowhere to keep it.
+    #[serde(default)]
+    pub icon: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+pub struct ParagraphBorder {
+    pub color: Color,
+    /// Line width in points.
+    pub width: f32,
+}
+
+/// Ready-made paragraph frames for callouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParagraphFramePreset {
+    Note,
+    Warning,
+    Tip,
+}
+
+impl ParagraphFramePreset {
+    pub const ALL: [Self; 3] = [Self::Note, Self::Warning, Self::Tip];
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|preset| preset.label().eq_ignore_ascii_case(label.trim()))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Note => "Note",
+            Self::Warning => "Warning",
+            Self::Tip => "Tip",
+        }
+    }
+
+    pub fn frame(self) -> ParagraphFrame {
+        let (icon, border, shading) = match self {
+            Self::Note => (
+                "\u{2139}",
+                Color::rgb(0.25, 0.47, 0.85),
+                Color::rgb(0.91, 0.95, 1.0),
+            ),
+            Self::Warning => (
+                "\u{26A0}",
+                Color::rgb(0.85, 0.6, 0.1),
+                Color::rgb(1.0, 0.96, 0.86),
+            ),
+            Self::Tip => (
+                "\u{1F4A1}",
+                Color::rgb(0.2, 0.62, 0.35),
+                Color::rgb(0.91, 0.97, 0.92),
+            ),
+        };
+        ParagraphFrame {
+            border: Some(ParagraphBorder {
+                color: border,
+                width: 1.5,
+            }),
+            shading: Some(shading),
+            icon: Some(icon.to_string()),
+        }
+    }
+}
+
+/// What a caption numbers: images are figures, tables count separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CaptionKind {
+    Figure,
+    Table,
+}
+
+impl CaptionKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Figure => "Figure",
+            Self::Table => "Table",
+        }
+    }
+}
+
+/// Kinds of callout block, one per GitHub alert marker.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CalloutKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Danger,
+}
+
+impl CalloutKind {
+    pub const ALL: [Self; 5] = [
+        Self::Note,
+        Self::Tip,
+        Self::Important,
+        Self::Warning,
+        Self::Danger,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Note => "Note",
+            Self::Tip => "Tip",
+            Self::Important => "Important",
+            Self::Warning => "Warning",
+            Self::Danger => "Danger",
+        }
+    }
+
+    /// The word between `[!` and `]` on the quote's first line. GitHub calls danger
+    /// "CAUTION".
+    pub fn alert_marker(self) -> &'static str {
+        match self {
+            Self::Note => "NOTE",
+            Self::Tip => "TIP",
+            Self::Important => "IMPORTANT",
+            Self::Warning => "WARNING",
+            Self::Danger => "CAUTION",
+        }
+    }
+
+    /// Reads a marker in any case; "DANGER" is taken as a synonym for "CAUTION".
+    pub fn from_alert_marker(marker: &str) -> Option<Self> {
+        let marker = marker.trim();
+        if marker.eq_ignore_ascii_case("danger") {
+            return Some(Self::Danger);
+        }
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.alert_marker().eq_ignore_ascii_case(marker))
+    }
+
+    /// Border, shading and icon of the callout box, shared with the paragraph presets
+    /// where there is one.
+    pub fn frame(self) -> ParagraphFrame {
+        let (icon, border, shading) = match self {
+            Self::Note => return ParagraphFramePreset::Note.frame(),
+            Self::Tip => return ParagraphFramePreset::Tip.frame(),
+            Self::Warning => return ParagraphFramePreset::Warning.frame(),
+            Self::Important => (
+                "\u{2757}",
+                Color::rgb(0.55, 0.35, 0.85),
+                Color::rgb(0.95, 0.92, 1.0),
+            ),
+            Self::Danger => (
+                "\u{26D4}",
+                Color::rgb(0.82, 0.2, 0.2),
+                Color::rgb(1.0, 0.92, 0.92),
+            ),
+        };
+        ParagraphFrame {
+            border: Some(ParagraphBorder {
+                color: border,
+                width: 1.5,
+            }),
+            shading: Some(shading),
+            icon: Some(icon.to_string()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct ImageBorder {
     pub style: ImageBorderStyle,
@@ -361,6 +759,13 @@ impl Default for ListType {
 }
 
 impl Document {
+    /// Records a change to the content made outside the edit engine: the document has
+    /// unsaved changes and takes a new revision.
+    pub fn mark_edited(&mut self) {
+        self.dirty = true;
+        self.revision = Revision::next();
+    }
+
     pub fn next_block_id(&self) -> BlockId {
         fn walk(block: &Block, max: &mut u64) {
             match block {
@@ -409,6 +814,37 @@ impl Document {
         BlockId(max + 1)
     }
 
+    /// Orders notes by first reference (unreferenced notes keep their order at the end) and
+    /// rewrites each reference run to its note's number.
+    pub fn renumber_footnotes(&mut self) {
+        let mut order: Vec<String> = Vec::new();
+        for_each_run_mut(&mut self.content, &mut |run| {
+            if let Some(label) = &run.style.footnote
+                && !order.contains(label)
+            {
+                order.push(label.clone());
+            }
+        });
+        self.footnotes.sort_by_key(|note| order.iter().position(|label| *label == note.label).unwrap_or(usize::MAX));
+
+        let numbers = self
+            .footnotes
+            .iter()
+            .enumerate()
+            .map(|(index, note)| (note.label.clone(), index + 1))
+            .collect::<HashMap<_, _>>();
+        for_each_run_mut(&mut self.content, &mut |run| {
+            if let Some(number) = run.style.footnote.as_ref().and_then(|label| numbers.get(label)) {
+                run.text = number.to_string();
+            }
+        });
+    }
+
+    /// 1-based display number of the footnote with `label`.
+    pub fn footnote_number(&self, label: &str) -> Option<usize> {
+        self.footnotes.iter().position(|note| note.label == label).map(|index| index + 1)
+    }
+
     pub fn insert_embedded_image_after(
         &mut self,
         after_block_id: Option<BlockId>,
@@ -454,7 +890,7 @@ impl Document {
             })
             .unwrap_or(self.content.len());
         self.content.insert(insert_index, image_block);
-        self.dirty = true;
+        self.mark_edited();
         block_id
     }
 
@@ -489,7 +925,7 @@ impl Document {
             key,
             source_path: None,
         }));
-        self.dirty = true;
+        self.mark_edited();
     }
 
     pub fn find_image_block_mut(&mut self, block_id: BlockId) -> Option<&mut ImageBlock> {
@@ -545,6 +981,38 @@ impl Document {
         None
     }
 
+    /// Calls `f` with every image block, including those inside tables, lists and quotes.
+    pub fn for_each_image_block_mut(&mut self, mut f: impl FnMut(&mut ImageBlock)) {
+        fn walk(block: &mut Block, f: &mut impl FnMut(&mut ImageBlock)) {
+            match block {
+                Block::Image(image) => f(image),
+                Block::Table(table) => table
+                    .rows
+                    .iter_mut()
+                    .flat_map(|row| row.cells.iter_mut())
+                    .flat_map(|cell| cell.blocks.iter_mut())
+                    .for_each(|nested| walk(nested, f)),
+                Block::List(list) => {
+                    for item in &mut list.items {
+                        let children = item
+                            .children
+                            .iter_mut()
+                            .flat_map(|child| &mut child.content);
+                        for nested in item.content.iter_mut().chain(children) {
+                            walk(nested, f);
+                        }
+                    }
+                }
+                Block::BlockQuote(q) => q.blocks.iter_mut().for_each(|nested| walk(nested, f)),
+                _ => {}
+            }
+        }
+
+        for block in &mut self.content {
+            walk(block, &mut f);
+        }
+    }
+
     pub fn remove_image_block(&mut self, block_id: BlockId) -> bool {
         let mut removed = false;
         let mut removed_key = None;
@@ -566,7 +1034,7 @@ impl Document {
             self.images.remove(key.as_str());
         }
         if removed {
-            self.dirty = true;
+            self.mark_edited();
         }
         removed
     }
@@ -585,6 +1053,35 @@ fn block_id_for_block(block: &Block) -> Option<BlockId> {
     }
 }
 
+/// Visits every run of `blocks`, including those in tables, lists and quotes.
+pub fn for_each_run_mut(blocks: &mut [Block], visit: &mut impl FnMut(&mut Run)) {
+    fn walk_item(item: &mut ListItem, visit: &mut impl FnMut(&mut Run)) {
+        for_each_run_mut(&mut item.content, visit);
+        for child in &mut item.children {
+            walk_item(child, visit);
+        }
+    }
+
+    for block in blocks {
+        match block {
+            Block::Paragraph(p) => p.runs.iter_mut().for_each(&mut *visit),
+            Block::Heading(h) => h.runs.iter_mut().for_each(&mut *visit),
+            Block::Table(t) => {
+                for cell in t.rows.iter_mut().flat_map(|row| row.cells.iter_mut()) {
+                    for_each_run_mut(&mut cell.blocks, visit);
+                }
+            }
+            Block::List(list) => {
+                for item in &mut list.items {
+                    walk_item(item, visit);
+                }
+            }
+            Block::BlockQuote(q) => for_each_run_mut(&mut q.blocks, visit),
+            Block::Image(_) | Block::CodeBlock(_) | Block::PageBreak | Block::HorizontalRule => {}
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -600,9 +1097,49 @@ mod tests {
             spacing: ParagraphSpacing::default(),
             indent: Indent::default(),
             style_id: None,
+            direction: TextDirection::Ltr,
+            frame: None,
         })
     }
 
+    #[test]
+    fn frame_presets_set_icon_border_and_shading() {
+        let note = ParagraphFramePreset::Note.frame();
+        assert_eq!(note.icon.as_deref(), Some("\u{2139}"));
+        let border = note.border.expect("note border");
+        assert_eq!(border.color, Color::rgb(0.25, 0.47, 0.85));
+        assert_eq!(border.width, 1.5);
+        assert_eq!(note.shading, Some(Color::rgb(0.91, 0.95, 1.0)));
+
+        let warning = ParagraphFramePreset::Warning.frame();
+        assert_eq!(warning.icon.as_deref(), Some("\u{26A0}"));
+        assert_eq!(
+            warning.border.map(|border| border.color),
+            Some(Color::rgb(0.85, 0.6, 0.1))
+        );
+        assert_eq!(
+            ParagraphFramePreset::Tip.frame().icon.as_deref(),
+            Some("\u{1F4A1}")
+        );
+
+        // Each preset looks different from the others.
+        let frames = ParagraphFramePreset::ALL.map(ParagraphFramePreset::frame);
+        assert_ne!(frames[0], frames[1]);
+        assert_ne!(frames[1], frames[2]);
+        assert_ne!(frames[0], frames[2]);
+    }
+
+    #[test]
+    fn right_to_left_swaps_left_and_right_alignment() {
+        use ParagraphAlignment::{Center, Justify, Left, Right};
+        assert!(matches!(Left.visual(TextDirection::Rtl), Right));
+        assert!(matches!(Right.visual(TextDirection::Rtl), Left));
+        assert!(matches!(Center.visual(TextDirection::Rtl), Center));
+        assert!(matches!(Justify.visual(TextDirection::Rtl), Justify));
+        assert!(matches!(Left.visual(TextDirection::Ltr), Left));
+        assert!(matches!(Right.visual(TextDirection::Ltr), Right));
+    }
+
     #[test]
     fn insert_embedded_image_after_cursor_block() {
         let mut doc = Document::default();
@@ -4,18 +4,26 @@ use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8, WINDOWS_1252};
 use ropey::Rope;
 
 use crate::document::DocumentFormat;
+use crate::document::export::to_plain_text;
 use crate::document::model::{
     Block,
     BlockId,
     DocumentModel,
+    LineEnding,
     Paragraph,
     ParagraphAlignment,
     ParagraphSpacing,
     Run,
     RunStyle,
+    TextEncoding,
 };
 
 pub mod renderer;
+pub mod view;
+
+/// Text files at least this large open as a [`view::TextView`] over their rope instead of
+/// one paragraph block per line.
+pub const STREAMING_THRESHOLD_BYTES: u64 = 32 * 1024 * 1024;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextWrapMode {
@@ -30,16 +38,24 @@ pub enum TextEditError {
     ColumnOutOfBounds,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TextDocument {
     pub rope: Rope,
     pub encoding_name: String,
+    /// Written between lines on save, whatever breaks the rope holds.
+    pub line_ending: LineEnding,
+    pub bom: bool,
     pub monospaced: bool,
     pub line_numbers: bool,
     pub wrap_mode: TextWrapMode,
 }
 
 impl TextDocument {
+    /// Whether `path` is a file big enough to open through a [`view::TextView`].
+    pub fn should_stream(path: &Path) -> bool {
+        fs::metadata(path).is_ok_and(|meta| meta.len() >= STREAMING_THRESHOLD_BYTES)
+    }
+
     pub fn load_from_path(path: &Path) -> std::io::Result<Self> {
         let bytes = fs::read(path)?;
         Ok(Self::from_bytes(&bytes))
@@ -48,6 +64,8 @@ impl TextDocument {
     pub fn from_bytes(bytes: &[u8]) -> Self {
         let (text, encoding_name) = decode_text(bytes);
         Self {
+            line_ending: LineEnding::detect(&text),
+            bom: Encoding::for_bom(bytes).is_some(),
             rope: Rope::from_str(&text),
             encoding_name,
             monospaced: true,
@@ -60,12 +78,56 @@ impl TextDocument {
         Self {
             rope: Rope::from_str(text),
             encoding_name: "UTF-8".to_string(),
+            line_ending: LineEnding::detect(text),
+            bom: false,
             monospaced: true,
             line_numbers: true,
             wrap_mode: TextWrapMode::WordBoundary,
         }
     }
 
+    /// The text a model from [`Self::to_document_model`] holds, one paragraph per line, with
+    /// the encoding and line ending it was read with.
+    pub fn from_document_model(model: &DocumentModel) -> Self {
+        let text = model
+            .content
+            .iter()
+            .map(|block| match block {
+                Block::Paragraph(p) => p.runs.iter().map(|run| run.text.as_str()).collect(),
+                block => {
+                    let mut single = DocumentModel::default();
+                    single.content.push(block.clone());
+                    to_plain_text(&single).trim_end_matches('\n').to_string()
+                }
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let mut document = Self::from_text(&text);
+        if let Some(encoding) = &model.metadata.text_encoding {
+            document.encoding_name = encoding.encoding_name.clone();
+            document.line_ending = encoding.line_ending;
+            document.bom = encoding.bom;
+        }
+        document
+    }
+
+    /// The file contents: every line break written as `line_ending`, encoded as
+    /// `encoding_name`. Text the encoding can't represent saves as UTF-8 instead of being
+    /// replaced.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let text = self
+            .rope
+            .lines()
+            .map(|line| trim_line_breaks(line.to_string()))
+            .collect::<Vec<_>>()
+            .join(self.line_ending.as_str());
+        encode_text(&text, &self.encoding_name, self.bom)
+    }
+
+    pub fn save_to_path(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, self.to_bytes())
+    }
+
     pub fn line_count(&self) -> usize {
         self.rope.len_lines()
     }
@@ -154,6 +216,11 @@ impl TextDocument {
     pub fn to_document_model(&self) -> DocumentModel {
         let mut model = DocumentModel::default();
         model.metadata.format = DocumentFormat::Text;
+        model.metadata.text_encoding = Some(TextEncoding {
+            encoding_name: self.encoding_name.clone(),
+            line_ending: self.line_ending,
+            bom: self.bom,
+        });
         model.content = self
             .rope
             .lines()
@@ -176,6 +243,8 @@ impl TextDocument {
                     spacing: ParagraphSpacing::default(),
                     indent: crate::document::model::Indent::default(),
                     style_id: None,
+                    direction: Default::default(),
+                    frame: None,
                 })
             })
             .collect();
@@ -203,7 +272,7 @@ impl TextDocument {
     }
 }
 
-fn decode_text(bytes: &[u8]) -> (String, String) {
+pub(crate) fn decode_text(bytes: &[u8]) -> (String, String) {
     if bytes.is_empty() {
         return (String::new(), "UTF-8".to_string());
     }
@@ -231,6 +300,34 @@ fn decode_text(bytes: &[u8]) -> (String, String) {
     decode_with_encoding(bytes, WINDOWS_1252)
 }
 
+pub(crate) fn encode_text(text: &str, encoding_name: &str, bom: bool) -> Vec<u8> {
+    let label = encoding_name.trim_end_matches(" (heuristic)");
+    let encoding = Encoding::for_label(label.as_bytes()).unwrap_or(UTF_8);
+    let mut bytes = Vec::with_capacity(text.len() + 3);
+    // encoding_rs only decodes UTF-16; its encoders write UTF-8 for it.
+    if encoding == UTF_16LE || encoding == UTF_16BE {
+        let units = bom.then_some(0xFEFF).into_iter().chain(text.encode_utf16());
+        for unit in units {
+            if encoding == UTF_16LE {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            } else {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        }
+        return bytes;
+    }
+    let (encoded, _, unmappable) = encoding.encode(text);
+    if encoding == UTF_8 || unmappable {
+        if bom || unmappable {
+            bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+        }
+        bytes.extend_from_slice(text.as_bytes());
+    } else {
+        bytes.extend_from_slice(&encoded);
+    }
+    bytes
+}
+
 fn decode_with_encoding(bytes: &[u8], encoding: &'static Encoding) -> (String, String) {
     let (text, _, _) = encoding.decode(bytes);
     (text.into_owned(), encoding.name().to_string())
@@ -321,6 +418,35 @@ mod tests {
         assert_eq!(doc.line_text(1).as_deref(), Some("XYZ"));
     }
 
+    #[test]
+    fn saving_keeps_windows1252_bytes_and_crlf() {
+        let original = b"caf\xE9\r\nna\xEFve\r\n".to_vec();
+        let model = TextDocument::from_bytes(&original).to_document_model();
+        assert_eq!(
+            model.metadata.text_encoding.as_ref().map(|e| e.line_ending),
+            Some(LineEnding::CrLf)
+        );
+
+        let path = std::env::temp_dir().join(format!("doco-1252-{}.txt", std::process::id()));
+        crate::document::export::save_with_format(&path, &model).expect("save text");
+        let saved = fs::read(&path).expect("read saved text");
+        let _ = fs::remove_file(&path);
+        assert_eq!(saved, original);
+    }
+
+    #[test]
+    fn utf16_and_unmappable_text_save_without_loss() {
+        let mut bytes = vec![0xFF, 0xFE];
+        for unit in "Hi\nthere".encode_utf16() {
+            bytes.extend_from_slice(&unit.to_le_bytes());
+        }
+        assert_eq!(TextDocument::from_bytes(&bytes).to_bytes(), bytes);
+
+        let mut doc = TextDocument::from_bytes(&[0x63, 0x61, 0x66, 0xE9]);
+        doc.insert_text(0, 4, " \u{2603}").expect("insert snowman");
+        assert_eq!(doc.to_bytes(), "\u{feff}café \u{2603}".as_bytes());
+    }
+
     #[test]
     fn to_document_model_respects_font_option() {
         let mut doc = TextDocument::from_text("line");
@@ -0,0 +1,187 @@
+use crate::document::txt::{TextDocument, TextEditError};
+
+/// A text file too large to turn into one paragraph block per line, shown and edited
+/// straight from its rope. Only the lines on screen become strings.
+#[derive(Debug, Clone)]
+pub struct TextView {
+    pub document: TextDocument,
+    /// First line on screen, zero-based.
+    pub top_line: usize,
+    /// Caret as a zero-based line and a column in chars.
+    pub cursor: (usize, usize),
+    pub dirty: bool,
+}
+
+impl TextView {
+    pub fn new(document: TextDocument) -> Self {
+        Self {
+            document,
+            top_line: 0,
+            cursor: (0, 0),
+            dirty: false,
+        }
+    }
+
+    /// The text of up to `rows` lines from `top_line` down, without line breaks.
+    pub fn visible_lines(&self, rows: usize) -> Vec<String> {
+        let end = self
+            .top_line
+            .saturating_add(rows)
+            .min(self.document.line_count());
+        (self.top_line..end)
+            .filter_map(|line| self.document.line_text(line))
+            .collect()
+    }
+
+    /// Moves the view `lines` down (up when negative), keeping the last line reachable.
+    pub fn scroll_by(&mut self, lines: isize) {
+        let last = self.document.line_count().saturating_sub(1);
+        self.top_line = self.top_line.saturating_add_signed(lines).min(last);
+    }
+
+    /// Scrolls just enough to show the caret in a view `rows` lines tall.
+    pub fn reveal_cursor(&mut self, rows: usize) {
+        let line = self.cursor.0;
+        if line < self.top_line {
+            self.top_line = line;
+        } else if line >= self.top_line + rows.max(1) {
+            self.top_line = line + 1 - rows.max(1);
+        }
+    }
+
+    /// Types `text` at the caret and moves the caret past it.
+    pub fn insert(&mut self, text: &str) -> Result<(), TextEditError> {
+        let (line, column) = self.cursor;
+        self.document.insert_text(line, column, text)?;
+        let breaks = text.matches('\n').count();
+        self.cursor = if breaks == 0 {
+            (line, column + text.chars().count())
+        } else {
+            let tail = text.rsplit('\n').next().unwrap_or_default();
+            (line + breaks, tail.chars().count())
+        };
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Deletes the character before the caret, joining with the line above at a line start.
+    pub fn backspace(&mut self) {
+        let (line, column) = self.cursor;
+        let start = match (line, column) {
+            (0, 0) => return,
+            (line, 0) => (line - 1, self.line_len(line - 1)),
+            (line, column) => (line, column - 1),
+        };
+        if self.document.delete_range(start, self.cursor).is_ok() {
+            self.cursor = start;
+            self.dirty = true;
+        }
+    }
+
+    /// Deletes the character after the caret, joining the next line at a line end.
+    pub fn delete_forward(&mut self) {
+        let (line, column) = self.cursor;
+        let end = if column < self.line_len(line) {
+            (line, column + 1)
+        } else if line + 1 < self.document.line_count() {
+            (line + 1, 0)
+        } else {
+            return;
+        };
+        if self.document.delete_range(self.cursor, end).is_ok() {
+            self.dirty = true;
+        }
+    }
+
+    /// Moves the caret `lines` down and `columns` right, wrapping across line ends when
+    /// moving sideways and keeping the column within the line when moving up or down.
+    pub fn move_cursor(&mut self, lines: isize, columns: isize) {
+        let last = self.document.line_count().saturating_sub(1);
+        let (mut line, mut column) = self.cursor;
+        line = line.saturating_add_signed(lines).min(last);
+        column = column.min(self.line_len(line));
+        if columns < 0 {
+            for _ in 0..columns.unsigned_abs() {
+                if column > 0 {
+                    column -= 1;
+                } else if line > 0 {
+                    line -= 1;
+                    column = self.line_len(line);
+                }
+            }
+        } else {
+            for _ in 0..columns {
+                if column < self.line_len(line) {
+                    column += 1;
+                } else if line < last {
+                    line += 1;
+                    column = 0;
+                }
+            }
+        }
+        self.cursor = (line, column);
+    }
+
+    /// Puts the caret at the start or end of its line.
+    pub fn move_to_line_edge(&mut self, end: bool) {
+        let line = self.cursor.0;
+        self.cursor.1 = if end { self.line_len(line) } else { 0 };
+    }
+
+    fn line_len(&self, line: usize) -> usize {
+        self.document
+            .line_text(line)
+            .map_or(0, |text| text.chars().count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextView;
+    use crate::document::txt::TextDocument;
+
+    fn numbered_lines(count: usize) -> TextView {
+        let text: String = (0..count).map(|i| format!("line-{i}\n")).collect();
+        TextView::new(TextDocument::from_text(&text))
+    }
+
+    #[test]
+    fn visible_lines_start_at_any_scroll_offset() {
+        let mut view = numbered_lines(200_000);
+        view.scroll_by(123_456);
+
+        assert_eq!(
+            view.visible_lines(3),
+            vec!["line-123456", "line-123457", "line-123458"]
+        );
+
+        view.scroll_by(-1_000_000);
+        assert_eq!(view.visible_lines(1), vec!["line-0"]);
+
+        // The trailing line break leaves one empty last line; scrolling stops there.
+        view.scroll_by(isize::MAX);
+        assert_eq!(view.top_line, 200_000);
+        assert_eq!(view.visible_lines(40), vec![""]);
+    }
+
+    #[test]
+    fn edits_go_to_the_rope_at_the_caret() {
+        let mut view = TextView::new(TextDocument::from_text("abc\ndef"));
+        view.move_cursor(1, 1);
+        view.insert("X\nY").expect("insert");
+        assert_eq!(view.visible_lines(3), vec!["abc", "dX", "Yef"]);
+        assert_eq!(view.cursor, (2, 1));
+
+        view.move_cursor(0, -1);
+        view.backspace();
+        assert_eq!(view.visible_lines(3), vec!["abc", "dXYef"]);
+        assert_eq!(view.cursor, (1, 2));
+
+        view.move_to_line_edge(true);
+        view.delete_forward();
+        view.move_cursor(-1, 0);
+        view.delete_forward();
+        assert_eq!(view.visible_lines(3), vec!["abcdXYef"]);
+        assert!(view.dirty);
+    }
+}
@@ -0,0 +1,155 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::model::{Block, BlockId},
+    settings::document_store::{DocumentStore, data_file_path},
+};
+
+use super::block_id_of;
+
+/// How a bookmarked block is found again after reopening: block ids are handed out afresh
+/// on every load, so a bookmark remembers the block's text and which of the blocks sharing
+/// that text it was, plus its position for when the text has since changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookmarkAnchor {
+    pub text: String,
+    pub occurrence: usize,
+    pub index: usize,
+}
+
+/// A bookmark as kept between sessions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SavedBookmark {
+    pub name: String,
+    pub page_number: usize,
+    pub snippet: String,
+    pub anchor: BookmarkAnchor,
+}
+
+/// The text a top-level block is recognised by; blocks without text share the empty key.
+fn anchor_text(block: &Block) -> String {
+    let runs = match block {
+        Block::Paragraph(paragraph) => &paragraph.runs,
+        Block::Heading(heading) => &heading.runs,
+        Block::CodeBlock(code) => return code.code.trim().to_string(),
+        Block::BlockQuote(quote) => {
+            return quote.blocks.first().map(anchor_text).unwrap_or_default();
+        }
+        _ => return String::new(),
+    };
+    runs.iter()
+        .map(|run| run.text.as_str())
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+/// The anchor for the top-level block `id` of `blocks`; `None` when no such block exists.
+pub fn bookmark_anchor(blocks: &[Block], id: BlockId) -> Option<BookmarkAnchor> {
+    let index = blocks
+        .iter()
+        .position(|block| block_id_of(block) == Some(id))?;
+    let text = anchor_text(&blocks[index]);
+    let occurrence = blocks[..index]
+        .iter()
+        .filter(|block| anchor_text(block) == text)
+        .count();
+    Some(BookmarkAnchor {
+        text,
+        occurrence,
+        index,
+    })
+}
+
+/// The block of `blocks` the anchor points at. A block with the anchor's text wins, the
+/// last one if the occurrence ran past the end; otherwise the block now at its position.
+pub fn resolve_bookmark(blocks: &[Block], anchor: &BookmarkAnchor) -> Option<BlockId> {
+    let same_text = blocks
+        .iter()
+        .filter(|block| anchor_text(block) == anchor.text)
+        .filter_map(block_id_of)
+        .collect::<Vec<_>>();
+    same_text
+        .get(anchor.occurrence)
+        .or(same_text.last())
+        .copied()
+        .or_else(|| {
+            let index = anchor.index.min(blocks.len().checked_sub(1)?);
+            block_id_of(&blocks[index])
+        })
+}
+
+/// Bookmarks per document path, kept in `bookmarks.json` next to the other app data.
+pub type BookmarkStore = DocumentStore<SavedBookmark>;
+
+pub fn bookmarks_path() -> PathBuf {
+    data_file_path("bookmarks.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::model::{Heading, Paragraph, Run};
+
+    fn runs(text: &str) -> Vec<Run> {
+        vec![Run {
+            text: text.to_string(),
+            ..Run::default()
+        }]
+    }
+
+    fn heading(id: u64, text: &str) -> Block {
+        Block::Heading(Heading {
+            id: BlockId(id),
+            level: 1,
+            runs: runs(text),
+        })
+    }
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: runs(text),
+            alignment: Default::default(),
+            spacing: Default::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    #[test]
+    fn bookmarks_follow_their_block_when_blocks_above_change() {
+        let doc = vec![
+            paragraph(1, "Note"),
+            heading(2, "Usage"),
+            paragraph(3, "Note"),
+        ];
+        // The second "Note" paragraph.
+        let anchor = bookmark_anchor(&doc, BlockId(3)).expect("anchor");
+
+        let reopened = vec![
+            heading(10, "Overview"),
+            paragraph(11, "Note"),
+            heading(12, "Usage"),
+            paragraph(13, "Note"),
+        ];
+        assert_eq!(resolve_bookmark(&reopened, &anchor), Some(BlockId(13)));
+
+        // Its text changed: the block now at its position stands in for it.
+        let edited = vec![
+            paragraph(20, "Preface"),
+            heading(21, "Usage"),
+            paragraph(22, "Reworded"),
+        ];
+        assert_eq!(resolve_bookmark(&edited, &anchor), Some(BlockId(22)));
+        assert_eq!(
+            resolve_bookmark(&[heading(30, "Only")], &anchor),
+            Some(BlockId(30))
+        );
+        assert_eq!(resolve_bookmark(&[], &anchor), None);
+    }
+}
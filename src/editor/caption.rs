@@ -0,0 +1,504 @@
+use std::ops::Range;
+
+use crate::{
+    document::model::{Block, BlockId, CaptionKind, Indent, ListItem, Paragraph, Run, RunStyle},
+    editor::commands::EditCommand,
+};
+
+/// Paragraph style of List of Figures and List of Tables lines, as Word names it.
+pub const CAPTION_LIST_STYLE: &str = "TableofFigures";
+
+/// The number a captioned figure or table shows, counted per kind in document order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptionNumber {
+    pub block_id: BlockId,
+    pub kind: CaptionKind,
+    pub number: u32,
+}
+
+impl CaptionNumber {
+    /// "Figure 2".
+    pub fn label(&self) -> String {
+        format!("{} {}", self.kind.label(), self.number)
+    }
+}
+
+/// Numbers every captioned image and table in `blocks`, including those in quotes, lists
+/// and table cells. Figures and tables count separately; blocks without a caption are
+/// skipped, so adding, removing or moving one renumbers the rest.
+pub fn number_captions(blocks: &[Block]) -> Vec<CaptionNumber> {
+    fn walk(blocks: &[Block], counts: &mut [u32; 2], out: &mut Vec<CaptionNumber>) {
+        for block in blocks {
+            if let Some((kind, _)) = caption_of(block) {
+                let count = &mut counts[kind as usize];
+                *count += 1;
+                out.push(CaptionNumber {
+                    block_id: captioned_id(block),
+                    kind,
+                    number: *count,
+                });
+            }
+            match block {
+                Block::Table(table) => {
+                    for cell in table.rows.iter().flat_map(|row| &row.cells) {
+                        walk(&cell.blocks, counts, out);
+                    }
+                }
+                Block::BlockQuote(quote) => walk(&quote.blocks, counts, out),
+                Block::List(list) => walk_items(&list.items, counts, out),
+                _ => {}
+            }
+        }
+    }
+
+    fn walk_items(items: &[ListItem], counts: &mut [u32; 2], out: &mut Vec<CaptionNumber>) {
+        for item in items {
+            walk(&item.content, counts, out);
+            walk_items(&item.children, counts, out);
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(blocks, &mut [0; 2], &mut out);
+    out
+}
+
+/// One line of a List of Figures or List of Tables: the numbered caption as drawn and the
+/// figure or table it links to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaptionEntry {
+    pub text: String,
+    pub number: CaptionNumber,
+}
+
+/// Captions of `kind` in document order, numbered the way they are drawn.
+pub fn caption_entries(blocks: &[Block], kind: CaptionKind) -> Vec<CaptionEntry> {
+    let numbers = number_captions(blocks);
+    numbers
+        .iter()
+        .filter(|number| number.kind == kind)
+        .filter_map(|number| {
+            let block = find_captioned(blocks, number.block_id)?;
+            Some(CaptionEntry {
+                text: numbered_caption(&numbers, block)?,
+                number: *number,
+            })
+        })
+        .collect()
+}
+
+/// Index of the top-level block that is or holds the captioned block `block_id`, the one
+/// whose page the caption is on.
+pub fn captioned_block_index(blocks: &[Block], block_id: BlockId) -> Option<usize> {
+    blocks
+        .iter()
+        .position(|block| find_captioned(std::slice::from_ref(block), block_id).is_some())
+}
+
+/// Indices of the document's list of `kind` captions: the first run of consecutive caption
+/// list lines that start with its label, so a List of Figures and a List of Tables can sit
+/// back to back.
+pub fn caption_list_range(blocks: &[Block], kind: CaptionKind) -> Option<Range<usize>> {
+    let is_line = |block: &Block| {
+        let Block::Paragraph(paragraph) = block else {
+            return false;
+        };
+        paragraph.style_id.as_deref() == Some(CAPTION_LIST_STYLE)
+            && paragraph
+                .runs
+                .first()
+                .is_some_and(|run| run.text.starts_with(&format!("{} ", kind.label())))
+    };
+    let start = blocks.iter().position(is_line)?;
+    let len = blocks[start..]
+        .iter()
+        .take_while(|block| is_line(block))
+        .count();
+    Some(start..start + len)
+}
+
+/// Caption list lines for `entries` with ids counting up from `first_id`. Each reads
+/// "Figure 1: text<TAB>page" with the page `page_of` reports, and links to its figure or
+/// table.
+pub fn caption_list_paragraphs(
+    entries: &[CaptionEntry],
+    first_id: u64,
+    page_of: impl Fn(BlockId) -> Option<usize>,
+) -> Vec<Block> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let block_id = entry.number.block_id;
+            let style = RunStyle {
+                internal_link: Some(block_id),
+                ..RunStyle::default()
+            };
+            let page = page_of(block_id).map_or_else(String::new, |page| page.to_string());
+            Block::Paragraph(Paragraph {
+                id: BlockId(first_id + index as u64),
+                runs: vec![
+                    Run {
+                        text: entry.text.clone(),
+                        style: style.clone(),
+                    },
+                    Run {
+                        text: format!("\t{page}"),
+                        style,
+                    },
+                ],
+                alignment: Default::default(),
+                spacing: Default::default(),
+                indent: Indent::default(),
+                style_id: Some(CAPTION_LIST_STYLE.to_string()),
+                direction: Default::default(),
+                frame: None,
+            })
+        })
+        .collect()
+}
+
+/// One undo step writing a List of Figures or List of Tables: over the existing one when
+/// the document has it, else at `insert_at`. With nothing of `kind` captioned any more, the
+/// existing list is cleared; `None` when there is no list to clear either.
+pub fn caption_list_command(
+    blocks: &[Block],
+    kind: CaptionKind,
+    insert_at: usize,
+    first_id: u64,
+    page_of: impl Fn(BlockId) -> Option<usize>,
+) -> Option<EditCommand> {
+    let entries = caption_entries(blocks, kind);
+    let (at_index, count) = match caption_list_range(blocks, kind) {
+        Some(existing) => (existing.start, existing.len()),
+        None if entries.is_empty() => return None,
+        None => (insert_at.min(blocks.len()), 0),
+    };
+    Some(EditCommand::ReplaceBlocks {
+        at_index,
+        count,
+        blocks: caption_list_paragraphs(&entries, first_id, page_of),
+        start_block: None,
+    })
+}
+
+/// The caption of an image or table, with the kind of label it gets.
+pub fn caption_of(block: &Block) -> Option<(CaptionKind, &[Run])> {
+    match block {
+        Block::Image(image) => Some((CaptionKind::Figure, image.caption.as_deref()?)),
+        Block::Table(table) => Some((CaptionKind::Table, table.caption.as_deref()?)),
+        _ => None,
+    }
+}
+
+/// The caption as drawn: "Figure 2: A cat", or only the label when there's no text.
+pub fn caption_line(number: &CaptionNumber, runs: &[Run]) -> String {
+    let text = runs.iter().map(|run| run.text.as_str()).collect::<String>();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        number.label()
+    } else {
+        format!("{}: {text}", number.label())
+    }
+}
+
+/// `caption_line` of `block`, numbered as in `numbers`; `None` when it has no caption.
+pub fn numbered_caption(numbers: &[CaptionNumber], block: &Block) -> Option<String> {
+    let (_, runs) = caption_of(block)?;
+    Some(caption_line(caption_number_of(numbers, block)?, runs))
+}
+
+/// The entry of `numbers` for the captioned `block`.
+pub fn caption_number_of<'a>(
+    numbers: &'a [CaptionNumber],
+    block: &Block,
+) -> Option<&'a CaptionNumber> {
+    caption_of(block)?;
+    numbers
+        .iter()
+        .find(|number| number.block_id == captioned_id(block))
+}
+
+/// `caption_line` of the captioned block `block_id`, wherever it sits.
+pub fn caption_text(blocks: &[Block], block_id: BlockId) -> Option<String> {
+    numbered_caption(&number_captions(blocks), find_captioned(blocks, block_id)?)
+}
+
+fn captioned_id(block: &Block) -> BlockId {
+    match block {
+        Block::Table(table) => table.id,
+        Block::Image(image) => image.id,
+        _ => BlockId::default(),
+    }
+}
+
+fn find_captioned(blocks: &[Block], block_id: BlockId) -> Option<&Block> {
+    blocks.iter().find_map(|block| {
+        if caption_of(block).is_some() && captioned_id(block) == block_id {
+            return Some(block);
+        }
+        match block {
+            Block::Table(table) => table
+                .rows
+                .iter()
+                .flat_map(|row| &row.cells)
+                .find_map(|cell| find_captioned(&cell.blocks, block_id)),
+            Block::BlockQuote(quote) => find_captioned(&quote.blocks, block_id),
+            Block::List(list) => find_captioned_in_items(&list.items, block_id),
+            _ => None,
+        }
+    })
+}
+
+fn find_captioned_in_items(items: &[ListItem], block_id: BlockId) -> Option<&Block> {
+    items.iter().find_map(|item| {
+        find_captioned(&item.content, block_id)
+            .or_else(|| find_captioned_in_items(&item.children, block_id))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        document::model::{
+            BlockQuote, CrossReference, CrossReferenceDisplay, DocumentModel, ImageBlock, Table,
+            TableCell, TableRow,
+        },
+        editor::{
+            EditEngine,
+            commands::EditCommand,
+            cross_reference::{cross_reference_text, ensure_anchor},
+        },
+    };
+
+    fn caption(text: &str) -> Option<Vec<Run>> {
+        Some(vec![Run {
+            text: text.to_string(),
+            ..Run::default()
+        }])
+    }
+
+    fn figure(id: u64, text: Option<&str>) -> Block {
+        Block::Image(ImageBlock {
+            id: BlockId(id),
+            caption: text.and_then(caption),
+            ..ImageBlock::default()
+        })
+    }
+
+    fn table(id: u64, text: &str) -> Block {
+        Block::Table(Table {
+            id: BlockId(id),
+            caption: caption(text),
+            ..Table::default()
+        })
+    }
+
+    fn labels(blocks: &[Block]) -> Vec<(u64, String)> {
+        number_captions(blocks)
+            .iter()
+            .map(|number| (number.block_id.0, number.label()))
+            .collect()
+    }
+
+    #[test]
+    fn figures_and_tables_number_separately_in_document_order() {
+        let blocks = vec![
+            figure(1, Some("Overview")),
+            table(2, "Results"),
+            // Uncaptioned images don't take a number.
+            figure(3, None),
+            figure(4, Some("")),
+            Block::BlockQuote(BlockQuote {
+                id: BlockId(5),
+                blocks: vec![table(6, "Quoted")],
+                callout: None,
+            }),
+            figure(7, Some("  Final\n  chart ")),
+        ];
+        assert_eq!(
+            labels(&blocks),
+            [
+                (1, "Figure 1".to_string()),
+                (2, "Table 1".to_string()),
+                (4, "Figure 2".to_string()),
+                (6, "Table 2".to_string()),
+                (7, "Figure 3".to_string()),
+            ]
+        );
+        assert_eq!(
+            caption_text(&blocks, BlockId(1)).as_deref(),
+            Some("Figure 1: Overview")
+        );
+        assert_eq!(
+            caption_text(&blocks, BlockId(4)).as_deref(),
+            Some("Figure 2")
+        );
+        assert_eq!(
+            caption_text(&blocks, BlockId(6)).as_deref(),
+            Some("Table 2: Quoted")
+        );
+        assert_eq!(
+            caption_text(&blocks, BlockId(7)).as_deref(),
+            Some("Figure 3: Final chart")
+        );
+        assert_eq!(caption_text(&blocks, BlockId(3)), None);
+    }
+
+    #[test]
+    fn deleting_a_figure_renumbers_the_rest_and_their_references() {
+        let mut doc = DocumentModel::default();
+        doc.content = vec![
+            figure(1, Some("First")),
+            table(2, "Only table"),
+            figure(3, Some("Second")),
+        ];
+        let name = ensure_anchor(&mut doc, BlockId(3)).expect("anchor");
+        assert_eq!(name, "Figure_2_Second");
+        let reference = CrossReference {
+            anchor: name,
+            display: CrossReferenceDisplay::Text,
+        };
+        assert_eq!(
+            cross_reference_text(&doc, &reference).as_deref(),
+            Some("Figure 2")
+        );
+
+        let mut engine = EditEngine::default();
+        let delete = EditCommand::DeleteBlock {
+            block_id: BlockId(1),
+        };
+        assert!(engine.apply_command(&mut doc, delete));
+        assert_eq!(
+            labels(&doc.content),
+            [(2, "Table 1".to_string()), (3, "Figure 1".to_string())]
+        );
+        assert_eq!(
+            cross_reference_text(&doc, &reference).as_deref(),
+            Some("Figure 1")
+        );
+        let number = CrossReference {
+            display: CrossReferenceDisplay::Number,
+            ..reference
+        };
+        assert_eq!(cross_reference_text(&doc, &number).as_deref(), Some("1"));
+
+        assert!(engine.undo(&mut doc));
+        assert_eq!(labels(&doc.content)[2], (3, "Figure 2".to_string()));
+    }
+
+    fn list_line(block: &Block) -> (String, Option<BlockId>) {
+        let Block::Paragraph(p) = block else {
+            panic!("caption list lines are paragraphs");
+        };
+        assert_eq!(p.style_id.as_deref(), Some(CAPTION_LIST_STYLE));
+        (
+            p.runs.iter().map(|r| r.text.as_str()).collect(),
+            p.runs[0].style.internal_link,
+        )
+    }
+
+    #[test]
+    fn caption_entries_follow_document_order_per_kind() {
+        let blocks = vec![
+            table(1, "Inputs"),
+            figure(2, Some("Setup")),
+            figure(3, None),
+            // A figure inside a table cell comes after the table holding it.
+            Block::Table(Table {
+                id: BlockId(4),
+                caption: caption("Grid"),
+                rows: vec![TableRow {
+                    cells: vec![TableCell {
+                        blocks: vec![figure(5, Some("Cell chart"))],
+                        ..TableCell::default()
+                    }],
+                }],
+                ..Table::default()
+            }),
+            figure(6, Some("")),
+        ];
+        let summary = |kind| {
+            caption_entries(&blocks, kind)
+                .into_iter()
+                .map(|entry| (entry.number.block_id.0, entry.number.number, entry.text))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            summary(CaptionKind::Figure),
+            [
+                (2, 1, "Figure 1: Setup".to_string()),
+                (5, 2, "Figure 2: Cell chart".to_string()),
+                (6, 3, "Figure 3".to_string()),
+            ]
+        );
+        assert_eq!(
+            summary(CaptionKind::Table),
+            [
+                (1, 1, "Table 1: Inputs".to_string()),
+                (4, 2, "Table 2: Grid".to_string()),
+            ]
+        );
+        assert_eq!(captioned_block_index(&blocks, BlockId(5)), Some(3));
+        assert_eq!(captioned_block_index(&blocks, BlockId(3)), None);
+    }
+
+    #[test]
+    fn caption_lists_insert_then_regenerate_in_place() {
+        let mut doc = DocumentModel::default();
+        doc.content = vec![figure(1, Some("First")), table(2, "Only table")];
+        let mut engine = EditEngine::default();
+        let page_of = |id: BlockId| Some(id.0 as usize);
+
+        let figures = caption_list_command(&doc.content, CaptionKind::Figure, 0, 10, page_of);
+        assert!(engine.apply_command(&mut doc, figures.expect("figures")));
+        let tables = caption_list_command(&doc.content, CaptionKind::Table, 1, 20, page_of);
+        assert!(engine.apply_command(&mut doc, tables.expect("tables")));
+        assert_eq!(
+            caption_list_range(&doc.content, CaptionKind::Figure),
+            Some(0..1)
+        );
+        assert_eq!(
+            caption_list_range(&doc.content, CaptionKind::Table),
+            Some(1..2)
+        );
+        assert_eq!(
+            list_line(&doc.content[0]),
+            ("Figure 1: First\t1".to_string(), Some(BlockId(1)))
+        );
+        assert_eq!(
+            list_line(&doc.content[1]),
+            ("Table 1: Only table\t2".to_string(), Some(BlockId(2)))
+        );
+
+        // A new figure ahead of the first renumbers it, and the list is rewritten, not repeated.
+        doc.content.insert(2, figure(3, Some("Zeroth")));
+        let figures = caption_list_command(&doc.content, CaptionKind::Figure, 4, 30, |_| Some(7));
+        assert!(engine.apply_command(&mut doc, figures.expect("figures")));
+        assert_eq!(
+            caption_list_range(&doc.content, CaptionKind::Figure),
+            Some(0..2)
+        );
+        assert_eq!(list_line(&doc.content[0]).0, "Figure 1: Zeroth\t7");
+        assert_eq!(list_line(&doc.content[1]).0, "Figure 2: First\t7");
+        assert_eq!(
+            caption_list_range(&doc.content, CaptionKind::Table),
+            Some(2..3)
+        );
+        assert_eq!(doc.content.len(), 6);
+
+        // Once the last table loses its caption, the List of Tables goes with it.
+        if let Block::Table(table) = &mut doc.content[5] {
+            table.caption = None;
+        }
+        let tables = caption_list_command(&doc.content, CaptionKind::Table, 0, 40, page_of);
+        assert!(engine.apply_command(&mut doc, tables.expect("clearing")));
+        assert_eq!(caption_list_range(&doc.content, CaptionKind::Table), None);
+        assert_eq!(doc.content.len(), 5);
+
+        assert!(
+            caption_list_command(&[figure(1, None)], CaptionKind::Figure, 0, 2, page_of).is_none()
+        );
+    }
+}
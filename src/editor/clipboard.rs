@@ -1,7 +1,7 @@
-use std::{mem::size_of, ptr::copy_nonoverlapping, sync::OnceLock};
+use std::{io::Cursor, mem::size_of, path::PathBuf, ptr::copy_nonoverlapping, sync::OnceLock};
 
 use encoding_rs::WINDOWS_1252;
-use image::GenericImageView;
+use image::{DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage};
 use serde::{Deserialize, Serialize};
 use windows::{
     Win32::{
@@ -20,18 +20,26 @@ use windows::{
 use crate::{
     document::{
         DocumentFormat,
-        model::{Run, RunStyle},
+        model::{Run, RunStyle, Table},
     },
     editor::{
         commands::EditCommand,
         cursor::{CursorPosition, SelectionRange},
+        image_ops::load_supported_image,
+        table::{TextDelimiter, table_to_lines},
     },
 };
 
 const CF_UNICODETEXT_U32: u32 = 13;
 const CF_DIB_U32: u32 = 8;
+const CF_HDROP_U32: u32 = 15;
+const CF_DIBV5_U32: u32 = 17;
+
+const BI_RGB: u32 = 0;
+const BI_BITFIELDS: u32 = 3;
 
 static INTERNAL_CLIPBOARD_FORMAT: OnceLock<u32> = OnceLock::new();
+static TABLE_CLIPBOARD_FORMAT: OnceLock<u32> = OnceLock::new();
 static RTF_CLIPBOARD_FORMAT: OnceLock<u32> = OnceLock::new();
 static HTML_CLIPBOARD_FORMAT: OnceLock<u32> = OnceLock::new();
 static PNG_CLIPBOARD_FORMAT: OnceLock<u32> = OnceLock::new();
@@ -146,6 +154,51 @@ pub fn copy_runs_to_clipboard(runs: &[Run], source_format: DocumentFormat) -> Re
     Ok(())
 }
 
+/// Puts `image` on the clipboard as a `CF_DIBV5`, which keeps its alpha, and as PNG for apps
+/// that read that instead.
+pub fn copy_image_to_clipboard(image: &RgbaImage) -> Result<()> {
+    let dib = rgba_to_dibv5(image);
+    let mut png = Vec::new();
+    let png_ok = image
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .is_ok();
+
+    let _guard = ClipboardGuard::open()?;
+    unsafe {
+        EmptyClipboard()?;
+    }
+    set_clipboard_raw(CF_DIBV5_U32, &dib, false)?;
+    if png_ok {
+        set_clipboard_raw(png_clipboard_format(), &png, false)?;
+    }
+    Ok(())
+}
+
+/// Puts the whole of `table` on the clipboard: its structure and styles in Doco's own
+/// format for pasting back, and its cells as tab-separated text for other apps.
+pub fn copy_table_to_clipboard(table: &Table) -> Result<()> {
+    let text = table_to_lines(table, TextDelimiter::Tab).join("\r\n");
+    let payload = encode_table_payload(table).ok();
+
+    let _guard = ClipboardGuard::open()?;
+    unsafe {
+        EmptyClipboard()?;
+    }
+    set_clipboard_unicode_text(&text)?;
+    if let Some(payload) = payload {
+        set_clipboard_bytes(table_clipboard_format(), &payload)?;
+    }
+    Ok(())
+}
+
+/// The table `copy_table_to_clipboard` put there, ids as copied; `None` when the
+/// clipboard holds anything else.
+pub fn read_clipboard_table() -> Result<Option<Table>> {
+    let _guard = ClipboardGuard::open()?;
+    Ok(get_clipboard_bytes(table_clipboard_format())?
+        .and_then(|bytes| decode_table_payload(&bytes)))
+}
+
 pub fn read_clipboard_for_paste(mode: PasteMode) -> Result<Option<ClipboardPastePayload>> {
     let _guard = ClipboardGuard::open()?;
 
@@ -231,19 +284,38 @@ pub fn read_clipboard_for_paste(mode: PasteMode) -> Result<Option<ClipboardPaste
     Ok(None)
 }
 
+/// Reads the best image on the clipboard: a `CF_DIBV5` bitmap, which can carry alpha, then
+/// PNG, then a plain `CF_DIB`, then the first image in a copied file list (`CF_HDROP`).
 pub fn read_clipboard_image() -> Result<Option<ClipboardImageData>> {
     let _guard = ClipboardGuard::open()?;
 
-    if let Some(bytes) = get_clipboard_bytes(png_clipboard_format())? {
-        if let Some(decoded) = decode_clipboard_image(bytes, "image/png") {
-            return Ok(Some(decoded));
-        }
+    if let Some(dib) = get_clipboard_bytes(CF_DIBV5_U32)?
+        && let Some(decoded) = decode_clipboard_dib(dib.as_slice())
+    {
+        return Ok(Some(decoded));
+    }
+
+    if let Some(bytes) = get_clipboard_bytes(png_clipboard_format())?
+        && let Some(decoded) = decode_clipboard_image(bytes, "image/png")
+    {
+        return Ok(Some(decoded));
     }
 
-    if let Some(dib_bytes) = get_clipboard_bytes(CF_DIB_U32)? {
-        if let Some(bmp_bytes) = dib_to_bmp_bytes(dib_bytes.as_slice()) {
-            if let Some(decoded) = decode_clipboard_image(bmp_bytes, "image/bmp") {
-                return Ok(Some(decoded));
+    if let Some(dib) = get_clipboard_bytes(CF_DIB_U32)?
+        && let Some(decoded) = decode_clipboard_dib(dib.as_slice())
+    {
+        return Ok(Some(decoded));
+    }
+
+    if let Some(drop_files) = get_clipboard_bytes(CF_HDROP_U32)? {
+        for path in parse_drop_files(drop_files.as_slice()) {
+            if let Ok(asset) = load_supported_image(&path) {
+                return Ok(Some(ClipboardImageData {
+                    bytes: asset.bytes,
+                    mime: asset.mime,
+                    width: asset.width,
+                    height: asset.height,
+                }));
             }
         }
     }
@@ -306,6 +378,24 @@ pub fn drag_drop_commands(
     commands
 }
 
+/// Where dropped text ended up, as a character range of its paragraph `text` read after
+/// the drop `commands` ran; `None` when they insert nothing.
+pub fn dropped_text_range(
+    commands: &[EditCommand],
+    text: &str,
+    dropped: &str,
+) -> Option<std::ops::Range<usize>> {
+    let landing = commands.iter().find_map(|command| match command {
+        EditCommand::InsertText { offset, .. } => Some(*offset),
+        _ => None,
+    })?;
+    let start = text
+        .char_indices()
+        .take_while(|(index, _)| *index < landing)
+        .count();
+    Some(start..start + dropped.chars().count())
+}
+
 pub fn copy_to_clipboard(text: &str) {
     let _ = set_plain_text(text);
 }
@@ -452,12 +542,16 @@ fn internal_clipboard_format() -> u32 {
         .get_or_init(|| unsafe { RegisterClipboardFormatW(w!("Doco.InternalRuns")) })
 }
 
+fn table_clipboard_format() -> u32 {
+    *TABLE_CLIPBOARD_FORMAT.get_or_init(|| unsafe { RegisterClipboardFormatW(w!("Doco.Table")) })
+}
+
 fn rtf_clipboard_format() -> u32 {
     *RTF_CLIPBOARD_FORMAT
         .get_or_init(|| unsafe { RegisterClipboardFormatW(w!("Rich Text Format")) })
 }
 
-fn html_clipboard_format() -> u32 {
+pub fn html_clipboard_format() -> u32 {
     *HTML_CLIPBOARD_FORMAT.get_or_init(|| unsafe { RegisterClipboardFormatW(w!("HTML Format")) })
 }
 
@@ -476,27 +570,56 @@ fn decode_clipboard_image(bytes: Vec<u8>, mime: &str) -> Option<ClipboardImageDa
     })
 }
 
-fn dib_to_bmp_bytes(dib: &[u8]) -> Option<Vec<u8>> {
-    if dib.len() < 40 {
+/// The parts of a `BITMAPINFOHEADER`, or a V4/V5 header, needed to find and read the pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DibHeader {
+    width: u32,
+    height: u32,
+    /// Rows run top to bottom (a negative stored height) instead of bottom to top.
+    top_down: bool,
+    bit_count: u16,
+    compression: u32,
+    /// Red, green, blue and alpha masks for `BI_BITFIELDS`; alpha is only given by a V3+
+    /// header.
+    masks: [u32; 4],
+    /// Where the pixels start, past the header, any trailing masks and the palette.
+    pixel_offset: usize,
+}
+
+fn parse_dib_header(dib: &[u8]) -> Option<DibHeader> {
+    let u32_at = |at: usize| Some(u32::from_le_bytes(dib.get(at..at + 4)?.try_into().ok()?));
+    let header_size = u32_at(0)? as usize;
+    if header_size < 40 || dib.len() < header_size {
         return None;
     }
 
-    let header_size = u32::from_le_bytes(dib[0..4].try_into().ok()?) as usize;
-    if header_size < 40 || dib.len() < header_size {
+    let width = u32_at(4)? as i32;
+    let height = u32_at(8)? as i32;
+    if width <= 0 || height == 0 {
         return None;
     }
+    let bit_count = u16::from_le_bytes(dib.get(14..16)?.try_into().ok()?);
+    let compression = u32_at(16)?;
+    let colors_used = u32_at(32)?;
 
-    let bpp = u16::from_le_bytes(dib[14..16].try_into().ok()?);
-    let compression = u32::from_le_bytes(dib[16..20].try_into().ok()?);
-    let colors_used = u32::from_le_bytes(dib[32..36].try_into().ok()?);
-    let masks_len = if compression == 3 && header_size == 40 {
+    // A plain 40-byte header is followed by its three masks; larger headers hold them.
+    let masks_len = if compression == BI_BITFIELDS && header_size == 40 {
         12usize
     } else {
         0usize
     };
-    let palette_entries = if bpp <= 8 {
+    let mut masks = [0u32; 4];
+    if compression == BI_BITFIELDS {
+        for (index, mask) in masks.iter_mut().enumerate() {
+            let at = 40 + index * 4;
+            if index < 3 || at + 4 <= header_size {
+                *mask = u32_at(at)?;
+            }
+        }
+    }
+    let palette_entries = if bit_count <= 8 {
         if colors_used == 0 {
-            1u32.checked_shl(bpp as u32).unwrap_or(0)
+            1u32.checked_shl(bit_count as u32).unwrap_or(0)
         } else {
             colors_used
         }
@@ -504,13 +627,165 @@ fn dib_to_bmp_bytes(dib: &[u8]) -> Option<Vec<u8>> {
         0
     };
     let palette_len = palette_entries as usize * 4;
-    let pixel_offset_in_dib = header_size
+    let pixel_offset = header_size
         .checked_add(masks_len)?
         .checked_add(palette_len)?;
-    if pixel_offset_in_dib > dib.len() {
+    if pixel_offset > dib.len() {
         return None;
     }
 
+    Some(DibHeader {
+        width: width as u32,
+        height: height.unsigned_abs(),
+        top_down: height < 0,
+        bit_count,
+        compression,
+        masks,
+        pixel_offset,
+    })
+}
+
+/// A clipboard DIB as image data. 24- and 32-bit bitmaps are read directly so a DIBV5's
+/// alpha survives and are handed on as PNG; anything else goes through the BMP decoder.
+fn decode_clipboard_dib(dib: &[u8]) -> Option<ClipboardImageData> {
+    let Some(rgba) = dib_to_rgba(dib) else {
+        return decode_clipboard_image(dib_to_bmp_bytes(dib)?, "image/bmp");
+    };
+    let (width, height) = rgba.dimensions();
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(rgba)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .ok()?;
+    Some(ClipboardImageData {
+        bytes: png,
+        mime: "image/png".to_string(),
+        width,
+        height,
+    })
+}
+
+fn dib_to_rgba(dib: &[u8]) -> Option<RgbaImage> {
+    let header = parse_dib_header(dib)?;
+    let bytes_per_pixel = match (header.bit_count, header.compression) {
+        (32, BI_RGB | BI_BITFIELDS) => 4,
+        (24, BI_RGB) => 3,
+        _ => return None,
+    };
+    let masks = if header.compression == BI_BITFIELDS {
+        header.masks
+    } else {
+        [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000]
+    };
+    let (width, height) = (header.width as usize, header.height as usize);
+    let stride = width.checked_mul(bytes_per_pixel)?.next_multiple_of(4);
+    let mut pixels = dib.get(header.pixel_offset..)?.to_vec();
+    // `get_clipboard_bytes` trims trailing zero bytes, which were black, clear pixels.
+    pixels.resize(stride.checked_mul(height)?, 0);
+
+    let mut image = RgbaImage::new(header.width, header.height);
+    let mut has_alpha = false;
+    for y in 0..height {
+        let row = if header.top_down { y } else { height - 1 - y };
+        let row_pixels = &pixels[row * stride..][..width * bytes_per_pixel];
+        for (x, pixel) in row_pixels.chunks_exact(bytes_per_pixel).enumerate() {
+            let mut value = [0u8; 4];
+            value[..bytes_per_pixel].copy_from_slice(pixel);
+            let value = u32::from_le_bytes(value);
+            let [r, g, b, a] = masks.map(|mask| mask_channel(value, mask));
+            has_alpha |= a != 0;
+            image.put_pixel(x as u32, y as u32, Rgba([r, g, b, a]));
+        }
+    }
+    // Most 32-bit DIBs leave the fourth byte unused; an all-zero alpha means opaque.
+    if !has_alpha {
+        for pixel in image.pixels_mut() {
+            pixel[3] = 255;
+        }
+    }
+    Some(image)
+}
+
+/// A bottom-up 32-bit `BITMAPV5HEADER` bitmap with straight BGRA pixels in sRGB.
+fn rgba_to_dibv5(image: &RgbaImage) -> Vec<u8> {
+    const HEADER_SIZE: u32 = 124;
+    const LCS_SRGB: u32 = 0x7352_4742;
+    const LCS_GM_IMAGES: u32 = 4;
+
+    let (width, height) = image.dimensions();
+    let image_size = width * height * 4;
+    let mut dib = Vec::with_capacity((HEADER_SIZE + image_size) as usize);
+    dib.extend_from_slice(&HEADER_SIZE.to_le_bytes());
+    dib.extend_from_slice(&(width as i32).to_le_bytes());
+    dib.extend_from_slice(&(height as i32).to_le_bytes());
+    dib.extend_from_slice(&1u16.to_le_bytes());
+    dib.extend_from_slice(&32u16.to_le_bytes());
+    dib.extend_from_slice(&BI_BITFIELDS.to_le_bytes());
+    dib.extend_from_slice(&image_size.to_le_bytes());
+    // Resolution, colors used and colors important.
+    dib.extend_from_slice(&[0u8; 16]);
+    for mask in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000] {
+        dib.extend_from_slice(&mask.to_le_bytes());
+    }
+    dib.extend_from_slice(&LCS_SRGB.to_le_bytes());
+    // Endpoints and gamma, unused for sRGB.
+    dib.extend_from_slice(&[0u8; 48]);
+    dib.extend_from_slice(&LCS_GM_IMAGES.to_le_bytes());
+    // Profile data, profile size and reserved.
+    dib.extend_from_slice(&[0u8; 12]);
+
+    for row in image.rows().rev() {
+        for pixel in row {
+            let [r, g, b, a] = pixel.0;
+            dib.extend_from_slice(&[b, g, r, a]);
+        }
+    }
+    dib
+}
+
+fn mask_channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shift = mask.trailing_zeros();
+    let max = u64::from(mask >> shift);
+    let channel = u64::from((value & mask) >> shift);
+    ((channel * 255 + max / 2) / max) as u8
+}
+
+/// Paths in a `DROPFILES` block: a 20-byte header whose first field is the offset of a
+/// NUL-separated list, wide when the last field is set.
+fn parse_drop_files(bytes: &[u8]) -> Vec<PathBuf> {
+    let field = |at: usize| Some(u32::from_le_bytes(bytes.get(at..at + 4)?.try_into().ok()?));
+    let (Some(offset), Some(wide)) = (field(0), field(16)) else {
+        return Vec::new();
+    };
+    let Some(list) = bytes.get(offset as usize..) else {
+        return Vec::new();
+    };
+    let names = if wide != 0 {
+        let units = list
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect::<Vec<_>>();
+        units
+            .split(|unit| *unit == 0)
+            .map(String::from_utf16_lossy)
+            .collect::<Vec<_>>()
+    } else {
+        list.split(|byte| *byte == 0)
+            .map(|name| WINDOWS_1252.decode(name).0.into_owned())
+            .collect()
+    };
+    names
+        .into_iter()
+        .filter(|name| !name.is_empty())
+        .map(PathBuf::from)
+        .collect()
+}
+
+fn dib_to_bmp_bytes(dib: &[u8]) -> Option<Vec<u8>> {
+    let pixel_offset_in_dib = parse_dib_header(dib)?.pixel_offset;
+
     let file_header_len = 14usize;
     let file_size = file_header_len.checked_add(dib.len())?;
     let pixel_offset_in_file = file_header_len.checked_add(pixel_offset_in_dib)?;
@@ -576,6 +851,27 @@ fn decode_internal_payload(bytes: &[u8]) -> Option<Vec<Run>> {
     Some(payload.runs)
 }
 
+#[derive(Serialize, Deserialize)]
+struct TableClipboardPayload {
+    version: u8,
+    table: Table,
+}
+
+fn encode_table_payload(table: &Table) -> serde_json::Result<Vec<u8>> {
+    serde_json::to_vec(&TableClipboardPayload {
+        version: 1,
+        table: table.clone(),
+    })
+}
+
+fn decode_table_payload(bytes: &[u8]) -> Option<Table> {
+    let payload = serde_json::from_slice::<TableClipboardPayload>(bytes).ok()?;
+    if payload.version != 1 {
+        return None;
+    }
+    Some(payload.table)
+}
+
 fn runs_to_minimal_rtf(runs: &[Run]) -> String {
     if runs.is_empty() {
         return String::new();
@@ -876,6 +1172,11 @@ fn build_cf_html(fragment: &str) -> String {
     format!("{header}{html_body}")
 }
 
+/// Plain text of an "HTML Format" payload, e.g. a selection dragged in from a browser.
+pub fn html_to_plain_text(raw_html: &str) -> String {
+    runs_to_plain_text(&parse_html_to_runs(raw_html))
+}
+
 fn parse_html_to_runs(raw_html: &str) -> Vec<Run> {
     let html = extract_html_fragment(raw_html);
     let mut i = 0usize;
@@ -1292,6 +1593,28 @@ mod tests {
         }
     }
 
+    #[test]
+    fn dropped_text_is_found_where_it_landed() {
+        let at = |offset| CursorPosition {
+            block_id: crate::document::model::BlockId(1),
+            offset,
+        };
+        // "cd" of "aécdxyz", by byte offsets, moved to after "xy".
+        let selection = SelectionRange {
+            start: at(3),
+            end: at(5),
+        };
+        let moved = drag_drop_commands(selection, at(7), "cd", false);
+        let text = "aéxycdz";
+        let range = dropped_text_range(&moved, text, "cd").expect("moved text");
+        assert_eq!(range, 4..6);
+        assert_eq!(text.chars().skip(4).take(2).collect::<String>(), "cd");
+
+        let copied = drag_drop_commands(selection, at(0), "cd", true);
+        assert_eq!(dropped_text_range(&copied, "cdaécdxyz", "cd"), Some(0..2));
+        assert_eq!(dropped_text_range(&[], text, "cd"), None);
+    }
+
     #[test]
     fn plain_source_does_not_force_rich_formats() {
         let runs = vec![plain_run("100000 chars test")];
@@ -1310,4 +1633,172 @@ mod tests {
         let decoded = image::load_from_memory(rebuilt.as_slice()).expect("decode rebuilt bmp");
         assert_eq!(decoded.dimensions(), (2, 2));
     }
+
+    /// A DIB of `size`-byte header, then `pixels`; V4/V5 sizes get BGRA bitfield masks.
+    fn dib(size: u32, width: i32, height: i32, bit_count: u16, pixels: &[u8]) -> Vec<u8> {
+        let compression = if size >= 108 { BI_BITFIELDS } else { BI_RGB };
+        let mut dib = vec![0u8; size as usize];
+        dib[0..4].copy_from_slice(&size.to_le_bytes());
+        dib[4..8].copy_from_slice(&width.to_le_bytes());
+        dib[8..12].copy_from_slice(&height.to_le_bytes());
+        dib[12..14].copy_from_slice(&1u16.to_le_bytes());
+        dib[14..16].copy_from_slice(&bit_count.to_le_bytes());
+        dib[16..20].copy_from_slice(&compression.to_le_bytes());
+        if compression == BI_BITFIELDS {
+            for (index, mask) in [0x00FF_0000u32, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000]
+                .into_iter()
+                .enumerate()
+            {
+                dib[40 + index * 4..44 + index * 4].copy_from_slice(&mask.to_le_bytes());
+            }
+        }
+        dib.extend_from_slice(pixels);
+        dib
+    }
+
+    #[test]
+    fn dib_header_gives_size_and_row_order() {
+        let bottom_up = parse_dib_header(&dib(40, 3, 2, 24, &[0; 24])).expect("bottom-up");
+        assert_eq!((bottom_up.width, bottom_up.height), (3, 2));
+        assert!(!bottom_up.top_down);
+        assert_eq!(bottom_up.pixel_offset, 40);
+
+        let top_down = parse_dib_header(&dib(124, 5, -4, 32, &[])).expect("top-down");
+        assert_eq!((top_down.width, top_down.height), (5, 4));
+        assert!(top_down.top_down);
+        assert_eq!(top_down.masks[3], 0xFF00_0000);
+        assert_eq!(top_down.pixel_offset, 124);
+
+        // An 8-bit DIB's palette sits between the header and the pixels.
+        assert_eq!(
+            parse_dib_header(&dib(40, 1, 1, 8, &[0; 1028])).map(|h| h.pixel_offset),
+            Some(40 + 256 * 4)
+        );
+        assert_eq!(parse_dib_header(&dib(40, 0, 2, 24, &[])), None);
+        assert_eq!(parse_dib_header(&[0; 12]), None);
+    }
+
+    #[test]
+    fn dibv5_pixels_keep_their_alpha_and_row_order() {
+        // Two BGRA rows of one pixel: a half-transparent red, then an opaque blue.
+        let rows = [0x00, 0x00, 0xFF, 0x80, 0xFF, 0x00, 0x00, 0xFF];
+        let bottom_up = dib_to_rgba(&dib(124, 1, 2, 32, &rows)).expect("bottom-up");
+        assert_eq!(bottom_up.get_pixel(0, 0).0, [0, 0, 255, 255]);
+        assert_eq!(bottom_up.get_pixel(0, 1).0, [255, 0, 0, 128]);
+
+        let top_down = dib_to_rgba(&dib(124, 1, -2, 32, &rows)).expect("top-down");
+        assert_eq!(top_down.get_pixel(0, 0).0, [255, 0, 0, 128]);
+
+        // A plain DIB whose fourth byte is unused comes out opaque.
+        let plain = dib_to_rgba(&dib(40, 1, 1, 32, &[0x10, 0x20, 0x30, 0x00])).expect("plain");
+        assert_eq!(plain.get_pixel(0, 0).0, [0x30, 0x20, 0x10, 255]);
+
+        let decoded = decode_clipboard_dib(&dib(124, 1, 2, 32, &rows)).expect("decode");
+        assert_eq!(decoded.mime, "image/png");
+        assert_eq!((decoded.width, decoded.height), (1, 2));
+    }
+
+    #[test]
+    fn copied_file_lists_are_read() {
+        let mut drop_files = vec![0u8; 20];
+        drop_files[0..4].copy_from_slice(&20u32.to_le_bytes());
+        drop_files[16..20].copy_from_slice(&1u32.to_le_bytes());
+        for name in [r"C:\shots\one.png", r"C:\shots\two.jpg"] {
+            for unit in name.encode_utf16().chain([0]) {
+                drop_files.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+        assert_eq!(
+            parse_drop_files(&drop_files),
+            vec![
+                PathBuf::from(r"C:\shots\one.png"),
+                PathBuf::from(r"C:\shots\two.jpg")
+            ]
+        );
+        assert!(parse_drop_files(&[1, 2, 3]).is_empty());
+    }
+
+    #[test]
+    fn copied_images_become_a_bottom_up_dibv5() {
+        let mut image = RgbaImage::new(3, 2);
+        image.put_pixel(0, 0, Rgba([255, 0, 0, 128]));
+        image.put_pixel(2, 1, Rgba([10, 20, 30, 0]));
+
+        let dib = rgba_to_dibv5(&image);
+        assert_eq!(dib.len(), 124 + 3 * 2 * 4);
+        let u32_at = |at: usize| u32::from_le_bytes(dib[at..at + 4].try_into().unwrap());
+        assert_eq!(u32_at(0), 124);
+        assert_eq!(u32_at(4), 3);
+        assert_eq!(u32_at(8), 2);
+        assert_eq!(u16::from_le_bytes([dib[14], dib[15]]), 32);
+        assert_eq!(u32_at(16), BI_BITFIELDS);
+        assert_eq!(u32_at(20), 24);
+        assert_eq!(
+            [u32_at(40), u32_at(44), u32_at(48), u32_at(52)],
+            [0x00FF_0000, 0x0000_FF00, 0x0000_00FF, 0xFF00_0000]
+        );
+        // The top-left pixel is written last, as BGRA.
+        assert_eq!(&dib[124 + 12..124 + 16], &[0, 0, 255, 128]);
+        assert_eq!(&dib[124 + 8..124 + 12], &[30, 20, 10, 0]);
+
+        // Reading it back gives the same pixels, alpha included.
+        assert_eq!(dib_to_rgba(&dib), Some(image));
+    }
+
+    #[test]
+    fn copied_tables_paste_back_whole_with_fresh_ids() {
+        use crate::{
+            document::model::{Block, BlockId, DocumentModel, TableStylePreset},
+            editor::table::{lines_to_table, table_for_paste},
+            ui::Color,
+        };
+
+        let lines = ["Name\tQty", "Apples\t3"].map(String::from);
+        let mut table = lines_to_table(&lines, TextDelimiter::Tab, BlockId(4)).expect("table");
+        table.column_widths = vec![180.0, 60.0];
+        table.row_heights = vec![32.0, 48.0];
+        table.style = TableStylePreset::HeaderAccent;
+        table.cell_padding = 9.0;
+        table.header_row = true;
+        table.header_repeat = true;
+        table.rows[1].cells[1].background = Some(Color::rgb(0.9, 0.95, 1.0));
+        let nested = lines_to_table(&["x".to_string()], TextDelimiter::Tab, BlockId(20));
+        table.rows[0].cells[0]
+            .blocks
+            .push(Block::Table(nested.expect("nested")));
+        let mut doc = DocumentModel::default();
+        doc.content = vec![Block::Table(table.clone())];
+
+        let bytes = encode_table_payload(&table).expect("encode");
+        let copied = decode_table_payload(&bytes).expect("decode");
+        let pasted = table_for_paste(&doc, copied);
+
+        // Everything but the ids survives the trip.
+        fn without_ids(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    map.remove("id");
+                    map.values_mut().for_each(without_ids);
+                }
+                serde_json::Value::Array(items) => items.iter_mut().for_each(without_ids),
+                _ => {}
+            }
+        }
+        let structure = |table: &Table| {
+            let mut value = serde_json::to_value(table).expect("json");
+            without_ids(&mut value);
+            value
+        };
+        assert_eq!(structure(&pasted), structure(&table));
+        assert_eq!(pasted.column_widths, [180.0, 60.0]);
+        assert!(matches!(pasted.style, TableStylePreset::HeaderAccent));
+
+        // The table and its nested blocks all get ids the document doesn't use yet.
+        let first_free = doc.next_block_id().0;
+        assert_eq!(pasted.id, BlockId(first_free));
+        doc.content.push(Block::Table(pasted));
+        assert_eq!(doc.next_block_id().0, first_free + 7);
+
+        assert!(decode_table_payload(b"{\"version\":2}").is_none());
+    }
 }
@@ -5,12 +5,20 @@ use crate::{
         ListType,
         Paragraph,
         ParagraphAlignment,
+        ParagraphFrame,
         Run,
         RunStyle,
+        Table,
+        TableCell,
+        TableRow,
+        TextDirection,
     },
     ui::Color,
 };
 
+pub const MIN_FONT_SIZE: f32 = 1.0;
+pub const MAX_FONT_SIZE: f32 = 400.0;
+
 #[derive(Debug, Clone)]
 pub enum EditCommand {
     InsertText {
@@ -51,6 +59,16 @@ pub enum EditCommand {
         block_id: BlockId,
         to_index: usize,
     },
+    /// Swaps `count` blocks starting at `at_index` for `blocks`, e.g. a table for its rows as text.
+    ReplaceBlocks {
+        at_index: usize,
+        count: usize,
+        blocks: Vec<Block>,
+        /// Block the span starts at, wherever it sits by then; `at_index` only counts when
+        /// it is unset or gone. Inverses set it so blocks added or removed above by edits
+        /// outside the undo history don't shift the span.
+        start_block: Option<BlockId>,
+    },
     ReplaceRuns {
         block_id: BlockId,
         runs: Vec<Run>,
@@ -74,6 +92,51 @@ pub enum EditCommand {
         block_id: BlockId,
         op: ParagraphFormatOp,
     },
+    /// A structural edit of one table, top level or nested in a cell.
+    Table {
+        table_id: BlockId,
+        op: TableOp,
+    },
+    /// Commands applied in order and undone together, e.g. the moves that carry a section.
+    Batch(Vec<EditCommand>),
+}
+
+/// Table edits with exact inverses. Changes without a cheap inverse (merging, splitting,
+/// restyling) swap in a whole new table and put the old one back on undo.
+#[derive(Debug, Clone)]
+pub enum TableOp {
+    InsertRow {
+        at: usize,
+    },
+    DeleteRow {
+        row: usize,
+    },
+    RestoreRow {
+        at: usize,
+        row: TableRow,
+        height: f32,
+    },
+    InsertColumn {
+        at: usize,
+    },
+    DeleteColumn {
+        col: usize,
+    },
+    /// Cells per row; `None` for rows that were too short to have one.
+    RestoreColumn {
+        at: usize,
+        cells: Vec<Option<TableCell>>,
+        width: f32,
+    },
+    ResizeColumn {
+        col: usize,
+        width: f32,
+    },
+    ResizeRow {
+        row: usize,
+        height: f32,
+    },
+    Replace(Box<Table>),
 }
 
 #[derive(Debug, Clone, Default)]
@@ -87,12 +150,51 @@ pub struct RunStylePatch {
     pub font_family: Option<String>,
     pub font_size: Option<f32>,
     pub color: Option<Color>,
-    pub background: Option<Color>,
+    /// `Some(None)` clears the highlight; `None` leaves it untouched.
+    pub background: Option<Option<Color>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HighlightChoice {
+    Yellow,
+    Green,
+    Pink,
+    None,
+}
+
+impl HighlightChoice {
+    pub const ALL: [Self; 4] = [Self::Yellow, Self::Green, Self::Pink, Self::None];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Yellow => "Yellow",
+            Self::Green => "Green",
+            Self::Pink => "Pink",
+            Self::None => "None",
+        }
+    }
+
+    pub fn color(self) -> Option<Color> {
+        match self {
+            Self::Yellow => Some(Color::rgb(1.0, 0.95, 0.36)),
+            Self::Green => Some(Color::rgb(0.63, 0.91, 0.67)),
+            Self::Pink => Some(Color::rgb(0.96, 0.69, 0.93)),
+            Self::None => None,
+        }
+    }
+
+    pub fn from_label(label: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|choice| choice.label().eq_ignore_ascii_case(label.trim()))
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum ParagraphFormatOp {
     Alignment(ParagraphAlignment),
+    Direction(TextDirection),
+    Frame(Option<ParagraphFrame>),
     HeadingLevel(Option<u8>),
     ListType(Option<ListType>),
     IndentDelta(f32),
@@ -114,6 +216,8 @@ pub enum Shortcut {
     AlignCenter,
     AlignRight,
     AlignJustify,
+    LeftToRight,
+    RightToLeft,
     Save,
     Open,
     New,
@@ -196,6 +300,8 @@ pub fn shortcut_from_vk(ctrl: bool, shift: bool, vk: u32) -> Option<Shortcut> {
         (false, 0x45) => Some(Shortcut::AlignCenter),
         (false, 0x52) => Some(Shortcut::AlignRight),
         (false, 0x4A) => Some(Shortcut::AlignJustify),
+        (true, 0x25) => Some(Shortcut::LeftToRight),
+        (true, 0x27) => Some(Shortcut::RightToLeft),
         (false, 0x53) => Some(Shortcut::Save),
         (false, 0x4F) => Some(Shortcut::Open),
         (false, 0x4E) => Some(Shortcut::New),
@@ -415,7 +521,7 @@ pub fn set_font_size(block_id: BlockId, range: SelectionRange, font_size: f32) -
         range.start,
         range.end,
         RunStylePatch {
-            font_size: Some(font_size.max(1.0)),
+            font_size: Some(clamp_font_size(font_size)),
             ..RunStylePatch::default()
         },
     )
@@ -433,7 +539,11 @@ pub fn set_text_color(block_id: BlockId, range: SelectionRange, color: Color) ->
     )
 }
 
-pub fn set_highlight_color(block_id: BlockId, range: SelectionRange, color: Color) -> EditCommand {
+pub fn set_highlight_color(
+    block_id: BlockId,
+    range: SelectionRange,
+    color: Option<Color>,
+) -> EditCommand {
     format_selection(
         block_id,
         range.start,
@@ -550,7 +660,7 @@ pub fn apply_or_set_font_size(
     font_size: f32,
     pending: &mut RunStyle,
 ) -> Option<EditCommand> {
-    let size = font_size.max(1.0);
+    let size = clamp_font_size(font_size);
     apply_or_pending_patch(
         block_id,
         selection,
@@ -584,7 +694,7 @@ pub fn apply_or_set_text_color(
 pub fn apply_or_set_highlight_color(
     block_id: BlockId,
     selection: Option<SelectionRange>,
-    color: Color,
+    color: Option<Color>,
     pending: &mut RunStyle,
 ) -> Option<EditCommand> {
     apply_or_pending_patch(
@@ -595,7 +705,7 @@ pub fn apply_or_set_highlight_color(
             ..RunStylePatch::default()
         },
         pending,
-        move |style| style.background = Some(color),
+        move |style| style.background = color,
     )
 }
 
@@ -635,6 +745,20 @@ pub fn align_justify(block_id: BlockId) -> EditCommand {
     set_alignment(block_id, ParagraphAlignment::Justify)
 }
 
+pub fn set_direction(block_id: BlockId, direction: TextDirection) -> EditCommand {
+    EditCommand::FormatParagraph {
+        block_id,
+        op: ParagraphFormatOp::Direction(direction),
+    }
+}
+
+pub fn set_paragraph_frame(block_id: BlockId, frame: Option<ParagraphFrame>) -> EditCommand {
+    EditCommand::FormatParagraph {
+        block_id,
+        op: ParagraphFormatOp::Frame(frame),
+    }
+}
+
 pub fn set_heading_level(block_id: BlockId, level: Option<u8>) -> EditCommand {
     EditCommand::FormatParagraph {
         block_id,
@@ -708,6 +832,68 @@ pub fn patch_toggle_underline() -> RunStylePatch {
     }
 }
 
+pub fn clamp_font_size(size: f32) -> f32 {
+    if size.is_finite() {
+        size.clamp(MIN_FONT_SIZE, MAX_FONT_SIZE)
+    } else {
+        MIN_FONT_SIZE
+    }
+}
+
+/// Parses a typed point size such as `"13"`, `"10.5"` or `"14pt"`, clamped to the supported range.
+pub fn parse_font_size(input: &str) -> Option<f32> {
+    let trimmed = input.trim();
+    let number = trimmed
+        .strip_suffix("pt")
+        .unwrap_or(trimmed)
+        .trim_end();
+    let size = number.parse::<f32>().ok()?;
+    size.is_finite().then(|| clamp_font_size(size))
+}
+
+/// Returns the value every run in `range` agrees on, or `None` when they differ.
+/// A collapsed range reports the run under the caret.
+pub fn common_run_value<T, F>(runs: &[Run], range: SelectionRange, selector: F) -> Option<T>
+where
+    T: PartialEq,
+    F: Fn(&RunStyle) -> T,
+{
+    let candidates = if range.is_empty() {
+        run_at_offset(runs, range.start).into_iter().collect()
+    } else {
+        selected_runs(runs, range)
+    };
+    let mut values = candidates.into_iter().map(|run| selector(&run.style));
+    let first = values.next()?;
+    values.all(|value| value == first).then_some(first)
+}
+
+pub fn common_font_family(runs: &[Run], range: SelectionRange, fallback: &str) -> Option<String> {
+    common_run_value(runs, range, |style| {
+        style
+            .font_family
+            .clone()
+            .unwrap_or_else(|| fallback.to_string())
+    })
+}
+
+pub fn common_font_size(runs: &[Run], range: SelectionRange, fallback: f32) -> Option<f32> {
+    common_run_value(runs, range, |style| style.font_size.unwrap_or(fallback))
+}
+
+/// The run typed text at `offset` would join: the one ending at the caret wins at a boundary.
+fn run_at_offset(runs: &[Run], offset: usize) -> Option<&Run> {
+    let mut cursor = 0usize;
+    for run in runs {
+        let next = cursor + run.text.len();
+        if offset <= next && (offset > cursor || cursor == 0) {
+            return Some(run);
+        }
+        cursor = next;
+    }
+    runs.last()
+}
+
 fn selected_runs<'a>(runs: &'a [Run], range: SelectionRange) -> Vec<&'a Run> {
     if runs.is_empty() || range.is_empty() {
         return Vec::new();
@@ -811,6 +997,8 @@ mod tests {
         assert_eq!(shortcut_from_vk(true, false, 0x30), Some(Shortcut::ZoomReset));
         assert_eq!(shortcut_from_vk(true, false, 0x30), Some(Shortcut::ZoomReset));
         assert_eq!(shortcut_from_vk(true, false, 0x41), Some(Shortcut::SelectAll));
+        assert_eq!(shortcut_from_vk(true, true, 0x25), Some(Shortcut::LeftToRight));
+        assert_eq!(shortcut_from_vk(true, true, 0x27), Some(Shortcut::RightToLeft));
     }
 
     #[test]
@@ -878,6 +1066,26 @@ mod tests {
         assert!(!pending.superscript);
     }
 
+    #[test]
+    fn highlight_none_emits_clearing_patch() {
+        let cmd = set_highlight_color(BlockId(3), SelectionRange::new(4, 1), HighlightChoice::None.color());
+        match cmd {
+            EditCommand::FormatRun {
+                start,
+                end,
+                style_patch,
+                ..
+            } => {
+                assert_eq!((start, end), (1, 4));
+                assert_eq!(style_patch.background, Some(None));
+            }
+            _ => panic!("expected format run command"),
+        }
+
+        assert_eq!(HighlightChoice::from_label(" pink "), Some(HighlightChoice::Pink));
+        assert!(HighlightChoice::Yellow.color().is_some());
+    }
+
     #[test]
     fn paragraph_format_builders_emit_expected_commands() {
         match set_heading_level(BlockId(1), Some(9)) {
@@ -904,4 +1112,55 @@ mod tests {
             _ => panic!("expected alignment command"),
         }
     }
+
+    #[test]
+    fn common_font_detected_at_caret_and_over_selection() {
+        let runs = vec![
+            run_with_style(
+                "Hello",
+                RunStyle {
+                    font_family: Some("Georgia".to_string()),
+                    font_size: Some(14.0),
+                    ..RunStyle::default()
+                },
+            ),
+            run_with_style(" world", RunStyle::default()),
+        ];
+
+        let caret = SelectionRange::new(3, 3);
+        assert_eq!(common_font_family(&runs, caret, "Segoe UI").as_deref(), Some("Georgia"));
+        assert_eq!(common_font_size(&runs, caret, 12.0), Some(14.0));
+
+        // At a run boundary the caret belongs to the run it just left.
+        let boundary = SelectionRange::new(5, 5);
+        assert_eq!(common_font_family(&runs, boundary, "Segoe UI").as_deref(), Some("Georgia"));
+
+        let inside_second = SelectionRange::new(7, 9);
+        assert_eq!(common_font_family(&runs, inside_second, "Segoe UI").as_deref(), Some("Segoe UI"));
+        assert_eq!(common_font_size(&runs, inside_second, 12.0), Some(12.0));
+
+        let spanning = SelectionRange::new(2, 8);
+        assert_eq!(common_font_family(&runs, spanning, "Segoe UI"), None);
+        assert_eq!(common_font_size(&runs, spanning, 12.0), None);
+        assert_eq!(common_font_family(&[], caret, "Segoe UI"), None);
+    }
+
+    #[test]
+    fn custom_font_sizes_are_validated_and_clamped() {
+        assert_eq!(parse_font_size("13"), Some(13.0));
+        assert_eq!(parse_font_size(" 10.5pt "), Some(10.5));
+        assert_eq!(parse_font_size("0"), Some(MIN_FONT_SIZE));
+        assert_eq!(parse_font_size("9000"), Some(MAX_FONT_SIZE));
+        assert_eq!(parse_font_size("NaN"), None);
+        assert_eq!(parse_font_size("big"), None);
+        assert_eq!(parse_font_size(""), None);
+
+        let mut pending = RunStyle::default();
+        match apply_or_set_font_size(BlockId(1), Some(SelectionRange::new(0, 2)), 5000.0, &mut pending) {
+            Some(EditCommand::FormatRun { style_patch, .. }) => {
+                assert_eq!(style_patch.font_size, Some(MAX_FONT_SIZE));
+            }
+            _ => panic!("expected font size format command"),
+        }
+    }
 }
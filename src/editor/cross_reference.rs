@@ -0,0 +1,424 @@
+use crate::{
+    document::model::{
+        Anchor, Block, BlockId, CrossReference, CrossReferenceDisplay, DocumentModel, Run,
+        RunStyle, for_each_run_mut,
+    },
+    editor::{
+        caption::{caption_text, number_captions},
+        commands::EditCommand,
+        find_paragraph_in,
+        outline::heading_level,
+    },
+};
+
+/// Word refuses bookmark names longer than this.
+const MAX_ANCHOR_NAME: usize = 40;
+
+/// The block the anchor `name` is set on.
+pub fn resolve_cross_reference(doc: &DocumentModel, name: &str) -> Option<BlockId> {
+    doc.anchors
+        .iter()
+        .find(|anchor| anchor.name == name)
+        .map(|anchor| anchor.block_id)
+}
+
+/// Name of the anchor set on `block_id`, if it has one.
+pub fn anchor_name_of(doc: &DocumentModel, block_id: BlockId) -> Option<&str> {
+    doc.anchors
+        .iter()
+        .find(|anchor| anchor.block_id == block_id)
+        .map(|anchor| anchor.name.as_str())
+}
+
+/// The anchor on `block_id`, set first if the block has none, named after its text or
+/// caption the way Word names bookmarks: a letter first, then letters, digits and
+/// underscores. `None` when the block isn't a heading or paragraph with text, or a captioned
+/// figure or table.
+pub fn ensure_anchor(doc: &mut DocumentModel, block_id: BlockId) -> Option<String> {
+    if let Some(name) = anchor_name_of(doc, block_id) {
+        return Some(name.to_string());
+    }
+    let text =
+        block_text(&doc.content, block_id).or_else(|| caption_text(&doc.content, block_id))?;
+    let mut base = String::new();
+    for ch in text.chars() {
+        if ch.is_alphanumeric() {
+            base.push(ch);
+        } else if !base.is_empty() && !base.ends_with('_') {
+            base.push('_');
+        }
+    }
+    let mut base = base.trim_end_matches('_').to_string();
+    if base.is_empty() {
+        return None;
+    }
+    if !base.starts_with(char::is_alphabetic) {
+        base.insert_str(0, "Ref_");
+    }
+    let base = base.chars().take(MAX_ANCHOR_NAME - 4).collect::<String>();
+    let name = (1..)
+        .map(|n| match n {
+            1 => base.clone(),
+            n => format!("{base}_{n}"),
+        })
+        .find(|name| resolve_cross_reference(doc, name).is_none())?;
+    doc.anchors.push(Anchor {
+        name: name.clone(),
+        block_id,
+    });
+    Some(name)
+}
+
+/// What `reference` shows right now; `None` when its anchor or the anchored block is gone.
+/// A captioned figure or table shows as its label, "Figure 2", or only its number.
+pub fn cross_reference_text(doc: &DocumentModel, reference: &CrossReference) -> Option<String> {
+    let block_id = resolve_cross_reference(doc, &reference.anchor)?;
+    if let Some(caption) = number_captions(&doc.content)
+        .into_iter()
+        .find(|caption| caption.block_id == block_id)
+    {
+        return Some(match reference.display {
+            CrossReferenceDisplay::Text => caption.label(),
+            CrossReferenceDisplay::Number => caption.number.to_string(),
+        });
+    }
+    match reference.display {
+        CrossReferenceDisplay::Text => block_text(&doc.content, block_id),
+        CrossReferenceDisplay::Number => outline_number(&doc.content, block_id),
+    }
+}
+
+/// Rewrites every cross-reference run to what its anchor shows now. References whose anchor
+/// is gone keep their last text. True when any run changed.
+pub fn update_cross_references(doc: &mut DocumentModel) -> bool {
+    if doc.anchors.is_empty() {
+        return false;
+    }
+    let mut references = Vec::new();
+    for_each_run_mut(&mut doc.content, &mut |run| {
+        if let Some(reference) = &run.style.cross_reference
+            && !references.contains(reference)
+        {
+            references.push(reference.clone());
+        }
+    });
+    let texts = references
+        .into_iter()
+        .filter_map(|reference| {
+            let text = cross_reference_text(doc, &reference)?;
+            Some((reference, text))
+        })
+        .collect::<Vec<_>>();
+    let mut changed = false;
+    for_each_run_mut(&mut doc.content, &mut |run| {
+        let current = run.style.cross_reference.as_ref().and_then(|reference| {
+            texts
+                .iter()
+                .find(|(known, _)| known == reference)
+                .map(|(_, text)| text)
+        });
+        if let Some(text) = current
+            && run.text != *text
+        {
+            run.text = text.clone();
+            changed = true;
+        }
+    });
+    changed
+}
+
+/// `ReplaceRuns` inserting a reference to `anchor` at byte `offset` of paragraph
+/// `block_id`. `None` when there's no such paragraph or anchor.
+pub fn insert_cross_reference_command(
+    doc: &DocumentModel,
+    block_id: BlockId,
+    offset: usize,
+    anchor: &str,
+    display: CrossReferenceDisplay,
+) -> Option<EditCommand> {
+    let reference = CrossReference {
+        anchor: anchor.to_string(),
+        display,
+    };
+    let text = cross_reference_text(doc, &reference)?;
+    let paragraph = find_paragraph_in(&doc.content, block_id)?;
+    let mut runs = Vec::new();
+    let mut start = 0usize;
+    let mut inserted = false;
+    for run in paragraph.runs.iter().filter(|run| !run.text.is_empty()) {
+        let end = start + run.text.len();
+        if !inserted && offset < end {
+            let cut = (offset.max(start) - start..=run.text.len())
+                .find(|cut| run.text.is_char_boundary(*cut))
+                .unwrap_or(run.text.len());
+            if cut > 0 {
+                runs.push(Run {
+                    text: run.text[..cut].to_string(),
+                    style: run.style.clone(),
+                });
+            }
+            runs.push(reference_run(&text, &run.style, &reference));
+            if cut < run.text.len() {
+                runs.push(Run {
+                    text: run.text[cut..].to_string(),
+                    style: run.style.clone(),
+                });
+            }
+            inserted = true;
+        } else {
+            runs.push(run.clone());
+        }
+        start = end;
+    }
+    if !inserted {
+        let style = paragraph
+            .runs
+            .last()
+            .map(|run| run.style.clone())
+            .unwrap_or_default();
+        runs.push(reference_run(&text, &style, &reference));
+    }
+    Some(EditCommand::ReplaceRuns { block_id, runs })
+}
+
+/// The reference run, in the surrounding text's font but without its links or notes.
+fn reference_run(text: &str, around: &RunStyle, reference: &CrossReference) -> Run {
+    Run {
+        text: text.to_string(),
+        style: RunStyle {
+            footnote: None,
+            internal_link: None,
+            math: None,
+            wiki_link: None,
+            cross_reference: Some(reference.clone()),
+            ..around.clone()
+        },
+    }
+}
+
+/// Text of the heading or paragraph `block_id`, wherever it sits, on one line.
+fn block_text(blocks: &[Block], block_id: BlockId) -> Option<String> {
+    let runs = blocks.iter().find_map(|block| match block {
+        Block::Paragraph(paragraph) if paragraph.id == block_id => Some(&paragraph.runs),
+        Block::Heading(heading) if heading.id == block_id => Some(&heading.runs),
+        _ => None,
+    });
+    let Some(runs) = runs else {
+        return blocks.iter().find_map(|block| match block {
+            Block::BlockQuote(quote) => block_text(&quote.blocks, block_id),
+            Block::List(list) => list
+                .items
+                .iter()
+                .find_map(|item| block_text(&item.content, block_id)),
+            _ => None,
+        });
+    };
+    let text = runs.iter().map(|run| run.text.as_str()).collect::<String>();
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    (!text.is_empty()).then_some(text)
+}
+
+/// Outline number ("2.1") of the heading `block_id`, or of the heading whose section holds
+/// the top-level block that contains it.
+fn outline_number(blocks: &[Block], block_id: BlockId) -> Option<String> {
+    let index = blocks.iter().position(|block| holds(block, block_id))?;
+    let mut counters = [0u32; 6];
+    let mut number = None;
+    for block in &blocks[..=index] {
+        let Some(level) = heading_level(block) else {
+            continue;
+        };
+        let level = level as usize;
+        counters[level - 1] += 1;
+        counters[level..].fill(0);
+        let parts = &counters[..level];
+        let first = parts.iter().position(|count| *count > 0).unwrap_or(0);
+        number = Some(
+            parts[first..]
+                .iter()
+                .map(u32::to_string)
+                .collect::<Vec<_>>()
+                .join("."),
+        );
+    }
+    number
+}
+
+fn holds(block: &Block, block_id: BlockId) -> bool {
+    match block {
+        Block::Paragraph(paragraph) => paragraph.id == block_id,
+        Block::Heading(heading) => heading.id == block_id,
+        Block::BlockQuote(quote) => {
+            quote.id == block_id || quote.blocks.iter().any(|nested| holds(nested, block_id))
+        }
+        Block::List(list) => list.items.iter().any(|item| {
+            item.id == block_id || item.content.iter().any(|nested| holds(nested, block_id))
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        document::model::{Heading, Paragraph},
+        editor::{EditEngine, ensure_single_run},
+    };
+
+    fn heading(id: u64, level: u8, text: &str) -> Block {
+        Block::Heading(Heading {
+            id: BlockId(id),
+            level,
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+        })
+    }
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: Default::default(),
+            spacing: Default::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    fn document() -> DocumentModel {
+        let mut doc = DocumentModel::default();
+        doc.content = vec![
+            heading(1, 1, "Intro"),
+            paragraph(2, "See "),
+            heading(3, 1, "Method"),
+            heading(4, 2, "Results & data"),
+            paragraph(5, "Numbers here."),
+        ];
+        doc
+    }
+
+    fn texts(doc: &DocumentModel, block_id: u64) -> Vec<String> {
+        let Some(Block::Paragraph(p)) = doc
+            .content
+            .iter()
+            .find(|block| matches!(block, Block::Paragraph(p) if p.id == BlockId(block_id)))
+        else {
+            panic!("expected paragraph {block_id}");
+        };
+        p.runs.iter().map(|run| run.text.clone()).collect()
+    }
+
+    #[test]
+    fn references_resolve_to_their_anchor() {
+        let mut doc = document();
+        let name = ensure_anchor(&mut doc, BlockId(4)).expect("anchor");
+        assert_eq!(name, "Results_data");
+        // Asking again reuses the anchor; another block with the same text gets a suffix.
+        assert_eq!(
+            ensure_anchor(&mut doc, BlockId(4)).as_deref(),
+            Some("Results_data")
+        );
+        doc.content.push(heading(6, 2, "Results: data"));
+        assert_eq!(
+            ensure_anchor(&mut doc, BlockId(6)).as_deref(),
+            Some("Results_data_2")
+        );
+        assert_eq!(
+            resolve_cross_reference(&doc, "Results_data"),
+            Some(BlockId(4))
+        );
+        assert_eq!(resolve_cross_reference(&doc, "Missing"), None);
+
+        let text =
+            insert_cross_reference_command(&doc, BlockId(2), 4, &name, CrossReferenceDisplay::Text)
+                .expect("text reference");
+        let mut engine = EditEngine::default();
+        assert!(engine.apply_command(&mut doc, text));
+        assert_eq!(texts(&doc, 2), ["See ", "Results & data"]);
+
+        let number = insert_cross_reference_command(
+            &doc,
+            BlockId(5),
+            0,
+            &name,
+            CrossReferenceDisplay::Number,
+        )
+        .expect("number reference");
+        assert!(engine.apply_command(&mut doc, number));
+        assert_eq!(texts(&doc, 5), ["2.1", "Numbers here."]);
+        assert!(
+            insert_cross_reference_command(&doc, BlockId(5), 0, "Missing", Default::default())
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn references_follow_edits_to_the_anchored_text() {
+        let mut doc = document();
+        let name = ensure_anchor(&mut doc, BlockId(5)).expect("anchor");
+        assert_eq!(name, "Numbers_here");
+        let mut engine = EditEngine::default();
+        let insert =
+            insert_cross_reference_command(&doc, BlockId(2), 4, &name, CrossReferenceDisplay::Text)
+                .expect("reference");
+        assert!(engine.apply_command(&mut doc, insert));
+
+        assert_eq!(texts(&doc, 2), ["See ", "Numbers here."]);
+
+        let rename = EditCommand::InsertText {
+            block_id: BlockId(5),
+            offset: 0,
+            text: "Final ".to_string(),
+        };
+        assert!(engine.apply_command(&mut doc, rename));
+        assert_eq!(texts(&doc, 2), ["See ", "Final Numbers here."]);
+
+        // Undoing the edit brings the reference back with it.
+        assert!(engine.undo(&mut doc));
+        assert_eq!(texts(&doc, 2), ["See ", "Numbers here."]);
+
+        // A paragraph's number is its section's, which a new heading above shifts.
+        let number = CrossReference {
+            anchor: name,
+            display: CrossReferenceDisplay::Number,
+        };
+        assert_eq!(cross_reference_text(&doc, &number).as_deref(), Some("2.1"));
+        doc.content.insert(0, heading(9, 1, "Preface"));
+        assert_eq!(cross_reference_text(&doc, &number).as_deref(), Some("3.1"));
+    }
+
+    #[test]
+    fn typing_into_a_paragraph_with_a_reference_keeps_the_typed_text() {
+        let mut doc = document();
+        let name = ensure_anchor(&mut doc, BlockId(4)).expect("anchor");
+        let number = insert_cross_reference_command(
+            &doc,
+            BlockId(5),
+            0,
+            &name,
+            CrossReferenceDisplay::Number,
+        )
+        .expect("number reference");
+        let mut engine = EditEngine::default();
+        assert!(engine.apply_command(&mut doc, number));
+        assert_eq!(texts(&doc, 5), ["2.1", "Numbers here."]);
+
+        // Typing merges the paragraph into one run, as the window's text input does.
+        let Some(Block::Paragraph(p)) = doc.content.get_mut(4) else {
+            panic!("expected paragraph 5");
+        };
+        ensure_single_run(&mut p.runs, &RunStyle::default());
+        p.runs[0].text.push_str(" More.");
+
+        assert!(!update_cross_references(&mut doc));
+        assert_eq!(texts(&doc, 5), ["2.1Numbers here. More."]);
+    }
+}
@@ -1,4 +1,9 @@
-use crate::document::model::BlockId;
+use std::ops::Range;
+
+use crate::{
+    document::model::BlockId,
+    editor::words::{next_word_end, previous_word_start, word_range_at},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CursorPosition {
@@ -15,7 +20,7 @@ impl Default for CursorPosition {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct SelectionRange {
     pub start: CursorPosition,
     pub end: CursorPosition,
@@ -118,10 +123,15 @@ impl CursorState {
         self.selection_anchor = None;
     }
 
-    pub fn move_in_text(&mut self, movement: Movement, text: &str, extend_selection: bool) {
+    pub fn move_in_text(
+        &mut self,
+        movement: Movement,
+        text: &str,
+        locale: &str,
+        extend_selection: bool,
+    ) {
         let old = self.primary;
-        let chars: Vec<char> = text.chars().collect();
-        let len = chars.len();
+        let len = text.chars().count();
         let mut offset = self.primary.offset.min(len);
 
         match movement {
@@ -129,22 +139,8 @@ impl CursorState {
             Movement::Right => offset = (offset + 1).min(len),
             Movement::Home | Movement::CtrlHome => offset = 0,
             Movement::End | Movement::CtrlEnd => offset = len,
-            Movement::CtrlLeft => {
-                while offset > 0 && chars[offset - 1].is_whitespace() {
-                    offset -= 1;
-                }
-                while offset > 0 && !chars[offset - 1].is_whitespace() {
-                    offset -= 1;
-                }
-            }
-            Movement::CtrlRight => {
-                while offset < len && chars[offset].is_whitespace() {
-                    offset += 1;
-                }
-                while offset < len && !chars[offset].is_whitespace() {
-                    offset += 1;
-                }
-            }
+            Movement::CtrlLeft => offset = previous_word_start(text, offset, locale),
+            Movement::CtrlRight => offset = next_word_end(text, offset, locale),
             Movement::Up | Movement::Down | Movement::PageUp | Movement::PageDown => {}
         }
 
@@ -250,22 +246,13 @@ impl CursorState {
         self.update_selection(old, extend_selection);
     }
 
-    pub fn select_word(&mut self, block_id: BlockId, text: &str, offset: usize) {
-        let chars: Vec<char> = text.chars().collect();
-        if chars.is_empty() {
+    pub fn select_word(&mut self, block_id: BlockId, text: &str, offset: usize, locale: &str) {
+        if text.is_empty() {
             self.clear_selection();
             return;
         }
 
-        let mut start = offset.min(chars.len());
-        while start > 0 && !chars[start.saturating_sub(1)].is_whitespace() {
-            start -= 1;
-        }
-
-        let mut end = offset.min(chars.len());
-        while end < chars.len() && !chars[end].is_whitespace() {
-            end += 1;
-        }
+        let Range { start, end } = word_range_at(text, offset, locale);
 
         self.set_selection(
             CursorPosition { block_id, offset: start },
@@ -291,6 +278,13 @@ impl CursorState {
         };
     }
 
+    /// Moves the caret to `pos`, stretching the selection from where it was anchored.
+    pub fn extend_to(&mut self, pos: CursorPosition) {
+        let old = self.primary;
+        self.primary = pos;
+        self.update_selection(old, true);
+    }
+
     pub fn drag_select(&mut self, start: CursorPosition, current: CursorPosition) {
         self.set_selection(start, current);
         self.primary = current;
@@ -332,6 +326,7 @@ fn word_right(offset: usize, len: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::editor::words::DEFAULT_LOCALE;
 
     #[test]
     fn block_navigation_moves_across_lines() {
@@ -353,13 +348,13 @@ mod tests {
     #[test]
     fn selection_and_multicursor_work() {
         let mut cursor = CursorState::default();
-        cursor.select_word(BlockId(9), "hello world", 1);
+        cursor.select_word(BlockId(9), "hello world", 1, DEFAULT_LOCALE);
         let sel = cursor.selection.expect("selection expected");
         assert_eq!(sel.start.offset, 0);
         assert_eq!(sel.end.offset, 5);
 
         cursor.primary.offset = 11;
-        cursor.move_in_text(Movement::CtrlLeft, "hello world", false);
+        cursor.move_in_text(Movement::CtrlLeft, "hello world", DEFAULT_LOCALE, false);
         assert_eq!(cursor.primary.offset, 6);
 
         cursor.select_next_occurrence(CursorPosition {
@@ -367,5 +362,17 @@ mod tests {
             offset: 7,
         });
         assert_eq!(cursor.extra_cursors.len(), 1);
+
+        // Extending keeps the first anchor however far the caret goes.
+        let at = |block_id, offset| CursorPosition {
+            block_id: BlockId(block_id),
+            offset,
+        };
+        cursor.clear_selection();
+        cursor.primary = at(9, 4);
+        cursor.extend_to(at(11, 2));
+        cursor.extend_to(at(3, 1));
+        let sel = cursor.selection.expect("selection expected");
+        assert_eq!((sel.start, sel.end), (at(3, 1), at(9, 4)));
     }
 }
@@ -0,0 +1,242 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    document::model::{Block, BlockId},
+    editor::outline::heading_level,
+    settings::document_store::{DocumentStore, data_file_path},
+};
+
+/// How a folded heading, code block or callout is found again after reopening: block ids are handed
+/// out afresh on every load, so a fold remembers the block's kind and text instead, plus
+/// which of the blocks sharing that key it was.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FoldAnchor {
+    pub key: String,
+    pub occurrence: usize,
+}
+
+/// The key a foldable block is remembered by; `None` for blocks that don't fold.
+fn fold_key(block: &Block) -> Option<(BlockId, String)> {
+    let (id, runs) = match block {
+        Block::CodeBlock(code) => {
+            let first_line = code.code.lines().next().unwrap_or_default();
+            let language = code.language.as_deref().unwrap_or_default();
+            return Some((code.id, format!("code:{language}:{}", first_line.trim())));
+        }
+        Block::BlockQuote(quote) => {
+            let kind = quote.callout?;
+            let text = quote
+                .blocks
+                .iter()
+                .find_map(|block| match block {
+                    Block::Paragraph(paragraph) => Some(&paragraph.runs),
+                    _ => None,
+                })
+                .map(|runs| runs.iter().map(|run| run.text.as_str()).collect::<String>())
+                .unwrap_or_default();
+            return Some((
+                quote.id,
+                format!("callout:{}:{}", kind.alert_marker(), text.trim()),
+            ));
+        }
+        Block::Heading(heading) => (heading.id, &heading.runs),
+        Block::Paragraph(paragraph) => (paragraph.id, &paragraph.runs),
+        _ => return None,
+    };
+    let level = heading_level(block)?;
+    let text = runs.iter().map(|run| run.text.as_str()).collect::<String>();
+    Some((id, format!("h{level}:{}", text.trim())))
+}
+
+/// Whether `id` is a callout among `blocks` or inside one of their quotes, which folds to
+/// its header when clicked.
+pub fn is_callout(blocks: &[Block], id: BlockId) -> bool {
+    blocks.iter().any(|block| match block {
+        Block::BlockQuote(quote) => {
+            (quote.id == id && quote.callout.is_some()) || is_callout(&quote.blocks, id)
+        }
+        _ => false,
+    })
+}
+
+/// Anchors for the `folded` blocks of `blocks`, in document order. Folded ids that no
+/// longer name a foldable block are dropped.
+pub fn fold_anchors(blocks: &[Block], folded: &[BlockId]) -> Vec<FoldAnchor> {
+    let mut seen: BTreeMap<String, usize> = BTreeMap::new();
+    let mut anchors = Vec::new();
+    for (id, key) in blocks.iter().filter_map(fold_key) {
+        let occurrence = seen.entry(key.clone()).or_default();
+        if folded.contains(&id) {
+            anchors.push(FoldAnchor {
+                key,
+                occurrence: *occurrence,
+            });
+        }
+        *occurrence += 1;
+    }
+    anchors
+}
+
+/// The blocks of `blocks` the anchors point at. An anchor whose block is gone resolves to
+/// nothing; one whose occurrence ran past the end falls back to the last block with its key.
+pub fn resolve_folds(blocks: &[Block], anchors: &[FoldAnchor]) -> Vec<BlockId> {
+    let mut by_key: BTreeMap<String, Vec<BlockId>> = BTreeMap::new();
+    for (id, key) in blocks.iter().filter_map(fold_key) {
+        by_key.entry(key).or_default().push(id);
+    }
+    let mut folded = Vec::new();
+    for anchor in anchors {
+        let Some(ids) = by_key.get(&anchor.key) else {
+            continue;
+        };
+        let id = ids.get(anchor.occurrence).or(ids.last()).copied();
+        if let Some(id) = id
+            && !folded.contains(&id)
+        {
+            folded.push(id);
+        }
+    }
+    folded
+}
+
+/// Fold anchors per document path, kept in `folds.json` next to the other app data.
+pub type FoldStore = DocumentStore<FoldAnchor>;
+
+pub fn folds_path() -> PathBuf {
+    data_file_path("folds.json")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+    use crate::document::model::{BlockQuote, CalloutKind, CodeBlock, Heading, Paragraph, Run};
+
+    fn heading(id: u64, level: u8, text: &str) -> Block {
+        Block::Heading(Heading {
+            id: BlockId(id),
+            level,
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+        })
+    }
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: Default::default(),
+            spacing: Default::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    fn code(id: u64, text: &str) -> Block {
+        Block::CodeBlock(CodeBlock {
+            id: BlockId(id),
+            language: Some("rust".to_string()),
+            code: text.to_string(),
+        })
+    }
+
+    fn document() -> Vec<Block> {
+        vec![
+            heading(1, 1, "Setup"),
+            paragraph(2, "text"),
+            heading(3, 2, "Example"),
+            code(4, "fn main() {}\n"),
+            heading(5, 1, "Usage"),
+            heading(6, 2, "Example"),
+        ]
+    }
+
+    #[test]
+    fn folds_survive_saving_and_reopening() {
+        let path = std::env::temp_dir().join(format!("doco-folds-{}.json", std::process::id()));
+        let doc = document();
+        let mut store = FoldStore::default();
+        store.remember(
+            Path::new("C:/Docs/Guide.md"),
+            fold_anchors(&doc, &[BlockId(4), BlockId(6)]),
+        );
+        store.save_to(&path).expect("save folds");
+
+        let reloaded = FoldStore::load_from(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            resolve_folds(&doc, reloaded.restore(Path::new("c:/docs/guide.md"))),
+            vec![BlockId(4), BlockId(6)]
+        );
+        assert!(reloaded.restore(Path::new("C:/Docs/Other.md")).is_empty());
+
+        // Unfolding everything forgets the document.
+        let mut store = reloaded;
+        store.remember(Path::new("C:/Docs/Guide.md"), fold_anchors(&doc, &[]));
+        assert!(store.restore(Path::new("C:/Docs/Guide.md")).is_empty());
+    }
+
+    #[test]
+    fn folds_follow_their_block_when_blocks_above_change() {
+        let doc = document();
+        // The second "Example" heading, under Usage.
+        let anchors = fold_anchors(&doc, &[BlockId(6)]);
+
+        // Reopened after a heading and paragraph were added near the top: every id moved.
+        let mut inserted = vec![heading(10, 1, "Overview"), paragraph(11, "new")];
+        inserted.extend(
+            document()
+                .into_iter()
+                .enumerate()
+                .map(|(index, block)| match block {
+                    Block::Heading(mut h) => {
+                        h.id = BlockId(20 + index as u64);
+                        Block::Heading(h)
+                    }
+                    other => other,
+                }),
+        );
+        assert_eq!(resolve_folds(&inserted, &anchors), vec![BlockId(25)]);
+
+        // Reopened after the Setup section above it was deleted.
+        let removed = vec![heading(30, 1, "Usage"), heading(31, 2, "Example")];
+        assert_eq!(resolve_folds(&removed, &anchors), vec![BlockId(31)]);
+
+        // A folded block that no longer exists doesn't fold anything.
+        let renamed = vec![heading(40, 2, "Examples")];
+        assert!(resolve_folds(&renamed, &anchors).is_empty());
+    }
+
+    #[test]
+    fn callouts_fold_but_plain_quotes_do_not() {
+        let quote = |id, callout, text| {
+            Block::BlockQuote(BlockQuote {
+                id: BlockId(id),
+                blocks: vec![paragraph(id + 1, text)],
+                callout,
+            })
+        };
+        let doc = vec![
+            quote(1, Some(CalloutKind::Warning), "Hot"),
+            quote(3, None, "Said someone"),
+        ];
+        assert!(is_callout(&doc, BlockId(1)));
+        assert!(!is_callout(&doc, BlockId(3)));
+
+        let anchors = fold_anchors(&doc, &[BlockId(1), BlockId(3)]);
+        assert_eq!(anchors.len(), 1);
+        assert_eq!(anchors[0].key, "callout:WARNING:Hot");
+        let reopened = vec![quote(7, Some(CalloutKind::Warning), "Hot")];
+        assert_eq!(resolve_folds(&reopened, &anchors), vec![BlockId(7)]);
+    }
+}
@@ -1,8 +1,21 @@
-use std::{fs, path::Path};
+use std::{collections::HashMap, fs, io::Cursor, path::Path};
 
-use image::GenericImageView;
+use image::{
+    DynamicImage, GenericImageView, ImageFormat, Rgba, RgbaImage,
+    codecs::jpeg::JpegEncoder,
+    imageops::{self, FilterType},
+};
 use regex::Regex;
 
+use crate::{
+    document::model::{DocumentModel, ImageData, ImageDataRef},
+    settings::schema::ImageQuality,
+};
+
+/// Embedded images no larger than this on disk are left as they are, unless they're bigger
+/// than the chosen quality keeps.
+const OPTIMIZE_MIN_BYTES: usize = 256 * 1024;
+
 #[derive(Debug, Clone)]
 pub struct LoadedImageAsset {
     pub bytes: Vec<u8>,
@@ -51,6 +64,165 @@ pub fn mime_for_extension(ext: &str) -> Option<&'static str> {
     }
 }
 
+/// Formats "Save image as..." writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageExportFormat {
+    Png,
+    Jpeg,
+}
+
+impl ImageExportFormat {
+    /// The format picked by the chosen file's extension.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "png" => Some(Self::Png),
+            "jpg" | "jpeg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+
+    pub fn mime(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+        }
+    }
+
+    fn encoder(self) -> ImageFormat {
+        match self {
+            Self::Png => ImageFormat::Png,
+            Self::Jpeg => ImageFormat::Jpeg,
+        }
+    }
+}
+
+/// `bytes` of type `mime` as a `format` file. Bytes already in that format are kept as
+/// they are; JPEG has no alpha, so transparent pixels are laid over white first.
+pub fn encode_image_for_export(
+    bytes: &[u8],
+    mime: &str,
+    format: ImageExportFormat,
+) -> Result<Vec<u8>, String> {
+    if mime.eq_ignore_ascii_case(format.mime()) {
+        return Ok(bytes.to_vec());
+    }
+    let decoded =
+        image::load_from_memory(bytes).map_err(|e| format!("failed to decode image: {e}"))?;
+    let decoded = match format {
+        ImageExportFormat::Png => decoded,
+        ImageExportFormat::Jpeg => {
+            let (width, height) = decoded.dimensions();
+            let mut flattened = RgbaImage::from_pixel(width, height, Rgba([255, 255, 255, 255]));
+            imageops::overlay(&mut flattened, &decoded.to_rgba8(), 0, 0);
+            DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(flattened).to_rgb8())
+        }
+    };
+    let mut encoded = Vec::new();
+    decoded
+        .write_to(&mut Cursor::new(&mut encoded), format.encoder())
+        .map_err(|e| format!("failed to encode image: {e}"))?;
+    Ok(encoded)
+}
+
+/// Whether "optimize images on save" should re-encode `image`: a still raster picture that
+/// is heavy on disk or has a side longer than `quality` keeps. SVG and GIF are left alone.
+pub fn should_optimize_image(image: &ImageData, quality: ImageQuality) -> bool {
+    let raster = matches!(
+        image.mime.to_ascii_lowercase().as_str(),
+        "image/png" | "image/jpeg" | "image/bmp" | "image/tiff" | "image/webp"
+    );
+    raster
+        && (image.bytes.len() > OPTIMIZE_MIN_BYTES
+            || image.width.max(image.height) > quality.max_dimension())
+}
+
+/// `image` scaled down to fit `quality` and re-encoded as JPEG, or as PNG when it has
+/// transparent pixels. `None` when it doesn't decode or the result wouldn't be smaller.
+pub fn optimize_image(image: &ImageData, quality: ImageQuality) -> Option<ImageData> {
+    let mut decoded = image::load_from_memory(&image.bytes).ok()?;
+    let max = quality.max_dimension();
+    if decoded.width().max(decoded.height()) > max {
+        decoded = decoded.resize(max, max, FilterType::Lanczos3);
+    }
+    let transparent =
+        decoded.color().has_alpha() && decoded.to_rgba8().pixels().any(|pixel| pixel[3] < 255);
+
+    let mut bytes = Vec::new();
+    let mime = if transparent {
+        decoded
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)
+            .ok()?;
+        "image/png"
+    } else {
+        JpegEncoder::new_with_quality(&mut bytes, quality.jpeg_quality())
+            .encode_image(&decoded.to_rgb8())
+            .ok()?;
+        "image/jpeg"
+    };
+    (bytes.len() < image.bytes.len()).then(|| ImageData {
+        bytes,
+        mime: mime.to_string(),
+        width: decoded.width(),
+        height: decoded.height(),
+    })
+}
+
+/// Pictures to put in place of a document's own, by their key in `DocumentModel::images`.
+/// Applying one hands back the pictures it replaced, so the same type carries optimized
+/// images on the way to disk and the originals kept to restore them.
+#[derive(Debug, Clone, Default)]
+pub struct ImageReplacements(HashMap<String, ImageData>);
+
+impl ImageReplacements {
+    /// Optimized versions of the document's pictures that `should_optimize_image` picks and
+    /// that come out smaller.
+    pub fn optimized(document: &DocumentModel, quality: ImageQuality) -> Self {
+        Self(
+            document
+                .images
+                .iter()
+                .filter(|(_, image)| should_optimize_image(image, quality))
+                .filter_map(|(key, image)| Some((key.clone(), optimize_image(image, quality)?)))
+                .collect(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Swaps the pictures into `document`, including the copies image blocks embed, and
+    /// returns the ones they replaced.
+    pub fn apply(self, document: &mut DocumentModel) -> Self {
+        document.for_each_image_block_mut(|block| {
+            if let ImageDataRef::Embedded(data) = &mut block.data
+                && let Some(next) = self.0.get(&block.key)
+            {
+                *data = next.clone();
+            }
+        });
+        let mut replaced = HashMap::new();
+        for (key, image) in self.0 {
+            if let Some(slot) = document.images.get_mut(&key) {
+                replaced.insert(key, std::mem::replace(slot, image));
+            }
+        }
+        Self(replaced)
+    }
+
+    /// Adds `originals`, keeping any picture already held for the same key, so the first
+    /// original wins when a document is optimized more than once.
+    pub fn remember(&mut self, originals: Self) {
+        for (key, image) in originals.0 {
+            self.0.entry(key).or_insert(image);
+        }
+    }
+}
+
 fn parse_svg_dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
     let source = String::from_utf8_lossy(bytes);
     let root = Regex::new(r"(?is)<svg\b([^>]*)>").ok()?;
@@ -117,9 +289,16 @@ mod tests {
         time::{SystemTime, UNIX_EPOCH},
     };
 
-    use image::DynamicImage;
+    use image::{DynamicImage, ImageFormat, Rgba, RgbaImage};
 
-    use super::load_supported_image;
+    use super::{
+        ImageExportFormat, ImageReplacements, encode_image_for_export, load_supported_image,
+        optimize_image, should_optimize_image,
+    };
+    use crate::{
+        document::model::{DocumentModel, ImageData, ImageDataRef},
+        settings::schema::ImageQuality,
+    };
 
     fn temp_file(name: &str) -> PathBuf {
         let tick = SystemTime::now()
@@ -162,6 +341,152 @@ mod tests {
         let _ = fs::remove_file(path);
     }
 
+    #[test]
+    fn save_format_follows_the_chosen_extension() {
+        let format = |name: &str| ImageExportFormat::for_path(&PathBuf::from(name));
+        assert_eq!(format("shot.png"), Some(ImageExportFormat::Png));
+        assert_eq!(format("shot.JPG"), Some(ImageExportFormat::Jpeg));
+        assert_eq!(format("shot.jpeg"), Some(ImageExportFormat::Jpeg));
+        assert_eq!(format("shot.gif"), None);
+        assert_eq!(format("shot"), None);
+        assert_eq!(ImageExportFormat::Png.encoder(), ImageFormat::Png);
+        assert_eq!(ImageExportFormat::Jpeg.encoder(), ImageFormat::Jpeg);
+        assert_eq!(ImageExportFormat::Jpeg.mime(), "image/jpeg");
+    }
+
+    #[test]
+    fn exports_re_encode_into_the_chosen_format() {
+        let mut source = Vec::new();
+        DynamicImage::ImageRgba8(RgbaImage::from_pixel(4, 3, Rgba([0, 0, 255, 0])))
+            .write_to(&mut std::io::Cursor::new(&mut source), ImageFormat::Bmp)
+            .expect("write bmp");
+
+        let png = encode_image_for_export(&source, "image/bmp", ImageExportFormat::Png)
+            .expect("encode png");
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        let decoded = image::load_from_memory(&png).expect("decode png");
+        assert_eq!((decoded.width(), decoded.height()), (4, 3));
+
+        // The clear pixels land on white rather than black.
+        let jpeg = encode_image_for_export(&png, "image/png", ImageExportFormat::Jpeg)
+            .expect("encode jpeg");
+        assert!(jpeg.starts_with(&[0xFF, 0xD8, 0xFF]));
+        let pixel = image::load_from_memory(&jpeg)
+            .expect("decode jpeg")
+            .to_rgb8()[(1, 1)];
+        assert!(pixel.0.iter().all(|channel| *channel > 240));
+
+        // Bytes already in the chosen format are written unchanged.
+        assert_eq!(
+            encode_image_for_export(&png, "image/png", ImageExportFormat::Png),
+            Ok(png)
+        );
+    }
+
+    fn image_data(mime: &str, byte_len: usize, width: u32, height: u32) -> ImageData {
+        ImageData {
+            bytes: vec![0; byte_len],
+            mime: mime.to_string(),
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn large_or_heavy_raster_images_are_picked_for_optimizing() {
+        let picked = |mime, byte_len, width, height, quality| {
+            should_optimize_image(&image_data(mime, byte_len, width, height), quality)
+        };
+        let medium = ImageQuality::Medium;
+        assert!(!picked("image/png", 40_000, 800, 600, medium));
+        // Heavy on disk, or bigger than the quality keeps.
+        assert!(picked("image/png", 900_000, 800, 600, medium));
+        assert!(picked("image/jpeg", 40_000, 4000, 300, medium));
+        assert!(!picked("image/jpeg", 40_000, 2048, 2048, medium));
+        assert!(picked("image/jpeg", 40_000, 2048, 2048, ImageQuality::Low));
+        // Vector and animated formats keep their bytes.
+        assert!(!picked("image/svg+xml", 900_000, 4000, 4000, medium));
+        assert!(!picked("image/gif", 900_000, 4000, 4000, medium));
+    }
+
+    #[test]
+    fn quality_sets_jpeg_quality_and_size_limit() {
+        let qualities = [ImageQuality::Low, ImageQuality::Medium, ImageQuality::High];
+        assert_eq!(qualities.map(ImageQuality::jpeg_quality), [60, 75, 90]);
+        assert_eq!(
+            qualities.map(ImageQuality::max_dimension),
+            [1280, 2048, 3072]
+        );
+        assert_eq!(ImageQuality::default(), ImageQuality::Medium);
+    }
+
+    fn encoded(image: RgbaImage) -> ImageData {
+        let (width, height) = image.dimensions();
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image)
+            .write_to(&mut std::io::Cursor::new(&mut bytes), ImageFormat::Bmp)
+            .expect("write bmp");
+        ImageData {
+            bytes,
+            mime: "image/bmp".to_string(),
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn optimized_images_shrink_and_keep_transparency() {
+        let opaque = encoded(RgbaImage::from_pixel(1600, 400, Rgba([40, 90, 160, 255])));
+        let optimized = optimize_image(&opaque, ImageQuality::Low).expect("optimized");
+        assert_eq!(optimized.mime, "image/jpeg");
+        assert_eq!((optimized.width, optimized.height), (1280, 320));
+        assert!(optimized.bytes.len() < opaque.bytes.len());
+
+        let clear = encoded(RgbaImage::from_pixel(300, 300, Rgba([40, 90, 160, 0])));
+        let optimized = optimize_image(&clear, ImageQuality::High).expect("optimized");
+        assert_eq!(optimized.mime, "image/png");
+        assert_eq!((optimized.width, optimized.height), (300, 300));
+    }
+
+    #[test]
+    fn originals_come_back_after_optimizing() {
+        let mut document = DocumentModel::default();
+        let original = encoded(RgbaImage::from_pixel(3000, 200, Rgba([200, 30, 30, 255])));
+        document.insert_embedded_image(
+            document.next_block_id(),
+            original.bytes.clone(),
+            original.mime.clone(),
+            original.width,
+            original.height,
+        );
+        let key = document.images.keys().next().cloned().expect("key");
+
+        let replacements = ImageReplacements::optimized(&document, ImageQuality::Medium);
+        assert_eq!(replacements.len(), 1);
+        let mut originals = ImageReplacements::default();
+        originals.remember(replacements.apply(&mut document));
+        assert_eq!(document.images[&key].mime, "image/jpeg");
+        let embedded = |document: &mut DocumentModel| {
+            let mut mimes = Vec::new();
+            document.for_each_image_block_mut(|block| {
+                if let ImageDataRef::Embedded(data) = &block.data {
+                    mimes.push(data.mime.clone());
+                }
+            });
+            mimes
+        };
+        assert_eq!(embedded(&mut document), vec!["image/jpeg"]);
+
+        // Optimizing again finds nothing to do and the first original is still kept.
+        let again = ImageReplacements::optimized(&document, ImageQuality::Medium);
+        assert!(again.is_empty());
+        originals.remember(again.apply(&mut document));
+
+        let _ = originals.apply(&mut document);
+        assert_eq!(document.images[&key].bytes, original.bytes);
+        assert_eq!(embedded(&mut document), vec!["image/bmp"]);
+    }
+
     #[test]
     fn rejects_unsupported_extension() {
         let path = temp_file("sample.txt");
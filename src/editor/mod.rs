@@ -1,21 +1,34 @@
 use std::time::Instant;
 
 use crate::{
-    document::model::{Block, DocumentModel, Paragraph, ParagraphAlignment, Run, RunStyle},
+    document::model::{
+        Block, DocumentModel, Paragraph, ParagraphAlignment, Revision, Run, RunStyle,
+    },
     editor::{
-        commands::{EditCommand, ParagraphFormatOp, RunStylePatch, Shortcut},
+        commands::{EditCommand, ParagraphFormatOp, RunStylePatch, Shortcut, TableOp},
+        cross_reference::update_cross_references,
         cursor::CursorState,
+        table::{apply_table_op, find_table_mut},
         undo::{UndoEntry, UndoStack},
     },
 };
 
+pub mod bookmarks;
+pub mod caption;
 pub mod clipboard;
 pub mod commands;
+pub mod cross_reference;
 pub mod cursor;
+pub mod folding;
 pub mod image_ops;
+pub mod outline;
+pub mod quote;
 pub mod search;
+pub mod snippets;
+pub mod stats;
 pub mod table;
 pub mod undo;
+pub mod words;
 
 #[derive(Default)]
 pub struct EditEngine {
@@ -25,31 +38,43 @@ pub struct EditEngine {
 }
 
 impl EditEngine {
-    pub fn apply_command(&mut self, doc: &mut DocumentModel, command: EditCommand) {
-        if let Some(inverse) = apply_to_document(doc, &command) {
-            let bytes = estimate_command_size(&command);
-            self.undo.push(UndoEntry {
-                command,
-                inverse,
-                bytes,
-                timestamp: Instant::now(),
-            });
-            doc.dirty = true;
-        }
+    /// Applies `command` and records it for undo; false when it didn't apply.
+    pub fn apply_command(&mut self, doc: &mut DocumentModel, command: EditCommand) -> bool {
+        let command = anchor_block_span(doc, command);
+        let Some(inverse) = apply_to_document(doc, &command) else {
+            return false;
+        };
+        let bytes = estimate_command_size(&command);
+        self.undo.push(UndoEntry {
+            command,
+            inverse,
+            bytes,
+            timestamp: Instant::now(),
+        });
+        update_cross_references(doc);
+        doc.dirty = true;
+        true
     }
 
-    pub fn undo(&mut self, doc: &mut DocumentModel) {
-        if let Some(entry) = self.undo.pop_undo() {
-            let _ = apply_to_document(doc, &entry.inverse);
-            doc.dirty = true;
-        }
+    /// Reverts the last command; false when there was nothing to undo.
+    pub fn undo(&mut self, doc: &mut DocumentModel) -> bool {
+        let Some(entry) = self.undo.pop_undo() else {
+            return false;
+        };
+        let _ = apply_to_document(doc, &entry.inverse);
+        update_cross_references(doc);
+        doc.dirty = true;
+        true
     }
 
-    pub fn redo(&mut self, doc: &mut DocumentModel) {
-        if let Some(entry) = self.undo.pop_redo() {
-            let _ = apply_to_document(doc, &entry.command);
-            doc.dirty = true;
-        }
+    pub fn redo(&mut self, doc: &mut DocumentModel) -> bool {
+        let Some(entry) = self.undo.pop_redo() else {
+            return false;
+        };
+        let _ = apply_to_document(doc, &entry.command);
+        update_cross_references(doc);
+        doc.dirty = true;
+        true
     }
 
     pub fn handle_shortcut(&mut self, shortcut: Shortcut) {
@@ -78,7 +103,34 @@ impl EditEngine {
     }
 }
 
-fn apply_to_document(doc: &mut DocumentModel, command: &EditCommand) -> Option<EditCommand> {
+/// Pins a `ReplaceBlocks` span to the block it starts at, so redoing it after edits outside
+/// the undo history still replaces the same blocks.
+fn anchor_block_span(doc: &DocumentModel, command: EditCommand) -> EditCommand {
+    match command {
+        EditCommand::ReplaceBlocks {
+            at_index,
+            count,
+            blocks,
+            start_block: None,
+        } => EditCommand::ReplaceBlocks {
+            at_index,
+            count,
+            blocks,
+            start_block: doc.content.get(at_index).and_then(block_id_of),
+        },
+        command => command,
+    }
+}
+
+/// Applies `command` directly to `doc` and returns its inverse, without touching undo history.
+/// The document takes a new revision.
+pub fn apply_to_document(doc: &mut DocumentModel, command: &EditCommand) -> Option<EditCommand> {
+    let inverse = apply_edit(doc, command)?;
+    doc.revision = Revision::next();
+    Some(inverse)
+}
+
+fn apply_edit(doc: &mut DocumentModel, command: &EditCommand) -> Option<EditCommand> {
     match command {
         EditCommand::InsertText {
             block_id,
@@ -240,6 +292,30 @@ fn apply_to_document(doc: &mut DocumentModel, command: &EditCommand) -> Option<E
                 to_index: from_idx,
             })
         }
+        EditCommand::ReplaceBlocks {
+            at_index,
+            count,
+            blocks,
+            start_block,
+        } => {
+            let idx = start_block
+                .and_then(|id| find_block_index_by_id(doc, id))
+                .unwrap_or(*at_index)
+                .min(doc.content.len());
+            let end = (idx + *count).min(doc.content.len());
+            let removed = doc
+                .content
+                .splice(idx..end, blocks.iter().cloned())
+                .collect::<Vec<_>>();
+            // The span now starts at the first new block, or the block after an emptied span.
+            let start_block = doc.content.get(idx).and_then(block_id_of);
+            Some(EditCommand::ReplaceBlocks {
+                at_index: idx,
+                count: blocks.len(),
+                blocks: removed,
+                start_block,
+            })
+        }
         EditCommand::ReplaceRuns { block_id, runs } => {
             let paragraph = find_paragraph_mut(doc, *block_id)?;
             let old = paragraph.runs.clone();
@@ -296,7 +372,7 @@ fn apply_to_document(doc: &mut DocumentModel, command: &EditCommand) -> Option<E
                     font_family: Some("Segoe UI".to_string()),
                     font_size: Some(12.0),
                     color: None,
-                    background: None,
+                    background: Some(None),
                 },
             );
             Some(EditCommand::ReplaceRuns {
@@ -310,6 +386,8 @@ fn apply_to_document(doc: &mut DocumentModel, command: &EditCommand) -> Option<E
 
             match op {
                 ParagraphFormatOp::Alignment(a) => paragraph.alignment = a.clone(),
+                ParagraphFormatOp::Direction(direction) => paragraph.direction = *direction,
+                ParagraphFormatOp::Frame(frame) => paragraph.frame = frame.clone(),
                 ParagraphFormatOp::HeadingLevel(level) => {
                     paragraph.style_id = level.map(|l| format!("Heading{l}"));
                 }
@@ -349,7 +427,28 @@ fn apply_to_document(doc: &mut DocumentModel, command: &EditCommand) -> Option<E
                 paragraph: old,
             })
         }
-        _ => None,
+        EditCommand::Table { table_id, op } => {
+            let inverse = apply_table_op(find_table_mut(doc, *table_id)?, op)?;
+            Some(EditCommand::Table {
+                table_id: *table_id,
+                op: inverse,
+            })
+        }
+        EditCommand::Batch(commands) => {
+            let mut inverses = Vec::with_capacity(commands.len());
+            for command in commands {
+                let Some(inverse) = apply_to_document(doc, command) else {
+                    // Roll back rather than leave the batch half applied.
+                    for inverse in inverses.iter().rev() {
+                        let _ = apply_to_document(doc, inverse);
+                    }
+                    return None;
+                };
+                inverses.push(inverse);
+            }
+            inverses.reverse();
+            Some(EditCommand::Batch(inverses))
+        }
     }
 }
 
@@ -400,7 +499,7 @@ fn apply_style_patch(paragraph: &mut Paragraph, start: usize, end: usize, patch:
             run.style.color = Some(c);
         }
         if let Some(bg) = patch.background {
-            run.style.background = Some(bg);
+            run.style.background = bg;
         }
     }
 
@@ -453,6 +552,31 @@ fn merge_adjacent_runs(runs: &mut Vec<Run>) {
     }
 }
 
+/// Merges `runs` into the one run typing edits, in the first run's style. A cross-reference
+/// in that style is dropped: the merged run holds the typed text too, which refreshing the
+/// reference would overwrite.
+pub fn ensure_single_run(runs: &mut Vec<Run>, default_style: &RunStyle) {
+    if runs.is_empty() {
+        runs.push(Run {
+            text: String::new(),
+            style: default_style.clone(),
+        });
+        return;
+    }
+    if runs.len() == 1 {
+        runs[0].style.cross_reference = None;
+        return;
+    }
+
+    let text = runs.iter().map(|r| r.text.as_str()).collect::<String>();
+    let style = RunStyle {
+        cross_reference: None,
+        ..runs[0].style.clone()
+    };
+    runs.clear();
+    runs.push(Run { text, style });
+}
+
 fn find_or_create_run(
     doc: &mut DocumentModel,
     block_id: crate::document::model::BlockId,
@@ -478,8 +602,28 @@ fn find_paragraph_mut(
     doc: &mut DocumentModel,
     block_id: crate::document::model::BlockId,
 ) -> Option<&mut Paragraph> {
-    doc.content.iter_mut().find_map(|b| match b {
+    find_paragraph_in_mut(&mut doc.content, block_id)
+}
+
+fn find_paragraph_in(
+    blocks: &[Block],
+    block_id: crate::document::model::BlockId,
+) -> Option<&Paragraph> {
+    blocks.iter().find_map(|b| match b {
+        Block::Paragraph(p) if p.id == block_id => Some(p),
+        Block::BlockQuote(q) => find_paragraph_in(&q.blocks, block_id),
+        _ => None,
+    })
+}
+
+/// Looks inside block quotes too, so quoted text stays editable.
+fn find_paragraph_in_mut(
+    blocks: &mut [Block],
+    block_id: crate::document::model::BlockId,
+) -> Option<&mut Paragraph> {
+    blocks.iter_mut().find_map(|b| match b {
         Block::Paragraph(p) if p.id == block_id => Some(p),
+        Block::BlockQuote(q) => find_paragraph_in_mut(&mut q.blocks, block_id),
         _ => None,
     })
 }
@@ -517,6 +661,8 @@ fn make_empty_paragraph(id: crate::document::model::BlockId) -> Paragraph {
         spacing: crate::document::model::ParagraphSpacing::default(),
         indent: crate::document::model::Indent::default(),
         style_id: None,
+        direction: Default::default(),
+        frame: None,
     }
 }
 
@@ -540,6 +686,20 @@ fn estimate_command_size(cmd: &EditCommand) -> usize {
             Block::Paragraph(p) => p.runs.iter().map(|r| r.text.len() + 32).sum::<usize>() + 64,
             _ => 128,
         },
+        EditCommand::ReplaceBlocks { blocks, .. } => blocks.len() * 128,
+        EditCommand::Table {
+            op: TableOp::Replace(table),
+            ..
+        } => {
+            table
+                .rows
+                .iter()
+                .map(|row| row.cells.len() * 64)
+                .sum::<usize>()
+                + 128
+        }
+        EditCommand::Table { .. } => 48,
+        EditCommand::Batch(commands) => commands.iter().map(estimate_command_size).sum(),
         EditCommand::ReplaceParagraph { paragraph, .. } => {
             paragraph
                 .runs
@@ -561,6 +721,8 @@ fn _new_paragraph_with_id(id: crate::document::model::BlockId) -> Paragraph {
         spacing: crate::document::model::ParagraphSpacing::default(),
         indent: crate::document::model::Indent::default(),
         style_id: None,
+        direction: Default::default(),
+        frame: None,
     }
 }
 
@@ -581,6 +743,8 @@ mod tests {
             spacing: crate::document::model::ParagraphSpacing::default(),
             indent: crate::document::model::Indent::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         }));
         doc
     }
@@ -632,4 +796,341 @@ mod tests {
         engine.redo(&mut doc);
         assert_eq!(doc.content.len(), 2);
     }
+
+    fn paragraph_runs(doc: &DocumentModel) -> Vec<Run> {
+        match &doc.content[0] {
+            Block::Paragraph(p) => p.runs.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn superscript_patch_clears_subscript_in_range() {
+        let mut doc = model_with_text("H2O");
+        let _ = apply_to_document(
+            &mut doc,
+            &EditCommand::FormatRun {
+                block_id: BlockId(1),
+                start: 1,
+                end: 2,
+                style_patch: RunStylePatch {
+                    subscript: Some(true),
+                    ..RunStylePatch::default()
+                },
+            },
+        )
+        .expect("subscript should apply");
+
+        let inverse = apply_to_document(
+            &mut doc,
+            &EditCommand::FormatRun {
+                block_id: BlockId(1),
+                start: 1,
+                end: 2,
+                style_patch: RunStylePatch {
+                    superscript: Some(true),
+                    ..RunStylePatch::default()
+                },
+            },
+        )
+        .expect("superscript should apply");
+
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[1].text, "2");
+        assert!(runs[1].style.superscript);
+        assert!(!runs[1].style.subscript);
+        assert!(!runs[0].style.superscript && !runs[2].style.superscript);
+
+        let _ = apply_to_document(&mut doc, &inverse).expect("inverse should apply");
+        let runs = paragraph_runs(&doc);
+        assert!(runs[1].style.subscript);
+        assert!(!runs[1].style.superscript);
+    }
+
+    #[test]
+    fn highlight_applies_and_clears_over_sub_range() {
+        let yellow = crate::ui::Color::rgb(1.0, 0.95, 0.36);
+        let mut doc = model_with_text("hello world");
+        let _ = apply_to_document(
+            &mut doc,
+            &EditCommand::FormatRun {
+                block_id: BlockId(1),
+                start: 0,
+                end: 11,
+                style_patch: RunStylePatch {
+                    background: Some(Some(yellow)),
+                    ..RunStylePatch::default()
+                },
+            },
+        )
+        .expect("highlight should apply");
+
+        let _ = apply_to_document(
+            &mut doc,
+            &EditCommand::FormatRun {
+                block_id: BlockId(1),
+                start: 6,
+                end: 11,
+                style_patch: RunStylePatch {
+                    background: Some(None),
+                    ..RunStylePatch::default()
+                },
+            },
+        )
+        .expect("clear should apply");
+
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].text, "hello ");
+        assert_eq!(runs[0].style.background, Some(yellow));
+        assert_eq!(runs[1].text, "world");
+        assert_eq!(runs[1].style.background, None);
+
+        // Clearing formatting removes the highlight too.
+        let _ = apply_to_document(
+            &mut doc,
+            &EditCommand::ClearFormatting {
+                block_id: BlockId(1),
+                start: 0,
+                end: 11,
+            },
+        )
+        .expect("clear formatting should apply");
+        assert!(
+            paragraph_runs(&doc)
+                .iter()
+                .all(|run| run.style.background.is_none())
+        );
+    }
+
+    #[test]
+    fn text_color_splits_runs_and_inverse_restores() {
+        let red = crate::ui::Color::rgb(0.9, 0.1, 0.1);
+        let blue = crate::ui::Color::rgb(0.1, 0.2, 0.9);
+        let mut doc = model_with_text("one two three");
+        if let Block::Paragraph(p) = &mut doc.content[0] {
+            p.runs[0].style.color = Some(red);
+        }
+
+        let color_patch = |start, end| EditCommand::FormatRun {
+            block_id: BlockId(1),
+            start,
+            end,
+            style_patch: RunStylePatch {
+                color: Some(blue),
+                ..RunStylePatch::default()
+            },
+        };
+
+        let inverse = apply_to_document(&mut doc, &color_patch(4, 7)).expect("color should apply");
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[1].text, "two");
+        assert_eq!(runs[1].style.color, Some(blue));
+        assert_eq!(runs[2].style.color, Some(red));
+
+        let _ = apply_to_document(&mut doc, &inverse).expect("inverse should apply");
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].style.color, Some(red));
+
+        let _ = apply_to_document(&mut doc, &color_patch(4, 7)).expect("color should apply");
+        let _ = apply_to_document(&mut doc, &color_patch(7, 13)).expect("color should apply");
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[1].text, "two three");
+        assert_eq!(runs[1].style.color, Some(blue));
+    }
+
+    #[test]
+    fn font_patch_applies_family_and_size_to_range_only() {
+        let mut doc = model_with_text("one two three");
+        let inverse = apply_to_document(
+            &mut doc,
+            &EditCommand::FormatRun {
+                block_id: BlockId(1),
+                start: 4,
+                end: 7,
+                style_patch: RunStylePatch {
+                    font_family: Some("Georgia".to_string()),
+                    font_size: Some(18.0),
+                    ..RunStylePatch::default()
+                },
+            },
+        )
+        .expect("font patch should apply");
+
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 3);
+        assert_eq!(runs[1].text, "two");
+        assert_eq!(runs[1].style.font_family.as_deref(), Some("Georgia"));
+        assert_eq!(runs[1].style.font_size, Some(18.0));
+        assert_eq!(runs[0].style.font_family, None);
+        assert_eq!(runs[2].style.font_size, None);
+
+        let _ = apply_to_document(&mut doc, &inverse).expect("inverse should apply");
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].style.font_family, None);
+        assert_eq!(runs[0].style.font_size, None);
+    }
+
+    #[test]
+    fn subscript_patch_clears_superscript() {
+        let mut doc = model_with_text("x2");
+        if let Block::Paragraph(p) = &mut doc.content[0] {
+            p.runs[0].style.superscript = true;
+        }
+
+        let _ = apply_to_document(
+            &mut doc,
+            &EditCommand::FormatRun {
+                block_id: BlockId(1),
+                start: 0,
+                end: 2,
+                style_patch: RunStylePatch {
+                    subscript: Some(true),
+                    ..RunStylePatch::default()
+                },
+            },
+        )
+        .expect("subscript should apply");
+
+        let runs = paragraph_runs(&doc);
+        assert_eq!(runs.len(), 1);
+        assert!(runs[0].style.subscript);
+        assert!(!runs[0].style.superscript);
+    }
+
+    fn table_texts(doc: &DocumentModel) -> Vec<Vec<String>> {
+        let Some(Block::Table(table)) = doc.content.get(1) else {
+            return Vec::new();
+        };
+        table
+            .rows
+            .iter()
+            .map(|row| {
+                row.cells
+                    .iter()
+                    .map(|cell| match cell.blocks.first() {
+                        Some(Block::Paragraph(p)) => p.runs[0].text.clone(),
+                        _ => String::new(),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn table_edits_undo_and_redo_through_the_engine() {
+        let mut doc = model_with_text("intro");
+        let table_id = table::insert_table(&mut doc, 1, 2, 2);
+        let table = find_table_mut(&mut doc, table_id).expect("table inserted");
+        for (index, cell) in table
+            .rows
+            .iter_mut()
+            .flat_map(|row| row.cells.iter_mut())
+            .enumerate()
+        {
+            cell.blocks = model_with_text(&format!("cell {index}")).content;
+        }
+        let original = table_texts(&doc);
+        let mut engine = EditEngine::default();
+        let table_edit = |op| EditCommand::Table { table_id, op };
+
+        assert!(engine.apply_command(&mut doc, table_edit(TableOp::InsertRow { at: 1 })));
+        assert_eq!(table_texts(&doc).len(), 3);
+        assert!(engine.undo(&mut doc));
+        assert_eq!(table_texts(&doc), original);
+        assert!(engine.redo(&mut doc));
+        assert_eq!(table_texts(&doc)[1], vec![String::new(), String::new()]);
+        assert!(engine.undo(&mut doc));
+
+        // Deleting a column and resizing another come back with their contents and widths.
+        assert!(engine.apply_command(&mut doc, table_edit(TableOp::DeleteColumn { col: 0 })));
+        assert!(engine.apply_command(
+            &mut doc,
+            table_edit(TableOp::ResizeColumn {
+                col: 0,
+                width: 300.0
+            })
+        ));
+        assert_eq!(table_texts(&doc), vec![vec!["cell 1"], vec!["cell 3"]]);
+        assert!(engine.undo(&mut doc) && engine.undo(&mut doc));
+        assert_eq!(table_texts(&doc), original);
+        let Some(Block::Table(table)) = doc.content.get(1) else {
+            panic!("table kept");
+        };
+        assert_eq!(table.column_widths, vec![120.0, 120.0]);
+
+        // Whole-table replacements (merges, styles) restore the previous table.
+        let mut merged = table.clone();
+        table::merge_cells(
+            &mut merged,
+            table::TableSelection {
+                start: table::CellPos { row: 0, col: 0 },
+                end: table::CellPos { row: 0, col: 1 },
+            },
+        );
+        assert!(engine.apply_command(&mut doc, table_edit(TableOp::Replace(Box::new(merged)))));
+        assert_ne!(table_texts(&doc), original);
+        assert!(engine.undo(&mut doc));
+        assert_eq!(table_texts(&doc), original);
+        assert!(!engine.apply_command(&mut doc, table_edit(TableOp::DeleteRow { row: 9 })));
+    }
+
+    #[test]
+    fn replaced_blocks_undo_in_place_after_edits_above_them() {
+        let paragraph = |id, text: &str| {
+            let mut block = model_with_text(text).content.remove(0);
+            if let Block::Paragraph(p) = &mut block {
+                p.id = BlockId(id);
+            }
+            block
+        };
+        let texts = |doc: &DocumentModel| {
+            doc.content
+                .iter()
+                .map(|block| match block {
+                    Block::Paragraph(p) => p.runs[0].text.clone(),
+                    _ => String::new(),
+                })
+                .collect::<Vec<_>>()
+        };
+        let mut doc = model_with_text("intro");
+        doc.content.push(paragraph(2, "old"));
+        let mut engine = EditEngine::default();
+        assert!(engine.apply_command(
+            &mut doc,
+            EditCommand::ReplaceBlocks {
+                at_index: 1,
+                count: 1,
+                blocks: vec![paragraph(3, "new a"), paragraph(4, "new b")],
+                start_block: None,
+            }
+        ));
+
+        // Typing Enter splits paragraphs without going through the engine.
+        doc.content.insert(0, paragraph(5, "typed"));
+        assert!(engine.undo(&mut doc));
+        assert_eq!(texts(&doc), ["typed", "intro", "old"]);
+        assert!(engine.redo(&mut doc));
+        assert_eq!(texts(&doc), ["typed", "intro", "new a", "new b"]);
+
+        // An emptied span is found again through the block that followed it.
+        doc.content.push(paragraph(6, "tail"));
+        assert!(engine.apply_command(
+            &mut doc,
+            EditCommand::ReplaceBlocks {
+                at_index: 2,
+                count: 2,
+                blocks: Vec::new(),
+                start_block: Some(BlockId(3)),
+            }
+        ));
+        doc.content.remove(0);
+        assert!(engine.undo(&mut doc));
+        assert_eq!(texts(&doc), ["intro", "new a", "new b", "tail"]);
+    }
 }
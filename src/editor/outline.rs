@@ -0,0 +1,485 @@
+use std::ops::Range;
+
+use crate::{
+    document::model::{Block, BlockId, Indent, Paragraph, Run, RunStyle},
+    editor::{block_id_of, commands::EditCommand},
+};
+
+/// One line of a Table of Contents: a heading's text, its outline level and the heading it
+/// links to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TocEntry {
+    pub text: String,
+    pub level: u8,
+    pub block_id: BlockId,
+}
+
+/// Outline level of a top-level block: headings by level, and paragraphs styled "HeadingN"
+/// the way DOCX imports them.
+pub fn heading_level(block: &Block) -> Option<u8> {
+    match block {
+        Block::Heading(heading) => Some(heading.level.clamp(1, 6)),
+        Block::Paragraph(paragraph) => paragraph
+            .style_id
+            .as_deref()
+            .and_then(|style| {
+                style
+                    .to_ascii_lowercase()
+                    .strip_prefix("heading")?
+                    .parse::<u8>()
+                    .ok()
+            })
+            .filter(|level| (1..=6).contains(level)),
+        _ => None,
+    }
+}
+
+/// Headings of the document in order, the same ones the Outline panel lists.
+pub fn toc_entries(blocks: &[Block]) -> Vec<TocEntry> {
+    blocks
+        .iter()
+        .filter_map(|block| {
+            let level = heading_level(block)?;
+            let (block_id, runs) = match block {
+                Block::Heading(heading) => (heading.id, &heading.runs),
+                Block::Paragraph(paragraph) => (paragraph.id, &paragraph.runs),
+                _ => return None,
+            };
+            Some(TocEntry {
+                text: runs.iter().map(|run| run.text.as_str()).collect(),
+                level,
+                block_id,
+            })
+        })
+        .collect()
+}
+
+/// The headings whose sections hold the block `block_id`, outermost first, as a breadcrumb
+/// reads them. A heading block is the last entry of its own path. Empty for blocks that
+/// aren't at the top level or come before the first heading.
+pub fn heading_path(blocks: &[Block], block_id: BlockId) -> Vec<TocEntry> {
+    let Some(index) = blocks
+        .iter()
+        .position(|block| block_id_of(block) == Some(block_id))
+    else {
+        return Vec::new();
+    };
+    let mut path: Vec<TocEntry> = Vec::new();
+    for entry in toc_entries(&blocks[..=index]) {
+        while path.last().is_some_and(|last| last.level >= entry.level) {
+            path.pop();
+        }
+        path.push(entry);
+    }
+    path
+}
+
+/// Level of a generated Table of Contents line, styled "TOC1".."TOC6" as Word names them.
+pub fn toc_level(block: &Block) -> Option<u8> {
+    let Block::Paragraph(paragraph) = block else {
+        return None;
+    };
+    paragraph
+        .style_id
+        .as_deref()?
+        .strip_prefix("TOC")?
+        .parse::<u8>()
+        .ok()
+        .filter(|level| (1..=6).contains(level))
+}
+
+/// Indices of the document's Table of Contents: the first run of consecutive TOC lines.
+pub fn toc_range(blocks: &[Block]) -> Option<Range<usize>> {
+    let start = blocks.iter().position(|block| toc_level(block).is_some())?;
+    let len = blocks[start..]
+        .iter()
+        .take_while(|block| toc_level(block).is_some())
+        .count();
+    Some(start..start + len)
+}
+
+/// TOC lines for `entries` with ids counting up from `first_id`. Each reads "text<TAB>page"
+/// with the page `page_of` reports for its heading, and every run links to the heading.
+pub fn toc_paragraphs(
+    entries: &[TocEntry],
+    first_id: u64,
+    page_of: impl Fn(BlockId) -> Option<usize>,
+) -> Vec<Block> {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            let style = RunStyle {
+                internal_link: Some(entry.block_id),
+                ..RunStyle::default()
+            };
+            let page = page_of(entry.block_id).map_or_else(String::new, |page| page.to_string());
+            Block::Paragraph(Paragraph {
+                id: BlockId(first_id + index as u64),
+                runs: vec![
+                    Run {
+                        text: entry.text.clone(),
+                        style: style.clone(),
+                    },
+                    Run {
+                        text: format!("\t{page}"),
+                        style,
+                    },
+                ],
+                alignment: Default::default(),
+                spacing: Default::default(),
+                indent: Indent {
+                    left: f32::from(entry.level - 1) * 18.0,
+                    ..Indent::default()
+                },
+                style_id: Some(format!("TOC{}", entry.level)),
+                direction: Default::default(),
+                frame: None,
+            })
+        })
+        .collect()
+}
+
+/// One undo step writing a Table of Contents: over the existing one when the document has
+/// it, else at `insert_at`. `None` when there are no headings to list.
+pub fn table_of_contents_command(
+    blocks: &[Block],
+    insert_at: usize,
+    first_id: u64,
+    page_of: impl Fn(BlockId) -> Option<usize>,
+) -> Option<EditCommand> {
+    let entries = toc_entries(blocks);
+    if entries.is_empty() {
+        return None;
+    }
+    let (at_index, count) = match toc_range(blocks) {
+        Some(existing) => (existing.start, existing.len()),
+        None => (insert_at.min(blocks.len()), 0),
+    };
+    Some(EditCommand::ReplaceBlocks {
+        at_index,
+        count,
+        blocks: toc_paragraphs(&entries, first_id, page_of),
+        start_block: None,
+    })
+}
+
+/// Indices of the blocks a heading owns: the heading itself and everything after it up to
+/// the next heading of the same or a higher level.
+pub fn section_range(blocks: &[Block], heading_id: BlockId) -> Option<Range<usize>> {
+    let start = blocks
+        .iter()
+        .position(|block| block_id_of(block) == Some(heading_id))?;
+    let level = heading_level(&blocks[start])?;
+    let end = blocks[start + 1..]
+        .iter()
+        .position(|block| heading_level(block).is_some_and(|other| other <= level))
+        .map_or(blocks.len(), |offset| start + 1 + offset);
+    Some(start..end)
+}
+
+/// One undo step moving the section of `heading_id` so it starts where `before` is now, or at
+/// the end of the document when `before` is `None`. `None` when the drop lands inside the
+/// section or leaves it where it is.
+///
+/// Sections move with `MoveBlock` commands; one holding blocks without an id (lists, rules,
+/// page breaks) is swapped in place with `ReplaceBlocks` instead.
+pub fn move_section_command(
+    blocks: &[Block],
+    heading_id: BlockId,
+    before: Option<BlockId>,
+) -> Option<EditCommand> {
+    let section = section_range(blocks, heading_id)?;
+    let target = match before {
+        Some(id) => blocks
+            .iter()
+            .position(|block| block_id_of(block) == Some(id))?,
+        None => blocks.len(),
+    };
+    if (section.start..=section.end).contains(&target) {
+        return None;
+    }
+
+    let ids = blocks[section.clone()]
+        .iter()
+        .map(block_id_of)
+        .collect::<Option<Vec<_>>>();
+    let Some(ids) = ids else {
+        let span = section.start.min(target)..section.end.max(target);
+        let mut reordered = blocks[span.clone()].to_vec();
+        if target < section.start {
+            reordered.rotate_right(section.len());
+        } else {
+            reordered.rotate_left(section.len());
+        }
+        return Some(EditCommand::ReplaceBlocks {
+            at_index: span.start,
+            count: span.len(),
+            blocks: reordered,
+            start_block: None,
+        });
+    };
+
+    let moves = ids
+        .into_iter()
+        .enumerate()
+        .map(|(offset, block_id)| EditCommand::MoveBlock {
+            block_id,
+            // Moving up, each block lands after the one before it. Moving down, each leaves a
+            // gap in front of the target, so they all drop just before it.
+            to_index: if target < section.start {
+                target + offset
+            } else {
+                target - 1
+            },
+        })
+        .collect();
+    Some(EditCommand::Batch(moves))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        document::model::{DocumentModel, Heading, List, ListItem, Paragraph, Run},
+        editor::EditEngine,
+    };
+
+    fn heading(id: u64, level: u8, text: &str) -> Block {
+        Block::Heading(Heading {
+            id: BlockId(id),
+            level,
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+        })
+    }
+
+    fn styled_paragraph(id: u64, text: &str, style_id: Option<&str>) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: Default::default(),
+            spacing: Default::default(),
+            indent: Default::default(),
+            style_id: style_id.map(str::to_string),
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        styled_paragraph(id, text, None)
+    }
+
+    fn texts(blocks: &[Block]) -> Vec<String> {
+        blocks
+            .iter()
+            .map(|block| match block {
+                Block::Heading(h) => h.runs.iter().map(|r| r.text.as_str()).collect(),
+                Block::Paragraph(p) => p.runs.iter().map(|r| r.text.as_str()).collect(),
+                Block::List(_) => "list".to_string(),
+                _ => String::new(),
+            })
+            .collect()
+    }
+
+    /// # A / a / ## A1 / a1 / # B / b / # C
+    fn outline_doc() -> DocumentModel {
+        DocumentModel {
+            content: vec![
+                heading(1, 1, "A"),
+                paragraph(2, "a"),
+                heading(3, 2, "A1"),
+                paragraph(4, "a1"),
+                heading(5, 1, "B"),
+                paragraph(6, "b"),
+                heading(7, 1, "C"),
+            ],
+            ..DocumentModel::default()
+        }
+    }
+
+    fn toc_line(block: &Block) -> (String, Option<String>, Option<BlockId>) {
+        let Block::Paragraph(p) = block else {
+            panic!("TOC lines are paragraphs");
+        };
+        (
+            p.runs.iter().map(|r| r.text.as_str()).collect(),
+            p.style_id.clone(),
+            p.runs[0].style.internal_link,
+        )
+    }
+
+    #[test]
+    fn toc_entries_list_nested_headings_with_their_levels_and_ids() {
+        let mut doc = outline_doc();
+        doc.content
+            .insert(4, styled_paragraph(8, "A1a", Some("Heading3")));
+        let entries = toc_entries(&doc.content);
+        let summary = entries
+            .iter()
+            .map(|e| (e.text.as_str(), e.level, e.block_id.0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            [
+                ("A", 1, 1),
+                ("A1", 2, 3),
+                ("A1a", 3, 8),
+                ("B", 1, 5),
+                ("C", 1, 7)
+            ]
+        );
+        assert!(toc_entries(&[paragraph(1, "body")]).is_empty());
+    }
+
+    #[test]
+    fn heading_path_follows_the_nearest_heading_at_each_level() {
+        let mut doc = outline_doc();
+        // # A / a / ## A1 / ### A1a / a1a / a1 / # B / b / # C
+        doc.content
+            .insert(3, styled_paragraph(8, "A1a", Some("Heading3")));
+        doc.content.insert(4, paragraph(9, "a1a"));
+        let path = |id| {
+            heading_path(&doc.content, BlockId(id))
+                .into_iter()
+                .map(|entry| entry.text)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(path(9), ["A", "A1", "A1a"]);
+        assert_eq!(path(4), ["A", "A1", "A1a"]);
+        assert_eq!(path(3), ["A", "A1"]);
+        assert_eq!(path(2), ["A"]);
+        // A level-one heading closes everything opened under the previous one.
+        assert_eq!(path(6), ["B"]);
+        assert_eq!(path(7), ["C"]);
+        assert!(path(42).is_empty());
+
+        let body_first = [paragraph(1, "preface"), heading(2, 2, "Late")];
+        assert!(heading_path(&body_first, BlockId(1)).is_empty());
+        assert_eq!(heading_path(&body_first, BlockId(2))[0].level, 2);
+    }
+
+    #[test]
+    fn table_of_contents_inserts_then_updates_in_place() {
+        let mut doc = outline_doc();
+        let mut engine = EditEngine::default();
+        let page_of = |id: BlockId| Some(if id.0 < 5 { 1 } else { 2 });
+
+        let command = table_of_contents_command(&doc.content, 0, 100, page_of).unwrap();
+        assert!(engine.apply_command(&mut doc, command));
+        assert_eq!(toc_range(&doc.content), Some(0..4));
+        assert_eq!(
+            toc_line(&doc.content[1]),
+            (
+                "A1\t1".to_string(),
+                Some("TOC2".to_string()),
+                Some(BlockId(3))
+            )
+        );
+        assert_eq!(toc_line(&doc.content[3]).0, "C\t2");
+
+        // A new heading and a stale page are both picked up, and no second TOC appears.
+        doc.content.push(heading(9, 2, "C1"));
+        let command =
+            table_of_contents_command(&doc.content, doc.content.len(), 200, |_| Some(3)).unwrap();
+        assert!(engine.apply_command(&mut doc, command));
+        assert_eq!(toc_range(&doc.content), Some(0..5));
+        assert_eq!(toc_line(&doc.content[0]).0, "A\t3");
+        assert_eq!(toc_line(&doc.content[4]).2, Some(BlockId(9)));
+        assert_eq!(texts(&doc.content[5..]).len(), 8);
+
+        assert!(engine.undo(&mut doc));
+        assert_eq!(toc_range(&doc.content), Some(0..4));
+        assert!(table_of_contents_command(&[paragraph(1, "body")], 0, 2, page_of).is_none());
+    }
+
+    #[test]
+    fn section_range_runs_to_the_next_heading_of_the_same_or_higher_level() {
+        let doc = outline_doc();
+        assert_eq!(section_range(&doc.content, BlockId(1)), Some(0..4));
+        assert_eq!(section_range(&doc.content, BlockId(3)), Some(2..4));
+        assert_eq!(section_range(&doc.content, BlockId(5)), Some(4..6));
+        assert_eq!(section_range(&doc.content, BlockId(7)), Some(6..7));
+        // Body text and unknown ids own no section.
+        assert_eq!(section_range(&doc.content, BlockId(2)), None);
+        assert_eq!(section_range(&doc.content, BlockId(99)), None);
+
+        let styled = vec![
+            styled_paragraph(1, "styled", Some("Heading2")),
+            paragraph(2, "body"),
+            heading(3, 3, "deeper"),
+            heading(4, 1, "next"),
+        ];
+        assert_eq!(section_range(&styled, BlockId(1)), Some(0..3));
+    }
+
+    #[test]
+    fn moving_a_section_carries_its_blocks_and_undoes_in_one_step() {
+        let mut doc = outline_doc();
+        let original = texts(&doc.content);
+        let mut engine = EditEngine::default();
+
+        // A (with A1) dropped before C.
+        let command = move_section_command(&doc.content, BlockId(1), Some(BlockId(7))).unwrap();
+        assert!(engine.apply_command(&mut doc, command));
+        assert_eq!(texts(&doc.content), ["B", "b", "A", "a", "A1", "a1", "C"]);
+
+        // C dropped at the top, then A1 pulled out of A to sit before B.
+        let command = move_section_command(&doc.content, BlockId(7), Some(BlockId(5))).unwrap();
+        assert!(engine.apply_command(&mut doc, command));
+        assert_eq!(texts(&doc.content), ["C", "B", "b", "A", "a", "A1", "a1"]);
+        let command = move_section_command(&doc.content, BlockId(3), Some(BlockId(5))).unwrap();
+        assert!(engine.apply_command(&mut doc, command));
+        assert_eq!(texts(&doc.content), ["C", "A1", "a1", "B", "b", "A", "a"]);
+
+        assert!(engine.undo(&mut doc));
+        assert!(engine.undo(&mut doc));
+        assert!(engine.undo(&mut doc));
+        assert_eq!(texts(&doc.content), original);
+        assert!(engine.redo(&mut doc));
+        assert_eq!(texts(&doc.content), ["B", "b", "A", "a", "A1", "a1", "C"]);
+    }
+
+    #[test]
+    fn drops_inside_the_section_do_nothing() {
+        let doc = outline_doc();
+        assert!(move_section_command(&doc.content, BlockId(1), Some(BlockId(1))).is_none());
+        assert!(move_section_command(&doc.content, BlockId(1), Some(BlockId(3))).is_none());
+        // Right after its own last block is where it already is.
+        assert!(move_section_command(&doc.content, BlockId(1), Some(BlockId(5))).is_none());
+        assert!(move_section_command(&doc.content, BlockId(7), None).is_none());
+    }
+
+    #[test]
+    fn sections_with_lists_move_as_one_replacement() {
+        let mut doc = outline_doc();
+        doc.content.insert(
+            6,
+            Block::List(List {
+                items: vec![ListItem {
+                    id: BlockId(20),
+                    content: vec![paragraph(21, "item")],
+                    ..ListItem::default()
+                }],
+                ..List::default()
+            }),
+        );
+        let mut engine = EditEngine::default();
+        let command = move_section_command(&doc.content, BlockId(5), Some(BlockId(1))).unwrap();
+        assert!(matches!(command, EditCommand::ReplaceBlocks { .. }));
+        assert!(engine.apply_command(&mut doc, command));
+        assert_eq!(
+            texts(&doc.content),
+            ["B", "b", "list", "A", "a", "A1", "a1", "C"]
+        );
+        assert!(engine.undo(&mut doc));
+        assert_eq!(texts(&doc.content)[4..], ["B", "b", "list", "C"]);
+    }
+}
@@ -0,0 +1,164 @@
+use crate::{
+    document::model::{Block, BlockId, BlockQuote, DocumentModel},
+    editor::{block_id_of, commands::EditCommand},
+};
+
+/// `ReplaceBlocks` command that wraps the top-level blocks holding `first..=last` in a new
+/// block quote or, when they are all block quotes already, lifts their contents out by one
+/// level. `None` when either block isn't in the document.
+pub fn toggle_block_quote_command(
+    doc: &DocumentModel,
+    first: BlockId,
+    last: BlockId,
+) -> Option<EditCommand> {
+    let index_of = |id: BlockId| doc.content.iter().position(|block| holds_block(block, id));
+    let (start, end) = (index_of(first)?, index_of(last)?);
+    let (start, end) = (start.min(end), start.max(end));
+    let selected = &doc.content[start..=end];
+    let blocks = if selected
+        .iter()
+        .all(|block| matches!(block, Block::BlockQuote(_)))
+    {
+        selected
+            .iter()
+            .flat_map(|block| match block {
+                Block::BlockQuote(quote) => quote.blocks.clone(),
+                _ => Vec::new(),
+            })
+            .collect()
+    } else {
+        vec![Block::BlockQuote(BlockQuote {
+            id: doc.next_block_id(),
+            blocks: selected.to_vec(),
+            callout: None,
+        })]
+    };
+    Some(EditCommand::ReplaceBlocks {
+        at_index: start,
+        count: end - start + 1,
+        blocks,
+        start_block: None,
+    })
+}
+
+/// Whether `block` is `id` or holds it in a quote or list item, where the cursor can sit.
+fn holds_block(block: &Block, id: BlockId) -> bool {
+    match block {
+        Block::BlockQuote(quote) => {
+            quote.id == id || quote.blocks.iter().any(|nested| holds_block(nested, id))
+        }
+        Block::List(list) => list
+            .items
+            .iter()
+            .any(|item| item.id == id || item.content.iter().any(|nested| holds_block(nested, id))),
+        other => block_id_of(other) == Some(id),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        document::model::{Paragraph, Run},
+        editor::EditEngine,
+    };
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: Default::default(),
+            spacing: Default::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    /// Each block as its text, with quoted blocks prefixed by one `>` per level.
+    fn outline(blocks: &[Block]) -> Vec<String> {
+        fn walk(blocks: &[Block], prefix: &str, out: &mut Vec<String>) {
+            for block in blocks {
+                match block {
+                    Block::Paragraph(p) => out.push(format!(
+                        "{prefix}{}",
+                        p.runs.iter().map(|r| r.text.as_str()).collect::<String>()
+                    )),
+                    Block::BlockQuote(quote) => walk(&quote.blocks, &format!("{prefix}>"), out),
+                    _ => out.push(format!("{prefix}?")),
+                }
+            }
+        }
+        let mut out = Vec::new();
+        walk(blocks, "", &mut out);
+        out
+    }
+
+    fn document() -> DocumentModel {
+        let mut doc = DocumentModel::default();
+        doc.content = vec![
+            paragraph(1, "a"),
+            paragraph(2, "b"),
+            paragraph(3, "c"),
+            paragraph(4, "d"),
+        ];
+        doc
+    }
+
+    #[test]
+    fn selected_blocks_wrap_in_a_quote_and_unwrap_again() {
+        let mut doc = document();
+        let mut engine = EditEngine::default();
+
+        // The selection may run upwards.
+        let wrap = toggle_block_quote_command(&doc, BlockId(3), BlockId(2)).expect("wrap");
+        assert!(engine.apply_command(&mut doc, wrap));
+        assert_eq!(outline(&doc.content), ["a", ">b", ">c", "d"]);
+        let Block::BlockQuote(quote) = &doc.content[1] else {
+            panic!("expected a block quote");
+        };
+        assert_eq!(quote.id, BlockId(5));
+
+        // From inside the quote, the same command lifts the text back out.
+        let unwrap = toggle_block_quote_command(&doc, BlockId(2), BlockId(2)).expect("unwrap");
+        assert!(engine.apply_command(&mut doc, unwrap));
+        assert_eq!(outline(&doc.content), ["a", "b", "c", "d"]);
+
+        assert!(engine.undo(&mut doc));
+        assert_eq!(outline(&doc.content), ["a", ">b", ">c", "d"]);
+        assert!(engine.undo(&mut doc));
+        assert_eq!(outline(&doc.content), ["a", "b", "c", "d"]);
+        assert!(toggle_block_quote_command(&doc, BlockId(1), BlockId(99)).is_none());
+    }
+
+    #[test]
+    fn unwrapping_a_nested_quote_removes_one_level() {
+        let mut doc = document();
+        doc.content[1] = Block::BlockQuote(BlockQuote {
+            id: BlockId(10),
+            blocks: vec![
+                paragraph(2, "b"),
+                Block::BlockQuote(BlockQuote {
+                    id: BlockId(11),
+                    blocks: vec![paragraph(12, "deep")],
+                    callout: None,
+                }),
+            ],
+            callout: None,
+        });
+        let mut engine = EditEngine::default();
+
+        let unwrap = toggle_block_quote_command(&doc, BlockId(12), BlockId(12)).expect("unwrap");
+        assert!(engine.apply_command(&mut doc, unwrap));
+        assert_eq!(outline(&doc.content), ["a", "b", ">deep", "c", "d"]);
+
+        // A selection mixing quoted and plain blocks wraps them all, nesting the quote.
+        let wrap = toggle_block_quote_command(&doc, BlockId(1), BlockId(12)).expect("wrap");
+        assert!(engine.apply_command(&mut doc, wrap));
+        assert_eq!(outline(&doc.content), [">a", ">b", ">>deep", "c", "d"]);
+    }
+}
@@ -1,19 +1,26 @@
 use std::{
     collections::HashSet,
     hash::{Hash, Hasher},
+    ops::Range,
     time::{Duration, Instant},
 };
 
 use regex::{Regex, RegexBuilder};
 
-use crate::document::model::{
-    Block,
-    BlockId,
-    DocumentModel,
-    Heading,
-    List,
-    Paragraph,
-    Table,
+use crate::{
+    document::model::{
+        Block,
+        BlockId,
+        DocumentModel,
+        Heading,
+        List,
+        ListItem,
+        Paragraph,
+        Run,
+        Table,
+    },
+    editor::cursor::SelectionRange,
+    settings::schema::FindSettings,
 };
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -47,6 +54,7 @@ pub struct SearchMatch {
 struct SearchCacheKey {
     query: String,
     options: SearchOptions,
+    scope: Option<SelectionRange>,
     doc_fingerprint: u64,
 }
 
@@ -57,6 +65,15 @@ struct CachedRegex {
     compiled: Regex,
 }
 
+/// A pattern that failed to compile, kept so it isn't recompiled until the query or options
+/// change.
+#[derive(Debug, Clone)]
+struct RegexFailure {
+    query: String,
+    options: SearchOptions,
+    message: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct FindReplaceState {
     pub find_visible: bool,
@@ -64,6 +81,10 @@ pub struct FindReplaceState {
     pub query: String,
     pub replacement: String,
     pub options: SearchOptions,
+    /// "Match in selection only": results are limited to `selection` when it is set.
+    pub in_selection: bool,
+    /// The editor selection captured when the find bar opened.
+    pub selection: Option<SelectionRange>,
     pub results: Vec<SearchMatch>,
     pub current_index: usize,
     pub result_count_text: String,
@@ -73,8 +94,13 @@ pub struct FindReplaceState {
     pub pending_live_update: bool,
     cache_key: Option<SearchCacheKey>,
     compiled_regex: Option<CachedRegex>,
-    background_blocks: Vec<SearchableBlock>,
+    regex_failure: Option<RegexFailure>,
+    index: SearchIndex,
+    /// Indices into `index` still to search after the visible blocks.
+    background_blocks: Vec<usize>,
     background_cursor: usize,
+    /// The selection the current results were clipped to, in document order.
+    active_scope: Option<SelectionRange>,
 }
 
 impl Default for FindReplaceState {
@@ -85,6 +111,8 @@ impl Default for FindReplaceState {
             query: String::new(),
             replacement: String::new(),
             options: SearchOptions::default(),
+            in_selection: false,
+            selection: None,
             results: Vec::new(),
             current_index: 0,
             result_count_text: "0 results".to_string(),
@@ -94,8 +122,11 @@ impl Default for FindReplaceState {
             pending_live_update: false,
             cache_key: None,
             compiled_regex: None,
+            regex_failure: None,
+            index: SearchIndex::default(),
             background_blocks: Vec::new(),
             background_cursor: 0,
+            active_scope: None,
         }
     }
 }
@@ -126,6 +157,43 @@ impl FindReplaceState {
         self.replacement = replacement;
     }
 
+    /// Restores toggles and the live-search delay saved from an earlier session.
+    pub fn apply_settings(&mut self, find: FindSettings) {
+        let options = SearchOptions {
+            case_sensitive: find.case_sensitive,
+            whole_word: find.whole_word,
+            regex: find.regex,
+        };
+        if options != self.options || find.in_selection != self.in_selection {
+            self.invalidate_cache();
+        }
+        self.options = options;
+        self.in_selection = find.in_selection;
+        self.debounce_ms = find.debounce_ms;
+    }
+
+    /// The toggles and live-search delay to save for the next session.
+    pub fn settings(&self) -> FindSettings {
+        FindSettings {
+            case_sensitive: self.options.case_sensitive,
+            whole_word: self.options.whole_word,
+            regex: self.options.regex,
+            in_selection: self.in_selection,
+            debounce_ms: self.debounce_ms,
+        }
+    }
+
+    /// Why the regex query doesn't compile, while regex mode is on and it doesn't.
+    pub fn regex_error(&self) -> Option<&str> {
+        self.regex_failure
+            .as_ref()
+            .map(|failure| failure.message.as_str())
+    }
+
+    fn selection_scope(&self) -> Option<SelectionRange> {
+        self.selection.filter(|_| self.in_selection)
+    }
+
     pub fn should_live_update(&self, now: Instant) -> bool {
         self.pending_live_update
             && now.duration_since(self.last_input_at) >= Duration::from_millis(self.debounce_ms)
@@ -151,10 +219,22 @@ impl FindReplaceState {
             return 0;
         }
 
-        let doc_fingerprint = document_fingerprint(doc);
+        if self.options.regex && self.ensure_compiled_regex().is_none() {
+            self.results.clear();
+            self.current_index = 0;
+            self.background_blocks.clear();
+            self.background_cursor = 0;
+            self.pending_live_update = false;
+            self.cache_key = None;
+            self.update_result_count_text();
+            return 0;
+        }
+
+        let doc_fingerprint = self.index.sync(doc);
         let cache_key = SearchCacheKey {
             query: self.query.clone(),
             options: self.options,
+            scope: self.selection_scope(),
             doc_fingerprint,
         };
         let background_done = self.background_cursor >= self.background_blocks.len();
@@ -164,16 +244,25 @@ impl FindReplaceState {
             return self.results.len();
         }
 
-        let all_blocks = collect_searchable_blocks(doc);
+        let mut range = 0..self.index.blocks.len();
+        self.active_scope = None;
+        if let Some((first, last, scope)) = self
+            .selection_scope()
+            .and_then(|selection| selection_span(&self.index.blocks, selection))
+        {
+            range = first..last + 1;
+            self.active_scope = Some(scope);
+        }
         let (visible_blocks, background_blocks) =
-            split_visible_and_background_blocks(all_blocks, visible_block_ids);
+            split_visible_and_background_blocks(&self.index.blocks, range, visible_block_ids);
         let regex = self.ensure_compiled_regex();
-        self.results = search_blocks(
+        self.results = self.index.search_blocks(
             &visible_blocks,
             self.query.as_str(),
             self.options,
             regex.as_ref(),
         );
+        retain_matches_in_scope(&mut self.results, self.active_scope);
         self.current_index = self.current_index.min(self.results.len().saturating_sub(1));
         self.background_blocks = background_blocks;
         self.background_cursor = 0;
@@ -197,7 +286,9 @@ impl FindReplaceState {
         let regex = self.ensure_compiled_regex();
         let chunk = self.background_blocks[self.background_cursor..end].to_vec();
         let mut chunk_matches =
-            search_blocks(&chunk, self.query.as_str(), self.options, regex.as_ref());
+            self.index
+                .search_blocks(&chunk, self.query.as_str(), self.options, regex.as_ref());
+        retain_matches_in_scope(&mut chunk_matches, self.active_scope);
         let changed = !chunk_matches.is_empty();
         self.results.append(&mut chunk_matches);
         self.background_cursor = end;
@@ -216,6 +307,12 @@ impl FindReplaceState {
         self.background_cursor = 0;
     }
 
+    /// Drops the cached results and the text index; call after editing the searched document.
+    pub fn document_edited(&mut self) {
+        self.invalidate_cache();
+        self.index.invalidate();
+    }
+
     pub fn current_result(&self) -> Option<&SearchMatch> {
         self.results.get(self.current_index)
     }
@@ -243,6 +340,15 @@ impl FindReplaceState {
     fn ensure_compiled_regex(&mut self) -> Option<Regex> {
         if !self.options.regex || self.query.is_empty() {
             self.compiled_regex = None;
+            self.regex_failure = None;
+            return None;
+        }
+
+        let already_failed = self
+            .regex_failure
+            .as_ref()
+            .is_some_and(|failure| failure.query == self.query && failure.options == self.options);
+        if already_failed {
             return None;
         }
 
@@ -251,13 +357,22 @@ impl FindReplaceState {
             .as_ref()
             .is_none_or(|cached| cached.query != self.query || cached.options != self.options);
         if needs_rebuild {
-            self.compiled_regex = build_regex(self.query.as_str(), self.options)
-                .ok()
-                .map(|compiled| CachedRegex {
+            self.regex_failure = None;
+            self.compiled_regex = match build_regex(self.query.as_str(), self.options) {
+                Ok(compiled) => Some(CachedRegex {
                     query: self.query.clone(),
                     options: self.options,
                     compiled,
-                });
+                }),
+                Err(error) => {
+                    self.regex_failure = Some(RegexFailure {
+                        query: self.query.clone(),
+                        options: self.options,
+                        message: regex_error_message(&error),
+                    });
+                    None
+                }
+            };
         }
 
         self.compiled_regex
@@ -268,6 +383,8 @@ impl FindReplaceState {
     fn update_result_count_text(&mut self) {
         if self.query.is_empty() {
             self.result_count_text = "0 results".to_string();
+        } else if let Some(message) = self.regex_error() {
+            self.result_count_text = format!("Invalid regex: {message}");
         } else if self.has_pending_background_search() {
             self.result_count_text = format!("{}+ results for '{}'", self.results.len(), self.query);
         } else {
@@ -276,145 +393,328 @@ impl FindReplaceState {
     }
 }
 
-pub fn search_document(doc: &DocumentModel, query: &str, options: SearchOptions) -> Vec<SearchMatch> {
-    if query.is_empty() {
-        return Vec::new();
-    }
+/// Searchable text of one document, gathered once and shared by every query until the
+/// document changes. Blocks keep a lowercase copy for case-insensitive queries, and while a
+/// plain query grows the way typing grows it, blocks that lacked the shorter query are
+/// skipped without being scanned again.
+#[derive(Debug, Clone, Default)]
+pub struct SearchIndex {
+    fingerprint: Option<u64>,
+    blocks: Vec<SearchableBlock>,
+    folded: Vec<String>,
+    plain_hits: Option<PlainHits>,
+}
 
-    let blocks = collect_searchable_blocks(doc);
-    let compiled = if options.regex {
-        build_regex(query, options).ok()
-    } else {
-        None
-    };
-    search_blocks(&blocks, query, options, compiled.as_ref())
+/// Which blocks contain the `needle` of the last plain query; `None` for blocks not yet
+/// checked.
+#[derive(Debug, Clone)]
+struct PlainHits {
+    needle: String,
+    case_sensitive: bool,
+    contains: Vec<Option<bool>>,
 }
 
-fn search_blocks(
-    blocks: &[SearchableBlock],
-    query: &str,
-    options: SearchOptions,
-    compiled_regex: Option<&Regex>,
-) -> Vec<SearchMatch> {
-    if query.is_empty() {
-        return Vec::new();
+impl SearchIndex {
+    /// Brings the index up to date with `doc`, rebuilding only when its searchable text
+    /// changed, and returns that text's fingerprint.
+    pub fn sync(&mut self, doc: &DocumentModel) -> u64 {
+        let fingerprint = document_fingerprint(doc);
+        if self.fingerprint != Some(fingerprint) {
+            self.blocks = collect_searchable_blocks(doc);
+            self.folded = self
+                .blocks
+                .iter()
+                .map(|block| block.text.to_ascii_lowercase())
+                .collect();
+            self.plain_hits = None;
+            self.fingerprint = Some(fingerprint);
+        }
+        fingerprint
     }
 
-    let mut matches = Vec::new();
-    if options.regex {
-        let Some(regex) = compiled_regex else {
-            return Vec::new();
+    /// Forgets the indexed text so the next [`SearchIndex::sync`] rebuilds it.
+    pub fn invalidate(&mut self) {
+        self.fingerprint = None;
+        self.plain_hits = None;
+    }
+
+    /// Every match in the indexed document, in block order. An invalid regex matches nothing.
+    pub fn search(&mut self, query: &str, options: SearchOptions) -> Vec<SearchMatch> {
+        let compiled = if options.regex {
+            match build_regex(query, options) {
+                Ok(compiled) => Some(compiled),
+                Err(_) => return Vec::new(),
+            }
+        } else {
+            None
         };
+        let all = (0..self.blocks.len()).collect::<Vec<_>>();
+        self.search_blocks(&all, query, options, compiled.as_ref())
+    }
 
-        for block in blocks {
-            for cap in regex.captures_iter(block.text.as_str()) {
-                if let Some(m) = cap.get(0) {
-                    if options.whole_word && !is_whole_word(block.text.as_str(), m.start(), m.end()) {
-                        continue;
-                    }
+    /// Matches in the blocks at `indices`, in that order.
+    fn search_blocks(
+        &mut self,
+        indices: &[usize],
+        query: &str,
+        options: SearchOptions,
+        compiled_regex: Option<&Regex>,
+    ) -> Vec<SearchMatch> {
+        let mut matches = Vec::new();
+        if query.is_empty() {
+            return matches;
+        }
 
-                    let groups = (1..cap.len())
-                        .filter_map(|i| cap.get(i).map(|g| (g.start(), g.end())))
-                        .collect::<Vec<_>>();
-                    matches.push(SearchMatch {
-                        block_id: block.id,
-                        start: m.start(),
-                        end: m.end(),
-                        line_or_page: block.line_or_page,
-                        snippet: snippet(block.text.as_str(), m.start(), m.end()),
-                        capture_groups: groups,
-                    });
-                }
+        if options.regex {
+            let Some(regex) = compiled_regex else {
+                return matches;
+            };
+            for &index in indices {
+                regex_matches_in_block(&self.blocks[index], regex, options, &mut matches);
+            }
+            return matches;
+        }
+
+        let needle = if options.case_sensitive {
+            query.to_string()
+        } else {
+            query.to_ascii_lowercase()
+        };
+        let mut hits = self.take_plain_hits(needle.as_str(), options.case_sensitive);
+        for &index in indices {
+            if hits.contains[index] == Some(false) {
+                continue;
+            }
+            let block = &self.blocks[index];
+            let hay = if options.case_sensitive {
+                block.text.as_str()
+            } else {
+                self.folded[index].as_str()
+            };
+            let found = hay.contains(needle.as_str());
+            hits.contains[index] = Some(found);
+            if found {
+                plain_matches_in_block(block, hay, needle.as_str(), options, &mut matches);
             }
         }
-        return matches;
+        self.plain_hits = Some(hits);
+        matches
     }
 
-    let (needle, hay_transform): (String, fn(&str) -> String) = if options.case_sensitive {
-        (query.to_string(), |s| s.to_string())
-    } else {
-        (query.to_ascii_lowercase(), |s| s.to_ascii_lowercase())
-    };
+    /// The hit table for `needle`, seeded from the previous query when `needle` extends it:
+    /// a block without the shorter text can't contain the longer one.
+    fn take_plain_hits(&mut self, needle: &str, case_sensitive: bool) -> PlainHits {
+        match self.plain_hits.take() {
+            Some(hits) if hits.case_sensitive == case_sensitive && hits.needle == needle => hits,
+            Some(hits)
+                if hits.case_sensitive == case_sensitive
+                    && needle.contains(hits.needle.as_str()) =>
+            {
+                PlainHits {
+                    needle: needle.to_string(),
+                    case_sensitive,
+                    contains: hits
+                        .contains
+                        .into_iter()
+                        .map(|known| known.filter(|found| !*found))
+                        .collect(),
+                }
+            }
+            _ => PlainHits {
+                needle: needle.to_string(),
+                case_sensitive,
+                contains: vec![None; self.blocks.len()],
+            },
+        }
+    }
+}
 
-    for block in blocks {
-        let hay = hay_transform(block.text.as_str());
-        let mut from = 0usize;
+pub fn search_document(doc: &DocumentModel, query: &str, options: SearchOptions) -> Vec<SearchMatch> {
+    if query.is_empty() {
+        return Vec::new();
+    }
 
-        while from < hay.len() {
-            let Some(rel) = hay[from..].find(needle.as_str()) else {
-                break;
-            };
-            let start = from + rel;
-            let end = start + needle.len();
+    let mut index = SearchIndex::default();
+    index.sync(doc);
+    index.search(query, options)
+}
 
-            if options.whole_word && !is_whole_word(block.text.as_str(), start, end) {
-                from = start.saturating_add(1);
+fn regex_matches_in_block(
+    block: &SearchableBlock,
+    regex: &Regex,
+    options: SearchOptions,
+    matches: &mut Vec<SearchMatch>,
+) {
+    for cap in regex.captures_iter(block.text.as_str()) {
+        if let Some(m) = cap.get(0) {
+            if options.whole_word && !is_whole_word(block.text.as_str(), m.start(), m.end()) {
                 continue;
             }
 
+            let groups = (1..cap.len())
+                .filter_map(|i| cap.get(i).map(|g| (g.start(), g.end())))
+                .collect::<Vec<_>>();
             matches.push(SearchMatch {
                 block_id: block.id,
-                start,
-                end,
+                start: m.start(),
+                end: m.end(),
                 line_or_page: block.line_or_page,
-                snippet: snippet(block.text.as_str(), start, end),
-                capture_groups: Vec::new(),
+                snippet: snippet(block.text.as_str(), m.start(), m.end()),
+                capture_groups: groups,
             });
-            from = end.max(start.saturating_add(1));
         }
     }
+}
 
-    matches
+/// Plain matches of `needle` in `hay`, the block's text already lowercased for
+/// case-insensitive searches.
+fn plain_matches_in_block(
+    block: &SearchableBlock,
+    hay: &str,
+    needle: &str,
+    options: SearchOptions,
+    matches: &mut Vec<SearchMatch>,
+) {
+    let mut from = 0usize;
+    while from < hay.len() {
+        let Some(rel) = hay[from..].find(needle) else {
+            break;
+        };
+        let start = from + rel;
+        let end = start + needle.len();
+
+        if options.whole_word && !is_whole_word(block.text.as_str(), start, end) {
+            from = start.saturating_add(1);
+            continue;
+        }
+
+        matches.push(SearchMatch {
+            block_id: block.id,
+            start,
+            end,
+            line_or_page: block.line_or_page,
+            snippet: snippet(block.text.as_str(), start, end),
+            capture_groups: Vec::new(),
+        });
+        from = end.max(start.saturating_add(1));
+    }
 }
 
 fn split_visible_and_background_blocks(
-    blocks: Vec<SearchableBlock>,
+    blocks: &[SearchableBlock],
+    range: Range<usize>,
     visible_block_ids: &[BlockId],
-) -> (Vec<SearchableBlock>, Vec<SearchableBlock>) {
-    if blocks.len() < 10_000 {
-        return (blocks, Vec::new());
+) -> (Vec<usize>, Vec<usize>) {
+    if range.len() < 10_000 {
+        return (range.collect(), Vec::new());
     }
 
     let visible_ids: HashSet<BlockId> = visible_block_ids.iter().copied().collect();
-    let mut visible_blocks = Vec::new();
-    let mut background_blocks = Vec::new();
-
     if visible_ids.is_empty() {
-        let split = blocks.len().min(512);
-        visible_blocks.extend_from_slice(&blocks[..split]);
-        background_blocks.extend_from_slice(&blocks[split..]);
-        return (visible_blocks, background_blocks);
+        let split = range.start + range.len().min(512);
+        return ((range.start..split).collect(), (split..range.end).collect());
     }
 
-    for block in blocks {
-        if visible_ids.contains(&block.id) {
-            visible_blocks.push(block);
-        } else {
-            background_blocks.push(block);
-        }
-    }
+    let (mut visible_blocks, mut background_blocks): (Vec<usize>, Vec<usize>) =
+        range.partition(|index| visible_ids.contains(&blocks[*index].id));
 
     if visible_blocks.is_empty() && !background_blocks.is_empty() {
-        let mut fallback = Vec::new();
         let split = background_blocks.len().min(512);
-        fallback.extend_from_slice(&background_blocks[..split]);
-        background_blocks = background_blocks[split..].to_vec();
-        return (fallback, background_blocks);
+        visible_blocks = background_blocks.drain(..split).collect();
     }
 
     (visible_blocks, background_blocks)
 }
 
+/// First and last block `range` touches, plus the range ordered the way the blocks are;
+/// `None` when either end is outside searchable text.
+fn selection_span(
+    blocks: &[SearchableBlock],
+    range: SelectionRange,
+) -> Option<(usize, usize, SelectionRange)> {
+    let index_of = |id: BlockId| blocks.iter().position(|block| block.id == id);
+    let first = index_of(range.start.block_id)?;
+    let last = index_of(range.end.block_id)?;
+    if (first, range.start.offset) <= (last, range.end.offset) {
+        Some((first, last, range))
+    } else {
+        let swapped = SelectionRange {
+            start: range.end,
+            end: range.start,
+        };
+        Some((last, first, swapped))
+    }
+}
+
+/// Drops matches that spill past the ends of `scope`; blocks between them are already
+/// limited to the selection.
+fn retain_matches_in_scope(matches: &mut Vec<SearchMatch>, scope: Option<SelectionRange>) {
+    let Some(scope) = scope else {
+        return;
+    };
+    matches.retain(|m| {
+        (m.block_id != scope.start.block_id || m.start >= scope.start.offset)
+            && (m.block_id != scope.end.block_id || m.end <= scope.end.offset)
+    });
+}
+
+/// Hashes the text `collect_searchable_blocks` would gather without building it, so an
+/// unchanged document is recognised without copying its text.
 fn document_fingerprint(doc: &DocumentModel) -> u64 {
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
-    for block in collect_searchable_blocks(doc) {
-        block.id.hash(&mut hasher);
-        block.line_or_page.hash(&mut hasher);
-        block.text.hash(&mut hasher);
+    for (index, block) in doc.content.iter().enumerate() {
+        hash_searchable_block(block, index + 1, &mut hasher);
     }
     hasher.finish()
 }
 
+fn hash_searchable_block<H: Hasher>(block: &Block, line_or_page: usize, hasher: &mut H) {
+    match block {
+        Block::Paragraph(p) => hash_runs(p.id, line_or_page, &p.runs, hasher),
+        Block::Heading(h) => hash_runs(h.id, line_or_page, &h.runs, hasher),
+        Block::Table(t) => {
+            for block in t
+                .rows
+                .iter()
+                .flat_map(|row| &row.cells)
+                .flat_map(|cell| &cell.blocks)
+            {
+                hash_searchable_block(block, line_or_page, hasher);
+            }
+        }
+        Block::List(l) => hash_list_items(&l.items, line_or_page, hasher),
+        Block::BlockQuote(q) => {
+            for block in &q.blocks {
+                hash_searchable_block(block, line_or_page, hasher);
+            }
+        }
+        Block::CodeBlock(code) => {
+            code.id.hash(hasher);
+            line_or_page.hash(hasher);
+            code.code.hash(hasher);
+        }
+        Block::Image(_) | Block::PageBreak | Block::HorizontalRule => {}
+    }
+}
+
+fn hash_runs<H: Hasher>(id: BlockId, line_or_page: usize, runs: &[Run], hasher: &mut H) {
+    id.hash(hasher);
+    line_or_page.hash(hasher);
+    for run in runs {
+        hasher.write(run.text.as_bytes());
+    }
+    hasher.write_u8(0xff);
+}
+
+fn hash_list_items<H: Hasher>(items: &[ListItem], line_or_page: usize, hasher: &mut H) {
+    for item in items {
+        for block in &item.content {
+            hash_searchable_block(block, line_or_page, hasher);
+        }
+        hash_list_items(&item.children, line_or_page, hasher);
+    }
+}
+
 pub fn replace_current(doc: &mut DocumentModel, state: &mut FindReplaceState) -> usize {
     let Some(current) = state.current_result().cloned() else {
         return 0;
@@ -430,7 +730,7 @@ pub fn replace_current(doc: &mut DocumentModel, state: &mut FindReplaceState) ->
         regex.as_ref(),
     ) {
         state.last_replaced_count = 1;
-        state.invalidate_cache();
+        state.document_edited();
         state.refresh_results(doc);
         1
     } else {
@@ -522,11 +822,68 @@ pub fn replace_all(doc: &mut DocumentModel, state: &mut FindReplaceState) -> usi
     }
 
     state.last_replaced_count = replaced;
-    state.invalidate_cache();
+    state.document_edited();
     state.refresh_results(doc);
     replaced
 }
 
+/// Results that fall inside `visible_ids`, paired with their index in `results` so callers can
+/// tell which one is current.
+pub fn matches_in_visible_blocks<'a>(
+    results: &'a [SearchMatch],
+    visible_ids: &[BlockId],
+) -> Vec<(usize, &'a SearchMatch)> {
+    let visible = visible_ids.iter().copied().collect::<HashSet<_>>();
+    results
+        .iter()
+        .enumerate()
+        .filter(|(_, m)| visible.contains(&m.block_id))
+        .collect()
+}
+
+/// Match or replacement counts for several documents, in the order they were visited.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiDocumentCounts {
+    pub per_document: Vec<usize>,
+}
+
+impl MultiDocumentCounts {
+    pub fn total(&self) -> usize {
+        self.per_document.iter().sum()
+    }
+
+    pub fn documents_with_matches(&self) -> usize {
+        self.per_document.iter().filter(|count| **count > 0).count()
+    }
+}
+
+pub fn count_matches_in_documents<'a, I>(docs: I, query: &str, options: SearchOptions) -> MultiDocumentCounts
+where
+    I: IntoIterator<Item = &'a DocumentModel>,
+{
+    MultiDocumentCounts {
+        per_document: docs
+            .into_iter()
+            .map(|doc| search_document(doc, query, options).len())
+            .collect(),
+    }
+}
+
+/// Runs [`replace_all`] over every document. `state.results` afterwards describe the last
+/// document visited, so callers should refresh them for whichever one is active.
+pub fn replace_all_in_documents<'a, I>(docs: I, state: &mut FindReplaceState) -> MultiDocumentCounts
+where
+    I: IntoIterator<Item = &'a mut DocumentModel>,
+{
+    let per_document = docs
+        .into_iter()
+        .map(|doc| replace_all(doc, state))
+        .collect::<Vec<_>>();
+    let counts = MultiDocumentCounts { per_document };
+    state.last_replaced_count = counts.total();
+    counts
+}
+
 pub fn replacement_preview(current: &SearchMatch, replacement: &str) -> String {
     format!("{} -> {}", current.snippet, replacement)
 }
@@ -547,21 +904,15 @@ fn collect_searchable_blocks(doc: &DocumentModel) -> Vec<SearchableBlock> {
 }
 
 fn collect_block(block: &Block, line_or_page: usize, out: &mut Vec<SearchableBlock>) {
+    if let Some((id, text)) = leaf_text(block) {
+        out.push(SearchableBlock {
+            id,
+            line_or_page,
+            text,
+        });
+        return;
+    }
     match block {
-        Block::Paragraph(p) => {
-            out.push(SearchableBlock {
-                id: p.id,
-                line_or_page,
-                text: paragraph_text(p),
-            });
-        }
-        Block::Heading(h) => {
-            out.push(SearchableBlock {
-                id: h.id,
-                line_or_page,
-                text: heading_text(h),
-            });
-        }
         Block::Table(t) => collect_table(t, line_or_page, out),
         Block::List(l) => collect_list(l, line_or_page, out),
         Block::BlockQuote(q) => {
@@ -569,15 +920,65 @@ fn collect_block(block: &Block, line_or_page: usize, out: &mut Vec<SearchableBlo
                 collect_block(block, line_or_page, out);
             }
         }
-        Block::CodeBlock(code) => {
-            out.push(SearchableBlock {
-                id: code.id,
-                line_or_page,
-                text: code.code.clone(),
-            });
+        _ => {}
+    }
+}
+
+/// The text of a paragraph, heading or code block with its id; `None` for blocks that only
+/// hold other blocks or have no text.
+fn leaf_text(block: &Block) -> Option<(BlockId, String)> {
+    match block {
+        Block::Paragraph(p) => Some((p.id, paragraph_text(p))),
+        Block::Heading(h) => Some((h.id, heading_text(h))),
+        Block::CodeBlock(code) => Some((code.id, code.code.clone())),
+        _ => None,
+    }
+}
+
+/// Appends the plain text of `block` to `out`: a line per paragraph, heading and code block,
+/// and for tables a line per row with a space after each cell. Indexed searches read the
+/// same text per block; the screen reader view reads it whole.
+pub fn append_block_text(block: &Block, out: &mut String) {
+    if let Some((_, text)) = leaf_text(block) {
+        out.push_str(text.as_str());
+        out.push('\n');
+        return;
+    }
+    match block {
+        Block::List(list) => {
+            for item in &list.items {
+                for nested in &item.content {
+                    append_block_text(nested, out);
+                }
+            }
         }
-        Block::Image(_) | Block::PageBreak | Block::HorizontalRule => {}
+        Block::Table(table) => {
+            for row in &table.rows {
+                for cell in &row.cells {
+                    for nested in &cell.blocks {
+                        append_block_text(nested, out);
+                    }
+                    out.push(' ');
+                }
+                out.push('\n');
+            }
+        }
+        Block::BlockQuote(quote) => {
+            for nested in &quote.blocks {
+                append_block_text(nested, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// The whole document as plain text, built with [`append_block_text`].
+pub fn collect_document_plain_text(document: &DocumentModel) -> String {
+    let mut out = String::new();
+    for block in &document.content {
+        append_block_text(block, &mut out);
     }
+    out
 }
 
 fn collect_table(table: &Table, line_or_page: usize, out: &mut Vec<SearchableBlock>) {
@@ -625,6 +1026,18 @@ fn build_regex(query: &str, options: SearchOptions) -> Result<Regex, regex::Erro
     builder.build()
 }
 
+/// The one-line reason from a regex error; syntax errors otherwise span several lines that
+/// point a caret at the pattern.
+fn regex_error_message(error: &regex::Error) -> String {
+    let text = error.to_string();
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("error: "))
+        .or_else(|| text.lines().next())
+        .unwrap_or("invalid pattern")
+        .trim()
+        .to_string()
+}
+
 fn is_whole_word(text: &str, start: usize, end: usize) -> bool {
     let before_ok = if start == 0 {
         true
@@ -839,6 +1252,8 @@ mod tests {
             spacing: crate::document::model::ParagraphSpacing::default(),
             indent: crate::document::model::Indent::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         })
     }
 
@@ -973,6 +1388,53 @@ mod tests {
         assert_eq!(paragraph_text_by_id(&doc, BlockId(1)), "123/abc 456/def");
     }
 
+    #[test]
+    fn visible_filter_keeps_matches_in_visible_blocks_with_indices() {
+        let doc = doc_with_blocks(vec![
+            paragraph_block(1, "alpha"),
+            paragraph_block(2, "alpha alpha"),
+            paragraph_block(3, "alpha"),
+        ]);
+        let results = search_document(&doc, "alpha", SearchOptions::default());
+        assert_eq!(results.len(), 4);
+
+        let visible = matches_in_visible_blocks(&results, &[BlockId(3), BlockId(2)]);
+        let indices = visible.iter().map(|(idx, _)| *idx).collect::<Vec<_>>();
+        assert_eq!(indices, vec![1, 2, 3]);
+        assert!(visible.iter().all(|(_, m)| m.block_id != BlockId(1)));
+        assert!(matches_in_visible_blocks(&results, &[]).is_empty());
+    }
+
+    #[test]
+    fn replace_across_documents_aggregates_per_document_counts() {
+        let mut docs = [
+            doc_with_blocks(vec![paragraph_block(1, "Cat cat catalog")]),
+            doc_with_blocks(vec![paragraph_block(1, "dog only")]),
+            doc_with_blocks(vec![heading_block(1, "The cat")]),
+        ];
+        let mut state = FindReplaceState {
+            query: "cat".to_string(),
+            replacement: "fox".to_string(),
+            options: SearchOptions {
+                case_sensitive: false,
+                whole_word: true,
+                regex: false,
+            },
+            ..FindReplaceState::default()
+        };
+
+        let preview = count_matches_in_documents(docs.iter(), state.query.as_str(), state.options);
+        assert_eq!(preview.per_document, vec![2, 0, 1]);
+
+        let replaced = replace_all_in_documents(docs.iter_mut(), &mut state);
+        assert_eq!(replaced, preview);
+        assert_eq!(replaced.total(), 3);
+        assert_eq!(replaced.documents_with_matches(), 2);
+        assert_eq!(state.last_replaced_count, 3);
+        assert_eq!(paragraph_text_by_id(&docs[0], BlockId(1)), "fox fox catalog");
+        assert_eq!(paragraph_text_by_id(&docs[1], BlockId(1)), "dog only");
+    }
+
     #[test]
     fn replace_current_supports_regex_backreferences() {
         let mut doc = doc_with_blocks(vec![paragraph_block(1, "foo-10 bar-20")]);
@@ -1022,6 +1484,228 @@ mod tests {
         assert!(!state.has_pending_background_search());
     }
 
+    #[test]
+    fn match_in_selection_keeps_only_matches_inside_the_selection() {
+        use crate::editor::cursor::CursorPosition;
+
+        let doc = doc_with_blocks(vec![
+            paragraph_block(1, "cat one cat"),
+            paragraph_block(2, "cat two"),
+            paragraph_block(3, "cat three cat"),
+            paragraph_block(4, "cat four"),
+        ]);
+        let at = |id, offset| CursorPosition {
+            block_id: BlockId(id),
+            offset,
+        };
+        // Selected backwards from inside block 3 to the middle of block 1.
+        let mut state = FindReplaceState {
+            query: "cat".to_string(),
+            in_selection: true,
+            selection: Some(SelectionRange {
+                start: at(3, 9),
+                end: at(1, 4),
+            }),
+            ..FindReplaceState::default()
+        };
+
+        state.refresh_results(&doc);
+        let found = state
+            .results
+            .iter()
+            .map(|m| (m.block_id.0, m.start))
+            .collect::<Vec<_>>();
+        assert_eq!(found, vec![(1, 8), (2, 0), (3, 0)]);
+
+        // Turning the option off searches the whole document again.
+        state.in_selection = false;
+        assert_eq!(state.refresh_results(&doc), 6);
+    }
+
+    #[test]
+    fn find_options_round_trip_through_settings() {
+        let mut state = FindReplaceState::default();
+        let saved = FindSettings {
+            case_sensitive: true,
+            whole_word: false,
+            regex: true,
+            in_selection: true,
+            debounce_ms: 250,
+        };
+        state.apply_settings(saved);
+        assert!(state.options.case_sensitive && state.options.regex && state.in_selection);
+        assert_eq!(state.debounce_ms, 250);
+        assert_eq!(state.settings(), saved);
+
+        let json = serde_json::to_string(&saved).expect("serialize");
+        assert_eq!(
+            serde_json::from_str::<FindSettings>(&json).expect("parse"),
+            saved
+        );
+        // Settings files written before these options existed fall back to the defaults.
+        let older = serde_json::from_str::<FindSettings>(r#"{"regex":true}"#).expect("parse");
+        assert_eq!(
+            older,
+            FindSettings {
+                regex: true,
+                ..FindSettings::default()
+            }
+        );
+    }
+
+    #[test]
+    fn invalid_regex_reports_the_error_until_the_pattern_is_fixed() {
+        let doc = doc_with_blocks(vec![paragraph_block(1, "abc (abc) abd")]);
+        let mut state = FindReplaceState {
+            query: "(ab".to_string(),
+            options: SearchOptions {
+                case_sensitive: false,
+                whole_word: false,
+                regex: true,
+            },
+            ..FindReplaceState::default()
+        };
+
+        assert_eq!(state.refresh_results(&doc), 0);
+        assert!(state.results.is_empty());
+        assert_eq!(state.regex_error(), Some("unclosed group"));
+        assert_eq!(state.result_count_text, "Invalid regex: unclosed group");
+
+        state.set_query("\\(ab".to_string());
+        assert_eq!(state.refresh_results(&doc), 1);
+        assert_eq!(state.regex_error(), None);
+        assert_eq!(state.result_count_text, "1 results for '\\(ab'");
+
+        // The same text is fine as a plain search.
+        state.set_query("(ab".to_string());
+        state.options.regex = false;
+        assert_eq!(state.refresh_results(&doc), 1);
+        assert_eq!(state.regex_error(), None);
+    }
+
+    fn next_random(seed: &mut u64) -> u64 {
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 7;
+        *seed ^= *seed << 17;
+        *seed
+    }
+
+    fn random_text(seed: &mut u64) -> String {
+        let len = (next_random(seed) % 40) as usize;
+        (0..len)
+            .map(|_| ['a', 'b', 'A', 'B', ' ', '-'][(next_random(seed) % 6) as usize])
+            .collect()
+    }
+
+    /// A straight scan of every block, independent of the index and its narrowing.
+    fn naive_matches(
+        doc: &DocumentModel,
+        query: &str,
+        options: SearchOptions,
+    ) -> Vec<(BlockId, usize, usize)> {
+        let mut out = Vec::new();
+        for block in collect_searchable_blocks(doc) {
+            let (hay, needle) = if options.case_sensitive {
+                (block.text.clone(), query.to_string())
+            } else {
+                (block.text.to_ascii_lowercase(), query.to_ascii_lowercase())
+            };
+            let mut at = 0;
+            while at + needle.len() <= hay.len() {
+                let end = at + needle.len();
+                if hay[at..].starts_with(needle.as_str())
+                    && (!options.whole_word || is_whole_word(block.text.as_str(), at, end))
+                {
+                    out.push((block.id, at, end));
+                    at = end;
+                } else {
+                    at += 1;
+                }
+            }
+        }
+        out
+    }
+
+    #[test]
+    fn index_matches_a_naive_scan_on_generated_documents() {
+        let mut seed = 0x9e37_79b9_7f4a_7c15u64;
+        for _ in 0..20 {
+            let mut next_id = 1u64;
+            let mut blocks = Vec::new();
+            for _ in 0..60 {
+                next_id += 3;
+                let paragraph = paragraph_block(next_id, &random_text(&mut seed));
+                blocks.push(match next_random(&mut seed) % 4 {
+                    0 => table_block(next_id + 1, paragraph),
+                    1 => list_block(next_id + 2, paragraph),
+                    _ => paragraph,
+                });
+            }
+            let mut doc = doc_with_blocks(blocks);
+            let mut index = SearchIndex::default();
+            index.sync(&doc);
+
+            // Queries grow and shrink as typing would, so the narrowing gets exercised.
+            let typed = random_text(&mut seed);
+            let mut queries = (1..=typed.len().min(6))
+                .map(|len| typed[..len].to_string())
+                .collect::<Vec<_>>();
+            queries.extend(["a", "ab", "b", "ab", "Ab", "a b"].map(str::to_string));
+            for query in queries.iter().filter(|query| !query.trim().is_empty()) {
+                for (case_sensitive, whole_word) in
+                    [(false, false), (true, false), (false, true), (true, true)]
+                {
+                    let options = SearchOptions {
+                        case_sensitive,
+                        whole_word,
+                        regex: false,
+                    };
+                    let found = index
+                        .search(query, options)
+                        .iter()
+                        .map(|m| (m.block_id, m.start, m.end))
+                        .collect::<Vec<_>>();
+                    assert_eq!(
+                        found,
+                        naive_matches(&doc, query, options),
+                        "query {query:?} {options:?}"
+                    );
+                }
+            }
+
+            let regex_options = SearchOptions {
+                case_sensitive: false,
+                whole_word: false,
+                regex: true,
+            };
+            let regex = build_regex("a+b", regex_options).expect("valid pattern");
+            let expected = collect_searchable_blocks(&doc)
+                .iter()
+                .flat_map(|block| {
+                    regex
+                        .find_iter(block.text.as_str())
+                        .map(|m| (block.id, m.start(), m.end()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>();
+            let found = index
+                .search("a+b", regex_options)
+                .iter()
+                .map(|m| (m.block_id, m.start, m.end))
+                .collect::<Vec<_>>();
+            assert_eq!(found, expected);
+
+            // An edit changes the fingerprint, so the next sync picks up the new text.
+            doc.content.push(paragraph_block(next_id + 10, "ab ab"));
+            index.sync(&doc);
+            let options = SearchOptions::default();
+            assert_eq!(
+                index.search("ab", options).len(),
+                naive_matches(&doc, "ab", options).len()
+            );
+        }
+    }
+
     #[test]
     fn refresh_uses_cache_until_invalidated() {
         let doc = doc_with_blocks(vec![paragraph_block(1, "alpha beta alpha")]);
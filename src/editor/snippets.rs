@@ -0,0 +1,272 @@
+use chrono::{Datelike, NaiveDateTime, Timelike};
+use windows::{
+    Win32::Globalization::{
+        GetLocaleInfoEx, LOCALE_S1159, LOCALE_S2359, LOCALE_SABBREVDAYNAME1,
+        LOCALE_SABBREVMONTHNAME1, LOCALE_SDAYNAME1, LOCALE_SLONGDATE, LOCALE_SMONTHNAME1,
+        LOCALE_SSHORTDATE, LOCALE_SSHORTTIME,
+    },
+    core::PCWSTR,
+};
+
+use crate::settings::schema::{DateTimeFormat, Snippet};
+
+/// Date and time pictures plus the month/day names of a locale, in Windows picture syntax
+/// (`d`, `MMMM`, `yyyy`, `h`, `HH`, `mm`, `tt`, `'literal'`...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocaleFormats {
+    pub short_date: String,
+    pub long_date: String,
+    pub short_time: String,
+    pub month_names: [String; 12],
+    pub month_abbreviations: [String; 12],
+    /// Monday first, as Windows orders them.
+    pub day_names: [String; 7],
+    pub day_abbreviations: [String; 7],
+    pub am: String,
+    pub pm: String,
+}
+
+impl Default for LocaleFormats {
+    fn default() -> Self {
+        Self {
+            short_date: "M/d/yyyy".to_string(),
+            long_date: "dddd, MMMM d, yyyy".to_string(),
+            short_time: "h:mm tt".to_string(),
+            month_names: [
+                "January", "February", "March", "April", "May", "June", "July", "August",
+                "September", "October", "November", "December",
+            ]
+            .map(str::to_string),
+            month_abbreviations: [
+                "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+            ]
+            .map(str::to_string),
+            day_names: ["Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday"]
+                .map(str::to_string),
+            day_abbreviations: ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"].map(str::to_string),
+            am: "AM".to_string(),
+            pm: "PM".to_string(),
+        }
+    }
+}
+
+impl LocaleFormats {
+    /// Formats of the user's default locale; anything Windows can't report keeps the en-US value.
+    pub fn system() -> Self {
+        let mut formats = Self::default();
+        let mut read = |lctype: u32, target: &mut String| {
+            if let Some(value) = locale_info(lctype) {
+                *target = value;
+            }
+        };
+        read(LOCALE_SSHORTDATE, &mut formats.short_date);
+        read(LOCALE_SLONGDATE, &mut formats.long_date);
+        read(LOCALE_SSHORTTIME, &mut formats.short_time);
+        read(LOCALE_S1159, &mut formats.am);
+        read(LOCALE_S2359, &mut formats.pm);
+        for (index, name) in formats.month_names.iter_mut().enumerate() {
+            read(LOCALE_SMONTHNAME1 + index as u32, name);
+        }
+        for (index, name) in formats.month_abbreviations.iter_mut().enumerate() {
+            read(LOCALE_SABBREVMONTHNAME1 + index as u32, name);
+        }
+        for (index, name) in formats.day_names.iter_mut().enumerate() {
+            read(LOCALE_SDAYNAME1 + index as u32, name);
+        }
+        for (index, name) in formats.day_abbreviations.iter_mut().enumerate() {
+            read(LOCALE_SABBREVDAYNAME1 + index as u32, name);
+        }
+        formats
+    }
+}
+
+fn locale_info(lctype: u32) -> Option<String> {
+    // A null locale name means LOCALE_NAME_USER_DEFAULT.
+    let len = unsafe { GetLocaleInfoEx(PCWSTR::null(), lctype, None) };
+    if len <= 1 {
+        return None;
+    }
+    let mut buffer = vec![0u16; len as usize];
+    let written = unsafe { GetLocaleInfoEx(PCWSTR::null(), lctype, Some(&mut buffer)) };
+    if written <= 1 {
+        return None;
+    }
+    Some(String::from_utf16_lossy(&buffer[..written as usize - 1]))
+}
+
+/// Text for "Insert date/time" at `at` in the given format.
+pub fn format_date_time(format: DateTimeFormat, at: &NaiveDateTime, locale: &LocaleFormats) -> String {
+    match format {
+        DateTimeFormat::ShortDate => format_picture(&locale.short_date, at, locale),
+        DateTimeFormat::LongDate => format_picture(&locale.long_date, at, locale),
+        DateTimeFormat::Time => format_picture(&locale.short_time, at, locale),
+        DateTimeFormat::ShortDateTime => format!(
+            "{} {}",
+            format_picture(&locale.short_date, at, locale),
+            format_picture(&locale.short_time, at, locale)
+        ),
+        DateTimeFormat::Iso8601 => at.format("%Y-%m-%dT%H:%M").to_string(),
+    }
+}
+
+/// Expands a Windows date/time picture. `M` is month and `m` minutes, as in Windows;
+/// quoted text is copied verbatim and unknown letters pass through.
+pub fn format_picture(picture: &str, at: &NaiveDateTime, locale: &LocaleFormats) -> String {
+    let chars = picture.chars().collect::<Vec<_>>();
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let ch = chars[i];
+        if ch == '\'' {
+            i += 1;
+            while i < chars.len() {
+                if chars[i] == '\'' {
+                    // '' inside quotes is a literal apostrophe.
+                    if chars.get(i + 1) == Some(&'\'') {
+                        out.push('\'');
+                        i += 2;
+                        continue;
+                    }
+                    break;
+                }
+                out.push(chars[i]);
+                i += 1;
+            }
+            i += 1;
+            continue;
+        }
+
+        let run = chars[i..].iter().take_while(|c| **c == ch).count();
+        i += run;
+        let hour12 = match at.hour() % 12 {
+            0 => 12,
+            hour => hour,
+        };
+        match ch {
+            'd' => match run {
+                1 => out.push_str(&at.day().to_string()),
+                2 => out.push_str(&format!("{:02}", at.day())),
+                3 => out.push_str(&locale.day_abbreviations[at.weekday().num_days_from_monday() as usize]),
+                _ => out.push_str(&locale.day_names[at.weekday().num_days_from_monday() as usize]),
+            },
+            'M' => match run {
+                1 => out.push_str(&at.month().to_string()),
+                2 => out.push_str(&format!("{:02}", at.month())),
+                3 => out.push_str(&locale.month_abbreviations[at.month0() as usize]),
+                _ => out.push_str(&locale.month_names[at.month0() as usize]),
+            },
+            'y' => match run {
+                1 => out.push_str(&(at.year() % 100).to_string()),
+                2 => out.push_str(&format!("{:02}", at.year() % 100)),
+                _ => out.push_str(&at.year().to_string()),
+            },
+            'h' => push_number(&mut out, hour12, run),
+            'H' => push_number(&mut out, at.hour(), run),
+            'm' => push_number(&mut out, at.minute(), run),
+            's' => push_number(&mut out, at.second(), run),
+            't' => {
+                let marker = if at.hour() < 12 { &locale.am } else { &locale.pm };
+                if run == 1 {
+                    out.extend(marker.chars().take(1));
+                } else {
+                    out.push_str(marker);
+                }
+            }
+            // Era designators; the Gregorian calendar doesn't need one.
+            'g' => {}
+            _ => (0..run).for_each(|_| out.push(ch)),
+        }
+    }
+    out
+}
+
+fn push_number(out: &mut String, value: u32, run: usize) {
+    if run >= 2 {
+        out.push_str(&format!("{value:02}"));
+    } else {
+        out.push_str(&value.to_string());
+    }
+}
+
+/// Characters that end an abbreviation and trigger its expansion.
+pub fn is_expansion_trigger(ch: char) -> bool {
+    ch == ' ' || matches!(ch, '.' | ',' | ';' | ':' | '!' | '?' | ')')
+}
+
+/// The snippet whose abbreviation ends `text_before_cursor` as a whole word, preferring the
+/// longest match. Abbreviations are case-sensitive.
+pub fn match_abbreviation<'a>(text_before_cursor: &str, snippets: &'a [Snippet]) -> Option<&'a Snippet> {
+    snippets
+        .iter()
+        .filter(|snippet| !snippet.abbreviation.is_empty())
+        .filter(|snippet| {
+            let Some(prefix) = text_before_cursor.strip_suffix(snippet.abbreviation.as_str()) else {
+                return false;
+            };
+            !prefix
+                .chars()
+                .next_back()
+                .is_some_and(|ch| ch.is_alphanumeric() || ch == '_')
+        })
+        .max_by_key(|snippet| snippet.abbreviation.chars().count())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 3, 7)
+            .and_then(|date| date.and_hms_opt(hour, minute, 5))
+            .expect("valid date")
+    }
+
+    fn snippet(abbreviation: &str, text: &str) -> Snippet {
+        Snippet {
+            abbreviation: abbreviation.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn date_formats_follow_locale_pictures() {
+        let us = LocaleFormats::default();
+        assert_eq!(format_date_time(DateTimeFormat::ShortDate, &at(9, 4), &us), "3/7/2026");
+        assert_eq!(
+            format_date_time(DateTimeFormat::LongDate, &at(9, 4), &us),
+            "Saturday, March 7, 2026"
+        );
+        assert_eq!(format_date_time(DateTimeFormat::Time, &at(0, 4), &us), "12:04 AM");
+        assert_eq!(
+            format_date_time(DateTimeFormat::ShortDateTime, &at(15, 30), &us),
+            "3/7/2026 3:30 PM"
+        );
+        assert_eq!(format_date_time(DateTimeFormat::Iso8601, &at(15, 30), &us), "2026-03-07T15:30");
+
+        let german = LocaleFormats {
+            short_date: "dd.MM.yyyy".to_string(),
+            long_date: "dddd, d. MMMM yyyy".to_string(),
+            short_time: "HH:mm".to_string(),
+            ..LocaleFormats::default()
+        };
+        assert_eq!(format_date_time(DateTimeFormat::ShortDate, &at(9, 4), &german), "07.03.2026");
+        assert_eq!(format_date_time(DateTimeFormat::Time, &at(9, 4), &german), "09:04");
+        assert_eq!(format_picture("yy'-''Q'''", &at(9, 4), &german), "26-'Q'");
+    }
+
+    #[test]
+    fn abbreviations_expand_only_as_whole_words() {
+        let snippets = vec![snippet("addr", "1 Main St"), snippet("sig", "Regards"), snippet("xsig", "Cheers")];
+        assert_eq!(match_abbreviation("Send to addr", &snippets).map(|s| s.text.as_str()), Some("1 Main St"));
+        assert_eq!(match_abbreviation("sig", &snippets).map(|s| s.text.as_str()), Some("Regards"));
+        // "xsig" wins over its "sig" suffix, and "mysig" is a different word.
+        assert_eq!(match_abbreviation("(xsig", &snippets).map(|s| s.text.as_str()), Some("Cheers"));
+        assert_eq!(match_abbreviation("mysig", &snippets), None);
+        assert_eq!(match_abbreviation("Sig", &snippets), None);
+        assert_eq!(match_abbreviation("", &[snippet("", "never")]), None);
+        assert!(is_expansion_trigger(' ') && is_expansion_trigger(','));
+        assert!(!is_expansion_trigger('a'));
+    }
+}
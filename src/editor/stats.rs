@@ -0,0 +1,246 @@
+use crate::{
+    document::model::{Block, BlockId},
+    editor::cursor::SelectionRange,
+};
+
+/// Silent reading speed the reading-time estimate assumes.
+pub const READING_WORDS_PER_MINUTE: usize = 230;
+
+/// Counts over some stretch of a document. Lines are hard lines of text, not the wrapped
+/// lines on the page; paragraphs are the text blocks that aren't blank.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TextStats {
+    pub words: usize,
+    pub characters: usize,
+    pub lines: usize,
+    pub paragraphs: usize,
+}
+
+impl TextStats {
+    /// Whole minutes to read the words, rounded up; zero only when there are no words.
+    pub fn reading_minutes(&self) -> usize {
+        self.words.div_ceil(READING_WORDS_PER_MINUTE)
+    }
+
+    fn add_text(&mut self, text: &str) {
+        self.characters += text.chars().count();
+        self.words += text.split_whitespace().count();
+        self.lines += text.split('\n').count();
+        if !text.trim().is_empty() {
+            self.paragraphs += 1;
+        }
+    }
+
+    fn add_block(&mut self, block: &Block) {
+        match block {
+            Block::Paragraph(p) => {
+                self.add_text(&p.runs.iter().map(|r| r.text.as_str()).collect::<String>());
+            }
+            Block::Heading(h) => {
+                self.add_text(&h.runs.iter().map(|r| r.text.as_str()).collect::<String>());
+            }
+            Block::CodeBlock(c) => self.add_text(c.code.trim_end_matches('\n')),
+            Block::List(list) => {
+                for item in &list.items {
+                    for nested in &item.content {
+                        self.add_block(nested);
+                    }
+                }
+            }
+            Block::Table(table) => {
+                for row in &table.rows {
+                    for cell in &row.cells {
+                        for nested in &cell.blocks {
+                            self.add_block(nested);
+                        }
+                    }
+                }
+            }
+            Block::BlockQuote(q) => {
+                for nested in &q.blocks {
+                    self.add_block(nested);
+                }
+            }
+            Block::Image(_) | Block::PageBreak | Block::HorizontalRule => {}
+        }
+    }
+}
+
+/// Stats for every block, descending into lists, tables and quotes.
+pub fn collect_document_stats(blocks: &[Block]) -> TextStats {
+    let mut stats = TextStats::default();
+    for block in blocks {
+        stats.add_block(block);
+    }
+    stats
+}
+
+/// Stats for the text `selection` covers. The first and last blocks count only from and
+/// up to the selection's offsets; whole blocks in between count as they would for the
+/// document. An empty selection, or one whose ends aren't in `blocks`, counts nothing.
+pub fn collect_selection_stats(blocks: &[Block], selection: SelectionRange) -> TextStats {
+    let mut stats = TextStats::default();
+    let (Some(a), Some(b)) = (
+        block_index(blocks, selection.start.block_id),
+        block_index(blocks, selection.end.block_id),
+    ) else {
+        return stats;
+    };
+    // Order by position, not id: ids aren't guaranteed to follow the document.
+    let (start, end) = if (a, selection.start.offset) <= (b, selection.end.offset) {
+        ((a, selection.start.offset), (b, selection.end.offset))
+    } else {
+        ((b, selection.end.offset), (a, selection.start.offset))
+    };
+    if start == end {
+        return stats;
+    }
+
+    for (index, block) in blocks.iter().enumerate().take(end.0 + 1).skip(start.0) {
+        let Some(text) = block_text(block) else {
+            stats.add_block(block);
+            continue;
+        };
+        let from = if index == start.0 { start.1 } else { 0 };
+        let to = if index == end.0 { end.1 } else { usize::MAX };
+        // A selection that ends at the very start of a block doesn't reach into it.
+        if index != start.0 && to == 0 {
+            continue;
+        }
+        let part = text
+            .chars()
+            .skip(from)
+            .take(to.saturating_sub(from))
+            .collect::<String>();
+        stats.add_text(&part);
+    }
+    stats
+}
+
+fn block_text(block: &Block) -> Option<String> {
+    match block {
+        Block::Paragraph(p) => Some(p.runs.iter().map(|r| r.text.as_str()).collect()),
+        Block::Heading(h) => Some(h.runs.iter().map(|r| r.text.as_str()).collect()),
+        Block::CodeBlock(c) => Some(c.code.trim_end_matches('\n').to_string()),
+        _ => None,
+    }
+}
+
+fn block_index(blocks: &[Block], id: BlockId) -> Option<usize> {
+    blocks.iter().position(|block| match block {
+        Block::Paragraph(p) => p.id == id,
+        Block::Heading(h) => h.id == id,
+        Block::CodeBlock(c) => c.id == id,
+        Block::Image(image) => image.id == id,
+        Block::Table(table) => table.id == id,
+        Block::BlockQuote(quote) => quote.id == id,
+        Block::List(_) | Block::PageBreak | Block::HorizontalRule => false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        document::model::{CodeBlock, Heading, Paragraph, Run},
+        editor::cursor::CursorPosition,
+    };
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: Default::default(),
+            spacing: Default::default(),
+            indent: Default::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    fn document() -> Vec<Block> {
+        vec![
+            Block::Heading(Heading {
+                id: BlockId(1),
+                level: 1,
+                runs: vec![Run {
+                    text: "Field notes".to_string(),
+                    ..Run::default()
+                }],
+            }),
+            paragraph(2, "The quick brown fox"),
+            paragraph(3, ""),
+            paragraph(4, "jumps over the lazy dog"),
+            Block::CodeBlock(CodeBlock {
+                id: BlockId(5),
+                language: None,
+                code: "let a = 1;\nlet b = 2;\n".to_string(),
+            }),
+        ]
+    }
+
+    fn select(start: (u64, usize), end: (u64, usize)) -> SelectionRange {
+        let at = |(id, offset): (u64, usize)| CursorPosition {
+            block_id: BlockId(id),
+            offset,
+        };
+        SelectionRange {
+            start: at(start),
+            end: at(end),
+        }
+    }
+
+    #[test]
+    fn document_stats_count_every_text_block() {
+        let stats = collect_document_stats(&document());
+        assert_eq!(stats.words, 2 + 4 + 5 + 8);
+        assert_eq!(stats.characters, 11 + 19 + 23 + 21);
+        // One line per paragraph and heading, two for the code block.
+        assert_eq!(stats.lines, 6);
+        // The blank paragraph isn't a paragraph.
+        assert_eq!(stats.paragraphs, 4);
+        assert_eq!(stats.reading_minutes(), 1);
+        assert_eq!(TextStats::default().reading_minutes(), 0);
+    }
+
+    #[test]
+    fn selection_stats_cut_the_first_and_last_paragraphs_at_the_offsets() {
+        let doc = document();
+        // From "brown" in the first paragraph to the end of "over" in the second.
+        let stats = collect_selection_stats(&doc, select((2, 10), (4, 10)));
+        assert_eq!(stats.words, 2 + 2);
+        assert_eq!(stats.characters, "brown fox".len() + "jumps over".len());
+        assert_eq!(stats.lines, 3);
+        assert_eq!(stats.paragraphs, 2);
+
+        // Dragging backwards selects the same text.
+        assert_eq!(
+            collect_selection_stats(&doc, select((4, 10), (2, 10))),
+            stats
+        );
+
+        // Splitting a word counts each half as a word.
+        let inside = collect_selection_stats(&doc, select((2, 6), (2, 12)));
+        assert_eq!((inside.words, inside.characters), (2, 6));
+        assert_eq!(inside.paragraphs, 1);
+
+        // Ending at the start of the code block doesn't pull it in.
+        let up_to_code = collect_selection_stats(&doc, select((1, 0), (5, 0)));
+        assert_eq!(up_to_code.words, 2 + 4 + 5);
+        assert_eq!(up_to_code.lines, 4);
+
+        // Ending partway through the code block counts the lines it reaches.
+        let into_code = collect_selection_stats(&doc, select((4, 19), (5, 14)));
+        assert_eq!(into_code.words, 1 + 5);
+        assert_eq!(into_code.lines, 1 + 2);
+
+        assert_eq!(
+            collect_selection_stats(&doc, select((2, 4), (2, 4))),
+            TextStats::default()
+        );
+    }
+}
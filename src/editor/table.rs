@@ -1,7 +1,15 @@
-use crate::document::model::{
-    Block, BlockId, DocumentModel, Table, TableBorders, TableCell, TableRow, TableStylePreset,
+use crate::{
+    document::model::{
+        Block, BlockId, DocumentModel, Indent, ListItem, Paragraph, ParagraphAlignment,
+        ParagraphSpacing, Run, RunStyle, Table, TableBorders, TableCell, TableRow,
+        TableStylePreset,
+    },
+    editor::commands::{EditCommand, TableOp},
 };
 
+/// Shortest a row can be resized to and still show a line of text.
+pub const MIN_ROW_HEIGHT: f32 = 18.0;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct CellPos {
     pub row: usize,
@@ -15,6 +23,17 @@ pub struct TableSelection {
 }
 
 impl TableSelection {
+    /// Every cell of `table`, as Ctrl+A selects it.
+    pub fn whole(table: &Table) -> TableSelection {
+        TableSelection {
+            start: CellPos { row: 0, col: 0 },
+            end: CellPos {
+                row: table.rows.len().saturating_sub(1),
+                col: table.column_widths.len().saturating_sub(1),
+            },
+        }
+    }
+
     pub fn normalized(&self) -> TableSelection {
         TableSelection {
             start: CellPos {
@@ -84,11 +103,13 @@ pub fn insert_table(doc: &mut DocumentModel, at_index: usize, rows: usize, cols:
         cell_padding: 4.0,
         header_row: false,
         alternating_rows: false,
+        header_repeat: false,
+        caption: None,
     };
 
     let idx = at_index.min(doc.content.len());
     doc.content.insert(idx, Block::Table(table));
-    doc.dirty = true;
+    doc.mark_edited();
     next_id
 }
 
@@ -123,6 +144,87 @@ pub fn delete_row(table: &mut Table, row: usize) -> bool {
     }
 }
 
+/// Applies `op` to `table` and returns the op that reverts it, or `None` if it didn't apply.
+pub fn apply_table_op(table: &mut Table, op: &TableOp) -> Option<TableOp> {
+    match op {
+        TableOp::InsertRow { at } => {
+            let at = (*at).min(table.rows.len());
+            insert_row(table, at);
+            Some(TableOp::DeleteRow { row: at })
+        }
+        TableOp::DeleteRow { row } => {
+            ensure_row_heights(table);
+            if *row >= table.rows.len() {
+                return None;
+            }
+            let height = table.row_heights.remove(*row);
+            Some(TableOp::RestoreRow {
+                at: *row,
+                row: table.rows.remove(*row),
+                height,
+            })
+        }
+        TableOp::RestoreRow { at, row, height } => {
+            ensure_row_heights(table);
+            let at = (*at).min(table.rows.len());
+            table.rows.insert(at, row.clone());
+            table.row_heights.insert(at, *height);
+            Some(TableOp::DeleteRow { row: at })
+        }
+        TableOp::InsertColumn { at } => {
+            let at = (*at).min(table.column_widths.len());
+            insert_column(table, at);
+            Some(TableOp::DeleteColumn { col: at })
+        }
+        TableOp::DeleteColumn { col } => {
+            let width = *table.column_widths.get(*col)?;
+            table.column_widths.remove(*col);
+            let cells = table
+                .rows
+                .iter_mut()
+                .map(|row| (*col < row.cells.len()).then(|| row.cells.remove(*col)))
+                .collect();
+            Some(TableOp::RestoreColumn {
+                at: *col,
+                cells,
+                width,
+            })
+        }
+        TableOp::RestoreColumn { at, cells, width } => {
+            let at = (*at).min(table.column_widths.len());
+            table.column_widths.insert(at, *width);
+            for (row, cell) in table.rows.iter_mut().zip(cells) {
+                if let Some(cell) = cell {
+                    let idx = at.min(row.cells.len());
+                    row.cells.insert(idx, cell.clone());
+                }
+            }
+            Some(TableOp::DeleteColumn { col: at })
+        }
+        TableOp::ResizeColumn { col, width } => {
+            let old = *table.column_widths.get(*col)?;
+            resize_column(table, *col, *width);
+            Some(TableOp::ResizeColumn {
+                col: *col,
+                width: old,
+            })
+        }
+        TableOp::ResizeRow { row, height } => {
+            ensure_row_heights(table);
+            let old = *table.row_heights.get(*row)?;
+            resize_row(table, *row, *height);
+            Some(TableOp::ResizeRow {
+                row: *row,
+                height: old,
+            })
+        }
+        TableOp::Replace(next) => {
+            let old = std::mem::replace(table, (**next).clone());
+            Some(TableOp::Replace(Box::new(old)))
+        }
+    }
+}
+
 pub fn insert_row_above(table: &mut Table, row: usize) {
     insert_row(table, row);
 }
@@ -169,63 +271,132 @@ pub fn insert_column_right(table: &mut Table, col: usize) {
     insert_column(table, col.saturating_add(1));
 }
 
+/// Logical column count: the widest row, or the column widths when rows fall short.
+fn grid_width(table: &Table) -> usize {
+    table
+        .rows
+        .iter()
+        .map(|row| row.cells.len())
+        .max()
+        .unwrap_or(0)
+        .max(table.column_widths.len())
+}
+
+/// Pads short rows with empty cells (and the widths with default columns) so every row
+/// spans the full grid and a cell's index is its logical column.
+fn normalize_grid(table: &mut Table) {
+    let width = grid_width(table);
+    table.column_widths.resize(width, 120.0);
+    for row in &mut table.rows {
+        row.cells.resize_with(width, || TableCell {
+            blocks: Vec::new(),
+            rowspan: 1,
+            colspan: 1,
+            background: None,
+        });
+    }
+}
+
+/// Cells a visible cell covers: its position stretched by its spans, clipped to the grid so
+/// a stale span never reaches past the last row or column. `None` for covered cells.
+fn cell_extent(table: &Table, pos: CellPos) -> Option<TableSelection> {
+    let cell = table.rows.get(pos.row)?.cells.get(pos.col)?;
+    if cell.rowspan == 0 || cell.colspan == 0 {
+        return None;
+    }
+    Some(TableSelection {
+        start: pos,
+        end: CellPos {
+            row: (pos.row + usize::from(cell.rowspan) - 1).min(table.rows.len() - 1),
+            col: (pos.col + usize::from(cell.colspan) - 1).min(grid_width(table) - 1),
+        },
+    })
+}
+
+/// Merges the selected cells into the top-left one, which takes the others' content. The
+/// selection must be a rectangle of whole cells: a merged cell sticking out of it, or a
+/// single cell, leaves the table unchanged.
 pub fn merge_cells(table: &mut Table, selection: TableSelection) -> bool {
     let sel = selection.normalized();
-    if sel.start.row >= table.rows.len() {
+    if sel.end.row >= table.rows.len() || sel.end.col >= grid_width(table) || sel.start == sel.end {
         return false;
     }
+    normalize_grid(table);
 
-    let mut collected = Vec::new();
-    for r in sel.start.row..=sel.end.row {
-        let Some(row) = table.rows.get_mut(r) else {
-            break;
-        };
-
-        for c in sel.start.col..=sel.end.col {
-            if c >= row.cells.len() {
+    let mut anchors = Vec::new();
+    let mut covered_area = 0usize;
+    for row in 0..table.rows.len() {
+        for col in 0..table.rows[row].cells.len() {
+            let pos = CellPos { row, col };
+            let Some(extent) = cell_extent(table, pos) else {
                 continue;
-            }
-
-            if r == sel.start.row && c == sel.start.col {
+            };
+            let inside = |p: CellPos| {
+                (sel.start.row..=sel.end.row).contains(&p.row)
+                    && (sel.start.col..=sel.end.col).contains(&p.col)
+            };
+            let overlaps = extent.start.row <= sel.end.row
+                && extent.end.row >= sel.start.row
+                && extent.start.col <= sel.end.col
+                && extent.end.col >= sel.start.col;
+            if !overlaps {
                 continue;
             }
+            if !inside(extent.start) || !inside(extent.end) {
+                return false;
+            }
+            covered_area +=
+                (extent.end.row - extent.start.row + 1) * (extent.end.col - extent.start.col + 1);
+            anchors.push(pos);
+        }
+    }
+    // Covered cells whose anchor lies outside the selection leave holes in it.
+    let area = (sel.end.row - sel.start.row + 1) * (sel.end.col - sel.start.col + 1);
+    if covered_area != area || anchors.first() != Some(&sel.start) {
+        return false;
+    }
 
-            let cell = &mut row.cells[c];
-            collected.append(&mut cell.blocks);
+    let mut collected = Vec::new();
+    for pos in anchors.into_iter().skip(1) {
+        collected.append(&mut table.rows[pos.row].cells[pos.col].blocks);
+    }
+    for row in &mut table.rows[sel.start.row..=sel.end.row] {
+        for cell in &mut row.cells[sel.start.col..=sel.end.col] {
             cell.rowspan = 0;
             cell.colspan = 0;
         }
     }
-
-    if let Some(anchor) = table
-        .rows
-        .get_mut(sel.start.row)
-        .and_then(|row| row.cells.get_mut(sel.start.col))
-    {
-        anchor.rowspan = (sel.end.row - sel.start.row + 1) as u16;
-        anchor.colspan = (sel.end.col - sel.start.col + 1) as u16;
-        anchor.blocks.extend(collected);
-        true
-    } else {
-        false
-    }
+    let anchor = &mut table.rows[sel.start.row].cells[sel.start.col];
+    anchor.rowspan = (sel.end.row - sel.start.row + 1) as u16;
+    anchor.colspan = (sel.end.col - sel.start.col + 1) as u16;
+    anchor.blocks.extend(collected);
+    true
 }
 
+/// Splits a merged cell back into the grid cells it covered; its content stays in the
+/// top-left one and the rest come back empty.
 pub fn split_cell(table: &mut Table, pos: CellPos) -> bool {
-    let Some(cell) = table
-        .rows
-        .get_mut(pos.row)
-        .and_then(|row| row.cells.get_mut(pos.col))
-    else {
+    let Some(extent) = cell_extent(table, pos) else {
         return false;
     };
-
-    if cell.rowspan <= 1 && cell.colspan <= 1 {
+    if extent.start == extent.end {
         return false;
     }
+    normalize_grid(table);
 
-    cell.rowspan = 1;
-    cell.colspan = 1;
+    for row in &mut table.rows[extent.start.row..=extent.end.row] {
+        for cell in &mut row.cells[extent.start.col..=extent.end.col] {
+            // Only the cells this merge hid; a visible cell inside a stale span keeps its own.
+            if cell.rowspan == 0 || cell.colspan == 0 {
+                cell.blocks.clear();
+            }
+            cell.rowspan = cell.rowspan.max(1);
+            cell.colspan = cell.colspan.max(1);
+        }
+    }
+    let anchor = &mut table.rows[pos.row].cells[pos.col];
+    anchor.rowspan = 1;
+    anchor.colspan = 1;
     true
 }
 
@@ -241,13 +412,21 @@ pub fn resize_column(table: &mut Table, col: usize, width: f32) -> bool {
 pub fn resize_row(table: &mut Table, row: usize, height: f32) -> bool {
     ensure_row_heights(table);
     if let Some(slot) = table.row_heights.get_mut(row) {
-        *slot = height.max(18.0);
+        *slot = height.max(MIN_ROW_HEIGHT);
         true
     } else {
         false
     }
 }
 
+/// Sets every row to `height`, clamped to the minimum row height.
+pub fn set_uniform_row_height(table: &mut Table, height: f32) {
+    ensure_row_heights(table);
+    for slot in &mut table.row_heights {
+        *slot = height.max(MIN_ROW_HEIGHT);
+    }
+}
+
 pub fn distribute_columns_evenly(table: &mut Table, total_width: f32) {
     if table.column_widths.is_empty() {
         return;
@@ -258,43 +437,82 @@ pub fn distribute_columns_evenly(table: &mut Table, total_width: f32) {
     }
 }
 
-pub fn fit_columns_to_content(table: &mut Table, max_total_width: f32) {
-    if table.column_widths.is_empty() {
+/// Gives every row an equal share of `total_height`, clamped to the minimum row height.
+pub fn distribute_rows_evenly(table: &mut Table, total_height: f32) {
+    if table.rows.is_empty() {
         return;
     }
+    set_uniform_row_height(table, total_height / table.rows.len() as f32);
+}
+
+/// Character-count estimate of how wide `text` sets at the body size, for when no renderer
+/// is around to measure it.
+pub fn estimate_text_width(text: &str) -> f32 {
+    text.chars().count() as f32 * 6.2
+}
 
+/// Width each column needs to show its longest paragraph on one line, as `measure` reports
+/// it, plus room for padding and borders; capped at `max_column_width`. Cells spanning
+/// several columns don't count towards any one of them.
+pub fn natural_column_widths(
+    table: &Table,
+    measure: impl Fn(&str) -> f32,
+    max_column_width: f32,
+) -> Vec<f32> {
     let mut widths = vec![24.0f32; table.column_widths.len()];
     for row in &table.rows {
         for (col, cell) in row.cells.iter().enumerate() {
             if col >= widths.len() {
                 break;
             }
-            let chars = cell
+            if cell.colspan != 1 {
+                continue;
+            }
+            let text_width = cell
                 .blocks
                 .iter()
                 .filter_map(|block| match block {
-                    Block::Paragraph(p) => Some(
-                        p.runs
-                            .iter()
-                            .map(|run| run.text.chars().count())
-                            .sum::<usize>(),
-                    ),
+                    Block::Paragraph(p) => {
+                        Some(p.runs.iter().map(|run| run.text.as_str()).collect())
+                    }
                     _ => None,
                 })
-                .max()
-                .unwrap_or(0);
-            let estimate = 24.0 + (chars as f32 * 6.2);
-            widths[col] = widths[col].max(estimate);
+                .map(|text: String| measure(&text))
+                .fold(0.0f32, f32::max);
+            widths[col] = widths[col].max(24.0 + text_width);
         }
     }
+    widths
+        .into_iter()
+        .map(|width| width.min(max_column_width.max(24.0)))
+        .collect()
+}
+
+/// Fits `natural` column widths into `total`. They are kept as they are when they fit;
+/// otherwise columns narrower than an even share keep their width and the wider ones split
+/// what is left equally.
+pub fn distribute_column_widths(natural: &[f32], total: f32) -> Vec<f32> {
+    if natural.iter().sum::<f32>() <= total {
+        return natural.to_vec();
+    }
 
-    let sum = widths.iter().sum::<f32>().max(1.0);
-    let scale = (max_total_width / sum).clamp(0.2, 1.0);
-    for (idx, slot) in widths.iter_mut().enumerate() {
-        *slot = (*slot * scale).max(24.0);
-        if let Some(column) = table.column_widths.get_mut(idx) {
-            *column = *slot;
-        }
+    let mut order = (0..natural.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| natural[*a].total_cmp(&natural[*b]));
+    let mut widths = natural.to_vec();
+    let mut remaining = total;
+    for (placed, index) in order.into_iter().enumerate() {
+        let share = remaining / (natural.len() - placed) as f32;
+        widths[index] = natural[index].min(share).max(24.0);
+        remaining = (remaining - widths[index]).max(0.0);
+    }
+    widths
+}
+
+/// Sets the column widths from their `natural` widths fitted into `max_total_width`.
+pub fn fit_columns_to_content(table: &mut Table, natural: &[f32], max_total_width: f32) {
+    let widths = distribute_column_widths(natural, max_total_width);
+    for (column, width) in table.column_widths.iter_mut().zip(widths) {
+        *column = width;
     }
 }
 
@@ -322,6 +540,8 @@ pub fn apply_style(table: &mut Table, style: TableStylePreset) {
             table.cell_padding = 6.0;
         }
     }
+    // Presets with a styled header row repeat it on continued pages unless switched off later.
+    table.header_repeat = table.header_row;
 }
 
 pub fn visible_row_range(table: &Table, scroll_y: f32, viewport_h: f32, row_h: f32) -> (usize, usize) {
@@ -330,13 +550,627 @@ pub fn visible_row_range(table: &Table, scroll_y: f32, viewport_h: f32, row_h: f
     (start.min(table.rows.len()), end.min(table.rows.len()))
 }
 
+/// Per-table view options for the canvas overlay: horizontal scroll of wide tables and which
+/// parts stay put while the rest scrolls.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TableView {
+    pub scroll_x: f32,
+    pub freeze_first_column: bool,
+    pub freeze_header_row: bool,
+}
+
+/// Geometry of a table overlay whose cells scroll under an optional frozen first column and
+/// header row. Coordinates are relative to the overlay's top-left corner; the row gutter and
+/// column header band never scroll.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TableViewport {
+    pub rows: usize,
+    pub cols: usize,
+    pub cell_w: f32,
+    pub cell_h: f32,
+    pub gutter_w: f32,
+    pub header_h: f32,
+    pub scroll_x: f32,
+    pub scroll_y: f32,
+    pub freeze_first_column: bool,
+    pub freeze_header_row: bool,
+}
+
+impl TableViewport {
+    /// Width of the frozen column band, zero when nothing is frozen.
+    pub fn frozen_width(&self) -> f32 {
+        if self.freeze_first_column && self.cols > 1 {
+            self.cell_w
+        } else {
+            0.0
+        }
+    }
+
+    /// Height of the frozen header row, zero when nothing is frozen.
+    pub fn frozen_height(&self) -> f32 {
+        if self.freeze_header_row && self.rows > 1 {
+            self.cell_h
+        } else {
+            0.0
+        }
+    }
+
+    /// Left edge of `col` on screen; the frozen column ignores the horizontal scroll.
+    pub fn column_x(&self, col: usize) -> f32 {
+        let x = self.gutter_w + col as f32 * self.cell_w;
+        if col == 0 && self.frozen_width() > 0.0 {
+            x
+        } else {
+            x - self.scroll_x
+        }
+    }
+
+    /// Top edge of `row` on screen; the frozen header row ignores the vertical scroll.
+    pub fn row_y(&self, row: usize) -> f32 {
+        let y = self.header_h + row as f32 * self.cell_h;
+        if row == 0 && self.frozen_height() > 0.0 {
+            y
+        } else {
+            y - self.scroll_y
+        }
+    }
+
+    /// Unscrolled x within the cell area for a point at `x`, honouring the frozen column.
+    pub fn content_x(&self, x: f32) -> f32 {
+        let x = (x - self.gutter_w).max(0.0);
+        if x < self.frozen_width() {
+            x
+        } else {
+            x + self.scroll_x
+        }
+    }
+
+    /// Unscrolled y within the cell area for a point at `y`, honouring the frozen header row.
+    pub fn content_y(&self, y: f32) -> f32 {
+        let y = (y - self.header_h).max(0.0);
+        if y < self.frozen_height() {
+            y
+        } else {
+            y + self.scroll_y
+        }
+    }
+
+    /// Cell under a point, clamped to the table.
+    pub fn cell_at(&self, x: f32, y: f32) -> CellPos {
+        let col = (self.content_x(x) / self.cell_w.max(1.0)).floor() as usize;
+        let row = (self.content_y(y) / self.cell_h.max(1.0)).floor() as usize;
+        CellPos {
+            row: row.min(self.rows.saturating_sub(1)),
+            col: col.min(self.cols.saturating_sub(1)),
+        }
+    }
+
+    /// Largest horizontal scroll that still fills an overlay `visible_w` wide.
+    pub fn max_scroll_x(&self, visible_w: f32) -> f32 {
+        (self.gutter_w + self.cols as f32 * self.cell_w - visible_w).max(0.0)
+    }
+}
+
+/// Separator between cells when a table is written out as lines of text, or read back in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextDelimiter {
+    Tab,
+    Comma,
+    Pipe,
+}
+
+impl TextDelimiter {
+    pub const ALL: [Self; 3] = [Self::Tab, Self::Comma, Self::Pipe];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Tab => "tabs",
+            Self::Comma => "commas",
+            Self::Pipe => "pipes",
+        }
+    }
+
+    /// The delimiter that splits the most `lines` into several cells; tabs win ties.
+    pub fn detect(lines: &[String]) -> Self {
+        Self::ALL
+            .into_iter()
+            .rev()
+            .max_by_key(|delimiter| {
+                lines
+                    .iter()
+                    .filter(|line| split_delimited(line, *delimiter).len() > 1)
+                    .count()
+            })
+            .unwrap_or(Self::Tab)
+    }
+}
+
+/// One line per row, cells joined by `delimiter`. Commas quote cells the CSV way, pipe rows
+/// are Markdown-style with `\|` escapes and stray tabs become spaces, so `lines_to_table`
+/// reads the cells back.
+pub fn table_to_lines(table: &Table, delimiter: TextDelimiter) -> Vec<String> {
+    table
+        .rows
+        .iter()
+        .map(|row| {
+            let cells = row.cells.iter().map(|cell| {
+                let text = cell_text(cell);
+                match delimiter {
+                    TextDelimiter::Tab => text.replace('\t', " "),
+                    TextDelimiter::Comma if text.contains([',', '"']) => {
+                        format!("\"{}\"", text.replace('"', "\"\""))
+                    }
+                    TextDelimiter::Comma => text,
+                    TextDelimiter::Pipe => text.replace('|', "\\|"),
+                }
+            });
+            match delimiter {
+                TextDelimiter::Tab => cells.collect::<Vec<_>>().join("\t"),
+                TextDelimiter::Comma => cells.collect::<Vec<_>>().join(","),
+                TextDelimiter::Pipe => format!("| {} |", cells.collect::<Vec<_>>().join(" | ")),
+            }
+        })
+        .collect()
+}
+
+/// Builds a table from delimited `lines`, one row each; short rows are padded with empty
+/// cells. Cell paragraphs take ids after `id`.
+pub fn lines_to_table(lines: &[String], delimiter: TextDelimiter, id: BlockId) -> Option<Table> {
+    if lines.is_empty() {
+        return None;
+    }
+    let rows = lines
+        .iter()
+        .map(|line| split_delimited(line, delimiter))
+        .collect::<Vec<_>>();
+    let cols = rows.iter().map(Vec::len).max().unwrap_or(1).clamp(1, 64);
+    let mut next_id = id.0;
+    let rows = rows
+        .into_iter()
+        .map(|cells| TableRow {
+            cells: (0..cols)
+                .map(|col| {
+                    let text = cells.get(col).cloned().unwrap_or_default();
+                    TableCell {
+                        blocks: if text.is_empty() {
+                            Vec::new()
+                        } else {
+                            next_id += 1;
+                            vec![Block::Paragraph(text_paragraph(BlockId(next_id), text))]
+                        },
+                        rowspan: 1,
+                        colspan: 1,
+                        background: None,
+                    }
+                })
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+    Some(Table {
+        id,
+        row_heights: vec![28.0; rows.len()],
+        rows,
+        column_widths: vec![120.0; cols],
+        borders: TableBorders::default(),
+        style: TableStylePreset::Grid,
+        cell_padding: 4.0,
+        header_row: false,
+        alternating_rows: false,
+        header_repeat: false,
+        caption: None,
+    })
+}
+
+/// Splits one line of delimited text into cell strings, undoing `table_to_lines`' quoting.
+pub fn split_delimited(line: &str, delimiter: TextDelimiter) -> Vec<String> {
+    match delimiter {
+        TextDelimiter::Tab => line.split('\t').map(str::to_string).collect(),
+        TextDelimiter::Comma => {
+            let mut cells = vec![String::new()];
+            let mut quoted = false;
+            let mut chars = line.chars().peekable();
+            while let Some(ch) = chars.next() {
+                let cell = cells.last_mut().expect("at least one cell");
+                match ch {
+                    '"' if quoted && chars.peek() == Some(&'"') => {
+                        cell.push('"');
+                        chars.next();
+                    }
+                    '"' if quoted || cell.is_empty() => quoted = !quoted,
+                    ',' if !quoted => cells.push(String::new()),
+                    _ => cell.push(ch),
+                }
+            }
+            cells
+        }
+        TextDelimiter::Pipe => {
+            // Markdown-style rows may also have outer pipes: `| a | b |`.
+            let trimmed = line.trim();
+            let trimmed = trimmed.strip_prefix('|').unwrap_or(trimmed);
+            let trimmed = match trimmed.strip_suffix('|') {
+                Some(rest) if !rest.ends_with('\\') => rest,
+                _ => trimmed,
+            };
+            let mut cells = vec![String::new()];
+            let mut chars = trimmed.chars().peekable();
+            while let Some(ch) = chars.next() {
+                let cell = cells.last_mut().expect("at least one cell");
+                match ch {
+                    '\\' if chars.peek() == Some(&'|') => {
+                        cell.push('|');
+                        chars.next();
+                    }
+                    '|' => cells.push(String::new()),
+                    _ => cell.push(ch),
+                }
+            }
+            cells
+                .into_iter()
+                .map(|cell| cell.trim().to_string())
+                .collect()
+        }
+    }
+}
+
+/// `ReplaceBlocks` command turning the table `table_id` into one paragraph per row.
+pub fn table_to_text_command(
+    doc: &DocumentModel,
+    table_id: BlockId,
+    delimiter: TextDelimiter,
+) -> Option<EditCommand> {
+    let index = doc
+        .content
+        .iter()
+        .position(|block| matches!(block, Block::Table(t) if t.id == table_id))?;
+    let Block::Table(table) = &doc.content[index] else {
+        return None;
+    };
+    let first_id = next_block_id(doc).0;
+    let blocks = table_to_lines(table, delimiter)
+        .into_iter()
+        .enumerate()
+        .map(|(row, line)| Block::Paragraph(text_paragraph(BlockId(first_id + row as u64), line)))
+        .collect();
+    Some(EditCommand::ReplaceBlocks {
+        at_index: index,
+        count: 1,
+        blocks,
+        start_block: None,
+    })
+}
+
+/// `ReplaceBlocks` command turning the paragraphs `first..=last` into a table, splitting on
+/// whichever delimiter they use. `None` when the range holds anything but paragraphs.
+pub fn text_to_table_command(
+    doc: &DocumentModel,
+    first: BlockId,
+    last: BlockId,
+) -> Option<(EditCommand, TextDelimiter)> {
+    let index_of = |id: BlockId| {
+        doc.content.iter().position(|block| match block {
+            Block::Paragraph(p) => p.id == id,
+            _ => false,
+        })
+    };
+    let (start, end) = (index_of(first)?, index_of(last)?);
+    let (start, end) = (start.min(end), start.max(end));
+    let lines = doc.content[start..=end]
+        .iter()
+        .map(|block| match block {
+            Block::Paragraph(p) => Some(p.runs.iter().map(|r| r.text.as_str()).collect::<String>()),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let delimiter = TextDelimiter::detect(&lines);
+    let table = lines_to_table(&lines, delimiter, next_block_id(doc))?;
+    Some((
+        EditCommand::ReplaceBlocks {
+            at_index: start,
+            count: end - start + 1,
+            blocks: vec![Block::Table(table)],
+            start_block: None,
+        },
+        delimiter,
+    ))
+}
+
+fn cell_text(cell: &TableCell) -> String {
+    cell.blocks
+        .iter()
+        .filter_map(|block| match block {
+            Block::Paragraph(p) => Some(p.runs.iter().map(|r| r.text.as_str()).collect::<String>()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn text_paragraph(id: BlockId, text: String) -> Paragraph {
+    Paragraph {
+        id,
+        runs: vec![Run {
+            text,
+            style: RunStyle::default(),
+        }],
+        alignment: ParagraphAlignment::Left,
+        spacing: ParagraphSpacing::default(),
+        indent: Indent::default(),
+        style_id: None,
+        direction: Default::default(),
+        frame: None,
+    }
+}
+
+/// Tables nest inside cells at most this deep; deeper tables are neither laid out nor reached.
+pub const MAX_TABLE_NESTING: usize = 3;
+
+/// Finds a table by id, looking inside table cells down to `MAX_TABLE_NESTING`.
 pub fn find_table_mut(doc: &mut DocumentModel, table_id: BlockId) -> Option<&mut Table> {
-    doc.content.iter_mut().find_map(|block| match block {
+    find_table_in_mut(&mut doc.content, table_id, 0)
+}
+
+fn find_table_in_mut(blocks: &mut [Block], table_id: BlockId, depth: usize) -> Option<&mut Table> {
+    if depth > MAX_TABLE_NESTING {
+        return None;
+    }
+    blocks.iter_mut().find_map(|block| match block {
+        // One arm for both cases; a guarded arm would borrow the table twice.
+        Block::Table(table) => {
+            if table.id == table_id {
+                return Some(table);
+            }
+            table
+                .rows
+                .iter_mut()
+                .flat_map(|row| row.cells.iter_mut())
+                .find_map(|cell| find_table_in_mut(&mut cell.blocks, table_id, depth + 1))
+        }
+        _ => None,
+    })
+}
+
+pub fn find_table(doc: &DocumentModel, table_id: BlockId) -> Option<&Table> {
+    find_table_in(&doc.content, table_id, 0)
+}
+
+fn find_table_in(blocks: &[Block], table_id: BlockId, depth: usize) -> Option<&Table> {
+    if depth > MAX_TABLE_NESTING {
+        return None;
+    }
+    blocks.iter().find_map(|block| match block {
         Block::Table(table) if table.id == table_id => Some(table),
+        Block::Table(table) => table
+            .rows
+            .iter()
+            .flat_map(|row| row.cells.iter())
+            .find_map(|cell| find_table_in(&cell.blocks, table_id, depth + 1)),
+        _ => None,
+    })
+}
+
+/// The table and cell that directly contain the nested table `table_id`.
+pub fn table_parent(doc: &DocumentModel, table_id: BlockId) -> Option<(BlockId, CellPos)> {
+    fn search(blocks: &[Block], table_id: BlockId, depth: usize) -> Option<(BlockId, CellPos)> {
+        if depth > MAX_TABLE_NESTING {
+            return None;
+        }
+        blocks.iter().find_map(|block| {
+            let Block::Table(table) = block else {
+                return None;
+            };
+            table.rows.iter().enumerate().find_map(|(row, table_row)| {
+                table_row.cells.iter().enumerate().find_map(|(col, cell)| {
+                    if nested_table(cell).is_some_and(|nested| nested.id == table_id) {
+                        Some((table.id, CellPos { row, col }))
+                    } else {
+                        search(&cell.blocks, table_id, depth + 1)
+                    }
+                })
+            })
+        })
+    }
+    search(&doc.content, table_id, 0)
+}
+
+/// The first table inside `cell`, which Tab navigation descends into.
+pub fn nested_table(cell: &TableCell) -> Option<&Table> {
+    cell.blocks.iter().find_map(|block| match block {
+        Block::Table(table) => Some(table),
         _ => None,
     })
 }
 
+/// Removes the table `table_id` from `blocks` or from a cell of a table among them.
+pub fn delete_table(blocks: &mut Vec<Block>, table_id: BlockId) -> bool {
+    fn remove(blocks: &mut Vec<Block>, table_id: BlockId, depth: usize) -> bool {
+        if depth > MAX_TABLE_NESTING {
+            return false;
+        }
+        if let Some(idx) = blocks
+            .iter()
+            .position(|block| matches!(block, Block::Table(t) if t.id == table_id))
+        {
+            blocks.remove(idx);
+            return true;
+        }
+        blocks.iter_mut().any(|block| match block {
+            Block::Table(table) => table
+                .rows
+                .iter_mut()
+                .flat_map(|row| row.cells.iter_mut())
+                .any(|cell| remove(&mut cell.blocks, table_id, depth + 1)),
+            _ => false,
+        })
+    }
+    remove(blocks, table_id, 0)
+}
+
+/// Where Tab or Shift+Tab goes from `cell` of `table_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellStep {
+    Cell(BlockId, CellPos),
+    /// Tab past the last cell of a top-level table adds a row to that table.
+    AppendRow(BlockId),
+}
+
+/// Steps one cell forwards or backwards. A target cell holding a nested table descends into
+/// it (its first cell, or last when going backwards); stepping off either end of a nested
+/// table climbs back out to the cell beside the one that contains it.
+pub fn step_table_cell(
+    doc: &DocumentModel,
+    table_id: BlockId,
+    cell: CellPos,
+    backwards: bool,
+) -> Option<CellStep> {
+    let table = find_table(doc, table_id)?;
+    let rows = table.rows.len().max(1);
+    let cols = table.column_widths.len().max(1);
+    let row = cell.row.min(rows - 1);
+    let col = cell.col.min(cols - 1);
+
+    let target = if backwards {
+        if col > 0 {
+            Some(CellPos { row, col: col - 1 })
+        } else if row > 0 {
+            Some(CellPos {
+                row: row - 1,
+                col: cols - 1,
+            })
+        } else {
+            None
+        }
+    } else if col + 1 < cols {
+        Some(CellPos { row, col: col + 1 })
+    } else if row + 1 < rows {
+        Some(CellPos { row: row + 1, col: 0 })
+    } else {
+        None
+    };
+
+    let Some(mut target) = target else {
+        return match table_parent(doc, table_id) {
+            Some((parent, parent_cell)) => step_table_cell(doc, parent, parent_cell, backwards),
+            None if backwards => Some(CellStep::Cell(table_id, CellPos { row: 0, col: 0 })),
+            None => Some(CellStep::AppendRow(table_id)),
+        };
+    };
+
+    let mut target_table = table;
+    while let Some(inner) = target_table
+        .rows
+        .get(target.row)
+        .and_then(|r| r.cells.get(target.col))
+        .and_then(nested_table)
+        .filter(|inner| find_table(doc, inner.id).is_some())
+    {
+        target_table = inner;
+        target = if backwards {
+            CellPos {
+                row: inner.rows.len().saturating_sub(1),
+                col: inner.column_widths.len().saturating_sub(1),
+            }
+        } else {
+            CellPos { row: 0, col: 0 }
+        };
+    }
+    Some(CellStep::Cell(target_table.id, target))
+}
+
+/// The cell Ctrl+Arrow jumps to from `from`, stepping by `row_step` and `col_step` as a
+/// spreadsheet does: along a run of filled cells to its last one, or else on to the next
+/// filled cell, stopping at the table's edge when there is none.
+pub fn jump_to_edge(table: &Table, from: CellPos, row_step: isize, col_step: isize) -> CellPos {
+    let rows = table.rows.len().max(1);
+    let cols = table.column_widths.len().max(1);
+    let step = |pos: CellPos| {
+        let row = pos.row.checked_add_signed(row_step)?;
+        let col = pos.col.checked_add_signed(col_step)?;
+        (row < rows && col < cols).then_some(CellPos { row, col })
+    };
+    let filled = |pos: CellPos| {
+        table
+            .rows
+            .get(pos.row)
+            .and_then(|row| row.cells.get(pos.col))
+            .is_some_and(|cell| !cell_is_blank(cell))
+    };
+
+    let from = CellPos {
+        row: from.row.min(rows - 1),
+        col: from.col.min(cols - 1),
+    };
+    let Some(mut at) = step(from) else {
+        return from;
+    };
+    if filled(from) && filled(at) {
+        while let Some(next) = step(at).filter(|next| filled(*next)) {
+            at = next;
+        }
+    } else {
+        while !filled(at) {
+            let Some(next) = step(at) else {
+                break;
+            };
+            at = next;
+        }
+    }
+    at
+}
+
+/// Whether `cell` shows nothing: no blocks, or only paragraphs of whitespace.
+fn cell_is_blank(cell: &TableCell) -> bool {
+    cell.blocks.iter().all(|block| match block {
+        Block::Paragraph(p) => p.runs.iter().all(|run| run.text.trim().is_empty()),
+        _ => false,
+    })
+}
+
+/// `table` as a paste into `doc`: its layout, styles and content unchanged, with the table
+/// and every block nested in its cells renumbered past the ids `doc` already uses.
+pub fn table_for_paste(doc: &DocumentModel, mut table: Table) -> Table {
+    fn take(id: &mut BlockId, next: &mut u64) {
+        *id = BlockId(*next);
+        *next += 1;
+    }
+
+    fn renumber_table(table: &mut Table, next: &mut u64) {
+        take(&mut table.id, next);
+        for cell in table.rows.iter_mut().flat_map(|row| &mut row.cells) {
+            renumber_blocks(&mut cell.blocks, next);
+        }
+    }
+
+    fn renumber_blocks(blocks: &mut [Block], next: &mut u64) {
+        for block in blocks {
+            match block {
+                Block::Paragraph(p) => take(&mut p.id, next),
+                Block::Heading(h) => take(&mut h.id, next),
+                Block::CodeBlock(c) => take(&mut c.id, next),
+                Block::Image(i) => take(&mut i.id, next),
+                Block::Table(t) => renumber_table(t, next),
+                Block::BlockQuote(quote) => {
+                    take(&mut quote.id, next);
+                    renumber_blocks(&mut quote.blocks, next);
+                }
+                Block::List(list) => renumber_items(&mut list.items, next),
+                Block::PageBreak | Block::HorizontalRule => {}
+            }
+        }
+    }
+
+    fn renumber_items(items: &mut [ListItem], next: &mut u64) {
+        for item in items {
+            take(&mut item.id, next);
+            renumber_blocks(&mut item.content, next);
+            renumber_items(&mut item.children, next);
+        }
+    }
+
+    let mut next = doc.next_block_id().0;
+    renumber_table(&mut table, &mut next);
+    table
+}
+
 fn next_block_id(doc: &DocumentModel) -> BlockId {
     let max = doc
         .content
@@ -366,8 +1200,6 @@ fn ensure_row_heights(table: &mut Table) {
 
 #[cfg(test)]
 mod tests {
-    use crate::document::model::{Paragraph, ParagraphAlignment, ParagraphSpacing, Run, RunStyle};
-
     use super::*;
 
     fn paragraph_block(id: u64, text: &str) -> Block {
@@ -381,6 +1213,8 @@ mod tests {
             spacing: ParagraphSpacing::default(),
             indent: Default::default(),
             style_id: None,
+            direction: Default::default(),
+            frame: None,
         })
     }
 
@@ -418,10 +1252,14 @@ mod tests {
                 cells: vec![
                     TableCell {
                         blocks: vec![paragraph_block(10, "short")],
+                        rowspan: 1,
+                        colspan: 1,
                         ..TableCell::default()
                     },
                     TableCell {
                         blocks: vec![paragraph_block(11, "this is a much longer cell payload")],
+                        rowspan: 1,
+                        colspan: 1,
                         ..TableCell::default()
                     },
                 ],
@@ -430,7 +1268,8 @@ mod tests {
             row_heights: vec![28.0],
             ..Table::default()
         };
-        fit_columns_to_content(&mut table, 300.0);
+        let natural = natural_column_widths(&table, estimate_text_width, 300.0);
+        fit_columns_to_content(&mut table, &natural, 300.0);
         assert!(table.column_widths[1] > table.column_widths[0]);
         assert!(table.column_widths.iter().sum::<f32>() <= 300.0 + 0.1);
     }
@@ -447,4 +1286,442 @@ mod tests {
         cache.invalidate();
         assert!(cache.generation() > before);
     }
+
+    #[test]
+    fn frozen_column_clicks_map_to_the_same_cell_at_any_scroll_offset() {
+        let mut viewport = TableViewport {
+            rows: 6,
+            cols: 12,
+            cell_w: 40.0,
+            cell_h: 24.0,
+            gutter_w: 18.0,
+            header_h: 18.0,
+            freeze_first_column: true,
+            ..TableViewport::default()
+        };
+        // x = 30 and y = 50 fall in column 0, row 1 of the unscrolled table.
+        for scroll_x in [0.0, 35.0, 120.0, 400.0] {
+            viewport.scroll_x = scroll_x;
+            assert_eq!(viewport.cell_at(30.0, 50.0), CellPos { row: 1, col: 0 }, "scroll {scroll_x}");
+            assert_eq!(viewport.column_x(0), 18.0);
+        }
+
+        // Past the frozen band the scroll applies: 18 + 40 + 5 is column 1 unscrolled...
+        viewport.scroll_x = 0.0;
+        assert_eq!(viewport.cell_at(63.0, 50.0).col, 1);
+        // ...and column 4 once three columns have scrolled under the frozen one.
+        viewport.scroll_x = 120.0;
+        assert_eq!(viewport.cell_at(63.0, 50.0).col, 4);
+        assert_eq!(viewport.column_x(4), 58.0);
+
+        // Without freezing, the first column scrolls away like the others.
+        viewport.freeze_first_column = false;
+        assert_eq!(viewport.cell_at(30.0, 50.0).col, 3);
+
+        // A frozen header row stays under the column band while rows scroll vertically.
+        viewport.freeze_header_row = true;
+        viewport.scroll_y = 48.0;
+        assert_eq!(viewport.cell_at(30.0, 20.0).row, 0);
+        assert_eq!(viewport.cell_at(30.0, 50.0).row, 3);
+        assert_eq!(viewport.cell_at(30.0, 5000.0).row, 5);
+        assert_eq!(viewport.max_scroll_x(300.0), 18.0 + 480.0 - 300.0);
+    }
+
+    fn sample_lines() -> Vec<Vec<&'static str>> {
+        vec![
+            vec!["Name", "Role", "Notes"],
+            vec!["Ada", "Engineer", "likes \"quotes\", commas"],
+            vec!["", "a|b", "last"],
+        ]
+    }
+
+    fn sample_table() -> Table {
+        let lines = sample_lines();
+        Table {
+            id: BlockId(1),
+            rows: lines
+                .iter()
+                .map(|row| TableRow {
+                    cells: row
+                        .iter()
+                        .enumerate()
+                        .map(|(col, text)| TableCell {
+                            blocks: vec![paragraph_block(10 + col as u64, text)],
+                            rowspan: 1,
+                            colspan: 1,
+                            background: None,
+                        })
+                        .collect(),
+                })
+                .collect(),
+            column_widths: vec![120.0; 3],
+            ..Table::default()
+        }
+    }
+
+    fn table_cells(table: &Table) -> Vec<Vec<String>> {
+        table
+            .rows
+            .iter()
+            .map(|row| row.cells.iter().map(cell_text).collect())
+            .collect()
+    }
+
+    fn spans(table: &Table) -> Vec<Vec<(u16, u16)>> {
+        table
+            .rows
+            .iter()
+            .map(|row| row.cells.iter().map(|c| (c.rowspan, c.colspan)).collect())
+            .collect()
+    }
+
+    fn select(start: (usize, usize), end: (usize, usize)) -> TableSelection {
+        TableSelection {
+            start: CellPos {
+                row: start.0,
+                col: start.1,
+            },
+            end: CellPos {
+                row: end.0,
+                col: end.1,
+            },
+        }
+    }
+
+    #[test]
+    fn merging_a_block_then_splitting_it_restores_the_grid() {
+        let mut table = sample_table();
+        // Selected bottom-right to top-left; the selection is normalized.
+        assert!(merge_cells(&mut table, select((1, 1), (0, 0))));
+        assert_eq!(
+            spans(&table),
+            vec![
+                vec![(2, 2), (0, 0), (1, 1)],
+                vec![(0, 0), (0, 0), (1, 1)],
+                vec![(1, 1), (1, 1), (1, 1)],
+            ]
+        );
+        assert_eq!(cell_text(&table.rows[0].cells[0]), "Name Role Ada Engineer");
+
+        // Only the merged cell splits, and it comes back as the four cells it covered.
+        assert!(!split_cell(&mut table, CellPos { row: 1, col: 1 }));
+        assert!(split_cell(&mut table, CellPos { row: 0, col: 0 }));
+        assert!(spans(&table).iter().flatten().all(|span| *span == (1, 1)));
+        assert_eq!(table_cells(&table)[1], ["", "", "likes \"quotes\", commas"]);
+        assert!(!split_cell(&mut table, CellPos { row: 0, col: 0 }));
+    }
+
+    #[test]
+    fn merges_must_cover_whole_cells() {
+        let mut table = sample_table();
+        assert!(merge_cells(&mut table, select((0, 0), (1, 1))));
+        let merged = spans(&table);
+
+        // Half of the 2x2 cell, a row cutting through it, and a single cell are all refused.
+        assert!(!merge_cells(&mut table, select((0, 1), (1, 2))));
+        assert!(!merge_cells(&mut table, select((1, 0), (2, 0))));
+        assert!(!merge_cells(&mut table, select((2, 2), (2, 2))));
+        assert!(!merge_cells(&mut table, select((0, 0), (0, 5))));
+        assert_eq!(spans(&table), merged);
+
+        // Taking the merged cell whole with its neighbours is fine.
+        assert!(merge_cells(&mut table, select((0, 0), (2, 1))));
+        assert_eq!(spans(&table)[0][0], (3, 2));
+        assert!(split_cell(&mut table, CellPos { row: 0, col: 0 }));
+        assert!(spans(&table).iter().flatten().all(|span| *span == (1, 1)));
+    }
+
+    #[test]
+    fn short_rows_are_padded_before_merging() {
+        let mut table = sample_table();
+        table.rows[2].cells.truncate(1);
+        assert!(merge_cells(&mut table, select((1, 1), (2, 2))));
+        assert_eq!(table.rows[2].cells.len(), 3);
+        assert_eq!(spans(&table)[2], vec![(1, 1), (0, 0), (0, 0)]);
+        assert!(split_cell(&mut table, CellPos { row: 1, col: 1 }));
+        assert_eq!(spans(&table)[2], vec![(1, 1); 3]);
+    }
+
+    #[test]
+    fn rows_distribute_evenly_and_never_shrink_below_the_minimum() {
+        let mut table = sample_table();
+        table.row_heights = vec![20.0, 60.0];
+        distribute_rows_evenly(&mut table, 120.0);
+        assert_eq!(table.row_heights, [40.0; 3]);
+
+        distribute_rows_evenly(&mut table, 30.0);
+        assert_eq!(table.row_heights, [MIN_ROW_HEIGHT; 3]);
+        set_uniform_row_height(&mut table, 32.0);
+        assert_eq!(table.row_heights, [32.0; 3]);
+        assert!(resize_row(&mut table, 1, 2.0));
+        assert_eq!(table.row_heights, [32.0, MIN_ROW_HEIGHT, 32.0]);
+
+        let mut empty = Table::default();
+        distribute_rows_evenly(&mut empty, 100.0);
+        assert!(empty.row_heights.is_empty());
+    }
+
+    #[test]
+    fn column_widths_fit_the_budget_without_squeezing_narrow_columns() {
+        // Enough room: everything keeps its natural width.
+        assert_eq!(
+            distribute_column_widths(&[60.0, 200.0], 400.0),
+            [60.0, 200.0]
+        );
+        // Too wide: the narrow column keeps its width and the wide ones share the rest.
+        assert_eq!(
+            distribute_column_widths(&[60.0, 300.0, 500.0], 460.0),
+            [60.0, 200.0, 200.0]
+        );
+        // A wide column that still fits its share keeps its own width.
+        assert_eq!(
+            distribute_column_widths(&[50.0, 150.0, 400.0], 400.0),
+            [50.0, 150.0, 200.0]
+        );
+        // Never below the minimum, even when the budget is gone.
+        assert_eq!(
+            distribute_column_widths(&[100.0, 100.0], 20.0),
+            [24.0, 24.0]
+        );
+        assert!(distribute_column_widths(&[], 100.0).is_empty());
+    }
+
+    #[test]
+    fn natural_widths_follow_the_longest_measured_cell() {
+        let mut table = sample_table();
+        let natural = natural_column_widths(&table, |text| text.len() as f32 * 10.0, 200.0);
+        // "Engineer" is the longest in the middle column; the last column hits the cap.
+        assert_eq!(natural, [24.0 + 40.0, 24.0 + 80.0, 200.0]);
+
+        assert!(merge_cells(&mut table, select((1, 1), (1, 2))));
+        let natural = natural_column_widths(&table, estimate_text_width, 1000.0);
+        assert_eq!(natural[1], 24.0 + estimate_text_width("Role"));
+
+        fit_columns_to_content(&mut table, &[100.0, 100.0, 100.0], 240.0);
+        assert_eq!(table.column_widths, [80.0, 80.0, 80.0]);
+    }
+
+    #[test]
+    fn table_text_round_trips_with_each_delimiter() {
+        let table = sample_table();
+        let expected = sample_lines();
+        assert_eq!(
+            table_to_lines(&table, TextDelimiter::Comma),
+            vec![
+                "Name,Role,Notes".to_string(),
+                "Ada,Engineer,\"likes \"\"quotes\"\", commas\"".to_string(),
+                ",a|b,last".to_string(),
+            ]
+        );
+        assert_eq!(table_to_lines(&table, TextDelimiter::Pipe)[2], "|  | a\\|b | last |");
+        assert_eq!(table_to_lines(&table, TextDelimiter::Tab)[0], "Name\tRole\tNotes");
+
+        for delimiter in TextDelimiter::ALL {
+            let lines = table_to_lines(&table, delimiter);
+            assert_eq!(TextDelimiter::detect(&lines), delimiter);
+            let rebuilt = lines_to_table(&lines, delimiter, BlockId(50)).expect("table from text");
+            assert_eq!(rebuilt.column_widths.len(), 3);
+            assert_eq!(rebuilt.row_heights.len(), 3);
+            assert_eq!(table_cells(&rebuilt), expected, "{}", delimiter.label());
+        }
+        assert!(lines_to_table(&[], TextDelimiter::Tab, BlockId(1)).is_none());
+    }
+
+    #[test]
+    fn conversion_commands_undo_back_to_the_original_blocks() {
+        let mut doc = DocumentModel::default();
+        doc.content.push(paragraph_block(1, "before"));
+        doc.content.push(Block::Table(Table {
+            id: BlockId(2),
+            ..sample_table()
+        }));
+
+        let command =
+            table_to_text_command(&doc, BlockId(2), TextDelimiter::Tab).expect("table found");
+        let undo_text = crate::editor::apply_to_document(&mut doc, &command).expect("applies");
+        assert_eq!(doc.content.len(), 4);
+        let Block::Paragraph(first_row) = &doc.content[1] else {
+            panic!("rows become paragraphs");
+        };
+        assert_eq!(first_row.runs[0].text, "Name\tRole\tNotes");
+        let (first, last) = match (&doc.content[1], &doc.content[3]) {
+            (Block::Paragraph(a), Block::Paragraph(b)) => (a.id, b.id),
+            _ => panic!("rows become paragraphs"),
+        };
+
+        let (back, delimiter) = text_to_table_command(&doc, first, last).expect("paragraph range");
+        assert_eq!(delimiter, TextDelimiter::Tab);
+        let undo_table = crate::editor::apply_to_document(&mut doc, &back).expect("applies");
+        assert_eq!(doc.content.len(), 2);
+        let Block::Table(table) = &doc.content[1] else {
+            panic!("paragraphs become a table");
+        };
+        assert_eq!(table_cells(table), sample_lines());
+
+        // Undo both steps and the original table is back in place.
+        crate::editor::apply_to_document(&mut doc, &undo_table).expect("undo text to table");
+        crate::editor::apply_to_document(&mut doc, &undo_text).expect("undo table to text");
+        assert_eq!(doc.content.len(), 2);
+        let Block::Table(table) = &doc.content[1] else {
+            panic!("table restored");
+        };
+        assert_eq!(table.id, BlockId(2));
+        assert!(text_to_table_command(&doc, BlockId(1), BlockId(2)).is_none());
+    }
+
+    fn grid(id: u64, rows: usize, cols: usize) -> Table {
+        Table {
+            id: BlockId(id),
+            rows: (0..rows)
+                .map(|_| TableRow {
+                    cells: (0..cols).map(|_| TableCell::default()).collect(),
+                })
+                .collect(),
+            column_widths: vec![120.0; cols],
+            row_heights: vec![28.0; rows],
+            ..Table::default()
+        }
+    }
+
+    #[test]
+    fn tab_navigation_descends_into_and_climbs_out_of_nested_tables() {
+        let mut outer = grid(1, 2, 2);
+        let mut inner = grid(2, 2, 2);
+        inner.rows[1].cells[1].blocks.push(Block::Table(grid(3, 1, 1)));
+        outer.rows[0].cells[1].blocks = vec![paragraph_block(9, "see:"), Block::Table(inner)];
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Table(outer));
+
+        let cell = |row, col| CellPos { row, col };
+        let step = |table: u64, at: CellPos, backwards: bool| {
+            step_table_cell(&doc, BlockId(table), at, backwards)
+        };
+        assert_eq!(table_parent(&doc, BlockId(3)), Some((BlockId(2), cell(1, 1))));
+        assert_eq!(table_parent(&doc, BlockId(1)), None);
+
+        // Tab into the cell holding the nested table lands on its first cell.
+        assert_eq!(step(1, cell(0, 0), false), Some(CellStep::Cell(BlockId(2), cell(0, 0))));
+        assert_eq!(step(2, cell(1, 0), false), Some(CellStep::Cell(BlockId(3), cell(0, 0))));
+        // Past the innermost table's only cell, Tab climbs two levels to the outer row below.
+        assert_eq!(step(3, cell(0, 0), false), Some(CellStep::Cell(BlockId(1), cell(1, 0))));
+        assert_eq!(
+            step(1, cell(1, 1), false),
+            Some(CellStep::AppendRow(BlockId(1)))
+        );
+
+        // Shift+Tab enters from the end and leaves before the containing cell.
+        assert_eq!(step(1, cell(1, 0), true), Some(CellStep::Cell(BlockId(3), cell(0, 0))));
+        assert_eq!(step(2, cell(0, 0), true), Some(CellStep::Cell(BlockId(1), cell(0, 0))));
+        assert_eq!(step(1, cell(0, 0), true), Some(CellStep::Cell(BlockId(1), cell(0, 0))));
+
+        assert!(find_table_mut(&mut doc, BlockId(3)).is_some());
+        assert!(delete_table(&mut doc.content, BlockId(3)));
+        assert!(find_table(&doc, BlockId(3)).is_none());
+        assert_eq!(
+            step_table_cell(&doc, BlockId(2), cell(1, 0), false),
+            Some(CellStep::Cell(BlockId(2), cell(1, 1)))
+        );
+    }
+
+    #[test]
+    fn tab_out_of_a_nested_table_in_the_last_cell_grows_the_outer_table() {
+        let mut outer = grid(1, 1, 2);
+        outer.rows[0].cells[1].blocks = vec![Block::Table(grid(2, 2, 1))];
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Table(outer));
+
+        let last = CellPos { row: 1, col: 0 };
+        assert_eq!(
+            step_table_cell(&doc, BlockId(2), last, false),
+            Some(CellStep::AppendRow(BlockId(1)))
+        );
+    }
+
+    #[test]
+    fn tables_past_the_nesting_cap_are_out_of_reach() {
+        let mut table = grid(10, 1, 1);
+        for id in (1..10).rev() {
+            let mut outer = grid(id, 1, 1);
+            outer.rows[0].cells[0].blocks.push(Block::Table(table));
+            table = outer;
+        }
+        // The outermost table gets an empty first column to Tab from.
+        table.rows[0].cells.insert(0, TableCell::default());
+        table.column_widths.push(120.0);
+        let mut doc = DocumentModel::default();
+        doc.content.push(Block::Table(table));
+
+        let deepest_reachable = 1 + MAX_TABLE_NESTING as u64;
+        assert!(find_table(&doc, BlockId(deepest_reachable)).is_some());
+        assert!(find_table(&doc, BlockId(deepest_reachable + 1)).is_none());
+        // Tab stops at the deepest reachable table rather than descending further.
+        assert_eq!(
+            step_table_cell(&doc, BlockId(1), CellPos { row: 0, col: 0 }, false),
+            Some(CellStep::Cell(BlockId(deepest_reachable), CellPos { row: 0, col: 0 }))
+        );
+    }
+
+    #[test]
+    fn ctrl_arrows_jump_to_the_last_filled_cell_or_the_edge() {
+        // . = empty
+        // a b c . e
+        // . . . . .
+        // x . . . .
+        let lines = ["a\tb\tc\t\te", "\t\t\t\t", "x\t\t\t\t"].map(str::to_string);
+        let table = lines_to_table(&lines, TextDelimiter::Tab, BlockId(1)).expect("table");
+        let cell = |row, col| CellPos { row, col };
+
+        // Through a filled run to its last cell, then on to the next filled one.
+        assert_eq!(jump_to_edge(&table, cell(0, 0), 0, 1), cell(0, 2));
+        assert_eq!(jump_to_edge(&table, cell(0, 2), 0, 1), cell(0, 4));
+        assert_eq!(jump_to_edge(&table, cell(0, 4), 0, -1), cell(0, 2));
+        assert_eq!(jump_to_edge(&table, cell(0, 0), 1, 0), cell(2, 0));
+        // With nothing filled ahead, the jump stops at the table's edge.
+        assert_eq!(jump_to_edge(&table, cell(2, 0), 0, 1), cell(2, 4));
+        assert_eq!(jump_to_edge(&table, cell(0, 1), 1, 0), cell(2, 1));
+        assert_eq!(jump_to_edge(&table, cell(1, 3), -1, 0), cell(0, 3));
+        // Already at the edge, there's nowhere to go.
+        assert_eq!(jump_to_edge(&table, cell(0, 4), 0, 1), cell(0, 4));
+        assert_eq!(jump_to_edge(&table, cell(0, 0), -1, 0), cell(0, 0));
+
+        let whole = TableSelection::whole(&table);
+        assert_eq!((whole.start, whole.end), (cell(0, 0), cell(2, 4)));
+    }
+
+    #[test]
+    fn alternating_bands_follow_row_parity_through_inserts_and_deletes() {
+        let mut table = sample_table();
+        apply_style(&mut table, TableStylePreset::AlternatingRows);
+        // Only the flag is stored; no cell picks up a band colour.
+        assert!(table.alternating_rows);
+        assert!(
+            table
+                .rows
+                .iter()
+                .flat_map(|row| &row.cells)
+                .all(|c| c.background.is_none())
+        );
+        let fills = |table: &Table| {
+            (0..table.rows.len())
+                .map(|row| table.row_fill(row))
+                .collect::<Vec<_>>()
+        };
+        let band = Some(Table::BAND_FILL);
+        assert_eq!(fills(&table), [None, band, None]);
+
+        // "Ada" was banded as row 1; pushed down to row 2 by an insert above, it no longer is.
+        insert_row_above(&mut table, 1);
+        assert_eq!(cell_text(&table.rows[2].cells[0]), "Ada");
+        assert_eq!(fills(&table), [None, band, None, band]);
+        assert_eq!(table.row_fill(2), None);
+
+        assert!(delete_row(&mut table, 0));
+        assert_eq!(fills(&table), [None, band, None]);
+        assert_eq!(cell_text(&table.rows[1].cells[0]), "Ada");
+
+        // The header row keeps its own tint and the band carries on below it.
+        apply_style(&mut table, TableStylePreset::Professional);
+        insert_row_below(&mut table, 0);
+        assert_eq!(fills(&table), [Some(Table::HEADER_FILL), band, None, band]);
+    }
 }
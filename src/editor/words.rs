@@ -0,0 +1,178 @@
+use std::ops::Range;
+
+/// Locale used for word boundaries until a document says otherwise.
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+/// Languages that elide articles with an apostrophe ("l'homme"), so it ends a word instead
+/// of joining a contraction.
+const ELISION_LANGUAGES: &[&str] = &["fr", "it", "ca"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CharClass {
+    Space,
+    Word,
+    /// Han characters: without a dictionary each one is its own segment.
+    Ideograph,
+    Hiragana,
+    Katakana,
+    Punctuation,
+}
+
+fn char_class(ch: char) -> CharClass {
+    match ch as u32 {
+        0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2FA1F => {
+            CharClass::Ideograph
+        }
+        0x3040..=0x309F => CharClass::Hiragana,
+        0x30A0..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F => CharClass::Katakana,
+        _ if ch.is_whitespace() => CharClass::Space,
+        _ if ch.is_alphanumeric() || ch == '_' => CharClass::Word,
+        _ => CharClass::Punctuation,
+    }
+}
+
+/// Whether the punctuation at `index` joins the word characters on either side: apostrophes
+/// in contractions, hyphens in compounds, and separators inside numbers.
+fn joins_word(chars: &[char], index: usize, language: &str) -> bool {
+    let (Some(&before), Some(&after)) = (
+        index.checked_sub(1).and_then(|i| chars.get(i)),
+        chars.get(index + 1),
+    ) else {
+        return false;
+    };
+    if char_class(before) != CharClass::Word || char_class(after) != CharClass::Word {
+        return false;
+    }
+    match chars[index] {
+        '\'' | '\u{2019}' => !ELISION_LANGUAGES.contains(&language),
+        '-' | '\u{2010}' => true,
+        '.' | ',' => before.is_ascii_digit() && after.is_ascii_digit(),
+        _ => false,
+    }
+}
+
+fn language_of(locale: &str) -> String {
+    locale
+        .split(['-', '_'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// The word at char `offset` of `text`, as a char range, following `locale`'s rules.
+/// An offset just past a word's last character still picks that word, as a double-click
+/// there does.
+pub fn word_range_at(text: &str, offset: usize, locale: &str) -> Range<usize> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() {
+        return 0..0;
+    }
+    let language = language_of(locale);
+    let at = offset.min(chars.len());
+    let index = if at == chars.len()
+        || (at > 0
+            && char_class(chars[at]) == CharClass::Space
+            && char_class(chars[at - 1]) != CharClass::Space)
+    {
+        at - 1
+    } else {
+        at
+    };
+
+    let class = match char_class(chars[index]) {
+        CharClass::Punctuation if joins_word(&chars, index, &language) => CharClass::Word,
+        CharClass::Ideograph => return index..index + 1,
+        class => class,
+    };
+    let belongs = |i: usize| {
+        char_class(chars[i]) == class
+            || (class == CharClass::Word && joins_word(&chars, i, &language))
+    };
+
+    let mut start = index;
+    while start > 0 && belongs(start - 1) {
+        start -= 1;
+    }
+    let mut end = index + 1;
+    while end < chars.len() && belongs(end) {
+        end += 1;
+    }
+    start..end
+}
+
+/// Where Ctrl+Left lands from `offset`: the start of the word before it.
+pub fn previous_word_start(text: &str, offset: usize, locale: &str) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut at = offset.min(chars.len());
+    while at > 0 && chars[at - 1].is_whitespace() {
+        at -= 1;
+    }
+    if at == 0 {
+        return 0;
+    }
+    word_range_at(text, at - 1, locale).start
+}
+
+/// Where Ctrl+Right lands from `offset`: the end of the next word.
+pub fn next_word_end(text: &str, offset: usize, locale: &str) -> usize {
+    let chars: Vec<char> = text.chars().collect();
+    let mut at = offset.min(chars.len());
+    while at < chars.len() && chars[at].is_whitespace() {
+        at += 1;
+    }
+    if at == chars.len() {
+        return at;
+    }
+    word_range_at(text, at, locale).end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DEFAULT_LOCALE, next_word_end, previous_word_start, word_range_at};
+
+    #[test]
+    fn english_contractions_stay_one_word() {
+        assert_eq!(word_range_at("we don't stop", 4, DEFAULT_LOCALE), 3..8);
+        assert_eq!(word_range_at("it\u{2019}s here", 3, DEFAULT_LOCALE), 0..4);
+        assert_eq!(
+            word_range_at("don't", 3, DEFAULT_LOCALE),
+            0..5,
+            "on the apostrophe"
+        );
+        assert_eq!(word_range_at("l'homme", 4, "fr-FR"), 2..7, "French elides");
+        assert_eq!(word_range_at("'quoted'", 3, DEFAULT_LOCALE), 1..7);
+    }
+
+    #[test]
+    fn hyphenated_words_and_numbers_hold_together() {
+        assert_eq!(word_range_at("a well-known fact", 9, DEFAULT_LOCALE), 2..12);
+        assert_eq!(word_range_at("pre- and post-war", 1, DEFAULT_LOCALE), 0..3);
+        assert_eq!(
+            word_range_at("pi is 3.14, roughly", 7, DEFAULT_LOCALE),
+            6..10
+        );
+        assert_eq!(
+            word_range_at("end.", 4, DEFAULT_LOCALE),
+            3..4,
+            "past the end"
+        );
+    }
+
+    #[test]
+    fn cjk_runs_split_by_character_or_script() {
+        assert_eq!(word_range_at("我爱北京", 1, "zh-CN"), 1..2);
+        assert_eq!(word_range_at("カタカナです", 2, "ja-JP"), 0..4);
+        assert_eq!(word_range_at("カタカナです", 5, "ja-JP"), 4..6);
+        assert_eq!(word_range_at("東京tokyo", 3, "ja-JP"), 2..7);
+    }
+
+    #[test]
+    fn word_navigation_skips_whole_words() {
+        let text = "we don't  stop";
+        assert_eq!(next_word_end(text, 2, DEFAULT_LOCALE), 8);
+        assert_eq!(next_word_end(text, 8, DEFAULT_LOCALE), 14);
+        assert_eq!(previous_word_start(text, 10, DEFAULT_LOCALE), 3);
+        assert_eq!(previous_word_start(text, 6, DEFAULT_LOCALE), 3);
+        assert_eq!(previous_word_start(text, 3, DEFAULT_LOCALE), 0);
+    }
+}
@@ -0,0 +1,872 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+};
+
+use crate::{
+    document::model::{Block, BlockId, DocumentModel, Revision, Run, Table},
+    editor::table::MAX_TABLE_NESTING,
+};
+
+const DEFAULT_FONT_SIZE: f32 = 11.0;
+const LINE_HEIGHT_FACTOR: f32 = 1.35;
+const AVERAGE_CHAR_WIDTH_FACTOR: f32 = 0.5;
+const DEFAULT_MARGIN: f32 = 72.0;
+const DEFAULT_ROW_HEIGHT: f32 = 24.0;
+const RULE_HEIGHT: f32 = 12.0;
+/// Indent each level of block quote adds.
+const QUOTE_INDENT: f32 = 24.0;
+const QUOTE_BAR_WIDTH: f32 = 3.0;
+/// Gap between a level's start edge and its bar.
+const QUOTE_BAR_OFFSET: f32 = 4.0;
+
+/// Where a block quote's bars and text sit, as offsets from the start edge of the text
+/// column: the left edge, or the right one for right-to-left text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct QuoteGeometry {
+    /// Start and end offset of one bar per level, outermost first.
+    pub bars: Vec<(f32, f32)>,
+    pub text_inset: f32,
+}
+
+/// Geometry of text `depth` block quotes deep; depth 0 is unquoted text.
+pub fn quote_geometry(depth: usize) -> QuoteGeometry {
+    QuoteGeometry {
+        bars: (0..depth)
+            .map(|level| {
+                let start = level as f32 * QUOTE_INDENT + QUOTE_BAR_OFFSET;
+                (start, start + QUOTE_BAR_WIDTH)
+            })
+            .collect(),
+        text_inset: depth as f32 * QUOTE_INDENT,
+    }
+}
+
+/// Where a block starts: the page and column it lands in and its offset from the top of the
+/// text area.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct BlockPosition {
+    page: usize,
+    column: usize,
+    y: f32,
+}
+
+impl BlockPosition {
+    /// The top of the next column, or of the next page after the last of `columns`.
+    fn next_column(self, columns: usize) -> Self {
+        if self.column + 1 < columns {
+            Self {
+                column: self.column + 1,
+                y: 0.0,
+                ..self
+            }
+        } else {
+            self.next_page()
+        }
+    }
+
+    fn next_page(self) -> Self {
+        Self {
+            page: self.page + 1,
+            column: 0,
+            y: 0.0,
+        }
+    }
+}
+
+/// A page or column a table continues onto: the first body row placed there and, when the
+/// table repeats its header, the row index drawn again above it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TableContinuation {
+    pub table_id: BlockId,
+    pub page: usize,
+    pub column: usize,
+    pub first_row: usize,
+    pub repeated_header_row: Option<usize>,
+    block_index: usize,
+}
+
+/// A block's measured height and, for tables, the height of each row.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Measure {
+    height: f32,
+    rows: Vec<f32>,
+}
+
+/// Measured block heights keyed by content hash, plus the pagination derived from them.
+///
+/// `refresh` runs every frame but does nothing while the document keeps its revision and
+/// page geometry. After an edit only blocks whose content hash is new get measured and
+/// pagination restarts from the first block that changed.
+///
+/// With several columns, blocks are measured at the column width and fill each column of a
+/// page in turn; the last page's columns are balanced to even heights.
+#[derive(Debug, Clone, Default)]
+pub struct BlockLayoutCache {
+    /// Revision of the document the cache was last brought up to date with.
+    revision: Option<Revision>,
+    measures_by_hash: HashMap<u64, Measure>,
+    hashes: Vec<u64>,
+    heights: Vec<f32>,
+    row_heights: Vec<Vec<f32>>,
+    positions: Vec<BlockPosition>,
+    continuations: Vec<TableContinuation>,
+    text_width: f32,
+    text_height: f32,
+    /// Offset from the left margin and width of each column, in points.
+    columns: Vec<(f32, f32)>,
+    /// First block of the last page when its columns were balanced; pagination restarts
+    /// there at the latest, as the blocks after it no longer sit where plain flow put them.
+    balanced_from: Option<usize>,
+    page_count: usize,
+    total_height: f32,
+}
+
+impl BlockLayoutCache {
+    /// Brings the cache up to date with `document` and returns how many blocks were re-measured.
+    pub fn refresh(&mut self, document: &DocumentModel, page_size: (f32, f32)) -> usize {
+        let (text_width, text_height) = text_area(document, page_size);
+        let section = document.metadata.columns;
+        let columns = column_spans(text_width, section.count(), section.spacing);
+        if text_width != self.text_width
+            || text_height != self.text_height
+            || columns != self.columns
+        {
+            self.measures_by_hash.clear();
+            self.hashes.clear();
+            self.revision = None;
+            self.text_width = text_width;
+            self.text_height = text_height;
+            self.columns = columns;
+        }
+        if self.revision == Some(document.revision) {
+            return 0;
+        }
+        self.revision = Some(document.revision);
+        let column_width = self.column_span(0).1;
+
+        let mut measured = 0usize;
+        let mut first_changed = None;
+        let mut next_hashes = Vec::with_capacity(document.content.len());
+        let mut next_heights = Vec::with_capacity(document.content.len());
+        let mut next_rows = Vec::with_capacity(document.content.len());
+        for (index, block) in document.content.iter().enumerate() {
+            let hash = content_hash(block);
+            if self.hashes.get(index) != Some(&hash) && first_changed.is_none() {
+                first_changed = Some(index);
+            }
+            let measure = self.measures_by_hash.entry(hash).or_insert_with(|| {
+                measured += 1;
+                match block {
+                    Block::Table(table) => {
+                        let rows = measure_table_rows(table, column_width, 0);
+                        Measure {
+                            height: rows.iter().sum(),
+                            rows,
+                        }
+                    }
+                    _ => Measure {
+                        height: measure_block(block, column_width, 0),
+                        rows: Vec::new(),
+                    },
+                }
+            });
+            next_hashes.push(hash);
+            next_heights.push(measure.height);
+            next_rows.push(measure.rows.clone());
+        }
+        if first_changed.is_none() && next_hashes.len() != self.hashes.len() {
+            first_changed = Some(next_hashes.len().min(self.hashes.len()));
+        }
+
+        let live = next_hashes.iter().copied().collect::<HashSet<_>>();
+        self.measures_by_hash.retain(|hash, _| live.contains(hash));
+        self.hashes = next_hashes;
+        self.heights = next_heights;
+        self.row_heights = next_rows;
+        if let Some(start) = first_changed {
+            self.paginate_from(start, document);
+        }
+        measured
+    }
+
+    fn paginate_from(&mut self, start: usize, document: &DocumentModel) {
+        let start = self
+            .balanced_from
+            .take()
+            .map_or(start, |first| first.min(start));
+        self.positions.truncate(start);
+        self.continuations
+            .retain(|continuation| continuation.block_index < start);
+        let mut cursor = self.positions.last().copied().unwrap_or_default();
+        if let Some(prev) = start.checked_sub(1) {
+            // The previous block's continuations are kept; this only finds where it ends.
+            cursor = self.place(prev, &document.content[prev], cursor, &mut Vec::new());
+        }
+
+        let mut continuations = Vec::new();
+        for index in start..self.heights.len() {
+            let block = &document.content[index];
+            // A table only needs its first row to fit; the rest may continue on later pages.
+            let leading = self.row_heights[index]
+                .first()
+                .copied()
+                .unwrap_or(self.heights[index]);
+            if cursor.y > 0.0 && cursor.y + leading > self.text_height {
+                cursor = cursor.next_column(self.columns.len());
+            }
+            self.positions.push(cursor);
+            cursor = self.place(index, block, cursor, &mut continuations);
+        }
+        self.continuations.extend(continuations);
+        self.balance_last_page(document);
+
+        self.page_count = cursor.page + 1;
+        self.total_height = self.heights.iter().sum();
+    }
+
+    /// Evens out the columns of the last page, as Word does at the end of a section. Pages
+    /// whose first block doesn't start it, or holding page breaks or tables, keep plain flow.
+    fn balance_last_page(&mut self, document: &DocumentModel) {
+        let Some(last) = self.positions.last().map(|position| position.page) else {
+            return;
+        };
+        if self.columns.len() < 2 {
+            return;
+        }
+        let first = self
+            .positions
+            .iter()
+            .rposition(|position| position.page < last)
+            .map_or(0, |index| index + 1);
+        let starts_page = self.positions[first].column == 0 && self.positions[first].y == 0.0;
+        let plain = document.content[first..]
+            .iter()
+            .all(|block| !matches!(block, Block::PageBreak | Block::Table(_)));
+        let fits = self.heights[first..]
+            .iter()
+            .all(|height| *height <= self.text_height);
+        if !starts_page || !plain || !fits {
+            return;
+        }
+
+        let heights = &self.heights[first..];
+        let columns = balance_columns(heights, self.columns.len());
+        let mut at = BlockPosition {
+            page: last,
+            ..BlockPosition::default()
+        };
+        for (offset, (column, height)) in columns.into_iter().zip(heights).enumerate() {
+            if column != at.column {
+                at = BlockPosition {
+                    column,
+                    y: 0.0,
+                    ..at
+                };
+            }
+            self.positions[first + offset] = at;
+            at.y += height;
+        }
+        self.balanced_from = Some(first);
+    }
+
+    /// Lays out the block at `index` from `cursor` and returns where the next block starts.
+    /// Tables break between rows and record each page they continue onto.
+    fn place(
+        &self,
+        index: usize,
+        block: &Block,
+        cursor: BlockPosition,
+        continuations: &mut Vec<TableContinuation>,
+    ) -> BlockPosition {
+        let rows = &self.row_heights[index];
+        let Block::Table(table) = block else {
+            return self.advance(cursor, block, self.heights[index]);
+        };
+        if rows.is_empty() {
+            return self.advance(cursor, block, self.heights[index]);
+        }
+
+        let mut at = cursor;
+        for (row, height) in rows.iter().enumerate() {
+            if row > 0 && at.y > 0.0 && at.y + height > self.text_height {
+                at = at.next_column(self.columns.len());
+                let repeated_header_row = table.header_repeat.then_some(0);
+                if repeated_header_row.is_some() {
+                    at.y += rows[0];
+                }
+                continuations.push(TableContinuation {
+                    table_id: table.id,
+                    page: at.page,
+                    column: at.column,
+                    first_row: row,
+                    repeated_header_row,
+                    block_index: index,
+                });
+            }
+            at = self.advance(at, block, *height);
+        }
+        at
+    }
+
+    /// Where the block after one of `height` starting at `cursor` goes.
+    fn advance(&self, cursor: BlockPosition, block: &Block, height: f32) -> BlockPosition {
+        if matches!(block, Block::PageBreak) {
+            return cursor.next_page();
+        }
+
+        let mut next = BlockPosition {
+            y: cursor.y + height,
+            ..cursor
+        };
+        // Blocks taller than a column spill over into as many columns and pages as they need.
+        while next.y > self.text_height && self.text_height > 0.0 {
+            let overflow = next.y - self.text_height;
+            next = next.next_column(self.columns.len());
+            next.y = overflow;
+        }
+        next
+    }
+
+    /// Pages tables continue onto, in document order.
+    pub fn table_continuations(&self) -> &[TableContinuation] {
+        &self.continuations
+    }
+
+    /// Pages the measured content fills; at least one.
+    pub fn page_count(&self) -> usize {
+        self.page_count.max(1)
+    }
+
+    /// Page the block at `index` starts on, counting from zero.
+    pub fn block_page(&self, index: usize) -> Option<usize> {
+        self.positions.get(index).map(|position| position.page)
+    }
+
+    /// Page the block at `index` starts on and its offset below the top margin there.
+    pub fn block_top(&self, index: usize) -> Option<(usize, f32)> {
+        self.positions
+            .get(index)
+            .map(|position| (position.page, position.y))
+    }
+
+    /// Column of its page the block at `index` starts in, counting from zero.
+    pub fn block_column(&self, index: usize) -> Option<usize> {
+        self.positions.get(index).map(|position| position.column)
+    }
+
+    /// Offset from the left margin and width of `column`, in points.
+    pub fn column_span(&self, column: usize) -> (f32, f32) {
+        self.columns
+            .get(column)
+            .copied()
+            .unwrap_or((0.0, self.text_width))
+    }
+
+    /// Measured height of the block at `index`, over every page it spans.
+    pub fn block_height(&self, index: usize) -> Option<f32> {
+        self.heights.get(index).copied()
+    }
+
+    /// Offset of the block at `index` in the content laid out as one continuous column.
+    pub fn block_offset(&self, index: usize) -> Option<f32> {
+        (index < self.heights.len()).then(|| self.heights[..index].iter().sum())
+    }
+
+    /// Height of the content laid out as one continuous column, in points.
+    pub fn total_height(&self) -> f32 {
+        self.total_height
+    }
+}
+
+/// Splits a text area `width` wide into `count` equal columns `spacing` apart, as offsets from
+/// its left edge and widths. Works in points or pixels alike.
+pub fn column_spans(width: f32, count: usize, spacing: f32) -> Vec<(f32, f32)> {
+    let count = count.max(1);
+    let gaps = spacing.max(0.0) * (count - 1) as f32;
+    let column = ((width - gaps) / count as f32).max(1.0);
+    (0..count)
+        .map(|index| (index as f32 * (column + spacing.max(0.0)), column))
+        .collect()
+}
+
+/// Deals blocks of `heights`, in order, into at most `columns` columns so the tallest column
+/// is as short as it can be without splitting a block, and returns each block's column.
+pub fn balance_columns(heights: &[f32], columns: usize) -> Vec<usize> {
+    // Fills each column up to `limit`, returning every block's column.
+    let fill = |limit: f32| {
+        let mut column = 0;
+        let mut used = 0.0;
+        heights
+            .iter()
+            .map(|height| {
+                if used > 0.0 && used + height > limit {
+                    column += 1;
+                    used = 0.0;
+                }
+                used += height;
+                column
+            })
+            .collect::<Vec<_>>()
+    };
+    let columns = columns.max(1);
+    let mut low = heights.iter().copied().fold(0.0f32, f32::max);
+    let mut high = heights.iter().sum::<f32>().max(low);
+    // The shortest limit that still fits in `columns`, to well under a point.
+    for _ in 0..32 {
+        let mid = (low + high) * 0.5;
+        if fill(mid).last().is_some_and(|last| *last >= columns) {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    fill(high)
+}
+
+fn text_area(document: &DocumentModel, (page_width, page_height): (f32, f32)) -> (f32, f32) {
+    let margins = &document.metadata.margins;
+    let width = page_width - margin(margins.left) - margin(margins.right);
+    let height = page_height - margin(margins.top) - margin(margins.bottom);
+    (width.max(72.0), height.max(72.0))
+}
+
+/// Top-left corner of the text area on each page: the left and top margins, in points.
+pub fn text_origin(document: &DocumentModel) -> (f32, f32) {
+    let margins = &document.metadata.margins;
+    (margin(margins.left), margin(margins.top))
+}
+
+/// A margin as set, or an inch when the document leaves it unset.
+fn margin(value: f32) -> f32 {
+    if value > 0.0 { value } else { DEFAULT_MARGIN }
+}
+
+fn content_hash(block: &Block) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    hash_block(block, &mut hasher);
+    hasher.finish()
+}
+
+fn hash_block(block: &Block, hasher: &mut DefaultHasher) {
+    match block {
+        Block::Paragraph(paragraph) => {
+            0u8.hash(hasher);
+            hash_runs(&paragraph.runs, hasher);
+            paragraph.spacing.before.to_bits().hash(hasher);
+            paragraph.spacing.after.to_bits().hash(hasher);
+            paragraph.spacing.line.to_bits().hash(hasher);
+            paragraph.indent.left.to_bits().hash(hasher);
+            paragraph.indent.right.to_bits().hash(hasher);
+        }
+        Block::Heading(heading) => {
+            1u8.hash(hasher);
+            heading.level.hash(hasher);
+            hash_runs(&heading.runs, hasher);
+        }
+        Block::CodeBlock(code) => {
+            2u8.hash(hasher);
+            code.code.hash(hasher);
+        }
+        Block::List(list) => {
+            3u8.hash(hasher);
+            for item in &list.items {
+                item.content.len().hash(hasher);
+                for child in &item.content {
+                    hash_block(child, hasher);
+                }
+            }
+        }
+        Block::Table(table) => {
+            4u8.hash(hasher);
+            table.header_repeat.hash(hasher);
+            for height in &table.row_heights {
+                height.to_bits().hash(hasher);
+            }
+            for row in &table.rows {
+                row.cells.len().hash(hasher);
+                for cell in &row.cells {
+                    cell.blocks.len().hash(hasher);
+                    for child in &cell.blocks {
+                        hash_block(child, hasher);
+                    }
+                }
+            }
+        }
+        Block::Image(image) => {
+            5u8.hash(hasher);
+            image.width.to_bits().hash(hasher);
+            image.height.to_bits().hash(hasher);
+            image.original_width.hash(hasher);
+            image.original_height.hash(hasher);
+        }
+        Block::BlockQuote(quote) => {
+            6u8.hash(hasher);
+            quote.blocks.len().hash(hasher);
+            for child in &quote.blocks {
+                hash_block(child, hasher);
+            }
+        }
+        Block::PageBreak => 7u8.hash(hasher),
+        Block::HorizontalRule => 8u8.hash(hasher),
+    }
+}
+
+fn hash_runs(runs: &[Run], hasher: &mut DefaultHasher) {
+    runs.len().hash(hasher);
+    for run in runs {
+        run.text.hash(hasher);
+        run.style.font_size.map(f32::to_bits).hash(hasher);
+    }
+}
+
+/// `depth` counts the tables enclosing `block`; tables nested deeper than `MAX_TABLE_NESTING`
+/// are measured as a single placeholder row.
+fn measure_block(block: &Block, text_width: f32, depth: usize) -> f32 {
+    match block {
+        Block::Paragraph(paragraph) => {
+            let width =
+                text_width - paragraph.indent.left.max(0.0) - paragraph.indent.right.max(0.0);
+            measure_runs(&paragraph.runs, DEFAULT_FONT_SIZE, width)
+                + paragraph.spacing.before.max(0.0)
+                + paragraph.spacing.after.max(0.0)
+        }
+        Block::Heading(heading) => {
+            let size =
+                (24.0 - f32::from(heading.level.saturating_sub(1)) * 2.5).max(DEFAULT_FONT_SIZE);
+            measure_runs(&heading.runs, size, text_width) + size * 0.6
+        }
+        Block::CodeBlock(code) => {
+            let lines = code.code.lines().count().max(1);
+            lines as f32 * 10.0 * LINE_HEIGHT_FACTOR + 16.0
+        }
+        Block::List(list) => list
+            .items
+            .iter()
+            .flat_map(|item| item.content.iter())
+            .map(|child| measure_block(child, text_width - 18.0, depth))
+            .sum(),
+        Block::Table(_) if depth > MAX_TABLE_NESTING => DEFAULT_ROW_HEIGHT,
+        Block::Table(table) => measure_table_rows(table, text_width, depth).iter().sum(),
+        Block::Image(image) => {
+            let (width, height) = if image.width > 0.0 && image.height > 0.0 {
+                (image.width, image.height)
+            } else {
+                (image.original_width as f32, image.original_height as f32)
+            };
+            if width <= 0.0 {
+                return height.max(0.0);
+            }
+            height * (text_width / width).min(1.0)
+        }
+        Block::BlockQuote(quote) => quote
+            .blocks
+            .iter()
+            .map(|child| measure_block(child, text_width - QUOTE_INDENT, depth))
+            .sum(),
+        Block::PageBreak => 0.0,
+        Block::HorizontalRule => RULE_HEIGHT,
+    }
+}
+
+fn measure_table_rows(table: &Table, text_width: f32, depth: usize) -> Vec<f32> {
+    let columns = table
+        .rows
+        .iter()
+        .map(|row| row.cells.len())
+        .max()
+        .unwrap_or(1)
+        .max(1);
+    let cell_width = text_width / columns as f32;
+    table
+        .rows
+        .iter()
+        .enumerate()
+        .map(|(index, row)| {
+            let content = row
+                .cells
+                .iter()
+                .map(|cell| {
+                    cell.blocks
+                        .iter()
+                        .map(|child| measure_block(child, cell_width, depth + 1))
+                        .sum::<f32>()
+                })
+                .fold(0.0f32, f32::max);
+            let fixed = table.row_heights.get(index).copied().unwrap_or(0.0);
+            content.max(fixed).max(DEFAULT_ROW_HEIGHT)
+        })
+        .collect()
+}
+
+/// Estimates wrapped text height from character counts; exact shaping happens at draw time.
+fn measure_runs(runs: &[Run], base_size: f32, width: f32) -> f32 {
+    let size = runs
+        .iter()
+        .filter_map(|run| run.style.font_size)
+        .fold(base_size, f32::max);
+    let line_height = size * LINE_HEIGHT_FACTOR;
+    let chars_per_line = (width.max(1.0) / (size * AVERAGE_CHAR_WIDTH_FACTOR)).max(1.0);
+
+    let text = runs.iter().map(|run| run.text.as_str()).collect::<String>();
+    let lines = text
+        .split('\n')
+        .map(|line| {
+            (line.chars().count() as f32 / chars_per_line)
+                .ceil()
+                .max(1.0)
+        })
+        .sum::<f32>();
+    lines * line_height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BlockLayoutCache, DEFAULT_ROW_HEIGHT, balance_columns, column_spans, measure_block,
+        quote_geometry,
+    };
+    use crate::document::model::{
+        Block, BlockId, BlockQuote, DocumentModel, Indent, Paragraph, ParagraphAlignment,
+        ParagraphSpacing, Run, SectionColumns, Table, TableCell, TableRow,
+    };
+
+    const LETTER: (f32, f32) = (612.0, 792.0);
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    #[test]
+    fn editing_one_block_only_remeasures_that_block() {
+        let mut document = DocumentModel::default();
+        document.content = vec![
+            paragraph(1, "alpha"),
+            paragraph(2, "beta"),
+            paragraph(3, "gamma"),
+        ];
+        let mut cache = BlockLayoutCache::default();
+        assert_eq!(cache.refresh(&document, LETTER), 3);
+        assert_eq!(cache.refresh(&document, LETTER), 0);
+        let before = cache.heights.clone();
+
+        if let Block::Paragraph(p) = &mut document.content[1] {
+            p.runs[0].text = "beta ".repeat(200);
+        }
+        // Changes only show once the document takes a new revision.
+        assert_eq!(cache.refresh(&document, LETTER), 0);
+        assert_eq!(cache.heights[1], before[1]);
+        document.mark_edited();
+        assert_eq!(cache.refresh(&document, LETTER), 1);
+        assert_eq!(cache.heights[0], before[0]);
+        assert_eq!(cache.heights[2], before[2]);
+        assert!(cache.heights[1] > before[1]);
+    }
+
+    #[test]
+    fn long_content_and_page_breaks_add_pages() {
+        let mut document = DocumentModel::default();
+        document.content = (0..200).map(|id| paragraph(id, "line of text")).collect();
+        let mut cache = BlockLayoutCache::default();
+        cache.refresh(&document, LETTER);
+        let pages = cache.page_count();
+        assert!(pages > 1);
+
+        document.content.insert(1, Block::PageBreak);
+        document.mark_edited();
+        assert_eq!(cache.refresh(&document, LETTER), 1);
+        assert_eq!(cache.page_count(), pages + 1);
+        assert_eq!(cache.block_page(0), Some(0));
+        assert_eq!(cache.block_page(2), Some(1));
+        assert_eq!(cache.block_page(201), None);
+
+        document.content.truncate(1);
+        document.mark_edited();
+        assert_eq!(cache.refresh(&document, LETTER), 0);
+        assert_eq!(cache.page_count(), 1);
+    }
+
+    #[test]
+    fn continued_tables_repeat_the_header_row_on_each_new_page() {
+        // 80 rows of 24pt on a Letter page with 1" margins: 27 rows fit on a 648pt page.
+        let table = Table {
+            id: BlockId(7),
+            rows: (0..80)
+                .map(|_| TableRow {
+                    cells: vec![TableCell::default(), TableCell::default()],
+                })
+                .collect(),
+            header_repeat: true,
+            ..Table::default()
+        };
+        let mut document = DocumentModel::default();
+        document.content = vec![Block::Table(table)];
+        let mut cache = BlockLayoutCache::default();
+        cache.refresh(&document, LETTER);
+
+        let boundaries = cache
+            .table_continuations()
+            .iter()
+            .map(|c| (c.table_id, c.page, c.first_row, c.repeated_header_row))
+            .collect::<Vec<_>>();
+        // Each continued page spends one row's height on the repeated header.
+        assert_eq!(
+            boundaries,
+            [
+                (BlockId(7), 1, 27, Some(0)),
+                (BlockId(7), 2, 53, Some(0)),
+                (BlockId(7), 3, 79, Some(0)),
+            ]
+        );
+        assert_eq!(cache.page_count(), 4);
+
+        if let Block::Table(table) = &mut document.content[0] {
+            table.header_repeat = false;
+        }
+        document.mark_edited();
+        cache.refresh(&document, LETTER);
+        let boundaries = cache
+            .table_continuations()
+            .iter()
+            .map(|c| (c.page, c.first_row, c.repeated_header_row))
+            .collect::<Vec<_>>();
+        assert_eq!(boundaries, [(1, 27, None), (2, 54, None)]);
+        assert_eq!(cache.page_count(), 3);
+    }
+
+    #[test]
+    fn balanced_columns_keep_the_tallest_column_short() {
+        assert_eq!(balance_columns(&[10.0; 4], 2), [0, 0, 1, 1]);
+        // Blocks stay whole, so a tall first block gets a column to itself.
+        assert_eq!(balance_columns(&[30.0, 10.0, 10.0, 10.0], 2), [0, 1, 1, 1]);
+        assert_eq!(balance_columns(&[10.0; 7], 3), [0, 0, 0, 1, 1, 1, 2]);
+        assert_eq!(
+            balance_columns(&[20.0, 20.0, 10.0, 10.0, 10.0, 10.0], 2),
+            [0, 0, 1, 1, 1, 1]
+        );
+        // Too few blocks to go round leave the last columns empty.
+        assert_eq!(balance_columns(&[10.0; 3], 2), [0, 0, 1]);
+        assert_eq!(balance_columns(&[10.0; 3], 1), [0, 0, 0]);
+        assert!(balance_columns(&[], 2).is_empty());
+
+        assert_eq!(column_spans(468.0, 2, 36.0), [(0.0, 216.0), (252.0, 216.0)]);
+        assert_eq!(column_spans(468.0, 1, 36.0), [(0.0, 468.0)]);
+    }
+
+    #[test]
+    fn two_columns_fill_in_turn_and_balance_the_last_page() {
+        // Each paragraph is one 14.85pt line; 43 of them fill a 648pt column.
+        let mut document = DocumentModel::default();
+        document.metadata.columns = SectionColumns::new(2);
+        document.content = (0..100).map(|id| paragraph(id, "line of text")).collect();
+        let mut cache = BlockLayoutCache::default();
+        cache.refresh(&document, LETTER);
+
+        assert_eq!(cache.page_count(), 2);
+        assert_eq!(cache.column_span(1), (252.0, 216.0));
+        assert_eq!(cache.block_column(42), Some(0));
+        assert_eq!(cache.block_column(43), Some(1));
+        assert_eq!(cache.block_top(43), Some((0, 0.0)));
+        // The 14 blocks left over split seven and seven rather than filling one column.
+        assert_eq!(cache.block_page(86), Some(1));
+        assert_eq!(cache.block_column(92), Some(0));
+        assert_eq!(cache.block_top(93), Some((1, 0.0)));
+        assert_eq!(cache.block_column(93), Some(1));
+
+        // Adding a block rebalances the page, wherever pagination restarts.
+        document.content.push(paragraph(100, "line of text"));
+        document.mark_edited();
+        assert_eq!(cache.refresh(&document, LETTER), 0);
+        assert_eq!(cache.block_column(93), Some(0));
+        assert_eq!(cache.block_column(94), Some(1));
+        assert_eq!(cache.block_page(100), Some(1));
+
+        document.metadata.columns = SectionColumns::new(1);
+        cache.refresh(&document, LETTER);
+        assert_eq!(cache.page_count(), 3);
+        assert_eq!(cache.block_column(93), Some(0));
+    }
+
+    fn table_of(id: u64, rows: usize, cell: impl Fn(usize) -> Vec<Block>) -> Block {
+        Block::Table(Table {
+            id: BlockId(id),
+            rows: (0..rows)
+                .map(|row| TableRow {
+                    cells: vec![TableCell {
+                        blocks: cell(row),
+                        rowspan: 1,
+                        colspan: 1,
+                        background: None,
+                    }],
+                })
+                .collect(),
+            column_widths: vec![200.0],
+            row_heights: vec![30.0; rows],
+            ..Table::default()
+        })
+    }
+
+    #[test]
+    fn nested_tables_grow_their_cell_up_to_the_nesting_cap() {
+        let inner = table_of(3, 3, |row| vec![paragraph(10 + row as u64, "x")]);
+        assert_eq!(measure_block(&inner, 400.0, 0), 90.0);
+
+        // The outer row holds the whole inner table plus the paragraph above it.
+        let outer = table_of(1, 2, |row| {
+            if row == 0 {
+                vec![paragraph(2, "caption"), inner.clone()]
+            } else {
+                Vec::new()
+            }
+        });
+        let caption = measure_block(&paragraph(2, "caption"), 400.0, 1);
+        assert_eq!(measure_block(&outer, 400.0, 0), caption + 90.0 + 30.0);
+
+        // Tables nested past the cap collapse to one placeholder row instead of recursing.
+        let mut deep = inner.clone();
+        for level in 0..8 {
+            deep = table_of(20 + level, 1, |_| vec![deep.clone()]);
+        }
+        let height = measure_block(&deep, 400.0, 0);
+        assert!(height.is_finite());
+        assert_eq!(measure_block(&inner, 400.0, 5), DEFAULT_ROW_HEIGHT);
+        // The placeholder fits inside the 30pt rows of the levels above it.
+        assert_eq!(height, 30.0);
+    }
+
+    #[test]
+    fn nested_quotes_indent_and_add_a_bar_per_level() {
+        let geometry = quote_geometry(2);
+        assert_eq!(geometry.bars, vec![(4.0, 7.0), (28.0, 31.0)]);
+        assert_eq!(geometry.text_inset, 48.0);
+        assert_eq!(quote_geometry(0), Default::default());
+
+        // Text two quotes deep wraps in the column left inside both indents.
+        let text = paragraph(3, &"quoted words ".repeat(40));
+        let quote = |id, blocks| {
+            Block::BlockQuote(BlockQuote {
+                id: BlockId(id),
+                blocks,
+                callout: None,
+            })
+        };
+        let nested = quote(1, vec![quote(2, vec![text.clone()])]);
+        assert_eq!(
+            measure_block(&nested, 400.0, 0),
+            measure_block(&text, 400.0 - geometry.text_inset, 0)
+        );
+        assert!(measure_block(&nested, 400.0, 0) > measure_block(&text, 400.0, 0));
+    }
+}
@@ -1,8 +1,12 @@
 use std::collections::HashMap;
 
 use crate::{
-    document::model::DocumentModel,
-    render::animation::{Animation, Easing},
+    document::model::{BlockId, DocumentModel},
+    editor::table::TableView,
+    render::{
+        animation::{Animation, Easing},
+        block_layout::{BlockLayoutCache, text_origin},
+    },
     ui::{Point, Rect, Size},
 };
 
@@ -10,6 +14,17 @@ pub const PAGE_GAP: f32 = 24.0;
 pub const ZOOM_MIN: f32 = 0.25;
 pub const ZOOM_MAX: f32 = 5.0;
 pub const ZOOM_DEFAULT: f32 = 1.0;
+/// Height of the strip along the canvas bottom that grabs the horizontal scrollbar.
+pub const HSCROLL_HIT_HEIGHT: f32 = 12.0;
+const SCROLLBAR_MIN_THUMB: f32 = 28.0;
+/// Band inside the top and bottom edges where a selection drag starts auto-scrolling.
+pub const EDGE_SCROLL_ZONE: f32 = 24.0;
+const EDGE_SCROLL_MIN_SPEED: f32 = 120.0;
+/// Extra px/s for every pixel the pointer goes deeper into the band or past the edge.
+const EDGE_SCROLL_ACCEL: f32 = 18.0;
+const EDGE_SCROLL_MAX_SPEED: f32 = 2400.0;
+/// Context kept on screen by Page Up/Page Down so the reader doesn't lose their place.
+const PAGE_SCROLL_OVERLAP: f32 = 40.0;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum PageLayoutMode {
@@ -34,6 +49,45 @@ pub struct ScrollState {
     pub velocity_y: f32,
 }
 
+/// How the mouse wheel scrolls and zooms, from the editor's scroll settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WheelBehavior {
+    /// Multiplies how far one notch scrolls.
+    pub scroll_speed: f32,
+    /// Inverts the wheel, so the page follows the fingers as on a touchpad.
+    pub natural: bool,
+    /// Multiplies how far one Ctrl+wheel notch zooms.
+    pub zoom_sensitivity: f32,
+}
+
+impl Default for WheelBehavior {
+    fn default() -> Self {
+        Self {
+            scroll_speed: 1.0,
+            natural: false,
+            zoom_sensitivity: 1.0,
+        }
+    }
+}
+
+impl WheelBehavior {
+    /// How far `notches` of wheel travel scroll, positive towards the end of the document.
+    pub fn scroll_delta(&self, notches: f32) -> f32 {
+        let delta = -notches * 3.0 * self.scroll_speed;
+        if self.natural { -delta } else { delta }
+    }
+
+    /// The factor one Ctrl+wheel notch in the direction of `notches` multiplies zoom by.
+    pub fn zoom_factor(&self, notches: f32) -> f32 {
+        let step = 0.1 * self.zoom_sensitivity;
+        if notches > 0.0 {
+            1.0 + step
+        } else {
+            1.0 - step
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct ScrollbarState {
     pub visible: bool,
@@ -66,9 +120,15 @@ pub struct CanvasState {
     pub scroll_anim_y: Option<Animation>,
     pub show_margin_guides: bool,
     pub reduce_motion: bool,
+    pub wheel: WheelBehavior,
     pub scrollbar: ScrollbarState,
+    /// Auto-scroll in px/s while a selection drag holds the pointer at an edge.
+    pub edge_scroll: f32,
     pub cursor: CursorVisualState,
     pub page_cache: HashMap<usize, CachedPage>,
+    pub block_layout: BlockLayoutCache,
+    /// Horizontal scroll and frozen panes of each table overlay.
+    pub table_views: HashMap<BlockId, TableView>,
     pub dirty_rects: Vec<Rect>,
 }
 
@@ -88,17 +148,21 @@ impl Default for CanvasState {
             scroll_anim_y: None,
             show_margin_guides: false,
             reduce_motion: false,
+            wheel: WheelBehavior::default(),
             scrollbar: ScrollbarState {
                 visible: false,
                 alpha: 0.0,
                 thickness: 6.0,
                 idle_seconds: 0.0,
             },
+            edge_scroll: 0.0,
             cursor: CursorVisualState {
                 blink_timer_s: 0.0,
                 visible: true,
             },
             page_cache: HashMap::new(),
+            block_layout: BlockLayoutCache::default(),
+            table_views: HashMap::new(),
             dirty_rects: Vec::new(),
         }
     }
@@ -132,6 +196,12 @@ impl CanvasState {
         self.set_zoom(target, None);
     }
 
+    /// Applies `preset` against the page size of `document`.
+    pub fn fit_document(&mut self, preset: ZoomPreset, document: &DocumentModel) {
+        let (width, height) = page_dimensions_points(document);
+        self.apply_zoom_preset(preset, Size { width, height });
+    }
+
     pub fn set_zoom(&mut self, target_zoom: f32, cursor_pos: Option<Point>) {
         let clamped = target_zoom.clamp(ZOOM_MIN, ZOOM_MAX);
 
@@ -160,10 +230,9 @@ impl CanvasState {
 
     pub fn handle_mouse_wheel(&mut self, delta: f32, ctrl_down: bool, cursor: Point) {
         if ctrl_down {
-            let step = if delta > 0.0 { 0.1 } else { -0.1 };
-            self.set_zoom(self.zoom * (1.0 + step), Some(cursor));
+            self.set_zoom(self.zoom * self.wheel.zoom_factor(delta), Some(cursor));
         } else {
-            let impulse = -delta * 3.0;
+            let impulse = self.wheel.scroll_delta(delta);
             self.scroll.velocity_y += impulse;
             if self.reduce_motion {
                 self.scroll.y += impulse * 0.35;
@@ -184,7 +253,7 @@ impl CanvasState {
     }
 
     pub fn handle_horizontal_wheel(&mut self, delta: f32) {
-        let impulse = -delta * 3.0;
+        let impulse = self.wheel.scroll_delta(delta);
         self.scroll.velocity_x += impulse;
         if self.reduce_motion {
             self.scroll.x += impulse * 0.35;
@@ -203,6 +272,62 @@ impl CanvasState {
         self.mark_dirty_full();
     }
 
+    /// The horizontal thumb in canvas coordinates, or None when the content fits.
+    pub fn horizontal_thumb_rect(&self, content_width: f32) -> Option<Rect> {
+        let (offset, length) = scrollbar_thumb(self.viewport.width, content_width, self.scroll.x)?;
+        Some(Rect {
+            x: offset + 2.0,
+            y: self.viewport.height - self.scrollbar.thickness - 2.0,
+            width: (length - 2.0).max(1.0),
+            height: self.scrollbar.thickness,
+        })
+    }
+
+    /// Scrolls so the horizontal thumb starts `offset` along its track.
+    pub fn drag_horizontal_thumb(&mut self, content_width: f32, offset: f32) {
+        self.scroll.x = scroll_for_thumb_offset(self.viewport.width, content_width, offset);
+        self.scroll.velocity_x = 0.0;
+        self.scroll_anim_x = None;
+        self.show_scrollbar();
+    }
+
+    /// Moves the content with the pointer, as when panning with the middle button.
+    pub fn pan_by(&mut self, dx: f32, dy: f32) {
+        self.scroll.x -= dx;
+        self.scroll.y -= dy;
+        self.scroll.velocity_x = 0.0;
+        self.scroll.velocity_y = 0.0;
+        self.scroll_anim_x = None;
+        self.scroll_anim_y = None;
+        self.show_scrollbar();
+    }
+
+    /// How far Page Up/Page Down scrolls: a viewport height, less a little overlap.
+    pub fn page_scroll_delta(&self) -> f32 {
+        (self.viewport.height - PAGE_SCROLL_OVERLAP).max(self.viewport.height * 0.5)
+    }
+
+    /// Scrolls by whole pages of the viewport; negative counts scroll up.
+    pub fn scroll_pages(&mut self, pages: f32, document: &DocumentModel) {
+        self.scroll_to_y(self.scroll.y + pages * self.page_scroll_delta(), document);
+    }
+
+    /// Jumps straight to `y`, kept within the document.
+    pub fn scroll_to_y(&mut self, y: f32, document: &DocumentModel) {
+        self.scroll.y = y;
+        self.scroll.velocity_y = 0.0;
+        self.scroll_anim_y = None;
+        self.clamp_scroll(document);
+        self.show_scrollbar();
+    }
+
+    fn show_scrollbar(&mut self) {
+        self.scrollbar.visible = true;
+        self.scrollbar.alpha = self.scrollbar.alpha.max(0.65);
+        self.scrollbar.idle_seconds = 0.0;
+        self.mark_dirty_full();
+    }
+
     pub fn content_size(&self, document: &DocumentModel) -> Size {
         let (page_width, page_height) = page_dimensions_points(document);
         let scaled_w = page_width * self.zoom;
@@ -211,14 +336,14 @@ impl CanvasState {
         match self.layout_mode {
             PageLayoutMode::ReadMode => Size {
                 width: (self.viewport.width * 0.88).max(540.0),
-                height: (document.content.len() as f32 * 26.0 * self.zoom).max(self.viewport.height),
+                height: self.read_mode_height(),
             },
             PageLayoutMode::SinglePage => Size {
                 width: scaled_w,
                 height: scaled_h,
             },
             PageLayoutMode::Continuous => {
-                let page_count = document.pages.len().max(1);
+                let page_count = self.page_count(document);
                 Size {
                     width: scaled_w,
                     height: (page_count as f32 * scaled_h)
@@ -228,6 +353,16 @@ impl CanvasState {
         }
     }
 
+    /// Pages in the continuous layout, from the block layout cache unless the document
+    /// carries more explicit pages.
+    fn page_count(&self, document: &DocumentModel) -> usize {
+        document.pages.len().max(self.block_layout.page_count())
+    }
+
+    fn read_mode_height(&self) -> f32 {
+        (self.block_layout.total_height() * self.zoom).max(self.viewport.height)
+    }
+
     pub fn clamp_scroll(&mut self, document: &DocumentModel) {
         let content = self.content_size(document);
         let max_x = (content.width - self.viewport.width).max(0.0);
@@ -277,6 +412,13 @@ impl CanvasState {
             }
         }
 
+        if self.edge_scroll != 0.0 {
+            self.scroll.y += self.edge_scroll * dt_s;
+            self.scroll_anim_y = None;
+            self.show_scrollbar();
+            animating = true;
+        }
+
         self.cursor.blink_timer_s += dt_s;
         if self.cursor.blink_timer_s >= 0.53 {
             self.cursor.blink_timer_s = 0.0;
@@ -327,7 +469,7 @@ impl CanvasState {
                 x: -self.scroll.x,
                 y: -self.scroll.y,
                 width: (self.viewport.width * 0.88).max(540.0),
-                height: (document.content.len() as f32 * 26.0 * self.zoom).max(self.viewport.height),
+                height: self.read_mode_height(),
             }],
             PageLayoutMode::SinglePage => vec![Rect {
                 x: ((self.viewport.width - scaled_w) * 0.5).max(0.0) - self.scroll.x,
@@ -336,7 +478,7 @@ impl CanvasState {
                 height: scaled_h,
             }],
             PageLayoutMode::Continuous => {
-                let page_count = document.pages.len().max(1);
+                let page_count = self.page_count(document);
                 let left = ((self.viewport.width - scaled_w) * 0.5).max(0.0) - self.scroll.x;
                 (0..page_count)
                     .map(|i| Rect {
@@ -350,6 +492,36 @@ impl CanvasState {
         }
     }
 
+    /// Where the top-level block at `index` sits on screen: in the text column of the page the
+    /// layout flowed it onto, as tall as its measured height. `None` when the block is on a
+    /// page the current layout doesn't show, or hasn't been laid out yet.
+    pub fn block_rect(&self, document: &DocumentModel, index: usize) -> Option<Rect> {
+        // Read mode runs every column's text down one long first column.
+        let (page, column, y) = match self.layout_mode {
+            PageLayoutMode::ReadMode => (0, 0, self.block_layout.block_offset(index)?),
+            _ => {
+                let (page, y) = self.block_layout.block_top(index)?;
+                (page, self.block_layout.block_column(index)?, y)
+            }
+        };
+        let page_rect = self.page_rects(document).get(page).copied()?;
+        let (left, top) = text_origin(document);
+        let (column_x, column_width) = self.block_layout.column_span(column);
+        Some(Rect {
+            x: page_rect.x + (left + column_x) * self.zoom,
+            y: page_rect.y + (top + y) * self.zoom,
+            width: column_width * self.zoom,
+            height: self.block_layout.block_height(index)? * self.zoom,
+        })
+    }
+
+    /// Lays `document` out into pages again; only blocks that changed are re-measured.
+    pub fn refresh_layout(&mut self, document: &DocumentModel) {
+        let _ = self
+            .block_layout
+            .refresh(document, page_dimensions_points(document));
+    }
+
     pub fn visible_page_indices(&self, document: &DocumentModel) -> Vec<usize> {
         self.page_rects(document)
             .into_iter()
@@ -362,6 +534,7 @@ impl CanvasState {
     }
 
     pub fn cull_and_cache_visible_pages(&mut self, document: &DocumentModel) -> Vec<usize> {
+        self.refresh_layout(document);
         let visible = self.visible_page_indices(document);
         let bucket = (self.zoom * 100.0) as u16;
 
@@ -397,7 +570,45 @@ impl CanvasState {
     }
 }
 
-fn page_dimensions_points(document: &DocumentModel) -> (f32, f32) {
+/// Auto-scroll speed in px/s for a selection drag with the pointer at `y` in a canvas
+/// `height` tall: negative toward the top, positive toward the bottom, zero in between.
+pub fn edge_scroll_speed(y: f32, height: f32) -> f32 {
+    let (depth, direction) = if y < EDGE_SCROLL_ZONE {
+        (EDGE_SCROLL_ZONE - y, -1.0)
+    } else if y > height - EDGE_SCROLL_ZONE {
+        (y - (height - EDGE_SCROLL_ZONE), 1.0)
+    } else {
+        return 0.0;
+    };
+    direction * (EDGE_SCROLL_MIN_SPEED + depth * EDGE_SCROLL_ACCEL).min(EDGE_SCROLL_MAX_SPEED)
+}
+
+/// Thumb offset and length along a scrollbar track, or None when the content fits.
+pub fn scrollbar_thumb(viewport: f32, content: f32, scroll: f32) -> Option<(f32, f32)> {
+    let viewport = viewport.max(1.0);
+    if content <= viewport + 1.0 {
+        return None;
+    }
+    let length = ((viewport / content) * viewport).clamp(
+        SCROLLBAR_MIN_THUMB,
+        (viewport - 6.0).max(SCROLLBAR_MIN_THUMB),
+    );
+    let max_track = (viewport - length).max(1.0);
+    let max_scroll = (content - viewport).max(1.0);
+    let offset = (scroll / max_scroll).clamp(0.0, 1.0) * max_track;
+    Some((offset, length))
+}
+
+/// The scroll position whose thumb starts at `offset`; the inverse of [`scrollbar_thumb`].
+pub fn scroll_for_thumb_offset(viewport: f32, content: f32, offset: f32) -> f32 {
+    let Some((_, length)) = scrollbar_thumb(viewport, content, 0.0) else {
+        return 0.0;
+    };
+    let max_track = (viewport.max(1.0) - length).max(1.0);
+    (offset / max_track).clamp(0.0, 1.0) * (content - viewport.max(1.0))
+}
+
+pub fn page_dimensions_points(document: &DocumentModel) -> (f32, f32) {
     use crate::document::model::PageSize;
 
     match document.metadata.page_size {
@@ -413,7 +624,11 @@ fn page_dimensions_points(document: &DocumentModel) -> (f32, f32) {
 
 #[cfg(test)]
 mod tests {
-    use super::{CanvasState, Point};
+    use super::{
+        CanvasState, EDGE_SCROLL_ZONE, PageLayoutMode, Point, WheelBehavior, edge_scroll_speed,
+        scroll_for_thumb_offset, scrollbar_thumb,
+    };
+    use crate::document::model::{Block, BlockId, DocumentModel, Paragraph, Run};
 
     #[test]
     fn scrollbar_waits_before_fading_out() {
@@ -428,6 +643,140 @@ mod tests {
         assert!(canvas.scrollbar.alpha < 1.0);
     }
 
+    #[test]
+    fn wheel_speed_scales_scrolling_and_natural_scrolling_inverts_it() {
+        let default = WheelBehavior::default();
+        // Rolling the wheel towards you (negative) scrolls down the page.
+        assert_eq!(default.scroll_delta(-1.0), 3.0);
+        let fast = WheelBehavior {
+            scroll_speed: 2.0,
+            ..default
+        };
+        assert_eq!(fast.scroll_delta(-1.0), 6.0);
+        let natural = WheelBehavior {
+            natural: true,
+            ..fast
+        };
+        assert_eq!(natural.scroll_delta(-1.0), -6.0);
+
+        let mut canvas = CanvasState::default();
+        canvas.reduce_motion = true;
+        canvas.wheel = natural;
+        canvas.handle_mouse_wheel(1.0, false, Point { x: 40.0, y: 40.0 });
+        assert_eq!(canvas.scroll.velocity_y, 6.0);
+        canvas.handle_horizontal_wheel(1.0);
+        assert_eq!(canvas.scroll.velocity_x, 6.0);
+
+        // Zoom sensitivity is separate from scroll speed.
+        canvas.wheel.zoom_sensitivity = 2.0;
+        canvas.handle_mouse_wheel(1.0, true, Point { x: 40.0, y: 40.0 });
+        assert!((canvas.zoom - 1.2).abs() < 1e-4);
+        assert_eq!(canvas.scroll.velocity_y, 6.0);
+    }
+
+    #[test]
+    fn scrollbar_thumb_tracks_content_viewport_and_scroll() {
+        assert_eq!(scrollbar_thumb(800.0, 800.0, 0.0), None);
+
+        let (offset, length) = scrollbar_thumb(800.0, 1600.0, 0.0).unwrap();
+        assert_eq!((offset, length), (0.0, 400.0));
+        let (offset, _) = scrollbar_thumb(800.0, 1600.0, 400.0).unwrap();
+        assert_eq!(offset, 200.0);
+        let (offset, _) = scrollbar_thumb(800.0, 1600.0, 800.0).unwrap();
+        assert_eq!(offset, 400.0, "the thumb stops at the end of the track");
+
+        let (_, length) = scrollbar_thumb(800.0, 100_000.0, 0.0).unwrap();
+        assert_eq!(length, 28.0, "very wide content keeps a grabbable thumb");
+        assert_eq!(scroll_for_thumb_offset(800.0, 1600.0, 200.0), 400.0);
+    }
+
+    #[test]
+    fn dragging_the_horizontal_thumb_and_panning_scroll_the_canvas() {
+        let mut canvas = CanvasState::default();
+        canvas.set_viewport(800.0, 600.0);
+        assert!(canvas.horizontal_thumb_rect(600.0).is_none());
+
+        canvas.drag_horizontal_thumb(1600.0, 200.0);
+        assert_eq!(canvas.scroll.x, 400.0);
+        let thumb = canvas.horizontal_thumb_rect(1600.0).unwrap();
+        assert_eq!(thumb.x, 202.0);
+        assert_eq!(thumb.y, 600.0 - canvas.scrollbar.thickness - 2.0);
+
+        canvas.pan_by(50.0, -30.0);
+        assert_eq!(canvas.scroll.x, 350.0);
+        assert_eq!(canvas.scroll.y, 30.0);
+        assert!(canvas.scrollbar.visible);
+    }
+
+    #[test]
+    fn page_scrolling_moves_a_viewport_less_the_overlap() {
+        let mut canvas = CanvasState::default();
+        canvas.set_viewport(800.0, 600.0);
+        assert_eq!(canvas.page_scroll_delta(), 560.0);
+
+        canvas.set_viewport(800.0, 60.0);
+        assert_eq!(
+            canvas.page_scroll_delta(),
+            30.0,
+            "tiny viewports still make progress"
+        );
+    }
+
+    #[test]
+    fn page_and_jump_scrolls_stop_at_the_document_ends() {
+        let document = DocumentModel::default();
+        let mut canvas = CanvasState::default();
+        canvas.set_viewport(800.0, 600.0);
+        let max_y = canvas.content_size(&document).height - 600.0;
+        assert!(max_y > 0.0, "a page is taller than the viewport");
+
+        canvas.scroll_pages(-3.0, &document);
+        assert_eq!(canvas.scroll.y, 0.0);
+
+        canvas.scroll_to_y(f32::MAX, &document);
+        assert_eq!(canvas.scroll.y, max_y);
+        canvas.scroll_pages(2.0, &document);
+        assert_eq!(canvas.scroll.y, max_y);
+        assert!(canvas.scroll_anim_y.is_none());
+
+        canvas.scroll_to_y(0.0, &document);
+        assert_eq!(canvas.scroll.y, 0.0);
+    }
+
+    #[test]
+    fn edge_scroll_speeds_up_with_distance_past_the_edge() {
+        assert_eq!(edge_scroll_speed(300.0, 600.0), 0.0);
+        assert_eq!(edge_scroll_speed(EDGE_SCROLL_ZONE, 600.0), 0.0);
+
+        let near_top = edge_scroll_speed(EDGE_SCROLL_ZONE - 1.0, 600.0);
+        let above_top = edge_scroll_speed(-40.0, 600.0);
+        assert!(near_top < 0.0);
+        assert!(above_top < near_top, "further out scrolls faster");
+
+        let near_bottom = edge_scroll_speed(590.0, 600.0);
+        let below_bottom = edge_scroll_speed(700.0, 600.0);
+        assert!(near_bottom > 0.0);
+        assert!(below_bottom > near_bottom);
+        assert_eq!(
+            edge_scroll_speed(-40.0, 600.0),
+            -edge_scroll_speed(640.0, 600.0)
+        );
+        assert_eq!(
+            edge_scroll_speed(100_000.0, 600.0),
+            2400.0,
+            "speed is capped"
+        );
+    }
+
+    #[test]
+    fn edge_scroll_advances_the_view_each_tick() {
+        let mut canvas = CanvasState::default();
+        canvas.edge_scroll = 300.0;
+        assert!(canvas.update(0.5));
+        assert_eq!(canvas.scroll.y, 150.0);
+        assert!(canvas.scrollbar.visible);
+    }
+
     #[test]
     fn reduce_motion_disables_zoom_animation() {
         let mut canvas = CanvasState::default();
@@ -436,4 +785,73 @@ mod tests {
         assert!(canvas.zoom_anim.is_none());
         assert_eq!(canvas.zoom, 1.5);
     }
+
+    fn paragraphs(count: u64) -> Vec<Block> {
+        (0..count)
+            .map(|id| {
+                Block::Paragraph(Paragraph {
+                    id: BlockId(id),
+                    runs: vec![Run {
+                        text: "line of text".to_string(),
+                        ..Run::default()
+                    }],
+                    alignment: Default::default(),
+                    spacing: Default::default(),
+                    indent: Default::default(),
+                    style_id: None,
+                    direction: Default::default(),
+                    frame: None,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn content_taller_than_a_page_flows_onto_a_second_page() {
+        let mut document = DocumentModel::default();
+        // One line each; 50 of them overflow a Letter page's 648pt text area.
+        document.content = paragraphs(50);
+        let mut canvas = CanvasState::default();
+        canvas.set_viewport(800.0, 600.0);
+        canvas.refresh_layout(&document);
+
+        let pages = canvas.page_rects(&document);
+        assert_eq!(pages.len(), 2);
+        let first = canvas.block_rect(&document, 0).expect("first block");
+        assert_eq!((first.x, first.y), (pages[0].x + 72.0, pages[0].y + 72.0));
+        let last = canvas.block_rect(&document, 49).expect("last block");
+        assert!(last.y > pages[1].y && last.y < pages[1].y + pages[1].height);
+
+        // Wider margins leave less room, pushing blocks further along.
+        let moved = canvas.block_layout.block_page(40);
+        document.metadata.margins.top = 200.0;
+        canvas.refresh_layout(&document);
+        assert!(canvas.block_layout.block_page(40) >= moved);
+        assert_eq!(
+            canvas.block_rect(&document, 0).unwrap().y,
+            pages[0].y + 200.0
+        );
+    }
+
+    #[test]
+    fn a_page_break_starts_a_new_page_with_room_left() {
+        let mut document = DocumentModel::default();
+        document.content = paragraphs(2);
+        document.content.insert(1, Block::PageBreak);
+        let mut canvas = CanvasState::default();
+        canvas.set_viewport(800.0, 600.0);
+        canvas.refresh_layout(&document);
+
+        let pages = canvas.page_rects(&document);
+        assert_eq!(pages.len(), 2);
+        let after = canvas
+            .block_rect(&document, 2)
+            .expect("block after the break");
+        assert_eq!(after.y, pages[1].y + 72.0);
+
+        // A single page view shows only the first page's blocks.
+        canvas.set_layout_mode(PageLayoutMode::SinglePage);
+        assert!(canvas.block_rect(&document, 0).is_some());
+        assert!(canvas.block_rect(&document, 2).is_none());
+    }
 }
@@ -1,6 +1,6 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
-use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use std::{mem::ManuallyDrop, path::Path};
 
 use windows::{
@@ -9,9 +9,9 @@ use windows::{
         Graphics::{
             Direct2D::{
                 Common::{D2D_RECT_F, D2D1_ALPHA_MODE_IGNORE, D2D1_PIXEL_FORMAT},
-                D2D1_BITMAP_OPTIONS_CANNOT_DRAW, D2D1_BITMAP_OPTIONS_TARGET,
+                D2D1_ANTIALIAS_MODE_ALIASED, D2D1_BITMAP_OPTIONS_CANNOT_DRAW, D2D1_BITMAP_OPTIONS_TARGET,
                 D2D1_BITMAP_PROPERTIES1, D2D1_DEVICE_CONTEXT_OPTIONS_NONE,
-                D2D1_DRAW_TEXT_OPTIONS_CLIP, D2D1_DRAW_TEXT_OPTIONS_NONE,
+                D2D1_DRAW_TEXT_OPTIONS, D2D1_DRAW_TEXT_OPTIONS_CLIP, D2D1_DRAW_TEXT_OPTIONS_NONE,
                 D2D1_FACTORY_TYPE_SINGLE_THREADED, D2D1CreateFactory,
                 ID2D1Bitmap1, ID2D1Device, ID2D1DeviceContext, ID2D1Factory1, ID2D1Image,
                 ID2D1SolidColorBrush,
@@ -22,9 +22,12 @@ use windows::{
                 ID3D11Device, ID3D11DeviceContext,
             },
             DirectWrite::{
-                DWRITE_FACTORY_TYPE_SHARED, DWRITE_MEASURING_MODE_NATURAL, DWriteCreateFactory,
-                DWRITE_PARAGRAPH_ALIGNMENT_CENTER, DWRITE_TEXT_ALIGNMENT_CENTER,
-                DWRITE_WORD_WRAPPING_NO_WRAP, IDWriteFactory, IDWriteTextFormat,
+                DWRITE_FACTORY_TYPE_SHARED, DWRITE_FONT_FEATURE, DWRITE_FONT_FEATURE_TAG,
+                DWRITE_FONT_FEATURE_TAG_SUBSCRIPT, DWRITE_FONT_FEATURE_TAG_SUPERSCRIPT,
+                DWRITE_MEASURING_MODE_NATURAL, DWriteCreateFactory, DWRITE_PARAGRAPH_ALIGNMENT_CENTER,
+                DWRITE_TEXT_ALIGNMENT_CENTER, DWRITE_TEXT_ALIGNMENT_TRAILING, DWRITE_HIT_TEST_METRICS, DWRITE_READING_DIRECTION_RIGHT_TO_LEFT, DWRITE_TEXT_METRICS,
+                DWRITE_TEXT_RANGE, DWRITE_WORD_WRAPPING_NO_WRAP, IDWriteFactory, IDWriteFontFallback, IDWriteTextFormat, IDWriteTextLayout,
+                IDWriteTypography,
             },
             Dxgi::{
                 Common::{
@@ -33,28 +36,35 @@ use windows::{
                 },
                 DXGI_PRESENT, DXGI_SCALING_STRETCH, DXGI_SWAP_CHAIN_DESC1, DXGI_SWAP_CHAIN_FLAG,
                 DXGI_SWAP_EFFECT_DISCARD, DXGI_SWAP_EFFECT_FLIP_DISCARD,
-                DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL, DXGI_USAGE_RENDER_TARGET_OUTPUT, IDXGIDevice,
-                IDXGIFactory2, IDXGISurface, IDXGISwapChain1,
+                DXGI_SWAP_EFFECT_FLIP_SEQUENTIAL, DXGI_USAGE_RENDER_TARGET_OUTPUT, IDXGIAdapter,
+                IDXGIDevice, IDXGIFactory2, IDXGISurface, IDXGISwapChain1,
             },
         },
         UI::WindowsAndMessaging::GetClientRect,
     },
-    core::{HRESULT, Interface, Result, w},
+    core::{HRESULT, HSTRING, Interface, PCWSTR, Result, w},
 };
 use windows_numerics::Vector2;
 
 use crate::{
+    editor::table::TableViewport,
+    render::block_layout::{column_spans, quote_geometry},
+    render::canvas::scrollbar_thumb,
+    render::dwrite::{apply_font_fallback, create_font_fallback, with_color_fonts},
     render::image_cache::ImageCacheStats,
-    render::perf::{DebugPerformancePanel, query_process_working_set_bytes},
+    render::layout_cache::{LAYOUT_CACHE_CAPACITY, TextLayoutCache, TextLayoutKey},
+    render::perf::{DebugPerformancePanel, FRAME_HISTORY_LEN, query_process_working_set_bytes},
     theme::{
         Theme,
-        backgrounds::{BackgroundKind, BackgroundSettings, PatternStyle, preset_by_id},
+        backgrounds::{BackgroundKind, BackgroundSettings, PageColors, PatternStyle, preset_by_id},
     },
-    ui::Rect as UiRect,
+    ui::{FocusRing, Rect as UiRect, dialog::SettingsFocus, document_properties::PropertyFieldItem},
 };
 
 const D2DERR_RECREATE_TARGET: HRESULT = HRESULT(0x8899000C_u32 as i32);
 const LAYOUT_DPI: f32 = 96.0;
+/// Wiki links to pages that don't exist yet, set apart from the theme's link color.
+const BROKEN_LINK_COLOR: crate::ui::Color = crate::ui::Color::rgb(0.86, 0.36, 0.32);
 
 #[derive(Debug, Clone, Default)]
 pub struct CanvasImageShellItem {
@@ -75,6 +85,14 @@ pub struct CanvasTableShellItem {
     pub cell_h: f32,
     pub header_h: f32,
     pub gutter_w: f32,
+    /// Rows that begin a continued page, above which the header row is drawn again.
+    pub repeated_header_rows: Vec<usize>,
+    /// Header tint or alternating band behind each row, `None` for unfilled rows.
+    pub row_fills: Vec<Option<crate::ui::Color>>,
+    pub scroll_x: f32,
+    pub scroll_y: f32,
+    pub freeze_first_column: bool,
+    pub freeze_header_row: bool,
     pub selected: bool,
     pub selection_mode: u8,
     pub selection_start_row: usize,
@@ -100,6 +118,67 @@ pub struct ToolbarShellButton {
     pub enabled: bool,
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ToolbarSwatchShellItem {
+    pub rect: UiRect,
+    pub color: crate::ui::Color,
+    pub selected: bool,
+}
+
+/// A find match as a UTF-16 range of the joined canvas preview text.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanvasFindHighlight {
+    pub start: u32,
+    pub len: u32,
+    pub current: bool,
+}
+
+/// A UTF-16 range of the joined canvas preview text, used for footnote reference marks and
+/// math scripts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanvasTextSpan {
+    pub start: u32,
+    pub len: u32,
+}
+
+/// A UTF-16 range of the joined canvas preview text filled with its run's highlight color.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CanvasTextHighlight {
+    pub span: CanvasTextSpan,
+    pub color: crate::ui::Color,
+}
+
+/// The box around a framed paragraph's preview line: border color and width in points,
+/// background, and the symbol drawn before the text.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CanvasLineFrame {
+    pub border: Option<(crate::ui::Color, f32)>,
+    pub shading: Option<crate::ui::Color>,
+    pub icon: Option<String>,
+}
+
+/// A heading in the breadcrumb bar, outermost first; the last one holds the caret.
+#[derive(Debug, Clone, Default)]
+pub struct BreadcrumbShellItem {
+    pub rect: UiRect,
+    pub label: String,
+}
+
+/// A piece of text in the statistics popup; headers and row labels are `muted`.
+#[derive(Debug, Clone, Default)]
+pub struct StatsCellShellItem {
+    pub rect: UiRect,
+    pub text: String,
+    pub muted: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ToolbarDropdownRowShellItem {
+    pub rect: UiRect,
+    pub label: String,
+    pub selected: bool,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ShellRenderState {
     pub ui_scale: f32,
@@ -116,10 +195,48 @@ pub struct ShellRenderState {
     pub tab_transition_offset: f32,
     pub tab_has_overflow_left: bool,
     pub tab_has_overflow_right: bool,
+    /// Shown on the tab list chevron beside the overflow arrows.
+    pub tab_count: usize,
+    /// Outline around what F6 moved keyboard focus to.
+    pub region_focus_ring: FocusRing,
     pub toolbar_buttons: Vec<ToolbarShellButton>,
     pub toolbar_dropdown_open: bool,
     pub toolbar_dropdown_opacity: f32,
     pub toolbar_dropdown_scale: f32,
+    pub toolbar_dropdown_rect: UiRect,
+    pub toolbar_dropdown_swatches: Vec<ToolbarSwatchShellItem>,
+    pub toolbar_dropdown_input: Option<String>,
+    pub toolbar_dropdown_rows: Vec<ToolbarDropdownRowShellItem>,
+    pub document_properties_visible: bool,
+    pub document_properties_panel: UiRect,
+    pub document_properties_fields: Vec<PropertyFieldItem>,
+    pub zoom_popup_visible: bool,
+    pub zoom_popup_panel: UiRect,
+    pub zoom_popup_buttons: Vec<ToolbarDropdownRowShellItem>,
+    pub zoom_popup_track: UiRect,
+    pub zoom_popup_slider: f32,
+    pub stats_popup_visible: bool,
+    pub stats_popup_panel: UiRect,
+    pub stats_popup_cells: Vec<StatsCellShellItem>,
+    pub toolbar_customizer_visible: bool,
+    pub toolbar_customizer_panel: UiRect,
+    pub toolbar_customizer_rows: Vec<ToolbarDropdownRowShellItem>,
+    pub breadcrumb_bar: UiRect,
+    pub breadcrumb_crumbs: Vec<BreadcrumbShellItem>,
+    pub goto_symbol_visible: bool,
+    pub goto_symbol_panel: UiRect,
+    pub goto_symbol_query: String,
+    pub goto_symbol_rows: Vec<ToolbarDropdownRowShellItem>,
+    pub licenses_visible: bool,
+    pub licenses_panel: UiRect,
+    pub licenses_query: String,
+    pub licenses_rows: Vec<ToolbarDropdownRowShellItem>,
+    pub licenses_summary: String,
+    pub shortcut_editor_visible: bool,
+    pub shortcut_editor_panel: UiRect,
+    pub shortcut_editor_title: String,
+    pub shortcut_editor_rows: Vec<ToolbarDropdownRowShellItem>,
+    pub shortcut_editor_hint: String,
     pub active_sidebar_panel: String,
     pub sidebar_summary: String,
     pub sidebar_rows: Vec<String>,
@@ -127,7 +244,8 @@ pub struct ShellRenderState {
     pub command_palette_opacity: f32,
     pub command_palette_offset_y: f32,
     pub command_palette_query: String,
-    pub command_palette_results: Vec<String>,
+    /// Result labels with their shortcut hints.
+    pub command_palette_results: Vec<(String, Option<String>)>,
     pub command_palette_selected: usize,
     pub settings_visible: bool,
     pub settings_query: String,
@@ -135,6 +253,7 @@ pub struct ShellRenderState {
     pub settings_categories: Vec<String>,
     pub settings_rows: Vec<String>,
     pub settings_selected_row: usize,
+    pub settings_focus: SettingsFocus,
     pub settings_conflicts: bool,
     pub settings_save_error: String,
     pub table_picker_visible: bool,
@@ -151,6 +270,9 @@ pub struct ShellRenderState {
     pub find_case_sensitive: bool,
     pub find_whole_word: bool,
     pub find_regex: bool,
+    pub find_in_selection: bool,
+    /// The regex query doesn't compile; the find field and count line show it in red.
+    pub find_regex_error: bool,
     pub find_preview: String,
     pub find_current: usize,
     pub find_total: usize,
@@ -160,10 +282,36 @@ pub struct ShellRenderState {
     pub status_left: String,
     pub status_right: String,
     pub canvas_background: BackgroundSettings,
+    /// Page and document text colors, which may differ from the shell theme's.
+    pub canvas_page: PageColors,
     pub canvas_page_rects: Vec<UiRect>,
     pub canvas_preview_lines: Vec<String>,
+    /// Per preview line, whether it reads right to left.
+    pub canvas_rtl_lines: Vec<bool>,
+    /// Per preview line, the box drawn around it when its paragraph is framed.
+    pub canvas_line_frames: Vec<Option<CanvasLineFrame>>,
+    /// Per preview line, how many block quotes it sits in.
+    pub canvas_quote_depths: Vec<usize>,
+    /// Columns the page's text flows through, and the gap between them in pixels.
+    pub canvas_columns: usize,
+    pub canvas_column_gap: f32,
+    /// BCP 47 tag of the document's language, used as the canvas text locale.
+    pub canvas_language: String,
+    pub canvas_find_highlights: Vec<CanvasFindHighlight>,
+    /// Highlighted runs, filled behind their text and under find matches.
+    pub canvas_text_highlights: Vec<CanvasTextHighlight>,
+    pub canvas_footnote_marks: Vec<CanvasTextSpan>,
+    /// Superscript and subscript text, from runs formatted that way and from typeset math,
+    /// drawn with the font's script glyphs.
+    pub canvas_superscripts: Vec<CanvasTextSpan>,
+    pub canvas_subscripts: Vec<CanvasTextSpan>,
+    /// Wiki links whose page exists, and ones that would create it when followed.
+    pub canvas_links: Vec<CanvasTextSpan>,
+    pub canvas_broken_links: Vec<CanvasTextSpan>,
     pub canvas_show_margin_guides: bool,
     pub canvas_cursor_visible: bool,
+    /// Preview line and UTF-16 position where dragged text would drop.
+    pub canvas_drop_caret: Option<(usize, u32)>,
     pub canvas_scrollbar_visible: bool,
     pub canvas_scrollbar_alpha: f32,
     pub canvas_viewport_width: f32,
@@ -186,9 +334,185 @@ pub struct ShellRenderState {
     pub table_selected_id: u64,
 }
 
+/// Direct3D driver backing the renderer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RenderDriver {
+    Hardware,
+    Warp,
+}
+
+impl RenderDriver {
+    pub fn label(self) -> &'static str {
+        match self {
+            RenderDriver::Hardware => "Hardware (D3D11)",
+            RenderDriver::Warp => "WARP (software)",
+        }
+    }
+
+    fn d3d_driver_type(self) -> windows::Win32::Graphics::Direct3D::D3D_DRIVER_TYPE {
+        match self {
+            RenderDriver::Hardware => D3D_DRIVER_TYPE_HARDWARE,
+            RenderDriver::Warp => D3D_DRIVER_TYPE_WARP,
+        }
+    }
+}
+
+/// The display adapter the device was created on, as DXGI describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterInfo {
+    pub description: String,
+    pub vendor_id: u32,
+    pub dedicated_video_memory: u64,
+    /// User-mode driver version, four 16-bit parts packed high to low.
+    pub driver_version: Option<u64>,
+}
+
+impl AdapterInfo {
+    fn query(adapter: &IDXGIAdapter) -> Option<Self> {
+        unsafe {
+            let desc = adapter.GetDesc().ok()?;
+            let len = desc
+                .Description
+                .iter()
+                .position(|&unit| unit == 0)
+                .unwrap_or(desc.Description.len());
+            Some(Self {
+                description: String::from_utf16_lossy(&desc.Description[..len]),
+                vendor_id: desc.VendorId,
+                dedicated_video_memory: desc.DedicatedVideoMemory as u64,
+                driver_version: adapter
+                    .CheckInterfaceSupport(&IDXGIDevice::IID)
+                    .ok()
+                    .map(|version| version as u64),
+            })
+        }
+    }
+}
+
+/// Drivers to try in order; turning hardware acceleration off goes straight to WARP.
+pub fn driver_attempt_order(hardware_acceleration: bool) -> &'static [RenderDriver] {
+    if hardware_acceleration {
+        &[RenderDriver::Hardware, RenderDriver::Warp]
+    } else {
+        &[RenderDriver::Warp]
+    }
+}
+
+/// Ranges of one preview line drawn apart from its plain text.
+struct LineMarks {
+    footnotes: Vec<DWRITE_TEXT_RANGE>,
+    superscripts: Vec<DWRITE_TEXT_RANGE>,
+    subscripts: Vec<DWRITE_TEXT_RANGE>,
+    links: Vec<DWRITE_TEXT_RANGE>,
+    broken_links: Vec<DWRITE_TEXT_RANGE>,
+}
+
+impl LineMarks {
+    fn is_empty(&self) -> bool {
+        self.footnotes.is_empty()
+            && self.superscripts.is_empty()
+            && self.subscripts.is_empty()
+            && self.links.is_empty()
+            && self.broken_links.is_empty()
+    }
+}
+
+/// Lays a right-to-left line out from the right edge. Leading alignment follows the reading
+/// direction, so the line's start-aligned text flips to the right as well.
+fn set_reading_direction(layout: &IDWriteTextLayout, rtl: bool) -> Result<()> {
+    if rtl {
+        unsafe { layout.SetReadingDirection(DWRITE_READING_DIRECTION_RIGHT_TO_LEFT)? };
+    }
+    Ok(())
+}
+
+/// Boxes covering `len` UTF-16 units from `start` of the joined preview text, clipped to the
+/// bottom of `text_rect`. A range is drawn on the line it starts on.
+fn text_range_rects(
+    lines: &[PreviewLineLayout],
+    text_rect: D2D_RECT_F,
+    start: u32,
+    len: u32,
+) -> Vec<D2D_RECT_F> {
+    if len == 0 {
+        return Vec::new();
+    }
+    let mut line_start = 0u32;
+    let Some((line, local_start)) = lines.iter().find_map(|line| {
+        let first = line_start;
+        line_start += line.utf16_len + 1;
+        (start >= first && start < first + line.utf16_len).then(|| (line, start - first))
+    }) else {
+        return Vec::new();
+    };
+    let len = len.min(line.utf16_len - local_start);
+
+    let mut metrics = [DWRITE_HIT_TEST_METRICS::default(); 4];
+    let mut count = 0u32;
+    let hit = unsafe {
+        line.layout.HitTestTextRange(
+            local_start,
+            len,
+            line.left,
+            line.top,
+            Some(&mut metrics),
+            &mut count,
+        )
+    };
+    if hit.is_err() {
+        return Vec::new();
+    }
+    metrics
+        .iter()
+        .take(count as usize)
+        .map(|m| D2D_RECT_F {
+            left: m.left,
+            top: m.top,
+            right: m.left + m.width,
+            bottom: (m.top + m.height).min(text_rect.bottom),
+        })
+        .filter(|rect| rect.top < text_rect.bottom)
+        .collect()
+}
+
+fn ui_rect(rect: &D2D_RECT_F) -> UiRect {
+    UiRect {
+        x: rect.left,
+        y: rect.top,
+        width: rect.right - rect.left,
+        height: rect.bottom - rect.top,
+    }
+}
+
+/// Text draw options with color fonts enabled so emoji render in color.
+const TEXT_OPTIONS: D2D1_DRAW_TEXT_OPTIONS = with_color_fonts(D2D1_DRAW_TEXT_OPTIONS_NONE);
+const CLIPPED_TEXT_OPTIONS: D2D1_DRAW_TEXT_OPTIONS = with_color_fonts(D2D1_DRAW_TEXT_OPTIONS_CLIP);
+
+/// A preview line's cached layout and where it sits on the page.
+#[derive(Clone)]
+struct PreviewLineLayout {
+    layout: IDWriteTextLayout,
+    left: f32,
+    top: f32,
+    height: f32,
+    utf16_len: u32,
+}
+
+/// The preview lines drawn last frame, kept so the window can hit-test text under the pointer.
+struct PreviewHitMap {
+    lines: Vec<PreviewLineLayout>,
+}
+
+/// Space between a paragraph frame's border and its text, horizontally and vertically.
+const FRAME_PADDING: (f32, f32) = (10.0, 5.0);
+/// Room left for a frame's icon at the start of the line.
+const FRAME_ICON_WIDTH: f32 = 24.0;
+
 pub struct D2DRenderer {
     hwnd: HWND,
     dpi: f32,
+    driver: RenderDriver,
+    adapter: Option<AdapterInfo>,
     #[allow(dead_code)]
     d3d_device: ID3D11Device,
     #[allow(dead_code)]
@@ -201,23 +525,35 @@ pub struct D2DRenderer {
     swap_chain: IDXGISwapChain1,
     target_bitmap: Option<ID2D1Bitmap1>,
     dwrite_factory: IDWriteFactory,
+    font_fallback: Option<IDWriteFontFallback>,
     theme: Theme,
     debug_panel: DebugPerformancePanel,
     brush_cache: RefCell<HashMap<u32, ID2D1SolidColorBrush>>,
     default_text_format: RefCell<Option<IDWriteTextFormat>>,
+    canvas_text_format: RefCell<Option<(String, IDWriteTextFormat)>>,
     icon_text_format: RefCell<Option<IDWriteTextFormat>>,
+    text_layouts: RefCell<TextLayoutCache<IDWriteTextLayout>>,
+    preview_hits: RefCell<Option<PreviewHitMap>>,
 }
 
 impl D2DRenderer {
-    pub fn new(hwnd: HWND, width: u32, height: u32, dpi: f32, theme: Theme) -> Result<Self> {
+    pub fn new(
+        hwnd: HWND,
+        width: u32,
+        height: u32,
+        dpi: f32,
+        theme: Theme,
+        hardware_acceleration: bool,
+    ) -> Result<Self> {
         unsafe {
             let d2d_factory: ID2D1Factory1 =
                 D2D1CreateFactory(D2D1_FACTORY_TYPE_SINGLE_THREADED, None)?;
 
-            let (d3d_device, d3d_context) = Self::create_d3d_device()?;
+            let (driver, d3d_device, d3d_context) = Self::create_d3d_device(hardware_acceleration)?;
 
             let dxgi_device: IDXGIDevice = d3d_device.cast()?;
             let adapter = dxgi_device.GetAdapter()?;
+            let adapter_info = AdapterInfo::query(&adapter);
             let dxgi_factory: IDXGIFactory2 = adapter.GetParent()?;
 
             let swap_chain =
@@ -227,10 +563,19 @@ impl D2DRenderer {
             let d2d_context = d2d_device.CreateDeviceContext(D2D1_DEVICE_CONTEXT_OPTIONS_NONE)?;
 
             let dwrite_factory: IDWriteFactory = DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED)?;
+            let font_fallback = match create_font_fallback(&dwrite_factory) {
+                Ok(fallback) => Some(fallback),
+                Err(error) => {
+                    eprintln!("Font fallback unavailable, using DirectWrite defaults: {error:?}");
+                    None
+                }
+            };
 
             let mut renderer = Self {
                 hwnd,
                 dpi,
+                driver,
+                adapter: adapter_info,
                 d3d_device,
                 d3d_context,
                 d2d_factory,
@@ -239,11 +584,15 @@ impl D2DRenderer {
                 swap_chain,
                 target_bitmap: None,
                 dwrite_factory,
+                font_fallback,
                 theme,
                 debug_panel: DebugPerformancePanel::default(),
                 brush_cache: RefCell::new(HashMap::new()),
                 default_text_format: RefCell::new(None),
+                canvas_text_format: RefCell::new(None),
                 icon_text_format: RefCell::new(None),
+                text_layouts: RefCell::new(TextLayoutCache::new(LAYOUT_CACHE_CAPACITY)),
+                preview_hits: RefCell::new(None),
             };
 
             renderer.recreate_target_bitmap()?;
@@ -251,18 +600,29 @@ impl D2DRenderer {
         }
     }
 
-    fn create_d3d_device() -> Result<(ID3D11Device, ID3D11DeviceContext)> {
-        let hardware_result = Self::create_d3d_device_for_driver(D3D_DRIVER_TYPE_HARDWARE);
-        if let Ok(devices) = hardware_result {
-            return Ok(devices);
+    fn create_d3d_device(
+        hardware_acceleration: bool,
+    ) -> Result<(RenderDriver, ID3D11Device, ID3D11DeviceContext)> {
+        let order = driver_attempt_order(hardware_acceleration);
+        let mut last_error = None;
+        for driver in order {
+            match Self::create_d3d_device_for_driver(driver.d3d_driver_type()) {
+                Ok((device, context)) => return Ok((*driver, device, context)),
+                Err(error) => {
+                    eprintln!("{} D3D11 initialization failed: {error:?}", driver.label());
+                    last_error = Some(error);
+                }
+            }
         }
+        Err(last_error.expect("driver attempt order is never empty"))
+    }
 
-        if let Err(error) = hardware_result {
-            eprintln!(
-                "Hardware D3D11 initialization failed, falling back to WARP software renderer: {error:?}"
-            );
-        }
-        Self::create_d3d_device_for_driver(D3D_DRIVER_TYPE_WARP)
+    pub fn driver(&self) -> RenderDriver {
+        self.driver
+    }
+
+    pub fn adapter(&self) -> Option<&AdapterInfo> {
+        self.adapter.as_ref()
     }
 
     fn create_d3d_device_for_driver(
@@ -353,6 +713,7 @@ impl D2DRenderer {
 
     pub fn set_dpi(&mut self, dpi: f32) {
         self.dpi = dpi;
+        self.text_layouts.borrow_mut().clear();
         unsafe {
             let _ = self.d2d_context.SetDpi(LAYOUT_DPI, LAYOUT_DPI);
         }
@@ -414,6 +775,10 @@ impl D2DRenderer {
         self.debug_panel.set_visible(visible);
     }
 
+    pub fn update_frame_context(&mut self, since_input: Duration, reasons: Vec<&'static str>) {
+        self.debug_panel.update_frame_context(since_input, reasons);
+    }
+
     pub fn update_image_cache_stats(&mut self, stats: ImageCacheStats) {
         self.debug_panel.update_image_cache_stats(stats);
     }
@@ -423,6 +788,21 @@ impl D2DRenderer {
         self.brush_cache.borrow_mut().clear();
     }
 
+    /// Width of `text` on one line at the 11pt body size, measured with DirectWrite.
+    pub fn measure_text_width(&self, text: &str) -> Option<f32> {
+        let format = self.create_text_format().ok()?;
+        let utf16 = text.encode_utf16().collect::<Vec<u16>>();
+        let layout = unsafe {
+            self.dwrite_factory
+                .CreateTextLayout(&utf16, &format, f32::MAX, f32::MAX)
+                .ok()?
+        };
+        let mut metrics = DWRITE_TEXT_METRICS::default();
+        unsafe { layout.GetMetrics(&mut metrics) }.ok()?;
+        // The shared format is set at the UI size; scale to the document body size.
+        Some(metrics.widthIncludingTrailingWhitespace * 11.0 / unsafe { format.GetFontSize() })
+    }
+
     pub fn debug_panel(&self) -> &DebugPerformancePanel {
         &self.debug_panel
     }
@@ -548,6 +928,69 @@ impl D2DRenderer {
             };
             let text_brush = self.create_brush(text_color)?;
             let text_format = self.create_text_format()?;
+            if !shell.breadcrumb_crumbs.is_empty() {
+                let b = shell.breadcrumb_bar;
+                let bar = D2D_RECT_F {
+                    left: b.x,
+                    top: b.y,
+                    right: b.x + b.width,
+                    bottom: b.y + b.height,
+                };
+                self.d2d_context.FillRectangle(&bar, &tool_brush);
+                let border_brush = self.create_brush(self.theme.border_subtle.as_d2d())?;
+                self.d2d_context.DrawLine(
+                    Vector2 {
+                        X: bar.left,
+                        Y: bar.bottom - 0.5,
+                    },
+                    Vector2 {
+                        X: bar.right,
+                        Y: bar.bottom - 0.5,
+                    },
+                    &border_brush,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+                let ancestor_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                let separator = "\u{203a}".encode_utf16().collect::<Vec<u16>>();
+                let last = shell.breadcrumb_crumbs.len() - 1;
+                for (idx, crumb) in shell.breadcrumb_crumbs.iter().enumerate() {
+                    let r = crumb.rect;
+                    if idx > 0 {
+                        self.d2d_context.DrawText(
+                            &separator,
+                            &text_format,
+                            &D2D_RECT_F {
+                                left: r.x - 11.0,
+                                top: r.y + 3.0,
+                                right: r.x,
+                                bottom: r.y + r.height,
+                            },
+                            &ancestor_brush,
+                            CLIPPED_TEXT_OPTIONS,
+                            DWRITE_MEASURING_MODE_NATURAL,
+                        );
+                    }
+                    let label = crumb.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &label,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: r.x + 6.0,
+                            top: r.y + 3.0,
+                            right: r.x + r.width,
+                            bottom: r.y + r.height,
+                        },
+                        if idx == last {
+                            &text_brush
+                        } else {
+                            &ancestor_brush
+                        },
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+            }
             if sidebar_w > 0.0 {
                 let tab_titles = [
                     ("Files", "Files"),
@@ -577,7 +1020,7 @@ impl D2DRenderer {
                         &text_format,
                         &tab_rect,
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                        CLIPPED_TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                     if idx + 1 < tab_titles.len() {
@@ -617,7 +1060,7 @@ impl D2DRenderer {
                             bottom: row_bottom,
                         },
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                        CLIPPED_TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                     row_y += 24.0;
@@ -664,12 +1107,13 @@ impl D2DRenderer {
                         bottom: palette_y + 36.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
                 let mut row_y = palette_y + 44.0;
-                for (idx, row) in shell.command_palette_results.iter().enumerate() {
+                let hint_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                for (idx, (row, hint)) in shell.command_palette_results.iter().enumerate() {
                     if row_y + 24.0 > palette_y + palette_h - 8.0 {
                         break;
                     }
@@ -697,9 +1141,28 @@ impl D2DRenderer {
                             bottom: row_y + 20.0,
                         },
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
+                    if let Some(hint) = hint {
+                        let hint_utf16 = hint.encode_utf16().collect::<Vec<u16>>();
+                        let hint_layout = self.dwrite_factory.CreateTextLayout(
+                            &hint_utf16,
+                            &text_format,
+                            palette_w - 26.0,
+                            20.0,
+                        )?;
+                        let _ = hint_layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_TRAILING);
+                        self.d2d_context.DrawTextLayout(
+                            Vector2 {
+                                X: palette_x + 14.0,
+                                Y: row_y,
+                            },
+                            &hint_layout,
+                            &hint_brush,
+                            TEXT_OPTIONS,
+                        );
+                    }
                     row_y += 22.0;
                 }
             }
@@ -768,10 +1231,25 @@ impl D2DRenderer {
                         bottom: panel.top + 34.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
+                let mut focus_ring = FocusRing {
+                    visible: false,
+                    bounds: UiRect::default(),
+                    thickness: 2.0,
+                };
+                let search_rect = D2D_RECT_F {
+                    left: panel.left + 14.0,
+                    top: panel.top + 36.0,
+                    right: split_x - 8.0,
+                    bottom: panel.top + 58.0,
+                };
+                if shell.settings_focus == SettingsFocus::Search {
+                    focus_ring.visible = true;
+                    focus_ring.bounds = ui_rect(&search_rect);
+                }
                 let search = format!("Search: {}", shell.settings_query)
                     .encode_utf16()
                     .collect::<Vec<u16>>();
@@ -779,13 +1257,11 @@ impl D2DRenderer {
                     &search,
                     &text_format,
                     &D2D_RECT_F {
-                        left: panel.left + 14.0,
-                        top: panel.top + 36.0,
                         right: panel.right - 12.0,
-                        bottom: panel.top + 58.0,
+                        ..search_rect
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -800,6 +1276,10 @@ impl D2DRenderer {
                     if category == &shell.settings_category {
                         let active = self.create_brush(self.theme.surface_hover.as_d2d())?;
                         self.d2d_context.FillRectangle(&category_rect, &active);
+                        if shell.settings_focus == SettingsFocus::Categories {
+                            focus_ring.visible = true;
+                            focus_ring.bounds = ui_rect(&category_rect);
+                        }
                     }
                     let category_text = category.encode_utf16().collect::<Vec<u16>>();
                     self.d2d_context.DrawText(
@@ -807,7 +1287,7 @@ impl D2DRenderer {
                         &text_format,
                         &category_rect,
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                     category_y += 26.0;
@@ -827,7 +1307,7 @@ impl D2DRenderer {
                         bottom: panel.top + 34.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -843,13 +1323,17 @@ impl D2DRenderer {
                         let selected = self.create_brush(self.theme.surface_hover.as_d2d())?;
                         self.d2d_context.FillRectangle(&row_rect, &selected);
                     }
+                    if shell.settings_focus == SettingsFocus::Row(index) {
+                        focus_ring.visible = true;
+                        focus_ring.bounds = ui_rect(&row_rect);
+                    }
                     let row_text = row.encode_utf16().collect::<Vec<u16>>();
                     self.d2d_context.DrawText(
                         &row_text,
                         &text_format,
                         &row_rect,
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                     row_y += 26.0;
@@ -858,10 +1342,26 @@ impl D2DRenderer {
                     }
                 }
 
+                if focus_ring.visible {
+                    let ring_brush = self.create_brush(self.theme.border_focus.as_d2d())?;
+                    let inset = focus_ring.thickness * 0.5;
+                    self.d2d_context.DrawRectangle(
+                        &D2D_RECT_F {
+                            left: focus_ring.bounds.x + inset,
+                            top: focus_ring.bounds.y + inset,
+                            right: focus_ring.bounds.x + focus_ring.bounds.width - inset,
+                            bottom: focus_ring.bounds.y + focus_ring.bounds.height - inset,
+                        },
+                        &ring_brush,
+                        focus_ring.thickness,
+                        None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                    );
+                }
+
                 let footer_text = if shell.settings_conflicts {
-                    "Conflicting shortcuts detected. Adjust bindings or reset defaults."
+                    "Conflicting shortcuts detected. Open Keyboard Shortcuts to reassign or clear them."
                 } else {
-                    "Click a setting row to cycle values. Enter toggles selected row. Esc closes."
+                    "Tab/Shift+Tab moves focus. Space or Enter toggles the focused row. Esc closes."
                 };
                 let footer = footer_text.encode_utf16().collect::<Vec<u16>>();
                 self.d2d_context.DrawText(
@@ -874,7 +1374,7 @@ impl D2DRenderer {
                         bottom: panel.bottom - 12.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -893,7 +1393,7 @@ impl D2DRenderer {
                             bottom: panel.bottom - 38.0,
                         },
                         &err_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                 }
@@ -931,7 +1431,7 @@ impl D2DRenderer {
                         bottom: picker.top + 28.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -985,7 +1485,7 @@ impl D2DRenderer {
                         bottom: grid_top + 188.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1015,7 +1515,7 @@ impl D2DRenderer {
                         bottom: grid_top + 208.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1031,7 +1531,7 @@ impl D2DRenderer {
                         bottom: picker.bottom - 8.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
             }
@@ -1075,31 +1575,52 @@ impl D2DRenderer {
                         bottom: panel_y + 24.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
+                let error_brush = self.create_brush(self.theme.error_color.as_d2d())?;
+                let find_field_brush = if shell.find_regex_error {
+                    &error_brush
+                } else {
+                    &text_brush
+                };
+                let find_rect = D2D_RECT_F {
+                    left: panel_x + 10.0,
+                    top: panel_y + 28.0,
+                    right: panel_x + panel_w - 10.0,
+                    bottom: panel_y + 48.0,
+                };
+                if shell.find_regex_error {
+                    self.d2d_context.DrawRectangle(
+                        &D2D_RECT_F {
+                            left: find_rect.left - 4.0,
+                            top: find_rect.top - 1.0,
+                            right: find_rect.right + 4.0,
+                            bottom: find_rect.bottom + 1.0,
+                        },
+                        &error_brush,
+                        1.0,
+                        None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                    );
+                }
                 let find_line = format!("Find: {}", shell.find_query);
                 let find_utf16 = find_line.encode_utf16().collect::<Vec<u16>>();
                 self.d2d_context.DrawText(
                     &find_utf16,
                     &text_format,
-                    &D2D_RECT_F {
-                        left: panel_x + 10.0,
-                        top: panel_y + 28.0,
-                        right: panel_x + panel_w - 10.0,
-                        bottom: panel_y + 48.0,
-                    },
-                    &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    &find_rect,
+                    find_field_brush,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
                 let options = format!(
-                    "[{}] Case  [{}] Word  [{}] Regex   [Shift+Enter] Prev  [Enter] Next  [Esc] Close",
+                    "[{}] Case  [{}] Word  [{}] Regex  [{}] Selection   [Shift+Enter] Prev  [Enter] Next  [Esc] Close",
                     if shell.find_case_sensitive { "x" } else { " " },
                     if shell.find_whole_word { "x" } else { " " },
-                    if shell.find_regex { "x" } else { " " }
+                    if shell.find_regex { "x" } else { " " },
+                    if shell.find_in_selection { "x" } else { " " }
                 );
                 let options_utf16 = options.encode_utf16().collect::<Vec<u16>>();
                 self.d2d_context.DrawText(
@@ -1112,14 +1633,18 @@ impl D2DRenderer {
                         bottom: panel_y + 68.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
-                let count_line = format!(
-                    "{} ({}/{})",
-                    shell.find_result_count, shell.find_current, shell.find_total
-                );
+                let count_line = if shell.find_regex_error {
+                    shell.find_result_count.clone()
+                } else {
+                    format!(
+                        "{} ({}/{})",
+                        shell.find_result_count, shell.find_current, shell.find_total
+                    )
+                };
                 let count_utf16 = count_line.encode_utf16().collect::<Vec<u16>>();
                 self.d2d_context.DrawText(
                     &count_utf16,
@@ -1130,8 +1655,8 @@ impl D2DRenderer {
                         right: panel_x + panel_w - 10.0,
                         bottom: panel_y + 88.0,
                     },
-                    &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    find_field_brush,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1148,7 +1673,7 @@ impl D2DRenderer {
                             bottom: panel_y + 108.0,
                         },
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
 
@@ -1165,7 +1690,7 @@ impl D2DRenderer {
                             bottom: panel_y + 128.0,
                         },
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                 }
@@ -1187,7 +1712,7 @@ impl D2DRenderer {
                         bottom: panel_rect.bottom - 8.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1205,7 +1730,7 @@ impl D2DRenderer {
                                 bottom: gy + 18.0,
                             },
                             &text_brush,
-                            D2D1_DRAW_TEXT_OPTIONS_NONE,
+                            TEXT_OPTIONS,
                             DWRITE_MEASURING_MODE_NATURAL,
                         );
                         gy += 16.0;
@@ -1245,7 +1770,7 @@ impl D2DRenderer {
                         bottom: dialog.top + 28.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
                 let input = format!("Line/Page: {}", shell.goto_input)
@@ -1261,7 +1786,7 @@ impl D2DRenderer {
                         bottom: dialog.bottom - 10.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
             }
@@ -1300,7 +1825,7 @@ impl D2DRenderer {
                         bottom: toolbar.top + 28.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1319,7 +1844,7 @@ impl D2DRenderer {
                         bottom: toolbar.top + 50.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1335,7 +1860,7 @@ impl D2DRenderer {
                         bottom: toolbar.bottom - 6.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
             }
@@ -1373,7 +1898,7 @@ impl D2DRenderer {
                         bottom: props.top + 28.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1390,7 +1915,7 @@ impl D2DRenderer {
                         bottom: props.top + 50.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1407,7 +1932,7 @@ impl D2DRenderer {
                         bottom: props.top + 70.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1427,7 +1952,7 @@ impl D2DRenderer {
                         bottom: props.bottom - 10.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
             }
@@ -1470,11 +1995,11 @@ impl D2DRenderer {
                         bottom: panel.top + 28.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
-                let shortcuts = "Tab/Shift+Tab Move  Shift+Arrows Expand  Ctrl+Shift+M Merge  Ctrl+Shift+Y Split  Ctrl+Shift+1..5 Style  Ctrl+Shift+U/J/H/K Insert Row/Col";
+                let shortcuts = "Tab/Shift+Tab Move  Shift+Arrows Expand  Ctrl+Shift+M Merge  Ctrl+Shift+Y Split  Ctrl+Shift+1..5 Style  Ctrl+Shift+U/J/H/K Insert Row/Col  Ctrl+Shift+8 Even Rows  Ctrl+Shift+7 Match Row Height";
                 let shortcuts_utf16 = shortcuts.encode_utf16().collect::<Vec<u16>>();
                 self.d2d_context.DrawText(
                     &shortcuts_utf16,
@@ -1486,7 +2011,7 @@ impl D2DRenderer {
                         bottom: panel.bottom - 8.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
             }
@@ -1512,7 +2037,7 @@ impl D2DRenderer {
                         bottom: height - 2.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1527,7 +2052,7 @@ impl D2DRenderer {
                         bottom: height - 2.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
             }
@@ -1558,8 +2083,15 @@ impl D2DRenderer {
                         right: tabs_left + 52.0,
                         bottom: tab_h - 6.0,
                     };
+                    let list_rect = D2D_RECT_F {
+                        left: right_rect.right + 4.0,
+                        top: 6.0,
+                        right: right_rect.right + 40.0,
+                        bottom: tab_h - 6.0,
+                    };
                     self.d2d_context.FillRectangle(&left_rect, &overflow_bg);
                     self.d2d_context.FillRectangle(&right_rect, &overflow_bg);
+                    self.d2d_context.FillRectangle(&list_rect, &overflow_bg);
                     let left_arrow = if shell.tab_has_overflow_left { "<" } else { " " }
                         .encode_utf16()
                         .collect::<Vec<u16>>();
@@ -1571,7 +2103,7 @@ impl D2DRenderer {
                         &text_format,
                         &left_rect,
                         &overflow_text,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                     self.d2d_context.DrawText(
@@ -1579,10 +2111,21 @@ impl D2DRenderer {
                         &text_format,
                         &right_rect,
                         &overflow_text,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                    let chevron = format!("{}\u{25BE}", shell.tab_count)
+                        .encode_utf16()
+                        .collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &chevron,
+                        &text_format,
+                        &list_rect,
+                        &overflow_text,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
-                    tabs_left = right_rect.right + 6.0;
+                    tabs_left = list_rect.right + 6.0;
                 }
 
                 let new_btn_bg = self.create_brush(self.theme.surface_secondary.as_d2d())?;
@@ -1593,7 +2136,7 @@ impl D2DRenderer {
                     &text_format,
                     &new_btn_rect,
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
 
@@ -1647,7 +2190,7 @@ impl D2DRenderer {
                                 bottom: rect.bottom - 4.0,
                             },
                             &text_brush,
-                            D2D1_DRAW_TEXT_OPTIONS_NONE,
+                            TEXT_OPTIONS,
                             DWRITE_MEASURING_MODE_NATURAL,
                         );
 
@@ -1662,7 +2205,7 @@ impl D2DRenderer {
                                 bottom: rect.bottom - 5.0,
                             },
                             &close_brush,
-                            D2D1_DRAW_TEXT_OPTIONS_NONE,
+                            TEXT_OPTIONS,
                             DWRITE_MEASURING_MODE_NATURAL,
                         );
 
@@ -1746,17 +2289,23 @@ impl D2DRenderer {
                             bottom: rect.bottom - 4.0,
                         },
                         if button.enabled { &text_brush } else { &text_disabled },
-                        D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                        CLIPPED_TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                 }
             }
 
             if shell.toolbar_dropdown_open && shell.toolbar_dropdown_opacity > 0.01 {
-                let panel_w = 240.0 * shell.toolbar_dropdown_scale.clamp(0.9, 1.2);
-                let panel_h = 180.0 * shell.toolbar_dropdown_scale.clamp(0.9, 1.2);
-                let panel_x = sidebar_w + 20.0;
-                let panel_y = tab_h + toolbar_h + 8.0;
+                let scale = shell.toolbar_dropdown_scale.clamp(0.9, 1.2);
+                let anchored = shell.toolbar_dropdown_rect.width > 0.0;
+                let panel_w = if anchored { shell.toolbar_dropdown_rect.width } else { 240.0 } * scale;
+                let panel_h = if anchored { shell.toolbar_dropdown_rect.height } else { 180.0 } * scale;
+                let panel_x = if anchored { shell.toolbar_dropdown_rect.x } else { sidebar_w + 20.0 };
+                let panel_y = if anchored {
+                    shell.toolbar_dropdown_rect.y
+                } else {
+                    tab_h + toolbar_h + 8.0
+                };
                 let panel_rect = D2D_RECT_F {
                     left: panel_x,
                     top: panel_y,
@@ -1776,108 +2325,849 @@ impl D2DRenderer {
                     1.0,
                     None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
                 );
-            }
 
-            if !shell.toast_entries.is_empty() {
-                for (idx, entry) in shell.toast_entries.iter().enumerate().take(4) {
-                    let width_toast = 320.0;
-                    let height_toast = 64.0;
-                    let right = width - 14.0 + entry.slide_offset;
-                    let left = right - width_toast;
-                    let bottom = (height - status_h - 14.0) - (idx as f32 * 74.0);
-                    let top = bottom - height_toast;
+                let selected_brush = self.create_brush(self.theme.accent.as_d2d())?;
+                for swatch in &shell.toolbar_dropdown_swatches {
                     let rect = D2D_RECT_F {
-                        left,
-                        top,
-                        right,
-                        bottom,
+                        left: swatch.rect.x,
+                        top: swatch.rect.y,
+                        right: swatch.rect.x + swatch.rect.width,
+                        bottom: swatch.rect.y + swatch.rect.height,
                     };
-
-                    let mut bg = self.theme.surface_primary.as_d2d();
-                    bg.a = 0.94 * entry.opacity.clamp(0.0, 1.0);
-                    let mut border = self.theme.border_default.as_d2d();
-                    border.a *= entry.opacity.clamp(0.0, 1.0);
-                    let bg_brush = self.create_brush(bg)?;
-                    let border_brush = self.create_brush(border)?;
-                    self.d2d_context.FillRectangle(&rect, &bg_brush);
+                    let mut color = swatch.color.as_d2d();
+                    color.a *= shell.toolbar_dropdown_opacity.clamp(0.0, 1.0);
+                    let fill = self.create_brush(color)?;
+                    self.d2d_context.FillRectangle(&rect, &fill);
                     self.d2d_context.DrawRectangle(
                         &rect,
-                        &border_brush,
-                        1.0,
+                        if swatch.selected {
+                            &selected_brush
+                        } else {
+                            &panel_border_brush
+                        },
+                        if swatch.selected { 2.0 } else { 1.0 },
                         None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
                     );
+                }
 
-                    let title = entry.title.encode_utf16().collect::<Vec<u16>>();
+                if let Some(input) = &shell.toolbar_dropdown_input {
+                    let input_rect = D2D_RECT_F {
+                        left: panel_rect.left + 8.0,
+                        top: panel_rect.top + 8.0,
+                        right: panel_rect.right - 8.0,
+                        bottom: panel_rect.top + 32.0,
+                    };
+                    self.d2d_context.DrawRectangle(
+                        &input_rect,
+                        &selected_brush,
+                        1.0,
+                        None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                    );
+                    let placeholder = input.is_empty();
+                    let caption = if placeholder { "Type to filter" } else { input.as_str() };
+                    let hint_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                    let t = caption.encode_utf16().collect::<Vec<u16>>();
                     self.d2d_context.DrawText(
-                        &title,
+                        &t,
                         &text_format,
                         &D2D_RECT_F {
-                            left: rect.left + 10.0,
-                            top: rect.top + 8.0,
-                            right: rect.right - 10.0,
-                            bottom: rect.top + 28.0,
+                            left: input_rect.left + 6.0,
+                            top: input_rect.top + 3.0,
+                            right: input_rect.right - 6.0,
+                            bottom: input_rect.bottom,
                         },
-                        &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        if placeholder { &hint_brush } else { &text_brush },
+                        CLIPPED_TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
-                    let body = entry.body.encode_utf16().collect::<Vec<u16>>();
+                }
+
+                let row_hover = self.create_brush(self.theme.surface_hover.as_d2d())?;
+                for row in &shell.toolbar_dropdown_rows {
+                    let rect = D2D_RECT_F {
+                        left: row.rect.x,
+                        top: row.rect.y,
+                        right: row.rect.x + row.rect.width,
+                        bottom: row.rect.y + row.rect.height,
+                    };
+                    if row.selected {
+                        self.d2d_context.FillRectangle(&rect, &row_hover);
+                    }
+                    let t = row.label.encode_utf16().collect::<Vec<u16>>();
                     self.d2d_context.DrawText(
-                        &body,
+                        &t,
                         &text_format,
                         &D2D_RECT_F {
-                            left: rect.left + 10.0,
-                            top: rect.top + 28.0,
-                            right: rect.right - 10.0,
-                            bottom: rect.bottom - 8.0,
+                            left: rect.left + 6.0,
+                            top: rect.top + 3.0,
+                            right: rect.right - 6.0,
+                            bottom: rect.bottom,
                         },
                         &text_brush,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        CLIPPED_TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                 }
             }
 
-            if self.debug_panel.visible {
-                let panel_rect = D2D_RECT_F {
-                    left: width - 290.0,
-                    top: tab_h + 12.0,
-                    right: width - 12.0,
-                    bottom: tab_h + 146.0,
+            if shell.document_properties_visible && !shell.command_palette_open {
+                let scrim = self.create_brush(windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 0.25,
+                })?;
+                self.d2d_context.FillRectangle(
+                    &D2D_RECT_F {
+                        left: 0.0,
+                        top: 0.0,
+                        right: width,
+                        bottom: height,
+                    },
+                    &scrim,
+                );
+
+                let p = shell.document_properties_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
                 };
                 let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
                 let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
-                self.d2d_context.FillRectangle(&panel_rect, &panel_bg);
+                let focus_border = self.create_brush(self.theme.border_focus.as_d2d())?;
+                let hint_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
                 self.d2d_context.DrawRectangle(
-                    &panel_rect,
+                    &panel,
                     &panel_border,
                     1.0,
                     None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
                 );
 
-                let info = format!(
-                    "Debug\nFPS: {:.1}\nFrame: {:.2} ms\nMemory: {:.1} MB\nCache Hit: {:.0}%\nCache: {:.1} MB",
-                    self.debug_panel.snapshot.fps,
-                    self.debug_panel.snapshot.frame_time_ms,
-                    self.debug_panel.snapshot.process_memory_mb,
-                    self.debug_panel.snapshot.image_cache_hit_rate * 100.0,
-                    self.debug_panel.snapshot.image_cache_mb,
-                );
-                let text = info.encode_utf16().collect::<Vec<u16>>();
+                let title = "Document properties".encode_utf16().collect::<Vec<u16>>();
                 self.d2d_context.DrawText(
-                    &text,
+                    &title,
                     &text_format,
                     &D2D_RECT_F {
-                        left: panel_rect.left + 10.0,
-                        top: panel_rect.top + 8.0,
-                        right: panel_rect.right - 8.0,
-                        bottom: panel_rect.bottom - 8.0,
+                        left: panel.left + 14.0,
+                        top: panel.top + 10.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.top + 34.0,
                     },
                     &text_brush,
-                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                    TEXT_OPTIONS,
                     DWRITE_MEASURING_MODE_NATURAL,
                 );
-            }
+
+                for field in &shell.document_properties_fields {
+                    let input = D2D_RECT_F {
+                        left: field.input_rect.x,
+                        top: field.input_rect.y,
+                        right: field.input_rect.x + field.input_rect.width,
+                        bottom: field.input_rect.y + field.input_rect.height,
+                    };
+                    let label = field.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &label,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: input.left,
+                            top: input.top - 20.0,
+                            right: input.right,
+                            bottom: input.top - 2.0,
+                        },
+                        &hint_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                    self.d2d_context.DrawRectangle(
+                        &input,
+                        if field.focused { &focus_border } else { &panel_border },
+                        if field.focused { 2.0 } else { 1.0 },
+                        None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                    );
+                    let value = if field.focused {
+                        format!("{}|", field.value)
+                    } else {
+                        field.value.clone()
+                    };
+                    let t = value.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: input.left + 6.0,
+                            top: input.top + 4.0,
+                            right: input.right - 6.0,
+                            bottom: input.bottom,
+                        },
+                        &text_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+
+                let hint = "Tab moves between fields · Enter saves · Esc cancels"
+                    .encode_utf16()
+                    .collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &hint,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.bottom - 28.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.bottom - 6.0,
+                    },
+                    &hint_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+            }
+
+            if shell.zoom_popup_visible {
+                let p = shell.zoom_popup_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+
+                let row_hover = self.create_brush(self.theme.surface_hover.as_d2d())?;
+                for button in &shell.zoom_popup_buttons {
+                    let rect = D2D_RECT_F {
+                        left: button.rect.x,
+                        top: button.rect.y,
+                        right: button.rect.x + button.rect.width,
+                        bottom: button.rect.y + button.rect.height,
+                    };
+                    if button.selected {
+                        self.d2d_context.FillRectangle(&rect, &row_hover);
+                    }
+                    self.d2d_context.DrawRectangle(
+                        &rect,
+                        &panel_border,
+                        1.0,
+                        None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                    );
+                    let t = button.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 6.0,
+                            top: rect.top + 3.0,
+                            right: rect.right - 4.0,
+                            bottom: rect.bottom,
+                        },
+                        &text_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+
+                let track = shell.zoom_popup_track;
+                let mid_y = track.y + track.height * 0.5;
+                let thumb_x = track.x + track.width * shell.zoom_popup_slider.clamp(0.0, 1.0);
+                let accent_brush = self.create_brush(self.theme.accent.as_d2d())?;
+                self.d2d_context.FillRectangle(
+                    &D2D_RECT_F {
+                        left: track.x,
+                        top: mid_y - 2.0,
+                        right: track.x + track.width,
+                        bottom: mid_y + 2.0,
+                    },
+                    &panel_border,
+                );
+                self.d2d_context.FillRectangle(
+                    &D2D_RECT_F {
+                        left: track.x,
+                        top: mid_y - 2.0,
+                        right: thumb_x,
+                        bottom: mid_y + 2.0,
+                    },
+                    &accent_brush,
+                );
+                self.d2d_context.FillRectangle(
+                    &D2D_RECT_F {
+                        left: thumb_x - 4.0,
+                        top: mid_y - 9.0,
+                        right: thumb_x + 4.0,
+                        bottom: mid_y + 9.0,
+                    },
+                    &accent_brush,
+                );
+            }
+
+            if shell.stats_popup_visible {
+                let p = shell.stats_popup_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                let muted_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+                for cell in &shell.stats_popup_cells {
+                    let brush = if cell.muted {
+                        &muted_brush
+                    } else {
+                        &text_brush
+                    };
+                    let t = cell.text.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: cell.rect.x,
+                            top: cell.rect.y + 3.0,
+                            right: cell.rect.x + cell.rect.width,
+                            bottom: cell.rect.y + cell.rect.height,
+                        },
+                        brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+            }
+
+            if shell.toolbar_customizer_visible && !shell.command_palette_open {
+                let scrim = self.create_brush(windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 0.25,
+                })?;
+                self.d2d_context.FillRectangle(
+                    &D2D_RECT_F {
+                        left: 0.0,
+                        top: 0.0,
+                        right: width,
+                        bottom: height,
+                    },
+                    &scrim,
+                );
+
+                let p = shell.toolbar_customizer_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+
+                let title = "Customize toolbar".encode_utf16().collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &title,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.top + 8.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.top + 32.0,
+                    },
+                    &text_brush,
+                    TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+
+                let row_hover = self.create_brush(self.theme.surface_hover.as_d2d())?;
+                for row in &shell.toolbar_customizer_rows {
+                    let rect = D2D_RECT_F {
+                        left: row.rect.x,
+                        top: row.rect.y,
+                        right: row.rect.x + row.rect.width,
+                        bottom: row.rect.y + row.rect.height,
+                    };
+                    if row.selected {
+                        self.d2d_context.FillRectangle(&rect, &row_hover);
+                    }
+                    let t = row.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 6.0,
+                            top: rect.top + 3.0,
+                            right: rect.right - 6.0,
+                            bottom: rect.bottom,
+                        },
+                        &text_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+
+                let hint = "Space toggles · Ctrl+↑/↓ moves · Ins adds separator · Ctrl+R resets · Esc closes"
+                    .encode_utf16()
+                    .collect::<Vec<u16>>();
+                let hint_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                self.d2d_context.DrawText(
+                    &hint,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.bottom - 28.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.bottom - 6.0,
+                    },
+                    &hint_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+            }
+
+            if shell.goto_symbol_visible && !shell.command_palette_open {
+                let p = shell.goto_symbol_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+
+                let query = format!("# {}", shell.goto_symbol_query)
+                    .encode_utf16()
+                    .collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &query,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.top + 10.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.top + 34.0,
+                    },
+                    &text_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+
+                let row_hover = self.create_brush(self.theme.surface_hover.as_d2d())?;
+                for row in &shell.goto_symbol_rows {
+                    let rect = D2D_RECT_F {
+                        left: row.rect.x,
+                        top: row.rect.y,
+                        right: row.rect.x + row.rect.width,
+                        bottom: row.rect.y + row.rect.height,
+                    };
+                    if row.selected {
+                        self.d2d_context.FillRectangle(&rect, &row_hover);
+                    }
+                    let t = row.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 6.0,
+                            top: rect.top + 3.0,
+                            right: rect.right - 6.0,
+                            bottom: rect.bottom,
+                        },
+                        &text_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+            }
+
+            if shell.licenses_visible && !shell.command_palette_open {
+                let p = shell.licenses_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                let muted_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+
+                let (query, query_brush) = if shell.licenses_query.is_empty() {
+                    (
+                        "Open-source licenses \u{2014} type a crate name".to_string(),
+                        &muted_brush,
+                    )
+                } else {
+                    (format!("Crate: {}", shell.licenses_query), &text_brush)
+                };
+                let query = query.encode_utf16().collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &query,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.top + 10.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.top + 34.0,
+                    },
+                    query_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+
+                let row_hover = self.create_brush(self.theme.surface_hover.as_d2d())?;
+                for row in &shell.licenses_rows {
+                    let rect = D2D_RECT_F {
+                        left: row.rect.x,
+                        top: row.rect.y,
+                        right: row.rect.x + row.rect.width,
+                        bottom: row.rect.y + row.rect.height,
+                    };
+                    if row.selected {
+                        self.d2d_context.FillRectangle(&rect, &row_hover);
+                    }
+                    let t = row.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 6.0,
+                            top: rect.top + 3.0,
+                            right: rect.right - 6.0,
+                            bottom: rect.bottom,
+                        },
+                        &text_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+
+                let footer = format!(
+                    "{} \u{00B7} Enter opens the repository \u{00B7} Esc closes",
+                    shell.licenses_summary
+                )
+                .encode_utf16()
+                .collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &footer,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.bottom - 26.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.bottom - 4.0,
+                    },
+                    &muted_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+            }
+
+            if shell.shortcut_editor_visible && !shell.command_palette_open {
+                let scrim = self.create_brush(windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F {
+                    r: 0.0,
+                    g: 0.0,
+                    b: 0.0,
+                    a: 0.25,
+                })?;
+                self.d2d_context.FillRectangle(
+                    &D2D_RECT_F {
+                        left: 0.0,
+                        top: 0.0,
+                        right: width,
+                        bottom: height,
+                    },
+                    &scrim,
+                );
+
+                let p = shell.shortcut_editor_panel;
+                let panel = D2D_RECT_F {
+                    left: p.x,
+                    top: p.y,
+                    right: p.x + p.width,
+                    bottom: p.y + p.height,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                let muted_brush = self.create_brush(self.theme.text_secondary.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+
+                let title = shell
+                    .shortcut_editor_title
+                    .encode_utf16()
+                    .collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &title,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.top + 10.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.top + 34.0,
+                    },
+                    &text_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+
+                let row_hover = self.create_brush(self.theme.surface_hover.as_d2d())?;
+                for row in &shell.shortcut_editor_rows {
+                    let rect = D2D_RECT_F {
+                        left: row.rect.x,
+                        top: row.rect.y,
+                        right: row.rect.x + row.rect.width,
+                        bottom: row.rect.y + row.rect.height,
+                    };
+                    if row.selected {
+                        self.d2d_context.FillRectangle(&rect, &row_hover);
+                    }
+                    let t = row.label.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &t,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 6.0,
+                            top: rect.top + 3.0,
+                            right: rect.right - 6.0,
+                            bottom: rect.bottom,
+                        },
+                        &text_brush,
+                        CLIPPED_TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+
+                let hint = shell
+                    .shortcut_editor_hint
+                    .encode_utf16()
+                    .collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &hint,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel.left + 14.0,
+                        top: panel.bottom - 26.0,
+                        right: panel.right - 12.0,
+                        bottom: panel.bottom - 4.0,
+                    },
+                    &muted_brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+            }
+
+            if !shell.toast_entries.is_empty() {
+                for (idx, entry) in shell.toast_entries.iter().enumerate().take(4) {
+                    let width_toast = 320.0;
+                    let height_toast = 64.0;
+                    let right = width - 14.0 + entry.slide_offset;
+                    let left = right - width_toast;
+                    let bottom = (height - status_h - 14.0) - (idx as f32 * 74.0);
+                    let top = bottom - height_toast;
+                    let rect = D2D_RECT_F {
+                        left,
+                        top,
+                        right,
+                        bottom,
+                    };
+
+                    let mut bg = self.theme.surface_primary.as_d2d();
+                    bg.a = 0.94 * entry.opacity.clamp(0.0, 1.0);
+                    let mut border = self.theme.border_default.as_d2d();
+                    border.a *= entry.opacity.clamp(0.0, 1.0);
+                    let bg_brush = self.create_brush(bg)?;
+                    let border_brush = self.create_brush(border)?;
+                    self.d2d_context.FillRectangle(&rect, &bg_brush);
+                    self.d2d_context.DrawRectangle(
+                        &rect,
+                        &border_brush,
+                        1.0,
+                        None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                    );
+
+                    let title = entry.title.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &title,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 10.0,
+                            top: rect.top + 8.0,
+                            right: rect.right - 10.0,
+                            bottom: rect.top + 28.0,
+                        },
+                        &text_brush,
+                        TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                    let body = entry.body.encode_utf16().collect::<Vec<u16>>();
+                    self.d2d_context.DrawText(
+                        &body,
+                        &text_format,
+                        &D2D_RECT_F {
+                            left: rect.left + 10.0,
+                            top: rect.top + 28.0,
+                            right: rect.right - 10.0,
+                            bottom: rect.bottom - 8.0,
+                        },
+                        &text_brush,
+                        TEXT_OPTIONS,
+                        DWRITE_MEASURING_MODE_NATURAL,
+                    );
+                }
+            }
+
+            let ring = shell.region_focus_ring;
+            if ring.visible {
+                let ring_brush = self.create_brush(self.theme.border_focus.as_d2d())?;
+                let inset = ring.thickness * 0.5;
+                self.d2d_context.DrawRectangle(
+                    &D2D_RECT_F {
+                        left: ring.bounds.x + inset,
+                        top: ring.bounds.y + inset,
+                        right: ring.bounds.x + ring.bounds.width - inset,
+                        bottom: ring.bounds.y + ring.bounds.height - inset,
+                    },
+                    &ring_brush,
+                    ring.thickness,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+            }
+
+            if self.debug_panel.visible {
+                let panel_rect = D2D_RECT_F {
+                    left: width - 290.0,
+                    top: tab_h + 12.0,
+                    right: width - 12.0,
+                    bottom: tab_h + 306.0,
+                };
+                let panel_bg = self.create_brush(self.theme.surface_primary.as_d2d())?;
+                let panel_border = self.create_brush(self.theme.border_default.as_d2d())?;
+                self.d2d_context.FillRectangle(&panel_rect, &panel_bg);
+                self.d2d_context.DrawRectangle(
+                    &panel_rect,
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+
+                let snapshot = &self.debug_panel.snapshot;
+                let reasons = if self.debug_panel.repaint_reasons.is_empty() {
+                    "idle".to_string()
+                } else {
+                    self.debug_panel.repaint_reasons.join(", ")
+                };
+                let info = format!(
+                    "Debug ({})\nFPS: {:.1}\nFrame: {:.2} ms (avg {:.2}, p95 {:.2})\nSince input: {:.0} ms\nRepaint: {}\nMemory: {:.1} MB\nCache Hit: {:.0}%\nCache: {:.1} MB\nEvictions: {}",
+                    self.driver.label(),
+                    snapshot.fps,
+                    snapshot.frame_time_ms,
+                    snapshot.frame_avg_ms,
+                    snapshot.frame_p95_ms,
+                    snapshot.input_latency_ms,
+                    reasons,
+                    snapshot.process_memory_mb,
+                    snapshot.image_cache_hit_rate * 100.0,
+                    snapshot.image_cache_mb,
+                    snapshot.image_cache_evictions,
+                );
+                let text = info.encode_utf16().collect::<Vec<u16>>();
+                self.d2d_context.DrawText(
+                    &text,
+                    &text_format,
+                    &D2D_RECT_F {
+                        left: panel_rect.left + 10.0,
+                        top: panel_rect.top + 8.0,
+                        right: panel_rect.right - 8.0,
+                        bottom: panel_rect.bottom - 76.0,
+                    },
+                    &text_brush,
+                    TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+
+                // Rolling frame-time graph; the guide line marks a 60 Hz frame budget.
+                let graph = D2D_RECT_F {
+                    left: panel_rect.left + 10.0,
+                    top: panel_rect.bottom - 68.0,
+                    right: panel_rect.right - 10.0,
+                    bottom: panel_rect.bottom - 8.0,
+                };
+                let history = &self.debug_panel.frame_history;
+                let scale_ms = history.max().max(33.3);
+                let graph_h = graph.bottom - graph.top;
+                let bar_w = (graph.right - graph.left) / FRAME_HISTORY_LEN as f32;
+                let bar_brush = self.create_brush(self.theme.accent.as_d2d())?;
+                for (index, ms) in history.iter().enumerate() {
+                    let bar_h = (ms / scale_ms).clamp(0.0, 1.0) * graph_h;
+                    let left = graph.left + index as f32 * bar_w;
+                    self.d2d_context.FillRectangle(
+                        &D2D_RECT_F {
+                            left,
+                            top: graph.bottom - bar_h,
+                            right: left + (bar_w - 0.5).max(0.5),
+                            bottom: graph.bottom,
+                        },
+                        &bar_brush,
+                    );
+                }
+                let budget_y = graph.bottom - (16.7 / scale_ms) * graph_h;
+                self.d2d_context.DrawLine(
+                    Vector2 {
+                        X: graph.left,
+                        Y: budget_y,
+                    },
+                    Vector2 {
+                        X: graph.right,
+                        Y: budget_y,
+                    },
+                    &panel_border,
+                    1.0,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+            }
         }
 
         Ok(())
@@ -1954,14 +3244,15 @@ impl D2DRenderer {
         canvas_rect: D2D_RECT_F,
         shell: &ShellRenderState,
     ) -> Result<()> {
+        let page = &shell.canvas_page;
         let shadow_color = crate::ui::Color::rgba(
-            self.theme.page_shadow.r,
-            self.theme.page_shadow.g,
-            self.theme.page_shadow.b,
-            if self.theme.is_dark { 0.32 } else { 0.22 },
+            page.page_shadow.r,
+            page.page_shadow.g,
+            page.page_shadow.b,
+            if page.is_dark { 0.32 } else { 0.22 },
         );
         let shadow_brush = self.create_brush(shadow_color.as_d2d())?;
-        let page_brush = self.create_brush(self.theme.page_bg.as_d2d())?;
+        let page_brush = self.create_brush(page.page_bg.as_d2d())?;
         let border_brush = self.create_brush(self.theme.border_subtle.as_d2d())?;
         let guide_brush = self.create_brush(
             crate::ui::Color::rgba(
@@ -2148,7 +3439,7 @@ impl D2DRenderer {
             let image_bg = self.create_brush(self.theme.surface_secondary.as_d2d())?;
             let image_border = self.create_brush(self.theme.border_default.as_d2d())?;
             let image_selected = self.create_brush(self.theme.accent.as_d2d())?;
-            let image_text = self.create_brush(self.theme.text_secondary.as_d2d())?;
+            let image_text = self.create_brush(shell.canvas_page.secondary_text().as_d2d())?;
             let handle_brush = self.create_brush(self.theme.accent.as_d2d())?;
 
             for image in shell.canvas_images.iter().take(12) {
@@ -2203,7 +3494,7 @@ impl D2DRenderer {
                             bottom: top + 24.0,
                         },
                         &image_text,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                     self.d2d_context.DrawText(
@@ -2216,7 +3507,7 @@ impl D2DRenderer {
                             bottom: bottom - 4.0,
                         },
                         &image_text,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                 }
@@ -2271,7 +3562,7 @@ impl D2DRenderer {
                 .as_d2d(),
             )?;
             let table_selected = self.create_brush(self.theme.accent.as_d2d())?;
-            let table_text = self.create_brush(self.theme.text_secondary.as_d2d())?;
+            let table_text = self.create_brush(shell.canvas_page.secondary_text().as_d2d())?;
             let selection_fill = self.create_brush(
                 crate::ui::Color::rgba(
                     self.theme.selection_bg.r,
@@ -2333,44 +3624,196 @@ impl D2DRenderer {
                     self.d2d_context.FillRectangle(&gutter_rect, &table_header);
                 }
 
-                for r in 0..table.rows {
-                    for c in 0..table.cols {
-                        let cell_left = left + table.gutter_w + c as f32 * table.cell_w;
-                        let cell_top = top + table.header_h + r as f32 * table.cell_h;
-                        let cell_rect = D2D_RECT_F {
-                            left: cell_left,
-                            top: cell_top,
-                            right: cell_left + table.cell_w,
-                            bottom: cell_top + table.cell_h,
-                        };
+                let viewport = TableViewport {
+                    rows: table.rows,
+                    cols: table.cols,
+                    cell_w: table.cell_w,
+                    cell_h: table.cell_h,
+                    gutter_w: table.gutter_w,
+                    header_h: table.header_h,
+                    scroll_x: table.scroll_x,
+                    scroll_y: table.scroll_y,
+                    freeze_first_column: table.freeze_first_column,
+                    freeze_header_row: table.freeze_header_row,
+                };
+                let body_left = left + table.gutter_w;
+                let body_top = top + table.header_h;
+                let frozen_right = body_left + viewport.frozen_width();
+                let frozen_bottom = body_top + viewport.frozen_height();
+                // Scrolling cells first, then the frozen column and header row over them, each
+                // pass clipped so scrolled cells never show through the frozen panes.
+                let passes = [
+                    (false, false, frozen_right, frozen_bottom),
+                    (true, false, body_left, frozen_bottom),
+                    (false, true, frozen_right, body_top),
+                    (true, true, body_left, body_top),
+                ];
+                for (frozen_col, frozen_row, clip_left, clip_top) in passes {
+                    if (frozen_col && viewport.frozen_width() == 0.0)
+                        || (frozen_row && viewport.frozen_height() == 0.0)
+                    {
+                        continue;
+                    }
+                    let clip = D2D_RECT_F {
+                        left: clip_left,
+                        top: clip_top,
+                        right,
+                        bottom,
+                    };
+                    unsafe {
+                        self.d2d_context
+                            .PushAxisAlignedClip(&clip, D2D1_ANTIALIAS_MODE_ALIASED);
+                    }
+                    for r in 0..table.rows {
+                        if (r == 0 && viewport.frozen_height() > 0.0) != frozen_row {
+                            continue;
+                        }
+                        let cell_top = top + viewport.row_y(r);
+                        if cell_top + table.cell_h < clip.top || cell_top > clip.bottom {
+                            continue;
+                        }
+                        for c in 0..table.cols {
+                            if (c == 0 && viewport.frozen_width() > 0.0) != frozen_col {
+                                continue;
+                            }
+                            let cell_left = left + viewport.column_x(c);
+                            if cell_left + table.cell_w < clip.left || cell_left > clip.right {
+                                continue;
+                            }
+                            let cell_rect = D2D_RECT_F {
+                                left: cell_left,
+                                top: cell_top,
+                                right: cell_left + table.cell_w,
+                                bottom: cell_top + table.cell_h,
+                            };
 
-                        let in_selection = if table.selection_mode == 4 {
-                            true
-                        } else if table.selection_mode == 2 {
-                            r >= table.selection_start_row && r <= table.selection_end_row
-                        } else if table.selection_mode == 3 {
-                            c >= table.selection_start_col && c <= table.selection_end_col
-                        } else if table.selection_mode == 1 {
-                            r >= table.selection_start_row
-                                && r <= table.selection_end_row
-                                && c >= table.selection_start_col
-                                && c <= table.selection_end_col
-                        } else {
-                            false
-                        };
+                            let in_selection = if table.selection_mode == 4 {
+                                true
+                            } else if table.selection_mode == 2 {
+                                r >= table.selection_start_row && r <= table.selection_end_row
+                            } else if table.selection_mode == 3 {
+                                c >= table.selection_start_col && c <= table.selection_end_col
+                            } else if table.selection_mode == 1 {
+                                r >= table.selection_start_row
+                                    && r <= table.selection_end_row
+                                    && c >= table.selection_start_col
+                                    && c <= table.selection_end_col
+                            } else {
+                                false
+                            };
 
-                        unsafe {
-                            if in_selection {
-                                self.d2d_context.FillRectangle(&cell_rect, &selection_fill);
+                            if let Some(Some(fill)) = table.row_fills.get(r) {
+                                let brush = self.create_brush(fill.as_d2d())?;
+                                unsafe {
+                                    self.d2d_context.FillRectangle(&cell_rect, &brush);
+                                }
+                            }
+                            unsafe {
+                                if in_selection {
+                                    self.d2d_context.FillRectangle(&cell_rect, &selection_fill);
+                                }
+                                self.d2d_context.DrawRectangle(
+                                    &cell_rect,
+                                    &table_border,
+                                    1.0,
+                                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                                );
                             }
-                            self.d2d_context.DrawRectangle(
-                                &cell_rect,
-                                &table_border,
-                                1.0,
-                                None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
-                            );
                         }
                     }
+                    unsafe {
+                        self.d2d_context.PopAxisAlignedClip();
+                    }
+                }
+
+                // Edges of the frozen panes, plus a thumb showing how far a wide table has scrolled.
+                unsafe {
+                    if viewport.frozen_width() > 0.0 {
+                        self.d2d_context.DrawLine(
+                            Vector2 {
+                                X: frozen_right,
+                                Y: top,
+                            },
+                            Vector2 {
+                                X: frozen_right,
+                                Y: bottom,
+                            },
+                            &table_selected,
+                            1.5,
+                            None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                        );
+                    }
+                    if viewport.frozen_height() > 0.0 {
+                        self.d2d_context.DrawLine(
+                            Vector2 {
+                                X: left,
+                                Y: frozen_bottom,
+                            },
+                            Vector2 {
+                                X: right,
+                                Y: frozen_bottom,
+                            },
+                            &table_selected,
+                            1.5,
+                            None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                        );
+                    }
+                }
+                let max_scroll = viewport.max_scroll_x(table.rect.width);
+                if max_scroll > 0.0 {
+                    let track = (right - frozen_right).max(1.0);
+                    let content_w = track + max_scroll;
+                    let thumb_w = (track * track / content_w).max(16.0);
+                    let thumb_left =
+                        frozen_right + (track - thumb_w) * (table.scroll_x / max_scroll);
+                    unsafe {
+                        self.d2d_context.FillRectangle(
+                            &D2D_RECT_F {
+                                left: thumb_left,
+                                top: bottom - 3.0,
+                                right: thumb_left + thumb_w,
+                                bottom,
+                            },
+                            &table_selected,
+                        );
+                    }
+                }
+
+                // Mark where the table continues onto a new page and repeats its header there.
+                for row in table.repeated_header_rows.iter().filter(|row| **row < table.rows) {
+                    let row_top = top + viewport.row_y(*row);
+                    let band = D2D_RECT_F {
+                        left: left + table.gutter_w,
+                        top: row_top,
+                        right,
+                        bottom: row_top + (table.cell_h * 0.3).min(8.0),
+                    };
+                    unsafe {
+                        self.d2d_context.FillRectangle(&band, &table_header);
+                        self.d2d_context.DrawLine(
+                            Vector2 { X: left, Y: row_top },
+                            Vector2 { X: right, Y: row_top },
+                            &table_selected,
+                            1.5,
+                            None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                        );
+                    }
+                    let note = "header row repeats".encode_utf16().collect::<Vec<u16>>();
+                    unsafe {
+                        self.d2d_context.DrawText(
+                            &note,
+                            &self.create_text_format()?,
+                            &D2D_RECT_F {
+                                left: left + table.gutter_w + 4.0,
+                                top: row_top + 2.0,
+                                right: right - 4.0,
+                                bottom: row_top + table.cell_h,
+                            },
+                            &table_text,
+                            TEXT_OPTIONS,
+                            DWRITE_MEASURING_MODE_NATURAL,
+                        );
+                    }
                 }
 
                 let label = format!("[Table #{}] {}x{}", table.table_id, table.rows, table.cols);
@@ -2386,32 +3829,166 @@ impl D2DRenderer {
                             bottom: top + table.header_h - 2.0,
                         },
                         &table_text,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
+                        TEXT_OPTIONS,
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
                 }
             }
         }
 
-        let preview = shell
-            .canvas_preview_lines
-            .iter()
-            .take(42)
-            .map(|s| s.as_str())
-            .collect::<Vec<_>>()
-            .join("\n");
-        let text = preview.encode_utf16().collect::<Vec<u16>>();
-        let text_format = self.create_text_format()?;
-        let text_brush = self.create_brush(self.theme.text_primary.as_d2d())?;
-        unsafe {
-            self.d2d_context.DrawText(
-                &text,
+        let text_format = self.canvas_text_format(&shell.canvas_language)?;
+        let text_brush = self.create_brush(shell.canvas_page.text.as_d2d())?;
+        let mut lines = Vec::new();
+        let mut frames = Vec::new();
+        let mut quote_bars = Vec::new();
+        // Multi-column pages fill each column top to bottom before moving to the next.
+        let text_columns = column_spans(
+            text_rect.right - text_rect.left,
+            shell.canvas_columns,
+            shell.canvas_column_gap,
+        )
+        .into_iter()
+        .map(|(offset, width)| D2D_RECT_F {
+            left: text_rect.left + offset,
+            right: text_rect.left + offset + width,
+            ..text_rect
+        })
+        .collect::<Vec<_>>();
+        let mut column = 0;
+        let mut y = text_rect.top;
+        let mut line_start = 0u32;
+        for (index, line) in shell.canvas_preview_lines.iter().take(42).enumerate() {
+            if y >= text_rect.bottom {
+                if column + 1 >= text_columns.len() {
+                    break;
+                }
+                column += 1;
+                y = text_rect.top;
+            }
+            let column_rect = text_columns[column];
+            let rtl = shell.canvas_rtl_lines.get(index).copied().unwrap_or(false);
+            // Quoted lines are indented from their starting side, one step per level.
+            let quote = quote_geometry(shell.canvas_quote_depths.get(index).copied().unwrap_or(0));
+            let (column_left, column_right) = if rtl {
+                (column_rect.left, column_rect.right - quote.text_inset)
+            } else {
+                (column_rect.left + quote.text_inset, column_rect.right)
+            };
+            let frame = shell.canvas_line_frames.get(index).and_then(Option::as_ref);
+            // A framed line is inset by the padding, plus the icon on its starting side.
+            let (pad_x, pad_y) = frame.map_or((0.0, 0.0), |_| FRAME_PADDING);
+            let icon_width = if frame.is_some_and(|frame| frame.icon.is_some()) {
+                FRAME_ICON_WIDTH
+            } else {
+                0.0
+            };
+            let left = column_left + pad_x + if rtl { 0.0 } else { icon_width };
+            let line_width = (column_right - column_left - pad_x * 2.0 - icon_width).max(1.0);
+            let utf16_len = line.encode_utf16().count() as u32;
+            let line_ranges = |spans: &[CanvasTextSpan]| {
+                spans
+                    .iter()
+                    .filter(|mark| mark.start >= line_start && mark.start < line_start + utf16_len)
+                    .map(|mark| DWRITE_TEXT_RANGE {
+                        startPosition: mark.start - line_start,
+                        length: mark.len,
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let marks = LineMarks {
+                footnotes: line_ranges(&shell.canvas_footnote_marks),
+                superscripts: line_ranges(&shell.canvas_superscripts),
+                subscripts: line_ranges(&shell.canvas_subscripts),
+                links: line_ranges(&shell.canvas_links),
+                broken_links: line_ranges(&shell.canvas_broken_links),
+            };
+            line_start += utf16_len + 1;
+            let layout = if marks.is_empty() {
+                self.cached_text_layout(line, &text_format, line_width, rtl)?
+            } else {
+                let layout = self.marked_layout(line, &text_format, line_width, &marks)?;
+                set_reading_direction(&layout, rtl)?;
+                layout
+            };
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            unsafe { layout.GetMetrics(&mut metrics)? };
+            // Framed lines also keep a little space from the next line.
+            let advance = metrics.height + if frame.is_some() { pad_y * 3.0 } else { 0.0 };
+            for (start, end) in &quote.bars {
+                let (bar_left, bar_right) = if rtl {
+                    (column_rect.right - end, column_rect.right - start)
+                } else {
+                    (column_rect.left + start, column_rect.left + end)
+                };
+                quote_bars.push(D2D_RECT_F {
+                    left: bar_left,
+                    top: y,
+                    right: bar_right,
+                    bottom: y + advance,
+                });
+            }
+            if let Some(frame) = frame {
+                let rect = D2D_RECT_F {
+                    left: column_left,
+                    top: y,
+                    right: column_right,
+                    bottom: y + metrics.height + pad_y * 2.0,
+                };
+                let icon_left = if rtl {
+                    rect.right - pad_x - icon_width
+                } else {
+                    rect.left + pad_x
+                };
+                frames.push((frame, rect, icon_left));
+            }
+            lines.push(PreviewLineLayout {
+                layout,
+                left,
+                top: y + pad_y,
+                height: metrics.height,
+                utf16_len,
+            });
+            y += advance;
+        }
+        if !quote_bars.is_empty() {
+            let mut bar_color = self.theme.accent;
+            bar_color.a *= 0.55;
+            let bar_brush = self.create_brush(bar_color.as_d2d())?;
+            for bar in &quote_bars {
+                unsafe { self.d2d_context.FillRectangle(bar, &bar_brush) };
+            }
+        }
+        for (frame, rect, icon_left) in &frames {
+            self.draw_line_frame(
+                frame,
+                *rect,
+                *icon_left,
                 &text_format,
-                &text_rect,
-                &text_brush,
-                D2D1_DRAW_TEXT_OPTIONS_NONE,
-                DWRITE_MEASURING_MODE_NATURAL,
-            );
+                shell.canvas_page.is_dark,
+            )?;
+        }
+        for highlight in &shell.canvas_text_highlights {
+            let brush = self.create_brush(highlight.color.as_d2d())?;
+            let span = highlight.span;
+            for rect in text_range_rects(&lines, text_rect, span.start, span.len) {
+                unsafe { self.d2d_context.FillRectangle(&rect, &brush) };
+            }
+        }
+        if !shell.canvas_find_highlights.is_empty() {
+            self.draw_find_highlights(&lines, text_rect, &shell.canvas_find_highlights)?;
+        }
+        for line in &lines {
+            unsafe {
+                self.d2d_context.DrawTextLayout(
+                    Vector2 {
+                        X: line.left,
+                        Y: line.top,
+                    },
+                    &line.layout,
+                    &text_brush,
+                    TEXT_OPTIONS,
+                );
+            }
         }
 
         if shell.canvas_cursor_visible {
@@ -2427,6 +4004,222 @@ impl D2DRenderer {
             }
         }
 
+        if let Some((index, position)) = shell.canvas_drop_caret
+            && let Some(line) = lines.get(index)
+        {
+            let (mut x, mut y) = (0.0f32, 0.0f32);
+            let mut metrics = DWRITE_HIT_TEST_METRICS::default();
+            let hit = unsafe {
+                line.layout.HitTestTextPosition(
+                    position.min(line.utf16_len),
+                    false,
+                    &mut x,
+                    &mut y,
+                    &mut metrics,
+                )
+            };
+            if hit.is_ok() {
+                let caret_brush = self.create_brush(self.theme.accent.as_d2d())?;
+                let caret = D2D_RECT_F {
+                    left: line.left + x - 1.0,
+                    top: line.top + y,
+                    right: line.left + x + 1.0,
+                    bottom: line.top + y + metrics.height.max(1.0),
+                };
+                unsafe { self.d2d_context.FillRectangle(&caret, &caret_brush) };
+            }
+        }
+
+        *self.preview_hits.borrow_mut() = Some(PreviewHitMap { lines });
+        Ok(())
+    }
+
+    /// The preview line and UTF-16 text position under a client point, from the last frame.
+    pub fn preview_position_at(&self, x: f32, y: f32) -> Option<(usize, u32)> {
+        let hits = self.preview_hits.borrow();
+        let hits = hits.as_ref()?;
+        let index = hits
+            .lines
+            .iter()
+            .position(|line| y >= line.top && y < line.top + line.height)?;
+        let line = &hits.lines[index];
+        let mut trailing = windows::core::BOOL(0);
+        let mut inside = windows::core::BOOL(0);
+        let mut metrics = DWRITE_HIT_TEST_METRICS::default();
+        unsafe {
+            line.layout.HitTestPoint(
+                x - line.left,
+                y - line.top,
+                &mut trailing,
+                &mut inside,
+                &mut metrics,
+            )
+        }
+        .ok()?;
+        let position = metrics.textPosition + u32::from(trailing.as_bool());
+        Some((index, position.min(line.utf16_len)))
+    }
+
+    /// Returns the layout for one preview line, building it only when the text, format or
+    /// width changed since it was last drawn.
+    fn cached_text_layout(
+        &self,
+        text: &str,
+        text_format: &IDWriteTextFormat,
+        width: f32,
+        rtl: bool,
+    ) -> Result<IDWriteTextLayout> {
+        let font_size = unsafe { text_format.GetFontSize() };
+        let style = if rtl { "canvas-rtl" } else { "canvas" };
+        let key = TextLayoutKey::new(text, style, font_size, width);
+        if let Some(layout) = self.text_layouts.borrow_mut().get(&key) {
+            return Ok(layout);
+        }
+
+        let utf16 = text.encode_utf16().collect::<Vec<u16>>();
+        let layout = unsafe {
+            self.dwrite_factory
+                .CreateTextLayout(&utf16, text_format, width, f32::MAX)?
+        };
+        set_reading_direction(&layout, rtl)?;
+        self.text_layouts.borrow_mut().insert(key, layout.clone());
+        Ok(layout)
+    }
+
+    /// Builds an uncached layout for a preview line whose footnote references are raised with
+    /// the font's superscript glyphs and drawn in the accent color, and whose math scripts are
+    /// raised or lowered.
+    fn marked_layout(
+        &self,
+        text: &str,
+        text_format: &IDWriteTextFormat,
+        width: f32,
+        marks: &LineMarks,
+    ) -> Result<IDWriteTextLayout> {
+        let utf16 = text.encode_utf16().collect::<Vec<u16>>();
+        let accent = self.create_brush(self.theme.accent.as_d2d())?;
+        unsafe {
+            let layout = self
+                .dwrite_factory
+                .CreateTextLayout(&utf16, text_format, width, f32::MAX)?;
+            let superscript = self.feature_typography(DWRITE_FONT_FEATURE_TAG_SUPERSCRIPT)?;
+            let subscript = self.feature_typography(DWRITE_FONT_FEATURE_TAG_SUBSCRIPT)?;
+            for range in &marks.footnotes {
+                layout.SetTypography(&superscript, *range)?;
+                layout.SetDrawingEffect(&accent, *range)?;
+            }
+            for range in &marks.superscripts {
+                layout.SetTypography(&superscript, *range)?;
+            }
+            for range in &marks.subscripts {
+                layout.SetTypography(&subscript, *range)?;
+            }
+            for (ranges, color) in [
+                (&marks.links, self.theme.link_color),
+                (&marks.broken_links, BROKEN_LINK_COLOR),
+            ] {
+                let brush = self.create_brush(color.as_d2d())?;
+                for range in ranges {
+                    layout.SetUnderline(true, *range)?;
+                    layout.SetDrawingEffect(&brush, *range)?;
+                }
+            }
+            Ok(layout)
+        }
+    }
+
+    fn feature_typography(&self, tag: DWRITE_FONT_FEATURE_TAG) -> Result<IDWriteTypography> {
+        unsafe {
+            let typography = self.dwrite_factory.CreateTypography()?;
+            typography.AddFontFeature(DWRITE_FONT_FEATURE {
+                nameTag: tag,
+                parameter: 1,
+            })?;
+            Ok(typography)
+        }
+    }
+
+    /// Fills and outlines a framed paragraph's box and draws its icon in the border color.
+    /// On dark pages the shading is toned down so the page's light text stays readable.
+    fn draw_line_frame(
+        &self,
+        frame: &CanvasLineFrame,
+        rect: D2D_RECT_F,
+        icon_left: f32,
+        text_format: &IDWriteTextFormat,
+        dark_page: bool,
+    ) -> Result<()> {
+        if let Some(mut shading) = frame.shading {
+            if dark_page {
+                shading.a *= 0.22;
+            }
+            self.fill_rect(rect, shading)?;
+        }
+        if let Some((color, width)) = frame.border {
+            let brush = self.create_brush(color.as_d2d())?;
+            // Points to DIPs; the stroke is centered on the edge, so inset by half of it.
+            let stroke = (width * 96.0 / 72.0).max(1.0);
+            let inset = stroke * 0.5;
+            unsafe {
+                self.d2d_context.DrawRectangle(
+                    &D2D_RECT_F {
+                        left: rect.left + inset,
+                        top: rect.top + inset,
+                        right: rect.right - inset,
+                        bottom: rect.bottom - inset,
+                    },
+                    &brush,
+                    stroke,
+                    None::<&windows::Win32::Graphics::Direct2D::ID2D1StrokeStyle>,
+                );
+            }
+        }
+        if let Some(icon) = &frame.icon {
+            let color = frame.border.map_or(self.theme.accent, |(color, _)| color);
+            let brush = self.create_brush(color.as_d2d())?;
+            let icon = icon.encode_utf16().collect::<Vec<u16>>();
+            unsafe {
+                self.d2d_context.DrawText(
+                    &icon,
+                    text_format,
+                    &D2D_RECT_F {
+                        left: icon_left,
+                        top: rect.top + FRAME_PADDING.1,
+                        right: icon_left + FRAME_ICON_WIDTH,
+                        bottom: rect.bottom,
+                    },
+                    &brush,
+                    CLIPPED_TEXT_OPTIONS,
+                    DWRITE_MEASURING_MODE_NATURAL,
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills translucent boxes behind each find match. Highlight offsets index the preview
+    /// lines joined with newlines, so each one is mapped back onto its line's layout.
+    fn draw_find_highlights(
+        &self,
+        lines: &[PreviewLineLayout],
+        text_rect: D2D_RECT_F,
+        highlights: &[CanvasFindHighlight],
+    ) -> Result<()> {
+        let match_brush = self.create_brush(crate::ui::Color::rgba(1.0, 0.86, 0.2, 0.38).as_d2d())?;
+        let current_brush = self.create_brush(crate::ui::Color::rgba(1.0, 0.55, 0.1, 0.6).as_d2d())?;
+
+        for highlight in highlights {
+            let brush = if highlight.current {
+                &current_brush
+            } else {
+                &match_brush
+            };
+            for rect in text_range_rects(lines, text_rect, highlight.start, highlight.len) {
+                unsafe {
+                    self.d2d_context.FillRectangle(&rect, brush);
+                }
+            }
+        }
         Ok(())
     }
 
@@ -2435,30 +4228,27 @@ impl D2DRenderer {
         canvas_rect: D2D_RECT_F,
         shell: &ShellRenderState,
     ) -> Result<()> {
-        if !shell.canvas_scrollbar_visible && shell.canvas_scrollbar_alpha <= 0.01 {
-            return Ok(());
-        }
-
+        let fading = !shell.canvas_scrollbar_visible && shell.canvas_scrollbar_alpha <= 0.01;
         let alpha = shell.canvas_scrollbar_alpha.clamp(0.0, 1.0);
-        let thumb_color = crate::ui::Color::rgba(
-            self.theme.text_secondary.r,
-            self.theme.text_secondary.g,
-            self.theme.text_secondary.b,
-            0.45 * alpha,
-        );
-        let thumb_brush = self.create_brush(thumb_color.as_d2d())?;
+        let thumb_color = |alpha: f32| {
+            crate::ui::Color::rgba(
+                self.theme.text_secondary.r,
+                self.theme.text_secondary.g,
+                self.theme.text_secondary.b,
+                0.45 * alpha,
+            )
+        };
+        let thumb_brush = self.create_brush(thumb_color(alpha).as_d2d())?;
         let thickness = 6.0;
-        let viewport_h = shell.canvas_viewport_height.max(1.0);
-        let viewport_w = shell.canvas_viewport_width.max(1.0);
-        let content_h = shell.canvas_content_height.max(viewport_h);
-        let content_w = shell.canvas_content_width.max(viewport_w);
 
         unsafe {
-            if content_h > viewport_h + 1.0 {
-                let thumb_h = ((viewport_h / content_h) * viewport_h).clamp(28.0, viewport_h - 6.0);
-                let max_track = (viewport_h - thumb_h).max(1.0);
-                let max_scroll = (content_h - viewport_h).max(1.0);
-                let thumb_offset = (shell.canvas_scroll_y / max_scroll).clamp(0.0, 1.0) * max_track;
+            if !fading
+                && let Some((thumb_offset, thumb_h)) = scrollbar_thumb(
+                    shell.canvas_viewport_height,
+                    shell.canvas_content_height,
+                    shell.canvas_scroll_y,
+                )
+            {
                 let vbar = D2D_RECT_F {
                     left: canvas_rect.right - thickness - 2.0,
                     top: canvas_rect.top + thumb_offset + 2.0,
@@ -2468,18 +4258,27 @@ impl D2DRenderer {
                 self.d2d_context.FillRectangle(&vbar, &thumb_brush);
             }
 
-            if content_w > viewport_w + 1.0 {
-                let thumb_w = ((viewport_w / content_w) * viewport_w).clamp(28.0, viewport_w - 6.0);
-                let max_track = (viewport_w - thumb_w).max(1.0);
-                let max_scroll = (content_w - viewport_w).max(1.0);
-                let thumb_offset = (shell.canvas_scroll_x / max_scroll).clamp(0.0, 1.0) * max_track;
+            // Wide content keeps its horizontal bar on screen so it can always be dragged.
+            if let Some((thumb_offset, thumb_w)) = scrollbar_thumb(
+                shell.canvas_viewport_width,
+                shell.canvas_content_width,
+                shell.canvas_scroll_x,
+            ) {
+                let track = D2D_RECT_F {
+                    left: canvas_rect.left,
+                    top: canvas_rect.bottom - thickness - 4.0,
+                    right: canvas_rect.right,
+                    bottom: canvas_rect.bottom,
+                };
+                self.fill_rect(track, self.theme.scrollbar_track)?;
                 let hbar = D2D_RECT_F {
                     left: canvas_rect.left + thumb_offset + 2.0,
                     top: canvas_rect.bottom - thickness - 2.0,
                     right: canvas_rect.left + thumb_offset + thumb_w,
                     bottom: canvas_rect.bottom - 2.0,
                 };
-                self.d2d_context.FillRectangle(&hbar, &thumb_brush);
+                let hbar_brush = self.create_brush(thumb_color(alpha.max(0.8)).as_d2d())?;
+                self.d2d_context.FillRectangle(&hbar, &hbar_brush);
             }
         }
 
@@ -2709,7 +4508,7 @@ impl D2DRenderer {
                     bottom: rect.top + 38.0,
                 },
                 &text_brush,
-                D2D1_DRAW_TEXT_OPTIONS_NONE,
+                TEXT_OPTIONS,
                 DWRITE_MEASURING_MODE_NATURAL,
             );
         }
@@ -2731,6 +4530,29 @@ impl D2DRenderer {
             return Ok(existing.clone());
         }
 
+        let format = self.new_text_format(w!("en-US"))?;
+        *self.default_text_format.borrow_mut() = Some(format.clone());
+        Ok(format)
+    }
+
+    /// The canvas text format for the document's language. DirectWrite takes locale-specific
+    /// shaping and line breaking from it.
+    fn canvas_text_format(&self, language: &str) -> Result<IDWriteTextFormat> {
+        if let Some((cached, format)) = self.canvas_text_format.borrow().as_ref()
+            && cached == language
+        {
+            return Ok(format.clone());
+        }
+
+        let locale = HSTRING::from(language);
+        let format = self.new_text_format(PCWSTR(locale.as_ptr()))?;
+        // Layouts cached for the previous language were built from its format.
+        self.text_layouts.borrow_mut().clear();
+        *self.canvas_text_format.borrow_mut() = Some((language.to_string(), format.clone()));
+        Ok(format)
+    }
+
+    fn new_text_format(&self, locale: PCWSTR) -> Result<IDWriteTextFormat> {
         unsafe {
             let format = match self.dwrite_factory.CreateTextFormat(
                 w!("Segoe UI Variable"),
@@ -2739,7 +4561,7 @@ impl D2DRenderer {
                 windows::Win32::Graphics::DirectWrite::DWRITE_FONT_STYLE_NORMAL,
                 windows::Win32::Graphics::DirectWrite::DWRITE_FONT_STRETCH_NORMAL,
                 14.0,
-                w!("en-US"),
+                locale,
             ) {
                 Ok(format) => format,
                 Err(_) => self.dwrite_factory.CreateTextFormat(
@@ -2749,12 +4571,13 @@ impl D2DRenderer {
                     windows::Win32::Graphics::DirectWrite::DWRITE_FONT_STYLE_NORMAL,
                     windows::Win32::Graphics::DirectWrite::DWRITE_FONT_STRETCH_NORMAL,
                     14.0,
-                    w!("en-US"),
+                    locale,
                 )?,
             };
             let _ = format.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP);
-
-            *self.default_text_format.borrow_mut() = Some(format.clone());
+            if let Some(fallback) = &self.font_fallback {
+                let _ = apply_font_fallback(&format, fallback);
+            }
             Ok(format)
         }
     }
@@ -2855,3 +4678,17 @@ impl D2DRenderer {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{RenderDriver, driver_attempt_order};
+
+    #[test]
+    fn hardware_setting_maps_to_driver_order() {
+        assert_eq!(
+            driver_attempt_order(true),
+            &[RenderDriver::Hardware, RenderDriver::Warp]
+        );
+        assert_eq!(driver_attempt_order(false), &[RenderDriver::Warp]);
+    }
+}
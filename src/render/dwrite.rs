@@ -1,8 +1,166 @@
 use windows::{
-    Win32::Graphics::DirectWrite::{DWRITE_FACTORY_TYPE_SHARED, DWriteCreateFactory, IDWriteFactory},
-    core::Result,
+    Win32::Graphics::{
+        Direct2D::{D2D1_DRAW_TEXT_OPTIONS, D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT},
+        DirectWrite::{
+            DWRITE_FACTORY_TYPE_SHARED, DWRITE_UNICODE_RANGE, DWriteCreateFactory, IDWriteFactory,
+            IDWriteFactory2, IDWriteFontFallback, IDWriteLocalizedStrings, IDWriteTextFormat,
+            IDWriteTextFormat1,
+        },
+    },
+    core::{BOOL, Interface, PCWSTR, Result, w},
 };
 
+/// Color emoji font tried before the system fallback for emoji code points.
+const EMOJI_FONT_FAMILY: &str = "Segoe UI Emoji";
+
+/// Unicode blocks routed to the color emoji font.
+pub const EMOJI_RANGES: [DWRITE_UNICODE_RANGE; 6] = [
+    // Miscellaneous Symbols and Dingbats.
+    DWRITE_UNICODE_RANGE {
+        first: 0x2600,
+        last: 0x27BF,
+    },
+    // Mahjong through Symbols and Pictographs (includes flags and emoticons).
+    DWRITE_UNICODE_RANGE {
+        first: 0x1F000,
+        last: 0x1F6FF,
+    },
+    // Supplemental Symbols and Pictographs and Symbols and Pictographs Extended-A.
+    DWRITE_UNICODE_RANGE {
+        first: 0x1F900,
+        last: 0x1FAFF,
+    },
+    // Variation selector 16 (emoji presentation).
+    DWRITE_UNICODE_RANGE {
+        first: 0xFE0F,
+        last: 0xFE0F,
+    },
+    // Zero width joiner used in emoji sequences.
+    DWRITE_UNICODE_RANGE {
+        first: 0x200D,
+        last: 0x200D,
+    },
+    // Combining enclosing keycap.
+    DWRITE_UNICODE_RANGE {
+        first: 0x20E3,
+        last: 0x20E3,
+    },
+];
+
 pub fn create_factory() -> Result<IDWriteFactory> {
     unsafe { DWriteCreateFactory(DWRITE_FACTORY_TYPE_SHARED) }
 }
+
+/// Adds color-font rendering to `options`, so emoji glyphs draw in color rather than as outlines.
+pub const fn with_color_fonts(options: D2D1_DRAW_TEXT_OPTIONS) -> D2D1_DRAW_TEXT_OPTIONS {
+    D2D1_DRAW_TEXT_OPTIONS(options.0 | D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT.0)
+}
+
+/// Fallback chain of the color emoji font for emoji ranges, then the system fallback for
+/// everything else (CJK, Arabic, symbols...). Needs Windows 8.1 or later.
+pub fn create_font_fallback(factory: &IDWriteFactory) -> Result<IDWriteFontFallback> {
+    let factory: IDWriteFactory2 = factory.cast()?;
+    unsafe {
+        let builder = factory.CreateFontFallbackBuilder()?;
+        let family = EMOJI_FONT_FAMILY
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect::<Vec<u16>>();
+        builder.AddMapping(
+            &EMOJI_RANGES,
+            &[family.as_ptr()],
+            None,
+            PCWSTR::null(),
+            PCWSTR::null(),
+            1.0,
+        )?;
+        builder.AddMappings(&factory.GetSystemFontFallback()?)?;
+        builder.CreateFontFallback()
+    }
+}
+
+/// Attaches `fallback` to `format` so every layout built from it falls back per character.
+pub fn apply_font_fallback(format: &IDWriteTextFormat, fallback: &IDWriteFontFallback) -> Result<()> {
+    let format: IDWriteTextFormat1 = format.cast()?;
+    unsafe { format.SetFontFallback(fallback) }
+}
+
+/// Family names of the installed system fonts, sorted case-insensitively with duplicates removed.
+pub fn system_font_families() -> Result<Vec<String>> {
+    let factory = create_factory()?;
+    let mut collection = None;
+    unsafe { factory.GetSystemFontCollection(&mut collection, false)? };
+    let Some(collection) = collection else {
+        return Ok(Vec::new());
+    };
+
+    let count = unsafe { collection.GetFontFamilyCount() };
+    let mut families = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let Ok(family) = (unsafe { collection.GetFontFamily(index) }) else {
+            continue;
+        };
+        let Ok(names) = (unsafe { family.GetFamilyNames() }) else {
+            continue;
+        };
+        if let Some(name) = preferred_name(&names) {
+            families.push(name);
+        }
+    }
+
+    families.sort_by_key(|name| name.to_ascii_lowercase());
+    families.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+    Ok(families)
+}
+
+/// The en-us name when the font provides one, otherwise its first localized name.
+fn preferred_name(names: &IDWriteLocalizedStrings) -> Option<String> {
+    if unsafe { names.GetCount() } == 0 {
+        return None;
+    }
+
+    let mut index = 0u32;
+    let mut exists = BOOL(0);
+    unsafe { names.FindLocaleName(w!("en-us"), &mut index, &mut exists).ok()? };
+    if !exists.as_bool() {
+        index = 0;
+    }
+
+    let len = unsafe { names.GetStringLength(index).ok()? } as usize;
+    let mut buffer = vec![0u16; len + 1];
+    unsafe { names.GetString(index, &mut buffer).ok()? };
+    let name = String::from_utf16_lossy(&buffer[..len]);
+    (!name.trim().is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use windows::Win32::Graphics::Direct2D::{D2D1_DRAW_TEXT_OPTIONS_CLIP, D2D1_DRAW_TEXT_OPTIONS_NONE};
+
+    #[test]
+    fn color_font_option_is_added_without_dropping_others() {
+        let plain = with_color_fonts(D2D1_DRAW_TEXT_OPTIONS_NONE);
+        assert_eq!(plain.0, D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT.0);
+
+        let clipped = with_color_fonts(D2D1_DRAW_TEXT_OPTIONS_CLIP);
+        assert_ne!(clipped.0 & D2D1_DRAW_TEXT_OPTIONS_ENABLE_COLOR_FONT.0, 0);
+        assert_ne!(clipped.0 & D2D1_DRAW_TEXT_OPTIONS_CLIP.0, 0);
+    }
+
+    fn is_emoji_code_point(code_point: u32) -> bool {
+        EMOJI_RANGES
+            .iter()
+            .any(|range| (range.first..=range.last).contains(&code_point))
+    }
+
+    #[test]
+    fn emoji_ranges_cover_emoji_but_not_text() {
+        for emoji in ['😀', '👍', '🎉', '🧪', '☀', '❤', '\u{FE0F}'] {
+            assert!(is_emoji_code_point(emoji as u32), "{emoji:?}");
+        }
+        for text in ['A', 'é', '中', 'あ', 'ж', '1'] {
+            assert!(!is_emoji_code_point(text as u32), "{text:?}");
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     fs,
     hash::{Hash, Hasher},
     path::Path,
@@ -15,6 +15,9 @@ use crate::document::model::{
     ImageDataRef,
 };
 
+/// Mip-like thumbnail sizes; requests round up to the nearest level.
+const THUMBNAIL_LEVELS: [u32; 6] = [64, 128, 256, 512, 1024, 2048];
+
 #[derive(Debug, Clone)]
 pub struct DecodedBitmap {
     pub width: u32,
@@ -35,6 +38,7 @@ struct CacheEntry {
 pub struct ImageCacheStats {
     pub hits: u64,
     pub misses: u64,
+    pub evictions: u64,
     pub full_res_entries: usize,
     pub thumbnail_entries: usize,
     pub full_res_bytes: usize,
@@ -93,15 +97,19 @@ impl CacheStore {
         self.current_bytes = 0;
     }
 
-    fn prune_idle(&mut self, idle_ttl: Duration) {
+    fn prune_idle(&mut self, idle_ttl: Duration, visible: &HashSet<u64>) -> u64 {
         let now = Instant::now();
-        self.entries.retain(|_, entry| {
-            let stale = now.saturating_duration_since(entry.last_used) >= idle_ttl;
+        let mut evicted = 0;
+        self.entries.retain(|key, entry| {
+            let stale = !visible.contains(key)
+                && now.saturating_duration_since(entry.last_used) >= idle_ttl;
             if stale {
                 self.current_bytes = self.current_bytes.saturating_sub(entry.bytes);
+                evicted += 1;
             }
             !stale
         });
+        evicted
     }
 
     fn touch_keys(&mut self, keys: &[u64]) {
@@ -113,24 +121,29 @@ impl CacheStore {
         }
     }
 
-    fn remove_oldest_until(&mut self, max_bytes: usize) {
+    /// Evicts least-recently-used entries until under `max_bytes`, dropping every
+    /// non-visible entry before touching a visible one. Returns how many were evicted.
+    fn remove_oldest_until(&mut self, max_bytes: usize, visible: &HashSet<u64>) -> u64 {
         if self.current_bytes <= max_bytes {
-            return;
+            return 0;
         }
 
         let mut sorted = self
             .entries
             .iter()
-            .map(|(key, entry)| (*key, entry.last_used))
+            .map(|(key, entry)| (*key, visible.contains(key), entry.last_used))
             .collect::<Vec<_>>();
-        sorted.sort_by_key(|(_, last_used)| *last_used);
+        sorted.sort_by_key(|(_, is_visible, last_used)| (*is_visible, *last_used));
 
-        for (key, _) in sorted {
+        let mut evicted = 0;
+        for (key, _, _) in sorted {
             if self.current_bytes <= max_bytes {
                 break;
             }
             self.remove(key);
+            evicted += 1;
         }
+        evicted
     }
 }
 
@@ -141,6 +154,7 @@ pub struct ImageDecodeCache {
     full_res: CacheStore,
     thumbnails: CacheStore,
     idle_ttl: Duration,
+    visible: HashSet<u64>,
     stats: ImageCacheStats,
 }
 
@@ -152,6 +166,7 @@ impl Default for ImageDecodeCache {
             full_res: CacheStore::default(),
             thumbnails: CacheStore::default(),
             idle_ttl: Duration::from_secs(30),
+            visible: HashSet::new(),
             stats: ImageCacheStats::default(),
         }
     }
@@ -165,22 +180,49 @@ impl ImageDecodeCache {
     ) -> Result<DecodedBitmap, image::ImageError> {
         self.sweep_idle_decoded_bitmaps();
 
-        let key = hash_image_data(source, thumbnail_max_dim);
-        let target_cache = if thumbnail_max_dim.is_some() {
-            &mut self.thumbnails
-        } else {
-            &mut self.full_res
-        };
+        // The bytes are hashed once; each level's key only mixes the level into that hash.
+        let source_hash = hash_image_data(source);
+        let Some(max_dim) = thumbnail_max_dim.filter(|dim| *dim > 0) else {
+            let key = level_key(source_hash, None);
+            if let Some(bitmap) = self.full_res.get(key) {
+                self.stats.hits += 1;
+                return Ok(bitmap);
+            }
+            self.stats.misses += 1;
 
-        if let Some(bitmap) = target_cache.get(key) {
-            self.stats.hits += 1;
+            let image = image::load_from_memory(&source.bytes)?;
+            let bitmap = decode_bitmap(image, key, None);
+            self.full_res.insert(key, bitmap.clone());
+            self.prune_memory();
+            self.update_stats();
             return Ok(bitmap);
+        };
+
+        // Thumbnails are kept per mip level; any cached level at least as large as the
+        // request is close enough to draw without decoding again.
+        let level = thumbnail_level(max_dim);
+        for candidate in THUMBNAIL_LEVELS.iter().copied().filter(|dim| *dim >= level) {
+            if let Some(bitmap) = self.thumbnails.get(level_key(source_hash, Some(candidate))) {
+                self.stats.hits += 1;
+                return Ok(bitmap);
+            }
         }
         self.stats.misses += 1;
 
-        let image = image::load_from_memory(&source.bytes)?;
-        let bitmap = decode_bitmap(image, key, thumbnail_max_dim);
-        target_cache.insert(key, bitmap.clone());
+        let key = level_key(source_hash, Some(level));
+        let full_key = level_key(source_hash, None);
+        // Downscale from an already decoded full-resolution bitmap when one is resident.
+        let image = match self.full_res.get(full_key) {
+            Some(full) => image::RgbaImage::from_raw(full.width, full.height, full.rgba)
+                .map(DynamicImage::ImageRgba8),
+            None => None,
+        };
+        let image = match image {
+            Some(image) => image,
+            None => image::load_from_memory(&source.bytes)?,
+        };
+        let bitmap = decode_bitmap(image, key, Some(level));
+        self.thumbnails.insert(key, bitmap.clone());
 
         self.prune_memory();
         self.update_stats();
@@ -207,14 +249,16 @@ impl ImageDecodeCache {
         self.update_stats();
     }
 
+    /// Records the hashes drawn this frame; they are kept resident ahead of everything else.
     pub fn mark_visible_hashes(&mut self, hashes: &[u64]) {
+        self.visible = hashes.iter().copied().collect();
         self.full_res.touch_keys(hashes);
         self.thumbnails.touch_keys(hashes);
     }
 
     pub fn sweep_idle_decoded_bitmaps(&mut self) {
-        self.full_res.prune_idle(self.idle_ttl);
-        self.thumbnails.prune_idle(self.idle_ttl);
+        self.stats.evictions += self.full_res.prune_idle(self.idle_ttl, &self.visible);
+        self.stats.evictions += self.thumbnails.prune_idle(self.idle_ttl, &self.visible);
         self.update_stats();
     }
 
@@ -226,8 +270,10 @@ impl ImageDecodeCache {
         let full_budget = (self.max_bytes as f32 * 0.80) as usize;
         let thumb_budget = self.max_bytes.saturating_sub(full_budget);
 
-        self.full_res.remove_oldest_until(full_budget);
-        self.thumbnails.remove_oldest_until(thumb_budget.max(8 * 1024 * 1024));
+        self.stats.evictions += self.full_res.remove_oldest_until(full_budget, &self.visible);
+        self.stats.evictions += self
+            .thumbnails
+            .remove_oldest_until(thumb_budget.max(8 * 1024 * 1024), &self.visible);
 
         self.current_bytes = self.full_res.current_bytes + self.thumbnails.current_bytes;
     }
@@ -312,12 +358,28 @@ fn decode_bitmap(image: DynamicImage, source_hash: u64, thumbnail_max_dim: Optio
     }
 }
 
-fn hash_image_data(image: &ImageData, thumb_dim: Option<u32>) -> u64 {
+fn thumbnail_level(max_dim: u32) -> u32 {
+    THUMBNAIL_LEVELS
+        .iter()
+        .copied()
+        .find(|level| *level >= max_dim)
+        .unwrap_or(THUMBNAIL_LEVELS[THUMBNAIL_LEVELS.len() - 1])
+}
+
+fn hash_image_data(image: &ImageData) -> u64 {
     let mut hasher = std::collections::hash_map::DefaultHasher::new();
     image.bytes.hash(&mut hasher);
     image.width.hash(&mut hasher);
     image.height.hash(&mut hasher);
     image.mime.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Cache key of one decoded size of the image hashed to `source_hash`; `None` is full
+/// resolution.
+fn level_key(source_hash: u64, thumb_dim: Option<u32>) -> u64 {
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    source_hash.hash(&mut hasher);
     thumb_dim.unwrap_or(0).hash(&mut hasher);
     hasher.finish()
 }
@@ -335,3 +397,90 @@ fn mime_for_extension(ext: Option<&str>) -> String {
     }
     .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bitmap(source_hash: u64, bytes: usize) -> DecodedBitmap {
+        DecodedBitmap {
+            width: 1,
+            height: 1,
+            rgba: vec![0; bytes],
+            source_hash,
+            is_thumbnail: false,
+        }
+    }
+
+    fn cache_with(keys: &[u64]) -> ImageDecodeCache {
+        let mut cache = ImageDecodeCache::default();
+        for key in keys {
+            cache.full_res.insert(*key, bitmap(*key, 1000));
+            std::thread::sleep(Duration::from_millis(2));
+        }
+        cache
+    }
+
+    fn png_data(width: u32, height: u32) -> ImageData {
+        let mut bytes = Vec::new();
+        DynamicImage::ImageRgba8(image::RgbaImage::new(width, height))
+            .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .expect("encode png");
+        ImageData {
+            bytes,
+            mime: "image/png".to_string(),
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn repeated_thumbnail_requests_are_served_from_cache() {
+        let mut cache = ImageDecodeCache::default();
+        let data = png_data(900, 600);
+
+        let first = cache.get_or_decode(&data, Some(384)).expect("decode");
+        assert_eq!((cache.stats().hits, cache.stats().misses), (0, 1));
+        assert!(first.is_thumbnail);
+        assert_eq!(first.width.max(first.height), 512);
+
+        let second = cache.get_or_decode(&data, Some(384)).expect("decode");
+        assert_eq!((cache.stats().hits, cache.stats().misses), (1, 1));
+        assert_eq!(second.source_hash, first.source_hash);
+
+        // A smaller request is served by the resident larger level.
+        let smaller = cache.get_or_decode(&data, Some(100)).expect("decode");
+        assert_eq!((cache.stats().hits, cache.stats().misses), (2, 1));
+        assert_eq!(smaller.source_hash, first.source_hash);
+        assert_eq!(cache.stats().thumbnail_entries, 1);
+    }
+
+    #[test]
+    fn visible_hash_survives_when_a_hidden_one_can_go() {
+        // Key 1 is the least recently used, but it is on screen.
+        let mut cache = cache_with(&[1, 2, 3]);
+        cache.visible = [1].into_iter().collect();
+        cache.max_bytes = 2500;
+        cache.prune_memory();
+        cache.update_stats();
+
+        assert!(cache.full_res.entries.contains_key(&1));
+        assert!(!cache.full_res.entries.contains_key(&2));
+        assert!(cache.full_res.entries.contains_key(&3));
+        assert_eq!(cache.stats().evictions, 1);
+        assert_eq!(cache.stats().full_res_entries, 2);
+    }
+
+    #[test]
+    fn visible_hashes_are_evicted_only_after_all_others() {
+        let mut cache = cache_with(&[1, 2, 3]);
+        cache.visible = [1, 3].into_iter().collect();
+        cache.max_bytes = 1250;
+        cache.prune_memory();
+
+        assert!(!cache.full_res.entries.contains_key(&2));
+        assert!(!cache.full_res.entries.contains_key(&1));
+        assert!(cache.full_res.entries.contains_key(&3));
+        assert_eq!(cache.stats().evictions, 2);
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+
+/// Default number of text layouts kept alive between frames.
+pub const LAYOUT_CACHE_CAPACITY: usize = 256;
+
+/// Identifies a laid-out run of text: the text itself, the text format it was built with
+/// and the layout width. Any edit or resize produces a different key.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TextLayoutKey {
+    text: String,
+    style: &'static str,
+    font_size_bits: u32,
+    width_bits: u32,
+}
+
+impl TextLayoutKey {
+    pub fn new(text: &str, style: &'static str, font_size: f32, width: f32) -> Self {
+        Self {
+            text: text.to_string(),
+            style,
+            font_size_bits: font_size.to_bits(),
+            // Sub-pixel width jitter shouldn't defeat the cache.
+            width_bits: width.round().to_bits(),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct CachedLayout<V> {
+    value: V,
+    last_used: u64,
+}
+
+/// Bounded least-recently-used cache of text layouts.
+#[derive(Debug)]
+pub struct TextLayoutCache<V> {
+    entries: HashMap<TextLayoutKey, CachedLayout<V>>,
+    capacity: usize,
+    clock: u64,
+}
+
+impl<V: Clone> TextLayoutCache<V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            capacity: capacity.max(1),
+            clock: 0,
+        }
+    }
+
+    pub fn get(&mut self, key: &TextLayoutKey) -> Option<V> {
+        self.clock += 1;
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.value.clone())
+    }
+
+    pub fn insert(&mut self, key: TextLayoutKey, value: V) {
+        self.clock += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict_least_recent();
+        }
+        self.entries.insert(
+            key,
+            CachedLayout {
+                value,
+                last_used: self.clock,
+            },
+        );
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn evict_least_recent(&mut self) {
+        let oldest = self
+            .entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(text: &str, width: f32) -> TextLayoutKey {
+        TextLayoutKey::new(text, "canvas", 14.0, width)
+    }
+
+    #[test]
+    fn keys_compare_text_style_size_and_width() {
+        assert_eq!(key("Hello", 400.0), key("Hello", 400.2));
+        assert_ne!(key("Hello", 400.0), key("Hello!", 400.0));
+        assert_ne!(key("Hello", 400.0), key("Hello", 380.0));
+        assert_ne!(
+            key("Hello", 400.0),
+            TextLayoutKey::new("Hello", "canvas", 16.0, 400.0)
+        );
+        assert_ne!(
+            key("Hello", 400.0),
+            TextLayoutKey::new("Hello", "table", 14.0, 400.0)
+        );
+    }
+
+    #[test]
+    fn least_recently_used_layout_is_evicted_first() {
+        let mut cache = TextLayoutCache::new(2);
+        cache.insert(key("a", 100.0), 1);
+        cache.insert(key("b", 100.0), 2);
+        // Touching "a" makes "b" the eviction candidate.
+        assert_eq!(cache.get(&key("a", 100.0)), Some(1));
+        cache.insert(key("c", 100.0), 3);
+
+        assert_eq!(cache.get(&key("b", 100.0)), None);
+        assert_eq!(cache.get(&key("a", 100.0)), Some(1));
+        assert_eq!(cache.get(&key("c", 100.0)), Some(3));
+
+        // Replacing an existing key never evicts another entry.
+        cache.insert(key("c", 100.0), 4);
+        assert_eq!(cache.get(&key("a", 100.0)), Some(1));
+        assert_eq!(cache.get(&key("c", 100.0)), Some(4));
+
+        cache.clear();
+        assert_eq!(cache.get(&key("a", 100.0)), None);
+    }
+}
@@ -1,6 +1,8 @@
 pub mod animation;
+pub mod block_layout;
 pub mod canvas;
 pub mod d2d;
 pub mod dwrite;
 pub mod image_cache;
+pub mod layout_cache;
 pub mod perf;
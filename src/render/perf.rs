@@ -1,4 +1,7 @@
-use std::time::{Duration, Instant};
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
 
 use crate::render::image_cache::ImageCacheStats;
 
@@ -12,12 +15,70 @@ pub struct PerformanceSnapshot {
     pub process_memory_mb: f32,
     pub image_cache_hit_rate: f32,
     pub image_cache_mb: f32,
+    pub image_cache_evictions: u64,
+    pub frame_avg_ms: f32,
+    pub frame_p95_ms: f32,
+    pub input_latency_ms: f32,
+}
+
+/// Frames kept for the debug panel's rolling graph and summary.
+pub const FRAME_HISTORY_LEN: usize = 120;
+
+/// Fixed-size window of the most recent samples.
+#[derive(Debug, Clone)]
+pub struct RollingSamples {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl RollingSamples {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn push(&mut self, sample: f32) {
+        if self.samples.len() == self.capacity {
+            let _ = self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = f32> + '_ {
+        self.samples.iter().copied()
+    }
+
+    pub fn average(&self) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        self.samples.iter().sum::<f32>() / self.samples.len() as f32
+    }
+
+    /// Nearest-rank percentile, `pct` in 0..=100.
+    pub fn percentile(&self, pct: f32) -> f32 {
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+        let mut sorted = self.samples.iter().copied().collect::<Vec<_>>();
+        sorted.sort_by(f32::total_cmp);
+        let rank = ((pct.clamp(0.0, 100.0) / 100.0) * sorted.len() as f32).ceil() as usize;
+        sorted[rank.saturating_sub(1).min(sorted.len() - 1)]
+    }
+
+    pub fn max(&self) -> f32 {
+        self.samples.iter().copied().fold(0.0, f32::max)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct DebugPerformancePanel {
     pub visible: bool,
     pub snapshot: PerformanceSnapshot,
+    pub frame_history: RollingSamples,
+    pub repaint_reasons: Vec<&'static str>,
     frame_window_start: Instant,
     frame_count: u32,
 }
@@ -27,6 +88,8 @@ impl Default for DebugPerformancePanel {
         Self {
             visible: false,
             snapshot: PerformanceSnapshot::default(),
+            frame_history: RollingSamples::new(FRAME_HISTORY_LEN),
+            repaint_reasons: Vec::new(),
             frame_window_start: Instant::now(),
             frame_count: 0,
         }
@@ -44,6 +107,9 @@ impl DebugPerformancePanel {
 
     pub fn update_frame_time(&mut self, frame_time_ms: f32) {
         self.snapshot.frame_time_ms = frame_time_ms.max(0.0);
+        self.frame_history.push(self.snapshot.frame_time_ms);
+        self.snapshot.frame_avg_ms = self.frame_history.average();
+        self.snapshot.frame_p95_ms = self.frame_history.percentile(95.0);
         self.frame_count += 1;
 
         let elapsed = self.frame_window_start.elapsed();
@@ -55,6 +121,13 @@ impl DebugPerformancePanel {
         }
     }
 
+    /// Context for the frame about to render: time since the last input message and
+    /// why the window asked for another paint.
+    pub fn update_frame_context(&mut self, since_input: Duration, reasons: Vec<&'static str>) {
+        self.snapshot.input_latency_ms = since_input.as_secs_f32() * 1000.0;
+        self.repaint_reasons = reasons;
+    }
+
     pub fn update_memory_bytes(&mut self, bytes: u64) {
         self.snapshot.process_memory_mb = bytes as f32 / (1024.0 * 1024.0);
     }
@@ -63,6 +136,7 @@ impl DebugPerformancePanel {
         self.snapshot.image_cache_hit_rate = stats.hit_rate();
         self.snapshot.image_cache_mb =
             (stats.full_res_bytes + stats.thumbnail_bytes) as f32 / (1024.0 * 1024.0);
+        self.snapshot.image_cache_evictions = stats.evictions;
     }
 }
 
@@ -181,6 +255,49 @@ pub fn query_process_working_set_bytes() -> Option<u64> {
     None
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolling_samples_drop_oldest_and_average_the_window() {
+        let mut samples = RollingSamples::new(4);
+        assert_eq!(samples.average(), 0.0);
+        assert_eq!(samples.percentile(95.0), 0.0);
+        for value in [100.0, 2.0, 4.0, 6.0, 8.0] {
+            samples.push(value);
+        }
+        assert_eq!(samples.iter().collect::<Vec<_>>(), vec![2.0, 4.0, 6.0, 8.0]);
+        assert_eq!(samples.average(), 5.0);
+        assert_eq!(samples.max(), 8.0);
+    }
+
+    #[test]
+    fn percentile_uses_nearest_rank() {
+        let mut samples = RollingSamples::new(FRAME_HISTORY_LEN);
+        for value in (1..=20).rev() {
+            samples.push(value as f32);
+        }
+        assert_eq!(samples.percentile(50.0), 10.0);
+        assert_eq!(samples.percentile(95.0), 19.0);
+        assert_eq!(samples.percentile(100.0), 20.0);
+        assert_eq!(samples.percentile(0.0), 1.0);
+    }
+
+    #[test]
+    fn panel_summarises_frame_history() {
+        let mut panel = DebugPerformancePanel::default();
+        for ms in [10.0, 20.0, 30.0] {
+            panel.update_frame_time(ms);
+        }
+        assert_eq!(panel.snapshot.frame_avg_ms, 20.0);
+        assert_eq!(panel.snapshot.frame_p95_ms, 30.0);
+        panel.update_frame_context(Duration::from_millis(250), vec!["input"]);
+        assert_eq!(panel.snapshot.input_latency_ms, 250.0);
+        assert_eq!(panel.repaint_reasons, vec!["input"]);
+    }
+}
+
 #[macro_export]
 macro_rules! profile_scope {
     ($name:expr) => {
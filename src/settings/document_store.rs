@@ -0,0 +1,141 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+/// Entries kept per document path in a JSON file next to the other app data, such as the
+/// folds or bookmarks of documents opened before. Every window keeps its own copy, so
+/// saving merges in what other windows wrote since.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DocumentStore<T> {
+    documents: BTreeMap<String, Vec<T>>,
+    /// Documents recorded since the store was loaded or last saved.
+    #[serde(skip)]
+    changed: BTreeSet<String>,
+}
+
+impl<T> Default for DocumentStore<T> {
+    fn default() -> Self {
+        Self {
+            documents: BTreeMap::new(),
+            changed: BTreeSet::new(),
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> DocumentStore<T> {
+    /// Reads the store at `path`; a missing or unreadable file starts empty.
+    pub fn load_from(path: &Path) -> Self {
+        fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    /// Writes the store to `path`: the file as it is now, with the documents recorded here
+    /// since the last save put over it. Afterwards this store matches the file.
+    pub fn save_to(&mut self, path: &Path) -> std::io::Result<()> {
+        let mut merged = Self::load_from(path).documents;
+        for key in std::mem::take(&mut self.changed) {
+            match self.documents.remove(&key) {
+                Some(entries) => merged.insert(key, entries),
+                None => merged.remove(&key),
+            };
+        }
+        self.documents = merged;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(path, json)
+    }
+
+    /// Records the entries of the document at `document`; no entries forgets it.
+    pub fn remember(&mut self, document: &Path, entries: Vec<T>) {
+        let key = store_key(document);
+        self.changed.insert(key.clone());
+        if entries.is_empty() {
+            self.documents.remove(&key);
+        } else {
+            self.documents.insert(key, entries);
+        }
+    }
+
+    /// The entries kept for the document at `document`, in the order they were recorded.
+    pub fn restore(&self, document: &Path) -> &[T] {
+        self.documents
+            .get(&store_key(document))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+/// Windows paths compare without regard to case.
+fn store_key(document: &Path) -> String {
+    document.to_string_lossy().to_lowercase()
+}
+
+/// Where the app data file `name` lives: beside a portable install, else in the user's
+/// data folder.
+pub fn data_file_path(name: &str) -> PathBuf {
+    if let Some(portable) = super::portable_root() {
+        return portable.join(name);
+    }
+
+    if let Some(base) = dirs::data_dir() {
+        base.join("Doco").join(name)
+    } else {
+        PathBuf::from(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entries_survive_saving_and_reopening() {
+        let path = std::env::temp_dir().join(format!("doco-store-{}.json", std::process::id()));
+        let mut store = DocumentStore::default();
+        store.remember(Path::new("C:/Docs/Guide.md"), vec![4, 6]);
+        store.save_to(&path).expect("save store");
+
+        let mut reloaded = DocumentStore::<u32>::load_from(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(reloaded.restore(Path::new("c:/docs/guide.md")), [4, 6]);
+        assert!(reloaded.restore(Path::new("C:/Docs/Other.md")).is_empty());
+
+        // No entries forgets the document.
+        reloaded.remember(Path::new("C:/Docs/Guide.md"), Vec::new());
+        assert!(reloaded.documents.is_empty());
+    }
+
+    #[test]
+    fn saving_keeps_what_another_window_saved() {
+        let path =
+            std::env::temp_dir().join(format!("doco-store-merge-{}.json", std::process::id()));
+        let mut seed = DocumentStore::default();
+        seed.remember(Path::new("C:/Docs/Old.md"), vec![1]);
+        seed.save_to(&path).expect("seed store");
+
+        // Two windows load the same file and each records a different document.
+        let mut first = DocumentStore::<u32>::load_from(&path);
+        let mut second = DocumentStore::<u32>::load_from(&path);
+        first.remember(Path::new("C:/Docs/A.md"), vec![2]);
+        second.remember(Path::new("C:/Docs/B.md"), vec![3]);
+        second.remember(Path::new("C:/Docs/Old.md"), Vec::new());
+        first.save_to(&path).expect("save first");
+        second.save_to(&path).expect("save second");
+
+        let merged = DocumentStore::<u32>::load_from(&path);
+        let _ = fs::remove_file(&path);
+        assert_eq!(merged.restore(Path::new("C:/Docs/A.md")), [2]);
+        assert_eq!(merged.restore(Path::new("C:/Docs/B.md")), [3]);
+        assert!(merged.restore(Path::new("C:/Docs/Old.md")).is_empty());
+        assert_eq!(second.restore(Path::new("C:/Docs/A.md")), [2]);
+    }
+}
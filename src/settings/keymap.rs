@@ -0,0 +1,237 @@
+use std::fmt;
+
+/// Keys that have a name rather than a printable character on them.
+const NAMED_KEYS: [(u32, &str); 26] = [
+    (0x08, "Backspace"),
+    (0x09, "Tab"),
+    (0x0D, "Enter"),
+    (0x1B, "Esc"),
+    (0x20, "Space"),
+    (0x21, "PageUp"),
+    (0x22, "PageDown"),
+    (0x23, "End"),
+    (0x24, "Home"),
+    (0x25, "Left"),
+    (0x26, "Up"),
+    (0x27, "Right"),
+    (0x28, "Down"),
+    (0x2D, "Insert"),
+    (0x2E, "Delete"),
+    (0xBA, ";"),
+    (0xBB, "="),
+    (0xBC, ","),
+    (0xBD, "-"),
+    (0xBE, "."),
+    (0xBF, "/"),
+    (0xC0, "`"),
+    (0xDB, "["),
+    (0xDC, "\\"),
+    (0xDD, "]"),
+    (0xDE, "'"),
+];
+
+/// Other spellings accepted when reading a hand-edited settings file.
+const KEY_ALIASES: [(&str, u32); 9] = [
+    ("escape", 0x1B),
+    ("return", 0x0D),
+    ("del", 0x2E),
+    ("ins", 0x2D),
+    ("pgup", 0x21),
+    ("pgdn", 0x22),
+    ("plus", 0xBB),
+    ("+", 0xBB),
+    ("comma", 0xBC),
+];
+
+/// One key with its modifiers, written the way `keyboard_shortcuts` stores it:
+/// "Ctrl+Shift+P".
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyChord {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    /// Virtual-key code of the non-modifier key.
+    pub vk: u32,
+}
+
+impl KeyChord {
+    pub fn new(vk: u32, ctrl: bool, shift: bool, alt: bool) -> Self {
+        Self {
+            ctrl,
+            shift,
+            alt,
+            vk,
+        }
+    }
+
+    /// Reads "Ctrl+Shift+P", in any case and modifier order. "Ctrl++" is Ctrl with the
+    /// plus key.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => text.rsplit_once('+').unwrap_or(("", text)),
+        };
+        let mut chord = Self::new(vk_from_name(key.trim())?, false, false, false);
+        for modifier in modifiers.split('+').filter(|m| !m.trim().is_empty()) {
+            match modifier.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "shift" => chord.shift = true,
+                "alt" => chord.alt = true,
+                _ => return None,
+            }
+        }
+        Some(chord)
+    }
+
+    /// Whether `vk` is a modifier on its own, which can't finish a chord.
+    pub fn is_modifier_key(vk: u32) -> bool {
+        matches!(vk, 0x10 | 0x11 | 0x12 | 0x5B | 0x5C | 0xA0..=0xA5)
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.ctrl {
+            f.write_str("Ctrl+")?;
+        }
+        if self.shift {
+            f.write_str("Shift+")?;
+        }
+        if self.alt {
+            f.write_str("Alt+")?;
+        }
+        match key_name(self.vk) {
+            Some(name) => f.write_str(&name),
+            None => write!(f, "0x{:02X}", self.vk),
+        }
+    }
+}
+
+/// A shortcut as one chord or a two-step chord such as "Ctrl+K Ctrl+S", where the second
+/// chord is pressed after releasing the first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeySequence {
+    pub first: KeyChord,
+    pub second: Option<KeyChord>,
+}
+
+impl KeySequence {
+    /// Reads one or two space-separated chords; spaces around `+` don't split a chord.
+    pub fn parse(text: &str) -> Option<Self> {
+        let compact = text.split('+').map(str::trim).collect::<Vec<_>>().join("+");
+        let mut chords = compact.split_whitespace().map(KeyChord::parse);
+        let first = chords.next()??;
+        let second = match chords.next() {
+            Some(chord) => Some(chord?),
+            None => None,
+        };
+        if chords.next().is_some() {
+            return None;
+        }
+        Some(Self { first, second })
+    }
+}
+
+impl fmt::Display for KeySequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.second {
+            Some(second) => write!(f, "{} {}", self.first, second),
+            None => write!(f, "{}", self.first),
+        }
+    }
+}
+
+fn key_name(vk: u32) -> Option<String> {
+    match vk {
+        0x30..=0x39 | 0x41..=0x5A => char::from_u32(vk).map(String::from),
+        0x70..=0x87 => Some(format!("F{}", vk - 0x6F)),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|(code, _)| *code == vk)
+            .map(|(_, name)| name.to_string()),
+    }
+}
+
+fn vk_from_name(name: &str) -> Option<u32> {
+    let upper = name.to_ascii_uppercase();
+    if upper.len() == 1
+        && let Some(ch) = upper.chars().next()
+        && ch.is_ascii_alphanumeric()
+    {
+        return Some(ch as u32);
+    }
+    if let Some(number) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok())
+        && (1..=24).contains(&number)
+    {
+        return Some(0x6F + number);
+    }
+    if let Some(hex) = upper.strip_prefix("0X") {
+        return u32::from_str_radix(hex, 16).ok();
+    }
+    NAMED_KEYS
+        .iter()
+        .find(|(_, key)| key.eq_ignore_ascii_case(name))
+        .map(|(code, _)| *code)
+        .or_else(|| {
+            KEY_ALIASES
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+                .map(|(_, code)| *code)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_read_loosely_and_print_canonically() {
+        let chord = KeyChord::parse("shift + ctrl + p").expect("chord");
+        assert_eq!(chord, KeyChord::new(0x50, true, true, false));
+        assert_eq!(chord.to_string(), "Ctrl+Shift+P");
+
+        assert_eq!(KeyChord::parse("Ctrl+,").map(|c| c.vk), Some(0xBC));
+        assert_eq!(KeyChord::parse("Ctrl++"), KeyChord::parse("Ctrl+="));
+        assert_eq!(
+            KeyChord::parse("alt+f4").map(|c| c.to_string()).as_deref(),
+            Some("Alt+F4")
+        );
+        assert_eq!(
+            KeyChord::parse("Escape"),
+            Some(KeyChord::new(0x1B, false, false, false))
+        );
+        assert_eq!(KeyChord::parse("Hyper+K"), None);
+        assert_eq!(KeyChord::parse(""), None);
+    }
+
+    #[test]
+    fn chords_format_as_shortcut_hints() {
+        let hint = |vk, ctrl, shift, alt| KeyChord::new(vk, ctrl, shift, alt).to_string();
+        assert_eq!(hint(0x46, true, true, false), "Ctrl+Shift+F");
+        assert_eq!(hint(0x53, true, false, true), "Ctrl+Alt+S");
+        assert_eq!(hint(0x31, false, false, true), "Alt+1");
+        assert_eq!(hint(0x72, false, true, false), "Shift+F3");
+        assert_eq!(hint(0xBC, true, false, false), "Ctrl+,");
+        assert_eq!(hint(0x2E, false, false, false), "Delete");
+        // Keys without a name fall back to their code.
+        assert_eq!(hint(0xE2, true, false, false), "Ctrl+0xE2");
+    }
+
+    #[test]
+    fn sequences_hold_one_or_two_chords() {
+        let ctrl = |vk| KeyChord::new(vk, true, false, false);
+        let chord = KeySequence::parse("ctrl+k  ctrl + s").expect("chord");
+        assert_eq!(chord.first, ctrl(0x4B));
+        assert_eq!(chord.second, Some(ctrl(0x53)));
+        assert_eq!(chord.to_string(), "Ctrl+K Ctrl+S");
+
+        let single = KeySequence::parse("Shift + Ctrl + P").expect("single");
+        assert_eq!(single.second, None);
+        assert_eq!(single.to_string(), "Ctrl+Shift+P");
+
+        assert_eq!(KeySequence::parse("Ctrl+K Ctrl+S Ctrl+X"), None);
+        assert_eq!(KeySequence::parse("Ctrl+K Hyper+S"), None);
+        assert_eq!(KeySequence::parse("   "), None);
+    }
+}
@@ -1,4 +1,7 @@
+pub mod document_store;
+pub mod keymap;
 pub mod schema;
+pub mod workspace;
 
 use std::{
     fs,
@@ -180,7 +183,13 @@ fn settings_catalog() -> &'static [SettingSearchHit] {
             category: SettingsCategory::Appearance,
             setting_key: "appearance.canvas_background",
             title: "Canvas Background",
-            summary: "Preset paper/background styling plus custom option.",
+            summary: "Preset paper/background styling, a sepia reading page, plus custom option.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Appearance,
+            setting_key: "appearance.page_color",
+            title: "Page Color",
+            summary: "Keep the page white in dark mode, dark in light mode, or matching the theme.",
         },
         SettingSearchHit {
             category: SettingsCategory::Appearance,
@@ -284,12 +293,48 @@ fn settings_catalog() -> &'static [SettingSearchHit] {
             title: "Auto-close Brackets",
             summary: "Insert matching closing brackets automatically.",
         },
+        SettingSearchHit {
+            category: SettingsCategory::Editor,
+            setting_key: "editor.middle_click_paste",
+            title: "Middle-click Paste",
+            summary: "Paste clipboard text where you middle-click instead of panning.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Editor,
+            setting_key: "editor.scroll_speed",
+            title: "Scroll Speed",
+            summary: "How far one notch of the mouse wheel scrolls.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Editor,
+            setting_key: "editor.natural_scrolling",
+            title: "Natural Scrolling",
+            summary: "Invert the wheel so the page follows your fingers.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Editor,
+            setting_key: "editor.zoom_sensitivity",
+            title: "Zoom Sensitivity",
+            summary: "How far one notch of Ctrl+wheel zooms.",
+        },
         SettingSearchHit {
             category: SettingsCategory::Editor,
             setting_key: "editor.show_whitespace",
             title: "Show Whitespace",
             summary: "Off, selection only, or show all whitespace.",
         },
+        SettingSearchHit {
+            category: SettingsCategory::Editor,
+            setting_key: "editor.date_time_format",
+            title: "Date/Time Format",
+            summary: "Format used by Insert Date/Time.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Editor,
+            setting_key: "editor.find_debounce_ms",
+            title: "Live Search Delay",
+            summary: "Pause after typing before the find bar searches.",
+        },
         SettingSearchHit {
             category: SettingsCategory::Document,
             setting_key: "document.default_page_size",
@@ -332,6 +377,12 @@ fn settings_catalog() -> &'static [SettingSearchHit] {
             title: "Auto-save Interval",
             summary: "Configure auto-save frequency or disable it.",
         },
+        SettingSearchHit {
+            category: SettingsCategory::Files,
+            setting_key: "files.auto_save_custom_seconds",
+            title: "Custom Auto-save Interval",
+            summary: "Type a number of seconds between auto-saves, from 5 to 3600.",
+        },
         SettingSearchHit {
             category: SettingsCategory::Files,
             setting_key: "files.create_backup_before_save",
@@ -356,11 +407,29 @@ fn settings_catalog() -> &'static [SettingSearchHit] {
             title: "Default Open Folder",
             summary: "Last used, Documents, or a specific path.",
         },
+        SettingSearchHit {
+            category: SettingsCategory::Files,
+            setting_key: "files.open_delimited_as_table",
+            title: "Open CSV/TSV as Table",
+            summary: "Edit delimited files as a table instead of plain text.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Files,
+            setting_key: "files.optimize_images_on_save",
+            title: "Optimize Images on Save",
+            summary: "Shrink large embedded images when saving DOCX or PDF.",
+        },
+        SettingSearchHit {
+            category: SettingsCategory::Files,
+            setting_key: "files.image_quality",
+            title: "Image Quality",
+            summary: "Low, Medium or High quality for optimized images.",
+        },
         SettingSearchHit {
             category: SettingsCategory::KeyboardShortcuts,
             setting_key: "keyboard_shortcuts.bindings",
             title: "Keyboard Shortcuts",
-            summary: "Customize key bindings and detect conflicts.",
+            summary: "Reassign or clear key bindings and resolve conflicts.",
         },
         SettingSearchHit {
             category: SettingsCategory::KeyboardShortcuts,
@@ -414,13 +483,13 @@ fn settings_catalog() -> &'static [SettingSearchHit] {
             category: SettingsCategory::About,
             setting_key: "about.licenses_url",
             title: "Open Source Licenses",
-            summary: "Location of license information.",
+            summary: "Licenses of the open-source crates Doco is built from, searchable by name.",
         },
         SettingSearchHit {
             category: SettingsCategory::About,
             setting_key: "about.system_info_snapshot",
             title: "System Info Snapshot",
-            summary: "Current GPU, memory, and OS snapshot info.",
+            summary: "Current GPU, memory, and OS snapshot info; copy it for bug reports.",
         },
         SettingSearchHit {
             category: SettingsCategory::About,
@@ -3,6 +3,8 @@ use std::collections::BTreeMap;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use super::keymap::KeySequence;
+
 pub const SETTINGS_SCHEMA_VERSION: u32 = 1;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -77,6 +79,7 @@ impl Settings {
         }
 
         self.schema_version = SETTINGS_SCHEMA_VERSION;
+        self.keyboard_shortcuts.add_missing_defaults();
         self
     }
 }
@@ -93,6 +96,15 @@ pub struct AppearanceSettings {
     pub show_status_bar: bool,
     pub show_tab_bar: bool,
     pub sidebar_default_panel: SidebarDefaultPanel,
+    /// The panel the sidebar last showed, reopened in place of `sidebar_default_panel`.
+    pub sidebar_last_panel: Option<SidebarDefaultPanel>,
+    /// Width the sidebar splitter was last dragged to.
+    pub sidebar_width: f32,
+    /// Toolbar button ids in order, `"sep"` for separators; `None` keeps the default toolbar.
+    pub toolbar_layout: Option<Vec<String>>,
+    /// Where the main window was when last moved, resized or closed; `None` opens it
+    /// centered.
+    pub window: Option<WindowPlacement>,
 }
 
 impl Default for AppearanceSettings {
@@ -107,10 +119,24 @@ impl Default for AppearanceSettings {
             show_status_bar: true,
             show_tab_bar: true,
             sidebar_default_panel: SidebarDefaultPanel::Files,
+            sidebar_last_panel: None,
+            sidebar_width: 260.0,
+            toolbar_layout: None,
+            window: None,
         }
     }
 }
 
+/// The main window's restored rect in screen pixels, and whether it was maximized.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct WindowPlacement {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub maximized: bool,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ThemePreference {
     SystemAuto,
@@ -128,6 +154,7 @@ impl Default for ThemePreference {
 pub struct CanvasBackgroundPreference {
     pub preset_id: String,
     pub custom_payload: Option<String>,
+    pub page_color: PageColor,
 }
 
 impl Default for CanvasBackgroundPreference {
@@ -135,10 +162,28 @@ impl Default for CanvasBackgroundPreference {
         Self {
             preset_id: "paper".to_string(),
             custom_payload: None,
+            page_color: PageColor::MatchTheme,
         }
     }
 }
 
+/// Colors of the page itself, chosen apart from the shell theme.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PageColor {
+    /// The theme's page, or the reading colors of a preset such as sepia.
+    MatchTheme,
+    /// A white page with dark text, whatever the theme.
+    WhitePaper,
+    /// A dark page with light text, whatever the theme.
+    DarkPaper,
+}
+
+impl Default for PageColor {
+    fn default() -> Self {
+        Self::MatchTheme
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum UiScale {
     Percent100,
@@ -192,7 +237,15 @@ pub struct EditorSettings {
     pub cursor_blink: bool,
     pub auto_indent: bool,
     pub auto_close_brackets: bool,
+    /// Middle-clicking the canvas pastes clipboard text where it lands, as on X11, instead
+    /// of starting a pan. Middle-clicking a tab still closes it.
+    pub middle_click_paste: bool,
     pub show_whitespace: ShowWhitespaceMode,
+    pub date_time_format: DateTimeFormat,
+    /// Autotext: typing an abbreviation followed by a space or punctuation expands it.
+    pub snippets: Vec<Snippet>,
+    pub find: FindSettings,
+    pub scroll: ScrollSettings,
 }
 
 impl Default for EditorSettings {
@@ -208,11 +261,83 @@ impl Default for EditorSettings {
             cursor_blink: true,
             auto_indent: true,
             auto_close_brackets: true,
+            middle_click_paste: false,
             show_whitespace: ShowWhitespaceMode::Off,
+            date_time_format: DateTimeFormat::ShortDate,
+            snippets: Vec::new(),
+            find: FindSettings::default(),
+            scroll: ScrollSettings::default(),
         }
     }
 }
 
+/// Find bar toggles and the pause before live search, kept from one session to the next.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct FindSettings {
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub regex: bool,
+    pub in_selection: bool,
+    pub debounce_ms: u64,
+}
+
+impl Default for FindSettings {
+    fn default() -> Self {
+        Self {
+            case_sensitive: false,
+            whole_word: false,
+            regex: false,
+            in_selection: false,
+            debounce_ms: 100,
+        }
+    }
+}
+
+/// How the mouse wheel scrolls and zooms the canvas.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ScrollSettings {
+    /// Multiplies how far one wheel notch scrolls.
+    pub speed: f32,
+    /// Inverts the wheel, so the page follows the fingers as on a touchpad.
+    pub natural: bool,
+    /// Multiplies how far one Ctrl+wheel notch zooms.
+    pub zoom_sensitivity: f32,
+}
+
+impl Default for ScrollSettings {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            natural: false,
+            zoom_sensitivity: 1.0,
+        }
+    }
+}
+
+/// What "Insert date/time" produces; all but `Iso8601` follow the system locale.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum DateTimeFormat {
+    ShortDate,
+    LongDate,
+    Time,
+    ShortDateTime,
+    Iso8601,
+}
+
+impl Default for DateTimeFormat {
+    fn default() -> Self {
+        Self::ShortDate
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Snippet {
+    pub abbreviation: String,
+    pub text: String,
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum WordWrapMode {
     On,
@@ -323,6 +448,11 @@ pub struct FileSettings {
     pub default_save_format: String,
     pub recent_files_count: u16,
     pub default_open_folder: DefaultOpenFolder,
+    /// Open `.csv` and `.tsv` files as an editable table rather than as text.
+    pub open_delimited_as_table: bool,
+    /// Re-encode large embedded images when saving DOCX or PDF.
+    pub optimize_images_on_save: bool,
+    pub image_quality: ImageQuality,
 }
 
 impl Default for FileSettings {
@@ -333,10 +463,47 @@ impl Default for FileSettings {
             default_save_format: ".docx".to_string(),
             recent_files_count: 20,
             default_open_folder: DefaultOpenFolder::LastUsed,
+            open_delimited_as_table: true,
+            optimize_images_on_save: false,
+            image_quality: ImageQuality::Medium,
         }
     }
 }
 
+/// How hard "optimize images on save" squeezes embedded pictures.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum ImageQuality {
+    Low,
+    Medium,
+    High,
+}
+
+impl ImageQuality {
+    /// JPEG quality, out of 100, opaque pictures are written at.
+    pub fn jpeg_quality(self) -> u8 {
+        match self {
+            Self::Low => 60,
+            Self::Medium => 75,
+            Self::High => 90,
+        }
+    }
+
+    /// Longest side, in pixels, a picture is scaled down to fit.
+    pub fn max_dimension(self) -> u32 {
+        match self {
+            Self::Low => 1280,
+            Self::Medium => 2048,
+            Self::High => 3072,
+        }
+    }
+}
+
+impl Default for ImageQuality {
+    fn default() -> Self {
+        Self::Medium
+    }
+}
+
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
 pub enum AutoSaveInterval {
     Off,
@@ -344,10 +511,53 @@ pub enum AutoSaveInterval {
 }
 
 impl AutoSaveInterval {
+    /// Shortest interval autosave runs at; anything tighter would snapshot while typing.
+    pub const MIN_SECONDS: u64 = 5;
+    /// Longest custom interval, an hour.
+    pub const MAX_SECONDS: u64 = 60 * 60;
+    /// The choices the settings row steps through, in order.
+    pub const PRESETS: [Self; 5] = [
+        Self::Off,
+        Self::Seconds(30),
+        Self::Seconds(60),
+        Self::Seconds(5 * 60),
+        Self::Seconds(10 * 60),
+    ];
+
+    /// An interval typed into the custom field, pulled into the supported range.
+    pub fn custom(seconds: u64) -> Self {
+        Self::Seconds(seconds.clamp(Self::MIN_SECONDS, Self::MAX_SECONDS))
+    }
+
+    /// The interval autosave actually uses: `None` when off, and clamped into range even
+    /// when the settings file asks for something outside it.
     pub fn as_seconds(self) -> Option<u64> {
         match self {
             Self::Off => None,
-            Self::Seconds(v) => Some(v),
+            Self::Seconds(v) => Some(v.clamp(Self::MIN_SECONDS, Self::MAX_SECONDS)),
+        }
+    }
+
+    /// The preset after this one; a custom interval moves to the first preset above it.
+    pub fn next_preset(self) -> Self {
+        let Some(seconds) = self.as_seconds() else {
+            return Self::PRESETS[1];
+        };
+        Self::PRESETS
+            .into_iter()
+            .find(|preset| preset.as_seconds().is_some_and(|p| p > seconds))
+            .unwrap_or(Self::Off)
+    }
+
+    /// "Off", or the effective interval in minutes and seconds ("30s", "5m", "1m 30s").
+    pub fn label(self) -> String {
+        let Some(seconds) = self.as_seconds() else {
+            return "Off".to_string();
+        };
+        match (seconds / 60, seconds % 60) {
+            (0, s) => format!("{s}s"),
+            (m, 0) => format!("{m}m"),
+            (m, s) => format!("{m}m {s}s"),
         }
     }
 }
@@ -395,10 +605,80 @@ impl KeyboardShortcutsSettings {
         entry.customized = true;
     }
 
+    /// Binds `new_keys` unless another command already uses them; in that case nothing
+    /// changes and the commands holding the keys are returned.
+    pub fn try_set_binding(&mut self, command_id: &str, new_keys: &str) -> Result<(), Vec<String>> {
+        let holders = self.conflicts_with(command_id, new_keys);
+        if !holders.is_empty() {
+            return Err(holders);
+        }
+        self.set_binding(command_id, new_keys);
+        Ok(())
+    }
+
+    /// Binds `new_keys` to `command_id` and unbinds them from every other command, as when
+    /// the user confirms an override. Returns the commands that lost the keys.
+    pub fn take_binding(&mut self, command_id: &str, new_keys: &str) -> Vec<String> {
+        let holders = self.conflicts_with(command_id, new_keys);
+        for holder in &holders {
+            self.clear_binding(holder);
+        }
+        self.set_binding(command_id, new_keys);
+        holders
+    }
+
+    /// Leaves `command_id` without a shortcut.
+    pub fn clear_binding(&mut self, command_id: &str) {
+        if let Some(binding) = self.bindings.get_mut(command_id) {
+            binding.keys.clear();
+            binding.customized = true;
+        }
+    }
+
+    /// Commands other than `command_id` bound to `keys`, then what the keys do when they
+    /// are one of the window's fixed [`RESERVED_SHORTCUTS`].
+    pub fn conflicts_with(&self, command_id: &str, keys: &str) -> Vec<String> {
+        if keys.trim().is_empty() {
+            return Vec::new();
+        }
+        let keys = normalize_keys(keys);
+        self.bindings
+            .iter()
+            .filter(|(command, binding)| {
+                command.as_str() != command_id
+                    && !binding.keys.trim().is_empty()
+                    && normalize_keys(&binding.keys) == keys
+            })
+            .map(|(command, _)| command.clone())
+            .chain(
+                RESERVED_SHORTCUTS
+                    .iter()
+                    .filter(|(reserved, _)| normalize_keys(reserved) == keys)
+                    .map(|(_, action)| action.to_string()),
+            )
+            .collect()
+    }
+
     pub fn reset_to_defaults(&mut self) {
         self.bindings = default_shortcuts();
     }
 
+    /// Adds commands that gained a shortcut after the settings file was written. A default
+    /// whose keys the user has since given to another command comes in unbound.
+    pub fn add_missing_defaults(&mut self) {
+        for (command, mut binding) in default_shortcuts() {
+            if self.bindings.contains_key(&command) {
+                continue;
+            }
+            if !self.conflicts_with(&command, &binding.keys).is_empty() {
+                binding.keys.clear();
+            }
+            self.bindings.insert(command, binding);
+        }
+    }
+
+    /// Key combinations bound to more than one command. "shift+ctrl+p" and "Ctrl+Shift+P"
+    /// count as the same keys.
     pub fn detect_conflicts(&self) -> Vec<ShortcutConflict> {
         let mut by_keys: BTreeMap<String, Vec<String>> = BTreeMap::new();
         for (command, binding) in &self.bindings {
@@ -406,7 +686,7 @@ impl KeyboardShortcutsSettings {
                 continue;
             }
             by_keys
-                .entry(binding.keys.to_ascii_lowercase())
+                .entry(normalize_keys(&binding.keys))
                 .or_default()
                 .push(command.clone());
         }
@@ -456,12 +736,58 @@ impl Default for ShortcutBinding {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ShortcutConflict {
     pub keys: String,
     pub commands: Vec<String>,
 }
 
+impl ShortcutConflict {
+    /// Every two commands sharing the keys, for listing the conflict side by side.
+    pub fn pairs(&self) -> Vec<(&str, &str)> {
+        let mut pairs = Vec::new();
+        for (index, first) in self.commands.iter().enumerate() {
+            for second in &self.commands[index + 1..] {
+                pairs.push((first.as_str(), second.as_str()));
+            }
+        }
+        pairs
+    }
+}
+
+/// Keys in the form `KeySequence` prints them, or lowercased when they don't parse.
+/// Keys the window handles itself rather than through a binding, with what they do. A
+/// command bound to one of them takes it over.
+pub const RESERVED_SHORTCUTS: [(&str, &str); 21] = [
+    ("Ctrl+Shift+Z", "Redo"),
+    ("Ctrl+1", "Switch to Tab 1"),
+    ("Ctrl+2", "Switch to Tab 2"),
+    ("Ctrl+3", "Switch to Tab 3"),
+    ("Ctrl+4", "Switch to Tab 4"),
+    ("Ctrl+5", "Switch to Tab 5"),
+    ("Ctrl+6", "Switch to Tab 6"),
+    ("Ctrl+7", "Switch to Tab 7"),
+    ("Ctrl+8", "Switch to Tab 8"),
+    ("Ctrl+9", "Switch to Tab 9"),
+    ("Ctrl+Left", "Previous Word"),
+    ("Ctrl+Right", "Next Word"),
+    ("Ctrl+Home", "Start of Document"),
+    ("Ctrl+End", "End of Document"),
+    ("Ctrl+Shift+Left", "Left-to-Right Paragraph"),
+    ("Ctrl+Shift+Right", "Right-to-Left Paragraph"),
+    ("F1", "Command Palette"),
+    ("F2", "Next Bookmark"),
+    ("Shift+F2", "Previous Bookmark"),
+    ("F6", "Next Region"),
+    ("Shift+F6", "Previous Region"),
+];
+
+fn normalize_keys(keys: &str) -> String {
+    KeySequence::parse(keys)
+        .map(|sequence| sequence.to_string())
+        .unwrap_or_else(|| keys.trim().to_ascii_lowercase())
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct PerformanceSettings {
@@ -504,6 +830,11 @@ pub struct AboutSettings {
     pub last_update_check_utc: Option<DateTime<Utc>>,
     pub licenses_url: String,
     pub system_info_snapshot: String,
+    /// Where the update check reads the latest release from.
+    pub update_manifest_url: String,
+    /// The newest release the last check found, and its download page.
+    pub latest_version: Option<String>,
+    pub latest_release_url: Option<String>,
 }
 
 impl Default for AboutSettings {
@@ -514,6 +845,10 @@ impl Default for AboutSettings {
             last_update_check_utc: None,
             licenses_url: "https://opensource.org/licenses".to_string(),
             system_info_snapshot: String::new(),
+            update_manifest_url:
+                "https://api.github.com/repos/ShreyanshVaibhaw/doco/releases/latest".to_string(),
+            latest_version: None,
+            latest_release_url: None,
         }
     }
 }
@@ -532,6 +867,18 @@ fn default_shortcuts() -> BTreeMap<String, ShortcutBinding> {
         "file.save".to_string(),
         ShortcutBinding::new("Save", "Ctrl+S"),
     );
+    map.insert(
+        "file.save_as".to_string(),
+        ShortcutBinding::new("Save As", "Ctrl+Shift+S"),
+    );
+    map.insert(
+        "file.save_all".to_string(),
+        ShortcutBinding::new("Save All", "Ctrl+Alt+S"),
+    );
+    map.insert(
+        "file.print".to_string(),
+        ShortcutBinding::new("Print", "Ctrl+P"),
+    );
     map.insert(
         "edit.find".to_string(),
         ShortcutBinding::new("Find", "Ctrl+F"),
@@ -552,5 +899,233 @@ fn default_shortcuts() -> BTreeMap<String, ShortcutBinding> {
         "view.debug_panel".to_string(),
         ShortcutBinding::new("Debug Panel", "Ctrl+Shift+D"),
     );
+    map.insert(
+        "view.keyboard_shortcuts".to_string(),
+        ShortcutBinding::new("Keyboard Shortcuts", "Ctrl+K Ctrl+S"),
+    );
+    map.insert(
+        "document.goto_page".to_string(),
+        ShortcutBinding::new("Go to Line/Page", "Ctrl+G"),
+    );
+    map.insert(
+        "document.goto_heading".to_string(),
+        ShortcutBinding::new("Go to Heading", "Ctrl+Shift+O"),
+    );
+    map.insert(
+        "document.add_bookmark".to_string(),
+        ShortcutBinding::new("Add Bookmark", "Ctrl+Shift+B"),
+    );
+    map.insert(
+        "document.follow_link".to_string(),
+        ShortcutBinding::new("Follow Link", "Ctrl+Enter"),
+    );
+    map.insert(
+        "edit.undo".to_string(),
+        ShortcutBinding::new("Undo", "Ctrl+Z"),
+    );
+    map.insert(
+        "edit.redo".to_string(),
+        ShortcutBinding::new("Redo", "Ctrl+Y"),
+    );
+    map.insert(
+        "edit.cut".to_string(),
+        ShortcutBinding::new("Cut", "Ctrl+X"),
+    );
+    map.insert(
+        "edit.copy".to_string(),
+        ShortcutBinding::new("Copy", "Ctrl+C"),
+    );
+    map.insert(
+        "edit.paste".to_string(),
+        ShortcutBinding::new("Paste", "Ctrl+V"),
+    );
+    map.insert(
+        "edit.find_in_tabs".to_string(),
+        ShortcutBinding::new("Find in All Tabs", "Ctrl+Shift+F"),
+    );
+    map.insert(
+        "format.subscript".to_string(),
+        ShortcutBinding::new("Subscript", "Ctrl+="),
+    );
+    map.insert(
+        "format.superscript".to_string(),
+        ShortcutBinding::new("Superscript", "Ctrl+Shift+="),
+    );
+    map.insert(
+        "file.new_tab".to_string(),
+        ShortcutBinding::new("New Tab", "Ctrl+T"),
+    );
+    map.insert(
+        "file.close_tab".to_string(),
+        ShortcutBinding::new("Close Tab", "Ctrl+W"),
+    );
+    map.insert(
+        "view.next_tab".to_string(),
+        ShortcutBinding::new("Next Tab", "Ctrl+Tab"),
+    );
+    map.insert(
+        "view.previous_tab".to_string(),
+        ShortcutBinding::new("Previous Tab", "Ctrl+Shift+Tab"),
+    );
+    map.insert(
+        "view.toggle_sidebar".to_string(),
+        ShortcutBinding::new("Toggle Sidebar", "Ctrl+B"),
+    );
+    map.insert(
+        "view.toggle_toolbar".to_string(),
+        ShortcutBinding::new("Toggle Toolbar", "Ctrl+Shift+T"),
+    );
+    map.insert(
+        "view.toggle_statusbar".to_string(),
+        ShortcutBinding::new("Toggle Status Bar", "Ctrl+L"),
+    );
     map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn autosave_presets_map_to_their_seconds() {
+        let seconds = AutoSaveInterval::PRESETS.map(AutoSaveInterval::as_seconds);
+        assert_eq!(seconds, [None, Some(30), Some(60), Some(300), Some(600)]);
+        let labels = AutoSaveInterval::PRESETS.map(AutoSaveInterval::label);
+        assert_eq!(labels, ["Off", "30s", "1m", "5m", "10m"]);
+
+        // Stepping walks the presets in order and wraps from 10m back to Off.
+        let mut interval = AutoSaveInterval::Off;
+        for expected in AutoSaveInterval::PRESETS.iter().cycle().skip(1).take(5) {
+            interval = interval.next_preset();
+            assert_eq!(interval, *expected);
+        }
+        // A custom value steps to the next preset above it.
+        assert_eq!(
+            AutoSaveInterval::Seconds(90).next_preset(),
+            AutoSaveInterval::Seconds(300)
+        );
+    }
+
+    #[test]
+    fn shortcut_conflicts_are_found_across_spellings() {
+        let mut shortcuts = KeyboardShortcutsSettings::default();
+        assert!(shortcuts.detect_conflicts().is_empty());
+
+        shortcuts.set_binding("edit.replace", "ctrl+f");
+        shortcuts.set_binding("view.debug_panel", "Shift+Ctrl+P");
+        shortcuts.set_binding("file.open", "Ctrl+Shift+P");
+        let conflicts = shortcuts.detect_conflicts();
+        assert_eq!(
+            conflicts,
+            vec![
+                ShortcutConflict {
+                    keys: "Ctrl+F".to_string(),
+                    commands: vec!["edit.find".to_string(), "edit.replace".to_string()],
+                },
+                ShortcutConflict {
+                    keys: "Ctrl+Shift+P".to_string(),
+                    commands: vec![
+                        "file.open".to_string(),
+                        "view.command_palette".to_string(),
+                        "view.debug_panel".to_string(),
+                    ],
+                },
+            ]
+        );
+        assert_eq!(conflicts[0].pairs(), vec![("edit.find", "edit.replace")]);
+        assert_eq!(conflicts[1].pairs().len(), 3);
+
+        // Cleared bindings never conflict.
+        shortcuts.clear_binding("edit.replace");
+        assert_eq!(shortcuts.detect_conflicts().len(), 1);
+        assert!(shortcuts.conflicts_with("edit.replace", "").is_empty());
+    }
+
+    #[test]
+    fn reassigning_a_binding_resolves_its_conflict() {
+        let mut shortcuts = KeyboardShortcutsSettings::default();
+
+        // A duplicate is refused until the override is confirmed.
+        assert_eq!(
+            shortcuts.try_set_binding("edit.replace", "Ctrl+F"),
+            Err(vec!["edit.find".to_string()])
+        );
+        assert_eq!(shortcuts.bindings["edit.replace"].keys, "Ctrl+H");
+        assert_eq!(
+            shortcuts.try_set_binding("edit.replace", "Ctrl+Alt+H"),
+            Ok(())
+        );
+
+        // Taking the keys over clears them from the other command.
+        shortcuts.set_binding("edit.replace", "Ctrl+F");
+        assert_eq!(shortcuts.detect_conflicts().len(), 1);
+        assert_eq!(
+            shortcuts.take_binding("edit.find", "Ctrl+F"),
+            vec!["edit.replace".to_string()]
+        );
+        assert!(shortcuts.detect_conflicts().is_empty());
+        assert_eq!(shortcuts.bindings["edit.find"].keys, "Ctrl+F");
+        assert!(shortcuts.bindings["edit.replace"].keys.is_empty());
+
+        // Or the other side moves to free keys.
+        shortcuts.set_binding("edit.replace", "Ctrl+F");
+        assert_eq!(shortcuts.try_set_binding("edit.find", "Ctrl+Alt+F"), Ok(()));
+        assert!(shortcuts.detect_conflicts().is_empty());
+    }
+
+    #[test]
+    fn editing_keys_and_reserved_keys_count_as_taken() {
+        let mut shortcuts = KeyboardShortcutsSettings::default();
+        assert_eq!(
+            shortcuts.try_set_binding("edit.find", "Ctrl+Z"),
+            Err(vec!["edit.undo".to_string()])
+        );
+        assert_eq!(
+            shortcuts.conflicts_with("file.print", "ctrl+shift+z"),
+            vec!["Redo".to_string()]
+        );
+        assert_eq!(
+            shortcuts.try_set_binding("file.new", "Ctrl+3"),
+            Err(vec!["Switch to Tab 3".to_string()])
+        );
+        // A confirmed override hands the reserved keys to the command.
+        assert!(
+            shortcuts
+                .take_binding("file.new", "Ctrl+3")
+                .contains(&"Switch to Tab 3".to_string())
+        );
+        assert_eq!(shortcuts.bindings["file.new"].keys, "Ctrl+3");
+        assert!(shortcuts.detect_conflicts().is_empty());
+    }
+
+    #[test]
+    fn older_settings_files_gain_new_default_shortcuts() {
+        let settings: Settings = serde_json::from_str(
+            r#"{ "keyboard_shortcuts": { "bindings": {
+                "file.save": { "label": "Save", "keys": "Ctrl+S", "default_keys": "Ctrl+S" },
+                "edit.find": { "label": "Find", "keys": "Ctrl+P", "default_keys": "Ctrl+F",
+                               "customized": true } } } }"#,
+        )
+        .expect("settings");
+        let shortcuts = settings.migrate().keyboard_shortcuts;
+        assert_eq!(shortcuts.bindings["file.save_as"].keys, "Ctrl+Shift+S");
+        // Print's default is taken by the user's Find, so it arrives unbound.
+        assert!(shortcuts.bindings["file.print"].keys.is_empty());
+        assert_eq!(shortcuts.bindings["edit.find"].keys, "Ctrl+P");
+        assert!(shortcuts.detect_conflicts().is_empty());
+    }
+
+    #[test]
+    fn custom_autosave_intervals_stay_in_range() {
+        assert_eq!(AutoSaveInterval::custom(1), AutoSaveInterval::Seconds(5));
+        assert_eq!(AutoSaveInterval::custom(90).label(), "1m 30s");
+        assert_eq!(
+            AutoSaveInterval::custom(86_400),
+            AutoSaveInterval::Seconds(3600)
+        );
+        // Hand-edited settings files get the same limits.
+        assert_eq!(AutoSaveInterval::Seconds(0).as_seconds(), Some(5));
+        assert_eq!(AutoSaveInterval::Seconds(1_000_000).label(), "60m");
+        assert_eq!(AutoSaveInterval::Off.as_seconds(), None);
+    }
+}
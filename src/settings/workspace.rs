@@ -0,0 +1,252 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::{Map, Value};
+
+use super::schema::Settings;
+
+pub const WORKSPACE_FILE_NAME: &str = ".doco.json";
+
+/// Top-level sections a workspace may override; appearance, shortcuts and the rest stay
+/// per user.
+pub const OVERRIDABLE_SECTIONS: [&str; 3] = ["editor", "document", "files"];
+
+/// A `.doco.json` found above the open document: partial `editor`, `document` and `files`
+/// sections laid over the global settings for documents under its folder.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkspaceOverrides {
+    pub path: PathBuf,
+    modified: Option<SystemTime>,
+    overrides: Map<String, Value>,
+}
+
+impl WorkspaceOverrides {
+    /// The nearest `.doco.json` in the document's folder or any folder above it.
+    pub fn find_for(document: &Path) -> Option<PathBuf> {
+        document
+            .ancestors()
+            .skip(1)
+            .map(|folder| folder.join(WORKSPACE_FILE_NAME))
+            .find(|candidate| candidate.is_file())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|error| error.to_string())?;
+        let mut overrides = Self::parse(&text)?;
+        overrides.path = path.to_path_buf();
+        overrides.modified = fs::metadata(path).and_then(|meta| meta.modified()).ok();
+        Ok(overrides)
+    }
+
+    /// Reads the overridable sections of `text`; other top-level keys are ignored.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let Value::Object(root) = serde_json::from_str::<Value>(text).map_err(|e| e.to_string())?
+        else {
+            return Err("expected a JSON object".to_string());
+        };
+        let overrides = root
+            .into_iter()
+            .filter(|(name, value)| {
+                OVERRIDABLE_SECTIONS.contains(&name.as_str()) && value.is_object()
+            })
+            .collect();
+        Ok(Self {
+            path: PathBuf::new(),
+            modified: None,
+            overrides,
+        })
+    }
+
+    /// Whether the file on disk is still the one that was loaded.
+    pub fn is_current(&self) -> bool {
+        fs::metadata(&self.path)
+            .and_then(|meta| meta.modified())
+            .ok()
+            == self.modified
+    }
+
+    /// The folder the file governs.
+    pub fn root(&self) -> &Path {
+        self.path.parent().unwrap_or(Path::new(""))
+    }
+
+    /// `global` with the workspace values merged over it. A section whose overrides don't
+    /// fit the schema (a string where a number belongs, say) is left as it was.
+    pub fn apply(&self, global: &Settings) -> Settings {
+        let mut settings = global.clone();
+        for (name, overlay) in &self.overrides {
+            match name.as_str() {
+                "editor" => overlay_section(&mut settings.editor, overlay),
+                "document" => overlay_section(&mut settings.document, overlay),
+                "files" => overlay_section(&mut settings.files, overlay),
+                _ => {}
+            }
+        }
+        settings
+    }
+
+    /// Dotted paths of every value the workspace sets, e.g. `editor.tab_size`.
+    pub fn overridden_keys(&self) -> Vec<String> {
+        let mut keys = Vec::new();
+        for (name, value) in &self.overrides {
+            collect_leaf_keys(name, value, &mut keys);
+        }
+        keys
+    }
+
+    /// Whether the settings dialog row `setting_key` is decided by the workspace.
+    pub fn overrides(&self, setting_key: &str) -> bool {
+        // Dialog rows that edit a value stored under a different name.
+        let path = match setting_key {
+            "editor.find_debounce_ms" => "editor.find.debounce_ms",
+            "files.auto_save_custom_seconds" => "files.auto_save_interval",
+            other => other,
+        };
+        self.overridden_keys().iter().any(|key| {
+            key == path
+                || key.starts_with(&format!("{path}."))
+                || path.starts_with(&format!("{key}."))
+        })
+    }
+}
+
+fn overlay_section<T: Serialize + DeserializeOwned>(section: &mut T, overlay: &Value) {
+    let Ok(mut value) = serde_json::to_value(&*section) else {
+        return;
+    };
+    merge(&mut value, overlay);
+    if let Ok(merged) = serde_json::from_value(value) {
+        *section = merged;
+    }
+}
+
+/// Objects merge key by key; anything else in `overlay` replaces what `base` had.
+fn merge(base: &mut Value, overlay: &Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay.clone(),
+    }
+}
+
+fn collect_leaf_keys(prefix: &str, value: &Value, keys: &mut Vec<String>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                collect_leaf_keys(&format!("{prefix}.{key}"), value, keys);
+            }
+        }
+        _ => keys.push(prefix.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::schema::{AutoSaveInterval, WordWrapMode};
+
+    #[test]
+    fn workspace_values_win_over_global_which_win_over_defaults() {
+        // The global file sets the tab size and find debounce; everything else is default.
+        let global: Settings = serde_json::from_str(
+            r#"{ "editor": { "tab_size": 8, "find": { "debounce_ms": 400 } },
+                 "files": { "recent_files_count": 5 } }"#,
+        )
+        .expect("global settings");
+        let workspace = WorkspaceOverrides::parse(
+            r#"{ "editor": { "tab_size": 2, "word_wrap": "Off" },
+                 "files": { "auto_save_interval": "Off" },
+                 "appearance": { "show_toolbar": false } }"#,
+        )
+        .expect("workspace file");
+
+        let settings = workspace.apply(&global);
+        // Workspace over global.
+        assert_eq!(settings.editor.tab_size, 2);
+        assert_eq!(settings.editor.word_wrap, WordWrapMode::Off);
+        assert_eq!(settings.files.auto_save_interval, AutoSaveInterval::Off);
+        // Global where the workspace is silent, including inside a merged section.
+        assert_eq!(settings.editor.find.debounce_ms, 400);
+        assert_eq!(settings.files.recent_files_count, 5);
+        // Default where neither says anything.
+        let defaults = Settings::default();
+        assert_eq!(settings.editor.cursor_blink, defaults.editor.cursor_blink);
+        assert_eq!(
+            settings.document.default_zoom_percent,
+            defaults.document.default_zoom_percent
+        );
+        // Sections outside the overridable set are ignored.
+        assert_eq!(
+            settings.appearance.show_toolbar,
+            global.appearance.show_toolbar
+        );
+
+        let mut keys = workspace.overridden_keys();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                "editor.tab_size",
+                "editor.word_wrap",
+                "files.auto_save_interval"
+            ]
+        );
+        assert!(workspace.overrides("editor.tab_size"));
+        assert!(workspace.overrides("files.auto_save_custom_seconds"));
+        assert!(!workspace.overrides("editor.find_debounce_ms"));
+        assert!(!workspace.overrides("appearance.show_toolbar"));
+    }
+
+    #[test]
+    fn ill_typed_sections_fall_back_to_global() {
+        let global = Settings::default();
+        let workspace = WorkspaceOverrides::parse(
+            r#"{ "editor": { "tab_size": "wide" }, "document": { "spelling_check": false } }"#,
+        )
+        .expect("workspace file");
+        let settings = workspace.apply(&global);
+        assert_eq!(settings.editor.tab_size, global.editor.tab_size);
+        assert!(!settings.document.spelling_check);
+
+        assert!(WorkspaceOverrides::parse("[1, 2]").is_err());
+        assert!(WorkspaceOverrides::parse("{ not json").is_err());
+    }
+
+    #[test]
+    fn nearest_workspace_file_above_the_document_is_found() {
+        let root = std::env::temp_dir().join(format!("doco-workspace-{}", std::process::id()));
+        let nested = root.join("notes").join("drafts");
+        fs::create_dir_all(&nested).expect("create folders");
+        fs::write(
+            root.join(WORKSPACE_FILE_NAME),
+            r#"{ "editor": { "tab_size": 3 } }"#,
+        )
+        .expect("write workspace file");
+
+        let document = nested.join("idea.md");
+        let found = WorkspaceOverrides::find_for(&document);
+        assert_eq!(
+            found.as_deref(),
+            Some(root.join(WORKSPACE_FILE_NAME).as_path())
+        );
+        let workspace =
+            WorkspaceOverrides::load(&root.join(WORKSPACE_FILE_NAME)).expect("load workspace file");
+        assert_eq!(workspace.root(), root.as_path());
+        assert!(workspace.is_current());
+
+        let _ = fs::remove_dir_all(&root);
+        assert!(!workspace.is_current());
+    }
+}
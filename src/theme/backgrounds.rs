@@ -1,8 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-use crate::settings::schema::CanvasBackgroundPreference;
+use crate::settings::schema::{CanvasBackgroundPreference, PageColor, PatternQuality};
 use crate::ui::Color;
 
+use super::{Theme, default_dark_theme, default_light_theme};
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum BackgroundFillMode {
     Fill,
@@ -54,6 +56,8 @@ pub enum BackgroundKind {
 pub struct BackgroundSettings {
     pub kind: BackgroundKind,
     pub enable_particles: bool,
+    /// Page colors that replace the shell theme's; `None` draws the page in the theme.
+    pub page: Option<PageColors>,
 }
 
 impl Default for BackgroundSettings {
@@ -63,10 +67,43 @@ impl Default for BackgroundSettings {
                 color: Color::rgb(0.08, 0.1, 0.14),
             },
             enable_particles: false,
+            page: None,
         }
     }
 }
 
+/// The colors the page and the document text on it are drawn in.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct PageColors {
+    pub is_dark: bool,
+    pub page_bg: Color,
+    pub page_shadow: Color,
+    pub text: Color,
+}
+
+impl PageColors {
+    pub fn from_theme(theme: &Theme) -> Self {
+        Self {
+            is_dark: theme.is_dark,
+            page_bg: theme.page_bg,
+            page_shadow: theme.page_shadow,
+            text: theme.text_primary,
+        }
+    }
+
+    /// The quieter text on the page, such as the labels of images and tables.
+    pub fn secondary_text(&self) -> Color {
+        Color::rgba(self.text.r, self.text.g, self.text.b, 0.7)
+    }
+}
+
+/// The page colors to render with under `theme`.
+pub fn page_colors(theme: &Theme, background: &BackgroundSettings) -> PageColors {
+    background
+        .page
+        .unwrap_or_else(|| PageColors::from_theme(theme))
+}
+
 pub fn preset_by_id(id: &str) -> BackgroundSettings {
     match id.to_ascii_lowercase().as_str() {
         "clean" => BackgroundSettings {
@@ -74,6 +111,7 @@ pub fn preset_by_id(id: &str) -> BackgroundSettings {
                 color: Color::rgb(0.09, 0.11, 0.15),
             },
             enable_particles: false,
+            page: None,
         },
         "paper" => BackgroundSettings {
             kind: BackgroundKind::Pattern {
@@ -83,6 +121,7 @@ pub fn preset_by_id(id: &str) -> BackgroundSettings {
                 scale: 1.0,
             },
             enable_particles: false,
+            page: None,
         },
         "blueprint" => BackgroundSettings {
             kind: BackgroundKind::Pattern {
@@ -92,6 +131,7 @@ pub fn preset_by_id(id: &str) -> BackgroundSettings {
                 scale: 1.0,
             },
             enable_particles: false,
+            page: None,
         },
         "cozy" => BackgroundSettings {
             kind: BackgroundKind::Gradient {
@@ -100,6 +140,7 @@ pub fn preset_by_id(id: &str) -> BackgroundSettings {
                 angle_degrees: 22.0,
             },
             enable_particles: false,
+            page: None,
         },
         "midnight" => BackgroundSettings {
             kind: BackgroundKind::Pattern {
@@ -109,16 +150,185 @@ pub fn preset_by_id(id: &str) -> BackgroundSettings {
                 scale: 1.0,
             },
             enable_particles: true,
+            page: None,
+        },
+        "aurora" => BackgroundSettings {
+            kind: BackgroundKind::AnimatedGradient {
+                colors: vec![
+                    Color::rgb(0.05, 0.16, 0.22),
+                    Color::rgb(0.10, 0.30, 0.28),
+                    Color::rgb(0.20, 0.14, 0.32),
+                ],
+                speed: 0.08,
+            },
+            enable_particles: false,
+            page: None,
+        },
+        // Reading preset: a warm page with brown text and less blue light than white paper.
+        "sepia" => BackgroundSettings {
+            kind: BackgroundKind::Solid {
+                color: Color::rgb(0.80, 0.74, 0.63),
+            },
+            enable_particles: false,
+            page: Some(PageColors {
+                is_dark: false,
+                page_bg: Color::rgb(0.98, 0.94, 0.85),
+                page_shadow: Color::rgb(0.56, 0.48, 0.35),
+                text: Color::rgb(0.37, 0.29, 0.20),
+            }),
         },
         _ => BackgroundSettings::default(),
     }
 }
 
-pub fn from_canvas_preference(preference: &CanvasBackgroundPreference) -> BackgroundSettings {
+/// Resolves the canvas background preference for rendering. With `animate` off an animated
+/// gradient becomes its first static frame; low quality coarsens patterns and drops particles.
+/// A page color other than the theme's, from the preset or the page color choice, is carried
+/// in `page`.
+pub fn from_canvas_preference(
+    preference: &CanvasBackgroundPreference,
+    animate: bool,
+    quality: PatternQuality,
+) -> BackgroundSettings {
     let preset = preference.preset_id.trim();
-    if preset.is_empty() {
+    let mut settings = if preset.is_empty() {
         BackgroundSettings::default()
     } else {
         preset_by_id(preset)
+    };
+
+    if !animate
+        && let BackgroundKind::AnimatedGradient { colors, .. } = &settings.kind
+    {
+        settings.kind = match colors.as_slice() {
+            [] => BackgroundSettings::default().kind,
+            [color] => BackgroundKind::Solid { color: *color },
+            [start, end, ..] => BackgroundKind::Gradient {
+                start: *start,
+                end: *end,
+                angle_degrees: 18.0,
+            },
+        };
+    }
+
+    match preference.page_color {
+        PageColor::MatchTheme => {}
+        PageColor::WhitePaper => {
+            settings.page = Some(PageColors::from_theme(&default_light_theme()));
+        }
+        PageColor::DarkPaper => {
+            settings.page = Some(PageColors::from_theme(&default_dark_theme()));
+        }
+    }
+
+    if quality == PatternQuality::Low {
+        settings.enable_particles = false;
+        if let BackgroundKind::Pattern { style, scale, .. } = &mut settings.kind {
+            *scale *= 2.0;
+            if matches!(style, PatternStyle::Noise | PatternStyle::CrossHatch) {
+                *style = PatternStyle::Dots;
+            }
+        }
+    }
+
+    settings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preference(id: &str) -> CanvasBackgroundPreference {
+        CanvasBackgroundPreference {
+            preset_id: id.to_string(),
+            ..CanvasBackgroundPreference::default()
+        }
+    }
+
+    #[test]
+    fn disabled_animation_resolves_to_a_static_kind() {
+        let animated = from_canvas_preference(&preference("aurora"), true, PatternQuality::High);
+        assert!(matches!(animated.kind, BackgroundKind::AnimatedGradient { .. }));
+
+        let still = from_canvas_preference(&preference("aurora"), false, PatternQuality::High);
+        assert!(matches!(still.kind, BackgroundKind::Gradient { .. }));
+    }
+
+    #[test]
+    fn low_quality_coarsens_patterns_and_drops_particles() {
+        let high = from_canvas_preference(&preference("midnight"), true, PatternQuality::High);
+        let low = from_canvas_preference(&preference("midnight"), true, PatternQuality::Low);
+        assert!(high.enable_particles);
+        assert!(!low.enable_particles);
+        match (high.kind, low.kind) {
+            (
+                BackgroundKind::Pattern { scale: high, .. },
+                BackgroundKind::Pattern { scale: low, .. },
+            ) => assert!(low > high),
+            other => panic!("unexpected kinds {other:?}"),
+        }
+
+        let paper = from_canvas_preference(&preference("paper"), true, PatternQuality::Low);
+        assert!(matches!(
+            paper.kind,
+            BackgroundKind::Pattern {
+                style: PatternStyle::Dots,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn page_color_is_chosen_apart_from_the_shell_theme() {
+        let dark_shell = default_dark_theme();
+        let mut white = preference("clean");
+        white.page_color = PageColor::WhitePaper;
+        let page = page_colors(
+            &dark_shell,
+            &from_canvas_preference(&white, true, PatternQuality::High),
+        );
+        assert!(!page.is_dark);
+        assert_eq!(page.page_bg, Color::rgb(1.0, 1.0, 1.0));
+        assert!(page.text.r < 0.5 && page.text.g < 0.5 && page.text.b < 0.5);
+
+        // Matching the theme keeps the dark page the shell would draw.
+        let matched = page_colors(
+            &dark_shell,
+            &from_canvas_preference(&preference("clean"), true, PatternQuality::High),
+        );
+        assert_eq!(matched, PageColors::from_theme(&dark_shell));
+
+        let mut dark = preference("paper");
+        dark.page_color = PageColor::DarkPaper;
+        let page = page_colors(
+            &default_light_theme(),
+            &from_canvas_preference(&dark, false, PatternQuality::Low),
+        );
+        assert!(page.is_dark);
+        assert_eq!(page.page_bg, dark_shell.page_bg);
+    }
+
+    #[test]
+    fn sepia_preset_tints_the_page_and_text() {
+        let sepia = from_canvas_preference(&preference("sepia"), true, PatternQuality::High);
+        let page = sepia.page.expect("sepia page colors");
+        assert!(!page.is_dark);
+        assert_eq!(page.page_bg, Color::rgb(0.98, 0.94, 0.85));
+        assert_eq!(page.text, Color::rgb(0.37, 0.29, 0.20));
+        // Warm and low in blue on both the page and the text.
+        for color in [page.page_bg, page.text] {
+            assert!(color.r > color.g && color.g > color.b);
+        }
+
+        // The preset keeps its colors under any shell theme...
+        assert_eq!(page_colors(&default_dark_theme(), &sepia), page);
+        // ...unless a page color is chosen explicitly.
+        let mut white = preference("sepia");
+        white.page_color = PageColor::WhitePaper;
+        let page = from_canvas_preference(&white, true, PatternQuality::High).page;
+        assert_eq!(
+            page.map(|page| page.page_bg),
+            Some(Color::rgb(1.0, 1.0, 1.0))
+        );
     }
 }
@@ -1,16 +1,21 @@
 use std::{
     collections::HashMap,
     fs,
-    path::PathBuf,
+    path::{Path, PathBuf},
     mem::size_of,
-    sync::Arc,
+    sync::{
+        Arc,
+        mpsc::{self, Receiver},
+    },
 };
 
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use windows::{
     Win32::{
         Foundation::ERROR_SUCCESS,
+        Graphics::Gdi::{COLOR_WINDOW, GetSysColor},
         System::Registry::{
             HKEY_CURRENT_USER,
             REG_VALUE_TYPE,
@@ -28,7 +33,7 @@ pub mod backgrounds;
 pub mod colors;
 pub mod mica;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Theme {
     pub name: String,
     pub is_dark: bool,
@@ -63,8 +68,12 @@ pub struct Theme {
     pub selection_bg: Color,
     pub cursor_color: Color,
     pub line_number_color: Color,
+    /// Text and outline for invalid input, such as a find pattern that does not compile.
+    #[serde(default = "default_error_color")]
+    pub error_color: Color,
 }
 
+#[derive(Clone)]
 pub struct ThemeManager {
     shared: Arc<RwLock<Theme>>,
     themes: HashMap<String, Theme>,
@@ -77,18 +86,9 @@ impl ThemeManager {
             .map(|t| (t.name.clone(), t))
             .collect::<HashMap<_, _>>();
 
-        for path in [PathBuf::from("assets/themes"), appdata_themes_dir()] {
-            if let Ok(entries) = fs::read_dir(path) {
-                for entry in entries.flatten() {
-                    let p = entry.path();
-                    if p.extension().and_then(|s| s.to_str()) == Some("json") {
-                        if let Ok(text) = fs::read_to_string(&p) {
-                            if let Ok(theme) = serde_json::from_str::<Theme>(&text) {
-                                themes.insert(theme.name.clone(), theme);
-                            }
-                        }
-                    }
-                }
+        for path in theme_dirs() {
+            for theme in read_theme_dir(&path) {
+                themes.insert(theme.name.clone(), theme);
             }
         }
 
@@ -137,6 +137,40 @@ impl ThemeManager {
         self.active()
     }
 
+    /// Re-reads the theme folders after a file changed, as `rescan` does.
+    pub fn reload(&mut self) -> Vec<String> {
+        self.rescan(&theme_dirs())
+    }
+
+    /// Merges the theme files in `dirs`, later folders winning, into the known themes and
+    /// returns the names of those added or edited. An edited active theme is republished
+    /// through `shared`. Themes whose file was deleted stay until restart.
+    pub fn rescan(&mut self, dirs: &[PathBuf]) -> Vec<String> {
+        let mut incoming = HashMap::new();
+        for dir in dirs {
+            for theme in read_theme_dir(dir) {
+                incoming.insert(theme.name.clone(), theme);
+            }
+        }
+
+        let mut changed = Vec::new();
+        for (name, theme) in incoming {
+            if self.themes.get(&name) != Some(&theme) {
+                self.themes.insert(name.clone(), theme);
+                changed.push(name);
+            }
+        }
+        changed.sort();
+
+        let active = self.shared.read().name.clone();
+        if changed.contains(&active)
+            && let Some(theme) = self.themes.get(&active)
+        {
+            *self.shared.write() = theme.clone();
+        }
+        changed
+    }
+
     pub fn names(&self) -> Vec<String> {
         let mut names = self.themes.keys().cloned().collect::<Vec<_>>();
         names.sort();
@@ -144,6 +178,65 @@ impl ThemeManager {
     }
 }
 
+/// Watches the theme folders so a theme file saved while Doco runs can be reloaded.
+pub struct ThemeWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+}
+
+impl ThemeWatcher {
+    /// Watches whichever theme folders exist; `None` if there are none to watch.
+    pub fn start() -> Option<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .ok()?;
+        let mut watching = false;
+        for dir in theme_dirs() {
+            watching |= watcher.watch(&dir, RecursiveMode::NonRecursive).is_ok();
+        }
+        watching.then_some(Self {
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Whether a theme file was written, added or removed since the last call.
+    pub fn poll(&self) -> bool {
+        let mut touched = false;
+        while let Ok(event) = self.rx.try_recv() {
+            if let Ok(event) = event {
+                touched |= event.paths.iter().any(|path| is_theme_file(path));
+            }
+        }
+        touched
+    }
+}
+
+/// Folders theme files are read from; a theme in a later folder replaces one of the same
+/// name in an earlier folder.
+fn theme_dirs() -> [PathBuf; 2] {
+    [PathBuf::from("assets/themes"), appdata_themes_dir()]
+}
+
+fn read_theme_dir(dir: &Path) -> Vec<Theme> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| is_theme_file(path))
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .filter_map(|text| serde_json::from_str::<Theme>(&text).ok())
+        .collect()
+}
+
+fn is_theme_file(path: &Path) -> bool {
+    path.extension().and_then(|s| s.to_str()) == Some("json")
+}
+
 fn appdata_themes_dir() -> PathBuf {
     if let Some(portable) = crate::settings::portable_root() {
         return portable.join("themes");
@@ -198,16 +291,27 @@ fn theme_template(name: &str, is_dark: bool, bg: u32, surface: u32, accent: u32,
         selection_bg: Color::rgba(c(accent).r, c(accent).g, c(accent).b, 0.33),
         cursor_color: c(text),
         line_number_color: c(if is_dark { 0x8992A8 } else { 0x8B94A4 }),
+        error_color: c(if is_dark { 0xFF736B } else { 0xC41C1C }),
     }
 }
 
+/// Error color for theme files written before `error_color` existed; readable on either
+/// a dark or a light background.
+fn default_error_color() -> Color {
+    c(0xE0443E)
+}
+
 pub fn default_dark_theme() -> Theme {
     theme_template("Dark", true, 0x141821, 0x1D2330, 0x5EA1FF, 0xE6EAF2)
 }
 
+pub fn default_light_theme() -> Theme {
+    theme_template("Light", false, 0xF4F7FC, 0xFFFFFF, 0x0A6DDA, 0x1E2530)
+}
+
 pub fn built_in_themes() -> Vec<Theme> {
     vec![
-        theme_template("Light", false, 0xF4F7FC, 0xFFFFFF, 0x0A6DDA, 0x1E2530),
+        default_light_theme(),
         default_dark_theme(),
         theme_template("Nord", true, 0x2E3440, 0x3B4252, 0x88C0D0, 0xECEFF4),
         theme_template("Catppuccin Mocha", true, 0x1E1E2E, 0x313244, 0x89B4FA, 0xCDD6F4),
@@ -217,9 +321,71 @@ pub fn built_in_themes() -> Vec<Theme> {
         theme_template("One Dark", true, 0x282C34, 0x2F343F, 0x61AFEF, 0xABB2BF),
         theme_template("Dracula", true, 0x282A36, 0x343746, 0xBD93F9, 0xF8F8F2),
         theme_template("Gruvbox Dark", true, 0x282828, 0x3C3836, 0xD79921, 0xEBDBB2),
+        high_contrast_theme(true),
+        high_contrast_theme(false),
     ]
 }
 
+/// Pure black/white palette with saturated accents, used while Windows high contrast is on.
+pub fn high_contrast_theme(is_dark: bool) -> Theme {
+    let (name, bg, fg, accent) = if is_dark {
+        ("High Contrast Dark", 0x000000, 0xFFFFFF, 0xFFFF00)
+    } else {
+        ("High Contrast Light", 0xFFFFFF, 0x000000, 0x1C00CF)
+    };
+    let mut theme = theme_template(name, is_dark, bg, bg, accent, fg);
+    theme.mica_opacity = 1.0;
+    for color in [
+        &mut theme.surface_secondary,
+        &mut theme.surface_tertiary,
+        &mut theme.canvas_bg,
+        &mut theme.page_bg,
+        &mut theme.scrollbar_track,
+    ] {
+        *color = c(bg);
+    }
+    for color in [
+        &mut theme.border_default,
+        &mut theme.border_subtle,
+        &mut theme.text_secondary,
+        &mut theme.scrollbar_thumb,
+        &mut theme.line_number_color,
+        &mut theme.page_shadow,
+    ] {
+        *color = c(fg);
+    }
+    theme.surface_hover = c(accent);
+    theme.surface_pressed = c(accent);
+    theme.surface_selected = c(accent);
+    theme.accent_hover = c(accent);
+    theme.accent_pressed = c(accent);
+    theme.scrollbar_thumb_hover = c(accent);
+    theme.text_disabled = c(if is_dark { 0x3FF23F } else { 0x600000 });
+    theme.error_color = c(if is_dark { 0xFF4040 } else { 0xB00000 });
+    theme.text_on_accent = c(bg);
+    theme.selection_bg = c(accent);
+    theme
+}
+
+/// The theme to render with: `base`, unless high contrast is on, in which case the
+/// high-contrast variant matching `high_contrast_dark` replaces it.
+pub fn theme_for_accessibility(base: Theme, high_contrast: bool, high_contrast_dark: bool) -> Theme {
+    if high_contrast {
+        high_contrast_theme(high_contrast_dark)
+    } else {
+        base
+    }
+}
+
+/// Whether the active Windows high-contrast scheme uses a dark window background.
+pub fn high_contrast_scheme_is_dark() -> bool {
+    let window = unsafe { GetSysColor(COLOR_WINDOW) };
+    let r = (window & 0xFF) as f32;
+    let g = ((window >> 8) & 0xFF) as f32;
+    let b = ((window >> 16) & 0xFF) as f32;
+    (0.2126 * r + 0.7152 * g + 0.0722 * b) < 128.0
+}
+
 pub fn system_prefers_dark_mode() -> bool {
     unsafe {
         let mut value: u32 = 1;
@@ -243,3 +409,82 @@ pub fn system_prefers_dark_mode() -> bool {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_contrast_preference_selects_the_high_contrast_variant() {
+        let base = default_dark_theme();
+        assert_eq!(theme_for_accessibility(base.clone(), false, true).name, "Dark");
+
+        let dark = theme_for_accessibility(base.clone(), true, true);
+        assert_eq!(dark.name, "High Contrast Dark");
+        assert!(dark.is_dark);
+        assert_eq!(dark.text_primary, Color::rgb(1.0, 1.0, 1.0));
+        assert_eq!(dark.page_bg, Color::rgb(0.0, 0.0, 0.0));
+
+        let light = theme_for_accessibility(base, true, false);
+        assert_eq!(light.name, "High Contrast Light");
+        assert!(!light.is_dark);
+        assert_eq!(light.text_primary, Color::rgb(0.0, 0.0, 0.0));
+    }
+
+    #[test]
+    fn high_contrast_themes_are_built_in() {
+        let names = built_in_themes()
+            .into_iter()
+            .map(|theme| theme.name)
+            .collect::<Vec<_>>();
+        assert!(names.iter().any(|name| name == "High Contrast Dark"));
+        assert!(names.iter().any(|name| name == "High Contrast Light"));
+    }
+
+    #[test]
+    fn theme_files_without_an_error_color_still_load() {
+        let mut value = serde_json::to_value(default_light_theme()).expect("serialize theme");
+        value
+            .as_object_mut()
+            .expect("theme object")
+            .remove("error_color");
+        let theme = serde_json::from_value::<Theme>(value).expect("older theme file loads");
+        assert_eq!(theme.error_color, default_error_color());
+    }
+
+    #[test]
+    fn rescan_merges_new_theme_files_and_replaces_edited_ones() {
+        let dir = std::env::temp_dir().join(format!("doco-themes-{}", std::process::id()));
+        fs::create_dir_all(&dir).expect("create theme folder");
+        let mut manager = ThemeManager {
+            shared: Arc::new(RwLock::new(default_dark_theme())),
+            themes: built_in_themes()
+                .into_iter()
+                .map(|theme| (theme.name.clone(), theme))
+                .collect(),
+        };
+        let dirs = [dir.clone()];
+        let write_theme = |theme: &Theme| {
+            let text = serde_json::to_string_pretty(theme).expect("serialize theme");
+            fs::write(dir.join("paper-night.json"), text).expect("write theme file");
+        };
+
+        let mut authored =
+            theme_template("Paper Night", true, 0x101820, 0x18222C, 0xF2AA4C, 0xEDE6D6);
+        write_theme(&authored);
+        fs::write(dir.join("notes.txt"), "not a theme").expect("write notes");
+        assert_eq!(manager.rescan(&dirs), vec!["Paper Night"]);
+        assert!(manager.names().iter().any(|name| name == "Paper Night"));
+        // Nothing changed on disk, nothing reported.
+        assert!(manager.rescan(&dirs).is_empty());
+
+        assert!(manager.set_active("Paper Night"));
+        authored.accent = c(0x3FB950);
+        write_theme(&authored);
+        assert_eq!(manager.rescan(&dirs), vec!["Paper Night"]);
+        // The edit reaches whoever holds the shared active theme.
+        assert_eq!(manager.shared().read().accent, c(0x3FB950));
+        assert_eq!(manager.active(), authored);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
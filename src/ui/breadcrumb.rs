@@ -0,0 +1,181 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    document::model::BlockId,
+    editor::outline::TocEntry,
+    theme::Theme,
+    ui::{InputEvent, Point, Rect, UIComponent},
+};
+
+pub const BREADCRUMB_HEIGHT: f32 = 22.0;
+const CRUMB_PADDING: f32 = 6.0;
+const SEPARATOR_WIDTH: f32 = 14.0;
+/// Average advance of the shell's UI font; crumbs are laid out without measuring text.
+const APPROX_CHAR_WIDTH: f32 = 7.0;
+const MAX_CRUMB_CHARS: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Crumb {
+    pub block_id: BlockId,
+    pub label: String,
+}
+
+/// Thin bar along the top of the canvas naming the headings that contain the caret,
+/// outermost first. Clicking a crumb leaves its heading in `pending_jump`.
+#[derive(Debug, Clone, Default)]
+pub struct Breadcrumb {
+    bounds: Rect,
+    visible: bool,
+    crumbs: Vec<Crumb>,
+    pub pending_jump: Option<BlockId>,
+}
+
+impl Breadcrumb {
+    /// Replaces the crumbs with `path`, the heading chain from `heading_path`.
+    pub fn set_path(&mut self, path: &[TocEntry]) {
+        self.crumbs = path
+            .iter()
+            .map(|entry| {
+                let label = if entry.text.chars().count() > MAX_CRUMB_CHARS {
+                    let head = entry
+                        .text
+                        .chars()
+                        .take(MAX_CRUMB_CHARS - 1)
+                        .collect::<String>();
+                    format!("{}\u{2026}", head.trim_end())
+                } else {
+                    entry.text.clone()
+                };
+                Crumb {
+                    block_id: entry.block_id,
+                    label,
+                }
+            })
+            .collect();
+    }
+
+    /// Shown only while there is a heading above the caret.
+    pub fn is_shown(&self) -> bool {
+        self.visible && !self.crumbs.is_empty()
+    }
+
+    /// Each crumb with the rect its label occupies; separators sit in the gaps between.
+    pub fn crumb_rects(&self) -> Vec<(Rect, &Crumb)> {
+        let mut x = self.bounds.x + CRUMB_PADDING;
+        let right = self.bounds.x + self.bounds.width - CRUMB_PADDING;
+        let mut out = Vec::with_capacity(self.crumbs.len());
+        for crumb in &self.crumbs {
+            if x >= right {
+                break;
+            }
+            let width = (crumb.label.chars().count() as f32 * APPROX_CHAR_WIDTH
+                + CRUMB_PADDING * 2.0)
+                .min(right - x);
+            out.push((
+                Rect {
+                    x,
+                    y: self.bounds.y,
+                    width,
+                    height: self.bounds.height,
+                },
+                crumb,
+            ));
+            x += width + SEPARATOR_WIDTH;
+        }
+        out
+    }
+}
+
+impl UIComponent for Breadcrumb {
+    fn layout(&mut self, bounds: Rect, _dpi: f32) {
+        self.bounds = bounds;
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // Drawn in host renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.is_shown() {
+            return false;
+        }
+
+        match event {
+            InputEvent::MouseDown(point) if contains(self.bounds, *point) => {
+                self.pending_jump = self
+                    .crumb_rects()
+                    .into_iter()
+                    .find(|(rect, _)| contains(*rect, *point))
+                    .map(|(_, crumb)| crumb.block_id);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.is_shown() && contains(self.bounds, point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+fn contains(rect: Rect, point: Point) -> bool {
+    point.x >= rect.x
+        && point.x <= rect.x + rect.width
+        && point.y >= rect.y
+        && point.y <= rect.y + rect.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u64, level: u8, text: &str) -> TocEntry {
+        TocEntry {
+            text: text.to_string(),
+            level,
+            block_id: BlockId(id),
+        }
+    }
+
+    #[test]
+    fn clicking_a_crumb_queues_a_jump_to_its_heading() {
+        let mut bar = Breadcrumb::default();
+        bar.layout(
+            Rect {
+                x: 100.0,
+                y: 80.0,
+                width: 600.0,
+                height: BREADCRUMB_HEIGHT,
+            },
+            96.0,
+        );
+        bar.set_visible(true);
+        bar.set_path(&[
+            entry(1, 1, "Intro"),
+            entry(4, 2, "Background"),
+            entry(9, 3, "Method"),
+        ]);
+
+        let rects = bar.crumb_rects();
+        assert_eq!(rects.len(), 3);
+        let (second, _) = rects[1];
+        let click = Point {
+            x: second.x + 2.0,
+            y: second.y + 5.0,
+        };
+        assert!(bar.handle_input(&InputEvent::MouseDown(click)));
+        assert_eq!(bar.pending_jump, Some(BlockId(4)));
+
+        bar.set_path(&[]);
+        assert!(!bar.is_shown());
+        assert!(!bar.handle_input(&InputEvent::MouseDown(click)));
+    }
+}
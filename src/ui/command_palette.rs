@@ -5,6 +5,7 @@ use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
 use crate::{
     app::AppState,
     render::animation::{Animation, Easing},
+    settings::{keymap::KeySequence, schema::KeyboardShortcutsSettings},
     theme::Theme,
     ui::{InputEvent, Point, Rect, UIComponent},
 };
@@ -67,6 +68,9 @@ pub struct CommandPalette {
     recent_ids: VecDeque<&'static str>,
     pub grouped_result_headers: Vec<(String, usize)>,
     pub close_on_click_outside: bool,
+    /// Keys bound in settings, by command id, as shown beside each result. An empty hint
+    /// means the binding was cleared.
+    key_hints: HashMap<String, String>,
 }
 
 impl Default for CommandPalette {
@@ -93,6 +97,7 @@ impl CommandPalette {
             recent_ids: VecDeque::new(),
             grouped_result_headers: Vec::new(),
             close_on_click_outside: true,
+            key_hints: key_hints(&KeyboardShortcutsSettings::default()),
         };
         palette.refresh_results(None);
         palette
@@ -159,6 +164,13 @@ impl CommandPalette {
         self.opacity
     }
 
+    /// Shows the keys currently bound in settings; commands without a configurable binding
+    /// keep their built-in hint.
+    pub fn set_key_bindings(&mut self, shortcuts: &KeyboardShortcutsSettings) {
+        self.key_hints = key_hints(shortcuts);
+        self.refresh_results(None);
+    }
+
     pub fn slide_offset(&self) -> f32 {
         self.slide_offset
     }
@@ -255,7 +267,7 @@ impl CommandPalette {
                 "{} {} {} {}",
                 command.label,
                 command.category,
-                command.shortcut.unwrap_or_default(),
+                shortcut_hint(&self.key_hints, command).unwrap_or_default(),
                 command.id
             )
             .to_ascii_lowercase();
@@ -281,12 +293,19 @@ impl CommandPalette {
         self.commands.get(index)
     }
 
-    pub fn result_labels(&self, max: usize) -> Vec<String> {
+    /// Labels of the first `max` results, each with the shortcut drawn right-aligned
+    /// beside it.
+    pub fn result_labels(&self, max: usize) -> Vec<(String, Option<String>)> {
         self.results
             .iter()
             .take(max)
             .filter_map(|hit| self.command(hit.command_index))
-            .map(|cmd| cmd.label.to_string())
+            .map(|cmd| {
+                (
+                    cmd.label.to_string(),
+                    shortcut_hint(&self.key_hints, cmd).map(str::to_string),
+                )
+            })
             .collect()
     }
 
@@ -469,9 +488,21 @@ fn default_command_registry() -> Vec<Command> {
     push("file.save_as", "Save As", "File", Some("Ctrl+Shift+S"), Box::new(|state| {
         state.status_text = "Save As".to_string();
     }));
+    push("file.save_all", "Save All", "File", Some("Ctrl+Alt+S"), Box::new(|state| {
+        state.status_text = "Save all".to_string();
+    }));
     push("file.export_pdf", "Export as PDF", "File", None, Box::new(|state| {
         state.status_text = "Export PDF".to_string();
     }));
+    push("file.save_image_as", "Save Image As...", "File", None, Box::new(|state| {
+        state.status_text = "Save image as".to_string();
+    }));
+    push("file.restore_original_images", "Restore Original Images", "File", None, Box::new(|state| {
+        state.status_text = "Restore original images".to_string();
+    }));
+    push("file.properties", "Document Properties", "File", None, Box::new(|state| {
+        state.status_text = "Document properties".to_string();
+    }));
     push("file.print", "Print", "File", Some("Ctrl+P"), Box::new(|state| {
         state.status_text = "Print".to_string();
     }));
@@ -488,6 +519,9 @@ fn default_command_registry() -> Vec<Command> {
     push("edit.replace", "Replace", "Edit", Some("Ctrl+H"), Box::new(|state| {
         state.status_text = "Replace".to_string();
     }));
+    push("edit.replace_all_tabs", "Replace in All Tabs", "Edit", None, Box::new(|state| {
+        state.status_text = "Replace in all tabs".to_string();
+    }));
 
     push("format.bold", "Bold", "Format", Some("Ctrl+B"), Box::new(|state| {
         state.status_text = "Bold".to_string();
@@ -511,10 +545,37 @@ fn default_command_registry() -> Vec<Command> {
     push("insert.link", "Insert Link", "Insert", None, Box::new(|state| {
         state.status_text = "Insert link".to_string();
     }));
+    push("insert.date_time", "Insert Date/Time", "Insert", None, Box::new(|state| {
+        state.status_text = "Insert date/time".to_string();
+    }));
     push("insert.table", "Insert Table", "Insert", None, Box::new(|state| {
         state.status_text = "Insert table".to_string();
     }));
+    push("table.freeze_first_column", "Freeze Table First Column", "Table", None, Box::new(|state| {
+        state.status_text = "Freeze table first column".to_string();
+    }));
+    push("table.freeze_header_row", "Freeze Table Header Row", "Table", None, Box::new(|state| {
+        state.status_text = "Freeze table header row".to_string();
+    }));
+    push("table.to_text_tabs", "Convert Table to Text (Tabs)", "Table", None, Box::new(|state| {
+        state.status_text = "Table to text: tabs".to_string();
+    }));
+    push("table.to_text_commas", "Convert Table to Text (Commas)", "Table", None, Box::new(|state| {
+        state.status_text = "Table to text: commas".to_string();
+    }));
+    push("table.to_text_pipes", "Convert Table to Text (Pipes)", "Table", None, Box::new(|state| {
+        state.status_text = "Table to text: pipes".to_string();
+    }));
+    push("table.from_text", "Convert Text to Table", "Table", None, Box::new(|state| {
+        state.status_text = "Text to table".to_string();
+    }));
 
+    push("view.customize_toolbar", "Customize Toolbar", "View", None, Box::new(|state| {
+        state.status_text = "Customize toolbar".to_string();
+    }));
+    push("view.keyboard_shortcuts", "Keyboard Shortcuts", "View", Some("Ctrl+K Ctrl+S"), Box::new(|state| {
+        state.status_text = "Keyboard shortcuts".to_string();
+    }));
     push("view.zoom_in", "Zoom In", "View", Some("Ctrl++"), Box::new(|state| {
         state.status_text = "Zoom in".to_string();
     }));
@@ -583,16 +644,94 @@ fn default_command_registry() -> Vec<Command> {
     push("document.properties", "Document Properties", "Document", None, Box::new(|state| {
         state.status_text = "Document properties".to_string();
     }));
+    push("document.goto_footnote", "Go to Footnote / Back", "Document", None, Box::new(|state| {
+        state.status_text = "Go to footnote".to_string();
+    }));
+    push("document.insert_toc", "Insert Table of Contents", "Document", None, Box::new(|state| {
+        state.status_text = "Insert table of contents".to_string();
+    }));
+    push("document.update_toc", "Update Table of Contents", "Document", None, Box::new(|state| {
+        state.status_text = "Update table of contents".to_string();
+    }));
+    push("document.insert_figure_list", "Insert List of Figures", "Document", None, Box::new(|state| {
+        state.status_text = "Insert list of figures".to_string();
+    }));
+    push("document.update_figure_list", "Update List of Figures", "Document", None, Box::new(|state| {
+        state.status_text = "Update list of figures".to_string();
+    }));
+    push("document.insert_table_list", "Insert List of Tables", "Document", None, Box::new(|state| {
+        state.status_text = "Insert list of tables".to_string();
+    }));
+    push("document.update_table_list", "Update List of Tables", "Document", None, Box::new(|state| {
+        state.status_text = "Update list of tables".to_string();
+    }));
+    push("insert.caption", "Insert or Remove Caption", "Insert", None, Box::new(|state| {
+        state.status_text = "Toggle caption".to_string();
+    }));
+    push("document.add_anchor", "Add Anchor", "Document", None, Box::new(|state| {
+        state.status_text = "Add anchor".to_string();
+    }));
+    push("insert.cross_reference", "Insert Cross-Reference", "Insert", None, Box::new(|state| {
+        state.status_text = "Insert cross-reference".to_string();
+    }));
+    push("insert.cross_reference_number", "Insert Cross-Reference (Number)", "Insert", None, Box::new(|state| {
+        state.status_text = "Insert cross-reference number".to_string();
+    }));
+    push("document.follow_link", "Follow Link", "Document", Some("Ctrl+Enter"), Box::new(|state| {
+        state.status_text = "Follow link".to_string();
+    }));
+    push("document.columns_one", "Columns: One", "Document", None, Box::new(|state| {
+        state.status_text = "One column".to_string();
+    }));
+    push("document.columns_two", "Columns: Two", "Document", None, Box::new(|state| {
+        state.status_text = "Two columns".to_string();
+    }));
+    push("document.columns_three", "Columns: Three", "Document", None, Box::new(|state| {
+        state.status_text = "Three columns".to_string();
+    }));
     push("document.goto_page", "Go to Page", "Document", None, Box::new(|state| {
         state.status_text = "Go to page".to_string();
     }));
+    push("document.goto_heading", "Go to Heading", "Document", Some("Ctrl+Shift+O"), Box::new(|state| {
+        state.status_text = "Go to heading".to_string();
+    }));
+    push("document.next_bookmark", "Next Bookmark", "Document", Some("F2"), Box::new(|state| {
+        state.status_text = "Next bookmark".to_string();
+    }));
+    push("document.previous_bookmark", "Previous Bookmark", "Document", Some("Shift+F2"), Box::new(|state| {
+        state.status_text = "Previous bookmark".to_string();
+    }));
 
     push("file.open_folder", "Open Folder", "File", Some("Ctrl+K Ctrl+O"), Box::new(|state| {
         state.status_text = "Open folder".to_string();
     }));
+    push("file.edit_anyway", "Edit Anyway", "File", None, Box::new(|state| {
+        state.status_text = "Edit anyway".to_string();
+    }));
+    push("file.toggle_read_only", "Toggle Read-Only", "File", None, Box::new(|state| {
+        state.status_text = "Toggle read-only".to_string();
+    }));
+    push("file.copy_path", "Copy File Path", "File", None, Box::new(|state| {
+        state.status_text = "Copy file path".to_string();
+    }));
+    push("file.copy_name", "Copy Filename", "File", None, Box::new(|state| {
+        state.status_text = "Copy filename".to_string();
+    }));
+    push("file.open_containing_folder", "Open Containing Folder", "File", None, Box::new(|state| {
+        state.status_text = "Open containing folder".to_string();
+    }));
     push("file.close_tab", "Close Tab", "File", Some("Ctrl+W"), Box::new(|state| {
         state.status_text = "Close tab".to_string();
     }));
+    push("file.duplicate_tab", "Duplicate Tab", "File", None, Box::new(|state| {
+        state.status_text = "Duplicate tab".to_string();
+    }));
+    push("file.close_all_tabs", "Close All Tabs", "File", None, Box::new(|state| {
+        state.status_text = "Close all tabs".to_string();
+    }));
+    push("file.close_saved_tabs", "Close Saved Tabs", "File", None, Box::new(|state| {
+        state.status_text = "Close saved tabs".to_string();
+    }));
     push("file.close_window", "Close Window", "File", Some("Alt+F4"), Box::new(|state| {
         state.status_text = "Close window".to_string();
     }));
@@ -622,6 +761,18 @@ fn default_command_registry() -> Vec<Command> {
     push("format.subscript", "Subscript", "Format", Some("Ctrl+="), Box::new(|state| {
         state.status_text = "Subscript".to_string();
     }));
+    push("format.highlight_yellow", "Highlight Yellow", "Format", None, Box::new(|state| {
+        state.status_text = "Highlight: Yellow".to_string();
+    }));
+    push("format.highlight_green", "Highlight Green", "Format", None, Box::new(|state| {
+        state.status_text = "Highlight: Green".to_string();
+    }));
+    push("format.highlight_pink", "Highlight Pink", "Format", None, Box::new(|state| {
+        state.status_text = "Highlight: Pink".to_string();
+    }));
+    push("format.highlight_none", "Remove Highlight", "Format", None, Box::new(|state| {
+        state.status_text = "Highlight: None".to_string();
+    }));
     push("format.heading_2", "Heading 2", "Format", None, Box::new(|state| {
         state.status_text = "Heading 2".to_string();
     }));
@@ -667,6 +818,21 @@ fn default_command_registry() -> Vec<Command> {
     push("format.line_spacing", "Line Spacing", "Format", None, Box::new(|state| {
         state.status_text = "Line spacing".to_string();
     }));
+    push("format.block_quote", "Toggle Block Quote", "Format", None, Box::new(|state| {
+        state.status_text = "Block quote".to_string();
+    }));
+    push("format.frame_note", "Note Frame", "Format", None, Box::new(|state| {
+        state.status_text = "Paragraph frame: Note".to_string();
+    }));
+    push("format.frame_warning", "Warning Frame", "Format", None, Box::new(|state| {
+        state.status_text = "Paragraph frame: Warning".to_string();
+    }));
+    push("format.frame_tip", "Tip Frame", "Format", None, Box::new(|state| {
+        state.status_text = "Paragraph frame: Tip".to_string();
+    }));
+    push("format.frame_none", "Remove Paragraph Frame", "Format", None, Box::new(|state| {
+        state.status_text = "Paragraph frame: None".to_string();
+    }));
 
     push("insert.horizontal_rule", "Horizontal Rule", "Insert", None, Box::new(|state| {
         state.status_text = "Horizontal rule".to_string();
@@ -684,6 +850,31 @@ fn default_command_registry() -> Vec<Command> {
     commands
 }
 
+/// Bound keys in canonical form, so a hand-edited "ctrl + shift + f" reads "Ctrl+Shift+F".
+/// Keys that don't parse get no hint, as they don't dispatch either.
+fn key_hints(shortcuts: &KeyboardShortcutsSettings) -> HashMap<String, String> {
+    shortcuts
+        .bindings
+        .iter()
+        .map(|(command, binding)| {
+            let hint = KeySequence::parse(&binding.keys)
+                .map(|keys| keys.to_string())
+                .unwrap_or_default();
+            (command.clone(), hint)
+        })
+        .collect()
+}
+
+fn shortcut_hint<'a>(
+    key_hints: &'a HashMap<String, String>,
+    command: &'a Command,
+) -> Option<&'a str> {
+    match key_hints.get(command.id) {
+        Some(keys) => (!keys.is_empty()).then_some(keys.as_str()),
+        None => command.shortcut,
+    }
+}
+
 fn normalize_query(query: &str) -> String {
     query.trim().to_ascii_lowercase()
 }
@@ -698,7 +889,7 @@ fn strip_prefix_query(query: &str, mode: QuickActionMode) -> String {
     }
 }
 
-fn fuzzy_score(needle: &str, haystack: &str) -> Option<(i32, Vec<usize>)> {
+pub(crate) fn fuzzy_score(needle: &str, haystack: &str) -> Option<(i32, Vec<usize>)> {
     if needle.is_empty() {
         return Some((0, Vec::new()));
     }
@@ -765,7 +956,11 @@ mod tests {
         let mut palette = CommandPalette::new();
         palette.set_query(">fsi");
         let labels = palette.result_labels(8);
-        assert!(labels.iter().any(|label| label == "Font Size Increase"));
+        assert!(
+            labels
+                .iter()
+                .any(|(label, _)| label == "Font Size Increase")
+        );
     }
 
     #[test]
@@ -790,6 +985,35 @@ mod tests {
         assert!(!palette.grouped_result_headers.is_empty());
     }
 
+    #[test]
+    fn results_show_the_configured_keybinding() {
+        let hint_for = |palette: &CommandPalette, label: &str| {
+            palette
+                .result_labels(8)
+                .into_iter()
+                .find(|(row, _)| row == label)
+                .and_then(|(_, hint)| hint)
+        };
+        let mut palette = CommandPalette::new();
+        palette.set_query(">replace");
+        assert_eq!(hint_for(&palette, "Replace").as_deref(), Some("Ctrl+H"));
+        // Commands without a configurable binding keep the built-in hint.
+        palette.set_query(">zoom in");
+        assert_eq!(hint_for(&palette, "Zoom In").as_deref(), Some("Ctrl++"));
+
+        let mut shortcuts = KeyboardShortcutsSettings::default();
+        shortcuts.set_binding("edit.replace", "ctrl + shift + h");
+        shortcuts.clear_binding("edit.find");
+        palette.set_key_bindings(&shortcuts);
+        palette.set_query(">replace");
+        assert_eq!(
+            hint_for(&palette, "Replace").as_deref(),
+            Some("Ctrl+Shift+H")
+        );
+        palette.set_query(">find");
+        assert_eq!(hint_for(&palette, "Find"), None);
+    }
+
     #[test]
     fn reduce_motion_disables_open_animation() {
         let mut palette = CommandPalette::new();
@@ -30,10 +30,13 @@ pub enum ContextAction {
     InsertTable,
     SelectAll,
     CloseTab,
+    DuplicateTab,
     CloseOthers,
     CloseAll,
+    CloseSaved,
     CloseToRight,
     CopyFilePath,
+    CopyFileName,
     ShowInExplorer,
     SaveImageAs,
     ImageProperties,
@@ -205,7 +208,7 @@ impl UIComponent for ContextMenu {
     }
 }
 
-fn default_items(kind: ContextMenuKind) -> Vec<ContextMenuItem> {
+pub fn default_items(kind: ContextMenuKind) -> Vec<ContextMenuItem> {
     let mut entries = Vec::new();
 
     let mut push = |label: &'static str, action: ContextAction| {
@@ -231,15 +234,19 @@ fn default_items(kind: ContextMenuKind) -> Vec<ContextMenuItem> {
         }
         ContextMenuKind::Tab => {
             push("Close", ContextAction::CloseTab);
+            push("Duplicate", ContextAction::DuplicateTab);
             push("Close Others", ContextAction::CloseOthers);
             push("Close All", ContextAction::CloseAll);
+            push("Close Saved", ContextAction::CloseSaved);
             push("Close to the Right", ContextAction::CloseToRight);
             push("Copy File Path", ContextAction::CopyFilePath);
-            push("Show in Explorer", ContextAction::ShowInExplorer);
+            push("Copy Filename", ContextAction::CopyFileName);
+            push("Open Containing Folder", ContextAction::ShowInExplorer);
         }
         ContextMenuKind::Sidebar => {
             push("Copy File Path", ContextAction::CopyFilePath);
-            push("Show in Explorer", ContextAction::ShowInExplorer);
+            push("Copy Filename", ContextAction::CopyFileName);
+            push("Open Containing Folder", ContextAction::ShowInExplorer);
         }
         ContextMenuKind::Image => {
             push("Cut", ContextAction::Cut);
@@ -5,11 +5,13 @@ use crate::{
         SettingSearchHit,
         SettingsStore,
         schema::{
-            AutoSaveInterval, CursorStyle, DefaultMargins, DefaultOpenFolder, DefaultPageSize,
-            DefaultViewMode, PatternQuality, Settings, SettingsCategory, ShowWhitespaceMode,
-            SidebarDefaultPanel, ThemePreference, UiScale, WordWrapMode,
+            AutoSaveInterval, CursorStyle, DateTimeFormat, DefaultMargins, DefaultOpenFolder,
+            DefaultPageSize, DefaultViewMode, ImageQuality, PageColor, PatternQuality, Settings,
+            SettingsCategory, ShowWhitespaceMode, SidebarDefaultPanel, ThemePreference, UiScale,
+            WordWrapMode,
         },
         search_settings,
+        workspace::WorkspaceOverrides,
     },
     theme::Theme,
     ui::{InputEvent, Point, Rect, UIComponent},
@@ -20,6 +22,33 @@ const HEADER_HEIGHT: f32 = 56.0;
 const SEARCH_HEIGHT: f32 = 36.0;
 const ROW_HEIGHT: f32 = 30.0;
 
+/// Keyboard focus target inside the settings dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SettingsFocus {
+    #[default]
+    Search,
+    /// The category list as a single stop; Up/Down switch categories.
+    Categories,
+    Row(usize),
+}
+
+/// Tab order over the dialog: search box, category list, then each setting row.
+pub fn focus_order(row_count: usize) -> Vec<SettingsFocus> {
+    let mut order = vec![SettingsFocus::Search, SettingsFocus::Categories];
+    order.extend((0..row_count).map(SettingsFocus::Row));
+    order
+}
+
+/// The stop after (or before, with `backwards`) `current`, wrapping at either end.
+pub fn next_focus(order: &[SettingsFocus], current: SettingsFocus, backwards: bool) -> SettingsFocus {
+    let Some(position) = order.iter().position(|focus| *focus == current) else {
+        return order.first().copied().unwrap_or_default();
+    };
+    let len = order.len() as isize;
+    let step = if backwards { -1 } else { 1 };
+    order[(position as isize + step).rem_euclid(len) as usize]
+}
+
 pub struct Dialog {
     bounds: Rect,
     visible: bool,
@@ -27,10 +56,27 @@ pub struct Dialog {
     selected_category: SettingsCategory,
     filtered_hits: Vec<SettingSearchHit>,
     selected_hit: usize,
+    focus: SettingsFocus,
     scroll_y: f32,
     last_save_error: Option<String>,
     store: Option<SettingsStore>,
     fallback_settings: Settings,
+    /// Digits typed into the custom auto-save field while it is being edited.
+    custom_autosave_entry: Option<String>,
+    pub pending_about_action: Option<AboutAction>,
+    /// Set by the Keyboard Shortcuts row; the window opens the shortcut editor.
+    pub pending_shortcut_editor: bool,
+    /// The `.doco.json` governing the active document, if any; rows it decides say so.
+    workspace: Option<WorkspaceOverrides>,
+}
+
+/// About rows that reach outside the dialog; the window carries them out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AboutAction {
+    CheckForUpdates,
+    OpenRelease(String),
+    CopySystemInfo,
+    OpenLicenses,
 }
 
 impl Default for Dialog {
@@ -49,10 +95,15 @@ impl Dialog {
                 selected_category: SettingsCategory::Appearance,
                 filtered_hits: search_settings(""),
                 selected_hit: 0,
+                focus: SettingsFocus::Search,
                 scroll_y: 0.0,
                 last_save_error: None,
                 fallback_settings: Settings::default(),
                 store: Some(store),
+                custom_autosave_entry: None,
+                pending_about_action: None,
+                pending_shortcut_editor: false,
+                workspace: None,
             },
             Err(err) => Self {
                 bounds: Rect::default(),
@@ -61,10 +112,15 @@ impl Dialog {
                 selected_category: SettingsCategory::Appearance,
                 filtered_hits: search_settings(""),
                 selected_hit: 0,
+                focus: SettingsFocus::Search,
                 scroll_y: 0.0,
                 last_save_error: Some(err.to_string()),
                 fallback_settings: Settings::default(),
                 store: None,
+                custom_autosave_entry: None,
+                pending_about_action: None,
+                pending_shortcut_editor: false,
+                workspace: None,
             },
         }
     }
@@ -75,6 +131,7 @@ impl Dialog {
 
     pub fn close(&mut self) {
         self.visible = false;
+        self.custom_autosave_entry = None;
     }
 
     pub fn toggle(&mut self) {
@@ -148,15 +205,59 @@ impl Dialog {
         self.visible_setting_hits()
             .into_iter()
             .map(|hit| {
-                format!(
-                    "{}: {}",
-                    hit.title,
-                    setting_value_preview(settings, hit.setting_key)
-                )
+                let value = match &self.custom_autosave_entry {
+                    Some(entry) if hit.setting_key == "files.auto_save_custom_seconds" => {
+                        format!(
+                            "{entry}\u{2502} seconds ({}\u{2013}{}, Enter to apply)",
+                            AutoSaveInterval::MIN_SECONDS,
+                            AutoSaveInterval::MAX_SECONDS
+                        )
+                    }
+                    _ => setting_value_preview(settings, hit.setting_key),
+                };
+                match &self.workspace {
+                    // Edits still change the global value, which the workspace then hides.
+                    Some(workspace) if workspace.overrides(hit.setting_key) => format!(
+                        "{}: {} (workspace override; global: {})",
+                        hit.title,
+                        setting_value_preview(&workspace.apply(settings), hit.setting_key),
+                        value
+                    ),
+                    _ => format!("{}: {}", hit.title, value),
+                }
             })
             .collect()
     }
 
+    /// Focused control, with a row focus clamped to the rows currently shown.
+    pub fn focus(&self) -> SettingsFocus {
+        match self.focus {
+            SettingsFocus::Row(row) => {
+                let total = self.visible_setting_hits().len();
+                if total == 0 {
+                    SettingsFocus::Categories
+                } else {
+                    SettingsFocus::Row(row.min(total - 1))
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Moves keyboard focus to the next (Tab) or previous (Shift+Tab) control.
+    pub fn move_focus(&mut self, backwards: bool) {
+        let order = focus_order(self.visible_setting_hits().len());
+        self.focus = next_focus(&order, self.focus(), backwards);
+        if let SettingsFocus::Row(row) = self.focus {
+            self.selected_hit = row;
+            self.scroll_y = self.scroll_y.min(row as f32 * ROW_HEIGHT);
+        }
+    }
+
+    pub fn set_workspace(&mut self, workspace: Option<WorkspaceOverrides>) {
+        self.workspace = workspace;
+    }
+
     pub fn settings(&self) -> &Settings {
         if let Some(store) = &self.store {
             store.settings()
@@ -200,8 +301,17 @@ impl Dialog {
             .is_empty()
     }
 
-    pub fn set_shortcut(&mut self, command_id: &str, keys: impl Into<String>) {
+    /// Binds `keys` unless another command has them, returning those commands instead.
+    pub fn set_shortcut(&mut self, command_id: &str, keys: &str) -> Result<(), Vec<String>> {
+        let holders = self
+            .settings()
+            .keyboard_shortcuts
+            .conflicts_with(command_id, keys);
+        if !holders.is_empty() {
+            return Err(holders);
+        }
         self.apply_change(|settings| settings.keyboard_shortcuts.set_binding(command_id, keys));
+        Ok(())
     }
 
     pub fn reset_shortcuts(&mut self) {
@@ -270,6 +380,31 @@ impl Dialog {
         self.apply_setting_cycle(hits[index].setting_key)
     }
 
+    fn sync_row_focus(&mut self) {
+        if matches!(self.focus, SettingsFocus::Row(_)) {
+            self.focus = SettingsFocus::Row(self.selected_hit);
+        }
+    }
+
+    /// Space/Enter on the focused control: toggles a row, or steps from the category
+    /// list into its first row.
+    fn activate_focused(&mut self) -> bool {
+        match self.focus() {
+            SettingsFocus::Search => false,
+            SettingsFocus::Categories => {
+                if !self.visible_setting_hits().is_empty() {
+                    self.focus = SettingsFocus::Row(0);
+                    self.selected_hit = 0;
+                }
+                true
+            }
+            SettingsFocus::Row(row) => {
+                self.selected_hit = row;
+                self.activate_selected_hit()
+            }
+        }
+    }
+
     fn row_index_from_point(&self, point: Point) -> Option<usize> {
         let list = self.list_rect();
         if !contains(list, point) {
@@ -281,7 +416,62 @@ impl Dialog {
         Some(row)
     }
 
+    /// Keys while the custom auto-save field is being edited: digits, Backspace, Enter to
+    /// apply the clamped value and Esc to leave it unchanged. Everything is swallowed.
+    fn edit_custom_autosave(&mut self, event: &InputEvent) -> bool {
+        let Some(entry) = &mut self.custom_autosave_entry else {
+            return false;
+        };
+        match event {
+            InputEvent::KeyDown(0x08) => remove_last_char(entry),
+            InputEvent::KeyDown(0x1B) => self.custom_autosave_entry = None,
+            InputEvent::KeyDown(0x0D) => {
+                let seconds = entry.parse::<u64>().ok();
+                self.custom_autosave_entry = None;
+                if let Some(seconds) = seconds {
+                    self.apply_change(|settings| {
+                        settings.files.auto_save_interval = AutoSaveInterval::custom(seconds);
+                    });
+                }
+            }
+            InputEvent::Char(ch) if ch.is_ascii_digit() && entry.len() < 6 => entry.push(*ch),
+            InputEvent::KeyDown(_) | InputEvent::Char(_) => {}
+            // Clicking away leaves the field without applying it.
+            InputEvent::MouseDown(_) => {
+                self.custom_autosave_entry = None;
+                return false;
+            }
+            _ => return false,
+        }
+        true
+    }
+
     fn apply_setting_cycle(&mut self, key: &str) -> bool {
+        if key == "files.auto_save_custom_seconds" {
+            let current = self.settings().files.auto_save_interval.as_seconds();
+            self.custom_autosave_entry = Some(current.map(|s| s.to_string()).unwrap_or_default());
+            return true;
+        }
+        if key == "keyboard_shortcuts.bindings" {
+            self.pending_shortcut_editor = true;
+            return true;
+        }
+        if key == "about.check_for_updates" {
+            // With an update already found, the row links to it instead of checking again.
+            self.pending_about_action = Some(match &self.settings().about.latest_release_url {
+                Some(url) => AboutAction::OpenRelease(url.clone()),
+                None => AboutAction::CheckForUpdates,
+            });
+            return true;
+        }
+        if key == "about.licenses_url" {
+            self.pending_about_action = Some(AboutAction::OpenLicenses);
+            return true;
+        }
+        if key == "about.system_info_snapshot" {
+            self.pending_about_action = Some(AboutAction::CopySystemInfo);
+            return true;
+        }
         let before = serde_json::to_string(self.settings()).ok();
         self.apply_change(|settings| match key {
             "appearance.theme" => {
@@ -299,10 +489,20 @@ impl Dialog {
                     "clean" => "blueprint",
                     "blueprint" => "cozy",
                     "cozy" => "midnight",
+                    "midnight" => "aurora",
+                    "aurora" => "sepia",
                     _ => "paper",
                 };
                 settings.appearance.canvas_background.preset_id = next.to_string();
             }
+            "appearance.page_color" => {
+                let page_color = &mut settings.appearance.canvas_background.page_color;
+                *page_color = match page_color {
+                    PageColor::MatchTheme => PageColor::WhitePaper,
+                    PageColor::WhitePaper => PageColor::DarkPaper,
+                    PageColor::DarkPaper => PageColor::MatchTheme,
+                };
+            }
             "appearance.ui_font" => {
                 settings.appearance.ui_font = if settings.appearance.ui_font == "Segoe UI Variable" {
                     "Segoe UI".to_string()
@@ -338,6 +538,8 @@ impl Dialog {
                         SidebarDefaultPanel::Outline => SidebarDefaultPanel::Bookmarks,
                         SidebarDefaultPanel::Bookmarks => SidebarDefaultPanel::Files,
                     };
+                // Picking a default shows it now rather than the panel open last.
+                settings.appearance.sidebar_last_panel = None;
             }
             "editor.default_font_family" => {
                 settings.editor.default_font_family = match settings.editor.default_font_family.as_str()
@@ -392,6 +594,30 @@ impl Dialog {
             "editor.auto_close_brackets" => {
                 settings.editor.auto_close_brackets = !settings.editor.auto_close_brackets;
             }
+            "editor.middle_click_paste" => {
+                settings.editor.middle_click_paste = !settings.editor.middle_click_paste;
+            }
+            "editor.scroll_speed" => {
+                settings.editor.scroll.speed = match settings.editor.scroll.speed {
+                    v if (v - 1.0).abs() < f32::EPSILON => 1.5,
+                    v if (v - 1.5).abs() < f32::EPSILON => 2.0,
+                    v if (v - 2.0).abs() < f32::EPSILON => 3.0,
+                    v if (v - 3.0).abs() < f32::EPSILON => 0.5,
+                    v if (v - 0.5).abs() < f32::EPSILON => 0.75,
+                    _ => 1.0,
+                };
+            }
+            "editor.natural_scrolling" => {
+                settings.editor.scroll.natural = !settings.editor.scroll.natural;
+            }
+            "editor.zoom_sensitivity" => {
+                let scroll = &mut settings.editor.scroll;
+                scroll.zoom_sensitivity = match scroll.zoom_sensitivity {
+                    v if (v - 1.0).abs() < f32::EPSILON => 2.0,
+                    v if (v - 2.0).abs() < f32::EPSILON => 0.5,
+                    _ => 1.0,
+                };
+            }
             "editor.show_whitespace" => {
                 settings.editor.show_whitespace = match settings.editor.show_whitespace {
                     ShowWhitespaceMode::Off => ShowWhitespaceMode::Selection,
@@ -399,6 +625,23 @@ impl Dialog {
                     ShowWhitespaceMode::All => ShowWhitespaceMode::Off,
                 };
             }
+            "editor.date_time_format" => {
+                settings.editor.date_time_format = match settings.editor.date_time_format {
+                    DateTimeFormat::ShortDate => DateTimeFormat::LongDate,
+                    DateTimeFormat::LongDate => DateTimeFormat::Time,
+                    DateTimeFormat::Time => DateTimeFormat::ShortDateTime,
+                    DateTimeFormat::ShortDateTime => DateTimeFormat::Iso8601,
+                    DateTimeFormat::Iso8601 => DateTimeFormat::ShortDate,
+                };
+            }
+            "editor.find_debounce_ms" => {
+                settings.editor.find.debounce_ms = match settings.editor.find.debounce_ms {
+                    0 => 100,
+                    100 => 250,
+                    250 => 500,
+                    _ => 0,
+                };
+            }
             "document.default_page_size" => {
                 settings.document.default_page_size = match settings.document.default_page_size {
                     DefaultPageSize::Letter => DefaultPageSize::A4,
@@ -441,13 +684,7 @@ impl Dialog {
                 settings.document.spelling_check = !settings.document.spelling_check;
             }
             "files.auto_save_interval" => {
-                settings.files.auto_save_interval = match settings.files.auto_save_interval {
-                    AutoSaveInterval::Seconds(60) => AutoSaveInterval::Seconds(30),
-                    AutoSaveInterval::Seconds(30) => AutoSaveInterval::Seconds(120),
-                    AutoSaveInterval::Seconds(120) => AutoSaveInterval::Off,
-                    AutoSaveInterval::Off => AutoSaveInterval::Seconds(60),
-                    _ => AutoSaveInterval::Seconds(60),
-                };
+                settings.files.auto_save_interval = settings.files.auto_save_interval.next_preset();
             }
             "files.create_backup_before_save" => {
                 settings.files.create_backup_before_save = !settings.files.create_backup_before_save;
@@ -476,21 +713,18 @@ impl Dialog {
                     DefaultOpenFolder::SpecificPath(_) => DefaultOpenFolder::LastUsed,
                 };
             }
-            "keyboard_shortcuts.bindings" => {
-                let next = settings
-                    .keyboard_shortcuts
-                    .bindings
-                    .get("view.settings")
-                    .map(|binding| binding.keys.as_str())
-                    .map(|keys| {
-                        if keys.eq_ignore_ascii_case("Ctrl+,") {
-                            "Ctrl+Alt+S"
-                        } else {
-                            "Ctrl+,"
-                        }
-                    })
-                    .unwrap_or("Ctrl+,");
-                settings.keyboard_shortcuts.set_binding("view.settings", next);
+            "files.open_delimited_as_table" => {
+                settings.files.open_delimited_as_table = !settings.files.open_delimited_as_table;
+            }
+            "files.optimize_images_on_save" => {
+                settings.files.optimize_images_on_save = !settings.files.optimize_images_on_save;
+            }
+            "files.image_quality" => {
+                settings.files.image_quality = match settings.files.image_quality {
+                    ImageQuality::Low => ImageQuality::Medium,
+                    ImageQuality::Medium => ImageQuality::High,
+                    ImageQuality::High => ImageQuality::Low,
+                };
             }
             "keyboard_shortcuts.reset_defaults" => {
                 settings.keyboard_shortcuts.reset_to_defaults();
@@ -527,9 +761,6 @@ impl Dialog {
             "about.check_updates_on_startup" => {
                 settings.about.check_updates_on_startup = !settings.about.check_updates_on_startup;
             }
-            "about.check_for_updates" => {
-                settings.about.last_update_check_utc = Some(chrono::Utc::now());
-            }
             _ => {}
         });
         let after = serde_json::to_string(self.settings()).ok();
@@ -550,6 +781,9 @@ impl UIComponent for Dialog {
         if !self.visible {
             return false;
         }
+        if self.edit_custom_autosave(event) {
+            return true;
+        }
 
         match event {
             InputEvent::KeyDown(vk) => match *vk {
@@ -564,11 +798,20 @@ impl UIComponent for Dialog {
                     self.scroll_y = 0.0;
                     true
                 }
+                0x26 if self.focus() == SettingsFocus::Categories => {
+                    self.advance_category(-1);
+                    true
+                }
+                0x28 if self.focus() == SettingsFocus::Categories => {
+                    self.advance_category(1);
+                    true
+                }
                 0x26 => {
                     if self.selected_hit > 0 {
                         self.selected_hit -= 1;
                     }
                     self.scroll_y = (self.scroll_y - ROW_HEIGHT).max(0.0);
+                    self.sync_row_focus();
                     true
                 }
                 0x28 => {
@@ -577,19 +820,27 @@ impl UIComponent for Dialog {
                         self.selected_hit = (self.selected_hit + 1).min(total - 1);
                     }
                     self.scroll_y += ROW_HEIGHT;
+                    self.sync_row_focus();
                     true
                 }
                 0x09 => {
-                    self.advance_category(1);
+                    self.move_focus(false);
                     true
                 }
-                0x0D => self.activate_selected_hit(),
+                // Space on a focused control activates it; in the search box it types.
+                0x20 => self.activate_focused(),
+                0x0D => match self.focus() {
+                    SettingsFocus::Search => self.activate_selected_hit(),
+                    _ => self.activate_focused(),
+                },
                 _ => false,
             },
+            InputEvent::Char(' ') if self.focus() != SettingsFocus::Search => true,
             InputEvent::Char(ch) => {
                 if ch.is_control() {
                     false
                 } else {
+                    self.focus = SettingsFocus::Search;
                     self.search_query.push(*ch);
                     self.filtered_hits = search_settings(self.search_query.as_str());
                     self.selected_hit = 0;
@@ -612,6 +863,7 @@ impl UIComponent for Dialog {
                     let hits = self.current_hits();
                     if row < hits.len() {
                         self.selected_hit = row;
+                        self.focus = SettingsFocus::Row(row);
                         self.clamp_selected_hit();
                         return self.activate_selected_hit();
                     }
@@ -649,6 +901,11 @@ fn setting_value_preview(settings: &Settings, key: &str) -> String {
             ThemePreference::Named(name) => name.clone(),
         },
         "appearance.canvas_background" => settings.appearance.canvas_background.preset_id.clone(),
+        "appearance.page_color" => match settings.appearance.canvas_background.page_color {
+            PageColor::MatchTheme => "Match theme".to_string(),
+            PageColor::WhitePaper => "White paper".to_string(),
+            PageColor::DarkPaper => "Dark paper".to_string(),
+        },
         "appearance.ui_font" => settings.appearance.ui_font.clone(),
         "appearance.ui_scale" => format!("{:.0}%", settings.appearance.ui_scale.as_factor() * 100.0),
         "appearance.show_toolbar" => bool_text(settings.appearance.show_toolbar),
@@ -680,11 +937,23 @@ fn setting_value_preview(settings: &Settings, key: &str) -> String {
         "editor.cursor_blink" => bool_text(settings.editor.cursor_blink),
         "editor.auto_indent" => bool_text(settings.editor.auto_indent),
         "editor.auto_close_brackets" => bool_text(settings.editor.auto_close_brackets),
+        "editor.middle_click_paste" => bool_text(settings.editor.middle_click_paste),
+        "editor.scroll_speed" => format!("{}x", settings.editor.scroll.speed),
+        "editor.natural_scrolling" => bool_text(settings.editor.scroll.natural),
+        "editor.zoom_sensitivity" => format!("{}x", settings.editor.scroll.zoom_sensitivity),
         "editor.show_whitespace" => match settings.editor.show_whitespace {
             ShowWhitespaceMode::Off => "Off".to_string(),
             ShowWhitespaceMode::Selection => "Selection".to_string(),
             ShowWhitespaceMode::All => "All".to_string(),
         },
+        "editor.date_time_format" => match settings.editor.date_time_format {
+            DateTimeFormat::ShortDate => "Short date".to_string(),
+            DateTimeFormat::LongDate => "Long date".to_string(),
+            DateTimeFormat::Time => "Time".to_string(),
+            DateTimeFormat::ShortDateTime => "Short date and time".to_string(),
+            DateTimeFormat::Iso8601 => "ISO 8601".to_string(),
+        },
+        "editor.find_debounce_ms" => format!("{} ms", settings.editor.find.debounce_ms),
         "document.default_page_size" => match settings.document.default_page_size {
             DefaultPageSize::Letter => "Letter".to_string(),
             DefaultPageSize::A4 => "A4".to_string(),
@@ -703,9 +972,10 @@ fn setting_value_preview(settings: &Settings, key: &str) -> String {
         },
         "document.default_zoom_percent" => format!("{}%", settings.document.default_zoom_percent),
         "document.spelling_check" => bool_text(settings.document.spelling_check),
-        "files.auto_save_interval" => match settings.files.auto_save_interval {
-            AutoSaveInterval::Off => "Off".to_string(),
-            AutoSaveInterval::Seconds(seconds) => format!("{}s", seconds),
+        "files.auto_save_interval" => settings.files.auto_save_interval.label(),
+        "files.auto_save_custom_seconds" => match settings.files.auto_save_interval.as_seconds() {
+            Some(seconds) => format!("{seconds} seconds"),
+            None => "Off".to_string(),
         },
         "files.create_backup_before_save" => bool_text(settings.files.create_backup_before_save),
         "files.default_save_format" => settings.files.default_save_format.clone(),
@@ -715,7 +985,21 @@ fn setting_value_preview(settings: &Settings, key: &str) -> String {
             DefaultOpenFolder::Documents => "Documents".to_string(),
             DefaultOpenFolder::SpecificPath(path) => path.clone(),
         },
-        "keyboard_shortcuts.bindings" => format!("{} bindings", settings.keyboard_shortcuts.bindings.len()),
+        "files.open_delimited_as_table" => bool_text(settings.files.open_delimited_as_table),
+        "files.optimize_images_on_save" => bool_text(settings.files.optimize_images_on_save),
+        "files.image_quality" => match settings.files.image_quality {
+            ImageQuality::Low => "Low".to_string(),
+            ImageQuality::Medium => "Medium".to_string(),
+            ImageQuality::High => "High".to_string(),
+        },
+        "keyboard_shortcuts.bindings" => {
+            let bindings = settings.keyboard_shortcuts.bindings.len();
+            match settings.keyboard_shortcuts.detect_conflicts().len() {
+                0 => format!("{bindings} bindings (Edit)"),
+                1 => format!("{bindings} bindings, 1 conflict (Edit)"),
+                conflicts => format!("{bindings} bindings, {conflicts} conflicts (Edit)"),
+            }
+        }
         "keyboard_shortcuts.reset_defaults" => "Reset all to defaults".to_string(),
         "performance.hardware_acceleration" => bool_text(settings.performance.hardware_acceleration),
         "performance.max_undo_history" => settings.performance.max_undo_history.to_string(),
@@ -727,19 +1011,30 @@ fn setting_value_preview(settings: &Settings, key: &str) -> String {
         "performance.max_image_cache_mb" => format!("{} MB", settings.performance.max_image_cache_mb),
         "about.version" => settings.about.version.clone(),
         "about.check_updates_on_startup" => bool_text(settings.about.check_updates_on_startup),
-        "about.licenses_url" => settings.about.licenses_url.clone(),
+        "about.licenses_url" => "View the bundled crate licenses".to_string(),
         "about.system_info_snapshot" => {
             if settings.about.system_info_snapshot.trim().is_empty() {
-                "Not captured".to_string()
+                "Not captured (Copy)".to_string()
             } else {
-                settings.about.system_info_snapshot.clone()
+                // The report's lines, minus the version the row above already shows.
+                let summary = settings
+                    .about
+                    .system_info_snapshot
+                    .lines()
+                    .skip(1)
+                    .collect::<Vec<_>>()
+                    .join(" \u{00B7} ");
+                format!("{summary} (Copy)")
             }
         }
-        "about.check_for_updates" => settings
-            .about
-            .last_update_check_utc
-            .map(|v| format!("Last checked: {}", v.to_rfc3339()))
-            .unwrap_or_else(|| "Never checked".to_string()),
+        "about.check_for_updates" => match &settings.about.latest_version {
+            Some(version) => format!("Update available: {version} (open release page)"),
+            None => settings
+                .about
+                .last_update_check_utc
+                .map(|v| format!("Last checked: {}", v.to_rfc3339()))
+                .unwrap_or_else(|| "Never checked".to_string()),
+        },
         _ => "Tap to edit".to_string(),
     }
 }
@@ -842,6 +1137,51 @@ impl ErrorDialogState {
     }
 }
 
+#[cfg(test)]
+mod focus_tests {
+    use super::{SettingsFocus, focus_order, next_focus};
+
+    #[test]
+    fn focus_order_visits_search_categories_then_rows() {
+        assert_eq!(
+            focus_order(3),
+            vec![
+                SettingsFocus::Search,
+                SettingsFocus::Categories,
+                SettingsFocus::Row(0),
+                SettingsFocus::Row(1),
+                SettingsFocus::Row(2),
+            ]
+        );
+        assert_eq!(
+            focus_order(0),
+            vec![SettingsFocus::Search, SettingsFocus::Categories]
+        );
+    }
+
+    #[test]
+    fn tab_and_shift_tab_wrap_around_the_order() {
+        let order = focus_order(2);
+        assert_eq!(
+            next_focus(&order, SettingsFocus::Categories, false),
+            SettingsFocus::Row(0)
+        );
+        assert_eq!(
+            next_focus(&order, SettingsFocus::Row(1), false),
+            SettingsFocus::Search
+        );
+        assert_eq!(
+            next_focus(&order, SettingsFocus::Search, true),
+            SettingsFocus::Row(1)
+        );
+        // A stale row focus from a longer category restarts at the search box.
+        assert_eq!(
+            next_focus(&order, SettingsFocus::Row(7), false),
+            SettingsFocus::Search
+        );
+    }
+}
+
 #[cfg(test)]
 mod polish_tests {
     use super::{ErrorDialogKind, ErrorDialogState, LoadingKind, LoadingState};
@@ -0,0 +1,184 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    document::model::DocumentMetadata,
+    theme::Theme,
+    ui::{InputEvent, Point, Rect, UIComponent},
+};
+
+const PANEL_WIDTH: f32 = 420.0;
+const PANEL_HEADER: f32 = 40.0;
+const PANEL_FOOTER: f32 = 36.0;
+const FIELD_HEIGHT: f32 = 52.0;
+const FIELD_LABELS: [&str; 4] = ["Title", "Author", "Subject", "Keywords"];
+
+/// One editable property row: label, current value and whether it has keyboard focus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyFieldItem {
+    pub label: &'static str,
+    pub value: String,
+    pub input_rect: Rect,
+    pub focused: bool,
+}
+
+/// "Document properties" dialog editing the title, author, subject and keywords that are
+/// written into DOCX core properties and the PDF Info dictionary. Enter leaves the edited
+/// values in `pending_apply`.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentPropertiesDialog {
+    bounds: Rect,
+    visible: bool,
+    values: [String; 4],
+    focused: usize,
+    pub pending_apply: Option<[String; 4]>,
+}
+
+impl DocumentPropertiesDialog {
+    pub fn open(&mut self, metadata: &DocumentMetadata) {
+        self.values = [
+            metadata.title.clone(),
+            metadata.author.clone(),
+            metadata.subject.clone(),
+            metadata.keywords.clone(),
+        ];
+        self.focused = 0;
+        self.pending_apply = None;
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    pub fn panel_rect(&self) -> Rect {
+        let height = PANEL_HEADER + FIELD_LABELS.len() as f32 * FIELD_HEIGHT + PANEL_FOOTER;
+        Rect {
+            x: self.bounds.x + ((self.bounds.width - PANEL_WIDTH) * 0.5).max(0.0),
+            y: self.bounds.y + ((self.bounds.height - height) * 0.5).max(0.0),
+            width: PANEL_WIDTH.min(self.bounds.width),
+            height,
+        }
+    }
+
+    pub fn fields(&self) -> Vec<PropertyFieldItem> {
+        FIELD_LABELS
+            .iter()
+            .enumerate()
+            .map(|(index, label)| PropertyFieldItem {
+                label,
+                value: self.values[index].clone(),
+                input_rect: self.input_rect(index),
+                focused: index == self.focused,
+            })
+            .collect()
+    }
+
+    fn input_rect(&self, index: usize) -> Rect {
+        let panel = self.panel_rect();
+        Rect {
+            x: panel.x + 14.0,
+            y: panel.y + PANEL_HEADER + index as f32 * FIELD_HEIGHT + 20.0,
+            width: panel.width - 28.0,
+            height: 26.0,
+        }
+    }
+
+    fn apply(&mut self) {
+        self.pending_apply = Some(self.values.clone().map(|value| value.trim().to_string()));
+        self.close();
+    }
+}
+
+/// Writes dialog values (title, author, subject, keywords) into `metadata`; returns whether
+/// anything changed.
+pub fn apply_properties(metadata: &mut DocumentMetadata, values: [String; 4]) -> bool {
+    let [title, author, subject, keywords] = values;
+    let changed = metadata.title != title
+        || metadata.author != author
+        || metadata.subject != subject
+        || metadata.keywords != keywords;
+    metadata.title = title;
+    metadata.author = author;
+    metadata.subject = subject;
+    metadata.keywords = keywords;
+    changed
+}
+
+impl UIComponent for DocumentPropertiesDialog {
+    fn layout(&mut self, bounds: Rect, _dpi: f32) {
+        self.bounds = bounds;
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // The properties dialog is composed in the shell renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match event {
+            InputEvent::KeyDown(vk) => match *vk {
+                0x1B => {
+                    self.close();
+                    true
+                }
+                0x0D => {
+                    self.apply();
+                    true
+                }
+                0x09 | 0x28 => {
+                    self.focused = (self.focused + 1) % FIELD_LABELS.len();
+                    true
+                }
+                0x26 => {
+                    self.focused = (self.focused + FIELD_LABELS.len() - 1) % FIELD_LABELS.len();
+                    true
+                }
+                0x08 => {
+                    self.values[self.focused].pop();
+                    true
+                }
+                _ => false,
+            },
+            InputEvent::Char(ch) => {
+                if !ch.is_control() {
+                    self.values[self.focused].push(*ch);
+                }
+                true
+            }
+            InputEvent::MouseDown(point) => {
+                if !contains(self.panel_rect(), *point) {
+                    self.close();
+                    return true;
+                }
+                if let Some(index) = (0..FIELD_LABELS.len()).find(|index| contains(self.input_rect(*index), *point)) {
+                    self.focused = index;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && contains(self.panel_rect(), point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+fn contains(rect: Rect, point: Point) -> bool {
+    point.x >= rect.x && point.x <= rect.x + rect.width && point.y >= rect.y && point.y <= rect.y + rect.height
+}
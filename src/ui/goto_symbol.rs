@@ -0,0 +1,285 @@
+use std::cmp::Reverse;
+
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    document::model::BlockId,
+    theme::Theme,
+    ui::{
+        InputEvent, Point, Rect, UIComponent,
+        command_palette::fuzzy_score,
+        list_panel::{ListPanel, ListPanelLayout, PanelPlacement},
+        sidebar::OutlineItem,
+    },
+};
+
+const LAYOUT: ListPanelLayout = ListPanelLayout {
+    width: 480.0,
+    max_height: 420.0,
+    header: 40.0,
+    footer: 0.0,
+    placement: PanelPlacement::Top,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolEntry {
+    pub block_id: BlockId,
+    pub title: String,
+    pub level: u8,
+}
+
+impl SymbolEntry {
+    /// The title indented two spaces per level below the top heading level.
+    fn row_label(&self) -> String {
+        format!(
+            "{}{}",
+            "  ".repeat(self.level.saturating_sub(1) as usize),
+            self.title
+        )
+    }
+}
+
+/// Ctrl+Shift+O overlay listing the document's headings, narrowed by a fuzzy query.
+/// Picking one leaves its block in `pending_jump` for the window to navigate to.
+#[derive(Debug, Clone, Default)]
+pub struct GotoSymbol {
+    list: ListPanel,
+    visible: bool,
+    query: String,
+    entries: Vec<SymbolEntry>,
+    /// Indices into `entries` that match the query, best first.
+    results: Vec<usize>,
+    pub pending_jump: Option<BlockId>,
+}
+
+impl GotoSymbol {
+    /// Opens with the headings of the outline, in document order.
+    pub fn open(&mut self, outline: &[OutlineItem]) {
+        self.open_entries(
+            outline
+                .iter()
+                .map(|item| SymbolEntry {
+                    block_id: item.block_id,
+                    title: item.title.clone(),
+                    level: item.level,
+                })
+                .collect(),
+        );
+    }
+
+    /// Opens with `entries` as the rows, e.g. cross-reference targets.
+    pub fn open_entries(&mut self, entries: Vec<SymbolEntry>) {
+        self.entries = entries;
+        self.query.clear();
+        self.pending_jump = None;
+        self.refresh_results();
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    pub fn query(&self) -> &str {
+        self.query.as_str()
+    }
+
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.query = query.into();
+        self.refresh_results();
+    }
+
+    /// The heading the caret would jump to on Enter.
+    pub fn selected_target(&self) -> Option<BlockId> {
+        self.results
+            .get(self.list.selected)
+            .map(|&index| self.entries[index].block_id)
+    }
+
+    pub fn result_labels(&self) -> Vec<String> {
+        self.results
+            .iter()
+            .map(|&index| self.entries[index].row_label())
+            .collect()
+    }
+
+    pub fn panel_rect(&self) -> Rect {
+        self.list.panel_rect(&LAYOUT)
+    }
+
+    /// Rows currently scrolled into view, with their rect, label and selection state.
+    pub fn visible_rows(&self) -> Vec<(Rect, String, bool)> {
+        self.list
+            .visible_rows(&LAYOUT, self.results.len(), |position| {
+                self.entries[self.results[position]].row_label()
+            })
+    }
+
+    fn refresh_results(&mut self) {
+        let query = self.query.trim().to_lowercase();
+        let mut scored = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                fuzzy_score(query.as_str(), entry.title.to_lowercase().as_str())
+                    .map(|(score, _)| (index, score))
+            })
+            .collect::<Vec<_>>();
+        // Stable, so equally good matches keep document order.
+        scored.sort_by_key(|&(_, score)| Reverse(score));
+        self.results = scored.into_iter().map(|(index, _)| index).collect();
+        self.list.reset();
+    }
+
+    fn select(&mut self, position: usize) {
+        self.list.select(&LAYOUT, position, self.results.len());
+    }
+
+    fn accept(&mut self) {
+        self.pending_jump = self.selected_target();
+        self.close();
+    }
+}
+
+impl UIComponent for GotoSymbol {
+    fn layout(&mut self, bounds: Rect, _dpi: f32) {
+        self.list.bounds = bounds;
+        self.select(self.list.selected);
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // Composed in the shell renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match event {
+            InputEvent::KeyDown(vk) => match *vk {
+                0x1B => {
+                    self.close();
+                    true
+                }
+                0x0D => {
+                    self.accept();
+                    true
+                }
+                0x26 => {
+                    self.select(self.list.selected.saturating_sub(1));
+                    true
+                }
+                0x28 => {
+                    self.select(self.list.selected + 1);
+                    true
+                }
+                0x08 => {
+                    self.query.pop();
+                    self.refresh_results();
+                    true
+                }
+                _ => false,
+            },
+            InputEvent::Char(ch) if !ch.is_control() => {
+                self.query.push(*ch);
+                self.refresh_results();
+                true
+            }
+            InputEvent::MouseDown(point) => {
+                if !self.panel_rect().contains(*point) {
+                    self.close();
+                    return true;
+                }
+                let len = self.results.len();
+                if let Some(position) = self.list.row_from_point(&LAYOUT, *point, len) {
+                    self.select(position);
+                    self.accept();
+                }
+                true
+            }
+            InputEvent::MouseWheel { delta, .. } => {
+                let rows = if *delta > 0.0 { -1 } else { 1 };
+                self.list.scroll_by(&LAYOUT, rows, self.results.len());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && self.panel_rect().contains(point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.list.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline() -> Vec<OutlineItem> {
+        [
+            (1, "Introduction", 1),
+            (4, "Installing on Windows", 2),
+            (9, "Configuration", 1),
+            (12, "Config file format", 2),
+            (15, "Appendix", 1),
+        ]
+        .into_iter()
+        .map(|(id, title, level)| OutlineItem {
+            block_id: BlockId(id),
+            title: title.to_string(),
+            level,
+            collapsed: false,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn fuzzy_query_filters_headings_and_keeps_levels_indented() {
+        let mut picker = GotoSymbol::default();
+        picker.open(&outline());
+        assert_eq!(picker.result_labels().len(), 5);
+        assert_eq!(picker.result_labels()[1], "  Installing on Windows");
+
+        picker.set_query("cfg");
+        assert_eq!(
+            picker.result_labels(),
+            vec!["Configuration", "  Config file format"]
+        );
+
+        picker.set_query("INSTWIN");
+        assert_eq!(picker.result_labels(), vec!["  Installing on Windows"]);
+
+        picker.set_query("zzz");
+        assert!(picker.result_labels().is_empty());
+        assert_eq!(picker.selected_target(), None);
+    }
+
+    #[test]
+    fn enter_queues_a_jump_to_the_selected_heading() {
+        let mut picker = GotoSymbol::default();
+        picker.open(&outline());
+        for ch in "conf".chars() {
+            assert!(picker.handle_input(&InputEvent::Char(ch)));
+        }
+        picker.handle_input(&InputEvent::KeyDown(0x28));
+        assert_eq!(picker.selected_target(), Some(BlockId(12)));
+
+        picker.handle_input(&InputEvent::KeyDown(0x0D));
+        assert!(!picker.is_open());
+        assert_eq!(picker.pending_jump, Some(BlockId(12)));
+    }
+}
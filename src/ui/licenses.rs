@@ -0,0 +1,319 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    theme::Theme,
+    ui::{
+        InputEvent, Point, Rect, UIComponent,
+        list_panel::{ListPanel, ListPanelLayout, PanelPlacement},
+    },
+};
+
+/// Crates compiled into the executable, regenerated by `scripts/generate-licenses.ps1`.
+const BUNDLED_LICENSES: &str = include_str!("../../resources/licenses.tsv");
+
+const LAYOUT: ListPanelLayout = ListPanelLayout {
+    width: 560.0,
+    max_height: 480.0,
+    header: 40.0,
+    footer: 28.0,
+    placement: PanelPlacement::Top,
+};
+
+/// One third-party crate and the license it ships under.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LicenseEntry {
+    pub name: String,
+    pub version: String,
+    /// SPDX expression, such as "MIT OR Apache-2.0".
+    pub license: String,
+    pub repository: Option<String>,
+}
+
+impl LicenseEntry {
+    fn row_label(&self) -> String {
+        format!("{} {} \u{2014} {}", self.name, self.version, self.license)
+    }
+}
+
+/// Parses the bundled list: one tab-separated `name, version, license[, repository]` line
+/// per crate. Blank lines, `#` comments and lines missing a field are skipped.
+pub fn parse_licenses(text: &str) -> Vec<LicenseEntry> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split('\t').map(str::trim);
+            let (Some(name), Some(version), Some(license)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                return None;
+            };
+            if name.is_empty() || version.is_empty() || license.is_empty() {
+                return None;
+            }
+            Some(LicenseEntry {
+                name: name.to_string(),
+                version: version.to_string(),
+                license: license.to_string(),
+                repository: fields
+                    .next()
+                    .filter(|repository| !repository.is_empty())
+                    .map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+/// Indices of the entries whose crate name contains `query`, ignoring case. Hyphens and
+/// underscores match each other, as they do in crate names.
+pub fn filter_licenses(entries: &[LicenseEntry], query: &str) -> Vec<usize> {
+    let normalize = |text: &str| text.trim().to_lowercase().replace('_', "-");
+    let query = normalize(query);
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| normalize(&entry.name).contains(&query))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// Settings › About list of the open-source crates Doco is built from, narrowed by typing
+/// a crate name. Enter or a click leaves the crate's repository in `pending_open`.
+#[derive(Debug, Clone, Default)]
+pub struct LicensesView {
+    list: ListPanel,
+    visible: bool,
+    query: String,
+    entries: Vec<LicenseEntry>,
+    /// Indices into `entries` that match the query, in name order.
+    results: Vec<usize>,
+    pub pending_open: Option<String>,
+}
+
+impl LicensesView {
+    /// Opens on the bundled list, parsed the first time the view is shown.
+    pub fn open(&mut self) {
+        if self.entries.is_empty() {
+            self.entries = parse_licenses(BUNDLED_LICENSES);
+        }
+        self.query.clear();
+        self.pending_open = None;
+        self.refresh_results();
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    pub fn query(&self) -> &str {
+        self.query.as_str()
+    }
+
+    pub fn set_query(&mut self, query: impl Into<String>) {
+        self.query = query.into();
+        self.refresh_results();
+    }
+
+    pub fn result_names(&self) -> Vec<&str> {
+        self.results
+            .iter()
+            .map(|&index| self.entries[index].name.as_str())
+            .collect()
+    }
+
+    /// "12 of 188 crates", for the footer.
+    pub fn summary(&self) -> String {
+        if self.results.len() == self.entries.len() {
+            format!("{} crates", self.entries.len())
+        } else {
+            format!("{} of {} crates", self.results.len(), self.entries.len())
+        }
+    }
+
+    pub fn panel_rect(&self) -> Rect {
+        self.list.panel_rect(&LAYOUT)
+    }
+
+    /// Rows currently scrolled into view, with their rect, label and selection state.
+    pub fn visible_rows(&self) -> Vec<(Rect, String, bool)> {
+        self.list
+            .visible_rows(&LAYOUT, self.results.len(), |position| {
+                self.entries[self.results[position]].row_label()
+            })
+    }
+
+    fn refresh_results(&mut self) {
+        self.results = filter_licenses(&self.entries, &self.query);
+        self.list.reset();
+    }
+
+    fn select(&mut self, position: usize) {
+        self.list.select(&LAYOUT, position, self.results.len());
+    }
+
+    /// Queues the selected crate's repository; the view stays open to come back to.
+    fn accept(&mut self) {
+        self.pending_open = self
+            .results
+            .get(self.list.selected)
+            .and_then(|&index| self.entries[index].repository.clone());
+    }
+}
+
+impl UIComponent for LicensesView {
+    fn layout(&mut self, bounds: Rect, _dpi: f32) {
+        self.list.bounds = bounds;
+        self.select(self.list.selected);
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // Composed in the shell renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match event {
+            InputEvent::KeyDown(vk) => match *vk {
+                0x1B => {
+                    self.close();
+                    true
+                }
+                0x0D => {
+                    self.accept();
+                    true
+                }
+                0x26 => {
+                    self.select(self.list.selected.saturating_sub(1));
+                    true
+                }
+                0x28 => {
+                    self.select(self.list.selected + 1);
+                    true
+                }
+                // Page Up / Page Down.
+                0x21 => {
+                    let page = self.list.row_capacity(&LAYOUT);
+                    self.select(self.list.selected.saturating_sub(page));
+                    true
+                }
+                0x22 => {
+                    let page = self.list.row_capacity(&LAYOUT);
+                    self.select(self.list.selected + page);
+                    true
+                }
+                0x08 => {
+                    self.query.pop();
+                    self.refresh_results();
+                    true
+                }
+                _ => false,
+            },
+            InputEvent::Char(ch) if !ch.is_control() => {
+                self.query.push(*ch);
+                self.refresh_results();
+                true
+            }
+            InputEvent::MouseDown(point) => {
+                if !self.panel_rect().contains(*point) {
+                    self.close();
+                    return true;
+                }
+                let len = self.results.len();
+                if let Some(position) = self.list.row_from_point(&LAYOUT, *point, len) {
+                    self.select(position);
+                    self.accept();
+                }
+                true
+            }
+            InputEvent::MouseWheel { delta, .. } => {
+                let rows = if *delta > 0.0 { -3 } else { 3 };
+                self.list.scroll_by(&LAYOUT, rows, self.results.len());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && self.panel_rect().contains(point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.list.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = "# name, version, license, repository\n\
+        \n\
+        serde\t1.0.228\tMIT OR Apache-2.0\thttps://github.com/serde-rs/serde\n\
+        serde_json\t1.0.145\tMIT OR Apache-2.0\n\
+        quick-xml\t0.38.3\tMIT\thttps://github.com/tafia/quick-xml\n\
+        broken\t1.0\n";
+
+    #[test]
+    fn bundled_file_parses_into_entries() {
+        let entries = parse_licenses(SAMPLE);
+        assert_eq!(entries.len(), 3);
+        assert_eq!(
+            entries[0],
+            LicenseEntry {
+                name: "serde".to_string(),
+                version: "1.0.228".to_string(),
+                license: "MIT OR Apache-2.0".to_string(),
+                repository: Some("https://github.com/serde-rs/serde".to_string()),
+            }
+        );
+        assert_eq!(entries[1].repository, None);
+        assert_eq!(entries[2].row_label(), "quick-xml 0.38.3 \u{2014} MIT");
+
+        // The file shipped in the executable parses completely.
+        let bundled = parse_licenses(BUNDLED_LICENSES);
+        let lines = BUNDLED_LICENSES
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+            .count();
+        assert_eq!(bundled.len(), lines);
+        assert!(bundled.iter().any(|entry| entry.name == "serde"));
+    }
+
+    #[test]
+    fn search_matches_crate_names_only() {
+        let entries = parse_licenses(SAMPLE);
+        assert_eq!(filter_licenses(&entries, ""), vec![0, 1, 2]);
+        assert_eq!(filter_licenses(&entries, "SERDE"), vec![0, 1]);
+        assert_eq!(filter_licenses(&entries, "serde-json"), vec![1]);
+        assert_eq!(filter_licenses(&entries, "quick_xml"), vec![2]);
+        // Licenses aren't searched.
+        assert!(filter_licenses(&entries, "apache").is_empty());
+
+        let mut view = LicensesView {
+            entries,
+            visible: true,
+            ..LicensesView::default()
+        };
+        view.set_query("xml");
+        assert_eq!(view.result_names(), vec!["quick-xml"]);
+        assert_eq!(view.summary(), "1 of 3 crates");
+        view.handle_input(&InputEvent::KeyDown(0x0D));
+        assert_eq!(
+            view.pending_open.as_deref(),
+            Some("https://github.com/tafia/quick-xml")
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use crate::ui::{Point, Rect};
+
+pub const ROW_HEIGHT: f32 = 24.0;
+
+/// Where a list overlay sits in the window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanelPlacement {
+    /// Just below the top edge, like the command palette.
+    Top,
+    /// In the middle, like a dialog.
+    Center,
+}
+
+/// The size of a list overlay: a header, rows of `ROW_HEIGHT` and a footer.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ListPanelLayout {
+    pub width: f32,
+    pub max_height: f32,
+    pub header: f32,
+    pub footer: f32,
+    pub placement: PanelPlacement,
+}
+
+/// Geometry, selection and scrolling shared by the overlays that list rows to pick from,
+/// such as Go to Symbol or the shortcut editor. The overlay keeps its own rows and passes
+/// how many there are; positions index into them.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ListPanel {
+    pub bounds: Rect,
+    pub selected: usize,
+    pub scroll: usize,
+}
+
+impl ListPanel {
+    pub fn panel_rect(&self, layout: &ListPanelLayout) -> Rect {
+        let bounds = self.bounds;
+        let min_height = layout.header + layout.footer + ROW_HEIGHT;
+        match layout.placement {
+            PanelPlacement::Top => {
+                let height = layout
+                    .max_height
+                    .min((bounds.height - 40.0).max(min_height));
+                let width = layout.width.min(bounds.width - 24.0).max(240.0);
+                Rect {
+                    x: bounds.x + ((bounds.width - width) * 0.5).max(0.0),
+                    y: bounds.y + 20.0,
+                    width,
+                    height,
+                }
+            }
+            PanelPlacement::Center => {
+                let height = layout
+                    .max_height
+                    .min((bounds.height - 80.0).max(min_height));
+                let width = layout.width.min(bounds.width);
+                Rect {
+                    x: bounds.x + ((bounds.width - width) * 0.5).max(0.0),
+                    y: bounds.y + ((bounds.height - height) * 0.5).max(0.0),
+                    width,
+                    height,
+                }
+            }
+        }
+    }
+
+    /// How many rows fit between the header and the footer; at least one.
+    pub fn row_capacity(&self, layout: &ListPanelLayout) -> usize {
+        let list_height = self.panel_rect(layout).height - layout.header - layout.footer;
+        ((list_height / ROW_HEIGHT).floor() as usize).max(1)
+    }
+
+    /// Rows scrolled into view out of `len`, with their rect, `label` and selection state.
+    pub fn visible_rows(
+        &self,
+        layout: &ListPanelLayout,
+        len: usize,
+        label: impl Fn(usize) -> String,
+    ) -> Vec<(Rect, String, bool)> {
+        let panel = self.panel_rect(layout);
+        (self.scroll..len)
+            .take(self.row_capacity(layout))
+            .enumerate()
+            .map(|(slot, position)| {
+                (
+                    Rect {
+                        x: panel.x + 10.0,
+                        y: panel.y + layout.header + slot as f32 * ROW_HEIGHT,
+                        width: panel.width - 20.0,
+                        height: ROW_HEIGHT - 2.0,
+                    },
+                    label(position),
+                    position == self.selected,
+                )
+            })
+            .collect()
+    }
+
+    /// Selects `position`, kept within `len` rows, and scrolls it into view.
+    pub fn select(&mut self, layout: &ListPanelLayout, position: usize, len: usize) {
+        self.selected = position.min(len.saturating_sub(1));
+        let capacity = self.row_capacity(layout);
+        if self.selected < self.scroll {
+            self.scroll = self.selected;
+        } else if self.selected >= self.scroll + capacity {
+            self.scroll = self.selected + 1 - capacity;
+        }
+    }
+
+    /// Scrolls `rows` down, or up when negative, without leaving rows of `len` unshown.
+    pub fn scroll_by(&mut self, layout: &ListPanelLayout, rows: isize, len: usize) {
+        let max_scroll = len.saturating_sub(self.row_capacity(layout));
+        self.scroll = self.scroll.saturating_add_signed(rows).min(max_scroll);
+    }
+
+    /// Back to the first row, as when the list is refilled.
+    pub fn reset(&mut self) {
+        self.selected = 0;
+        self.scroll = 0;
+    }
+
+    /// The position of the row of `len` under `point`.
+    pub fn row_from_point(
+        &self,
+        layout: &ListPanelLayout,
+        point: Point,
+        len: usize,
+    ) -> Option<usize> {
+        let panel = self.panel_rect(layout);
+        let top = panel.y + layout.header;
+        if !panel.contains(point) || point.y < top {
+            return None;
+        }
+        let slot = ((point.y - top) / ROW_HEIGHT).floor() as usize;
+        (slot < self.row_capacity(layout))
+            .then_some(self.scroll + slot)
+            .filter(|position| *position < len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LAYOUT: ListPanelLayout = ListPanelLayout {
+        width: 400.0,
+        max_height: 400.0,
+        header: 40.0,
+        footer: 16.0,
+        placement: PanelPlacement::Center,
+    };
+
+    #[test]
+    fn selection_scrolls_into_view_and_clicks_find_rows() {
+        // The panel comes out 160px tall: room for four rows between header and footer.
+        let mut list = ListPanel {
+            bounds: Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 600.0,
+                height: 240.0,
+            },
+            ..ListPanel::default()
+        };
+        assert_eq!(list.row_capacity(&LAYOUT), 4);
+
+        list.select(&LAYOUT, 6, 10);
+        assert_eq!((list.selected, list.scroll), (6, 3));
+        list.select(&LAYOUT, 40, 10);
+        assert_eq!((list.selected, list.scroll), (9, 6));
+        list.scroll_by(&LAYOUT, -10, 10);
+        assert_eq!(list.scroll, 0);
+        list.scroll_by(&LAYOUT, 10, 10);
+        assert_eq!(list.scroll, 6);
+
+        let rows = list.visible_rows(&LAYOUT, 10, |position| position.to_string());
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[3].1, "9");
+        assert!(rows[3].2);
+
+        let panel = list.panel_rect(&LAYOUT);
+        let at = |slot: f32| Point {
+            x: panel.x + 20.0,
+            y: panel.y + LAYOUT.header + slot * ROW_HEIGHT + 4.0,
+        };
+        assert_eq!(list.row_from_point(&LAYOUT, at(1.0), 10), Some(7));
+        assert_eq!(list.row_from_point(&LAYOUT, at(1.0), 7), None);
+        assert_eq!(list.row_from_point(&LAYOUT, at(-1.0), 10), None);
+    }
+}
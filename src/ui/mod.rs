@@ -23,6 +23,16 @@ pub struct Rect {
     pub height: f32,
 }
 
+impl Rect {
+    /// Whether `point` is inside, edges included.
+    pub fn contains(&self, point: Point) -> bool {
+        point.x >= self.x
+            && point.x <= self.x + self.width
+            && point.y >= self.y
+            && point.y <= self.y + self.height
+    }
+}
+
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
 pub struct Color {
     pub r: f32,
@@ -187,8 +197,16 @@ impl Default for AccessibilityPreferences {
 pub mod command_palette;
 pub mod context_menu;
 pub mod dialog;
+pub mod document_properties;
+pub mod goto_symbol;
+pub mod licenses;
+pub mod list_panel;
+pub mod shortcut_editor;
 pub mod sidebar;
+pub mod stats_popup;
 pub mod statusbar;
 pub mod tabs;
 pub mod toast;
 pub mod toolbar;
+pub mod toolbar_customizer;
+pub mod zoom_popup;
@@ -0,0 +1,368 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    settings::{keymap::KeyChord, schema::KeyboardShortcutsSettings},
+    theme::Theme,
+    ui::{
+        InputEvent, Point, Rect, UIComponent,
+        list_panel::{ListPanel, ListPanelLayout, PanelPlacement},
+    },
+};
+
+const LAYOUT: ListPanelLayout = ListPanelLayout {
+    width: 520.0,
+    max_height: 520.0,
+    header: 40.0,
+    footer: 32.0,
+    placement: PanelPlacement::Center,
+};
+
+/// What the editor is doing with the keyboard.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ShortcutEditMode {
+    #[default]
+    Browse,
+    /// Waiting for the new keys of a command.
+    Capture(String),
+    /// The captured keys belong to other commands; Enter takes them over, Esc drops them.
+    Confirm {
+        command: String,
+        keys: String,
+        holders: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EditorRow {
+    command: String,
+    label: String,
+}
+
+/// Settings › Keyboard Shortcuts editor. Conflicting pairs are listed first, then every
+/// binding; Enter records new keys for the selected command and Delete clears it. Each
+/// change leaves the full binding map in `pending_bindings` for the window to save.
+#[derive(Debug, Clone, Default)]
+pub struct ShortcutEditor {
+    list: ListPanel,
+    visible: bool,
+    shortcuts: KeyboardShortcutsSettings,
+    rows: Vec<EditorRow>,
+    /// How many of `rows` make up the conflict list at the top.
+    conflict_rows: usize,
+    mode: ShortcutEditMode,
+    pub pending_bindings: Option<KeyboardShortcutsSettings>,
+}
+
+impl ShortcutEditor {
+    pub fn open(&mut self, shortcuts: &KeyboardShortcutsSettings) {
+        self.shortcuts = shortcuts.clone();
+        self.mode = ShortcutEditMode::Browse;
+        self.pending_bindings = None;
+        self.refresh_rows();
+        self.list.reset();
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.mode = ShortcutEditMode::Browse;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    /// Whether the next key press is recorded as a binding rather than handled.
+    pub fn is_capturing(&self) -> bool {
+        self.visible && matches!(self.mode, ShortcutEditMode::Capture(_))
+    }
+
+    pub fn mode(&self) -> &ShortcutEditMode {
+        &self.mode
+    }
+
+    pub fn title(&self) -> String {
+        match self.shortcuts.detect_conflicts().len() {
+            0 => "Keyboard shortcuts".to_string(),
+            1 => "Keyboard shortcuts \u{2014} 1 conflict".to_string(),
+            count => format!("Keyboard shortcuts \u{2014} {count} conflicts"),
+        }
+    }
+
+    /// The footer line, which changes with the mode.
+    pub fn hint(&self) -> String {
+        match &self.mode {
+            ShortcutEditMode::Browse => {
+                "Enter assigns new keys \u{00B7} Delete clears \u{00B7} Esc closes".to_string()
+            }
+            ShortcutEditMode::Capture(command) => {
+                format!(
+                    "Press the new keys for {} \u{00B7} Esc cancels",
+                    self.label_of(command)
+                )
+            }
+            ShortcutEditMode::Confirm { keys, holders, .. } => format!(
+                "{keys} is used by {} \u{00B7} Enter reassigns it \u{00B7} Esc cancels",
+                holders
+                    .iter()
+                    .map(|holder| self.label_of(holder))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        }
+    }
+
+    /// Starts recording keys for the selected command.
+    pub fn begin_capture(&mut self) -> bool {
+        let Some(row) = self.rows.get(self.list.selected) else {
+            return false;
+        };
+        self.mode = ShortcutEditMode::Capture(row.command.clone());
+        true
+    }
+
+    /// Records a key press while capturing. Modifiers alone keep waiting and a bare Esc
+    /// cancels. Keys another command already has need confirming before they're saved.
+    pub fn capture_key(&mut self, vk: u32, ctrl: bool, shift: bool, alt: bool) -> bool {
+        let ShortcutEditMode::Capture(command) = &self.mode else {
+            return false;
+        };
+        if KeyChord::is_modifier_key(vk) {
+            return true;
+        }
+        if vk == 0x1B && !ctrl && !shift && !alt {
+            self.mode = ShortcutEditMode::Browse;
+            return true;
+        }
+        let command = command.clone();
+        let keys = KeyChord::new(vk, ctrl, shift, alt).to_string();
+        match self.shortcuts.try_set_binding(&command, &keys) {
+            Ok(()) => {
+                self.mode = ShortcutEditMode::Browse;
+                self.commit();
+            }
+            Err(holders) => {
+                self.mode = ShortcutEditMode::Confirm {
+                    command,
+                    keys,
+                    holders,
+                };
+            }
+        }
+        true
+    }
+
+    /// Removes the selected command's keys.
+    pub fn clear_selected(&mut self) -> bool {
+        let Some(command) = self
+            .rows
+            .get(self.list.selected)
+            .map(|row| row.command.clone())
+        else {
+            return false;
+        };
+        self.shortcuts.clear_binding(&command);
+        self.commit();
+        true
+    }
+
+    pub fn panel_rect(&self) -> Rect {
+        self.list.panel_rect(&LAYOUT)
+    }
+
+    /// Rows currently scrolled into view, with their rect, label and selection state.
+    pub fn visible_rows(&self) -> Vec<(Rect, String, bool)> {
+        self.list.visible_rows(&LAYOUT, self.rows.len(), |index| {
+            self.rows[index].label.clone()
+        })
+    }
+
+    fn label_of<'a>(&'a self, command: &'a str) -> &'a str {
+        self.shortcuts
+            .bindings
+            .get(command)
+            .map(|binding| binding.label.as_str())
+            .filter(|label| !label.is_empty())
+            .unwrap_or(command)
+    }
+
+    /// Conflicting pairs first, each side on its own row so either can be edited, then
+    /// every binding in command order.
+    fn refresh_rows(&mut self) {
+        let mut rows = Vec::new();
+        for conflict in self.shortcuts.detect_conflicts() {
+            for (first, second) in conflict.pairs() {
+                for (command, other) in [(first, second), (second, first)] {
+                    rows.push(EditorRow {
+                        command: command.to_string(),
+                        label: format!(
+                            "\u{26A0} {}: {} \u{2194} {}",
+                            conflict.keys,
+                            self.label_of(command),
+                            self.label_of(other)
+                        ),
+                    });
+                }
+            }
+        }
+        self.conflict_rows = rows.len();
+        for (command, binding) in &self.shortcuts.bindings {
+            let keys = if binding.keys.trim().is_empty() {
+                "(none)"
+            } else {
+                binding.keys.as_str()
+            };
+            rows.push(EditorRow {
+                command: command.clone(),
+                label: format!("{} \u{2014} {}", self.label_of(command), keys),
+            });
+        }
+        self.rows = rows;
+    }
+
+    fn commit(&mut self) {
+        self.refresh_rows();
+        self.select(self.list.selected);
+        self.pending_bindings = Some(self.shortcuts.clone());
+    }
+
+    fn confirm_override(&mut self) {
+        let ShortcutEditMode::Confirm { command, keys, .. } = std::mem::take(&mut self.mode) else {
+            return;
+        };
+        self.shortcuts.take_binding(&command, &keys);
+        self.commit();
+    }
+
+    fn select(&mut self, index: usize) {
+        self.list.select(&LAYOUT, index, self.rows.len());
+    }
+}
+
+impl UIComponent for ShortcutEditor {
+    fn layout(&mut self, bounds: Rect, _dpi: f32) {
+        self.list.bounds = bounds;
+        self.select(self.list.selected);
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // Composed in the shell renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        if let ShortcutEditMode::Confirm { .. } = self.mode {
+            match event {
+                InputEvent::KeyDown(0x0D) => self.confirm_override(),
+                InputEvent::KeyDown(0x1B) | InputEvent::MouseDown(_) => {
+                    self.mode = ShortcutEditMode::Browse;
+                }
+                _ => {}
+            }
+            return true;
+        }
+
+        match event {
+            InputEvent::KeyDown(vk) => match *vk {
+                0x1B => {
+                    self.close();
+                    true
+                }
+                0x26 => {
+                    self.select(self.list.selected.saturating_sub(1));
+                    true
+                }
+                0x28 => {
+                    self.select(self.list.selected + 1);
+                    true
+                }
+                0x0D | 0x20 => self.begin_capture(),
+                0x2E | 0x08 => self.clear_selected(),
+                _ => false,
+            },
+            InputEvent::MouseDown(point) => {
+                if !self.panel_rect().contains(*point) {
+                    self.close();
+                    return true;
+                }
+                let len = self.rows.len();
+                if let Some(index) = self.list.row_from_point(&LAYOUT, *point, len) {
+                    self.select(index);
+                    self.begin_capture();
+                }
+                true
+            }
+            InputEvent::MouseWheel { delta, .. } => {
+                let rows = if *delta > 0.0 { -1 } else { 1 };
+                self.list.scroll_by(&LAYOUT, rows, self.rows.len());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && self.panel_rect().contains(point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.list.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conflicts_are_listed_and_resolved_inline() {
+        let mut shortcuts = KeyboardShortcutsSettings::default();
+        shortcuts.set_binding("edit.replace", "Ctrl+F");
+        let mut editor = ShortcutEditor::default();
+        editor.open(&shortcuts);
+        assert_eq!(editor.title(), "Keyboard shortcuts \u{2014} 1 conflict");
+        assert_eq!(editor.conflict_rows, 2);
+        assert_eq!(
+            editor.rows[0].label,
+            "\u{26A0} Ctrl+F: Find \u{2194} Replace"
+        );
+        assert_eq!(
+            editor.rows[1].label,
+            "\u{26A0} Ctrl+F: Replace \u{2194} Find"
+        );
+
+        // Give Replace other keys that Save already uses: nothing changes until confirmed.
+        editor.select(1);
+        editor.handle_input(&InputEvent::KeyDown(0x0D));
+        assert!(editor.is_capturing());
+        assert!(editor.capture_key(0x11, true, false, false));
+        editor.capture_key(0x53, true, false, false);
+        assert_eq!(
+            editor.mode(),
+            &ShortcutEditMode::Confirm {
+                command: "edit.replace".to_string(),
+                keys: "Ctrl+S".to_string(),
+                holders: vec!["file.save".to_string()],
+            }
+        );
+        editor.handle_input(&InputEvent::KeyDown(0x1B));
+        assert_eq!(editor.mode(), &ShortcutEditMode::Browse);
+        assert!(editor.pending_bindings.is_none());
+
+        // Free keys go straight through and the conflict list empties.
+        editor.begin_capture();
+        editor.capture_key(0x48, true, true, false);
+        let saved = editor.pending_bindings.take().expect("bindings to save");
+        assert_eq!(saved.bindings["edit.replace"].keys, "Ctrl+Shift+H");
+        assert!(saved.detect_conflicts().is_empty());
+        assert_eq!(editor.conflict_rows, 0);
+        assert_eq!(editor.title(), "Keyboard shortcuts");
+    }
+}
@@ -10,8 +10,13 @@ use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
 use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
 
 use crate::{
-    document::model::{Block, BlockId, DocumentModel, Heading},
+    document::model::{Block, BlockId, DocumentModel},
+    editor::{
+        bookmarks::{SavedBookmark, bookmark_anchor, resolve_bookmark},
+        outline::toc_entries,
+    },
     render::animation::{Animation, Easing},
+    settings::schema::{AppearanceSettings, SidebarDefaultPanel},
     theme::Theme,
     ui::{InputEvent, Point, Rect, UIComponent},
 };
@@ -44,6 +49,34 @@ impl SidebarPanel {
             SidebarPanel::SearchResults => "Search Results",
         }
     }
+
+    /// How settings remember this panel for the next launch. Search results aren't kept,
+    /// since the query that produced them isn't.
+    pub fn remembered(self) -> Option<SidebarDefaultPanel> {
+        match self {
+            SidebarPanel::Files => Some(SidebarDefaultPanel::Files),
+            SidebarPanel::Outline => Some(SidebarDefaultPanel::Outline),
+            SidebarPanel::Bookmarks => Some(SidebarDefaultPanel::Bookmarks),
+            SidebarPanel::SearchResults => None,
+        }
+    }
+
+    /// The panel the sidebar opens on: the one shown last, else the default from settings.
+    pub fn restored(appearance: &AppearanceSettings) -> Self {
+        match appearance
+            .sidebar_last_panel
+            .unwrap_or(appearance.sidebar_default_panel)
+        {
+            SidebarDefaultPanel::Files => SidebarPanel::Files,
+            SidebarDefaultPanel::Outline => SidebarPanel::Outline,
+            SidebarDefaultPanel::Bookmarks => SidebarPanel::Bookmarks,
+        }
+    }
+}
+
+/// `width` kept within the widths the sidebar splitter allows.
+pub fn clamp_sidebar_width(width: f32) -> f32 {
+    width.clamp(SIDEBAR_MIN_WIDTH, SIDEBAR_MAX_WIDTH)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -135,6 +168,44 @@ pub struct Bookmark {
     pub snippet: String,
 }
 
+impl Bookmark {
+    /// This bookmark as kept between sessions; `None` once its block is gone from `blocks`.
+    pub fn to_saved(&self, blocks: &[Block]) -> Option<SavedBookmark> {
+        Some(SavedBookmark {
+            name: self.name.clone(),
+            page_number: self.page_number,
+            snippet: self.snippet.clone(),
+            anchor: bookmark_anchor(blocks, self.block_id)?,
+        })
+    }
+}
+
+/// The bookmarks kept for a document, pointed at the blocks it opened with and numbered
+/// from 1 in list order.
+pub fn restore_bookmarks(blocks: &[Block], saved: &[SavedBookmark]) -> Vec<Bookmark> {
+    saved
+        .iter()
+        .filter_map(|saved| Some((resolve_bookmark(blocks, &saved.anchor)?, saved)))
+        .zip(1..)
+        .map(|((block_id, saved), id)| Bookmark {
+            id,
+            name: saved.name.clone(),
+            page_number: saved.page_number,
+            block_id,
+            snippet: saved.snippet.clone(),
+        })
+        .collect()
+}
+
+/// A bookmark or outline row being dragged. For bookmarks `over` is the row to swap into;
+/// for outline headings it is the row the section drops in front of, or one past the last
+/// row for the end of the document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct RowDrag {
+    from: usize,
+    over: usize,
+}
+
 #[derive(Debug, Clone)]
 pub struct SearchResultItem {
     pub block_id: BlockId,
@@ -168,6 +239,14 @@ pub struct Sidebar {
     hover_started: Option<Instant>,
     pub show_tooltip: bool,
     next_bookmark_id: u64,
+    /// The bookmark last reached by "next/previous bookmark".
+    current_bookmark: Option<u64>,
+    /// Whether a bookmark row holds the keys, so Delete removes it: from pressing the row
+    /// until the click jumps into the document, and while renaming.
+    bookmark_focused: bool,
+    row_drag: Option<RowDrag>,
+    /// Bookmark id and the name typed so far while renaming inline.
+    renaming_bookmark: Option<(u64, String)>,
     current_outline_block: Option<BlockId>,
     pending_intent: Option<SidebarIntent>,
 }
@@ -204,6 +283,10 @@ impl Sidebar {
             hover_started: None,
             show_tooltip: false,
             next_bookmark_id: 1,
+            current_bookmark: None,
+            bookmark_focused: false,
+            row_drag: None,
+            renaming_bookmark: None,
             current_outline_block: None,
             pending_intent: None,
         }
@@ -242,6 +325,9 @@ impl Sidebar {
     pub fn set_active_panel(&mut self, panel: SidebarPanel) {
         self.active_panel = panel;
         self.selected_index = 0;
+        self.bookmark_focused = false;
+        self.row_drag = None;
+        self.renaming_bookmark = None;
     }
 
     pub fn toggle(&mut self) {
@@ -262,7 +348,7 @@ impl Sidebar {
     }
 
     pub fn set_width(&mut self, width: f32) {
-        self.target_width = width.clamp(SIDEBAR_MIN_WIDTH, SIDEBAR_MAX_WIDTH);
+        self.target_width = clamp_sidebar_width(width);
         self.width = self.target_width;
         self.is_collapsed = self.width <= 0.1;
     }
@@ -326,34 +412,15 @@ impl Sidebar {
     }
 
     pub fn populate_outline(&mut self, document: &DocumentModel) {
-        self.outline_items.clear();
-        for block in &document.content {
-            match block {
-                Block::Heading(Heading { id, level, runs }) => {
-                    let title = runs.iter().map(|r| r.text.as_str()).collect::<String>();
-                    self.outline_items.push(OutlineItem {
-                        block_id: *id,
-                        title,
-                        level: (*level).clamp(1, 6),
-                        collapsed: false,
-                    });
-                }
-                Block::Paragraph(p) => {
-                    if let Some(style) = &p.style_id {
-                        if let Some(level) = heading_level_from_style(style) {
-                            let title = p.runs.iter().map(|r| r.text.as_str()).collect::<String>();
-                            self.outline_items.push(OutlineItem {
-                                block_id: p.id,
-                                title,
-                                level,
-                                collapsed: false,
-                            });
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
+        self.outline_items = toc_entries(&document.content)
+            .into_iter()
+            .map(|entry| OutlineItem {
+                block_id: entry.block_id,
+                title: entry.text,
+                level: entry.level,
+                collapsed: false,
+            })
+            .collect();
     }
 
     pub fn add_bookmark(&mut self, block_id: BlockId, page: usize, nearby_text: &str) -> u64 {
@@ -386,9 +453,134 @@ impl Sidebar {
     pub fn delete_bookmark(&mut self, id: u64) -> bool {
         let before = self.bookmarks.len();
         self.bookmarks.retain(|b| b.id != id);
+        if self.current_bookmark == Some(id) {
+            self.current_bookmark = None;
+        }
+        if self
+            .renaming_bookmark
+            .as_ref()
+            .is_some_and(|(renaming, _)| *renaming == id)
+        {
+            self.renaming_bookmark = None;
+        }
+        if self.active_panel == SidebarPanel::Bookmarks {
+            self.selected_index = self
+                .selected_index
+                .min(self.bookmarks.len().saturating_sub(1));
+        }
         self.bookmarks.len() != before
     }
 
+    /// Moves the bookmark at `from` so it ends up at `to`, shifting the ones in between.
+    pub fn move_bookmark(&mut self, from: usize, to: usize) -> bool {
+        if from >= self.bookmarks.len() || to >= self.bookmarks.len() || from == to {
+            return false;
+        }
+        let bookmark = self.bookmarks.remove(from);
+        self.bookmarks.insert(to, bookmark);
+        true
+    }
+
+    /// Steps to the next (or previous) bookmark in list order, wrapping at either end, and
+    /// queues a jump to it.
+    pub fn cycle_bookmark(&mut self, forward: bool) -> Option<&Bookmark> {
+        let len = self.bookmarks.len();
+        if len == 0 {
+            return None;
+        }
+        let index = match self
+            .current_bookmark
+            .and_then(|id| self.bookmarks.iter().position(|b| b.id == id))
+        {
+            Some(index) if forward => (index + 1) % len,
+            Some(index) => (index + len - 1) % len,
+            None if forward => 0,
+            None => len - 1,
+        };
+        let bookmark = &self.bookmarks[index];
+        self.current_bookmark = Some(bookmark.id);
+        if self.active_panel == SidebarPanel::Bookmarks {
+            self.selected_index = index;
+        }
+        self.pending_intent = Some(SidebarIntent::JumpToBlock(bookmark.block_id));
+        Some(bookmark)
+    }
+
+    /// Lists `bookmarks` in place of the current ones and returns those, so the list can
+    /// follow the active tab.
+    pub fn swap_bookmarks(&mut self, bookmarks: Vec<Bookmark>) -> Vec<Bookmark> {
+        let next_id = bookmarks.iter().map(|b| b.id + 1).max().unwrap_or(1);
+        self.next_bookmark_id = self.next_bookmark_id.max(next_id);
+        self.current_bookmark = None;
+        self.bookmark_focused = false;
+        self.renaming_bookmark = None;
+        self.row_drag = None;
+        if self.active_panel == SidebarPanel::Bookmarks {
+            self.selected_index = 0;
+        }
+        std::mem::replace(&mut self.bookmarks, bookmarks)
+    }
+
+    /// Whether Delete and typed text should go to the bookmark list rather than the document.
+    pub fn wants_bookmark_keys(&self) -> bool {
+        self.active_panel == SidebarPanel::Bookmarks
+            && (self.bookmark_focused || self.renaming_bookmark.is_some())
+    }
+
+    pub fn is_renaming_bookmark(&self) -> bool {
+        self.renaming_bookmark.is_some()
+    }
+
+    /// Starts renaming the bookmark row under `point` in place; used on double-click.
+    pub fn begin_rename_bookmark_at(&mut self, point: Point) -> bool {
+        if self.active_panel != SidebarPanel::Bookmarks {
+            return false;
+        }
+        let Some(index) = self.item_index_at_point(point) else {
+            return false;
+        };
+        let bookmark = &self.bookmarks[index];
+        self.selected_index = index;
+        self.bookmark_focused = true;
+        self.row_drag = None;
+        self.pending_intent = None;
+        self.renaming_bookmark = Some((bookmark.id, bookmark.name.clone()));
+        true
+    }
+
+    fn commit_bookmark_rename(&mut self) {
+        if let Some((id, name)) = self.renaming_bookmark.take() {
+            let name = name.trim();
+            if !name.is_empty() {
+                self.rename_bookmark(id, name.to_string());
+            }
+        }
+    }
+
+    fn handle_bookmark_key(&mut self, vk: u32) -> bool {
+        if self.renaming_bookmark.is_some() {
+            match vk {
+                0x0D => self.commit_bookmark_rename(),
+                0x1B => self.renaming_bookmark = None,
+                0x08 => {
+                    if let Some((_, draft)) = &mut self.renaming_bookmark {
+                        draft.pop();
+                    }
+                }
+                _ => {}
+            }
+            // Everything else is swallowed so the document doesn't react while typing.
+            return true;
+        }
+        if vk == 0x2E
+            && let Some(id) = self.bookmarks.get(self.selected_index).map(|b| b.id)
+        {
+            self.delete_bookmark(id);
+            return true;
+        }
+        false
+    }
+
     pub fn set_search_results(&mut self, term: impl Into<String>, results: Vec<SearchResultItem>) {
         self.search_term = term.into();
         self.search_results = results;
@@ -417,19 +609,38 @@ impl Sidebar {
                 }
             }
             SidebarPanel::Outline => {
-                for item in self.outline_items.iter().take(max_rows) {
+                let drop_row = self
+                    .row_drag
+                    .filter(|drag| drag.over != drag.from)
+                    .map(|drag| drag.over);
+                for (index, item) in self.outline_items.iter().enumerate().take(max_rows) {
                     let indent = "  ".repeat(item.level.saturating_sub(1) as usize);
-                    let active = if self.current_outline_block == Some(item.block_id) {
+                    let active = if drop_row == Some(index) {
+                        "=> "
+                    } else if self.current_outline_block == Some(item.block_id) {
                         "> "
                     } else {
                         ""
                     };
                     rows.push(format!("{active}{indent}{}", item.title));
                 }
+                if drop_row == Some(self.outline_items.len()) && rows.len() < max_rows {
+                    rows.push("=> (end of document)".to_string());
+                }
             }
             SidebarPanel::Bookmarks => {
-                for item in self.bookmarks.iter().take(max_rows) {
-                    rows.push(format!("{} (p{})", item.name, item.page_number));
+                for (index, item) in self.bookmarks.iter().enumerate().take(max_rows) {
+                    let marker = match self.row_drag {
+                        Some(drag) if drag.over == index && drag.from != index => "=> ",
+                        _ if self.current_bookmark == Some(item.id) => "> ",
+                        _ => "",
+                    };
+                    match &self.renaming_bookmark {
+                        Some((id, draft)) if *id == item.id => {
+                            rows.push(format!("{marker}{draft}|"))
+                        }
+                        _ => rows.push(format!("{marker}{} (p{})", item.name, item.page_number)),
+                    }
                 }
             }
             SidebarPanel::SearchResults => {
@@ -441,6 +652,11 @@ impl Sidebar {
         rows
     }
 
+    /// Queues an intent raised outside the sidebar so it goes through the same handling.
+    pub fn queue_intent(&mut self, intent: SidebarIntent) {
+        self.pending_intent = Some(intent);
+    }
+
     pub fn take_intent(&mut self) -> Option<SidebarIntent> {
         self.pending_intent.take()
     }
@@ -609,19 +825,45 @@ impl UIComponent for Sidebar {
     fn handle_input(&mut self, event: &InputEvent) -> bool {
         match event {
             InputEvent::KeyDown(vk) => {
+                if self.wants_bookmark_keys() && self.handle_bookmark_key(*vk) {
+                    return true;
+                }
                 if let Some(intent) = self.keyboard_navigate(*vk) {
                     self.pending_intent = Some(intent);
                     return true;
                 }
                 false
             }
+            InputEvent::Char(ch) => {
+                let Some((_, draft)) = &mut self.renaming_bookmark else {
+                    return false;
+                };
+                if !ch.is_control() {
+                    draft.push(*ch);
+                }
+                true
+            }
             InputEvent::MouseDown(point) => {
+                self.commit_bookmark_rename();
+                self.bookmark_focused = false;
                 if let Some(tab) = self.tab_hit_test(*point) {
-                    self.active_panel = tab;
-                    self.selected_index = 0;
+                    self.set_active_panel(tab);
                     return true;
                 }
                 if let Some(index) = self.item_index_at_point(*point) {
+                    if matches!(
+                        self.active_panel,
+                        SidebarPanel::Bookmarks | SidebarPanel::Outline
+                    ) {
+                        // The jump waits for the button to come up, so the row can be dragged.
+                        self.selected_index = index;
+                        self.bookmark_focused = self.active_panel == SidebarPanel::Bookmarks;
+                        self.row_drag = Some(RowDrag {
+                            from: index,
+                            over: index,
+                        });
+                        return true;
+                    }
                     self.selected_index = index;
                     self.pending_intent = self.intent_for_selected();
                     return self.pending_intent.is_some();
@@ -629,6 +871,21 @@ impl UIComponent for Sidebar {
                 self.hit_test(*point)
             }
             InputEvent::MouseMove(point) => {
+                if self.row_drag.is_some() {
+                    let row = ((point.y - self.panel_rect().y) / SIDEBAR_ITEM_HEIGHT)
+                        .floor()
+                        .max(0.0);
+                    let over = match self.active_panel {
+                        SidebarPanel::Outline => (row as usize).min(self.outline_items.len()),
+                        _ => (row as usize).min(self.bookmarks.len().saturating_sub(1)),
+                    };
+                    let Some(drag) = &mut self.row_drag else {
+                        return false;
+                    };
+                    let changed = drag.over != over;
+                    drag.over = over;
+                    return changed;
+                }
                 if self.active_panel == SidebarPanel::Files {
                     let hovered = self
                         .item_index_at_point(*point)
@@ -637,6 +894,27 @@ impl UIComponent for Sidebar {
                 }
                 self.hit_test(*point)
             }
+            InputEvent::MouseUp(_) => {
+                let Some(drag) = self.row_drag.take() else {
+                    return false;
+                };
+                if drag.over == drag.from {
+                    // The jump puts the caret in the document, so keys go there again.
+                    self.pending_intent = self.intent_for_selected();
+                    self.bookmark_focused = false;
+                } else if self.active_panel == SidebarPanel::Outline {
+                    if let Some(item) = self.outline_items.get(drag.from) {
+                        self.pending_intent = Some(SidebarIntent::MoveSection {
+                            heading: item.block_id,
+                            before: self.outline_items.get(drag.over).map(|it| it.block_id),
+                        });
+                    }
+                } else {
+                    self.move_bookmark(drag.from, drag.over);
+                    self.selected_index = drag.over;
+                }
+                true
+            }
             _ => false,
         }
     }
@@ -656,9 +934,17 @@ impl UIComponent for Sidebar {
 
 #[derive(Debug, Clone)]
 pub enum SidebarIntent {
-    OpenFile { path: PathBuf, new_tab: bool },
+    OpenFile {
+        path: PathBuf,
+        new_tab: bool,
+    },
     ToggleFolder(PathBuf),
     JumpToBlock(BlockId),
+    /// Move the section under `heading` in front of the heading `before`, or to the end.
+    MoveSection {
+        heading: BlockId,
+        before: Option<BlockId>,
+    },
 }
 
 fn build_tree(root: &Path, depth: usize) -> std::io::Result<Vec<FileNode>> {
@@ -731,14 +1017,6 @@ fn toggle_node_expanded(nodes: &mut [FileNode], path: &Path) -> bool {
     false
 }
 
-fn heading_level_from_style(style_id: &str) -> Option<u8> {
-    let lower = style_id.to_ascii_lowercase();
-    lower
-        .strip_prefix("heading")
-        .and_then(|n| n.parse::<u8>().ok())
-        .filter(|v| (1..=6).contains(v))
-}
-
 fn is_hidden(path: &Path) -> bool {
     path.file_name()
         .and_then(|s| s.to_str())
@@ -760,7 +1038,7 @@ fn map_notify(err: notify::Error) -> std::io::Error {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::document::model::{Block, Paragraph, Run};
+    use crate::document::model::{Block, Heading, Paragraph, Run};
 
     #[test]
     fn width_clamps_to_sidebar_limits() {
@@ -771,6 +1049,34 @@ mod tests {
         assert_eq!(sidebar.width, SIDEBAR_MAX_WIDTH);
     }
 
+    #[test]
+    fn saved_widths_clamp_to_the_splitter_range() {
+        assert_eq!(clamp_sidebar_width(0.0), 200.0);
+        assert_eq!(clamp_sidebar_width(312.0), 312.0);
+        assert_eq!(clamp_sidebar_width(1200.0), 400.0);
+    }
+
+    #[test]
+    fn the_last_panel_other_than_search_results_is_restored() {
+        let mut appearance = AppearanceSettings {
+            sidebar_default_panel: SidebarDefaultPanel::Bookmarks,
+            ..AppearanceSettings::default()
+        };
+        assert_eq!(SidebarPanel::restored(&appearance), SidebarPanel::Bookmarks);
+
+        appearance.sidebar_last_panel = SidebarPanel::Outline.remembered();
+        assert_eq!(SidebarPanel::restored(&appearance), SidebarPanel::Outline);
+
+        // Search results aren't remembered, so the panel before them comes back.
+        assert_eq!(SidebarPanel::SearchResults.remembered(), None);
+        for panel in SidebarPanel::all() {
+            if let Some(saved) = panel.remembered() {
+                appearance.sidebar_last_panel = Some(saved);
+                assert_eq!(SidebarPanel::restored(&appearance), panel);
+            }
+        }
+    }
+
     #[test]
     fn tab_hit_test_selects_expected_panel() {
         let mut sidebar = Sidebar::new();
@@ -862,6 +1168,203 @@ mod tests {
         assert!(sidebar.bookmarks.is_empty());
     }
 
+    fn bookmark_names(sidebar: &Sidebar) -> Vec<&str> {
+        sidebar.bookmarks.iter().map(|b| b.name.as_str()).collect()
+    }
+
+    #[test]
+    fn bookmarks_swap_per_document_and_restore_by_block_text() {
+        let paragraph = |id, text: &str| {
+            Block::Paragraph(Paragraph {
+                id: BlockId(id),
+                runs: vec![Run {
+                    text: text.to_string(),
+                    style: Default::default(),
+                }],
+                alignment: Default::default(),
+                spacing: Default::default(),
+                indent: Default::default(),
+                style_id: None,
+                direction: Default::default(),
+                frame: None,
+            })
+        };
+        let doc = [paragraph(1, "Intro"), paragraph(2, "Details")];
+        let mut sidebar = Sidebar::new();
+        sidebar.add_bookmark(BlockId(2), 1, "Details");
+        let saved = sidebar
+            .bookmarks
+            .iter()
+            .filter_map(|bookmark| bookmark.to_saved(&doc))
+            .collect::<Vec<_>>();
+
+        // Another tab becomes active; the first tab's list comes back out.
+        let first = sidebar.swap_bookmarks(Vec::new());
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].name, "Details");
+        assert!(sidebar.bookmarks.is_empty());
+
+        // Reopened with fresh block ids and a paragraph added above.
+        let reopened = [
+            paragraph(10, "New"),
+            paragraph(11, "Intro"),
+            paragraph(12, "Details"),
+        ];
+        let restored = restore_bookmarks(&reopened, &saved);
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].block_id, BlockId(12));
+        sidebar.swap_bookmarks(restored);
+        let added = sidebar.add_bookmark(BlockId(10), 1, "New");
+        assert_ne!(added, sidebar.bookmarks[0].id);
+    }
+
+    #[test]
+    fn bookmarks_reorder_and_delete() {
+        let mut sidebar = Sidebar::new();
+        let a = sidebar.add_bookmark(BlockId(1), 1, "a");
+        sidebar.add_bookmark(BlockId(2), 1, "b");
+        sidebar.add_bookmark(BlockId(3), 1, "c");
+
+        assert!(sidebar.move_bookmark(0, 2));
+        assert_eq!(bookmark_names(&sidebar), ["b", "c", "a"]);
+        assert!(sidebar.move_bookmark(2, 0));
+        assert_eq!(bookmark_names(&sidebar), ["a", "b", "c"]);
+        assert!(!sidebar.move_bookmark(1, 1));
+        assert!(!sidebar.move_bookmark(0, 3));
+
+        // Cycling follows list order and wraps; deleting the current one restarts it.
+        assert_eq!(
+            sidebar.cycle_bookmark(true).map(|b| b.block_id),
+            Some(BlockId(1))
+        );
+        assert_eq!(
+            sidebar.cycle_bookmark(false).map(|b| b.block_id),
+            Some(BlockId(3))
+        );
+        assert!(sidebar.delete_bookmark(sidebar.bookmarks[2].id));
+        assert!(!sidebar.delete_bookmark(99));
+        assert_eq!(bookmark_names(&sidebar), ["a", "b"]);
+        assert_eq!(
+            sidebar.cycle_bookmark(true).map(|b| b.block_id),
+            Some(BlockId(1))
+        );
+        assert!(matches!(
+            sidebar.take_intent(),
+            Some(SidebarIntent::JumpToBlock(BlockId(1)))
+        ));
+        assert!(sidebar.delete_bookmark(a));
+        assert!(sidebar.delete_bookmark(sidebar.bookmarks[0].id));
+        assert!(sidebar.cycle_bookmark(true).is_none());
+    }
+
+    #[test]
+    fn bookmark_rows_drag_rename_and_delete_with_mouse_and_keys() {
+        let mut sidebar = Sidebar::new();
+        sidebar.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 260.0,
+                height: 600.0,
+            },
+            96.0,
+        );
+        sidebar.set_active_panel(SidebarPanel::Bookmarks);
+        for (id, name) in [(1, "a"), (2, "b"), (3, "c")] {
+            sidebar.add_bookmark(BlockId(id), 1, name);
+        }
+        let row = |index: usize| Point {
+            x: 20.0,
+            y: 34.0 + index as f32 * SIDEBAR_ITEM_HEIGHT + 4.0,
+        };
+
+        // Dragging the first row onto the last reorders without jumping.
+        assert!(sidebar.handle_input(&InputEvent::MouseDown(row(0))));
+        assert!(sidebar.handle_input(&InputEvent::MouseMove(row(2))));
+        assert!(sidebar.handle_input(&InputEvent::MouseUp(row(2))));
+        assert_eq!(bookmark_names(&sidebar), ["b", "c", "a"]);
+        assert!(sidebar.take_intent().is_none());
+
+        // A plain click jumps.
+        sidebar.handle_input(&InputEvent::MouseDown(row(1)));
+        sidebar.handle_input(&InputEvent::MouseUp(row(1)));
+        assert!(matches!(
+            sidebar.take_intent(),
+            Some(SidebarIntent::JumpToBlock(BlockId(3)))
+        ));
+        assert!(!sidebar.wants_bookmark_keys());
+
+        assert!(sidebar.begin_rename_bookmark_at(row(1)));
+        assert!(sidebar.wants_bookmark_keys());
+        sidebar.handle_input(&InputEvent::KeyDown(0x08));
+        for ch in "Summary".chars() {
+            sidebar.handle_input(&InputEvent::Char(ch));
+        }
+        sidebar.handle_input(&InputEvent::KeyDown(0x0D));
+        assert_eq!(bookmark_names(&sidebar), ["b", "Summary", "a"]);
+
+        // Delete removes the focused row; a click elsewhere gives the keys back.
+        assert!(sidebar.handle_input(&InputEvent::KeyDown(0x2E)));
+        assert_eq!(bookmark_names(&sidebar), ["b", "a"]);
+        sidebar.handle_input(&InputEvent::MouseDown(Point { x: 600.0, y: 300.0 }));
+        assert!(!sidebar.wants_bookmark_keys());
+    }
+
+    #[test]
+    fn dragging_an_outline_heading_asks_to_move_its_section() {
+        let mut sidebar = Sidebar::new();
+        sidebar.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 260.0,
+                height: 600.0,
+            },
+            96.0,
+        );
+        sidebar.set_active_panel(SidebarPanel::Outline);
+        sidebar.outline_items = (1..=3)
+            .map(|id| OutlineItem {
+                block_id: BlockId(id),
+                title: format!("H{id}"),
+                level: 1,
+                collapsed: false,
+            })
+            .collect();
+        let row = |index: usize| Point {
+            x: 20.0,
+            y: 34.0 + index as f32 * SIDEBAR_ITEM_HEIGHT + 4.0,
+        };
+
+        sidebar.handle_input(&InputEvent::MouseDown(row(2)));
+        sidebar.handle_input(&InputEvent::MouseMove(row(0)));
+        assert_eq!(sidebar.panel_rows(10)[0], "=> H1");
+        sidebar.handle_input(&InputEvent::MouseUp(row(0)));
+        assert!(matches!(
+            sidebar.take_intent(),
+            Some(SidebarIntent::MoveSection {
+                heading: BlockId(3),
+                before: Some(BlockId(1)),
+            })
+        ));
+
+        // Below the last heading means the end of the document.
+        sidebar.handle_input(&InputEvent::MouseDown(row(0)));
+        sidebar.handle_input(&InputEvent::MouseMove(row(5)));
+        assert_eq!(
+            sidebar.panel_rows(10).last().map(String::as_str),
+            Some("=> (end of document)")
+        );
+        sidebar.handle_input(&InputEvent::MouseUp(row(5)));
+        assert!(matches!(
+            sidebar.take_intent(),
+            Some(SidebarIntent::MoveSection {
+                heading: BlockId(1),
+                before: None,
+            })
+        ));
+    }
+
     #[test]
     fn outline_populates_from_heading_and_heading_style() {
         let mut sidebar = Sidebar::new();
@@ -885,6 +1388,8 @@ mod tests {
                 spacing: Default::default(),
                 indent: Default::default(),
                 style_id: Some("Heading3".to_string()),
+                direction: Default::default(),
+                frame: None,
             }),
         ];
 
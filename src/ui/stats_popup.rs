@@ -0,0 +1,168 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    editor::stats::TextStats,
+    theme::Theme,
+    ui::{InputEvent, Point, Rect, UIComponent},
+};
+
+const POPUP_WIDTH: f32 = 300.0;
+const POPUP_PADDING: f32 = 10.0;
+const HEADER_HEIGHT: f32 = 24.0;
+const ROW_HEIGHT: f32 = 22.0;
+const GAP: f32 = 4.0;
+
+/// One line of the stats table: what is counted, then the document and selection figures.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsRow {
+    pub label: &'static str,
+    pub document: String,
+    pub selection: Option<String>,
+}
+
+/// Detailed counts opened from the status bar's word count, comparing the whole document
+/// with the current selection when there is one.
+#[derive(Debug, Default)]
+pub struct StatsPopup {
+    bounds: Rect,
+    visible: bool,
+    document: TextStats,
+    selection: Option<TextStats>,
+}
+
+impl StatsPopup {
+    /// Opens above `anchor` (the word count segment).
+    pub fn open(&mut self, anchor: Rect, document: TextStats, selection: Option<TextStats>) {
+        self.document = document;
+        self.selection = selection;
+        let height = POPUP_PADDING * 2.0 + HEADER_HEIGHT + self.rows().len() as f32 * ROW_HEIGHT;
+        self.bounds = Rect {
+            x: anchor.x.max(0.0),
+            y: (anchor.y - height - GAP).max(0.0),
+            width: POPUP_WIDTH,
+            height,
+        };
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    /// Column titles: the document column, then the selection column when there is one.
+    pub fn headers(&self) -> (&'static str, Option<&'static str>) {
+        ("Document", self.selection.map(|_| "Selection"))
+    }
+
+    pub fn rows(&self) -> Vec<StatsRow> {
+        let row = |label, value: fn(&TextStats) -> String| StatsRow {
+            label,
+            document: value(&self.document),
+            selection: self.selection.as_ref().map(value),
+        };
+        vec![
+            row("Words", |stats| stats.words.to_string()),
+            row("Characters", |stats| stats.characters.to_string()),
+            row("Lines", |stats| stats.lines.to_string()),
+            row("Paragraphs", |stats| stats.paragraphs.to_string()),
+            row("Reading time", |stats| {
+                reading_time_label(stats.reading_minutes())
+            }),
+        ]
+    }
+
+    /// Every piece of text in the table with its rect, and whether it is a header or row
+    /// label (drawn muted) rather than a figure.
+    pub fn cells(&self) -> Vec<(Rect, String, bool)> {
+        let inner = self.bounds.width - POPUP_PADDING * 2.0;
+        let left = self.bounds.x + POPUP_PADDING;
+        let columns = [
+            (left, inner * 0.42),
+            (left + inner * 0.42, inner * 0.29),
+            (left + inner * 0.71, inner * 0.29),
+        ];
+        let cell = |column: usize, y: f32, height: f32| Rect {
+            x: columns[column].0,
+            y,
+            width: columns[column].1,
+            height,
+        };
+
+        let top = self.bounds.y + POPUP_PADDING;
+        let (document, selection) = self.headers();
+        let mut out = vec![(cell(1, top, HEADER_HEIGHT), document.to_string(), true)];
+        if let Some(selection) = selection {
+            out.push((cell(2, top, HEADER_HEIGHT), selection.to_string(), true));
+        }
+        for (index, row) in self.rows().into_iter().enumerate() {
+            let y = top + HEADER_HEIGHT + index as f32 * ROW_HEIGHT;
+            out.push((cell(0, y, ROW_HEIGHT), row.label.to_string(), true));
+            out.push((cell(1, y, ROW_HEIGHT), row.document, false));
+            if let Some(selection) = row.selection {
+                out.push((cell(2, y, ROW_HEIGHT), selection, false));
+            }
+        }
+        out
+    }
+}
+
+fn reading_time_label(minutes: usize) -> String {
+    match minutes {
+        0 => "\u{2014}".to_string(),
+        1 => "1 min".to_string(),
+        _ => format!("{minutes} min"),
+    }
+}
+
+impl UIComponent for StatsPopup {
+    fn layout(&mut self, _bounds: Rect, _dpi: f32) {
+        // Positioned against the word count in `open`.
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // Drawn in host renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match event {
+            InputEvent::KeyDown(0x1B | 0x0D) => {
+                self.close();
+                true
+            }
+            InputEvent::MouseDown(point) => {
+                if !contains(self.bounds, *point) {
+                    self.close();
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && contains(self.bounds, point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+fn contains(rect: Rect, point: Point) -> bool {
+    point.x >= rect.x
+        && point.x <= rect.x + rect.width
+        && point.y >= rect.y
+        && point.y <= rect.y + rect.height
+}
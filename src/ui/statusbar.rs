@@ -7,11 +7,42 @@ use crate::{
 
 const STATUSBAR_HEIGHT: f32 = 28.0;
 const SEGMENT_PADDING: f32 = 12.0;
+/// Average advance of the status font; the left text is hit-tested without measuring it.
+const APPROX_CHAR_WIDTH: f32 = 7.0;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StatusAction {
     OpenZoomPopup,
+    OpenStatsPopup,
     ChangeEncoding,
+    ChangeLanguage,
+    EditAnyway,
+}
+
+/// What the status bar says about the active document's unsaved work.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveIndicator {
+    AllSaved,
+    /// Edits not yet written to the file. Autosave only keeps recovery snapshots, so these
+    /// stay until the document is saved.
+    Unsaved,
+}
+
+impl SaveIndicator {
+    pub fn new(unsaved: bool) -> Self {
+        if unsaved {
+            Self::Unsaved
+        } else {
+            Self::AllSaved
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::AllSaved => "All changes saved",
+            Self::Unsaved => "Unsaved changes",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -26,6 +57,11 @@ pub struct StatusBarInfo {
     pub zoom_percent: u16,
     pub file_format: String,
     pub encoding: String,
+    /// BCP 47 tag of the document's language.
+    pub language: String,
+    pub read_only: bool,
+    /// Hidden for tabs that aren't documents, like Welcome.
+    pub save_indicator: Option<SaveIndicator>,
 }
 
 impl Default for StatusBarInfo {
@@ -41,6 +77,9 @@ impl Default for StatusBarInfo {
             zoom_percent: 100,
             file_format: "DOCX".to_string(),
             encoding: "UTF-8".to_string(),
+            language: "en-US".to_string(),
+            read_only: false,
+            save_indicator: None,
         }
     }
 }
@@ -59,41 +98,100 @@ impl StatusBar {
     }
 
     pub fn left_text(&self) -> String {
+        let saved = self
+            .info
+            .save_indicator
+            .map(|indicator| format!(" | {}", indicator.label()))
+            .unwrap_or_default();
         format!(
-            "Page {} of {} | Words: {} | Chars: {}",
+            "Page {} of {} | Words: {} | Chars: {}{saved}",
             self.info.page_index, self.info.page_count, self.info.word_count, self.info.character_count
         )
     }
 
     pub fn right_text(&self) -> String {
+        let lock = if self.info.read_only {
+            "\u{1F512} Read-only | "
+        } else {
+            ""
+        };
         format!(
-            "{} | {}:{} | {}% | {} | {}",
+            "{lock}{} | {}:{} | {}% | {} | {} | {}",
             self.info.view_mode,
             self.info.line,
             self.info.column,
             self.info.zoom_percent,
             self.info.file_format,
-            self.info.encoding
+            self.info.encoding,
+            self.info.language
         )
     }
 
-    fn zoom_rect(&self) -> Rect {
+    /// The zoom percentage segment; the zoom popup anchors to it.
+    pub fn zoom_rect(&self) -> Rect {
         Rect {
-            x: self.bounds.x + self.bounds.width - 220.0,
+            x: self.bounds.x + self.bounds.width - 284.0,
             y: self.bounds.y,
             width: 62.0,
             height: self.bounds.height,
         }
     }
 
+    /// The page, word and character counts at the left end; clicking them opens the
+    /// statistics popup, which anchors here.
+    pub fn stats_rect(&self) -> Rect {
+        Rect {
+            x: self.bounds.x,
+            y: self.bounds.y,
+            width: 14.0 + self.left_text().chars().count() as f32 * APPROX_CHAR_WIDTH,
+            height: self.bounds.height,
+        }
+    }
+
+    /// The lock segment the right-hand text starts with for read-only tabs; clicking it
+    /// unlocks editing.
+    fn read_only_rect(&self) -> Rect {
+        Rect {
+            x: self.bounds.x + self.bounds.width - 484.0,
+            y: self.bounds.y,
+            width: 96.0,
+            height: self.bounds.height,
+        }
+    }
+
     fn encoding_rect(&self) -> Rect {
         Rect {
-            x: self.bounds.x + self.bounds.width - 82.0,
+            x: self.bounds.x + self.bounds.width - 146.0,
             y: self.bounds.y,
             width: 82.0,
             height: self.bounds.height,
         }
     }
+
+    /// The segments keyboard focus steps through from left to right, with the action Enter
+    /// takes on each.
+    pub fn focus_segments(&self) -> Vec<(Rect, StatusAction)> {
+        let mut segments = vec![(self.stats_rect(), StatusAction::OpenStatsPopup)];
+        if self.info.read_only {
+            segments.push((self.read_only_rect(), StatusAction::EditAnyway));
+        }
+        segments.extend([
+            (self.zoom_rect(), StatusAction::OpenZoomPopup),
+            (self.encoding_rect(), StatusAction::ChangeEncoding),
+            (self.language_rect(), StatusAction::ChangeLanguage),
+        ]);
+        segments
+    }
+
+    /// The document language segment at the right end; the language picker opens above it.
+    pub fn language_rect(&self) -> Rect {
+        Rect {
+            x: self.bounds.x + self.bounds.width - 64.0,
+            y: self.bounds.y,
+            width: 64.0,
+            height: self.bounds.height,
+        }
+    }
 }
 
 impl UIComponent for StatusBar {
@@ -125,6 +223,18 @@ impl UIComponent for StatusBar {
                     self.pending_action = Some(StatusAction::ChangeEncoding);
                     return true;
                 }
+                if contains(self.language_rect(), *point) {
+                    self.pending_action = Some(StatusAction::ChangeLanguage);
+                    return true;
+                }
+                if self.info.read_only && contains(self.read_only_rect(), *point) {
+                    self.pending_action = Some(StatusAction::EditAnyway);
+                    return true;
+                }
+                if contains(self.stats_rect(), *point) {
+                    self.pending_action = Some(StatusAction::OpenStatsPopup);
+                    return true;
+                }
                 false
             }
             _ => false,
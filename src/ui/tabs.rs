@@ -1,14 +1,18 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, time::SystemTime};
 
 use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
 
 use crate::{
-    document::model::DocumentModel,
-    editor::cursor::CursorState,
+    document::{
+        language::detected_language,
+        model::{BlockId, DocumentModel, Revision},
+        txt::view::TextView,
+    },
+    editor::{cursor::CursorState, image_ops::ImageReplacements},
     render::animation::{Animation, Easing},
     render::canvas::CanvasState,
     theme::Theme,
-    ui::{InputEvent, Point, Rect, UIComponent},
+    ui::{InputEvent, Point, Rect, UIComponent, sidebar::Bookmark},
 };
 
 const TAB_HEIGHT: f32 = 36.0;
@@ -18,12 +22,15 @@ const TAB_GAP: f32 = 6.0;
 const TAB_BAR_PADDING: f32 = 8.0;
 const NEW_TAB_BUTTON_WIDTH: f32 = 28.0;
 const OVERFLOW_BUTTON_WIDTH: f32 = 24.0;
+const TAB_LIST_BUTTON_WIDTH: f32 = 36.0;
 const TAB_SWITCH_ANIMATION_S: f32 = 0.15;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TabKind {
     Document,
     Welcome,
+    /// A document still being parsed in the background; not editable yet.
+    Loading,
 }
 
 #[derive(Debug, Clone)]
@@ -33,9 +40,27 @@ pub struct TabState {
     pub kind: TabKind,
     pub file_path: Option<PathBuf>,
     pub dirty: bool,
+    /// Refuses edits while set; turned on for files the OS marks read-only.
+    pub read_only: bool,
+    /// The file's modified time when Doco last loaded or saved it.
+    pub disk_modified: Option<SystemTime>,
     pub document: DocumentModel,
+    /// Set for text files too large for blocks; their text lives here and `document` only
+    /// carries the metadata.
+    pub text_view: Option<TextView>,
+    /// Headings and code blocks collapsed in this tab, remembered per file across sessions.
+    pub folded: Vec<BlockId>,
+    /// This tab's bookmarks while another tab is active; the sidebar holds the active tab's.
+    pub bookmarks: Vec<Bookmark>,
     pub cursor: CursorState,
     pub canvas: CanvasState,
+    /// Embedded pictures as they were before "optimize images on save" replaced them, kept
+    /// so this session can put them back.
+    pub original_images: ImageReplacements,
+    /// Set once the originals are restored; later saves in this session leave images alone.
+    pub keep_original_images: bool,
+    /// Language detected from the text and the document revision it was detected at.
+    detected_language: Option<(Revision, &'static str)>,
 }
 
 impl TabState {
@@ -46,9 +71,17 @@ impl TabState {
             kind: TabKind::Document,
             file_path,
             dirty: document.dirty,
+            read_only: false,
+            disk_modified: None,
             document,
+            text_view: None,
+            folded: Vec::new(),
+            bookmarks: Vec::new(),
             cursor: CursorState::default(),
             canvas: CanvasState::default(),
+            original_images: ImageReplacements::default(),
+            keep_original_images: false,
+            detected_language: None,
         }
     }
 
@@ -59,11 +92,128 @@ impl TabState {
             kind: TabKind::Welcome,
             file_path: None,
             dirty: false,
+            read_only: false,
+            disk_modified: None,
             document: DocumentModel::default(),
+            text_view: None,
+            folded: Vec::new(),
+            bookmarks: Vec::new(),
             cursor: CursorState::default(),
             canvas: CanvasState::default(),
+            original_images: ImageReplacements::default(),
+            keep_original_images: false,
+            detected_language: None,
         }
     }
+
+    /// The document's language as `document_language` gives it, only detecting it from the
+    /// text again once the document has changed.
+    pub fn language(&mut self) -> &str {
+        if let Some(language) = &self.document.metadata.language
+            && !language.is_empty()
+        {
+            return language;
+        }
+        let revision = self.document.revision;
+        match self.detected_language {
+            Some((at, language)) if at == revision => language,
+            _ => {
+                let language = detected_language(&self.document);
+                self.detected_language = Some((revision, language));
+                language
+            }
+        }
+    }
+
+    pub fn has_unsaved_changes(&self) -> bool {
+        self.dirty || self.document.dirty || self.text_view.as_ref().is_some_and(|view| view.dirty)
+    }
+
+    /// Ok when edits may change this tab, or the status to show when they may not.
+    pub fn edit_guard(&self) -> Result<(), &'static str> {
+        if self.read_only {
+            Err("Read-only. Use Edit Anyway to make changes")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Which tabs a bulk close command goes after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkClose {
+    All,
+    /// Only tabs without unsaved changes, so nothing prompts.
+    Saved,
+}
+
+/// How one tab fared during Save All.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SaveOutcome {
+    Saved,
+    /// Not saved without asking, e.g. still loading or its file is read-only.
+    Skipped,
+    /// The user dismissed the save dialog for an untitled tab.
+    Cancelled,
+    Failed,
+}
+
+/// Tally of a Save All run over the tabs with unsaved changes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SaveAllSummary {
+    pub attempted: usize,
+    pub saved: usize,
+    pub skipped: usize,
+    pub cancelled: usize,
+    pub failed: usize,
+}
+
+impl SaveAllSummary {
+    pub fn record(&mut self, outcome: SaveOutcome) {
+        self.attempted += 1;
+        match outcome {
+            SaveOutcome::Saved => self.saved += 1,
+            SaveOutcome::Skipped => self.skipped += 1,
+            SaveOutcome::Cancelled => self.cancelled += 1,
+            SaveOutcome::Failed => self.failed += 1,
+        }
+    }
+
+    /// Status line for the run, e.g. "Saved 3 of 4; 1 cancelled".
+    pub fn message(&self) -> String {
+        if self.attempted == 0 {
+            return "No unsaved documents".to_string();
+        }
+        let mut message = format!("Saved {} of {}", self.saved, self.attempted);
+        for (count, what) in [
+            (self.cancelled, "cancelled"),
+            (self.skipped, "skipped"),
+            (self.failed, "failed"),
+        ] {
+            if count > 0 {
+                message.push_str(&format!("; {count} {what}"));
+            }
+        }
+        message
+    }
+}
+
+/// One line of the tab list opened from the tab strip's chevron when not every tab fits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TabListEntry {
+    pub index: usize,
+    pub title: String,
+    pub dirty: bool,
+    pub active: bool,
+}
+
+/// Runs `save` on each tab index in turn and tallies what happened.
+pub fn save_all(indices: &[usize], mut save: impl FnMut(usize) -> SaveOutcome) -> SaveAllSummary {
+    let mut summary = SaveAllSummary::default();
+    for &index in indices {
+        summary.record(save(index));
+    }
+    summary
 }
 
 #[derive(Debug, Clone)]
@@ -144,8 +294,12 @@ pub struct TabsBar {
     pub new_tab_rect: Rect,
     pub overflow_left_rect: Rect,
     pub overflow_right_rect: Rect,
+    /// Chevron after the overflow arrows that lists every tab; empty while all tabs fit.
+    pub tab_list_rect: Rect,
     pub hovered: Option<usize>,
     dragging_tab: Option<usize>,
+    /// A tab dropped outside the strip, waiting for the window to move it elsewhere.
+    pending_detach: Option<usize>,
     transition: Option<TabTransition>,
     pub reduce_motion: bool,
     next_id: u64,
@@ -171,8 +325,10 @@ impl TabsBar {
             new_tab_rect: Rect::default(),
             overflow_left_rect: Rect::default(),
             overflow_right_rect: Rect::default(),
+            tab_list_rect: Rect::default(),
             hovered: None,
             dragging_tab: None,
+            pending_detach: None,
             transition: None,
             reduce_motion: false,
             next_id: 1,
@@ -229,15 +385,74 @@ impl TabsBar {
         self.active
     }
 
+    /// Document tabs with unsaved changes, in strip order; the ones Save All visits.
+    pub fn dirty_document_tabs(&self) -> Vec<usize> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .filter(|(_, tab)| tab.kind == TabKind::Document && tab.has_unsaved_changes())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// Opens a copy of the tab at `index` right after it and makes it active. The copy owns
+    /// its own document and has no file yet, so edits to either leave the other alone.
+    pub fn duplicate_tab(&mut self, index: usize) -> Option<usize> {
+        let source = self
+            .tabs
+            .get(index)
+            .filter(|tab| tab.kind == TabKind::Document)?;
+        let mut document = source.document.clone();
+        document.metadata.file_path = None;
+        document.mark_edited();
+        let mut copy = TabState::from_document(
+            self.next_id,
+            format!("Copy of {}", source.title),
+            None,
+            document,
+        );
+        copy.text_view = source.text_view.clone();
+        copy.folded = source.folded.clone();
+        copy.cursor = source.cursor.clone();
+        copy.canvas = source.canvas.clone();
+        self.next_id += 1;
+
+        let previous_active = self.active;
+        self.tabs.insert(index + 1, copy);
+        self.active = index + 1;
+        self.start_switch_transition(previous_active, self.active);
+        self.ensure_active_visible();
+        self.recalc_tab_layout();
+        Some(self.active)
+    }
+
+    /// Indices of the tabs `scope` closes, last first so closing one leaves the indices of
+    /// the rest unchanged. The Welcome tab is left alone; it comes back on its own anyway.
+    pub fn bulk_close_targets(&self, scope: BulkClose) -> Vec<usize> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, tab)| tab.kind != TabKind::Welcome)
+            .filter(|(_, tab)| scope == BulkClose::All || !tab.has_unsaved_changes())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
     pub fn close_active_tab(&mut self) -> bool {
         self.close_tab(self.active)
     }
 
     pub fn close_tab(&mut self, index: usize) -> bool {
+        self.detach_tab(index).is_some()
+    }
+
+    /// Removes a tab and hands it back whole, so another window can adopt it.
+    pub fn detach_tab(&mut self, index: usize) -> Option<TabState> {
         if index >= self.tabs.len() {
-            return false;
+            return None;
         }
-        self.tabs.remove(index);
+        let tab = self.tabs.remove(index);
 
         if self.tabs.is_empty() {
             self.ensure_welcome_tab();
@@ -251,7 +466,31 @@ impl TabsBar {
 
         self.ensure_active_visible();
         self.recalc_tab_layout();
-        true
+        Some(tab)
+    }
+
+    /// Adopts a tab detached from another window and makes it active.
+    /// The tab gets a fresh id, since ids are only unique within one strip.
+    pub fn attach_tab(&mut self, mut tab: TabState) -> usize {
+        let previous_active = self.active;
+        tab.id = self.next_id;
+        self.next_id += 1;
+        self.tabs.push(tab);
+        self.active = self.tabs.len() - 1;
+        self.start_switch_transition(previous_active, self.active);
+        self.remove_welcome_if_needed();
+        self.ensure_active_visible();
+        self.recalc_tab_layout();
+        self.active
+    }
+
+    pub fn is_dragging_tab(&self) -> bool {
+        self.dragging_tab.is_some()
+    }
+
+    /// The tab the user dragged out of the strip, if any; cleared once read.
+    pub fn take_pending_detach(&mut self) -> Option<usize> {
+        self.pending_detach.take()
     }
 
     pub fn close_tabs_to_right(&mut self, index: usize) {
@@ -380,6 +619,24 @@ impl TabsBar {
         self.overflow_right_rect.width > 0.0 && contains(self.overflow_right_rect, point)
     }
 
+    pub fn tab_list_hit_test(&self, point: Point) -> bool {
+        self.tab_list_rect.width > 0.0 && contains(self.tab_list_rect, point)
+    }
+
+    /// Every tab in strip order, labelled by `title`, including those scrolled out of view.
+    pub fn tab_list(&self, title: impl Fn(&TabState) -> String) -> Vec<TabListEntry> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(index, tab)| TabListEntry {
+                index,
+                title: title(tab),
+                dirty: tab.has_unsaved_changes(),
+                active: index == self.active,
+            })
+            .collect()
+    }
+
     pub fn is_tab_bar_hit(&self, point: Point) -> bool {
         self.visible && contains(self.bounds, point)
     }
@@ -391,6 +648,7 @@ impl TabsBar {
             && !self.new_button_hit_test(point)
             && !self.overflow_left_hit_test(point)
             && !self.overflow_right_hit_test(point)
+            && !self.tab_list_hit_test(point)
     }
 
     pub fn scroll_overflow_left(&mut self) -> bool {
@@ -412,6 +670,26 @@ impl TabsBar {
         true
     }
 
+    /// Whether the wheel turned at `point` scrolls the tab strip rather than the document:
+    /// over the strip while some tabs don't fit.
+    pub fn wheel_scrolls_tabs(&self, point: Point) -> bool {
+        self.is_tab_bar_hit(point) && self.overflow_right_rect.width > 0.0
+    }
+
+    /// Scrolls the strip one tab per wheel notch in `delta`, towards the first tab when the
+    /// wheel rolls away from you.
+    pub fn scroll_overflow_by_wheel(&mut self, delta: f32) -> bool {
+        let mut scrolled = false;
+        for _ in 0..(delta.abs().round() as usize).max(1) {
+            scrolled |= if delta > 0.0 {
+                self.scroll_overflow_left()
+            } else {
+                self.scroll_overflow_right()
+            };
+        }
+        scrolled
+    }
+
     pub fn middle_click_close(&mut self, point: Point) -> bool {
         if let Some(index) = self.tab_hit_test(point) {
             return self.close_tab(index);
@@ -489,6 +767,7 @@ impl TabsBar {
         self.new_tab_rect = Rect::default();
         self.overflow_left_rect = Rect::default();
         self.overflow_right_rect = Rect::default();
+        self.tab_list_rect = Rect::default();
 
         if self.bounds.width <= 0.0 {
             return;
@@ -524,7 +803,13 @@ impl TabsBar {
                 width: OVERFLOW_BUTTON_WIDTH,
                 height: TAB_HEIGHT - 12.0,
             };
-            tabs_left = self.overflow_right_rect.x + self.overflow_right_rect.width + TAB_GAP;
+            self.tab_list_rect = Rect {
+                x: self.overflow_right_rect.x + OVERFLOW_BUTTON_WIDTH + 4.0,
+                y: self.bounds.y + 6.0,
+                width: TAB_LIST_BUTTON_WIDTH,
+                height: TAB_HEIGHT - 12.0,
+            };
+            tabs_left = self.tab_list_rect.x + self.tab_list_rect.width + TAB_GAP;
         } else {
             self.overflow_offset = 0;
         }
@@ -620,8 +905,11 @@ impl UIComponent for TabsBar {
                 }
                 false
             }
-            InputEvent::MouseUp(_) => {
-                if self.dragging_tab.take().is_some() {
+            InputEvent::MouseUp(point) => {
+                if let Some(index) = self.dragging_tab.take() {
+                    if !self.is_tab_bar_hit(*point) {
+                        self.pending_detach = Some(index);
+                    }
                     true
                 } else {
                     false
@@ -679,6 +967,253 @@ mod tests {
         }
     }
 
+    #[test]
+    fn the_wheel_scrolls_the_strip_only_over_overflowing_tabs() {
+        let mut tabs = TabsBar::new();
+        for _ in 0..3 {
+            tabs.new_blank_tab();
+        }
+        let bounds = Rect {
+            x: 0.0,
+            y: 0.0,
+            width: 640.0,
+            height: TAB_HEIGHT,
+        };
+        tabs.layout(bounds, 96.0);
+        let over_tabs = Point { x: 320.0, y: 12.0 };
+        let over_canvas = Point { x: 320.0, y: 200.0 };
+
+        // Everything fits, so the wheel keeps scrolling the document.
+        assert!(!tabs.wheel_scrolls_tabs(over_tabs));
+
+        for _ in 0..21 {
+            tabs.new_blank_tab();
+        }
+        tabs.set_active(0);
+        tabs.layout(bounds, 96.0);
+        assert!(tabs.wheel_scrolls_tabs(over_tabs));
+        assert!(tabs.wheel_scrolls_tabs(Point { x: 4.0, y: 2.0 }));
+        assert!(!tabs.wheel_scrolls_tabs(over_canvas));
+
+        // Rolling towards you moves on through the tabs, away from you back to the first.
+        assert_eq!(tabs.overflow_offset, 0);
+        assert!(tabs.scroll_overflow_by_wheel(-2.0));
+        assert_eq!(tabs.overflow_offset, 2);
+        assert!(tabs.scroll_overflow_by_wheel(1.0));
+        assert_eq!(tabs.overflow_offset, 1);
+        assert!(tabs.scroll_overflow_by_wheel(3.0));
+        assert_eq!(tabs.overflow_offset, 0);
+        assert!(!tabs.scroll_overflow_by_wheel(1.0));
+    }
+
+    #[test]
+    fn tab_list_covers_every_tab_with_active_and_dirty_flags() {
+        let mut tabs = TabsBar::new();
+        for _ in 0..24 {
+            tabs.new_blank_tab();
+        }
+        tabs.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 640.0,
+                height: TAB_HEIGHT,
+            },
+            96.0,
+        );
+        tabs.tabs[3].dirty = true;
+        tabs.tabs[20].document.dirty = true;
+        tabs.set_active(17);
+
+        // Far more tabs than fit, so the strip shows the chevron beside the arrows.
+        assert!(tabs.tab_rects.len() < tabs.tabs.len());
+        let chevron = tabs.tab_list_rect;
+        assert!(chevron.x > tabs.overflow_right_rect.x);
+        assert!(tabs.tab_list_hit_test(Point {
+            x: chevron.x + chevron.width / 2.0,
+            y: chevron.y + chevron.height / 2.0,
+        }));
+        assert!(
+            tabs.tab_rects
+                .iter()
+                .all(|rect| rect.x > chevron.x + chevron.width)
+        );
+
+        let list = tabs.tab_list(|tab| tab.title.clone());
+        assert_eq!(list.len(), 24);
+        assert_eq!(
+            list.iter().map(|entry| entry.index).collect::<Vec<_>>(),
+            (0..24).collect::<Vec<_>>()
+        );
+        let active = list.iter().filter(|entry| entry.active).collect::<Vec<_>>();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].index, 17);
+        assert_eq!(active[0].title, tabs.tabs[17].title);
+        let dirty = list
+            .iter()
+            .filter(|entry| entry.dirty)
+            .map(|entry| entry.index)
+            .collect::<Vec<_>>();
+        assert_eq!(dirty, vec![3, 20]);
+
+        // With room for everything the chevron goes away.
+        tabs.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 8000.0,
+                height: TAB_HEIGHT,
+            },
+            96.0,
+        );
+        assert_eq!(tabs.tab_list_rect.width, 0.0);
+    }
+
+    #[test]
+    fn read_only_tabs_refuse_edits_until_unlocked() {
+        let mut tabs = TabsBar::new();
+        let index = tabs.new_blank_tab();
+        let tab = &mut tabs.tabs[index];
+        assert_eq!(tab.edit_guard(), Ok(()));
+
+        tab.read_only = true;
+        assert!(tab.edit_guard().is_err());
+
+        tab.read_only = false;
+        assert_eq!(tab.edit_guard(), Ok(()));
+    }
+
+    #[test]
+    fn detected_language_is_kept_until_the_document_changes() {
+        use crate::document::txt::TextDocument;
+
+        let model = |text: &str| TextDocument::from_text(text).to_document_model();
+        let french = model("Le chat est dans la maison et les enfants jouent.");
+        let mut tab = TabState::from_document(1, "Notes".to_string(), None, french);
+        assert_eq!(tab.language(), "fr-FR");
+
+        tab.document.content = model("The cat sat on the mat and it was happy.").content;
+        assert_eq!(tab.language(), "fr-FR");
+        tab.document.mark_edited();
+        assert_eq!(tab.language(), "en-US");
+
+        tab.document.metadata.language = Some("de-DE".to_string());
+        assert_eq!(tab.language(), "de-DE");
+    }
+
+    #[test]
+    fn save_all_visits_dirty_documents_and_counts_outcomes() {
+        let mut tabs = TabsBar::new();
+        for _ in 0..5 {
+            tabs.new_blank_tab();
+        }
+        for index in [0, 3, 4] {
+            tabs.tabs[index].dirty = true;
+        }
+        // Edits recorded only on the document count too.
+        tabs.tabs[1].document.dirty = true;
+        tabs.tabs[4].kind = TabKind::Loading;
+        let dirty = tabs.dirty_document_tabs();
+        assert_eq!(dirty, vec![0, 1, 3]);
+
+        let mut visited = Vec::new();
+        let summary = save_all(&[0, 1, 2, 3], |index| {
+            visited.push(index);
+            match index {
+                2 => SaveOutcome::Cancelled,
+                _ => SaveOutcome::Saved,
+            }
+        });
+        assert_eq!(visited, vec![0, 1, 2, 3]);
+        assert_eq!((summary.saved, summary.cancelled), (3, 1));
+        assert_eq!(summary.message(), "Saved 3 of 4; 1 cancelled");
+    }
+
+    #[test]
+    fn save_all_summary_reports_skips_and_failures() {
+        let outcomes = [
+            SaveOutcome::Skipped,
+            SaveOutcome::Failed,
+            SaveOutcome::Saved,
+        ];
+        let summary = save_all(&[0, 1, 2], |index| outcomes[index]);
+        assert_eq!(
+            summary,
+            SaveAllSummary {
+                attempted: 3,
+                saved: 1,
+                skipped: 1,
+                cancelled: 0,
+                failed: 1,
+            }
+        );
+        assert_eq!(summary.message(), "Saved 1 of 3; 1 skipped; 1 failed");
+        assert_eq!(
+            save_all(&[], |_| SaveOutcome::Saved).message(),
+            "No unsaved documents"
+        );
+    }
+
+    #[test]
+    fn bulk_close_targets_pick_tabs_last_first() {
+        let mut tabs = TabsBar::new();
+        for _ in 0..4 {
+            tabs.new_blank_tab();
+        }
+        tabs.tabs[1].dirty = true;
+        tabs.tabs[3].document.dirty = true;
+        tabs.tabs[2].kind = TabKind::Loading;
+
+        assert_eq!(tabs.bulk_close_targets(BulkClose::All), vec![3, 2, 1, 0]);
+        assert_eq!(tabs.bulk_close_targets(BulkClose::Saved), vec![2, 0]);
+
+        let mut welcome_only = TabsBar::new();
+        assert!(welcome_only.bulk_close_targets(BulkClose::All).is_empty());
+        welcome_only.new_blank_tab();
+        assert_eq!(welcome_only.bulk_close_targets(BulkClose::Saved), vec![0]);
+    }
+
+    #[test]
+    fn closing_every_target_falls_back_to_welcome() {
+        let mut tabs = TabsBar::new();
+        for _ in 0..3 {
+            tabs.new_blank_tab();
+        }
+        for index in tabs.bulk_close_targets(BulkClose::All) {
+            assert!(tabs.close_tab(index));
+        }
+        assert_eq!(tabs.tabs.len(), 1);
+        assert_eq!(tabs.tabs[0].kind, TabKind::Welcome);
+        assert_eq!(tabs.active, 0);
+    }
+
+    #[test]
+    fn duplicated_tabs_own_their_document() {
+        let mut tabs = TabsBar::new();
+        let index = tabs.new_blank_tab();
+        tabs.tabs[index].title = "Notes".to_string();
+        tabs.tabs[index].file_path = Some(PathBuf::from("notes.md"));
+        tabs.tabs[index].document.metadata.title = "Original".to_string();
+
+        let copy = tabs.duplicate_tab(index).expect("duplicate");
+        assert_eq!(copy, index + 1);
+        assert_eq!(tabs.active, copy);
+        assert_eq!(tabs.tabs[copy].title, "Copy of Notes");
+        assert!(tabs.tabs[copy].file_path.is_none());
+        assert!(tabs.tabs[copy].has_unsaved_changes());
+        assert_ne!(tabs.tabs[copy].id, tabs.tabs[index].id);
+
+        let copy_document = &mut tabs.tabs[copy].document;
+        copy_document.metadata.title = "Changed".to_string();
+        copy_document
+            .content
+            .push(crate::document::model::Block::HorizontalRule);
+        assert_eq!(tabs.tabs[index].document.metadata.title, "Original");
+        assert!(tabs.tabs[index].document.content.is_empty());
+        assert_eq!(tabs.tabs[copy].document.content.len(), 1);
+        assert!(!tabs.tabs[index].has_unsaved_changes());
+    }
+
     #[test]
     fn drag_reorders_tabs() {
         let mut tabs = TabsBar::new();
@@ -713,6 +1248,78 @@ mod tests {
         assert!(tabs.tabs.iter().any(|tab| tab.id == first));
     }
 
+    #[test]
+    fn dropping_a_tab_outside_the_strip_requests_a_detach() {
+        let mut tabs = TabsBar::new();
+        tabs.new_blank_tab();
+        tabs.new_blank_tab();
+        tabs.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 820.0,
+                height: TAB_HEIGHT,
+            },
+            96.0,
+        );
+
+        let p0 = Point {
+            x: tabs.tab_rects[0].x + 12.0,
+            y: tabs.tab_rects[0].y + 12.0,
+        };
+        let outside = Point {
+            x: p0.x,
+            y: TAB_HEIGHT * 4.0,
+        };
+
+        let _ = tabs.handle_input(&InputEvent::MouseDown(p0));
+        let _ = tabs.handle_input(&InputEvent::MouseUp(p0));
+        assert_eq!(tabs.take_pending_detach(), None);
+
+        let _ = tabs.handle_input(&InputEvent::MouseDown(p0));
+        let _ = tabs.handle_input(&InputEvent::MouseUp(outside));
+        assert_eq!(tabs.take_pending_detach(), Some(0));
+        assert_eq!(tabs.take_pending_detach(), None);
+    }
+
+    #[test]
+    fn detached_tabs_keep_document_and_dirty_state_when_attached() {
+        let mut source = TabsBar::new();
+        source.new_blank_tab();
+        let index = source.new_blank_tab();
+        {
+            let tab = &mut source.tabs[index];
+            tab.title = "Notes".to_string();
+            tab.file_path = Some(PathBuf::from("notes.docx"));
+            tab.document.metadata.title = "Quarterly notes".to_string();
+            tab.document.dirty = true;
+            tab.dirty = true;
+            tab.cursor.primary.offset = 7;
+        }
+
+        let tab = source.detach_tab(index).expect("tab detaches");
+        assert_eq!(source.tabs.len(), 1);
+        assert!(source.active < source.tabs.len());
+
+        let mut target = TabsBar::new();
+        let attached = target.attach_tab(tab);
+        assert_eq!(target.active, attached);
+        assert_eq!(target.tabs.len(), 1, "the welcome tab makes way");
+
+        let tab = &target.tabs[attached];
+        assert_eq!(tab.title, "Notes");
+        assert_eq!(tab.file_path, Some(PathBuf::from("notes.docx")));
+        assert_eq!(tab.document.metadata.title, "Quarterly notes");
+        assert!(tab.dirty && tab.document.dirty);
+        assert_eq!(tab.cursor.primary.offset, 7);
+
+        let back = target.detach_tab(attached).expect("tab detaches again");
+        assert_eq!(target.tabs[0].kind, TabKind::Welcome);
+        let docked = source.attach_tab(back);
+        assert!(source.tabs[docked].dirty);
+        assert_ne!(source.tabs[docked].id, source.tabs[0].id);
+    }
+
     #[test]
     fn switching_tabs_starts_transition() {
         let mut tabs = TabsBar::new();
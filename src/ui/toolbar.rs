@@ -3,6 +3,7 @@ use std::time::{Duration, Instant};
 use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
 
 use crate::{
+    editor::commands::parse_font_size,
     render::animation::{Animation, Easing},
     theme::Theme,
     ui::{Color, InputEvent, Point, Rect, UIComponent},
@@ -16,6 +17,15 @@ const TOOLTIP_DELAY: Duration = Duration::from_millis(500);
 const QUICK_ANIMATION: Duration = Duration::from_millis(100);
 const DROPDOWN_ANIMATION_S: f32 = 0.10;
 const DEFAULT_OVERFLOW_PANEL_WIDTH: f32 = 220.0;
+const DROPDOWN_PANEL_PADDING: f32 = 8.0;
+const SWATCH_SIZE: f32 = 20.0;
+const SWATCH_GAP: f32 = 4.0;
+const RECENT_COLOR_SLOTS: usize = 10;
+const DROPDOWN_ROW_HEIGHT: f32 = 24.0;
+const DROPDOWN_VISIBLE_ROWS: usize = 10;
+
+/// Layout id that stands for a separator; any number of them may appear in a layout.
+pub const TOOLBAR_SEPARATOR_ID: &str = "sep";
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ToolbarButtonType {
@@ -250,6 +260,7 @@ impl FontPickerState {
 pub struct SizePickerState {
     pub common_sizes: Vec<f32>,
     pub custom_input: Option<f32>,
+    pub custom_text: String,
 }
 
 impl Default for SizePickerState {
@@ -257,7 +268,31 @@ impl Default for SizePickerState {
         Self {
             common_sizes: vec![8.0, 9.0, 10.0, 11.0, 12.0, 14.0, 16.0, 18.0, 20.0, 24.0, 28.0, 32.0, 36.0, 48.0, 72.0],
             custom_input: None,
+            custom_text: String::new(),
+        }
+    }
+}
+
+impl SizePickerState {
+    /// Accepts digits and a single decimal point; anything else is ignored.
+    pub fn push_char(&mut self, ch: char) -> bool {
+        let accepted = ch.is_ascii_digit() || (ch == '.' && !self.custom_text.contains('.'));
+        if accepted && self.custom_text.len() < 6 {
+            self.custom_text.push(ch);
+            self.custom_input = parse_font_size(&self.custom_text);
+            return true;
         }
+        false
+    }
+
+    pub fn pop_char(&mut self) {
+        self.custom_text.pop();
+        self.custom_input = parse_font_size(&self.custom_text);
+    }
+
+    pub fn clear_custom(&mut self) {
+        self.custom_text.clear();
+        self.custom_input = None;
     }
 }
 
@@ -284,11 +319,41 @@ impl ColorPickerState {
     pub fn remember_color(&mut self, color: Color) {
         self.recent_colors.retain(|c| *c != color);
         self.recent_colors.insert(0, color);
-        if self.recent_colors.len() > 10 {
-            self.recent_colors.truncate(10);
+        if self.recent_colors.len() > RECENT_COLOR_SLOTS {
+            self.recent_colors.truncate(RECENT_COLOR_SLOTS);
         }
         self.selected = Some(color);
     }
+
+    /// Recent colors first, then the theme palette; duplicates are dropped.
+    pub fn swatches(&self) -> Vec<Color> {
+        let mut out = self.recent_colors.clone();
+        for color in &self.theme_colors {
+            if !out.contains(color) {
+                out.push(*color);
+            }
+        }
+        out
+    }
+
+    pub fn swatch_rects(&self, panel: Rect) -> Vec<Rect> {
+        let columns = self.columns.max(1);
+        (0..self.swatches().len())
+            .map(|idx| Rect {
+                x: panel.x + DROPDOWN_PANEL_PADDING + (idx % columns) as f32 * (SWATCH_SIZE + SWATCH_GAP),
+                y: panel.y + DROPDOWN_PANEL_PADDING + (idx / columns) as f32 * (SWATCH_SIZE + SWATCH_GAP),
+                width: SWATCH_SIZE,
+                height: SWATCH_SIZE,
+            })
+            .collect()
+    }
+
+    fn panel_size(&self) -> (f32, f32) {
+        let columns = self.columns.max(1);
+        let rows = self.swatches().len().div_ceil(columns).max(1);
+        let extent = |n: usize| DROPDOWN_PANEL_PADDING * 2.0 + n as f32 * SWATCH_SIZE + (n - 1) as f32 * SWATCH_GAP;
+        (extent(columns), extent(rows))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -332,6 +397,7 @@ pub struct ToolbarDropdownState {
     pub opacity: f32,
     pub scale: f32,
     pub panel_width: f32,
+    pub list_scroll: usize,
     pub font_picker: FontPickerState,
     pub size_picker: SizePickerState,
     pub color_picker: ColorPickerState,
@@ -349,6 +415,7 @@ impl Default for ToolbarDropdownState {
             opacity: 0.0,
             scale: 0.95,
             panel_width: DEFAULT_OVERFLOW_PANEL_WIDTH,
+            list_scroll: 0,
             font_picker: FontPickerState::default(),
             size_picker: SizePickerState::default(),
             color_picker: ColorPickerState::default(),
@@ -364,6 +431,9 @@ impl ToolbarDropdownState {
     pub fn open(&mut self, kind: ToolbarDropdownKind, anchor: Rect, reduce_motion: bool) {
         self.open = Some(kind);
         self.anchor = Some(anchor);
+        self.list_scroll = 0;
+        self.font_picker.query.clear();
+        self.size_picker.clear_custom();
         if reduce_motion {
             self.opacity = 1.0;
             self.scale = 1.0;
@@ -432,12 +502,20 @@ pub enum ToolbarIntent {
     OpenDropdown(ToolbarDropdownKind),
 }
 
+#[derive(Debug, Clone, PartialEq)]
+pub enum DropdownSelection {
+    TextColor(Color),
+    FontFamily(String),
+    FontSize(f32),
+}
+
 #[derive(Debug, Clone)]
 pub struct Toolbar {
     bounds: Rect,
     visible: bool,
     reduce_motion: bool,
     all_buttons: Vec<ToolbarButton>,
+    layout_source: Option<Vec<String>>,
     pub buttons: Vec<ToolbarButton>,
     pub overflow: Vec<ToolbarButton>,
     button_rects: Vec<Rect>,
@@ -448,6 +526,7 @@ pub struct Toolbar {
     pub show_tooltip: bool,
     pub tooltip_text: Option<String>,
     pub pending_intent: Option<ToolbarIntent>,
+    pub pending_selection: Option<DropdownSelection>,
     pub dropdown: ToolbarDropdownState,
     pub format_state: ToolbarFormatState,
 }
@@ -466,6 +545,7 @@ impl Toolbar {
             visible: true,
             reduce_motion: false,
             all_buttons: all_buttons.clone(),
+            layout_source: None,
             buttons: all_buttons,
             overflow: Vec::new(),
             button_rects: Vec::new(),
@@ -476,6 +556,7 @@ impl Toolbar {
             show_tooltip: false,
             tooltip_text: None,
             pending_intent: None,
+            pending_selection: None,
             dropdown: ToolbarDropdownState::default(),
             format_state: ToolbarFormatState {
                 font_family: "Segoe UI".to_string(),
@@ -488,59 +569,31 @@ impl Toolbar {
     }
 
     pub fn recalc_overflow(&mut self, available_width: f32) {
-        let more_button = self
-            .all_buttons
-            .iter()
-            .find(|b| b.id == "more")
-            .cloned()
-            .unwrap_or_else(default_more_button);
-
-        let mut core_buttons = self
-            .all_buttons
-            .iter()
-            .filter(|b| b.id != "more")
-            .cloned()
-            .collect::<Vec<_>>();
-        trim_separators(&mut core_buttons);
-
-        let horizontal_padding = TOOLBAR_PADDING_X * 2.0;
-        if available_width <= horizontal_padding + button_width(&more_button) {
-            self.buttons = vec![more_button];
-            self.overflow = core_buttons;
-            trim_separators(&mut self.overflow);
-            self.sync_button_states_from_format();
-            return;
-        }
-
-        let mut visible = Vec::with_capacity(core_buttons.len() + 1);
-        let mut overflow = Vec::new();
-        let budget = (available_width - horizontal_padding - button_width(&more_button)).max(0.0);
-        let mut used = 0.0_f32;
-
-        for button in core_buttons {
-            let needed = if visible.is_empty() {
-                button_width(&button)
-            } else {
-                BUTTON_GAP + button_width(&button)
-            };
-
-            if used + needed <= budget {
-                used += needed;
-                visible.push(button);
-            } else {
-                overflow.push(button);
-            }
-        }
-
-        trim_separators(&mut visible);
-        trim_separators(&mut overflow);
-
-        visible.push(more_button);
+        let (visible, overflow) = split_overflow(&self.all_buttons, available_width);
         self.buttons = visible;
         self.overflow = overflow;
         self.sync_button_states_from_format();
     }
 
+    /// Ids of the configured buttons in order, separators included and `More` excluded.
+    pub fn layout_ids(&self) -> Vec<String> {
+        layout_ids(&self.all_buttons)
+    }
+
+    /// Replaces the configured buttons with a saved layout (see `buttons_from_layout`) and
+    /// lays them out again at the current width.
+    pub fn set_layout(&mut self, ids: Option<&[String]>) {
+        if self.layout_source.as_deref() == ids {
+            return;
+        }
+        self.layout_source = ids.map(<[String]>::to_vec);
+        self.all_buttons = buttons_from_layout(ids);
+        self.hovered_index = None;
+        self.pressed_index = None;
+        self.dropdown.close();
+        self.layout(self.bounds, 96.0);
+    }
+
     pub fn set_format_state(&mut self, state: ToolbarFormatState) {
         self.format_state = state;
         self.sync_button_states_from_format();
@@ -566,6 +619,150 @@ impl Toolbar {
         self.dropdown.color_picker.remember_color(color);
     }
 
+    pub fn set_theme_palette(&mut self, is_dark: bool) {
+        self.dropdown.color_picker.theme_colors = theme_palette(is_dark);
+    }
+
+    pub fn dropdown_panel_rect(&self) -> Option<Rect> {
+        let kind = self.dropdown.open?;
+        let anchor = self.dropdown.anchor?;
+        let (width, height) = match kind {
+            ToolbarDropdownKind::TextColor => self.dropdown.color_picker.panel_size(),
+            ToolbarDropdownKind::FontFamily | ToolbarDropdownKind::FontSize => {
+                // One input row for the filter / custom size, then the visible list window.
+                let rows = self.dropdown_list_labels().len().min(DROPDOWN_VISIBLE_ROWS) + 1;
+                (
+                    self.dropdown.panel_width,
+                    DROPDOWN_PANEL_PADDING * 2.0 + rows as f32 * DROPDOWN_ROW_HEIGHT,
+                )
+            }
+            _ => (self.dropdown.panel_width, 180.0),
+        };
+        Some(Rect {
+            x: anchor.x,
+            y: anchor.y + anchor.height + BUTTON_GAP,
+            width,
+            height,
+        })
+    }
+
+    pub fn dropdown_swatches(&self) -> Vec<(Rect, Color)> {
+        if self.dropdown.open != Some(ToolbarDropdownKind::TextColor) {
+            return Vec::new();
+        }
+        let Some(panel) = self.dropdown_panel_rect() else {
+            return Vec::new();
+        };
+        let picker = &self.dropdown.color_picker;
+        picker.swatch_rects(panel).into_iter().zip(picker.swatches()).collect()
+    }
+
+    /// Whether the open dropdown takes typed characters (font filter or custom size).
+    pub fn dropdown_accepts_text(&self) -> bool {
+        matches!(
+            self.dropdown.open,
+            Some(ToolbarDropdownKind::FontFamily | ToolbarDropdownKind::FontSize)
+        )
+    }
+
+    /// Text shown in the input row of the font family / size dropdown.
+    pub fn dropdown_input_text(&self) -> Option<String> {
+        match self.dropdown.open? {
+            ToolbarDropdownKind::FontFamily => Some(self.dropdown.font_picker.query.clone()),
+            ToolbarDropdownKind::FontSize => Some(self.dropdown.size_picker.custom_text.clone()),
+            _ => None,
+        }
+    }
+
+    fn dropdown_list_labels(&self) -> Vec<String> {
+        match self.dropdown.open {
+            Some(ToolbarDropdownKind::FontFamily) => self.visible_fonts(),
+            Some(ToolbarDropdownKind::FontSize) => self
+                .dropdown
+                .size_picker
+                .common_sizes
+                .iter()
+                .map(|size| format_size(*size))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Visible list rows of the font family / size dropdown, below the input row.
+    pub fn dropdown_rows(&self) -> Vec<(Rect, String)> {
+        let Some(panel) = self.dropdown_panel_rect() else {
+            return Vec::new();
+        };
+        self.dropdown_list_labels()
+            .into_iter()
+            .skip(self.dropdown.list_scroll)
+            .take(DROPDOWN_VISIBLE_ROWS)
+            .enumerate()
+            .map(|(idx, label)| {
+                let rect = Rect {
+                    x: panel.x + DROPDOWN_PANEL_PADDING,
+                    y: panel.y + DROPDOWN_PANEL_PADDING + (idx + 1) as f32 * DROPDOWN_ROW_HEIGHT,
+                    width: (panel.width - DROPDOWN_PANEL_PADDING * 2.0).max(0.0),
+                    height: DROPDOWN_ROW_HEIGHT,
+                };
+                (rect, label)
+            })
+            .collect()
+    }
+
+    fn scroll_dropdown_list(&mut self, rows: isize) {
+        let max_scroll = self
+            .dropdown_list_labels()
+            .len()
+            .saturating_sub(DROPDOWN_VISIBLE_ROWS);
+        self.dropdown.list_scroll = self
+            .dropdown
+            .list_scroll
+            .saturating_add_signed(rows)
+            .min(max_scroll);
+    }
+
+    fn list_selection(&self, label: String) -> Option<DropdownSelection> {
+        match self.dropdown.open? {
+            ToolbarDropdownKind::FontFamily => Some(DropdownSelection::FontFamily(label)),
+            ToolbarDropdownKind::FontSize => parse_font_size(&label).map(DropdownSelection::FontSize),
+            _ => None,
+        }
+    }
+
+    /// Enter in the input row: the typed size, or the best font match for the filter.
+    fn commit_dropdown_input(&self) -> Option<DropdownSelection> {
+        match self.dropdown.open? {
+            ToolbarDropdownKind::FontFamily => self
+                .visible_fonts()
+                .into_iter()
+                .next()
+                .map(DropdownSelection::FontFamily),
+            ToolbarDropdownKind::FontSize => self
+                .dropdown
+                .size_picker
+                .custom_input
+                .map(DropdownSelection::FontSize),
+            _ => None,
+        }
+    }
+
+    fn pick_dropdown_item(&self, point: Point) -> Option<DropdownSelection> {
+        match self.dropdown.open? {
+            ToolbarDropdownKind::TextColor => self
+                .dropdown_swatches()
+                .into_iter()
+                .find(|(rect, _)| contains(*rect, point))
+                .map(|(_, color)| DropdownSelection::TextColor(color)),
+            ToolbarDropdownKind::FontFamily | ToolbarDropdownKind::FontSize => self
+                .dropdown_rows()
+                .into_iter()
+                .find(|(rect, _)| contains(*rect, point))
+                .and_then(|(_, label)| self.list_selection(label)),
+            _ => None,
+        }
+    }
+
     pub fn button_rect(&self, index: usize) -> Option<Rect> {
         self.button_rects.get(index).copied()
     }
@@ -748,6 +945,18 @@ impl UIComponent for Toolbar {
                 idx.is_some()
             }
             InputEvent::MouseDown(point) => {
+                if let Some(panel) = self.dropdown_panel_rect() {
+                    if contains(panel, *point) {
+                        if let Some(selection) = self.pick_dropdown_item(*point) {
+                            self.pending_selection = Some(selection);
+                            self.dropdown.close();
+                        }
+                        return true;
+                    }
+                    if self.hit_button(*point).is_none() {
+                        self.dropdown.close();
+                    }
+                }
                 self.pressed_index = self.hit_button(*point);
                 self.pressed_started = self.pressed_index.map(|_| Instant::now());
                 self.pressed_index.is_some()
@@ -761,13 +970,66 @@ impl UIComponent for Toolbar {
                 self.pressed_started = None;
                 was_pressed
             }
+            InputEvent::MouseWheel { delta, position } => {
+                let Some(panel) = self.dropdown_panel_rect() else {
+                    return false;
+                };
+                if !self.dropdown_accepts_text() || !contains(panel, *position) {
+                    return false;
+                }
+                self.scroll_dropdown_list(if *delta > 0.0 { -3 } else { 3 });
+                true
+            }
             InputEvent::KeyDown(vk) => {
                 if *vk == 0x1B {
                     self.dropdown.close();
                     return true;
                 }
-                false
+                if !self.dropdown_accepts_text() {
+                    return false;
+                }
+                match *vk {
+                    // Backspace edits the filter / custom size.
+                    0x08 => {
+                        match self.dropdown.open {
+                            Some(ToolbarDropdownKind::FontFamily) => {
+                                self.dropdown.font_picker.query.pop();
+                            }
+                            _ => self.dropdown.size_picker.pop_char(),
+                        }
+                        self.dropdown.list_scroll = 0;
+                        true
+                    }
+                    0x0D => {
+                        if let Some(selection) = self.commit_dropdown_input() {
+                            self.pending_selection = Some(selection);
+                            self.dropdown.close();
+                        }
+                        true
+                    }
+                    0x26 => {
+                        self.scroll_dropdown_list(-1);
+                        true
+                    }
+                    0x28 => {
+                        self.scroll_dropdown_list(1);
+                        true
+                    }
+                    _ => false,
+                }
             }
+            InputEvent::Char(ch) => match self.dropdown.open {
+                Some(ToolbarDropdownKind::FontFamily) if !ch.is_control() => {
+                    self.dropdown.font_picker.query.push(*ch);
+                    self.dropdown.list_scroll = 0;
+                    true
+                }
+                Some(ToolbarDropdownKind::FontSize) => {
+                    self.dropdown.size_picker.push_char(*ch);
+                    true
+                }
+                _ => false,
+            },
             _ => false,
         }
     }
@@ -818,6 +1080,96 @@ fn default_buttons() -> Vec<ToolbarButton> {
     ]
 }
 
+/// Builds the toolbar for a saved layout, or the default toolbar when there is none.
+/// Unknown and repeated ids are skipped, separators are tidied and `More` always comes last.
+pub fn buttons_from_layout(ids: Option<&[String]>) -> Vec<ToolbarButton> {
+    let Some(ids) = ids else {
+        return default_buttons();
+    };
+
+    let catalog = default_buttons();
+    let mut buttons = Vec::with_capacity(ids.len() + 1);
+    for id in ids {
+        if id == TOOLBAR_SEPARATOR_ID {
+            buttons.push(sep());
+            continue;
+        }
+        if id == "more" || buttons.iter().any(|b: &ToolbarButton| b.id == id) {
+            continue;
+        }
+        if let Some(button) = catalog.iter().find(|b| b.id == id) {
+            buttons.push(button.clone());
+        }
+    }
+    trim_separators(&mut buttons);
+    buttons.push(default_more_button());
+    buttons
+}
+
+/// Every button a layout can contain, in default order.
+pub fn customizable_buttons() -> Vec<ToolbarButton> {
+    default_buttons()
+        .into_iter()
+        .filter(|b| b.kind != ToolbarButtonType::Separator && b.id != "more")
+        .collect()
+}
+
+fn layout_ids(buttons: &[ToolbarButton]) -> Vec<String> {
+    buttons
+        .iter()
+        .filter(|b| b.id != "more")
+        .map(|b| b.id.to_string())
+        .collect()
+}
+
+/// Splits the configured buttons into those that fit in `available_width` and those that
+/// collapse under `More`, which always stays visible at the end.
+pub fn split_overflow(all_buttons: &[ToolbarButton], available_width: f32) -> (Vec<ToolbarButton>, Vec<ToolbarButton>) {
+    let more_button = all_buttons
+        .iter()
+        .find(|b| b.id == "more")
+        .cloned()
+        .unwrap_or_else(default_more_button);
+
+    let mut core_buttons = all_buttons
+        .iter()
+        .filter(|b| b.id != "more")
+        .cloned()
+        .collect::<Vec<_>>();
+    trim_separators(&mut core_buttons);
+
+    let horizontal_padding = TOOLBAR_PADDING_X * 2.0;
+    if available_width <= horizontal_padding + button_width(&more_button) {
+        return (vec![more_button], core_buttons);
+    }
+
+    let mut visible = Vec::with_capacity(core_buttons.len() + 1);
+    let mut overflow = Vec::new();
+    let budget = (available_width - horizontal_padding - button_width(&more_button)).max(0.0);
+    let mut used = 0.0_f32;
+
+    for button in core_buttons {
+        let needed = if visible.is_empty() {
+            button_width(&button)
+        } else {
+            BUTTON_GAP + button_width(&button)
+        };
+
+        if used + needed <= budget {
+            used += needed;
+            visible.push(button);
+        } else {
+            overflow.push(button);
+        }
+    }
+
+    trim_separators(&mut visible);
+    trim_separators(&mut overflow);
+
+    visible.push(more_button);
+    (visible, overflow)
+}
+
 fn btn(
     id: &'static str,
     label: &'static str,
@@ -941,7 +1293,10 @@ fn apply_format_to_button(button: &mut ToolbarButton, state: &ToolbarFormatState
 }
 
 fn format_size(size: f32) -> String {
-    if (size - size.round()).abs() < f32::EPSILON {
+    // A mixed selection reports no single size; leave the button blank like Word does.
+    if size <= 0.0 {
+        String::new()
+    } else if (size - size.round()).abs() < f32::EPSILON {
         format!("{}", size.round() as i32)
     } else {
         format!("{size:.1}")
@@ -1026,6 +1381,15 @@ fn default_theme_colors() -> Vec<Color> {
     ]
 }
 
+fn theme_palette(is_dark: bool) -> Vec<Color> {
+    let mut colors = default_theme_colors();
+    if is_dark {
+        // Lead with light neutrals so the first swatches stay readable on dark pages.
+        colors[..10].reverse();
+    }
+    colors
+}
+
 fn rgb(r: u8, g: u8, b: u8) -> Color {
     Color::rgb(r as f32 / 255.0, g as f32 / 255.0, b as f32 / 255.0)
 }
@@ -1176,6 +1540,98 @@ mod tests {
         assert!(toolbar.dropdown.scale >= 0.99);
     }
 
+    #[test]
+    fn color_dropdown_picks_swatch_and_remembers_recent() {
+        let mut toolbar = Toolbar::new();
+        toolbar.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 1400.0,
+                height: 44.0,
+            },
+            96.0,
+        );
+        let accent = Color::rgb(0.2, 0.4, 0.6);
+        toolbar.remember_color(accent);
+
+        let idx = find_button_index(&toolbar, "color").expect("color button missing");
+        let rect = toolbar.button_rect(idx).expect("color rect missing");
+        let _ = toolbar.invoke_with_point(Point {
+            x: rect.x + rect.width * 0.5,
+            y: rect.y + rect.height * 0.5,
+        });
+
+        let swatches = toolbar.dropdown_swatches();
+        assert_eq!(swatches.first().map(|(_, c)| *c), Some(accent));
+        let (swatch_rect, _) = swatches[0];
+        assert!(toolbar.handle_input(&InputEvent::MouseDown(Point {
+            x: swatch_rect.x + 2.0,
+            y: swatch_rect.y + 2.0,
+        })));
+        assert_eq!(toolbar.pending_selection, Some(DropdownSelection::TextColor(accent)));
+        assert!(toolbar.dropdown.open.is_none());
+    }
+
+    #[test]
+    fn font_dropdowns_pick_rows_and_clamp_custom_sizes() {
+        let mut toolbar = Toolbar::new();
+        toolbar.layout(
+            Rect {
+                x: 0.0,
+                y: 0.0,
+                width: 1400.0,
+                height: 44.0,
+            },
+            96.0,
+        );
+        toolbar.set_font_catalog(vec!["Arial".to_string(), "Georgia".to_string()]);
+
+        let open = |toolbar: &mut Toolbar, id: &str| {
+            let idx = find_button_index(toolbar, id).expect("button missing");
+            let rect = toolbar.button_rect(idx).expect("rect missing");
+            let _ = toolbar.invoke_with_point(Point {
+                x: rect.x + rect.width * 0.5,
+                y: rect.y + rect.height * 0.5,
+            });
+        };
+
+        open(&mut toolbar, "font");
+        assert!(toolbar.dropdown_accepts_text());
+        for ch in "geo".chars() {
+            assert!(toolbar.handle_input(&InputEvent::Char(ch)));
+        }
+        let rows = toolbar.dropdown_rows();
+        assert_eq!(rows.len(), 1);
+        let (row, _) = &rows[0];
+        assert!(toolbar.handle_input(&InputEvent::MouseDown(Point {
+            x: row.x + 4.0,
+            y: row.y + 4.0,
+        })));
+        assert_eq!(
+            toolbar.pending_selection.take(),
+            Some(DropdownSelection::FontFamily("Georgia".to_string()))
+        );
+
+        open(&mut toolbar, "size");
+        for ch in "5x00".chars() {
+            let _ = toolbar.handle_input(&InputEvent::Char(ch));
+        }
+        assert_eq!(toolbar.dropdown_input_text().as_deref(), Some("500"));
+        assert!(toolbar.handle_input(&InputEvent::KeyDown(0x0D)));
+        assert_eq!(toolbar.pending_selection, Some(DropdownSelection::FontSize(400.0)));
+        assert!(toolbar.dropdown.open.is_none());
+    }
+
+    #[test]
+    fn dark_palette_leads_with_light_neutrals() {
+        let light = theme_palette(false);
+        let dark = theme_palette(true);
+        assert_eq!(light[0], rgb(0x00, 0x00, 0x00));
+        assert_eq!(dark[0], rgb(0xFF, 0xFF, 0xFF));
+        assert_eq!(light.len(), dark.len());
+    }
+
     #[test]
     fn font_picker_prioritizes_recent_fonts() {
         let mut toolbar = Toolbar::new();
@@ -1192,4 +1648,49 @@ mod tests {
         assert_eq!(visible.first().map(|s| s.as_str()), Some("Segoe Print"));
         assert!(visible.iter().any(|f| f == "Segoe UI"));
     }
+
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn toolbar_layout_round_trips_through_settings() {
+        use crate::settings::schema::Settings;
+
+        let mut settings = Settings::default();
+        settings.appearance.toolbar_layout = Some(ids(&[
+            "sep", "bold", "bold", "unknown", "sep", "sep", "table", "more", "sep",
+        ]));
+        let json = serde_json::to_string(&settings).expect("settings serialize");
+        let restored: Settings = serde_json::from_str(&json).expect("settings deserialize");
+        assert_eq!(restored.appearance.toolbar_layout, settings.appearance.toolbar_layout);
+
+        let mut toolbar = Toolbar::new();
+        toolbar.set_layout(restored.appearance.toolbar_layout.as_deref());
+        // Duplicates and unknown ids are dropped and separators never lead, trail or repeat.
+        assert_eq!(toolbar.layout_ids(), ids(&["bold", "sep", "table"]));
+        assert_eq!(toolbar.all_buttons.last().map(|b| b.id), Some("more"));
+
+        // Settings written before layouts existed fall back to the default toolbar.
+        let legacy: Settings = serde_json::from_str(r#"{"appearance":{"show_toolbar":true}}"#)
+            .expect("legacy settings deserialize");
+        assert!(legacy.appearance.toolbar_layout.is_none());
+        toolbar.set_layout(legacy.appearance.toolbar_layout.as_deref());
+        assert_eq!(toolbar.layout_ids(), layout_ids(&default_buttons()));
+    }
+
+    #[test]
+    fn overflow_split_keeps_what_fits_and_collapses_the_rest() {
+        let buttons = buttons_from_layout(Some(&ids(&["bold", "italic", "sep", "font", "table"])));
+        // Padding (16) + More (40) + bold, italic and the separator with their gaps (80).
+        let (visible, overflow) = split_overflow(&buttons, 136.0);
+        let visible_ids = visible.iter().map(|b| b.id).collect::<Vec<_>>();
+        let overflow_ids = overflow.iter().map(|b| b.id).collect::<Vec<_>>();
+        assert_eq!(visible_ids, vec!["bold", "italic", "more"]);
+        assert_eq!(overflow_ids, vec!["font", "table"]);
+
+        let (visible, overflow) = split_overflow(&buttons, 40.0);
+        assert_eq!(visible.iter().map(|b| b.id).collect::<Vec<_>>(), vec!["more"]);
+        assert_eq!(overflow.len(), 5);
+    }
 }
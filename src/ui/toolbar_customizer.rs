@@ -0,0 +1,273 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    theme::Theme,
+    ui::{
+        InputEvent, Point, Rect, UIComponent,
+        list_panel::{ListPanel, ListPanelLayout, PanelPlacement},
+        toolbar::{TOOLBAR_SEPARATOR_ID, buttons_from_layout, customizable_buttons},
+    },
+};
+
+const LAYOUT: ListPanelLayout = ListPanelLayout {
+    width: 340.0,
+    max_height: 520.0,
+    header: 36.0,
+    footer: 32.0,
+    placement: PanelPlacement::Center,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CustomizeEntry {
+    pub id: String,
+    pub label: String,
+    pub shown: bool,
+}
+
+impl CustomizeEntry {
+    fn is_separator(&self) -> bool {
+        self.id == TOOLBAR_SEPARATOR_ID
+    }
+
+    fn row_label(&self) -> String {
+        if self.is_separator() {
+            "──── Separator ────".to_string()
+        } else if self.shown {
+            format!("[x] {}", self.label)
+        } else {
+            format!("[ ] {}", self.label)
+        }
+    }
+}
+
+/// "Customize toolbar" dialog: shown buttons in toolbar order, then the hidden ones.
+/// Every edit leaves the new layout in `pending_layout` for the window to persist.
+#[derive(Debug, Clone, Default)]
+pub struct ToolbarCustomizer {
+    list: ListPanel,
+    visible: bool,
+    entries: Vec<CustomizeEntry>,
+    pub pending_layout: Option<Vec<String>>,
+}
+
+impl ToolbarCustomizer {
+    pub fn open(&mut self, layout: &[String]) {
+        self.entries = entries_for_layout(Some(layout));
+        self.list.reset();
+        self.pending_layout = None;
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    /// Shown ids in order; this is what gets saved as the toolbar layout.
+    pub fn layout(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.shown)
+            .map(|entry| entry.id.clone())
+            .collect()
+    }
+
+    /// Shows or hides the selected button; a selected separator is removed instead.
+    pub fn toggle_selected(&mut self) -> bool {
+        let selected = self.list.selected;
+        let Some(entry) = self.entries.get_mut(selected) else {
+            return false;
+        };
+        if entry.is_separator() {
+            self.entries.remove(selected);
+            self.list.selected = selected.min(self.entries.len().saturating_sub(1));
+        } else {
+            entry.shown = !entry.shown;
+            // Shown buttons stay ahead of hidden ones so the list reads like the toolbar;
+            // the toggled entry lands at the boundary between the two.
+            let entry = self.entries.remove(selected);
+            self.list.selected = self.entries.iter().filter(|e| e.shown).count();
+            self.entries.insert(self.list.selected, entry);
+        }
+        self.mark_changed();
+        true
+    }
+
+    /// Moves the selected shown entry up or down among the shown entries.
+    pub fn move_selected(&mut self, delta: isize) -> bool {
+        let shown_count = self.entries.iter().filter(|e| e.shown).count();
+        if self.list.selected >= shown_count {
+            return false;
+        }
+        let target = self.list.selected as isize + delta;
+        if target < 0 || target as usize >= shown_count {
+            return false;
+        }
+        self.entries.swap(self.list.selected, target as usize);
+        self.list.selected = target as usize;
+        self.mark_changed();
+        true
+    }
+
+    /// Inserts a separator after the selected shown entry.
+    pub fn insert_separator(&mut self) -> bool {
+        let shown_count = self.entries.iter().filter(|e| e.shown).count();
+        let at = (self.list.selected + 1).min(shown_count);
+        self.entries.insert(
+            at,
+            CustomizeEntry {
+                id: TOOLBAR_SEPARATOR_ID.to_string(),
+                label: String::new(),
+                shown: true,
+            },
+        );
+        self.list.selected = at;
+        self.mark_changed();
+        true
+    }
+
+    pub fn reset_to_default(&mut self) {
+        self.entries = entries_for_layout(None);
+        self.list.reset();
+        self.pending_layout = Some(self.layout());
+    }
+
+    pub fn panel_rect(&self) -> Rect {
+        self.list.panel_rect(&LAYOUT)
+    }
+
+    /// Rows currently scrolled into view, with their rect, label and selection state.
+    pub fn visible_rows(&self) -> Vec<(Rect, String, bool)> {
+        self.list
+            .visible_rows(&LAYOUT, self.entries.len(), |index| {
+                self.entries[index].row_label()
+            })
+    }
+
+    fn select(&mut self, index: usize) {
+        self.list.select(&LAYOUT, index, self.entries.len());
+    }
+
+    fn mark_changed(&mut self) {
+        self.select(self.list.selected);
+        self.pending_layout = Some(self.layout());
+    }
+}
+
+fn entries_for_layout(layout: Option<&[String]>) -> Vec<CustomizeEntry> {
+    let mut entries = buttons_from_layout(layout)
+        .into_iter()
+        .filter(|button| button.id != "more")
+        .map(|button| CustomizeEntry {
+            id: button.id.to_string(),
+            label: button.tooltip,
+            shown: true,
+        })
+        .collect::<Vec<_>>();
+    for button in customizable_buttons() {
+        if !entries.iter().any(|entry| entry.id == button.id) {
+            entries.push(CustomizeEntry {
+                id: button.id.to_string(),
+                label: button.tooltip,
+                shown: false,
+            });
+        }
+    }
+    entries
+}
+
+impl UIComponent for ToolbarCustomizer {
+    fn layout(&mut self, bounds: Rect, _dpi: f32) {
+        self.list.bounds = bounds;
+        self.select(self.list.selected);
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // The customize dialog is composed in the shell renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match event {
+            InputEvent::KeyDown(vk) => match *vk {
+                0x1B => {
+                    self.close();
+                    true
+                }
+                0x26 => {
+                    self.select(self.list.selected.saturating_sub(1));
+                    true
+                }
+                0x28 => {
+                    self.select(self.list.selected + 1);
+                    true
+                }
+                0x20 | 0x0D | 0x2E => self.toggle_selected(),
+                0x2D => self.insert_separator(),
+                _ => false,
+            },
+            InputEvent::MouseDown(point) => {
+                if !self.panel_rect().contains(*point) {
+                    self.close();
+                    return true;
+                }
+                let len = self.entries.len();
+                if let Some(index) = self.list.row_from_point(&LAYOUT, *point, len) {
+                    self.select(index);
+                    self.toggle_selected();
+                }
+                true
+            }
+            InputEvent::MouseWheel { delta, .. } => {
+                let rows = if *delta > 0.0 { -1 } else { 1 };
+                self.list.scroll_by(&LAYOUT, rows, self.entries.len());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && self.panel_rect().contains(point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+    }
+
+    fn bounds(&self) -> Rect {
+        self.list.bounds
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn hidden_buttons_follow_the_shown_ones() {
+        let mut customizer = ToolbarCustomizer::default();
+        customizer.open(&ids(&["bold", "sep", "italic"]));
+        assert_eq!(customizer.layout(), ids(&["bold", "sep", "italic"]));
+        assert!(customizer.entries[3..].iter().all(|entry| !entry.shown));
+        assert!(customizer.entries.iter().any(|entry| entry.id == "table"));
+
+        // Hiding "bold" drops it behind the shown entries; moving "italic" up reorders.
+        customizer.toggle_selected();
+        assert_eq!(customizer.layout(), ids(&["sep", "italic"]));
+        customizer.select(1);
+        assert!(customizer.move_selected(-1));
+        assert_eq!(customizer.pending_layout, Some(ids(&["italic", "sep"])));
+        assert!(!customizer.move_selected(-1));
+    }
+}
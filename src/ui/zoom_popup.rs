@@ -0,0 +1,283 @@
+use windows::Win32::Graphics::Direct2D::ID2D1DeviceContext;
+
+use crate::{
+    render::canvas::{ZOOM_MAX, ZOOM_MIN, ZoomPreset},
+    theme::Theme,
+    ui::{InputEvent, Point, Rect, UIComponent},
+};
+
+pub const ZOOM_PRESETS: [u16; 6] = [50, 75, 100, 125, 150, 200];
+
+const POPUP_WIDTH: f32 = 216.0;
+const POPUP_PADDING: f32 = 8.0;
+const BUTTON_HEIGHT: f32 = 24.0;
+const BUTTON_GAP: f32 = 4.0;
+const SLIDER_HEIGHT: f32 = 28.0;
+const NUDGE_STEP_PERCENT: f32 = 10.0;
+
+/// Maps a slider position in `0..=1` to a zoom factor. The scale is logarithmic so that
+/// 50%–200%, where most zooming happens, gets a useful share of the track.
+pub fn slider_to_zoom(position: f32) -> f32 {
+    let t = position.clamp(0.0, 1.0);
+    ZOOM_MIN * (ZOOM_MAX / ZOOM_MIN).powf(t)
+}
+
+pub fn zoom_to_slider(zoom: f32) -> f32 {
+    let zoom = zoom.clamp(ZOOM_MIN, ZOOM_MAX);
+    (zoom / ZOOM_MIN).ln() / (ZOOM_MAX / ZOOM_MIN).ln()
+}
+
+/// Zoom after `steps` keyboard nudges: snaps to the next multiple of 10% in that direction.
+pub fn nudge_zoom(zoom: f32, steps: i32) -> f32 {
+    let percent = zoom * 100.0;
+    let snapped = if steps > 0 {
+        (percent / NUDGE_STEP_PERCENT + 0.01).floor() + steps as f32
+    } else {
+        (percent / NUDGE_STEP_PERCENT - 0.01).ceil() + steps as f32
+    };
+    (snapped * NUDGE_STEP_PERCENT / 100.0).clamp(ZOOM_MIN, ZOOM_MAX)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ZoomPopupAction {
+    SetZoom(f32),
+    Preset(ZoomPreset),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ZoomPopupButton {
+    Percent(u16),
+    Fit(ZoomPreset),
+}
+
+impl ZoomPopupButton {
+    fn label(self) -> String {
+        match self {
+            Self::Percent(percent) => format!("{percent}%"),
+            Self::Fit(ZoomPreset::FitWidth) => "Fit width".to_string(),
+            Self::Fit(ZoomPreset::FitPage) => "Fit page".to_string(),
+            Self::Fit(ZoomPreset::ActualSize) => "100%".to_string(),
+        }
+    }
+}
+
+/// Zoom control opened from the status bar's zoom indicator.
+#[derive(Debug, Default)]
+pub struct ZoomPopup {
+    bounds: Rect,
+    visible: bool,
+    zoom: f32,
+    dragging: bool,
+    pub pending_action: Option<ZoomPopupAction>,
+}
+
+impl ZoomPopup {
+    /// Opens above `anchor` (the zoom indicator), showing `zoom` as the current value.
+    pub fn open(&mut self, anchor: Rect, zoom: f32) {
+        let height = POPUP_PADDING * 2.0 + 3.0 * (BUTTON_HEIGHT + BUTTON_GAP) + SLIDER_HEIGHT;
+        self.bounds = Rect {
+            x: (anchor.x + anchor.width - POPUP_WIDTH).max(0.0),
+            y: (anchor.y - height - BUTTON_GAP).max(0.0),
+            width: POPUP_WIDTH,
+            height,
+        };
+        self.zoom = zoom;
+        self.dragging = false;
+        self.visible = true;
+    }
+
+    pub fn close(&mut self) {
+        self.visible = false;
+        self.dragging = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.visible
+    }
+
+    pub fn is_dragging(&self) -> bool {
+        self.dragging
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom = zoom;
+    }
+
+    /// Preset and fit buttons with their rects and whether they match the current zoom.
+    pub fn button_items(&self) -> Vec<(Rect, String, bool)> {
+        self.buttons()
+            .into_iter()
+            .map(|(rect, button)| {
+                let selected = matches!(button, ZoomPopupButton::Percent(p) if (self.zoom * 100.0).round() as u16 == p);
+                (rect, button.label(), selected)
+            })
+            .collect()
+    }
+
+    pub fn slider_track(&self) -> Rect {
+        Rect {
+            x: self.bounds.x + POPUP_PADDING,
+            y: self.bounds.y + self.bounds.height - POPUP_PADDING - SLIDER_HEIGHT,
+            width: self.bounds.width - POPUP_PADDING * 2.0,
+            height: SLIDER_HEIGHT,
+        }
+    }
+
+    /// Thumb position along the track, in `0..=1`.
+    pub fn slider_position(&self) -> f32 {
+        zoom_to_slider(self.zoom)
+    }
+
+    fn buttons(&self) -> Vec<(Rect, ZoomPopupButton)> {
+        let inner_width = self.bounds.width - POPUP_PADDING * 2.0;
+        let mut out = Vec::with_capacity(ZOOM_PRESETS.len() + 2);
+        let third = (inner_width - BUTTON_GAP * 2.0) / 3.0;
+        for (index, percent) in ZOOM_PRESETS.iter().enumerate() {
+            out.push((
+                Rect {
+                    x: self.bounds.x + POPUP_PADDING + (index % 3) as f32 * (third + BUTTON_GAP),
+                    y: self.bounds.y + POPUP_PADDING + (index / 3) as f32 * (BUTTON_HEIGHT + BUTTON_GAP),
+                    width: third,
+                    height: BUTTON_HEIGHT,
+                },
+                ZoomPopupButton::Percent(*percent),
+            ));
+        }
+        let half = (inner_width - BUTTON_GAP) / 2.0;
+        for (index, preset) in [ZoomPreset::FitWidth, ZoomPreset::FitPage].into_iter().enumerate() {
+            out.push((
+                Rect {
+                    x: self.bounds.x + POPUP_PADDING + index as f32 * (half + BUTTON_GAP),
+                    y: self.bounds.y + POPUP_PADDING + 2.0 * (BUTTON_HEIGHT + BUTTON_GAP),
+                    width: half,
+                    height: BUTTON_HEIGHT,
+                },
+                ZoomPopupButton::Fit(preset),
+            ));
+        }
+        out
+    }
+
+    fn drag_slider_to(&mut self, x: f32) {
+        let track = self.slider_track();
+        let position = ((x - track.x) / track.width.max(1.0)).clamp(0.0, 1.0);
+        // Whole percentages read better in the status bar than 137.4%.
+        self.zoom = (slider_to_zoom(position) * 100.0).round() / 100.0;
+        self.pending_action = Some(ZoomPopupAction::SetZoom(self.zoom));
+    }
+}
+
+impl UIComponent for ZoomPopup {
+    fn layout(&mut self, _bounds: Rect, _dpi: f32) {
+        // Positioned against the zoom indicator in `open`.
+    }
+
+    fn render(&self, _ctx: &ID2D1DeviceContext, _theme: &Theme) {
+        // Drawn in host renderer.
+    }
+
+    fn handle_input(&mut self, event: &InputEvent) -> bool {
+        if !self.visible {
+            return false;
+        }
+
+        match event {
+            InputEvent::KeyDown(vk) => match *vk {
+                0x1B | 0x0D => {
+                    self.close();
+                    true
+                }
+                0x26 | 0x28 => {
+                    self.zoom = nudge_zoom(self.zoom, if *vk == 0x26 { 1 } else { -1 });
+                    self.pending_action = Some(ZoomPopupAction::SetZoom(self.zoom));
+                    true
+                }
+                _ => false,
+            },
+            InputEvent::MouseDown(point) => {
+                if !contains(self.bounds, *point) {
+                    self.close();
+                    return true;
+                }
+                if contains(self.slider_track(), *point) {
+                    self.dragging = true;
+                    self.drag_slider_to(point.x);
+                    return true;
+                }
+                if let Some((_, button)) = self.buttons().into_iter().find(|(rect, _)| contains(*rect, *point)) {
+                    self.pending_action = Some(match button {
+                        ZoomPopupButton::Percent(percent) => {
+                            self.zoom = percent as f32 / 100.0;
+                            ZoomPopupAction::SetZoom(self.zoom)
+                        }
+                        ZoomPopupButton::Fit(preset) => ZoomPopupAction::Preset(preset),
+                    });
+                }
+                true
+            }
+            InputEvent::MouseMove(point) if self.dragging => {
+                self.drag_slider_to(point.x);
+                true
+            }
+            InputEvent::MouseUp(_) if self.dragging => {
+                self.dragging = false;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn hit_test(&self, point: Point) -> bool {
+        self.visible && contains(self.bounds, point)
+    }
+
+    fn set_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        if !visible {
+            self.dragging = false;
+        }
+    }
+
+    fn bounds(&self) -> Rect {
+        self.bounds
+    }
+}
+
+fn contains(rect: Rect, point: Point) -> bool {
+    point.x >= rect.x
+        && point.x <= rect.x + rect.width
+        && point.y >= rect.y
+        && point.y <= rect.y + rect.height
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn slider_ends_map_to_zoom_limits_and_back() {
+        assert!((slider_to_zoom(0.0) - ZOOM_MIN).abs() < 1e-5);
+        assert!((slider_to_zoom(1.0) - ZOOM_MAX).abs() < 1e-4);
+        assert!((slider_to_zoom(-3.0) - ZOOM_MIN).abs() < 1e-5);
+        assert_eq!(zoom_to_slider(0.01), 0.0);
+        assert!((zoom_to_slider(50.0) - 1.0).abs() < 1e-5);
+
+        for zoom in [0.5, 0.75, 1.0, 1.25, 1.5, 2.0, 3.3] {
+            let back = slider_to_zoom(zoom_to_slider(zoom));
+            assert!((back - zoom).abs() < 1e-4, "{zoom} came back as {back}");
+        }
+        // The logarithmic scale puts 100% well before the middle of a 25%–500% track.
+        assert!(zoom_to_slider(1.0) < 0.5);
+        assert!(zoom_to_slider(0.5) < zoom_to_slider(1.0));
+    }
+
+    #[test]
+    fn keyboard_nudges_snap_to_ten_percent_steps() {
+        assert!((nudge_zoom(1.0, 1) - 1.1).abs() < 1e-5);
+        assert!((nudge_zoom(1.0, -1) - 0.9).abs() < 1e-5);
+        assert!((nudge_zoom(1.37, 1) - 1.4).abs() < 1e-5);
+        assert!((nudge_zoom(1.37, -1) - 1.3).abs() < 1e-5);
+        assert_eq!(nudge_zoom(ZOOM_MAX, 1), ZOOM_MAX);
+        assert_eq!(nudge_zoom(ZOOM_MIN, -1), ZOOM_MIN);
+    }
+}
@@ -0,0 +1,344 @@
+use std::{ops::Range, sync::Arc};
+
+use parking_lot::Mutex;
+use windows::{
+    Win32::{
+        Foundation::{E_ACCESSDENIED, HWND, LPARAM, LRESULT, WPARAM},
+        System::Variant::VARIANT,
+        UI::Accessibility::{
+            IRawElementProviderSimple, IRawElementProviderSimple_Impl, IValueProvider,
+            IValueProvider_Impl, NotificationKind_Other, NotificationProcessing_MostRecent,
+            ProviderOptions, ProviderOptions_ServerSideProvider, ProviderOptions_UseComThreading,
+            UIA_ControlTypePropertyId, UIA_DocumentControlTypeId, UIA_IsKeyboardFocusablePropertyId,
+            UIA_NamePropertyId, UIA_PATTERN_ID, UIA_PROPERTY_ID, UIA_ValueIsReadOnlyPropertyId,
+            UIA_ValuePatternId, UIA_ValueValuePropertyId, UiaClientsAreListening,
+            UiaDisconnectProvider, UiaHostProviderFromHwnd, UiaRaiseNotificationEvent,
+            UiaReturnRawElementProvider, UiaRootObjectId,
+        },
+    },
+    core::{BOOL, BSTR, Error, IUnknown, IUnknownImpl, PCWSTR, Result, implement},
+};
+
+use crate::{
+    document::model::{Block, DocumentModel, Run},
+    editor::{
+        cursor::CursorPosition,
+        search::{append_block_text, collect_document_plain_text},
+    },
+};
+
+const CARET_ACTIVITY_ID: &str = "doco.caret";
+
+/// Where the caret sits in the document's plain text, as a screen reader would describe it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CaretDescription {
+    pub line: usize,
+    pub column: usize,
+    pub paragraph: String,
+}
+
+/// Linear character position of `cursor` in the text built by `collect_document_plain_text`,
+/// or `None` when the cursor's block isn't a text block of `document`.
+pub fn text_position(document: &DocumentModel, cursor: CursorPosition) -> Option<usize> {
+    let mut base = 0usize;
+    document
+        .content
+        .iter()
+        .find_map(|block| locate_in_block(block, cursor, &mut base))
+}
+
+/// Walks `block` in the same order as `append_block_text`, advancing `base` past every
+/// character that precedes the cursor.
+fn locate_in_block(block: &Block, cursor: CursorPosition, base: &mut usize) -> Option<usize> {
+    match block {
+        Block::Paragraph(p) if p.id == cursor.block_id => Some(*base + cursor.offset.min(runs_len(&p.runs))),
+        Block::Heading(h) if h.id == cursor.block_id => Some(*base + cursor.offset.min(runs_len(&h.runs))),
+        Block::CodeBlock(c) if c.id == cursor.block_id => {
+            Some(*base + cursor.offset.min(c.code.chars().count()))
+        }
+        Block::List(list) => list
+            .items
+            .iter()
+            .flat_map(|item| &item.content)
+            .find_map(|nested| locate_in_block(nested, cursor, base)),
+        Block::Table(table) => {
+            for row in &table.rows {
+                for cell in &row.cells {
+                    for nested in &cell.blocks {
+                        if let Some(position) = locate_in_block(nested, cursor, base) {
+                            return Some(position);
+                        }
+                    }
+                    *base += 1;
+                }
+                *base += 1;
+            }
+            None
+        }
+        Block::BlockQuote(quote) => quote
+            .blocks
+            .iter()
+            .find_map(|nested| locate_in_block(nested, cursor, base)),
+        _ => {
+            let mut text = String::new();
+            append_block_text(block, &mut text);
+            *base += text.chars().count();
+            None
+        }
+    }
+}
+
+fn runs_len(runs: &[Run]) -> usize {
+    runs.iter().map(|run| run.text.chars().count()).sum()
+}
+
+/// Character range of the paragraph (line) containing `position`, without its newline.
+pub fn paragraph_range(text: &str, position: usize) -> Range<usize> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let position = position.min(chars.len());
+    let start = chars[..position]
+        .iter()
+        .rposition(|ch| *ch == '\n')
+        .map_or(0, |newline| newline + 1);
+    let end = chars[position..]
+        .iter()
+        .position(|ch| *ch == '\n')
+        .map_or(chars.len(), |newline| position + newline);
+    start..end
+}
+
+pub fn describe_caret(text: &str, position: usize) -> CaretDescription {
+    let range = paragraph_range(text, position);
+    let line = text.chars().take(range.start).filter(|ch| *ch == '\n').count() + 1;
+    CaretDescription {
+        line,
+        column: position.min(range.end) - range.start + 1,
+        paragraph: text
+            .chars()
+            .skip(range.start)
+            .take(range.end - range.start)
+            .collect(),
+    }
+}
+
+#[derive(Debug, Default)]
+struct CanvasText {
+    name: String,
+    paragraph: String,
+}
+
+/// Read-only UI Automation element for the document canvas. The window owns the text it
+/// reports and refreshes it as the caret moves.
+#[implement(IRawElementProviderSimple, IValueProvider)]
+struct CanvasProvider {
+    hwnd: HWND,
+    text: Arc<Mutex<CanvasText>>,
+}
+
+impl IRawElementProviderSimple_Impl for CanvasProvider_Impl {
+    fn ProviderOptions(&self) -> Result<ProviderOptions> {
+        // COM threading keeps every call on the window's thread.
+        Ok(ProviderOptions(
+            ProviderOptions_ServerSideProvider.0 | ProviderOptions_UseComThreading.0,
+        ))
+    }
+
+    fn GetPatternProvider(&self, patternid: UIA_PATTERN_ID) -> Result<IUnknown> {
+        if patternid == UIA_ValuePatternId {
+            Ok(self.to_interface::<IValueProvider>().into())
+        } else {
+            // An empty error reports success with no pattern.
+            Err(Error::empty())
+        }
+    }
+
+    fn GetPropertyValue(&self, propertyid: UIA_PROPERTY_ID) -> Result<VARIANT> {
+        let text = self.text.lock();
+        Ok(match propertyid {
+            UIA_ControlTypePropertyId => VARIANT::from(UIA_DocumentControlTypeId.0),
+            UIA_NamePropertyId => VARIANT::from(BSTR::from(text.name.as_str())),
+            UIA_IsKeyboardFocusablePropertyId => VARIANT::from(true),
+            UIA_ValueValuePropertyId => VARIANT::from(BSTR::from(text.paragraph.as_str())),
+            UIA_ValueIsReadOnlyPropertyId => VARIANT::from(true),
+            _ => VARIANT::default(),
+        })
+    }
+
+    fn HostRawElementProvider(&self) -> Result<IRawElementProviderSimple> {
+        unsafe { UiaHostProviderFromHwnd(self.hwnd) }
+    }
+}
+
+impl IValueProvider_Impl for CanvasProvider_Impl {
+    fn SetValue(&self, _val: &PCWSTR) -> Result<()> {
+        Err(E_ACCESSDENIED.into())
+    }
+
+    fn Value(&self) -> Result<BSTR> {
+        Ok(BSTR::from(self.text.lock().paragraph.as_str()))
+    }
+
+    fn IsReadOnly(&self) -> Result<BOOL> {
+        Ok(true.into())
+    }
+}
+
+/// Screen-reader access to the active document: reads the current paragraph and announces
+/// caret moves. Nothing is computed until a UI Automation client asks for the provider.
+#[derive(Default)]
+pub struct CanvasAccessibility {
+    provider: Option<IRawElementProviderSimple>,
+    text: Arc<Mutex<CanvasText>>,
+    last_caret: Option<(u64, CursorPosition)>,
+    last_line: usize,
+}
+
+impl CanvasAccessibility {
+    /// Answers `WM_GETOBJECT` for the window's root element.
+    pub fn handle_get_object(&mut self, hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+        if lparam.0 as i32 != UiaRootObjectId {
+            return None;
+        }
+        let provider = self.provider.get_or_insert_with(|| {
+            CanvasProvider {
+                hwnd,
+                text: Arc::clone(&self.text),
+            }
+            .into()
+        });
+        Some(unsafe { UiaReturnRawElementProvider(hwnd, wparam, lparam, &*provider) })
+    }
+
+    /// Refreshes the reported paragraph and announces the caret when it moved.
+    pub fn sync(&mut self, title: &str, tab_id: u64, document: &DocumentModel, cursor: CursorPosition) {
+        let Some(provider) = self.provider.as_ref() else {
+            return;
+        };
+        if self.last_caret == Some((tab_id, cursor)) || !unsafe { UiaClientsAreListening() }.as_bool() {
+            return;
+        }
+        self.last_caret = Some((tab_id, cursor));
+
+        let text = collect_document_plain_text(document);
+        let position = text_position(document, cursor).unwrap_or(0);
+        let caret = describe_caret(&text, position);
+        let announcement = if caret.line == self.last_line {
+            format!("Line {}, column {}", caret.line, caret.column)
+        } else {
+            format!("{}. Line {}, column {}", caret.paragraph, caret.line, caret.column)
+        };
+        self.last_line = caret.line;
+        {
+            let mut shared = self.text.lock();
+            shared.name = title.to_string();
+            shared.paragraph = caret.paragraph;
+        }
+
+        let _ = unsafe {
+            UiaRaiseNotificationEvent(
+                provider,
+                NotificationKind_Other,
+                NotificationProcessing_MostRecent,
+                &BSTR::from(announcement.as_str()),
+                &BSTR::from(CARET_ACTIVITY_ID),
+            )
+        };
+    }
+
+    /// Releases the provider so UI Automation stops calling into a destroyed window.
+    pub fn disconnect(&mut self, hwnd: HWND) {
+        if let Some(provider) = self.provider.take() {
+            unsafe {
+                let _ = UiaReturnRawElementProvider(hwnd, WPARAM(0), LPARAM(0), None::<&IRawElementProviderSimple>);
+                let _ = UiaDisconnectProvider(&provider);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::model::{
+        BlockId, Indent, List, ListItem, Paragraph, ParagraphAlignment, ParagraphSpacing,
+    };
+
+    fn paragraph(id: u64, text: &str) -> Block {
+        Block::Paragraph(Paragraph {
+            id: BlockId(id),
+            runs: vec![Run {
+                text: text.to_string(),
+                ..Run::default()
+            }],
+            alignment: ParagraphAlignment::Left,
+            spacing: ParagraphSpacing::default(),
+            indent: Indent::default(),
+            style_id: None,
+            direction: Default::default(),
+            frame: None,
+        })
+    }
+
+    fn at(block_id: u64, offset: usize) -> CursorPosition {
+        CursorPosition {
+            block_id: BlockId(block_id),
+            offset,
+        }
+    }
+
+    #[test]
+    fn cursor_maps_to_linear_text_position() {
+        let mut document = DocumentModel::default();
+        document.content = vec![paragraph(1, "Hello"), Block::HorizontalRule, paragraph(2, "wörld")];
+        let text = collect_document_plain_text(&document);
+
+        assert_eq!(text_position(&document, at(1, 0)), Some(0));
+        assert_eq!(text_position(&document, at(1, 5)), Some(5));
+        // "Hello\n" precedes the second paragraph; offsets count characters, not bytes.
+        assert_eq!(text_position(&document, at(2, 3)), Some(9));
+        assert_eq!(text.chars().nth(9), Some('l'));
+        // Offsets past the end of a block clamp to it.
+        assert_eq!(text_position(&document, at(2, 40)), Some(11));
+        assert_eq!(text_position(&document, at(9, 0)), None);
+    }
+
+    #[test]
+    fn nested_blocks_follow_plain_text_order() {
+        let mut document = DocumentModel::default();
+        document.content = vec![
+            paragraph(1, "Intro"),
+            Block::List(List {
+                items: vec![
+                    ListItem {
+                        content: vec![paragraph(2, "one")],
+                        ..ListItem::default()
+                    },
+                    ListItem {
+                        content: vec![paragraph(3, "two")],
+                        ..ListItem::default()
+                    },
+                ],
+                ..List::default()
+            }),
+        ];
+        let text = collect_document_plain_text(&document);
+        let position = text_position(&document, at(3, 1)).expect("list item is a text block");
+        assert_eq!(text.chars().nth(position), Some('w'));
+    }
+
+    #[test]
+    fn caret_description_reports_line_column_and_paragraph() {
+        let text = "First line\nSecond one\n";
+        assert_eq!(paragraph_range(text, 13), 11..21);
+        assert_eq!(
+            describe_caret(text, 13),
+            CaretDescription {
+                line: 2,
+                column: 3,
+                paragraph: "Second one".to_string(),
+            }
+        );
+        assert_eq!(describe_caret(text, 0).column, 1);
+        assert_eq!(describe_caret("", 4).paragraph, "");
+    }
+}
@@ -1,9 +1,20 @@
 use std::time::{Duration, Instant};
 
+use windows::Win32::{
+    Foundation::HWND,
+    Graphics::Dwm::{DWM_TIMING_INFO, DwmGetCompositionTimingInfo},
+};
+
+/// Timer id used to wake the window for the next animation frame.
+pub const FRAME_TIMER_ID: usize = 0xF7A3;
+
+const FALLBACK_REFRESH_HZ: f64 = 60.0;
+
 #[derive(Debug, Clone)]
 pub struct Compositor {
     pub enabled: bool,
     pub target_frame_time: Duration,
+    pub timer_armed: bool,
     last_present: Instant,
 }
 
@@ -12,6 +23,7 @@ impl Default for Compositor {
         Self {
             enabled: true,
             target_frame_time: Duration::from_millis(16),
+            timer_armed: false,
             last_present: Instant::now(),
         }
     }
@@ -31,4 +43,86 @@ impl Compositor {
     pub fn should_throttle(&self) -> bool {
         self.enabled && self.last_present.elapsed() < self.target_frame_time
     }
+
+    pub fn set_refresh_rate(&mut self, hz: f64) {
+        let hz = if hz.is_finite() && hz >= 1.0 {
+            hz
+        } else {
+            FALLBACK_REFRESH_HZ
+        };
+        self.target_frame_time = Duration::from_secs_f64(1.0 / hz);
+    }
+
+    /// How long an animation frame should wait before rendering, or `None` to render now.
+    pub fn next_frame_delay(&self, now: Instant) -> Option<Duration> {
+        if !self.enabled {
+            return None;
+        }
+        frame_delay(self.last_present, now, self.target_frame_time)
+    }
+}
+
+/// Gates animation frames to one per `interval` since the last present.
+pub fn frame_delay(last_present: Instant, now: Instant, interval: Duration) -> Option<Duration> {
+    let elapsed = now.saturating_duration_since(last_present);
+    if elapsed >= interval {
+        None
+    } else {
+        Some(interval - elapsed)
+    }
 }
+
+/// Refresh rate of the display the compositor is presenting to, when DWM reports one.
+pub fn query_refresh_rate() -> Option<f64> {
+    let mut timing = DWM_TIMING_INFO {
+        cbSize: std::mem::size_of::<DWM_TIMING_INFO>() as u32,
+        ..Default::default()
+    };
+    // Since Windows 8.1 timing info is only available for the whole desktop (null hwnd).
+    unsafe { DwmGetCompositionTimingInfo(HWND::default(), &mut timing).ok()? };
+    let ratio = timing.rateRefresh;
+    if ratio.uiDenominator == 0 || ratio.uiNumerator == 0 {
+        return None;
+    }
+    Some(f64::from(ratio.uiNumerator) / f64::from(ratio.uiDenominator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INTERVAL: Duration = Duration::from_micros(16_667);
+
+    #[test]
+    fn frame_waits_for_the_rest_of_the_interval() {
+        let start = Instant::now();
+        assert_eq!(
+            frame_delay(start, start + Duration::from_millis(5), INTERVAL),
+            Some(INTERVAL - Duration::from_millis(5))
+        );
+        assert_eq!(frame_delay(start, start, INTERVAL), Some(INTERVAL));
+    }
+
+    #[test]
+    fn frame_renders_once_interval_has_elapsed() {
+        let start = Instant::now();
+        assert_eq!(frame_delay(start, start + INTERVAL, INTERVAL), None);
+        assert_eq!(frame_delay(start, start + Duration::from_millis(40), INTERVAL), None);
+    }
+
+    #[test]
+    fn refresh_rate_sets_interval_and_disabled_pacing_renders_now() {
+        let mut compositor = Compositor::default();
+        compositor.set_refresh_rate(120.0);
+        assert_eq!(compositor.target_frame_time, Duration::from_secs_f64(1.0 / 120.0));
+        compositor.set_refresh_rate(0.0);
+        assert_eq!(compositor.target_frame_time, Duration::from_secs_f64(1.0 / 60.0));
+
+        let frame_start = compositor.begin_frame();
+        let _ = compositor.end_frame(frame_start);
+        assert!(compositor.next_frame_delay(Instant::now()).is_some());
+        compositor.enabled = false;
+        assert!(compositor.next_frame_delay(Instant::now()).is_none());
+    }
+}
+
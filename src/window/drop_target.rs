@@ -0,0 +1,294 @@
+use std::{cell::Cell, path::PathBuf, sync::Arc};
+
+use parking_lot::Mutex;
+use windows::{
+    Win32::{
+        Foundation::{HGLOBAL, HWND, LPARAM, POINT, POINTL, WPARAM},
+        Graphics::Gdi::ScreenToClient,
+        System::{
+            Com::{DVASPECT_CONTENT, FORMATETC, IDataObject, TYMED_HGLOBAL},
+            Memory::{GlobalLock, GlobalSize, GlobalUnlock},
+            Ole::{
+                CF_HDROP, CF_UNICODETEXT, DROPEFFECT, DROPEFFECT_COPY, DROPEFFECT_NONE,
+                IDropTarget, IDropTarget_Impl, RegisterDragDrop, ReleaseStgMedium, RevokeDragDrop,
+            },
+            SystemServices::MODIFIERKEYS_FLAGS,
+        },
+        UI::{Shell::HDROP, WindowsAndMessaging::PostMessageW},
+    },
+    core::{Ref, Result, implement},
+};
+
+use super::integration::query_drop_files;
+use crate::{editor::clipboard::html_clipboard_format, ui::UiPoint};
+
+/// The payload an OLE drag offers, in the order a drop prefers it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropKind {
+    Files,
+    Html,
+    Text,
+}
+
+/// Files win so Explorer drags keep opening documents; HTML beats plain text because
+/// browsers and Word offer both and the HTML one is the selection as it was marked up.
+pub fn classify_drop_formats(has_files: bool, has_html: bool, has_text: bool) -> Option<DropKind> {
+    if has_files {
+        Some(DropKind::Files)
+    } else if has_html {
+        Some(DropKind::Html)
+    } else if has_text {
+        Some(DropKind::Text)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DropContent {
+    Files(Vec<PathBuf>),
+    Html(String),
+    Text(String),
+}
+
+/// Content dropped onto the window, with the drop point in client coordinates.
+#[derive(Debug, Clone)]
+pub struct ExternalDrop {
+    pub content: DropContent,
+    pub point: UiPoint,
+}
+
+/// OLE drop target for the main window. Drops are queued and announced with a posted
+/// message, so the window handles them from its own message loop.
+#[implement(IDropTarget)]
+struct WindowDropTarget {
+    hwnd: HWND,
+    message: u32,
+    kind: Cell<Option<DropKind>>,
+    drops: Arc<Mutex<Vec<ExternalDrop>>>,
+}
+
+impl WindowDropTarget {
+    fn effect(&self) -> DROPEFFECT {
+        if self.kind.get().is_some() {
+            DROPEFFECT_COPY
+        } else {
+            DROPEFFECT_NONE
+        }
+    }
+}
+
+impl IDropTarget_Impl for WindowDropTarget_Impl {
+    fn DragEnter(
+        &self,
+        pdataobj: Ref<IDataObject>,
+        _grfkeystate: MODIFIERKEYS_FLAGS,
+        _pt: &POINTL,
+        pdweffect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        self.kind.set(pdataobj.ok().ok().and_then(offered_kind));
+        set_effect(pdweffect, self.effect());
+        Ok(())
+    }
+
+    fn DragOver(
+        &self,
+        _grfkeystate: MODIFIERKEYS_FLAGS,
+        _pt: &POINTL,
+        pdweffect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        set_effect(pdweffect, self.effect());
+        Ok(())
+    }
+
+    fn DragLeave(&self) -> Result<()> {
+        self.kind.set(None);
+        Ok(())
+    }
+
+    fn Drop(
+        &self,
+        pdataobj: Ref<IDataObject>,
+        _grfkeystate: MODIFIERKEYS_FLAGS,
+        pt: &POINTL,
+        pdweffect: *mut DROPEFFECT,
+    ) -> Result<()> {
+        let content = match (self.kind.take(), pdataobj.ok()) {
+            (Some(kind), Ok(data)) => read_drop_content(data, kind),
+            _ => None,
+        };
+        let Some(content) = content else {
+            set_effect(pdweffect, DROPEFFECT_NONE);
+            return Ok(());
+        };
+
+        let mut client = POINT { x: pt.x, y: pt.y };
+        let _ = unsafe { ScreenToClient(self.hwnd, &mut client) };
+        self.drops.lock().push(ExternalDrop {
+            content,
+            point: UiPoint {
+                x: client.x as f32,
+                y: client.y as f32,
+            },
+        });
+        let _ = unsafe { PostMessageW(Some(self.hwnd), self.message, WPARAM(0), LPARAM(0)) };
+        set_effect(pdweffect, DROPEFFECT_COPY);
+        Ok(())
+    }
+}
+
+/// Accepts text, HTML and files dragged in from other applications.
+#[derive(Default)]
+pub struct WindowDropTargets {
+    target: Option<IDropTarget>,
+    drops: Arc<Mutex<Vec<ExternalDrop>>>,
+}
+
+impl WindowDropTargets {
+    /// Registers `hwnd` for OLE drops; each drop posts `message` back to it.
+    pub fn register(&mut self, hwnd: HWND, message: u32) -> Result<()> {
+        let target: IDropTarget = WindowDropTarget {
+            hwnd,
+            message,
+            kind: Cell::new(None),
+            drops: Arc::clone(&self.drops),
+        }
+        .into();
+        unsafe { RegisterDragDrop(hwnd, &target)? };
+        self.target = Some(target);
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, hwnd: HWND) {
+        if self.target.take().is_some() {
+            let _ = unsafe { RevokeDragDrop(hwnd) };
+        }
+    }
+
+    pub fn take_drops(&self) -> Vec<ExternalDrop> {
+        std::mem::take(&mut *self.drops.lock())
+    }
+}
+
+fn set_effect(pdweffect: *mut DROPEFFECT, effect: DROPEFFECT) {
+    if !pdweffect.is_null() {
+        unsafe { *pdweffect = effect };
+    }
+}
+
+fn hglobal_format(format: u16) -> FORMATETC {
+    FORMATETC {
+        cfFormat: format,
+        ptd: std::ptr::null_mut(),
+        dwAspect: DVASPECT_CONTENT.0,
+        lindex: -1,
+        tymed: TYMED_HGLOBAL.0 as u32,
+    }
+}
+
+fn html_format() -> u16 {
+    html_clipboard_format() as u16
+}
+
+fn offers(data: &IDataObject, format: u16) -> bool {
+    unsafe { data.QueryGetData(&hglobal_format(format)) }.is_ok()
+}
+
+fn offered_kind(data: &IDataObject) -> Option<DropKind> {
+    classify_drop_formats(
+        offers(data, CF_HDROP.0),
+        offers(data, html_format()),
+        offers(data, CF_UNICODETEXT.0),
+    )
+}
+
+fn read_drop_content(data: &IDataObject, kind: DropKind) -> Option<DropContent> {
+    match kind {
+        DropKind::Files => {
+            let files = with_global(data, CF_HDROP.0, |hglobal| {
+                Some(unsafe { query_drop_files(HDROP(hglobal.0)) })
+            })?;
+            (!files.is_empty()).then_some(DropContent::Files(files))
+        }
+        DropKind::Html => {
+            // "HTML Format" is UTF-8 with a header of offsets into the bytes.
+            let bytes = read_global_bytes(data, html_format())?;
+            let end = bytes
+                .iter()
+                .position(|&byte| byte == 0)
+                .unwrap_or(bytes.len());
+            Some(DropContent::Html(
+                String::from_utf8_lossy(&bytes[..end]).into_owned(),
+            ))
+        }
+        DropKind::Text => {
+            let bytes = read_global_bytes(data, CF_UNICODETEXT.0)?;
+            let units: Vec<u16> = bytes
+                .chunks_exact(2)
+                .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+                .take_while(|&unit| unit != 0)
+                .collect();
+            Some(DropContent::Text(
+                String::from_utf16_lossy(&units).replace("\r\n", "\n"),
+            ))
+        }
+    }
+}
+
+/// Fetches `format` from `data` as an HGLOBAL and releases it once `read` is done with it.
+fn with_global<T>(
+    data: &IDataObject,
+    format: u16,
+    read: impl FnOnce(HGLOBAL) -> Option<T>,
+) -> Option<T> {
+    let mut medium = unsafe { data.GetData(&hglobal_format(format)) }.ok()?;
+    let value = read(unsafe { medium.u.hGlobal });
+    unsafe { ReleaseStgMedium(&mut medium) };
+    value
+}
+
+fn read_global_bytes(data: &IDataObject, format: u16) -> Option<Vec<u8>> {
+    with_global(data, format, |hglobal| unsafe {
+        let ptr = GlobalLock(hglobal) as *const u8;
+        if ptr.is_null() {
+            return None;
+        }
+        let bytes = std::slice::from_raw_parts(ptr, GlobalSize(hglobal)).to_vec();
+        let _ = GlobalUnlock(hglobal);
+        Some(bytes)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DropKind, classify_drop_formats};
+
+    #[test]
+    fn file_lists_win_over_any_text_they_carry() {
+        assert_eq!(
+            classify_drop_formats(true, false, false),
+            Some(DropKind::Files)
+        );
+        assert_eq!(
+            classify_drop_formats(true, true, true),
+            Some(DropKind::Files)
+        );
+    }
+
+    #[test]
+    fn html_is_preferred_over_plain_text() {
+        assert_eq!(
+            classify_drop_formats(false, true, true),
+            Some(DropKind::Html)
+        );
+        assert_eq!(
+            classify_drop_formats(false, true, false),
+            Some(DropKind::Html)
+        );
+        assert_eq!(
+            classify_drop_formats(false, false, true),
+            Some(DropKind::Text)
+        );
+        assert_eq!(classify_drop_formats(false, false, false), None);
+    }
+}
@@ -1,4 +1,15 @@
-use crate::editor::commands::{shortcut_from_vk, Shortcut as EditorShortcut};
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    editor::commands::{shortcut_from_vk, Shortcut as EditorShortcut},
+    settings::{
+        keymap::{KeyChord, KeySequence},
+        schema::KeyboardShortcutsSettings,
+    },
+};
 
 #[derive(Clone, Copy, Debug, Default)]
 pub struct KeyModifiers {
@@ -32,6 +43,230 @@ pub enum InputEvent {
     FilesDropped(usize),
 }
 
+/// What a middle-button press does where it lands.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MiddleClick {
+    /// Close the tab under the pointer.
+    CloseTab(usize),
+    /// Paste clipboard text at the point clicked on the canvas.
+    Paste,
+    /// Drag the canvas around.
+    Pan,
+    Ignore,
+}
+
+/// Decides a middle click: a tab under the pointer is always closed, so pasting can't
+/// swallow the tab strip; on the canvas it pastes when `paste_enabled`, else pans.
+pub fn middle_click_target(
+    tab: Option<usize>,
+    on_canvas: bool,
+    paste_enabled: bool,
+) -> MiddleClick {
+    match (tab, on_canvas) {
+        (Some(index), _) => MiddleClick::CloseTab(index),
+        (None, true) if paste_enabled => MiddleClick::Paste,
+        (None, true) => MiddleClick::Pan,
+        (None, false) => MiddleClick::Ignore,
+    }
+}
+
+/// A part of the window F6 moves keyboard focus between.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FocusRegion {
+    #[default]
+    Document,
+    Sidebar,
+    Toolbar,
+    Tabs,
+    StatusBar,
+}
+
+impl FocusRegion {
+    /// The order F6 visits regions in; Shift+F6 goes the other way.
+    pub const CYCLE: [Self; 5] = [
+        Self::Document,
+        Self::Sidebar,
+        Self::Toolbar,
+        Self::Tabs,
+        Self::StatusBar,
+    ];
+
+    /// The region after this one, or before it when `backwards`, wrapping around and
+    /// skipping regions `shown` says are hidden. The document is always shown.
+    pub fn cycle(self, backwards: bool, shown: impl Fn(Self) -> bool) -> Self {
+        let count = Self::CYCLE.len();
+        let start = Self::CYCLE.iter().position(|r| *r == self).unwrap_or(0);
+        (1..count)
+            .map(|step| {
+                let index = if backwards {
+                    (start + count - step) % count
+                } else {
+                    (start + step) % count
+                };
+                Self::CYCLE[index]
+            })
+            .find(|region| *region == Self::Document || shown(*region))
+            .unwrap_or(Self::Document)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Document => "Document",
+            Self::Sidebar => "Sidebar",
+            Self::Toolbar => "Toolbar",
+            Self::Tabs => "Tabs",
+            Self::StatusBar => "Status bar",
+        }
+    }
+}
+
+/// How long the second key of a chord may take before the pending prefix is dropped.
+pub const CHORD_TIMEOUT: Duration = Duration::from_millis(1500);
+
+/// What a key press means to the configured shortcuts.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyDispatch {
+    /// Run this command id.
+    Command(String),
+    /// The first chord of a two-step shortcut; the next key decides.
+    Pending(KeyChord),
+    /// The key after a prefix finished no shortcut. Both are swallowed.
+    Cancelled { prefix: KeyChord, key: KeyChord },
+    /// Not a shortcut; handle the key as usual.
+    Unbound,
+}
+
+/// Maps pressed keys to the command ids `keyboard_shortcuts` binds them to, so rebinding a
+/// command in settings changes which keys run it. Two-step chords such as "Ctrl+K Ctrl+S"
+/// hold their prefix until the second key arrives or `CHORD_TIMEOUT` passes.
+#[derive(Debug, Clone)]
+pub struct ShortcutDispatcher {
+    commands: HashMap<KeyChord, String>,
+    chords: HashMap<(KeyChord, KeyChord), String>,
+    /// The `(command, keys)` pairs the maps were built from.
+    source: Vec<(String, String)>,
+    pending: Option<(KeyChord, Instant)>,
+}
+
+impl Default for ShortcutDispatcher {
+    fn default() -> Self {
+        Self::new(&KeyboardShortcutsSettings::default())
+    }
+}
+
+impl ShortcutDispatcher {
+    /// Cleared and unreadable bindings are skipped. Keys bound twice run the command that
+    /// sorts first until the conflict is resolved in settings.
+    pub fn new(shortcuts: &KeyboardShortcutsSettings) -> Self {
+        let mut commands = HashMap::new();
+        let mut chords = HashMap::new();
+        for (command, binding) in &shortcuts.bindings {
+            match KeySequence::parse(&binding.keys) {
+                Some(KeySequence {
+                    first,
+                    second: None,
+                }) => {
+                    commands.entry(first).or_insert_with(|| command.clone());
+                }
+                Some(KeySequence {
+                    first,
+                    second: Some(second),
+                }) => {
+                    chords
+                        .entry((first, second))
+                        .or_insert_with(|| command.clone());
+                }
+                None => {}
+            }
+        }
+        Self {
+            commands,
+            chords,
+            source: binding_source(shortcuts),
+            pending: None,
+        }
+    }
+
+    /// Rebuilds the maps if the bindings changed since they were built, and says whether
+    /// they did.
+    pub fn update(&mut self, shortcuts: &KeyboardShortcutsSettings) -> bool {
+        let changed = self.source != binding_source(shortcuts);
+        if changed {
+            *self = Self::new(shortcuts);
+        }
+        changed
+    }
+
+    /// The single-chord command bound to `vk` with exactly these modifiers held.
+    pub fn resolve(&self, vk: u32, modifiers: KeyModifiers) -> Option<&str> {
+        self.commands
+            .get(&chord_for(vk, modifiers))
+            .map(String::as_str)
+    }
+
+    /// Whether [`Self::press`] would do something with this key rather than leave it
+    /// unbound. Alt combinations arrive as system keys, which go to the menu otherwise.
+    pub fn claims(&self, vk: u32, modifiers: KeyModifiers) -> bool {
+        let key = chord_for(vk, modifiers);
+        !KeyChord::is_modifier_key(vk)
+            && (self.pending.is_some()
+                || self.commands.contains_key(&key)
+                || self.chords.keys().any(|(first, _)| *first == key))
+    }
+
+    /// Feeds one key press through the chord state. A chord that starts a two-step
+    /// shortcut waits for its second key, even when it is also bound on its own.
+    pub fn press(&mut self, vk: u32, modifiers: KeyModifiers, now: Instant) -> KeyDispatch {
+        if KeyChord::is_modifier_key(vk) {
+            return KeyDispatch::Unbound;
+        }
+        let key = chord_for(vk, modifiers);
+        self.expire(now);
+        if let Some((prefix, _)) = self.pending.take() {
+            return match self.chords.get(&(prefix, key)) {
+                Some(command) => KeyDispatch::Command(command.clone()),
+                None => KeyDispatch::Cancelled { prefix, key },
+            };
+        }
+        if self.chords.keys().any(|(first, _)| *first == key) {
+            self.pending = Some((key, now));
+            return KeyDispatch::Pending(key);
+        }
+        match self.commands.get(&key) {
+            Some(command) => KeyDispatch::Command(command.clone()),
+            None => KeyDispatch::Unbound,
+        }
+    }
+
+    /// Drops a prefix whose second key didn't come in time; true when one was dropped.
+    pub fn expire(&mut self, now: Instant) -> bool {
+        match self.pending {
+            Some((_, since)) if now.duration_since(since) >= CHORD_TIMEOUT => {
+                self.pending = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The first chord of a shortcut in progress, for the status bar.
+    pub fn pending_prefix(&self) -> Option<KeyChord> {
+        self.pending.map(|(prefix, _)| prefix)
+    }
+}
+
+fn chord_for(vk: u32, modifiers: KeyModifiers) -> KeyChord {
+    KeyChord::new(vk, modifiers.ctrl, modifiers.shift, modifiers.alt)
+}
+
+fn binding_source(shortcuts: &KeyboardShortcutsSettings) -> Vec<(String, String)> {
+    shortcuts
+        .bindings
+        .iter()
+        .map(|(command, binding)| (command.clone(), binding.keys.clone()))
+        .collect()
+}
+
 pub fn resolve_shortcut(vk: u32, modifiers: KeyModifiers) -> Option<AppShortcut> {
     match (vk, modifiers.ctrl, modifiers.shift, modifiers.alt) {
         (0xBC, true, false, false) => Some(AppShortcut::OpenSettings), // Ctrl+,
@@ -56,6 +291,54 @@ pub fn resolve_any_shortcut(vk: u32, modifiers: KeyModifiers) -> Option<Resolved
 mod tests {
     use super::*;
 
+    #[test]
+    fn middle_clicks_close_tabs_before_pasting_on_the_canvas() {
+        // Over a tab the tab closes, whether or not middle-click paste is on.
+        let close = MiddleClick::CloseTab;
+        assert_eq!(middle_click_target(Some(3), false, true), close(3));
+        assert_eq!(middle_click_target(Some(0), false, false), close(0));
+        // Even if the canvas reports the point too, the tab wins.
+        assert_eq!(middle_click_target(Some(2), true, true), close(2));
+
+        assert_eq!(middle_click_target(None, true, true), MiddleClick::Paste);
+        assert_eq!(middle_click_target(None, true, false), MiddleClick::Pan);
+        assert_eq!(middle_click_target(None, false, true), MiddleClick::Ignore);
+    }
+
+    #[test]
+    fn f6_cycles_focus_through_shown_regions_and_wraps() {
+        let all = |_| true;
+        let mut region = FocusRegion::Document;
+        let mut visited = Vec::new();
+        for _ in 0..6 {
+            region = region.cycle(false, all);
+            visited.push(region);
+        }
+        assert_eq!(
+            visited,
+            [
+                FocusRegion::Sidebar,
+                FocusRegion::Toolbar,
+                FocusRegion::Tabs,
+                FocusRegion::StatusBar,
+                FocusRegion::Document,
+                FocusRegion::Sidebar,
+            ]
+        );
+
+        // Shift+F6 walks back, wrapping from the document to the status bar.
+        let document = FocusRegion::Document;
+        assert_eq!(document.cycle(true, all), FocusRegion::StatusBar);
+        assert_eq!(FocusRegion::Toolbar.cycle(true, all), FocusRegion::Sidebar);
+
+        // Hidden regions are skipped; with everything hidden focus stays on the document.
+        let no_sidebar = |region| region != FocusRegion::Sidebar;
+        assert_eq!(document.cycle(false, no_sidebar), FocusRegion::Toolbar);
+        assert_eq!(FocusRegion::Toolbar.cycle(true, no_sidebar), document);
+        assert_eq!(document.cycle(false, |_| false), document);
+        assert_eq!(FocusRegion::StatusBar.cycle(false, |_| false), document);
+    }
+
     #[test]
     fn resolves_editor_and_app_shortcuts() {
         let ctrl = KeyModifiers {
@@ -69,4 +352,104 @@ mod tests {
         );
         assert_eq!(resolve_any_shortcut(0xBC, ctrl), Some(ResolvedShortcut::App(AppShortcut::OpenSettings)));
     }
+
+    #[test]
+    fn dispatcher_follows_rebound_shortcuts() {
+        let ctrl = KeyModifiers {
+            ctrl: true,
+            ..KeyModifiers::default()
+        };
+        let ctrl_alt = KeyModifiers { alt: true, ..ctrl };
+        let ctrl_shift = KeyModifiers {
+            shift: true,
+            ..ctrl
+        };
+
+        // The defaults are the keys the window used to check for directly.
+        let dispatcher = ShortcutDispatcher::default();
+        assert_eq!(dispatcher.resolve(0x53, ctrl), Some("file.save"));
+        assert_eq!(dispatcher.resolve(0x53, ctrl_shift), Some("file.save_as"));
+        assert_eq!(dispatcher.resolve(0x53, ctrl_alt), Some("file.save_all"));
+        assert_eq!(
+            dispatcher.resolve(0x50, ctrl_shift),
+            Some("view.command_palette")
+        );
+        assert_eq!(dispatcher.resolve(0x53, KeyModifiers::default()), None);
+
+        let mut shortcuts = KeyboardShortcutsSettings::default();
+        shortcuts.set_binding("file.save", "Ctrl+Alt+W");
+        let dispatcher = ShortcutDispatcher::new(&shortcuts);
+        assert_eq!(dispatcher.resolve(0x57, ctrl_alt), Some("file.save"));
+        assert_eq!(dispatcher.resolve(0x53, ctrl), None);
+
+        // A cleared binding no longer runs anything.
+        shortcuts.clear_binding("file.print");
+        let dispatcher = ShortcutDispatcher::new(&shortcuts);
+        assert_eq!(dispatcher.resolve(0x50, ctrl), None);
+
+        // Alt-only bindings are claimed from the system keys; other Alt keys are left alone.
+        let alt = KeyModifiers {
+            alt: true,
+            ..KeyModifiers::default()
+        };
+        shortcuts.set_binding("view.toggle_sidebar", "Alt+1");
+        let dispatcher = ShortcutDispatcher::new(&shortcuts);
+        assert!(dispatcher.claims(0x31, alt));
+        assert!(!dispatcher.claims(0x73, alt));
+        assert!(!dispatcher.claims(0x12, alt));
+    }
+
+    #[test]
+    fn chords_wait_for_their_second_key() {
+        let ctrl = KeyModifiers {
+            ctrl: true,
+            ..KeyModifiers::default()
+        };
+        let ctrl_k = KeyChord::new(0x4B, true, false, false);
+        let mut dispatcher = ShortcutDispatcher::default();
+        let start = Instant::now();
+
+        // Prefix, then completion. Releasing and pressing Ctrl again doesn't count.
+        assert_eq!(
+            dispatcher.press(0x4B, ctrl, start),
+            KeyDispatch::Pending(ctrl_k)
+        );
+        assert_eq!(dispatcher.pending_prefix(), Some(ctrl_k));
+        assert_eq!(dispatcher.press(0x11, ctrl, start), KeyDispatch::Unbound);
+        assert_eq!(
+            dispatcher.press(0x53, ctrl, start + Duration::from_millis(300)),
+            KeyDispatch::Command("view.keyboard_shortcuts".to_string())
+        );
+        assert_eq!(dispatcher.pending_prefix(), None);
+
+        // A second key that finishes nothing cancels the chord and is swallowed.
+        dispatcher.press(0x4B, ctrl, start);
+        assert_eq!(
+            dispatcher.press(0x58, KeyModifiers::default(), start),
+            KeyDispatch::Cancelled {
+                prefix: ctrl_k,
+                key: KeyChord::new(0x58, false, false, false),
+            }
+        );
+        assert_eq!(
+            dispatcher.press(0x53, ctrl, start),
+            KeyDispatch::Command("file.save".to_string())
+        );
+
+        // Waiting too long drops the prefix; the late key acts on its own.
+        dispatcher.press(0x4B, ctrl, start);
+        assert!(!dispatcher.expire(start + CHORD_TIMEOUT / 2));
+        assert!(dispatcher.expire(start + CHORD_TIMEOUT));
+        assert_eq!(dispatcher.pending_prefix(), None);
+        dispatcher.press(0x4B, ctrl, start);
+        assert_eq!(
+            dispatcher.press(0x53, ctrl, start + CHORD_TIMEOUT * 2),
+            KeyDispatch::Command("file.save".to_string())
+        );
+
+        // Unchanged bindings keep a pending prefix across updates.
+        dispatcher.press(0x4B, ctrl, start);
+        dispatcher.update(&KeyboardShortcutsSettings::default());
+        assert_eq!(dispatcher.pending_prefix(), Some(ctrl_k));
+    }
 }
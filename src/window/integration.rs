@@ -1,13 +1,26 @@
 use std::{
-    ffi::OsString,
-    os::windows::ffi::{OsStrExt, OsStringExt},
+    ffi::{OsStr, OsString},
+    io,
+    os::windows::{
+        ffi::{OsStrExt, OsStringExt},
+        process::CommandExt,
+    },
     path::{Path, PathBuf},
+    process::Command,
 };
 
 use windows::{
     Win32::{
         Foundation::HWND,
         Graphics::Gdi::DeleteDC,
+        Storage::EnhancedStorage::PKEY_Title,
+        System::{
+            Com::{
+                CLSCTX_INPROC_SERVER, CoCreateInstance,
+                StructuredStorage::{PROPVARIANT, PVCHF_DEFAULT, PropVariantChangeType},
+            },
+            Variant::VT_LPWSTR,
+        },
         UI::{
             Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW},
             Controls::Dialogs::{
@@ -15,17 +28,23 @@ use windows::{
                 OFN_OVERWRITEPROMPT, OFN_PATHMUSTEXIST, OPENFILENAMEW, PD_NOSELECTION, PD_PAGENUMS,
                 PD_RETURNDC, PD_USEDEVMODECOPIESANDCOLLATE, PRINTDLGW, PrintDlgW,
             },
-            Shell::{DragFinish, DragQueryFileW, HDROP, SHARD_PATHW, SHAddToRecentDocs},
+            Shell::{
+                Common::{IObjectArray, IObjectCollection},
+                DestinationList, DragFinish, DragQueryFileW, EnumerableObjectCollection, HDROP,
+                ICustomDestinationList, IShellLinkW,
+                PropertiesSystem::IPropertyStore,
+                SHARD_PATHW, SHAddToRecentDocs, ShellLink,
+            },
             WindowsAndMessaging::{
                 SPI_GETCLIENTAREAANIMATION, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
                 SystemParametersInfoW,
             },
         },
     },
-    core::w,
+    core::{HSTRING, Interface, w},
 };
 
-use crate::ui::AccessibilityPreferences;
+use crate::{settings::schema::DefaultOpenFolder, ui::AccessibilityPreferences};
 
 pub const SUPPORTED_DOCUMENT_EXTENSIONS: &[&str] = &["docx", "pdf", "txt", "md", "rtf"];
 pub const SUPPORTED_IMAGE_EXTENSIONS: &[&str] = &[
@@ -45,24 +64,69 @@ pub struct DropPayload {
     pub action: DropAction,
 }
 
+/// A fixed entry in the jump list's Tasks section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JumpListTask {
+    NewDocument,
+    OpenFile,
+}
+
+impl JumpListTask {
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::NewDocument => "New Document",
+            Self::OpenFile => "Open File",
+        }
+    }
+
+    /// The command-line switch a relaunch from this task passes to Doco.
+    pub fn argument(self) -> &'static str {
+        match self {
+            Self::NewDocument => "--new-document",
+            Self::OpenFile => "--open-file",
+        }
+    }
+
+    fn from_argument(argument: &OsStr) -> Option<Self> {
+        [Self::NewDocument, Self::OpenFile]
+            .into_iter()
+            .find(|task| argument == task.argument())
+    }
+}
+
+/// A jump list entry as the taskbar shows it: a title, and the arguments Doco relaunches with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JumpListItem {
+    pub title: String,
+    pub arguments: String,
+}
+
 #[derive(Debug, Default)]
 pub struct JumpListState {
     pub recent_files: Vec<PathBuf>,
-    pub pinned_tasks: Vec<String>,
+    pub pinned_tasks: Vec<JumpListTask>,
+    /// `files.recent_files_count`: how many recent files are kept, newest first.
+    pub recent_limit: usize,
 }
 
 impl JumpListState {
-    pub fn with_default_tasks() -> Self {
+    pub fn with_default_tasks(recent_limit: usize) -> Self {
         Self {
             recent_files: Vec::new(),
-            pinned_tasks: vec!["New Document".to_string(), "Open File".to_string()],
+            pinned_tasks: vec![JumpListTask::NewDocument, JumpListTask::OpenFile],
+            recent_limit,
         }
     }
 
+    /// Applies a new recent-files count, dropping the oldest entries beyond it.
+    pub fn set_recent_limit(&mut self, limit: usize) {
+        self.recent_limit = limit;
+        self.recent_files.truncate(limit);
+        let _ = self.publish();
+    }
+
     pub fn add_recent_file(&mut self, path: PathBuf) {
-        self.recent_files.retain(|existing| existing != &path);
-        self.recent_files.insert(0, path.clone());
-        self.recent_files.truncate(20);
+        self.remember_recent(path.clone());
 
         // Registers with Windows shell recent-docs list (backing Jump List source).
         let wide = path
@@ -73,6 +137,57 @@ impl JumpListState {
         unsafe {
             SHAddToRecentDocs(SHARD_PATHW.0 as u32, Some(wide.as_ptr().cast()));
         }
+        // The jump list is a convenience; a shell that refuses it shouldn't bother the user.
+        let _ = self.publish();
+    }
+
+    pub fn task_items(&self) -> Vec<JumpListItem> {
+        self.pinned_tasks
+            .iter()
+            .map(|task| JumpListItem {
+                title: task.title().to_string(),
+                arguments: task.argument().to_string(),
+            })
+            .collect()
+    }
+
+    /// Newest first; each relaunches Doco with the file's quoted path.
+    pub fn recent_items(&self) -> Vec<JumpListItem> {
+        self.recent_files
+            .iter()
+            .map(|path| JumpListItem {
+                title: path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| path.display().to_string()),
+                arguments: format!("\"{}\"", path.display()),
+            })
+            .collect()
+    }
+
+    /// Replaces the taskbar jump list with a Recent category and the pinned tasks.
+    pub fn publish(&self) -> windows::core::Result<()> {
+        let exe = std::env::current_exe()?;
+        unsafe {
+            let list: ICustomDestinationList =
+                CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+            let mut max_slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut max_slots)?;
+
+            let mut recent = self.recent_items();
+            recent.truncate(max_slots as usize);
+            if !recent.is_empty() {
+                list.AppendCategory(w!("Recent"), &shell_link_collection(&exe, &recent)?)?;
+            }
+            list.AddUserTasks(&shell_link_collection(&exe, &self.task_items())?)?;
+            list.CommitList()
+        }
+    }
+
+    fn remember_recent(&mut self, path: PathBuf) {
+        self.recent_files.retain(|existing| existing != &path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(self.recent_limit);
     }
 }
 
@@ -93,15 +208,76 @@ impl PrintState {
     }
 }
 
-pub fn parse_startup_files_from_cli() -> Vec<PathBuf> {
-    std::env::args_os()
-        .skip(1)
-        .map(PathBuf::from)
-        .filter(|path| is_supported_path(path))
-        .collect()
+/// Builds the shell links a jump list section holds, each launching `exe` with an item's
+/// arguments.
+fn shell_link_collection(
+    exe: &Path,
+    items: &[JumpListItem],
+) -> windows::core::Result<IObjectCollection> {
+    let exe = HSTRING::from(exe.as_os_str());
+    unsafe {
+        let collection: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        for item in items {
+            let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+            link.SetPath(&exe)?;
+            link.SetArguments(&HSTRING::from(item.arguments.as_str()))?;
+            link.SetIconLocation(&exe, 0)?;
+
+            // Jump list titles must be VT_LPWSTR; PROPVARIANT converts from a BSTR.
+            let mut title = PROPVARIANT::default();
+            PropVariantChangeType(
+                &mut title,
+                &PROPVARIANT::from(item.title.as_str()),
+                PVCHF_DEFAULT,
+                VT_LPWSTR,
+            )?;
+            let store: IPropertyStore = link.cast()?;
+            store.SetValue(&PKEY_Title, &title)?;
+            store.Commit()?;
+            collection.AddObject(&link)?;
+        }
+        Ok(collection)
+    }
+}
+
+/// What Doco was launched to do: open files, or run a jump list task.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct StartupArgs {
+    pub files: Vec<PathBuf>,
+    pub task: Option<JumpListTask>,
+}
+
+pub fn parse_startup_args(args: impl IntoIterator<Item = OsString>) -> StartupArgs {
+    let mut startup = StartupArgs::default();
+    for arg in args {
+        if let Some(task) = JumpListTask::from_argument(&arg) {
+            startup.task = Some(task);
+        } else {
+            let path = PathBuf::from(arg);
+            if is_supported_path(&path) {
+                startup.files.push(path);
+            }
+        }
+    }
+    startup
+}
+
+pub fn parse_startup_args_from_cli() -> StartupArgs {
+    parse_startup_args(std::env::args_os().skip(1))
 }
 
 pub unsafe fn extract_drop_payload(hdrop: HDROP) -> DropPayload {
+    let files = unsafe { query_drop_files(hdrop) };
+    unsafe { DragFinish(hdrop) };
+
+    let action = classify_drop(files.as_slice());
+    DropPayload { files, action }
+}
+
+/// Paths listed in `hdrop`. Unlike `extract_drop_payload` this leaves the handle alive, as
+/// an OLE drop's medium is released by its owner.
+pub unsafe fn query_drop_files(hdrop: HDROP) -> Vec<PathBuf> {
     let count = unsafe { DragQueryFileW(hdrop, u32::MAX, None) };
     let mut files = Vec::with_capacity(count as usize);
 
@@ -120,11 +296,7 @@ pub unsafe fn extract_drop_payload(hdrop: HDROP) -> DropPayload {
         let path = OsString::from_wide(&buffer[..written as usize]);
         files.push(PathBuf::from(path));
     }
-
-    unsafe { DragFinish(hdrop) };
-
-    let action = classify_drop(files.as_slice());
-    DropPayload { files, action }
+    files
 }
 
 pub fn classify_drop(files: &[PathBuf]) -> DropAction {
@@ -191,6 +363,50 @@ pub fn file_association_registry_commands(exe_path: &Path) -> Vec<String> {
     commands
 }
 
+/// How much of a file's path "Copy file path" and "Copy filename" put on the clipboard.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathCopyKind {
+    FullPath,
+    FileName,
+}
+
+/// Clipboard text for `path`: the whole path, or only its last component.
+pub fn path_clipboard_text(path: &Path, kind: PathCopyKind) -> String {
+    match kind {
+        PathCopyKind::FullPath => path.display().to_string(),
+        PathCopyKind::FileName => path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string()),
+    }
+}
+
+/// The file a tab's path commands act on, or the status to show for a tab never saved.
+pub fn saved_file_path(file_path: Option<&Path>) -> Result<&Path, &'static str> {
+    file_path.ok_or("This document hasn't been saved to a file yet")
+}
+
+/// Opens an Explorer window on the folder holding `path`, with the file selected.
+pub fn reveal_in_explorer(path: &Path) -> io::Result<()> {
+    // Explorer parses its own command line; a quoted path after "/select," is the form it
+    // accepts for names with spaces.
+    Command::new("explorer.exe")
+        .raw_arg(format!("/select,\"{}\"", path.display()))
+        .spawn()
+        .map(|_| ())
+}
+
+/// Opens an HTTPS link, such as a release page, in the default browser.
+pub fn open_in_browser(url: &str) -> io::Result<()> {
+    if !url.to_ascii_lowercase().starts_with("https://") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "only HTTPS links are opened",
+        ));
+    }
+    Command::new("explorer.exe").arg(url).spawn().map(|_| ())
+}
+
 #[derive(Debug, Clone, Copy, Default)]
 pub struct PrintDialogResult {
     pub page_range: Option<(u32, u32)>,
@@ -284,7 +500,76 @@ fn normalize_page_range(from: u16, to: u16) -> Option<(u32, u32)> {
     Some((start, end))
 }
 
-pub fn pick_image_file(hwnd: HWND) -> Option<PathBuf> {
+/// Expands `%NAME%` references in `raw` with `lookup`, leaving unknown names as written.
+pub fn expand_env_vars(raw: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut out = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('%') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find('%') else {
+            rest = &rest[start..];
+            break;
+        };
+        let name = &after[..end];
+        match lookup(name).filter(|_| !name.is_empty()) {
+            Some(value) => out.push_str(value.as_str()),
+            None => {
+                out.push('%');
+                out.push_str(name);
+                out.push('%');
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// The folder open, save and image dialogs start in under `policy`. A last-used or specific
+/// folder that no longer exists falls back to `documents`, and None leaves it to Windows.
+pub fn initial_dialog_dir(
+    policy: &DefaultOpenFolder,
+    last_used: Option<&Path>,
+    documents: Option<PathBuf>,
+) -> Option<PathBuf> {
+    let preferred = match policy {
+        DefaultOpenFolder::LastUsed => last_used.map(Path::to_path_buf),
+        DefaultOpenFolder::Documents => None,
+        DefaultOpenFolder::SpecificPath(raw) => {
+            let expanded = expand_env_vars(raw.trim(), |name| std::env::var(name).ok());
+            Some(PathBuf::from(expanded))
+        }
+    };
+    preferred
+        .filter(|dir| dir.is_dir())
+        .or_else(|| documents.filter(|dir| dir.is_dir()))
+}
+
+/// The folder a file dialog starts in, kept as the NUL-terminated wide string
+/// `lpstrInitialDir` points at while the dialog is open.
+struct InitialDir(Option<Vec<u16>>);
+
+impl InitialDir {
+    fn new(dir: Option<&Path>) -> Self {
+        Self(dir.map(|dir| {
+            dir.as_os_str()
+                .encode_wide()
+                .chain(std::iter::once(0))
+                .collect()
+        }))
+    }
+
+    /// Null without a folder, which leaves the choice to Windows.
+    fn as_pcwstr(&self) -> windows::core::PCWSTR {
+        self.0
+            .as_ref()
+            .map(|dir| windows::core::PCWSTR::from_raw(dir.as_ptr()))
+            .unwrap_or(windows::core::PCWSTR::null())
+    }
+}
+
+pub fn pick_image_file(hwnd: HWND, initial_dir: Option<&Path>) -> Option<PathBuf> {
     let mut file_buffer = vec![0u16; 260];
     let mut filter = String::new();
     filter.push_str("Image Files (*.png;*.jpg;*.jpeg;*.bmp;*.gif;*.webp;*.tif;*.tiff;*.svg)\0");
@@ -292,12 +577,14 @@ pub fn pick_image_file(hwnd: HWND) -> Option<PathBuf> {
     filter.push_str("All Files (*.*)\0*.*\0\0");
     let filter_wide = filter.encode_utf16().collect::<Vec<u16>>();
 
+    let initial_dir = InitialDir::new(initial_dir);
     let mut open = OPENFILENAMEW {
         lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
         hwndOwner: hwnd,
         lpstrFilter: windows::core::PCWSTR::from_raw(filter_wide.as_ptr()),
         lpstrFile: windows::core::PWSTR(file_buffer.as_mut_ptr()),
         nMaxFile: file_buffer.len() as u32,
+        lpstrInitialDir: initial_dir.as_pcwstr(),
         lpstrTitle: w!("Insert Image"),
         Flags: OFN_EXPLORER | OFN_FILEMUSTEXIST | OFN_PATHMUSTEXIST,
         ..Default::default()
@@ -319,24 +606,27 @@ pub fn pick_image_file(hwnd: HWND) -> Option<PathBuf> {
     Some(PathBuf::from(path))
 }
 
-pub fn pick_open_file(hwnd: HWND) -> Option<PathBuf> {
+pub fn pick_open_file(hwnd: HWND, initial_dir: Option<&Path>) -> Option<PathBuf> {
     let mut file_buffer = vec![0u16; 260];
     let mut filter = String::new();
-    filter.push_str("Supported Documents (*.docx;*.txt;*.md;*.rtf;*.pdf)\0");
-    filter.push_str("*.docx;*.txt;*.md;*.rtf;*.pdf\0");
+    filter.push_str("Supported Documents (*.docx;*.txt;*.md;*.rtf;*.pdf;*.epub)\0");
+    filter.push_str("*.docx;*.txt;*.md;*.rtf;*.pdf;*.epub\0");
     filter.push_str("Word Document (*.docx)\0*.docx\0");
     filter.push_str("Text Document (*.txt)\0*.txt\0");
     filter.push_str("Markdown (*.md)\0*.md\0");
     filter.push_str("PDF (*.pdf)\0*.pdf\0");
+    filter.push_str("EPUB (*.epub)\0*.epub\0");
     filter.push_str("All Files (*.*)\0*.*\0\0");
     let filter_wide = filter.encode_utf16().collect::<Vec<u16>>();
 
+    let initial_dir = InitialDir::new(initial_dir);
     let mut open = OPENFILENAMEW {
         lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
         hwndOwner: hwnd,
         lpstrFilter: windows::core::PCWSTR::from_raw(filter_wide.as_ptr()),
         lpstrFile: windows::core::PWSTR(file_buffer.as_mut_ptr()),
         nMaxFile: file_buffer.len() as u32,
+        lpstrInitialDir: initial_dir.as_pcwstr(),
         lpstrTitle: w!("Open Document"),
         Flags: OFN_EXPLORER | OFN_FILEMUSTEXIST | OFN_PATHMUSTEXIST,
         ..Default::default()
@@ -363,6 +653,54 @@ pub fn pick_save_file(
     hwnd: HWND,
     suggested_name: &str,
     suggested_extension: &str,
+    initial_dir: Option<&Path>,
+) -> Option<PathBuf> {
+    let mut filter = String::new();
+    filter.push_str("Word Document (*.docx)\0*.docx\0");
+    filter.push_str("PDF (*.pdf)\0*.pdf\0");
+    filter.push_str("Text Document (*.txt)\0*.txt\0");
+    filter.push_str("Markdown (*.md)\0*.md\0");
+    filter.push_str("HTML (*.html)\0*.html;*.htm\0");
+    filter.push_str("RTF (*.rtf)\0*.rtf\0");
+    filter.push_str("All Files (*.*)\0*.*\0\0");
+    save_file_dialog(
+        hwnd,
+        suggested_name,
+        suggested_extension,
+        initial_dir,
+        &filter,
+        w!("Save Document"),
+    )
+}
+
+/// Asks where to save an image, offering PNG and JPEG.
+pub fn pick_save_image_file(
+    hwnd: HWND,
+    suggested_name: &str,
+    suggested_extension: &str,
+    initial_dir: Option<&Path>,
+) -> Option<PathBuf> {
+    let mut filter = String::new();
+    filter.push_str("PNG Image (*.png)\0*.png\0");
+    filter.push_str("JPEG Image (*.jpg;*.jpeg)\0*.jpg;*.jpeg\0");
+    filter.push_str("All Files (*.*)\0*.*\0\0");
+    save_file_dialog(
+        hwnd,
+        suggested_name,
+        suggested_extension,
+        initial_dir,
+        &filter,
+        w!("Save Image As"),
+    )
+}
+
+fn save_file_dialog(
+    hwnd: HWND,
+    suggested_name: &str,
+    suggested_extension: &str,
+    initial_dir: Option<&Path>,
+    filter: &str,
+    title: windows::core::PCWSTR,
 ) -> Option<PathBuf> {
     let mut file_buffer = vec![0u16; 260];
     let suggested = if suggested_name.is_empty() {
@@ -375,14 +713,6 @@ pub fn pick_save_file(
     file_buffer[..suggested_len].copy_from_slice(&suggested_w[..suggested_len]);
     file_buffer[suggested_len] = 0;
 
-    let mut filter = String::new();
-    filter.push_str("Word Document (*.docx)\0*.docx\0");
-    filter.push_str("PDF (*.pdf)\0*.pdf\0");
-    filter.push_str("Text Document (*.txt)\0*.txt\0");
-    filter.push_str("Markdown (*.md)\0*.md\0");
-    filter.push_str("HTML (*.html)\0*.html;*.htm\0");
-    filter.push_str("RTF (*.rtf)\0*.rtf\0");
-    filter.push_str("All Files (*.*)\0*.*\0\0");
     let filter_wide = filter.encode_utf16().collect::<Vec<u16>>();
     let def_ext = suggested_extension
         .trim_start_matches('.')
@@ -390,6 +720,7 @@ pub fn pick_save_file(
         .chain(std::iter::once(0))
         .collect::<Vec<u16>>();
 
+    let initial_dir = InitialDir::new(initial_dir);
     let mut open = OPENFILENAMEW {
         lStructSize: std::mem::size_of::<OPENFILENAMEW>() as u32,
         hwndOwner: hwnd,
@@ -397,7 +728,8 @@ pub fn pick_save_file(
         lpstrDefExt: windows::core::PCWSTR::from_raw(def_ext.as_ptr()),
         lpstrFile: windows::core::PWSTR(file_buffer.as_mut_ptr()),
         nMaxFile: file_buffer.len() as u32,
-        lpstrTitle: w!("Save Document"),
+        lpstrInitialDir: initial_dir.as_pcwstr(),
+        lpstrTitle: title,
         Flags: OFN_EXPLORER | OFN_PATHMUSTEXIST | OFN_OVERWRITEPROMPT,
         ..Default::default()
     };
@@ -423,12 +755,23 @@ mod tests {
     use std::path::PathBuf;
 
     use super::{
+        DefaultOpenFolder,
         DropAction,
+        JumpListItem,
+        JumpListState,
+        JumpListTask,
+        PathCopyKind,
+        StartupArgs,
         classify_drop,
+        expand_env_vars,
         file_association_registry_commands,
+        initial_dialog_dir,
         is_image_path,
         normalize_page_range,
+        parse_startup_args,
+        path_clipboard_text,
         query_accessibility_preferences,
+        saved_file_path,
     };
 
     #[test]
@@ -447,6 +790,145 @@ mod tests {
         assert!(commands.iter().any(|line| line.contains("%1")));
     }
 
+    #[test]
+    fn copied_paths_are_full_or_name_only() {
+        let path = PathBuf::from("C:\\Users\\Ada\\My Notes\\plan v2.docx");
+        assert_eq!(
+            path_clipboard_text(&path, PathCopyKind::FullPath),
+            "C:\\Users\\Ada\\My Notes\\plan v2.docx"
+        );
+        assert_eq!(
+            path_clipboard_text(&path, PathCopyKind::FileName),
+            "plan v2.docx"
+        );
+        // A bare drive has no file name, so the whole path is the best there is.
+        assert_eq!(
+            path_clipboard_text(&PathBuf::from("C:\\"), PathCopyKind::FileName),
+            "C:\\"
+        );
+    }
+
+    #[test]
+    fn env_vars_expand_and_unknown_names_stay_literal() {
+        let lookup = |name: &str| (name == "USERPROFILE").then(|| "C:\\Users\\Ada".to_string());
+        assert_eq!(
+            expand_env_vars("%USERPROFILE%\\Documents", lookup),
+            "C:\\Users\\Ada\\Documents"
+        );
+        assert_eq!(expand_env_vars("%NOPE%\\x", lookup), "%NOPE%\\x");
+        assert_eq!(expand_env_vars("100% done", lookup), "100% done");
+    }
+
+    #[test]
+    fn dialog_dir_follows_policy_and_falls_back_to_documents() {
+        let existing = std::env::temp_dir();
+        let documents = existing.join("doco-documents-stand-in");
+        std::fs::create_dir_all(&documents).unwrap();
+        let missing = existing.join("doco-folder-that-does-not-exist");
+
+        let last_used = initial_dialog_dir(
+            &DefaultOpenFolder::LastUsed,
+            Some(existing.as_path()),
+            Some(documents.clone()),
+        );
+        assert_eq!(last_used, Some(existing.clone()));
+        let nothing_used_yet =
+            initial_dialog_dir(&DefaultOpenFolder::LastUsed, None, Some(documents.clone()));
+        assert_eq!(nothing_used_yet, Some(documents.clone()));
+
+        let documents_policy = initial_dialog_dir(
+            &DefaultOpenFolder::Documents,
+            Some(existing.as_path()),
+            Some(documents.clone()),
+        );
+        assert_eq!(documents_policy, Some(documents.clone()));
+
+        let specific = DefaultOpenFolder::SpecificPath(existing.display().to_string());
+        assert_eq!(
+            initial_dialog_dir(&specific, None, Some(documents.clone())),
+            Some(existing.clone())
+        );
+        let stale = DefaultOpenFolder::SpecificPath(missing.display().to_string());
+        assert_eq!(
+            initial_dialog_dir(&stale, None, Some(documents.clone())),
+            Some(documents.clone())
+        );
+        assert_eq!(initial_dialog_dir(&stale, None, Some(missing)), None);
+    }
+
+    #[test]
+    fn recent_files_keep_only_the_newest_up_to_the_limit() {
+        let limit = 10;
+        let mut jump_list = JumpListState::with_default_tasks(limit);
+        let paths = (0..limit + 5)
+            .map(|i| PathBuf::from(format!("C:\\docs\\{i}.md")))
+            .collect::<Vec<_>>();
+        for path in &paths {
+            jump_list.remember_recent(path.clone());
+        }
+        let newest_first = paths.iter().rev().take(limit).cloned().collect::<Vec<_>>();
+        assert_eq!(jump_list.recent_files, newest_first);
+
+        // Reopening moves a file to the front instead of duplicating it.
+        jump_list.remember_recent(paths[10].clone());
+        assert_eq!(jump_list.recent_files.len(), limit);
+        assert_eq!(jump_list.recent_files[0], paths[10]);
+
+        jump_list.set_recent_limit(3);
+        assert_eq!(jump_list.recent_files.len(), 3);
+        assert_eq!(jump_list.recent_files[0], paths[10]);
+    }
+
+    #[test]
+    fn jump_list_items_relaunch_recent_files_and_tasks() {
+        let mut jump_list = JumpListState::with_default_tasks(10);
+        jump_list.recent_files = vec![
+            PathBuf::from("C:\\docs\\Quarterly Report.docx"),
+            PathBuf::from("C:\\notes\\todo.md"),
+        ];
+
+        assert_eq!(
+            jump_list.recent_items(),
+            vec![
+                JumpListItem {
+                    title: "Quarterly Report.docx".to_string(),
+                    arguments: "\"C:\\docs\\Quarterly Report.docx\"".to_string(),
+                },
+                JumpListItem {
+                    title: "todo.md".to_string(),
+                    arguments: "\"C:\\notes\\todo.md\"".to_string(),
+                },
+            ]
+        );
+        let tasks = jump_list.task_items();
+        assert_eq!(tasks[0].title, "New Document");
+        assert_eq!(tasks[0].arguments, JumpListTask::NewDocument.argument());
+        assert_eq!(tasks[1].arguments, JumpListTask::OpenFile.argument());
+    }
+
+    #[test]
+    fn startup_args_split_task_switches_from_files() {
+        let args =
+            ["--new-document", "C:\\docs\\a.docx", "C:\\docs\\b.exe"].map(std::ffi::OsString::from);
+        assert_eq!(
+            parse_startup_args(args),
+            StartupArgs {
+                files: vec![PathBuf::from("C:\\docs\\a.docx")],
+                task: Some(JumpListTask::NewDocument),
+            }
+        );
+        let open = parse_startup_args([std::ffi::OsString::from("--open-file")]);
+        assert_eq!(open.task, Some(JumpListTask::OpenFile));
+        assert!(open.files.is_empty());
+    }
+
+    #[test]
+    fn path_commands_need_a_saved_file() {
+        let path = PathBuf::from("C:\\docs\\a.md");
+        assert_eq!(saved_file_path(Some(path.as_path())), Ok(path.as_path()));
+        assert!(saved_file_path(None).is_err());
+    }
+
     #[test]
     fn normalize_range_orders_bounds() {
         assert_eq!(normalize_page_range(0, 0), None);
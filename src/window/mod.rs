@@ -1,38 +1,54 @@
 use std::{
+    collections::HashMap,
     ffi::c_void,
     mem::size_of,
     path::{Path, PathBuf},
-    time::{Duration, Instant},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
 };
 
+use chrono::Local;
 use windows::{
     Win32::{
-        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, POINT, RECT, WPARAM},
         Graphics::{
             Dwm::{
                 DWMSBT_MAINWINDOW, DWMWA_SYSTEMBACKDROP_TYPE, DWMWA_USE_IMMERSIVE_DARK_MODE,
                 DwmSetWindowAttribute,
             },
-            Gdi::{BeginPaint, EndPaint, InvalidateRect, PAINTSTRUCT},
+            Gdi::{
+                BeginPaint, ClientToScreen, EndPaint, InvalidateRect, PAINTSTRUCT, ScreenToClient,
+            },
         },
-        System::LibraryLoader::GetModuleHandleW,
+        System::{LibraryLoader::GetModuleHandleW, Ole::OleInitialize},
         UI::{
             HiDpi::{DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2, SetProcessDpiAwarenessContext},
             Input::KeyboardAndMouse::{
-                GetKeyState, ReleaseCapture, SetCapture, VK_CONTROL, VK_DELETE, VK_SHIFT,
+                GetKeyState, ReleaseCapture, SetCapture, VK_CONTROL, VK_DELETE, VK_MENU, VK_SHIFT,
             },
             Shell::{DragAcceptFiles, HDROP},
             WindowsAndMessaging::{
-                AdjustWindowRectEx, CREATESTRUCTW, CS_DBLCLKS, CS_HREDRAW, CS_VREDRAW,
-                CreateWindowExW, DefWindowProcW, DispatchMessageW, GWLP_USERDATA, GetClientRect,
+                AdjustWindowRectEx, AppendMenuW, CREATESTRUCTW, CS_DBLCLKS, CS_HREDRAW,
+                GA_ROOT, GetAncestor, GetClassNameW, SWP_NOSIZE, SetForegroundWindow, WM_CLOSE,
+                WM_MBUTTONUP, WindowFromPoint,
+                CS_VREDRAW, CreatePopupMenu, CreateWindowExW, DestroyMenu, MF_GRAYED, MF_STRING,
+                MF_CHECKED, MENU_ITEM_FLAGS, TPM_BOTTOMALIGN, TPM_LEFTALIGN, TPM_RETURNCMD,
+                TPM_RIGHTALIGN, TPM_RIGHTBUTTON, TRACK_POPUP_MENU_FLAGS, TrackPopupMenu,
+                DefWindowProcW, DispatchMessageW, GWLP_USERDATA, GetClientRect,
                 GetMessageW, GetSystemMetrics, GetWindowLongPtrW, IDC_ARROW, LoadCursorW, MSG,
-                IDCANCEL, IDNO, IDYES, MB_ICONWARNING, MB_YESNOCANCEL, MessageBoxW,
+                IDCANCEL, IDNO, IDYES, MB_ICONQUESTION, MB_ICONWARNING, MB_YESNO, MB_YESNOCANCEL, MessageBoxW,
+                PostMessageW, WM_APP,
                 PostQuitMessage, RegisterClassExW, SM_CXSCREEN, SM_CYSCREEN, SW_SHOW,
+                SIZE_MAXIMIZED, SIZE_RESTORED, SW_MAXIMIZE, WM_EXITSIZEMOVE,
                 SWP_NOACTIVATE, SWP_NOZORDER, SetWindowLongPtrW, SetWindowPos, ShowWindow,
-                TranslateMessage, WINDOW_EX_STYLE, WM_CHAR, WM_CREATE, WM_DESTROY, WM_DPICHANGED,
+                KillTimer, SetTimer, TranslateMessage, WA_INACTIVE, WINDOW_EX_STYLE, WM_ACTIVATE,
+                WM_TIMER, WM_CHAR, WM_CREATE, WM_DESTROY, WM_DPICHANGED,
+                WM_GETOBJECT,
                 WM_DROPFILES, WM_KEYDOWN, WM_LBUTTONDBLCLK, WM_LBUTTONDOWN, WM_LBUTTONUP,
+                WM_SYSCHAR, WM_SYSKEYDOWN,
                 WM_MBUTTONDOWN, WM_MOUSEMOVE, WM_MOUSEWHEEL, WM_NCCREATE, WM_NCDESTROY,
-                WM_PAINT, WM_SETTINGCHANGE, WM_SIZE, WNDCLASSEXW, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+                WM_PAINT, WM_RBUTTONUP, WM_SETTINGCHANGE, WM_SIZE, WNDCLASSEXW,
+                WS_OVERLAPPEDWINDOW, WS_VISIBLE,
             },
         },
     },
@@ -42,30 +58,84 @@ use windows::{
 use crate::{
     app::AppState,
     document::{
-        DocumentFormat, detect_format,
+        DocumentFormat, changed_externally, detect_format, modified_time,
+        delimited::{is_delimited_path, load_delimited},
         docx::parser::parse_docx,
+        epub::parse_epub,
         export::{export_pdf, save_with_format},
-        markdown::MarkdownDocument,
+        language::{
+            LANGUAGES, detected_language, document_language, is_rtl_language, language_name,
+        },
+        markdown::{
+            MarkdownDocument,
+            wiki::{find_wiki_page, new_wiki_page_path, wiki_page_name, wiki_pages},
+        },
+        math::{MathScript, layout_math},
         model::{
-            Block, BlockId, DocumentModel, ImageAlignment, ImageBorder, ImageBorderStyle,
-            Indent, Paragraph, ParagraphAlignment, ParagraphSpacing, Run, RunStyle,
-            TableStylePreset,
+            Block, BlockId, CaptionKind, CrossReferenceDisplay, DocumentModel, ImageAlignment,
+            ImageBorder, ImageBorderStyle, ImageDataRef, Indent, Paragraph, ParagraphAlignment,
+            ParagraphFrame, ParagraphFramePreset, ParagraphSpacing, Revision, Run, RunStyle,
+            TableStylePreset, TextDirection,
         },
-        txt::TextDocument,
+        loader::{DocumentLoadJob, LoadProgress},
+        txt::{TextDocument, view::TextView},
     },
     editor::{
-        clipboard::{get_plain_text, read_clipboard_image, set_plain_text},
-        cursor::Movement,
-        image_ops::load_supported_image,
-        search::{FindReplaceState, replace_all, replace_current, replacement_preview},
+        EditEngine, apply_to_document, ensure_single_run,
+        bookmarks::{BookmarkStore, bookmarks_path},
+        caption::{
+            CaptionNumber, caption_entries, caption_list_command, caption_list_range, caption_text,
+            captioned_block_index, number_captions, numbered_caption,
+        },
+        clipboard::{
+            TextDragSession, copy_image_to_clipboard, copy_table_to_clipboard, dropped_text_range,
+            get_plain_text, html_to_plain_text, read_clipboard_image, read_clipboard_table,
+            set_plain_text,
+        },
+        commands::{
+            EditCommand, HighlightChoice, SelectionRange as FormatSelection, TableOp,
+            apply_or_set_font_family, apply_or_set_font_size, apply_or_set_highlight_color,
+            apply_or_set_text_color, apply_or_toggle_subscript, apply_or_toggle_superscript,
+            clamp_font_size, common_font_family, common_font_size, set_direction,
+            set_paragraph_frame,
+        },
+        cross_reference::{
+            anchor_name_of, ensure_anchor, insert_cross_reference_command, resolve_cross_reference,
+            update_cross_references,
+        },
+        cursor::{CursorPosition, Movement, SelectionRange as CursorSelection},
+        image_ops::{
+            ImageExportFormat, ImageReplacements, encode_image_for_export, load_supported_image,
+        },
+        folding::{FoldStore, fold_anchors, folds_path, is_callout, resolve_folds},
+        outline::{
+            heading_path, move_section_command, table_of_contents_command, toc_entries, toc_range,
+        },
+        quote::toggle_block_quote_command,
+        search::{
+            FindReplaceState, SearchIndex, count_matches_in_documents, matches_in_visible_blocks,
+            replace_all, replace_all_in_documents, replace_current, replacement_preview,
+        },
+        snippets::{LocaleFormats, format_date_time, is_expansion_trigger, match_abbreviation},
+        stats::{collect_document_stats, collect_selection_stats},
         table::{
             CellPos,
+            CellStep,
+            MAX_TABLE_NESTING,
             TableSelection,
+            TableViewport,
+            TextDelimiter,
             apply_style as apply_table_style,
             delete_column as delete_table_column,
             delete_row as delete_table_row,
+            delete_table,
             distribute_columns_evenly,
+            jump_to_edge,
+            distribute_rows_evenly,
+            estimate_text_width,
             fit_columns_to_content,
+            natural_column_widths,
+            find_table,
             find_table_mut,
             insert_column_left,
             insert_column_right,
@@ -73,50 +143,107 @@ use crate::{
             insert_row_below,
             insert_table,
             merge_cells as merge_table_cells,
+            nested_table,
             resize_column as resize_table_column,
             resize_row as resize_table_row,
+            set_uniform_row_height,
             split_cell as split_table_cell,
+            apply_table_op,
+            step_table_cell,
+            table_for_paste,
+            table_parent,
+            table_to_text_command,
+            text_to_table_command,
             visible_row_range,
         },
+        words::{DEFAULT_LOCALE, next_word_end, previous_word_start},
     },
-    render::canvas::PageLayoutMode,
-    render::d2d::{D2DRenderer, ShellRenderState},
+    render::block_layout::BlockLayoutCache,
+    render::canvas::{
+        HSCROLL_HIT_HEIGHT, PageLayoutMode, WheelBehavior, edge_scroll_speed,
+        page_dimensions_points,
+    },
+    render::d2d::{CanvasTextHighlight, D2DRenderer, ShellRenderState},
     render::image_cache::{ImageDecodeCache, interpolation_hint, resolve_image_data},
     render::perf::emit_startup_marker,
-    settings::schema::{Settings, SettingsCategory, SidebarDefaultPanel},
+    settings::schema::{Settings, SettingsCategory},
+    settings::workspace::WorkspaceOverrides,
     theme::{
-        Theme, ThemeManager,
-        backgrounds::{BackgroundKind, from_canvas_preference},
+        Theme, ThemeManager, ThemeWatcher, high_contrast_scheme_is_dark,
+        theme_for_accessibility,
+        backgrounds::{BackgroundKind, BackgroundSettings, from_canvas_preference, page_colors},
     },
     ui::{
-        AccessibilityPreferences, InputEvent as UiInputEvent, Point as UiPoint, Rect as UiRect,
-        UIComponent,
+        AccessibilityPreferences, FocusRing, InputEvent as UiInputEvent, Point as UiPoint,
+        Rect as UiRect, UIComponent,
+        breadcrumb::{BREADCRUMB_HEIGHT, Breadcrumb},
         command_palette::CommandPalette,
-        dialog::Dialog,
-        sidebar::{SearchResultItem, Sidebar, SidebarIntent, SidebarPanel},
-        statusbar::{StatusAction, StatusBar, StatusBarInfo},
-        tabs::{TabKind, TabsBar},
-        toast::Toast,
+        context_menu::{ContextAction, ContextMenuKind, default_items as context_menu_items},
+        dialog::{AboutAction, Dialog},
+        sidebar::{
+            SearchResultItem, Sidebar, SidebarIntent, SidebarPanel, clamp_sidebar_width,
+            restore_bookmarks,
+        },
+        statusbar::{SaveIndicator, StatusAction, StatusBar, StatusBarInfo},
+        tabs::{BulkClose, SaveOutcome, TabKind, TabsBar, save_all},
+        toast::{Toast, ToastLevel},
         toolbar::{
-            AlignmentState, HeadingState, ListState, ToggleState, Toolbar, ToolbarAction,
-            ToolbarFormatState, ToolbarIntent,
+            AlignmentState, DropdownSelection, HeadingState, ListState, ToggleState, Toolbar,
+            ToolbarAction, ToolbarButtonType, ToolbarDropdownKind, ToolbarFormatState,
+            ToolbarIntent,
         },
+        document_properties::{DocumentPropertiesDialog, apply_properties},
+        goto_symbol::{GotoSymbol, SymbolEntry},
+        licenses::LicensesView,
+        shortcut_editor::ShortcutEditor,
+        toolbar_customizer::ToolbarCustomizer,
+        stats_popup::StatsPopup,
+        zoom_popup::{ZoomPopup, ZoomPopupAction},
+    },
+    window::accessibility::CanvasAccessibility,
+    window::compositor::{Compositor, FRAME_TIMER_ID, query_refresh_rate},
+    window::drop_target::{DropContent, ExternalDrop, WindowDropTargets},
+    window::input::{
+        FocusRegion, KeyDispatch, KeyModifiers, MiddleClick, ShortcutDispatcher,
+        middle_click_target,
     },
     window::integration::{
-        DropAction, JumpListState, PrintState, extract_drop_payload, parse_startup_files_from_cli,
-        open_print_dialog, pick_image_file, pick_open_file, pick_save_file,
-        query_accessibility_preferences, send_toast_notification,
+        DropAction, DropPayload, JumpListState, JumpListTask, PathCopyKind, PrintState,
+        StartupArgs, classify_drop, extract_drop_payload,
+        initial_dialog_dir, open_print_dialog, parse_startup_args_from_cli, path_clipboard_text,
+        pick_image_file, pick_open_file, pick_save_file, pick_save_image_file,
+        query_accessibility_preferences,
+        open_in_browser, reveal_in_explorer, saved_file_path, send_toast_notification,
     },
+    window::placement::{clamp_to_monitors, current_placement, monitor_work_areas, placement_rect},
+    window::system_info::{SystemInfo, SystemProbe},
+    window::updates::{UpdateCheck, UpdateOutcome, is_newer},
 };
 
+pub mod accessibility;
 pub mod compositor;
+pub mod drop_target;
 pub mod input;
 pub mod integration;
+pub mod placement;
+pub mod system_info;
+pub mod updates;
 
 pub struct AppWindow {
     hwnd: HWND,
 }
 
+/// Posted from WM_CREATE to run a jump list task once the window exists.
+const WM_RUN_STARTUP_TASK: u32 = WM_APP + 1;
+/// Posted by the OLE drop target once text, HTML or files were dropped on the window.
+const WM_EXTERNAL_DROP: u32 = WM_APP + 2;
+
+/// Lines of a streamed text file shown at once, as many as the canvas preview draws.
+const TEXT_VIEW_ROWS: usize = 40;
+
+/// Doco windows still open; the message loop ends when the last one closes.
+static OPEN_WINDOWS: AtomicUsize = AtomicUsize::new(0);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum FindFieldFocus {
     Query,
@@ -141,6 +268,31 @@ struct CanvasTableOverlay {
     cell_h: f32,
     header_h: f32,
     gutter_w: f32,
+    /// Rows that open a continued page where pagination repeats the header row.
+    repeated_header_rows: Vec<usize>,
+    /// Header tint or band behind each row, from `Table::row_fill`.
+    row_fills: Vec<Option<crate::ui::Color>>,
+    scroll_x: f32,
+    scroll_y: f32,
+    freeze_first_column: bool,
+    freeze_header_row: bool,
+}
+
+impl CanvasTableOverlay {
+    fn viewport(&self) -> TableViewport {
+        TableViewport {
+            rows: self.rows,
+            cols: self.cols,
+            cell_w: self.cell_w,
+            cell_h: self.cell_h,
+            gutter_w: self.gutter_w,
+            header_h: self.header_h,
+            scroll_x: self.scroll_x,
+            scroll_y: self.scroll_y,
+            freeze_first_column: self.freeze_first_column,
+            freeze_header_row: self.freeze_header_row,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -192,18 +344,57 @@ struct WindowState {
     dpi: f32,
     theme: Theme,
     theme_manager: ThemeManager,
+    /// Notices theme files saved while the window is open so they can be reloaded.
+    theme_watcher: Option<ThemeWatcher>,
     debug_panel_visible: bool,
     dropped_files: Vec<PathBuf>,
     jump_list: JumpListState,
     print_state: PrintState,
     startup_files: Vec<PathBuf>,
+    /// Jump list task Doco was relaunched for, run once the window is up.
+    startup_task: Option<JumpListTask>,
+    document_loads: Vec<DocumentLoadJob>,
     app_state: AppState,
     tabs: TabsBar,
     sidebar: Sidebar,
     settings_dialog: Dialog,
+    /// `.doco.json` above the active document, laid over the global settings.
+    workspace: Option<WorkspaceOverrides>,
+    toolbar_customizer: ToolbarCustomizer,
+    goto_symbol: GotoSymbol,
+    /// Set while the heading picker chooses a cross-reference target instead of a jump.
+    cross_reference_pick: Option<CrossReferenceDisplay>,
+    licenses_view: LicensesView,
+    shortcut_editor: ShortcutEditor,
+    /// Resolves key presses to the commands `keyboard_shortcuts` binds them to.
+    shortcut_dispatcher: ShortcutDispatcher,
+    /// Set when an Alt shortcut ran from WM_SYSKEYDOWN, so its WM_SYSCHAR doesn't beep.
+    sys_key_handled: bool,
+    breadcrumb: Breadcrumb,
+    /// Folded headings and code blocks of documents opened before, keyed by path.
+    fold_store: FoldStore,
+    /// Bookmarks of documents opened before, keyed by path.
+    bookmark_store: BookmarkStore,
+    /// The tab whose bookmarks the sidebar lists.
+    bookmarks_tab: Option<u64>,
+    document_properties: DocumentPropertiesDialog,
+    zoom_popup: ZoomPopup,
+    stats_popup: StatsPopup,
+    footnote_return: Option<FootnoteReturn>,
+    /// Folder of the last file picked in a dialog, for the "Last used" open-folder policy.
+    last_dialog_dir: Option<PathBuf>,
+    /// The first window Doco opened; only it restores recovered documents.
+    primary_window: bool,
+    update_check: Option<UpdateCheck>,
     command_palette: CommandPalette,
     find_replace: FindReplaceState,
     find_focus: FindFieldFocus,
+    /// The region F6 last moved keyboard focus to.
+    focus_region: FocusRegion,
+    /// The toolbar button or status bar segment focused within `focus_region`.
+    focus_item: usize,
+    /// Whether focus arrived from the keyboard, so the region shows a focus ring.
+    focus_visible: bool,
     image_cache: ImageDecodeCache,
     canvas_image_overlays: Vec<CanvasImageOverlay>,
     selected_image: Option<BlockId>,
@@ -220,6 +411,10 @@ struct WindowState {
     table_selection_mode: Option<TableSelectionMode>,
     table_selection_range: Option<TableSelection>,
     table_resize: Option<TableResizeState>,
+    /// Undo history per tab, keyed by tab id.
+    edit_engines: HashMap<u64, EditEngine>,
+    /// Search text per tab for "find in all tabs", keyed by tab id.
+    search_indexes: HashMap<u64, SearchIndex>,
     goto_visible: bool,
     goto_input: String,
     toolbar: Toolbar,
@@ -227,14 +422,41 @@ struct WindowState {
     toast: Toast,
     accessibility: AccessibilityPreferences,
     last_ui_tick: Instant,
+    last_input: Instant,
+    compositor: Compositor,
+    canvas_accessibility: CanvasAccessibility,
+    drop_targets: WindowDropTargets,
     sidebar_resizing: bool,
     sidebar_resize_grab_offset: f32,
+    /// Where the pointer grabbed the horizontal scrollbar thumb, while dragging it.
+    hscroll_drag: Option<f32>,
+    /// Last pointer position of a middle-button pan.
+    pan_anchor: Option<UiPoint>,
+    /// Set while the left button drags a selection across the canvas.
+    selection_drag: bool,
+    /// Selected text being dragged to a new spot.
+    text_drag: Option<TextDrag>,
+    /// Markdown notes under the wiki link root, listed once per root for link coloring.
+    wiki_pages: Option<(PathBuf, Vec<PathBuf>)>,
 }
 
 impl AppWindow {
     pub fn new(theme_manager: ThemeManager, settings: Settings) -> Result<Self> {
+        Self::create(theme_manager, settings, parse_startup_args_from_cli(), true)
+    }
+
+    /// Opens another window, e.g. for a tab torn off the strip.
+    fn create(
+        theme_manager: ThemeManager,
+        settings: Settings,
+        startup: StartupArgs,
+        primary: bool,
+    ) -> Result<Self> {
         unsafe {
             let _ = SetProcessDpiAwarenessContext(DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2);
+            // The jump list is published through shell COM objects created on this thread, and
+            // OLE drag and drop needs the apartment initialized through OLE.
+            let _ = OleInitialize(None);
         }
 
         let hmodule = unsafe { GetModuleHandleW(None)? };
@@ -266,10 +488,28 @@ impl AppWindow {
             AdjustWindowRectEx(&mut rect, WS_OVERLAPPEDWINDOW, false, WINDOW_EX_STYLE(0))?;
         }
 
-        let width = rect.right - rect.left;
-        let height = rect.bottom - rect.top;
-        let x = (unsafe { GetSystemMetrics(SM_CXSCREEN) } - width).max(0) / 2;
-        let y = (unsafe { GetSystemMetrics(SM_CYSCREEN) } - height).max(0) / 2;
+        let (x, y, width, height) = match settings.appearance.window {
+            Some(saved) => {
+                let rect = clamp_to_monitors(placement_rect(&saved), &monitor_work_areas());
+                (
+                    rect.left,
+                    rect.top,
+                    rect.right - rect.left,
+                    rect.bottom - rect.top,
+                )
+            }
+            None => {
+                let width = rect.right - rect.left;
+                let height = rect.bottom - rect.top;
+                let x = (unsafe { GetSystemMetrics(SM_CXSCREEN) } - width).max(0) / 2;
+                let y = (unsafe { GetSystemMetrics(SM_CYSCREEN) } - height).max(0) / 2;
+                (x, y, width, height)
+            }
+        };
+        let maximized = settings
+            .appearance
+            .window
+            .is_some_and(|saved| saved.maximized);
         let theme = theme_manager.active();
         let mut app_state = AppState::default();
         app_state.settings = settings.clone();
@@ -277,30 +517,54 @@ impl AppWindow {
         app_state.show_sidebar = settings.appearance.show_sidebar;
         app_state.show_statusbar = settings.appearance.show_status_bar;
         app_state.show_tabs = settings.appearance.show_tab_bar;
+        app_state.sidebar_width = clamp_sidebar_width(settings.appearance.sidebar_width);
         let mut sidebar = Sidebar::default();
-        sidebar.set_active_panel(match settings.appearance.sidebar_default_panel {
-            SidebarDefaultPanel::Files => SidebarPanel::Files,
-            SidebarDefaultPanel::Outline => SidebarPanel::Outline,
-            SidebarDefaultPanel::Bookmarks => SidebarPanel::Bookmarks,
-        });
+        sidebar.set_active_panel(SidebarPanel::restored(&settings.appearance));
 
         let state = Box::new(WindowState {
             renderer: None,
             dpi: 96.0,
             theme,
             theme_manager,
+            theme_watcher: ThemeWatcher::start(),
             debug_panel_visible: false,
             dropped_files: Vec::new(),
-            jump_list: JumpListState::with_default_tasks(),
+            jump_list: JumpListState::with_default_tasks(
+                settings.files.recent_files_count as usize,
+            ),
             print_state: PrintState::default(),
-            startup_files: parse_startup_files_from_cli(),
+            startup_files: startup.files,
+            startup_task: startup.task,
+            document_loads: Vec::new(),
             app_state,
             tabs: TabsBar::default(),
             sidebar,
             settings_dialog: Dialog::default(),
+            workspace: None,
+            toolbar_customizer: ToolbarCustomizer::default(),
+            goto_symbol: GotoSymbol::default(),
+            cross_reference_pick: None,
+            licenses_view: LicensesView::default(),
+            shortcut_editor: ShortcutEditor::default(),
+            shortcut_dispatcher: ShortcutDispatcher::default(),
+            sys_key_handled: false,
+            breadcrumb: Breadcrumb::default(),
+            fold_store: FoldStore::load_from(&folds_path()),
+            bookmark_store: BookmarkStore::load_from(&bookmarks_path()),
+            bookmarks_tab: None,
+            document_properties: DocumentPropertiesDialog::default(),
+            zoom_popup: ZoomPopup::default(),
+            stats_popup: StatsPopup::default(),
+            footnote_return: None,
+            last_dialog_dir: None,
+            primary_window: primary,
+            update_check: None,
             command_palette: CommandPalette::default(),
             find_replace: FindReplaceState::default(),
             find_focus: FindFieldFocus::Query,
+            focus_region: FocusRegion::Document,
+            focus_item: 0,
+            focus_visible: false,
             image_cache: ImageDecodeCache::default(),
             canvas_image_overlays: Vec::new(),
             selected_image: None,
@@ -317,6 +581,8 @@ impl AppWindow {
             table_selection_mode: None,
             table_selection_range: None,
             table_resize: None,
+            edit_engines: HashMap::new(),
+            search_indexes: HashMap::new(),
             goto_visible: false,
             goto_input: String::new(),
             toolbar: Toolbar::default(),
@@ -324,8 +590,17 @@ impl AppWindow {
             toast: Toast::default(),
             accessibility: query_accessibility_preferences(),
             last_ui_tick: Instant::now(),
+            last_input: Instant::now(),
+            compositor: Compositor::default(),
+            canvas_accessibility: CanvasAccessibility::default(),
+            drop_targets: WindowDropTargets::default(),
             sidebar_resizing: false,
             sidebar_resize_grab_offset: 0.0,
+            hscroll_drag: None,
+            pan_anchor: None,
+            selection_drag: false,
+            text_drag: None,
+            wiki_pages: None,
         });
         let state_ptr = Box::into_raw(state);
 
@@ -347,8 +622,9 @@ impl AppWindow {
         };
 
         unsafe {
-            let _ = ShowWindow(hwnd, SW_SHOW);
+            let _ = ShowWindow(hwnd, if maximized { SW_MAXIMIZE } else { SW_SHOW });
         }
+        OPEN_WINDOWS.fetch_add(1, Ordering::SeqCst);
 
         Ok(Self { hwnd })
     }
@@ -437,6 +713,7 @@ fn load_document_for_path(path: &Path) -> DocumentModel {
     let detected = detect_format(path);
     let mut model = match detected {
         DocumentFormat::Docx => parse_docx(path).unwrap_or_default(),
+        DocumentFormat::Epub => parse_epub(path).unwrap_or_default(),
         DocumentFormat::Markdown => MarkdownDocument::load_from_path(path)
             .map(|doc| doc.to_document_model())
             .unwrap_or_default(),
@@ -459,21 +736,26 @@ fn load_document_for_path(path: &Path) -> DocumentModel {
     model
 }
 
+/// Loads a CSV or TSV file as one editable table, or as text when it can't be read as one.
+fn load_table_for_path(path: &Path) -> DocumentModel {
+    let Ok(mut model) = load_delimited(path) else {
+        return load_document_for_path(path);
+    };
+    model.metadata.title = document_title_from_path(path);
+    model
+}
+
 fn process_startup_file_queue(state: &mut WindowState) -> bool {
     if state.startup_files.is_empty() {
         return false;
     }
 
     let path = state.startup_files.remove(0);
-    let title = document_title_from_path(path.as_path());
-    let document = load_document_for_path(path.as_path());
-    state
-        .tabs
-        .open_document_tab(title.clone(), Some(path.clone()), document);
+    let title = begin_background_load(state, path.clone(), true);
     state.jump_list.add_recent_file(path);
 
     if state.startup_files.is_empty() {
-        state.app_state.status_text = format!("Opened {title}");
+        state.app_state.status_text = format!("Loading {title}...");
     } else {
         state.app_state.status_text = format!(
             "Opening startup files... {} remaining",
@@ -497,7 +779,8 @@ fn default_extension_for_document(state: &WindowState, format: DocumentFormat) -
         return from_settings;
     }
     match format {
-        DocumentFormat::Docx | DocumentFormat::Unknown => "docx".to_string(),
+        // EPUBs are only read, so their edits save as Word documents.
+        DocumentFormat::Docx | DocumentFormat::Epub | DocumentFormat::Unknown => "docx".to_string(),
         DocumentFormat::Pdf => "pdf".to_string(),
         DocumentFormat::Text => "txt".to_string(),
         DocumentFormat::Markdown => "md".to_string(),
@@ -522,7 +805,7 @@ fn suggested_save_name(tab: &crate::ui::tabs::TabState, default_ext: &str) -> St
 }
 
 fn pick_save_target_for_active_tab(
-    state: &WindowState,
+    state: &mut WindowState,
     hwnd: HWND,
     forced_ext: Option<&str>,
 ) -> Option<PathBuf> {
@@ -531,7 +814,142 @@ fn pick_save_target_for_active_tab(
         .map(|v| v.trim_start_matches('.').to_ascii_lowercase())
         .unwrap_or_else(|| default_extension_for_document(state, tab.document.metadata.format));
     let suggested = suggested_save_name(tab, default_ext.as_str());
-    pick_save_file(hwnd, suggested.as_str(), default_ext.as_str())
+    // A file saved before is saved again next to itself; new ones follow the folder policy.
+    let start_dir = tab
+        .file_path
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+        .or_else(|| dialog_start_dir(state));
+    let path = pick_save_file(
+        hwnd,
+        suggested.as_str(),
+        default_ext.as_str(),
+        start_dir.as_deref(),
+    )?;
+    remember_dialog_dir(state, &path);
+    Some(path)
+}
+
+/// The folder the next file dialog starts in, per `files.default_open_folder`.
+fn dialog_start_dir(state: &WindowState) -> Option<PathBuf> {
+    initial_dialog_dir(
+        &state.app_state.settings.files.default_open_folder,
+        state.last_dialog_dir.as_deref(),
+        dirs::document_dir(),
+    )
+}
+
+/// Records the folder of a file picked in a dialog as the "Last used" folder.
+fn remember_dialog_dir(state: &mut WindowState, path: &Path) {
+    if let Some(parent) = path.parent() {
+        state.last_dialog_dir = Some(parent.to_path_buf());
+    }
+}
+
+/// Asks for an image to insert, starting and remembering like the other file dialogs.
+fn pick_image_via_dialog(state: &mut WindowState, hwnd: HWND) -> Option<PathBuf> {
+    let path = pick_image_file(hwnd, dialog_start_dir(state).as_deref())?;
+    remember_dialog_dir(state, &path);
+    Some(path)
+}
+
+/// Writes the selected image to a file the user picks and returns the status to show. A
+/// linked image can be copied as its original file; otherwise the image is re-encoded to
+/// the PNG or JPEG the chosen name asks for.
+fn save_selected_image_as(state: &mut WindowState, hwnd: HWND) -> String {
+    let Some((image, tab)) = state
+        .selected_image
+        .and_then(|block_id| active_image_ref(state, block_id))
+        .zip(state.tabs.active_tab())
+    else {
+        return "Select an image to save".to_string();
+    };
+    let linked = match &image.data {
+        ImageDataRef::LinkedPath(path) => Some(path.clone()),
+        ImageDataRef::Empty => image.source_path.clone(),
+        _ => None,
+    }
+    .filter(|path| path.is_file());
+    let stem = linked
+        .as_deref()
+        .or(image.source_path.as_deref())
+        .and_then(Path::file_stem)
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("Image")
+        .to_string();
+    let data = resolve_image_data(image, &tab.document);
+    let start_dir = dialog_start_dir(state);
+
+    if let Some(original) = linked {
+        let original_name = file_name_or(&original, "the original file");
+        let prompt = format!(
+            "This image is linked to '{original_name}'. Save a copy of the original file?\n\n\
+             Choose No to save the picture as PNG or JPEG instead."
+        );
+        let prompt_wide = to_wide_null(prompt.as_str());
+        let choice = unsafe {
+            MessageBoxW(
+                Some(hwnd),
+                PCWSTR(prompt_wide.as_ptr()),
+                w!("Doco"),
+                MB_YESNOCANCEL | MB_ICONQUESTION,
+            )
+        };
+        if choice == IDCANCEL {
+            return "Save image cancelled".to_string();
+        }
+        if choice == IDYES {
+            let extension = original
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .unwrap_or("png");
+            let Some(path) =
+                pick_save_image_file(hwnd, &original_name, extension, start_dir.as_deref())
+            else {
+                return "Save image cancelled".to_string();
+            };
+            remember_dialog_dir(state, &path);
+            return match std::fs::copy(&original, &path) {
+                Ok(_) => format!("Saved a copy of {original_name}"),
+                Err(err) => format!("Save image failed: {err}"),
+            };
+        }
+    }
+
+    let Some(data) = data else {
+        return "Couldn't read the image".to_string();
+    };
+    let extension = if data.mime == ImageExportFormat::Jpeg.mime() {
+        "jpg"
+    } else {
+        "png"
+    };
+    let Some(path) = pick_save_image_file(
+        hwnd,
+        &format!("{stem}.{extension}"),
+        extension,
+        start_dir.as_deref(),
+    ) else {
+        return "Save image cancelled".to_string();
+    };
+    remember_dialog_dir(state, &path);
+    let Some(format) = ImageExportFormat::for_path(&path) else {
+        return "Images can be saved as .png or .jpg".to_string();
+    };
+    match encode_image_for_export(&data.bytes, &data.mime, format)
+        .and_then(|bytes| std::fs::write(&path, bytes).map_err(|err| err.to_string()))
+    {
+        Ok(()) => format!("Saved image to {}", file_name_or(&path, "file")),
+        Err(err) => format!("Save image failed: {err}"),
+    }
+}
+
+fn file_name_or(path: &Path, fallback: &str) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or(fallback)
+        .to_string()
 }
 
 fn path_is_read_only(path: &Path) -> bool {
@@ -540,8 +958,14 @@ fn path_is_read_only(path: &Path) -> bool {
         .unwrap_or(false)
 }
 
+/// Whether a tab opening `path` starts locked: the file is read-only, or it's an EPUB,
+/// which Doco opens for reading.
+fn opens_read_only(path: &Path) -> bool {
+    path_is_read_only(path) || detect_format(path) == DocumentFormat::Epub
+}
+
 fn is_tab_dirty(tab: &crate::ui::tabs::TabState) -> bool {
-    tab.dirty || tab.document.dirty
+    tab.has_unsaved_changes()
 }
 
 fn to_wide_null(value: &str) -> Vec<u16> {
@@ -561,6 +985,103 @@ fn open_new_blank_tab(state: &mut WindowState) -> usize {
     index
 }
 
+/// Opens an unsaved copy of the tab at `index` next to it, for trying changes out.
+fn duplicate_tab(state: &mut WindowState, index: usize) {
+    if state.tabs.duplicate_tab(index).is_none() {
+        state.app_state.status_text = "Only documents can be duplicated".to_string();
+        return;
+    }
+    sync_sidebar_with_active_tab(state);
+    if let Some(tab) = state.tabs.active_tab() {
+        state.app_state.status_text = format!("Opened {}", tab.title);
+    }
+}
+
+/// Records the folds and bookmarks of the tabs at `indices` that have a file, and writes
+/// the stores out.
+fn remember_document_marks(state: &mut WindowState, indices: &[usize]) {
+    let mut changed = false;
+    for tab in indices
+        .iter()
+        .filter_map(|&index| state.tabs.tabs.get(index))
+    {
+        if tab.kind != TabKind::Document || tab.text_view.is_some() {
+            continue;
+        }
+        let Some(path) = tab.file_path.as_deref() else {
+            continue;
+        };
+        let blocks = &tab.document.content;
+        state
+            .fold_store
+            .remember(path, fold_anchors(blocks, &tab.folded));
+        // The sidebar holds the bookmarks of the tab it lists.
+        let bookmarks = if state.bookmarks_tab == Some(tab.id) {
+            &state.sidebar.bookmarks
+        } else {
+            &tab.bookmarks
+        };
+        let saved = bookmarks
+            .iter()
+            .filter_map(|bookmark| bookmark.to_saved(blocks))
+            .collect();
+        state.bookmark_store.remember(path, saved);
+        changed = true;
+    }
+    if changed {
+        // Losing folds or bookmarks is an inconvenience, not something to interrupt closing for.
+        let _ = state.fold_store.save_to(&folds_path());
+        let _ = state.bookmark_store.save_to(&bookmarks_path());
+    }
+}
+
+/// Lists the active tab's bookmarks in the sidebar and hands the ones it listed back to
+/// their tab, unless that tab has closed.
+fn sync_bookmarks_with_active_tab(state: &mut WindowState) {
+    let active = state.tabs.active_tab().map(|tab| tab.id);
+    if state.bookmarks_tab == active {
+        return;
+    }
+    let incoming = state
+        .tabs
+        .active_tab_mut()
+        .map(|tab| std::mem::take(&mut tab.bookmarks))
+        .unwrap_or_default();
+    let listed = state.sidebar.swap_bookmarks(incoming);
+    if let Some(previous) = state.bookmarks_tab
+        && let Some(tab) = state.tabs.tabs.iter_mut().find(|tab| tab.id == previous)
+    {
+        tab.bookmarks = listed;
+    }
+    state.bookmarks_tab = active;
+}
+
+/// Closes the tabs `scope` targets one at a time through `close_tab_with_prompt`, so unsaved
+/// ones still ask, and stops at the first one the user keeps open.
+fn close_tabs(state: &mut WindowState, hwnd: HWND, scope: BulkClose) {
+    let targets = state.tabs.bulk_close_targets(scope);
+    if targets.is_empty() {
+        state.app_state.status_text = "No tabs to close".to_string();
+        return;
+    }
+    let mut closed = 0usize;
+    for index in targets {
+        let Some(id) = state.tabs.tabs.get(index).map(|tab| tab.id) else {
+            continue;
+        };
+        let _ = close_tab_with_prompt(state, hwnd, index);
+        if state.tabs.tabs.iter().any(|tab| tab.id == id) {
+            // Kept open; close_tab_with_prompt already said why.
+            return;
+        }
+        closed += 1;
+    }
+    state.app_state.status_text = match closed {
+        1 => "Closed 1 tab".to_string(),
+        n => format!("Closed {n} tabs"),
+    };
+}
+
 fn close_tab_with_prompt(state: &mut WindowState, hwnd: HWND, index: usize) -> bool {
     let (dirty, title) = match state.tabs.tabs.get(index) {
         Some(tab) => (is_tab_dirty(tab), tab.title.clone()),
@@ -603,13 +1124,16 @@ fn close_tab_with_prompt(state: &mut WindowState, hwnd: HWND, index: usize) -> b
         }
     }
 
-    let closed_title = state
+    let (closed_id, closed_title) = state
         .tabs
         .tabs
         .get(index)
-        .map(|tab| tab.title.clone())
-        .unwrap_or_else(|| "Tab".to_string());
+        .map(|tab| (tab.id, tab.title.clone()))
+        .unwrap_or_else(|| (0, "Tab".to_string()));
+    remember_document_marks(state, &[index]);
     if state.tabs.close_tab(index) {
+        state.edit_engines.remove(&closed_id);
+        state.search_indexes.remove(&closed_id);
         let active_title = state
             .tabs
             .active_tab()
@@ -623,17 +1147,373 @@ fn close_tab_with_prompt(state: &mut WindowState, hwnd: HWND, index: usize) -> b
     false
 }
 
+/// Runs a tab's file command: copying its path or name, or revealing it in Explorer.
+fn apply_tab_file_action(state: &mut WindowState, index: usize, action: ContextAction) {
+    let Some(tab) = state.tabs.tabs.get(index) else {
+        return;
+    };
+    let path = match saved_file_path(tab.file_path.as_deref()) {
+        Ok(path) => path.to_path_buf(),
+        Err(message) => {
+            state.app_state.status_text = message.to_string();
+            return;
+        }
+    };
+    let copy_kind = match action {
+        ContextAction::CopyFilePath => PathCopyKind::FullPath,
+        ContextAction::CopyFileName => PathCopyKind::FileName,
+        ContextAction::ShowInExplorer => {
+            state.app_state.status_text = match reveal_in_explorer(&path) {
+                Ok(()) => format!("Opened folder containing {}", path.display()),
+                Err(err) => format!("Could not open folder: {err}"),
+            };
+            return;
+        }
+        _ => return,
+    };
+    let text = path_clipboard_text(&path, copy_kind);
+    state.app_state.status_text = match set_plain_text(text.as_str()) {
+        Ok(()) => format!("Copied {text}"),
+        Err(err) => format!("Copy failed: {err}"),
+    };
+}
+
+/// The other Doco window under a screen point, if any.
+fn doco_window_at(screen: POINT, source: HWND) -> Option<HWND> {
+    let hit = unsafe { WindowFromPoint(screen) };
+    if hit.is_invalid() {
+        return None;
+    }
+    let root = unsafe { GetAncestor(hit, GA_ROOT) };
+    if root.is_invalid() || root == source {
+        return None;
+    }
+    let mut class = [0u16; 32];
+    let len = unsafe { GetClassNameW(root, &mut class) }.max(0) as usize;
+    (String::from_utf16_lossy(&class[..len]) == "DocoMainWindow").then_some(root)
+}
+
+/// Moves a tab dragged off the strip: docked onto another window's strip when
+/// dropped there, otherwise torn off into a new window at the drop point.
+fn move_dragged_tab(state: &mut WindowState, hwnd: HWND, index: usize, point: UiPoint) {
+    let Some(tab) = state.tabs.tabs.get(index) else {
+        return;
+    };
+    if tab.kind != TabKind::Document {
+        state.app_state.status_text = "Only open documents can move between windows".to_string();
+        return;
+    }
+    let title = tab.title.clone();
+    let only_tab = state.tabs.tabs.len() == 1;
+
+    let mut screen = POINT {
+        x: point.x as i32,
+        y: point.y as i32,
+    };
+    let _ = unsafe { ClientToScreen(hwnd, &mut screen) };
+    let dock = doco_window_at(screen, hwnd).filter(|&target| {
+        let mut client = screen;
+        let _ = unsafe { ScreenToClient(target, &mut client) };
+        let client = UiPoint {
+            x: client.x as f32,
+            y: client.y as f32,
+        };
+        unsafe { state_from_hwnd(target) }
+            .is_some_and(|other| other.app_state.show_tabs && other.tabs.is_tab_bar_hit(client))
+    });
+
+    let target = match dock {
+        Some(target) => target,
+        None if only_tab => {
+            // Tearing off the last tab would just swap one window for another.
+            state.app_state.status_text =
+                "Drop onto another window's tabs to move this window's only tab".to_string();
+            return;
+        }
+        None => match AppWindow::create(
+            state.theme_manager.clone(),
+            state.app_state.settings.clone(),
+            StartupArgs::default(),
+            false,
+        ) {
+            Ok(window) => {
+                // Put the new strip under the cursor so the tab lands where it was dropped.
+                let _ = unsafe {
+                    SetWindowPos(
+                        window.hwnd,
+                        None,
+                        screen.x - 120,
+                        screen.y - 20,
+                        0,
+                        0,
+                        SWP_NOSIZE | SWP_NOZORDER,
+                    )
+                };
+                window.hwnd
+            }
+            Err(err) => {
+                state.app_state.status_text = format!("Couldn't open a new window: {err}");
+                return;
+            }
+        },
+    };
+    let Some(other) = (unsafe { state_from_hwnd(target) }) else {
+        return;
+    };
+    // The sidebar holds the bookmarks of the tab it lists; they move with the tab.
+    if state.bookmarks_tab == Some(state.tabs.tabs[index].id) {
+        state.tabs.tabs[index].bookmarks = state.sidebar.swap_bookmarks(Vec::new());
+        state.bookmarks_tab = None;
+    }
+    let Some(tab) = state.tabs.detach_tab(index) else {
+        return;
+    };
+
+    let old_id = tab.id;
+    let attached = other.tabs.attach_tab(tab);
+    let new_id = other.tabs.tabs[attached].id;
+    if let Some(engine) = state.edit_engines.remove(&old_id) {
+        other.edit_engines.insert(new_id, engine);
+    }
+    state.search_indexes.remove(&old_id);
+    other.app_state.status_text = format!("Moved {title} here");
+    sync_sidebar_with_active_tab(other);
+    sync_sidebar_with_active_tab(state);
+    let _ = unsafe { InvalidateRect(Some(target), None, false) };
+    let _ = unsafe { SetForegroundWindow(target) };
+
+    if only_tab {
+        let _ = unsafe { PostMessageW(Some(hwnd), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+    } else {
+        state.app_state.status_text = format!("Moved {title} to another window");
+    }
+}
+
+/// Lets the user pick the active document's language from above the status bar segment,
+/// and returns the status to show.
+fn show_language_menu(state: &mut WindowState, hwnd: HWND) -> String {
+    let Some(tab) = state.tabs.active_tab() else {
+        return "No document".to_string();
+    };
+    let current = tab.document.metadata.language.clone();
+    let auto_label = format!(
+        "Auto-detect ({})",
+        language_name(detected_language(&tab.document))
+    );
+    let options: Vec<(Option<&str>, String)> = std::iter::once((None, auto_label))
+        .chain(
+            LANGUAGES
+                .iter()
+                .map(|(tag, name)| (Some(*tag), format!("{name}\t{tag}"))),
+        )
+        .collect();
+    let items = options.iter().map(|(tag, label)| {
+        let flags = if *tag == current.as_deref() {
+            MF_STRING | MF_CHECKED
+        } else {
+            MF_STRING
+        };
+        (label.as_str(), flags)
+    });
+    let anchor = state.statusbar.language_rect();
+    let at = UiPoint {
+        x: anchor.x + anchor.width,
+        y: anchor.y,
+    };
+    let Ok(chosen) = track_popup_menu(hwnd, items, at, TPM_RIGHTALIGN | TPM_BOTTOMALIGN) else {
+        return "Language picker unavailable".to_string();
+    };
+
+    let Some((language, _)) = chosen.and_then(|index| options.get(index)) else {
+        return state.app_state.status_text.clone();
+    };
+    set_document_language(state, language.map(str::to_string))
+}
+
+/// Drops a menu of every open tab below the tab strip's chevron, checking the active one,
+/// and switches to the one picked.
+fn show_tab_list_menu(state: &mut WindowState, hwnd: HWND) -> String {
+    let entries = state
+        .tabs
+        .tab_list(|tab| format!("{} {}", tab_icon_label(tab), tab.title));
+    let labels = entries
+        .iter()
+        .map(|entry| {
+            let label = if entry.dirty {
+                format!("{} *", entry.title)
+            } else {
+                entry.title.clone()
+            };
+            let flags = if entry.active {
+                MF_STRING | MF_CHECKED
+            } else {
+                MF_STRING
+            };
+            (label, flags)
+        })
+        .collect::<Vec<_>>();
+    let anchor = state.tabs.tab_list_rect;
+    let at = UiPoint {
+        x: anchor.x,
+        y: anchor.y + anchor.height,
+    };
+    let items = labels.iter().map(|(label, flags)| (label.as_str(), *flags));
+    let Ok(chosen) = track_popup_menu(hwnd, items, at, TPM_LEFTALIGN) else {
+        return "Tab list unavailable".to_string();
+    };
+
+    let Some(index) = chosen
+        .and_then(|position| entries.get(position))
+        .map(|entry| entry.index)
+    else {
+        return state.app_state.status_text.clone();
+    };
+    state.tabs.set_active(index);
+    sync_sidebar_with_active_tab(state);
+    state
+        .tabs
+        .active_tab()
+        .map(|tab| format!("Switched to {}", tab.title))
+        .unwrap_or_default()
+}
+
+/// Sets how many columns the active document's text flows through.
+fn set_page_columns(state: &mut WindowState, count: u8) -> String {
+    if edit_blocked_by_read_only(state) {
+        return state.app_state.status_text.clone();
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return "No document".to_string();
+    };
+    let columns = &mut tab.document.metadata.columns;
+    if columns.count() != usize::from(count) {
+        columns.count = count;
+        // The columns are saved with the document, so changing them is an edit.
+        tab.document.mark_edited();
+        tab.dirty = true;
+    }
+    match count {
+        1 => "Text in one column".to_string(),
+        count => format!("Text in {count} columns"),
+    }
+}
+
+/// Sets the active document's language, or with `None` goes back to detecting it.
+fn set_document_language(state: &mut WindowState, language: Option<String>) -> String {
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return "No document".to_string();
+    };
+    if tab.document.metadata.language == language {
+        return state.app_state.status_text.clone();
+    }
+    tab.document.metadata.language = language;
+    // The language is saved with the document, so choosing one is an edit.
+    tab.document.mark_edited();
+    tab.dirty = true;
+    let name = language_name(document_language(&tab.document)).to_string();
+    if tab.document.metadata.language.is_some() {
+        format!("Document language: {name}")
+    } else {
+        format!("Document language: detected {name}")
+    }
+}
+
+/// Shows a popup menu of `items`, each a label with its `MF_*` flags, at the client point
+/// `at` and returns the position of the item picked, or `None` when the menu is dismissed.
+fn track_popup_menu<'a>(
+    hwnd: HWND,
+    items: impl IntoIterator<Item = (&'a str, MENU_ITEM_FLAGS)>,
+    at: UiPoint,
+    flags: TRACK_POPUP_MENU_FLAGS,
+) -> Result<Option<usize>> {
+    let menu = unsafe { CreatePopupMenu() }?;
+    for (position, (label, item_flags)) in items.into_iter().enumerate() {
+        let label = to_wide_null(label);
+        // Command ids start at 1 because TrackPopupMenu returns 0 when dismissed.
+        let _ = unsafe { AppendMenuW(menu, item_flags, position + 1, PCWSTR(label.as_ptr())) };
+    }
+
+    let mut screen = POINT {
+        x: at.x as i32,
+        y: at.y as i32,
+    };
+    let _ = unsafe { ClientToScreen(hwnd, &mut screen) };
+    let chosen = unsafe {
+        TrackPopupMenu(
+            menu,
+            TPM_RETURNCMD | flags,
+            screen.x,
+            screen.y,
+            None,
+            hwnd,
+            None,
+        )
+    };
+    let _ = unsafe { DestroyMenu(menu) };
+    Ok((chosen.0 as usize).checked_sub(1))
+}
+
+/// Shows the tab strip's right-click menu for the tab at `index` and runs the chosen item.
+fn show_tab_context_menu(state: &mut WindowState, hwnd: HWND, index: usize, point: UiPoint) {
+    let items = context_menu_items(ContextMenuKind::Tab);
+    let entries = items.iter().map(|item| {
+        let supported = matches!(
+            item.action,
+            ContextAction::CloseTab
+                | ContextAction::DuplicateTab
+                | ContextAction::CloseAll
+                | ContextAction::CloseSaved
+                | ContextAction::CopyFilePath
+                | ContextAction::CopyFileName
+                | ContextAction::ShowInExplorer
+        );
+        let flags = if item.enabled && supported {
+            MF_STRING
+        } else {
+            MF_STRING | MF_GRAYED
+        };
+        (item.label, flags)
+    });
+    let chosen = track_popup_menu(hwnd, entries, point, TPM_RIGHTBUTTON);
+    let Some(item) = chosen.ok().flatten().and_then(|i| items.get(i)) else {
+        return;
+    };
+    match item.action {
+        ContextAction::CloseTab => {
+            let _ = close_tab_with_prompt(state, hwnd, index);
+        }
+        ContextAction::DuplicateTab => duplicate_tab(state, index),
+        ContextAction::CloseAll => close_tabs(state, hwnd, BulkClose::All),
+        ContextAction::CloseSaved => close_tabs(state, hwnd, BulkClose::Saved),
+        action => apply_tab_file_action(state, index, action),
+    }
+}
+
 fn save_active_document(state: &mut WindowState, hwnd: HWND, save_as: bool) -> bool {
-    let (existing_path, document) = {
+    let _ = save_active_tab(state, hwnd, save_as);
+    true
+}
+
+/// Saves the active tab, asking for a path when it has none or `save_as` wants a new one.
+fn save_active_tab(state: &mut WindowState, hwnd: HWND, save_as: bool) -> SaveOutcome {
+    if active_tab_is_loading(state) {
+        state.app_state.status_text = "Document is still loading".to_string();
+        return SaveOutcome::Skipped;
+    }
+    let (existing_path, mut document, text, keep_original_images) = {
         let Some(tab) = state.tabs.active_tab() else {
             state.app_state.status_text = "No active tab to save".to_string();
-            return true;
+            return SaveOutcome::Skipped;
         };
         (
             tab.file_path
                 .clone()
-                .or_else(|| tab.document.metadata.file_path.clone()),
+                .or_else(|| tab.document.metadata.file_path.clone())
+                // Doco can't write EPUB, so saving one asks where to put a copy instead.
+                .filter(|path| detect_format(path) != DocumentFormat::Epub),
             tab.document.clone(),
+            tab.text_view.as_ref().map(|view| view.document.clone()),
+            tab.keep_original_images,
         )
     };
 
@@ -645,43 +1525,139 @@ fn save_active_document(state: &mut WindowState, hwnd: HWND, save_as: bool) -> b
 
     let Some(target) = target else {
         state.app_state.status_text = "Save cancelled".to_string();
-        return true;
+        return SaveOutcome::Cancelled;
     };
 
     if target.exists() && path_is_read_only(target.as_path()) {
         state.app_state.status_text = format!("Save blocked (read-only): {}", target.display());
-        return true;
+        return SaveOutcome::Skipped;
+    }
+
+    // A streamed text view only has its text to write, which would not make a valid
+    // Word, PDF or EPUB file.
+    if text.is_some()
+        && matches!(
+            detect_format(target.as_path()),
+            DocumentFormat::Docx | DocumentFormat::Pdf | DocumentFormat::Epub
+        )
+    {
+        state.app_state.status_text = format!(
+            "Save blocked: large text files can only be saved as text, not {}",
+            target.display()
+        );
+        return SaveOutcome::Skipped;
+    }
+
+    // Large pictures are shrunk in what gets written; once that succeeds the tab takes the
+    // same pictures and keeps the originals so they can be restored this session.
+    let files = &state.app_state.settings.files;
+    let optimized = if text.is_none()
+        && files.optimize_images_on_save
+        && !keep_original_images
+        && matches!(
+            detect_format(target.as_path()),
+            DocumentFormat::Docx | DocumentFormat::Pdf
+        ) {
+        ImageReplacements::optimized(&document, files.image_quality)
+    } else {
+        ImageReplacements::default()
+    };
+    let optimized_count = optimized.len();
+    if !optimized.is_empty() {
+        let _ = optimized.clone().apply(&mut document);
     }
 
-    match save_with_format(target.as_path(), &document) {
+    let saved = match &text {
+        Some(text) => text.save_to_path(target.as_path()),
+        None => save_with_format(target.as_path(), &document),
+    };
+    match saved {
         Ok(_) => {
             if let Some(tab) = state.tabs.active_tab_mut() {
+                if let Some(view) = &mut tab.text_view {
+                    view.dirty = false;
+                }
+                if !optimized.is_empty() {
+                    let originals = optimized.apply(&mut tab.document);
+                    tab.original_images.remember(originals);
+                    // The saved pictures replace the ones on screen without making the
+                    // document dirty again.
+                    tab.document.revision = Revision::next();
+                }
                 tab.file_path = Some(target.clone());
                 tab.title = document_title_from_path(target.as_path());
                 tab.document.metadata.file_path = Some(target.clone());
                 tab.document.metadata.format = detect_format(target.as_path());
                 tab.document.dirty = false;
                 tab.dirty = false;
+                tab.disk_modified = modified_time(target.as_path());
             }
             state.jump_list.add_recent_file(target.clone());
             let _ = state.app_state.autosave.clear_recovery_files();
-            state.app_state.status_text = format!("Saved {}", target.display());
+            state.app_state.status_text = if optimized_count > 0 {
+                format!(
+                    "Saved {} ({optimized_count} image(s) optimized)",
+                    target.display()
+                )
+            } else {
+                format!("Saved {}", target.display())
+            };
             sync_sidebar_with_active_tab(state);
+            SaveOutcome::Saved
         }
         Err(err) => {
             state.app_state.status_text = format!("Save failed: {err}");
+            SaveOutcome::Failed
         }
     }
-    true
 }
 
-fn export_active_document(state: &mut WindowState, hwnd: HWND, ext: &str) -> bool {
-    let document = {
-        let Some(tab) = state.tabs.active_tab() else {
-            state.app_state.status_text = "No active tab to export".to_string();
-            return true;
-        };
-        tab.document.clone()
+/// Puts back the pictures "optimize images on save" replaced in the active tab; later saves
+/// this session leave them as they are.
+fn restore_original_images(state: &mut WindowState) -> String {
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return "No document".to_string();
+    };
+    if tab.original_images.is_empty() {
+        return "No optimized images to restore".to_string();
+    }
+    if let Err(message) = tab.edit_guard() {
+        return message.to_string();
+    }
+    let originals = std::mem::take(&mut tab.original_images);
+    let count = originals.len();
+    let _ = originals.apply(&mut tab.document);
+    tab.keep_original_images = true;
+    tab.document.mark_edited();
+    tab.dirty = true;
+    format!("Restored {count} original image(s); save to keep them")
+}
+
+/// Saves every document tab with unsaved changes, bringing each to the front so untitled
+/// ones can be named, then returns to the tab that was active and reports the tally.
+fn save_all_documents(state: &mut WindowState, hwnd: HWND) {
+    let previous = state.tabs.active;
+    let dirty = state.tabs.dirty_document_tabs();
+    let summary = save_all(&dirty, |index| {
+        if state.tabs.active != index {
+            state.tabs.set_active(index);
+        }
+        save_active_tab(state, hwnd, false)
+    });
+    if state.tabs.active != previous {
+        state.tabs.set_active(previous);
+        sync_sidebar_with_active_tab(state);
+    }
+    state.app_state.status_text = summary.message();
+}
+
+fn export_active_document(state: &mut WindowState, hwnd: HWND, ext: &str) -> bool {
+    let document = {
+        let Some(tab) = state.tabs.active_tab() else {
+            state.app_state.status_text = "No active tab to export".to_string();
+            return true;
+        };
+        tab.document.clone()
     };
 
     let Some(path) = pick_save_target_for_active_tab(state, hwnd, Some(ext)) else {
@@ -730,7 +1706,7 @@ fn restore_recovery_tabs(state: &mut WindowState) -> usize {
             Err(_) => continue,
         };
         document.metadata.file_path = None;
-        document.dirty = true;
+        document.mark_edited();
         let title = recovery
             .file_stem()
             .and_then(|v| v.to_str())
@@ -744,6 +1720,13 @@ fn restore_recovery_tabs(state: &mut WindowState) -> usize {
 
 fn sync_sidebar_with_active_tab(state: &mut WindowState) {
     let mut root_path = None;
+    let document_path = state.tabs.active_tab().and_then(|tab| {
+        tab.file_path
+            .clone()
+            .or_else(|| tab.document.metadata.file_path.clone())
+    });
+    refresh_workspace_overrides(state, document_path.as_deref());
+    sync_bookmarks_with_active_tab(state);
     if let Some(tab) = state.tabs.active_tab() {
         state.sidebar.populate_outline(&tab.document);
         state
@@ -787,35 +1770,372 @@ fn sync_sidebar_with_active_tab(state: &mut WindowState) {
     }
 }
 
+/// Picks up the `.doco.json` governing `document`, reading it again only when it is a
+/// different file or was edited since it was loaded, and applies it to the live settings.
+fn refresh_workspace_overrides(state: &mut WindowState, document: Option<&Path>) {
+    let found = document.and_then(WorkspaceOverrides::find_for);
+    let unchanged = match (&state.workspace, &found) {
+        (Some(current), Some(path)) => current.path == *path && current.is_current(),
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged {
+        return;
+    }
+
+    state.workspace = found.and_then(|path| match WorkspaceOverrides::load(&path) {
+        Ok(workspace) => Some(workspace),
+        Err(error) => {
+            state.app_state.status_text = format!("Ignoring {}: {error}", path.display());
+            None
+        }
+    });
+    state.settings_dialog.set_workspace(state.workspace.clone());
+    state.app_state.settings = effective_settings(state);
+    apply_editing_settings(state);
+}
+
+/// The global settings with the active workspace's overrides, if any, merged over them.
+fn effective_settings(state: &WindowState) -> Settings {
+    let global = state.settings_dialog.settings();
+    match &state.workspace {
+        Some(workspace) => workspace.apply(global),
+        None => global.clone(),
+    }
+}
+
+/// Pushes the editor and files settings a workspace may override into the parts of the
+/// window that keep their own copy.
+fn apply_editing_settings(state: &mut WindowState) {
+    state
+        .find_replace
+        .apply_settings(state.app_state.settings.editor.find);
+    state
+        .jump_list
+        .set_recent_limit(state.app_state.settings.files.recent_files_count as usize);
+    let autosave_interval = state.app_state.settings.files.auto_save_interval;
+    state.app_state.autosave.set_interval(autosave_interval);
+}
+
 fn open_path_from_sidebar(state: &mut WindowState, path: PathBuf, new_tab: bool) {
+    let title = begin_background_load(state, path, new_tab);
+    state.app_state.status_text = format!("Loading {title}...");
+    sync_sidebar_with_active_tab(state);
+}
+
+fn loading_placeholder_document(path: &Path) -> DocumentModel {
+    let mut document = DocumentModel::default();
+    document.metadata.file_path = Some(path.to_path_buf());
+    document.metadata.title = document_title_from_path(path);
+    document.content.push(Block::Paragraph(Paragraph {
+        id: BlockId(0),
+        runs: vec![Run {
+            text: "Loading...".to_string(),
+            style: RunStyle::default(),
+        }],
+        alignment: ParagraphAlignment::Left,
+        spacing: ParagraphSpacing::default(),
+        indent: Indent::default(),
+        style_id: None,
+        direction: Default::default(),
+        frame: None,
+    }));
+    document
+}
+
+/// Shows a "Loading..." tab for `path` (a new one, or the active tab when `new_tab` is false)
+/// and parses the file on a worker; `process_document_loads` fills the tab in as it arrives.
+fn begin_background_load(state: &mut WindowState, path: PathBuf, new_tab: bool) -> String {
     let title = document_title_from_path(&path);
-    let document = load_document_for_path(&path);
-    if new_tab {
-        state
-            .tabs
-            .open_document_tab(title.clone(), Some(path), document);
-    } else if let Some(tab) = state.tabs.active_tab_mut() {
+    let placeholder = loading_placeholder_document(&path);
+    let reuse_active = !new_tab && state.tabs.active_tab().is_some();
+    let tab_id = if reuse_active {
+        let Some(tab) = state.tabs.active_tab_mut() else {
+            return title;
+        };
         tab.title = title.clone();
-        tab.kind = TabKind::Document;
-        tab.file_path = Some(path);
-        tab.document = document;
+        tab.file_path = Some(path.clone());
+        tab.document = placeholder;
+        tab.text_view = None;
         tab.cursor = Default::default();
         tab.canvas = Default::default();
         tab.dirty = false;
+        tab.read_only = opens_read_only(&path);
+        tab.disk_modified = modified_time(&path);
+        tab.kind = TabKind::Loading;
+        tab.id
     } else {
-        state
+        let index = state
             .tabs
-            .open_document_tab(title.clone(), Some(path), document);
+            .open_document_tab(title.clone(), Some(path.clone()), placeholder);
+        let tab = &mut state.tabs.tabs[index];
+        tab.read_only = opens_read_only(&path);
+        tab.disk_modified = modified_time(&path);
+        tab.kind = TabKind::Loading;
+        tab.id
+    };
+
+    let as_table =
+        state.app_state.settings.files.open_delimited_as_table && is_delimited_path(&path);
+    let as_text_view = !as_table
+        && matches!(
+            detect_format(&path),
+            DocumentFormat::Text | DocumentFormat::Unknown
+        )
+        && TextDocument::should_stream(&path);
+    let job = if as_text_view {
+        DocumentLoadJob::spawn_text(tab_id, path)
+    } else if as_table {
+        DocumentLoadJob::spawn(tab_id, path, load_table_for_path)
+    } else {
+        DocumentLoadJob::spawn(tab_id, path, load_document_for_path)
+    };
+    // Dropping a superseded job for the same tab cancels its worker.
+    state.document_loads.retain(|job| job.tab_id != tab_id);
+    state.document_loads.push(job);
+    title
+}
+
+/// Applies worker output to loading tabs. Jobs whose tab has been closed are dropped, which
+/// cancels them. Returns true when any tab changed.
+fn process_document_loads(state: &mut WindowState) -> bool {
+    let mut changed = false;
+    let mut finished_titles = Vec::new();
+    let tabs = &mut state.tabs.tabs;
+    let fold_store = &state.fold_store;
+    let bookmark_store = &state.bookmark_store;
+    let bookmarks_tab = state.bookmarks_tab;
+    // Bookmarks for a tab the sidebar already lists go to the sidebar instead.
+    let mut listed_bookmarks = None;
+    state.document_loads.retain_mut(|job| {
+        let Some(tab) = tabs.iter_mut().find(|tab| tab.id == job.tab_id) else {
+            job.cancel();
+            return false;
+        };
+        match job.poll() {
+            LoadProgress::Pending => true,
+            LoadProgress::Blocks(blocks) => {
+                if job.blocks_delivered() == blocks.len() {
+                    tab.document.content.clear();
+                }
+                tab.document.content.extend(blocks);
+                tab.document.revision = Revision::next();
+                changed = true;
+                true
+            }
+            LoadProgress::Finished(model) => {
+                let content = std::mem::take(&mut tab.document.content);
+                let streamed_any = job.blocks_delivered() > 0;
+                tab.document = *model;
+                if streamed_any {
+                    tab.document.content = content;
+                }
+                tab.document.dirty = false;
+                tab.dirty = false;
+                tab.kind = TabKind::Document;
+                tab.folded = resolve_folds(&tab.document.content, fold_store.restore(&job.path));
+                let bookmarks =
+                    restore_bookmarks(&tab.document.content, bookmark_store.restore(&job.path));
+                if bookmarks_tab == Some(tab.id) {
+                    listed_bookmarks = Some(bookmarks);
+                } else {
+                    tab.bookmarks = bookmarks;
+                }
+                finished_titles.push(tab.title.clone());
+                changed = true;
+                false
+            }
+            LoadProgress::Text(text) => {
+                tab.document = DocumentModel::default();
+                tab.document.metadata.format = DocumentFormat::Text;
+                tab.document.metadata.title = tab.title.clone();
+                tab.document.metadata.file_path = Some(job.path.clone());
+                tab.text_view = Some(TextView::new(text));
+                tab.dirty = false;
+                tab.kind = TabKind::Document;
+                finished_titles.push(tab.title.clone());
+                changed = true;
+                false
+            }
+            LoadProgress::Failed => {
+                tab.document.content.clear();
+                tab.document.revision = Revision::next();
+                tab.kind = TabKind::Document;
+                finished_titles.push(format!("{} (failed)", job.path.display()));
+                changed = true;
+                false
+            }
+        }
+    });
+    if let Some(bookmarks) = listed_bookmarks {
+        let _ = state.sidebar.swap_bookmarks(bookmarks);
     }
-    state.app_state.status_text = format!("Opened {title}");
-    sync_sidebar_with_active_tab(state);
+
+    if let Some(title) = finished_titles.last() {
+        let read_only = state.tabs.active_tab().is_some_and(|tab| tab.read_only);
+        state.app_state.status_text = if read_only {
+            format!("Opened {title} (read-only)")
+        } else {
+            format!("Opened {title}")
+        };
+    }
+    if changed {
+        sync_sidebar_with_active_tab(state);
+    }
+    changed
+}
+
+/// Shows the read-only status and returns true when the active tab refuses edits.
+fn edit_blocked_by_read_only(state: &mut WindowState) -> bool {
+    let Some(Err(message)) = state.tabs.active_tab().map(|tab| tab.edit_guard()) else {
+        return false;
+    };
+    state.app_state.status_text = message.to_string();
+    true
+}
+
+/// Types `text` into the active tab's streamed text view, if it has one.
+fn insert_into_text_view(state: &mut WindowState, text: &str) -> bool {
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let Some(view) = tab.text_view.as_mut() else {
+        return false;
+    };
+    if view.insert(text).is_ok() {
+        view.reveal_cursor(TEXT_VIEW_ROWS);
+        tab.dirty = true;
+    }
+    true
+}
+
+/// Editing and caret keys for a streamed text view; returns false when the active tab has
+/// no text view or the key isn't one it handles.
+fn apply_text_view_key(state: &mut WindowState, vk: u32) -> bool {
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let Some(view) = tab.text_view.as_mut() else {
+        return false;
+    };
+    let rows = TEXT_VIEW_ROWS as isize;
+    match vk {
+        0x08 => view.backspace(),
+        0x0D => {
+            let _ = view.insert("\n");
+        }
+        0x09 => {
+            let _ = view.insert("\t");
+        }
+        0x2E => view.delete_forward(),
+        0x25 => view.move_cursor(0, -1),
+        0x27 => view.move_cursor(0, 1),
+        0x26 => view.move_cursor(-1, 0),
+        0x28 => view.move_cursor(1, 0),
+        0x24 => view.move_to_line_edge(false),
+        0x23 => view.move_to_line_edge(true),
+        0x21 => {
+            view.move_cursor(-rows, 0);
+            view.scroll_by(-rows);
+        }
+        0x22 => {
+            view.move_cursor(rows, 0);
+            view.scroll_by(rows);
+        }
+        _ => return false,
+    }
+    view.reveal_cursor(TEXT_VIEW_ROWS);
+    if view.dirty {
+        tab.dirty = true;
+    }
+    true
+}
+
+/// Locks or unlocks editing in the active tab and returns the status to show.
+fn set_active_tab_read_only(state: &mut WindowState, read_only: bool) -> String {
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return "No active tab".to_string();
+    };
+    if tab.kind != TabKind::Document {
+        return "Only documents can be locked".to_string();
+    }
+    tab.read_only = read_only;
+    if read_only {
+        format!("{} is read-only", tab.title)
+    } else {
+        format!("Editing enabled for {}", tab.title)
+    }
+}
+
+/// Offers to reload each tab whose file another program rewrote since Doco last loaded or
+/// saved it. Returns true when the user was asked about any tab.
+fn check_external_changes(state: &mut WindowState, hwnd: HWND) -> bool {
+    let mut asked = false;
+    for index in 0..state.tabs.tabs.len() {
+        let (tab_id, path, prompt, dirty) = {
+            let tab = &mut state.tabs.tabs[index];
+            let Some(path) = tab.file_path.clone() else {
+                continue;
+            };
+            let current = modified_time(&path);
+            if tab.kind != TabKind::Document || !changed_externally(tab.disk_modified, current) {
+                continue;
+            }
+            // Take the new time before prompting; the message box steals focus, and
+            // reactivating the window must not ask again.
+            tab.disk_modified = current;
+            let dirty = is_tab_dirty(tab);
+            let mut prompt = format!("'{}' was changed by another program. Reload it?", tab.title);
+            if dirty {
+                prompt.push_str("\n\nYour unsaved changes in Doco will be lost.");
+            }
+            (tab.id, path, prompt, dirty)
+        };
+        asked = true;
+
+        let prompt_wide = to_wide_null(prompt.as_str());
+        let icon = if dirty {
+            MB_ICONWARNING
+        } else {
+            MB_ICONQUESTION
+        };
+        let choice = unsafe {
+            MessageBoxW(
+                Some(hwnd),
+                PCWSTR(prompt_wide.as_ptr()),
+                w!("Doco"),
+                MB_YESNO | icon,
+            )
+        };
+        if choice == IDYES {
+            state.tabs.set_active(index);
+            state.edit_engines.remove(&tab_id);
+            state.search_indexes.remove(&tab_id);
+            let title = begin_background_load(state, path, false);
+            state.app_state.status_text = format!("Reloading {title}");
+        } else {
+            state.app_state.status_text = format!("Kept the open copy of {}", path.display());
+        }
+    }
+    if asked {
+        sync_sidebar_with_active_tab(state);
+    }
+    asked
+}
+
+fn active_tab_is_loading(state: &WindowState) -> bool {
+    state
+        .tabs
+        .active_tab()
+        .is_some_and(|tab| tab.kind == TabKind::Loading)
 }
 
 fn open_file_via_picker(state: &mut WindowState, hwnd: HWND, new_tab: bool) -> bool {
-    let Some(path) = pick_open_file(hwnd) else {
+    let Some(path) = pick_open_file(hwnd, dialog_start_dir(state).as_deref()) else {
         state.app_state.status_text = "Open cancelled".to_string();
         return true;
     };
+    remember_dialog_dir(state, &path);
     open_path_from_sidebar(state, path.clone(), new_tab);
     state.jump_list.add_recent_file(path);
     true
@@ -843,11 +2163,38 @@ fn apply_pending_sidebar_intents(state: &mut WindowState) -> bool {
                     changed = true;
                 }
             }
+            SidebarIntent::MoveSection { heading, before } => {
+                let command = state
+                    .tabs
+                    .active_tab()
+                    .and_then(|tab| move_section_command(&tab.document.content, heading, before));
+                if let Some(command) = command
+                    && apply_undoable_edit(state, command)
+                {
+                    sync_sidebar_with_active_tab(state);
+                    state.app_state.status_text = "Moved section".to_string();
+                    changed = true;
+                }
+            }
         }
     }
     changed
 }
 
+/// Jumps to the next (or previous) bookmark in the order the Bookmarks panel lists them.
+fn cycle_bookmark(state: &mut WindowState, forward: bool) {
+    let Some(name) = state
+        .sidebar
+        .cycle_bookmark(forward)
+        .map(|b| b.name.clone())
+    else {
+        state.app_state.status_text = "No bookmarks".to_string();
+        return;
+    };
+    apply_pending_sidebar_intents(state);
+    state.app_state.status_text = format!("Bookmark: {name}");
+}
+
 fn canvas_origin(state: &WindowState) -> UiPoint {
     let tab_h = if state.app_state.show_tabs { 36.0 } else { 0.0 };
     let toolbar_h = if state.app_state.show_toolbar {
@@ -856,7 +2203,7 @@ fn canvas_origin(state: &WindowState) -> UiPoint {
         0.0
     };
     let sidebar_w = if state.app_state.show_sidebar {
-        state.app_state.sidebar_width.clamp(200.0, 400.0)
+        clamp_sidebar_width(state.app_state.sidebar_width)
     } else {
         0.0
     };
@@ -896,12 +2243,10 @@ fn active_image_ref(
 }
 
 fn active_table_ref(state: &WindowState, table_id: BlockId) -> Option<&crate::document::model::Table> {
-    state.tabs.active_tab().and_then(|tab| {
-        tab.document.content.iter().find_map(|block| match block {
-            Block::Table(table) if table.id == table_id => Some(table),
-            _ => None,
-        })
-    })
+    state
+        .tabs
+        .active_tab()
+        .and_then(|tab| find_table(&tab.document, table_id))
 }
 
 fn open_table_picker(state: &mut WindowState) {
@@ -913,69 +2258,879 @@ fn open_table_picker(state: &mut WindowState) {
     state.table_picker_custom_focus_rows = true;
 }
 
-fn parse_table_picker_custom(value: &str, fallback: usize) -> usize {
-    value
-        .trim()
-        .parse::<usize>()
-        .ok()
-        .unwrap_or(fallback)
-        .clamp(1, 64)
+fn open_toolbar_customizer(state: &mut WindowState) {
+    let layout = state.toolbar.layout_ids();
+    state.toolbar.dropdown.close();
+    state.toolbar_customizer.open(&layout);
 }
 
-fn table_insert_index_for_cursor(tab: &crate::ui::tabs::TabState) -> usize {
-    tab.document
-        .content
-        .iter()
-        .position(|block| match block {
-            Block::Paragraph(p) => p.id == tab.cursor.primary.block_id,
-            Block::Heading(h) => h.id == tab.cursor.primary.block_id,
-            Block::CodeBlock(c) => c.id == tab.cursor.primary.block_id,
-            Block::Image(i) => i.id == tab.cursor.primary.block_id,
-            Block::Table(t) => t.id == tab.cursor.primary.block_id,
-            Block::BlockQuote(q) => q.id == tab.cursor.primary.block_id,
-            _ => false,
-        })
-        .map(|idx| idx + 1)
-        .unwrap_or(tab.document.content.len())
+fn open_goto_symbol(state: &mut WindowState) {
+    if state.sidebar.outline_items.is_empty() {
+        state.app_state.status_text = "No headings".to_string();
+        return;
+    }
+    state.goto_symbol.open(&state.sidebar.outline_items);
+    state.cross_reference_pick = None;
+    state.focus_region = FocusRegion::Document;
+    state.app_state.status_text = "Go to heading".to_string();
 }
 
-fn insert_table_at_cursor(state: &mut WindowState, rows: usize, cols: usize) -> Option<BlockId> {
-    let inserted = {
-        let tab = state.tabs.active_tab_mut()?;
-        let insert_idx = table_insert_index_for_cursor(tab);
-        let id = insert_table(&mut tab.document, insert_idx, rows, cols);
-        tab.cursor.primary.block_id = id;
-        tab.cursor.primary.offset = 0;
-        tab.dirty = true;
-        id
+/// Jumps to the heading picked in the go-to-heading overlay, or refers to it when the
+/// overlay was opened to pick a cross-reference target.
+fn apply_goto_symbol(state: &mut WindowState) {
+    if let Some(block_id) = state.goto_symbol.pending_jump.take() {
+        match state.cross_reference_pick.take() {
+            Some(display) => insert_cross_reference(state, block_id, display),
+            None => jump_to_heading(state, block_id),
+        }
+    }
+}
+
+/// Moves the caret to a heading the same way picking it in the Outline panel does.
+fn jump_to_heading(state: &mut WindowState, block_id: BlockId) {
+    state
+        .sidebar
+        .queue_intent(SidebarIntent::JumpToBlock(block_id));
+    apply_pending_sidebar_intents(state);
+}
+
+/// Fetches the release manifest in the background unless a check is already running.
+fn start_update_check(state: &mut WindowState, manual: bool) {
+    if state.update_check.is_some() {
+        return;
+    }
+    let url = state
+        .settings_dialog
+        .settings()
+        .about
+        .update_manifest_url
+        .clone();
+    state.update_check = Some(UpdateCheck::spawn(
+        url,
+        env!("CARGO_PKG_VERSION").to_string(),
+        manual,
+    ));
+    if manual {
+        state.app_state.status_text = "Checking for updates\u{2026}".to_string();
+    }
+}
+
+/// Records a finished update check. A check the user asked for reports every outcome; the
+/// startup check stays quiet unless there is something new.
+fn process_update_check(state: &mut WindowState) {
+    let Some(outcome) = state.update_check.as_ref().and_then(UpdateCheck::poll) else {
+        return;
     };
+    let manual = state.update_check.take().is_some_and(|check| check.manual);
+    if outcome == UpdateOutcome::Failed {
+        if manual {
+            state.app_state.status_text = "Couldn't reach the update server".to_string();
+        }
+        return;
+    }
 
-    state.selected_table = Some(inserted);
-    state.table_selection_mode = Some(TableSelectionMode::Cell(CellPos { row: 0, col: 0 }));
-    state.table_selection_range = Some(TableSelection {
-        start: CellPos { row: 0, col: 0 },
-        end: CellPos { row: 0, col: 0 },
+    let available = match &outcome {
+        UpdateOutcome::Available { version, url } => Some((version.clone(), url.clone())),
+        _ => None,
+    };
+    state.settings_dialog.apply_change(|settings| {
+        settings.about.last_update_check_utc = Some(chrono::Utc::now());
+        settings.about.latest_version = available.as_ref().map(|(version, _)| version.clone());
+        settings.about.latest_release_url = available.as_ref().map(|(_, url)| url.clone());
     });
-    sync_sidebar_with_active_tab(state);
-    Some(inserted)
+    match available {
+        Some((version, _)) => {
+            state.toast.push(
+                ToastLevel::Info,
+                "Update available",
+                format!("Doco {version} is out. Settings \u{203A} About links to the release."),
+            );
+            state.app_state.status_text = format!("Update available: Doco {version}");
+        }
+        None if manual => {
+            state.app_state.status_text = "Doco is up to date".to_string();
+        }
+        None => {}
+    }
 }
 
-fn insert_table_from_picker(state: &mut WindowState) -> Option<BlockId> {
-    let rows = parse_table_picker_custom(state.table_picker_custom_rows.as_str(), state.table_picker_rows);
-    let cols = parse_table_picker_custom(state.table_picker_custom_cols.as_str(), state.table_picker_cols);
-    state.table_picker_rows = rows.clamp(1, 10);
-    state.table_picker_cols = cols.clamp(1, 10);
-    state.table_picker_visible = false;
-    insert_table_at_cursor(state, rows, cols)
+/// Runs what an About row asked for: a manual update check, opening the release page or
+/// the licenses list, or copying the system info.
+fn apply_about_action(state: &mut WindowState) {
+    match state.settings_dialog.pending_about_action.take() {
+        Some(AboutAction::CheckForUpdates) => start_update_check(state, true),
+        Some(AboutAction::OpenRelease(url)) => {
+            state.app_state.status_text = match open_in_browser(&url) {
+                Ok(()) => "Opened the release page".to_string(),
+                Err(error) => format!("Couldn't open the release page: {error}"),
+            };
+        }
+        Some(AboutAction::OpenLicenses) => state.licenses_view.open(),
+        Some(AboutAction::CopySystemInfo) => {
+            // Memory moves while the app runs, so copy a fresh reading.
+            let report = capture_system_info(state);
+            state.app_state.status_text = match set_plain_text(&report) {
+                Ok(()) => "System info copied for a bug report".to_string(),
+                Err(error) => format!("Couldn't copy system info: {error}"),
+            };
+        }
+        None => {}
+    }
 }
 
-fn table_picker_layout(state: &WindowState) -> TablePickerLayout {
-    let origin = canvas_origin(state);
-    let panel = UiRect {
-        x: origin.x + 10.0,
-        y: origin.y + 10.0,
-        width: 292.0,
-        height: 236.0,
+/// Opens the repository of the crate picked in the licenses list, if any.
+fn open_license_repository(state: &mut WindowState) {
+    let Some(url) = state.licenses_view.pending_open.take() else {
+        return;
+    };
+    state.app_state.status_text = match open_in_browser(&url) {
+        Ok(()) => format!("Opened {url}"),
+        Err(error) => format!("Couldn't open {url}: {error}"),
+    };
+}
+
+/// Takes a system info snapshot, keeps it for the About page and returns its report text.
+fn capture_system_info(state: &mut WindowState) -> String {
+    let probe = SystemProbe::query(
+        state.renderer.as_ref().and_then(|r| r.adapter().cloned()),
+        state.renderer.as_ref().map(|r| r.driver()),
+    );
+    let report = SystemInfo::assemble(env!("CARGO_PKG_VERSION"), &probe).report();
+    state
+        .settings_dialog
+        .apply_change(|settings| settings.about.system_info_snapshot = report.clone());
+    report
+}
+
+fn open_shortcut_editor(state: &mut WindowState) {
+    let shortcuts = &state.settings_dialog.settings().keyboard_shortcuts;
+    state.shortcut_editor.open(shortcuts);
+}
+
+/// Saves the bindings the shortcut editor produced, if any.
+fn apply_shortcut_edits(state: &mut WindowState, hwnd: HWND) {
+    let Some(bindings) = state.shortcut_editor.pending_bindings.take() else {
+        return;
+    };
+    state
+        .settings_dialog
+        .apply_change(|settings| settings.keyboard_shortcuts = bindings);
+    sync_runtime_from_settings(state, hwnd);
+    state.app_state.status_text = "Shortcuts updated".to_string();
+}
+
+/// Saves the layout the customize dialog produced, if any, and applies it to the toolbar.
+fn apply_toolbar_customization(state: &mut WindowState, hwnd: HWND) {
+    let Some(layout) = state.toolbar_customizer.pending_layout.take() else {
+        return;
+    };
+    state
+        .settings_dialog
+        .apply_change(|settings| settings.appearance.toolbar_layout = Some(layout));
+    sync_runtime_from_settings(state, hwnd);
+    state.app_state.status_text = "Toolbar updated".to_string();
+}
+
+/// A selection picked up with the mouse, and where it would land if dropped now.
+#[derive(Debug, Clone)]
+struct TextDrag {
+    /// Holds the selection as byte offsets, the unit text edits address.
+    session: TextDragSession,
+    target: Option<CanvasTextHit>,
+}
+
+/// Text under the pointer: a block offset, plus the preview line position for the caret.
+#[derive(Debug, Clone, Copy)]
+struct CanvasTextHit {
+    block_id: BlockId,
+    /// Char offset into the block's text.
+    offset: usize,
+    line: usize,
+    utf16: u32,
+}
+
+/// Where "Go to footnote" left from, so running it again returns there.
+#[derive(Debug, Clone, Copy)]
+struct FootnoteReturn {
+    tab_id: u64,
+    block_id: BlockId,
+    offset: usize,
+    scroll_y: f32,
+}
+
+/// Jumps from the footnote reference at the cursor to the footnotes region at the end of the
+/// document, or back to the reference when the last jump came from this tab.
+fn toggle_footnote_jump(state: &mut WindowState) {
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return;
+    };
+
+    if let Some(back) = state.footnote_return.take()
+        && back.tab_id == tab.id
+    {
+        tab.cursor.primary.block_id = back.block_id;
+        tab.cursor.primary.offset = back.offset;
+        tab.canvas.scroll.y = back.scroll_y;
+        tab.canvas.clamp_scroll(&tab.document);
+        tab.canvas.mark_dirty_full();
+        state.app_state.status_text = "Back to footnote reference".to_string();
+        return;
+    }
+
+    let cursor = tab.cursor.primary;
+    let label = block_runs_by_id(&tab.document.content, cursor.block_id)
+        .and_then(|runs| footnote_label_near_offset(runs, cursor.offset));
+    let Some((label, number)) =
+        label.and_then(|label| tab.document.footnote_number(&label).map(|number| (label, number)))
+    else {
+        state.app_state.status_text = "No footnote reference at the cursor".to_string();
+        return;
+    };
+
+    state.footnote_return = Some(FootnoteReturn {
+        tab_id: tab.id,
+        block_id: cursor.block_id,
+        offset: cursor.offset,
+        scroll_y: tab.canvas.scroll.y,
+    });
+    // The footnotes region closes the document, so its end is where the notes are.
+    tab.canvas.scroll.y = tab.canvas.content_size(&tab.document).height;
+    tab.canvas.clamp_scroll(&tab.document);
+    tab.canvas.mark_dirty_full();
+    let text = tab.document.footnotes[number - 1]
+        .runs
+        .iter()
+        .map(|r| r.text.as_str())
+        .collect::<String>();
+    state.app_state.status_text = format!("Footnote {number} [{label}]: {}", text.replace('\n', " "));
+}
+
+/// Label of the footnote reference under or after `offset` (in chars), else the last one
+/// before it.
+fn footnote_label_near_offset(runs: &[Run], offset: usize) -> Option<String> {
+    let mut start = 0usize;
+    let mut last = None;
+    for run in runs {
+        let end = start + run.text.chars().count();
+        if let Some(label) = &run.style.footnote {
+            if end >= offset {
+                return Some(label.clone());
+            }
+            last = Some(label.clone());
+        }
+        start = end;
+    }
+    last
+}
+
+fn block_runs_by_id(blocks: &[Block], id: BlockId) -> Option<&[Run]> {
+    blocks.iter().find_map(|block| match block {
+        Block::Paragraph(p) if p.id == id => Some(p.runs.as_slice()),
+        Block::Heading(h) if h.id == id => Some(h.runs.as_slice()),
+        Block::List(list) => list.items.iter().find_map(|item| block_runs_by_id(&item.content, id)),
+        Block::BlockQuote(q) => block_runs_by_id(&q.blocks, id),
+        _ => None,
+    })
+}
+
+/// Where following a run's link leads.
+enum LinkTarget {
+    Block(BlockId),
+    Wiki(String),
+    /// A footnote reference, which leads to its note.
+    Footnote,
+}
+
+/// The link on the run covering char `offset` of block `block_id`, if it has one.
+fn link_at(document: &DocumentModel, block_id: BlockId, offset: usize) -> Option<LinkTarget> {
+    let runs = block_runs_by_id(&document.content, block_id)?;
+    let mut start = 0usize;
+    runs.iter().find_map(|run| {
+        let end = start + run.text.chars().count();
+        let hit = (start..=end).contains(&offset);
+        start = end;
+        if hit && let Some(reference) = &run.style.cross_reference {
+            return resolve_cross_reference(document, &reference.anchor).map(LinkTarget::Block);
+        }
+        if hit && run.style.footnote.is_some() {
+            return Some(LinkTarget::Footnote);
+        }
+        match (hit, run.style.internal_link, &run.style.wiki_link) {
+            (false, _, _) => None,
+            (true, Some(block), _) => Some(LinkTarget::Block(block)),
+            (true, None, page) => page.clone().map(LinkTarget::Wiki),
+        }
+    })
+}
+
+/// Jumps to the block the link under the cursor points at, e.g. from a TOC line to its heading.
+fn follow_link_at_cursor(state: &mut WindowState) {
+    let target = state.tabs.active_tab().and_then(|tab| {
+        let cursor = tab.cursor.primary;
+        link_at(&tab.document, cursor.block_id, cursor.offset)
+    });
+    match target {
+        Some(target) => follow_link(state, target),
+        None => state.app_state.status_text = "No link at the cursor".to_string(),
+    }
+}
+
+/// Folds or unfolds the callout whose header was clicked on the canvas.
+fn toggle_callout_at_point(state: &mut WindowState, point: UiPoint) -> bool {
+    let Some(hit) = canvas_text_hit(state, point).filter(|_| canvas_contains(state, point)) else {
+        return false;
+    };
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    if !is_callout(&tab.document.content, hit.block_id) {
+        return false;
+    }
+    let collapsed = match tab.folded.iter().position(|id| *id == hit.block_id) {
+        Some(index) => {
+            tab.folded.remove(index);
+            false
+        }
+        None => {
+            tab.folded.push(hit.block_id);
+            true
+        }
+    };
+    state.app_state.status_text = if collapsed {
+        "Callout collapsed".to_string()
+    } else {
+        "Callout expanded".to_string()
+    };
+    true
+}
+
+/// Follows the link under a Ctrl+click on the canvas.
+fn follow_link_at_point(state: &mut WindowState, point: UiPoint) -> bool {
+    if !canvas_contains(state, point) {
+        return false;
+    }
+    // A note reached from its reference leads back there.
+    if state.footnote_return.is_some() && footnote_note_at_point(state, point) {
+        toggle_footnote_jump(state);
+        return true;
+    }
+    let Some(hit) = canvas_text_hit(state, point) else {
+        return false;
+    };
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let Some(target) = link_at(&tab.document, hit.block_id, hit.offset) else {
+        return false;
+    };
+    if matches!(target, LinkTarget::Footnote) {
+        // The jump starts from the reference clicked, not from an earlier one.
+        tab.cursor.primary.block_id = hit.block_id;
+        tab.cursor.primary.offset = hit.offset;
+        state.footnote_return = None;
+    }
+    follow_link(state, target);
+    true
+}
+
+/// Whether `point` is on one of the notes in the footnotes region closing the canvas.
+fn footnote_note_at_point(state: &WindowState, point: UiPoint) -> bool {
+    let Some((line, _)) = state
+        .renderer
+        .as_ref()
+        .and_then(|renderer| renderer.preview_position_at(point.x, point.y))
+    else {
+        return false;
+    };
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let notes = tab.document.footnotes.len();
+    let preview = collect_preview_lines(&tab.document, &tab.folded, 40);
+    notes > 0 && line < preview.len() && line >= preview.len() - notes
+}
+
+fn follow_link(state: &mut WindowState, target: LinkTarget) {
+    match target {
+        LinkTarget::Block(block_id) => {
+            let Some(tab) = state.tabs.active_tab_mut() else {
+                return;
+            };
+            tab.cursor.primary.block_id = block_id;
+            tab.cursor.primary.offset = 0;
+            state.sidebar.set_current_outline_block(Some(block_id));
+            state.app_state.status_text = format!("Jumped to block {}", block_id.0);
+        }
+        LinkTarget::Wiki(page) => open_wiki_page(state, &page),
+        LinkTarget::Footnote => toggle_footnote_jump(state),
+    }
+}
+
+/// Folder the active document's `[[wiki links]]` resolve against: the sidebar's folder when
+/// the document lives inside it, else the document's own folder.
+fn wiki_root(state: &WindowState) -> Option<PathBuf> {
+    let tab = state.tabs.active_tab()?;
+    let path = tab
+        .file_path
+        .as_ref()
+        .or(tab.document.metadata.file_path.as_ref())?;
+    match &state.sidebar.file_root {
+        Some(root) if path.starts_with(root) => Some(root.clone()),
+        _ => path.parent().map(Path::to_path_buf),
+    }
+}
+
+/// Opens the note `[[page]]` names, creating `<root>/<page>.md` when none matches yet. A
+/// note that is already open is switched to rather than opened twice.
+fn open_wiki_page(state: &mut WindowState, page: &str) {
+    let Some(root) = wiki_root(state) else {
+        state.app_state.status_text = "Save the document to follow wiki links".to_string();
+        return;
+    };
+    let pages = wiki_pages(&root);
+    let path = match find_wiki_page(&pages, page) {
+        Some(path) => path.to_path_buf(),
+        None => {
+            let path = new_wiki_page_path(&root, page);
+            if let Err(err) = std::fs::write(&path, format!("# {}\n", wiki_page_name(page))) {
+                state.app_state.status_text = format!("Could not create {}: {err}", path.display());
+                return;
+            }
+            path
+        }
+    };
+    state.wiki_pages = None;
+    state.jump_list.add_recent_file(path.clone());
+    if let Some(index) = state
+        .tabs
+        .tabs
+        .iter()
+        .position(|tab| tab.file_path.as_ref() == Some(&path))
+    {
+        state.tabs.set_active(index);
+        sync_sidebar_with_active_tab(state);
+        state.app_state.status_text = format!("Switched to {}", document_title_from_path(&path));
+        return;
+    }
+    open_path_from_sidebar(state, path, true);
+}
+
+/// Writes a generated list at the cursor, or over the one the document already has. The
+/// list goes into a copy of the document first, since its length moves what it lists, so
+/// each entry gets the page its block lands on once the list is in place. `build` makes the
+/// command writing the list for some blocks with a page lookup, and `listed` names each
+/// listed block of a document with the index of the top-level block it is on. `None` when
+/// `build` has nothing to write, else whether the list was written.
+fn write_generated_list(
+    state: &mut WindowState,
+    build: impl Fn(&[Block], usize, u64, &dyn Fn(BlockId) -> Option<usize>) -> Option<EditCommand>,
+    listed: impl Fn(&DocumentModel) -> Vec<(BlockId, usize)>,
+) -> Option<bool> {
+    let tab = state.tabs.active_tab()?;
+    let document = &tab.document;
+    let insert_at = find_block_index_by_id(document, tab.cursor.primary.block_id)
+        .unwrap_or(document.content.len());
+    let first_id = document.next_block_id().0;
+
+    let mut trial = DocumentModel {
+        metadata: document.metadata.clone(),
+        content: document.content.clone(),
+        ..DocumentModel::default()
+    };
+    let draft = build(&trial.content, insert_at, first_id, &|_| None)?;
+    let _ = apply_to_document(&mut trial, &draft);
+    let mut layout = BlockLayoutCache::default();
+    let _ = layout.refresh(&trial, page_dimensions_points(&trial));
+    let pages = listed(&trial)
+        .into_iter()
+        .filter_map(|(block_id, index)| Some((block_id, layout.block_page(index)? + 1)))
+        .collect::<HashMap<_, _>>();
+
+    let command = build(&document.content, insert_at, first_id, &|id| {
+        pages.get(&id).copied()
+    })?;
+    let written = apply_undoable_edit(state, command);
+    if written {
+        sync_sidebar_with_active_tab(state);
+    }
+    Some(written)
+}
+
+/// Inserts a Table of Contents at the cursor, or refreshes the one the document already has,
+/// numbering each heading with the page it lands on once the TOC itself is in place.
+fn write_table_of_contents(state: &mut WindowState, update: bool) {
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    if update && toc_range(&tab.document.content).is_none() {
+        state.app_state.status_text = "No table of contents to update".to_string();
+        return;
+    }
+    let written = write_generated_list(
+        state,
+        |blocks, insert_at, first_id, page_of| {
+            table_of_contents_command(blocks, insert_at, first_id, page_of)
+        },
+        |trial| {
+            toc_entries(&trial.content)
+                .into_iter()
+                .filter_map(|entry| {
+                    let index = find_block_index_by_id(trial, entry.block_id)?;
+                    Some((entry.block_id, index))
+                })
+                .collect()
+        },
+    );
+    state.app_state.status_text = match written {
+        None => "No headings for a table of contents".to_string(),
+        Some(false) => return,
+        Some(true) if update => "Table of contents updated".to_string(),
+        Some(true) => "Table of contents inserted".to_string(),
+    };
+}
+
+/// Inserts a List of Figures or List of Tables at the cursor, or refreshes the one the
+/// document already has, giving each caption the page its figure or table lands on. A list
+/// whose captions have all gone is cleared.
+fn write_caption_list(state: &mut WindowState, kind: CaptionKind, update: bool) {
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    let blocks = &tab.document.content;
+    let name = format!("List of {}s", kind.label().to_lowercase());
+    if update && caption_list_range(blocks, kind).is_none() {
+        state.app_state.status_text = format!("No {} to update", name.to_lowercase());
+        return;
+    }
+    let emptied = caption_entries(blocks, kind).is_empty();
+    let written = write_generated_list(
+        state,
+        |blocks, insert_at, first_id, page_of| {
+            caption_list_command(blocks, kind, insert_at, first_id, page_of)
+        },
+        |trial| {
+            caption_entries(&trial.content, kind)
+                .into_iter()
+                .filter_map(|entry| {
+                    let block_id = entry.number.block_id;
+                    Some((block_id, captioned_block_index(&trial.content, block_id)?))
+                })
+                .collect()
+        },
+    );
+    let captioned = format!("captioned {}s", kind.label().to_lowercase());
+    state.app_state.status_text = match written {
+        None => format!("No {captioned} to list"),
+        Some(false) => return,
+        Some(true) if emptied => format!("{name} cleared: no {captioned} left"),
+        Some(true) if update => format!("{name} updated"),
+        Some(true) => format!("{name} inserted"),
+    };
+}
+
+/// Gives the selected image or table a numbered caption, starting from the image's alt
+/// text, or takes away the one it has.
+fn toggle_caption(state: &mut WindowState) {
+    if edit_blocked_by_read_only(state) {
+        return;
+    }
+    if let Some(table_id) = state.selected_table {
+        let mut added = false;
+        let changed = edit_table_copy(state, table_id, |table| {
+            added = table.caption.is_none();
+            table.caption = added.then(Vec::new);
+            true
+        });
+        if changed {
+            state.app_state.status_text = caption_status(state, table_id, added);
+        }
+        return;
+    }
+    let Some(image_id) = state.selected_image else {
+        state.app_state.status_text = "Select an image or table to caption".to_string();
+        return;
+    };
+    let Some(image) = active_image_mut(state, image_id) else {
+        return;
+    };
+    let added = image.caption.is_none();
+    image.caption = added.then(|| {
+        let text = image.alt_text.trim();
+        if text.is_empty() {
+            Vec::new()
+        } else {
+            vec![Run {
+                text: text.to_string(),
+                style: RunStyle::default(),
+            }]
+        }
+    });
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        // Images change in place, outside the edit engine that keeps references current.
+        update_cross_references(&mut tab.document);
+        tab.document.mark_edited();
+        tab.dirty = true;
+    }
+    state.app_state.status_text = caption_status(state, image_id, added);
+}
+
+fn caption_status(state: &WindowState, block_id: BlockId, added: bool) -> String {
+    let label = state
+        .tabs
+        .active_tab()
+        .and_then(|tab| {
+            number_captions(&tab.document.content)
+                .into_iter()
+                .find(|number| number.block_id == block_id)
+        })
+        .map(|number| number.label());
+    match (added, label) {
+        (true, Some(label)) => format!("Captioned as {label}"),
+        _ => "Caption removed".to_string(),
+    }
+}
+
+/// Names the block at the cursor after its text so cross-references can point at it.
+fn add_anchor_at_cursor(state: &mut WindowState) {
+    if edit_blocked_by_read_only(state) {
+        return;
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return;
+    };
+    let block_id = tab.cursor.primary.block_id;
+    if let Some(name) = anchor_name_of(&tab.document, block_id) {
+        state.app_state.status_text = format!("Already anchored as \"{name}\"");
+        return;
+    }
+    state.app_state.status_text = match ensure_anchor(&mut tab.document, block_id) {
+        Some(name) => {
+            tab.document.mark_edited();
+            tab.dirty = true;
+            format!("Anchor \"{name}\" added")
+        }
+        None => "Only headings and paragraphs with text can be anchored".to_string(),
+    };
+}
+
+/// Lists the headings, captioned figures and tables, and anchored paragraphs in the heading
+/// picker; the one picked gets a cross-reference at the cursor.
+fn open_cross_reference_picker(state: &mut WindowState, display: CrossReferenceDisplay) {
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    let mut entries = state
+        .sidebar
+        .outline_items
+        .iter()
+        .map(|item| SymbolEntry {
+            block_id: item.block_id,
+            title: item.title.clone(),
+            level: item.level,
+        })
+        .collect::<Vec<_>>();
+    for number in number_captions(&tab.document.content) {
+        entries.push(SymbolEntry {
+            block_id: number.block_id,
+            title: caption_text(&tab.document.content, number.block_id)
+                .unwrap_or_else(|| number.label()),
+            level: 1,
+        });
+    }
+    for anchor in &tab.document.anchors {
+        if !entries
+            .iter()
+            .any(|entry| entry.block_id == anchor.block_id)
+        {
+            entries.push(SymbolEntry {
+                block_id: anchor.block_id,
+                title: format!("#{}", anchor.name),
+                level: 1,
+            });
+        }
+    }
+    if entries.is_empty() {
+        state.app_state.status_text = "No headings, captions or anchors to refer to".to_string();
+        return;
+    }
+    state.goto_symbol.open_entries(entries);
+    state.cross_reference_pick = Some(display);
+    state.app_state.status_text = "Pick what the cross-reference points at".to_string();
+}
+
+/// Inserts a reference to `target` at the cursor, anchoring `target` first if it has no
+/// anchor yet.
+fn insert_cross_reference(
+    state: &mut WindowState,
+    target: BlockId,
+    display: CrossReferenceDisplay,
+) {
+    if edit_blocked_by_read_only(state) {
+        return;
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return;
+    };
+    let Some(anchor) = ensure_anchor(&mut tab.document, target) else {
+        state.app_state.status_text = "That block has no text to refer to".to_string();
+        return;
+    };
+    let cursor = tab.cursor.primary;
+    let command = insert_cross_reference_command(
+        &tab.document,
+        cursor.block_id,
+        cursor.offset,
+        &anchor,
+        display,
+    );
+    let Some(command) = command else {
+        state.app_state.status_text = "Cross-references go into a paragraph".to_string();
+        return;
+    };
+    if apply_undoable_edit(state, command) {
+        state.app_state.status_text = format!("Cross-reference to \"{anchor}\" inserted");
+    }
+}
+
+fn open_document_properties(state: &mut WindowState) {
+    let Some(tab) = state.tabs.active_tab() else {
+        state.app_state.status_text = "No document to edit properties for".to_string();
+        return;
+    };
+    state.document_properties.open(&tab.document.metadata);
+}
+
+/// Writes the properties the dialog confirmed into the active document.
+fn apply_document_properties(state: &mut WindowState) {
+    let Some(values) = state.document_properties.pending_apply.take() else {
+        return;
+    };
+    if edit_blocked_by_read_only(state) {
+        return;
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return;
+    };
+    if apply_properties(&mut tab.document.metadata, values) {
+        tab.document.mark_edited();
+        tab.dirty = true;
+        state.app_state.status_text = "Document properties updated".to_string();
+    }
+}
+
+fn open_zoom_popup(state: &mut WindowState) {
+    let zoom = state
+        .tabs
+        .active_tab()
+        .map_or(1.0, |tab| tab.canvas.zoom_target);
+    state.zoom_popup.open(state.statusbar.zoom_rect(), zoom);
+}
+
+/// Opens the statistics popup over the word count, with selection figures when the
+/// active document has a selection.
+fn open_stats_popup(state: &mut WindowState) -> String {
+    let Some(tab) = state
+        .tabs
+        .active_tab()
+        .filter(|tab| tab.kind == TabKind::Document)
+    else {
+        return "No document to count".to_string();
+    };
+    let document = collect_document_stats(&tab.document.content);
+    let selection = tab
+        .cursor
+        .selection
+        .map(|selection| collect_selection_stats(&tab.document.content, selection));
+    state
+        .stats_popup
+        .open(state.statusbar.stats_rect(), document, selection);
+    "Document statistics".to_string()
+}
+
+/// Applies the zoom the popup asked for to the active tab's canvas.
+fn apply_zoom_popup_action(state: &mut WindowState) {
+    let Some(action) = state.zoom_popup.pending_action.take() else {
+        return;
+    };
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return;
+    };
+    match action {
+        ZoomPopupAction::SetZoom(zoom) => tab.canvas.set_zoom(zoom, None),
+        ZoomPopupAction::Preset(preset) => tab.canvas.fit_document(preset, &tab.document),
+    }
+    tab.canvas.clamp_scroll(&tab.document);
+    state.zoom_popup.set_zoom(tab.canvas.zoom_target);
+    state.app_state.status_text = format!("Zoom: {}%", (tab.canvas.zoom_target * 100.0).round() as u16);
+}
+
+fn parse_table_picker_custom(value: &str, fallback: usize) -> usize {
+    value
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .unwrap_or(fallback)
+        .clamp(1, 64)
+}
+
+fn table_insert_index_for_cursor(tab: &crate::ui::tabs::TabState) -> usize {
+    tab.document
+        .content
+        .iter()
+        .position(|block| match block {
+            Block::Paragraph(p) => p.id == tab.cursor.primary.block_id,
+            Block::Heading(h) => h.id == tab.cursor.primary.block_id,
+            Block::CodeBlock(c) => c.id == tab.cursor.primary.block_id,
+            Block::Image(i) => i.id == tab.cursor.primary.block_id,
+            Block::Table(t) => t.id == tab.cursor.primary.block_id,
+            Block::BlockQuote(q) => q.id == tab.cursor.primary.block_id,
+            _ => false,
+        })
+        .map(|idx| idx + 1)
+        .unwrap_or(tab.document.content.len())
+}
+
+fn insert_table_at_cursor(state: &mut WindowState, rows: usize, cols: usize) -> Option<BlockId> {
+    if edit_blocked_by_read_only(state) {
+        return None;
+    }
+    let inserted = {
+        let tab = state.tabs.active_tab_mut()?;
+        let insert_idx = table_insert_index_for_cursor(tab);
+        let id = insert_table(&mut tab.document, insert_idx, rows, cols);
+        tab.cursor.primary.block_id = id;
+        tab.cursor.primary.offset = 0;
+        tab.document.mark_edited();
+        tab.dirty = true;
+        id
+    };
+
+    state.selected_table = Some(inserted);
+    state.table_selection_mode = Some(TableSelectionMode::Cell(CellPos { row: 0, col: 0 }));
+    state.table_selection_range = Some(TableSelection {
+        start: CellPos { row: 0, col: 0 },
+        end: CellPos { row: 0, col: 0 },
+    });
+    sync_sidebar_with_active_tab(state);
+    Some(inserted)
+}
+
+fn insert_table_from_picker(state: &mut WindowState) -> Option<BlockId> {
+    let rows = parse_table_picker_custom(state.table_picker_custom_rows.as_str(), state.table_picker_rows);
+    let cols = parse_table_picker_custom(state.table_picker_custom_cols.as_str(), state.table_picker_cols);
+    state.table_picker_rows = rows.clamp(1, 10);
+    state.table_picker_cols = cols.clamp(1, 10);
+    state.table_picker_visible = false;
+    insert_table_at_cursor(state, rows, cols)
+}
+
+fn table_picker_layout(state: &WindowState) -> TablePickerLayout {
+    let origin = canvas_origin(state);
+    let panel = UiRect {
+        x: origin.x + 10.0,
+        y: origin.y + 10.0,
+        width: 292.0,
+        height: 236.0,
     };
     let grid = UiRect {
         x: panel.x + 12.0,
@@ -1094,21 +3249,43 @@ fn move_table_selection(state: &mut WindowState, row_delta: isize, col_delta: is
     let current = selected_table_cell(state).unwrap_or(CellPos { row: 0, col: 0 });
     let row = (current.row as isize + row_delta).clamp(0, rows.saturating_sub(1) as isize) as usize;
     let col = (current.col as isize + col_delta).clamp(0, cols.saturating_sub(1) as isize) as usize;
-    let end = CellPos { row, col };
+    select_table_cell(state, current, CellPos { row, col }, expand);
+    true
+}
+
+/// Extends the table selection from its moving end to the edge of the filled cells in the
+/// direction pressed, as Shift+Ctrl+Arrow does in a spreadsheet.
+fn extend_table_selection_to_edge(
+    state: &mut WindowState,
+    row_delta: isize,
+    col_delta: isize,
+) -> bool {
+    let Some(table_id) = state.selected_table else {
+        return false;
+    };
+    let current = selected_table_cell(state).unwrap_or(CellPos { row: 0, col: 0 });
+    let Some(table) = active_table_ref(state, table_id) else {
+        return false;
+    };
+    let end = jump_to_edge(table, current, row_delta, col_delta);
+    select_table_cell(state, current, end, true);
+    true
+}
 
-    if expand {
-        let start = state
+/// Moves the selection's active cell to `end`; with `expand` the selection keeps its anchor,
+/// or starts from `current` when there is none.
+fn select_table_cell(state: &mut WindowState, current: CellPos, end: CellPos, expand: bool) {
+    let start = if expand {
+        state
             .table_selection_range
             .as_ref()
             .map(|selection| selection.start)
-            .unwrap_or(current);
-        state.table_selection_mode = Some(TableSelectionMode::Cell(end));
-        state.table_selection_range = Some(TableSelection { start, end });
+            .unwrap_or(current)
     } else {
-        state.table_selection_mode = Some(TableSelectionMode::Cell(end));
-        state.table_selection_range = Some(TableSelection { start: end, end });
-    }
-    true
+        end
+    };
+    state.table_selection_mode = Some(TableSelectionMode::Cell(end));
+    state.table_selection_range = Some(TableSelection { start, end });
 }
 
 fn table_selected_row_col(state: &WindowState) -> Option<(usize, usize)> {
@@ -1134,7 +3311,26 @@ fn apply_table_shortcut(state: &mut WindowState, vk: u32, ctrl_down: bool, shift
         }
     }
 
-    if !ctrl_down && (vk == 0x25 || vk == 0x26 || vk == 0x27 || vk == 0x28) {
+    if ctrl_down && !shift_down && vk == 0x41 {
+        // A second Ctrl+A goes on to select the whole document.
+        if state.table_selection_mode == Some(TableSelectionMode::Table) {
+            return false;
+        }
+        let Some(table) = active_table_ref(state, table_id) else {
+            return false;
+        };
+        let whole = TableSelection::whole(table);
+        state.app_state.status_text = format!(
+            "Selected table ({}x{})",
+            whole.end.row + 1,
+            whole.end.col + 1
+        );
+        state.table_selection_mode = Some(TableSelectionMode::Table);
+        state.table_selection_range = Some(whole);
+        return true;
+    }
+
+    if (!ctrl_down || shift_down) && (vk == 0x25 || vk == 0x26 || vk == 0x27 || vk == 0x28) {
         let (dr, dc) = match vk {
             0x25 => (0, -1),
             0x26 => (-1, 0),
@@ -1142,7 +3338,12 @@ fn apply_table_shortcut(state: &mut WindowState, vk: u32, ctrl_down: bool, shift
             0x28 => (1, 0),
             _ => (0, 0),
         };
-        if move_table_selection(state, dr, dc, shift_down) {
+        let moved = if ctrl_down {
+            extend_table_selection_to_edge(state, dr, dc)
+        } else {
+            move_table_selection(state, dr, dc, shift_down)
+        };
+        if moved {
             if let Some(sel) = &state.table_selection_range {
                 state.app_state.status_text = format!(
                     "Table selection {}:{}, {}:{}",
@@ -1157,40 +3358,25 @@ fn apply_table_shortcut(state: &mut WindowState, vk: u32, ctrl_down: bool, shift
     }
 
     if vk == VK_DELETE.0 as u32 {
-        let mut changed = false;
-        if let Some(tab) = state.tabs.active_tab_mut() {
-            if let Some(table) = find_table_mut(&mut tab.document, table_id) {
-                match state.table_selection_mode {
-                    Some(TableSelectionMode::Row(row)) => {
-                        if table.rows.len() > 1 {
-                            changed = delete_table_row(table, row.min(table.rows.len() - 1));
-                        }
-                    }
-                    Some(TableSelectionMode::Column(col)) => {
-                        if table.column_widths.len() > 1 {
-                            changed =
-                                delete_table_column(table, col.min(table.column_widths.len() - 1));
-                        }
-                    }
-                    Some(TableSelectionMode::Table) => {
-                        if let Some(idx) = tab.document.content.iter().position(|block| {
-                            matches!(block, Block::Table(t) if t.id == table_id)
-                        }) {
-                            tab.document.content.remove(idx);
-                            changed = true;
-                            state.selected_table = None;
-                            state.table_selection_mode = None;
-                            state.table_selection_range = None;
-                        }
-                    }
-                    _ => {}
-                }
-                if changed {
-                    tab.document.dirty = true;
-                    tab.dirty = true;
-                }
+        let changed = match state.table_selection_mode {
+            Some(TableSelectionMode::Row(row)) => edit_table_copy(state, table_id, |table| {
+                table.rows.len() > 1 && delete_table_row(table, row.min(table.rows.len() - 1))
+            }),
+            Some(TableSelectionMode::Column(col)) => edit_table_copy(state, table_id, |table| {
+                table.column_widths.len() > 1
+                    && delete_table_column(table, col.min(table.column_widths.len() - 1))
+            }),
+            Some(TableSelectionMode::Table) => {
+                let deleted = delete_table_with_undo(state, table_id);
+                if deleted {
+                    state.selected_table = None;
+                    state.table_selection_mode = None;
+                    state.table_selection_range = None;
+                }
+                deleted
             }
-        }
+            _ => false,
+        };
         if changed {
             state.app_state.status_text = "Table structure updated".to_string();
             return true;
@@ -1204,100 +3390,116 @@ fn apply_table_shortcut(state: &mut WindowState, vk: u32, ctrl_down: bool, shift
     let row_col = table_selected_row_col(state);
     let selected_cell = selected_table_cell(state);
     let selection_range = state.table_selection_range.clone();
-    let mut changed = false;
+    // Measured up front: the edit below holds the window state.
+    let natural_widths = match active_table_ref(state, table_id) {
+        Some(table) if vk == 0x39 => {
+            let measure = |text: &str| {
+                state
+                    .renderer
+                    .as_ref()
+                    .and_then(|renderer| renderer.measure_text_width(text))
+                    .unwrap_or_else(|| estimate_text_width(text))
+            };
+            natural_column_widths(table, measure, 360.0)
+        }
+        _ => Vec::new(),
+    };
     let mut message = None::<String>;
-    if let Some(tab) = state.tabs.active_tab_mut() {
-        if let Some(table) = find_table_mut(&mut tab.document, table_id) {
-            match vk {
-                0x55 => {
-                    if let Some((row, _)) = row_col {
-                        insert_row_above(table, row);
-                        changed = true;
-                        message = Some("Inserted row above".to_string());
-                    }
-                }
-                0x4A => {
-                    if let Some((row, _)) = row_col {
-                        insert_row_below(table, row);
-                        changed = true;
-                        message = Some("Inserted row below".to_string());
-                    }
-                }
-                0x48 => {
-                    if let Some((_, col)) = row_col {
-                        insert_column_left(table, col);
-                        changed = true;
-                        message = Some("Inserted column left".to_string());
-                    }
-                }
-                0x4B => {
-                    if let Some((_, col)) = row_col {
-                        insert_column_right(table, col);
-                        changed = true;
-                        message = Some("Inserted column right".to_string());
-                    }
-                }
-                0x4D => {
-                    if let Some(selection) = selection_range.clone() {
-                        changed = merge_table_cells(table, selection);
-                        if changed {
-                            message = Some("Merged selected cells".to_string());
-                        }
-                    }
-                }
-                0x59 => {
-                    if let Some(cell) = selected_cell {
-                        changed = split_table_cell(table, cell);
-                        if changed {
-                            message = Some("Split selected cell".to_string());
-                        }
-                    }
-                }
-                0x30 => {
-                    let total = table.column_widths.iter().sum::<f32>().max(300.0);
-                    distribute_columns_evenly(table, total);
-                    changed = true;
-                    message = Some("Distributed columns evenly".to_string());
+    let changed = edit_table_copy(state, table_id, |table| {
+        match vk {
+            0x55 => {
+                if let Some((row, _)) = row_col {
+                    insert_row_above(table, row);
+                    message = Some("Inserted row above".to_string());
                 }
-                0x39 => {
-                    let total = table.column_widths.iter().sum::<f32>().max(420.0);
-                    fit_columns_to_content(table, total);
-                    changed = true;
-                    message = Some("Auto-fit columns to content".to_string());
+            }
+            0x4A => {
+                if let Some((row, _)) = row_col {
+                    insert_row_below(table, row);
+                    message = Some("Inserted row below".to_string());
                 }
-                0x31 => {
-                    apply_table_style(table, TableStylePreset::Plain);
-                    changed = true;
-                    message = Some("Applied table style: Plain".to_string());
+            }
+            0x48 => {
+                if let Some((_, col)) = row_col {
+                    insert_column_left(table, col);
+                    message = Some("Inserted column left".to_string());
                 }
-                0x32 => {
-                    apply_table_style(table, TableStylePreset::Grid);
-                    changed = true;
-                    message = Some("Applied table style: Grid".to_string());
+            }
+            0x4B => {
+                if let Some((_, col)) = row_col {
+                    insert_column_right(table, col);
+                    message = Some("Inserted column right".to_string());
                 }
-                0x33 => {
-                    apply_table_style(table, TableStylePreset::HeaderAccent);
-                    changed = true;
-                    message = Some("Applied table style: Header row".to_string());
+            }
+            0x4D => {
+                if let Some(selection) = selection_range
+                    && merge_table_cells(table, selection)
+                {
+                    message = Some("Merged selected cells".to_string());
                 }
-                0x34 => {
-                    apply_table_style(table, TableStylePreset::AlternatingRows);
-                    changed = true;
-                    message = Some("Applied table style: Alternating rows".to_string());
+            }
+            0x59 => {
+                if let Some(cell) = selected_cell
+                    && split_table_cell(table, cell)
+                {
+                    message = Some("Split selected cell".to_string());
                 }
-                0x35 => {
-                    apply_table_style(table, TableStylePreset::Professional);
-                    changed = true;
-                    message = Some("Applied table style: Professional".to_string());
+            }
+            0x30 => {
+                let total = table.column_widths.iter().sum::<f32>().max(300.0);
+                distribute_columns_evenly(table, total);
+                message = Some("Distributed columns evenly".to_string());
+            }
+            0x38 => {
+                let total = table.row_heights.iter().sum::<f32>();
+                let total = total.max(28.0 * table.rows.len() as f32);
+                distribute_rows_evenly(table, total);
+                message = Some("Distributed rows evenly".to_string());
+            }
+            0x37 => {
+                if let Some((row, _)) = row_col {
+                    let height = table.row_heights.get(row).copied().unwrap_or(28.0);
+                    set_uniform_row_height(table, height);
+                    message = Some(format!("Set every row to {height:.0}pt"));
                 }
-                _ => {}
             }
-            if changed {
-                tab.document.dirty = true;
-                tab.dirty = true;
+            0x39 => {
+                let total = table.column_widths.iter().sum::<f32>().max(420.0);
+                fit_columns_to_content(table, &natural_widths, total);
+                message = Some("Auto-fit columns to content".to_string());
+            }
+            0x52 => {
+                table.header_repeat = !table.header_repeat;
+                message = Some(if table.header_repeat {
+                    "Header row repeats on each page".to_string()
+                } else {
+                    "Header row no longer repeats".to_string()
+                });
+            }
+            0x31 => {
+                apply_table_style(table, TableStylePreset::Plain);
+                message = Some("Applied table style: Plain".to_string());
+            }
+            0x32 => {
+                apply_table_style(table, TableStylePreset::Grid);
+                message = Some("Applied table style: Grid".to_string());
+            }
+            0x33 => {
+                apply_table_style(table, TableStylePreset::HeaderAccent);
+                message = Some("Applied table style: Header row".to_string());
+            }
+            0x34 => {
+                apply_table_style(table, TableStylePreset::AlternatingRows);
+                message = Some("Applied table style: Alternating rows".to_string());
+            }
+            0x35 => {
+                apply_table_style(table, TableStylePreset::Professional);
+                message = Some("Applied table style: Professional".to_string());
             }
+            _ => {}
         }
-    }
+        message.is_some()
+    });
 
     if changed {
         state.app_state.status_text = message.unwrap_or_else(|| "Table updated".to_string());
@@ -1305,6 +3507,113 @@ fn apply_table_shortcut(state: &mut WindowState, vk: u32, ctrl_down: bool, shift
     changed
 }
 
+/// Applies `command` to the active document through the tab's edit engine so that Ctrl+Z
+/// can revert it.
+fn apply_undoable_edit(state: &mut WindowState, command: EditCommand) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let engine = state.edit_engines.entry(tab.id).or_default();
+    if !engine.apply_command(&mut tab.document, command) {
+        return false;
+    }
+    tab.dirty = true;
+    if let Some(index) = state.search_indexes.get_mut(&tab.id) {
+        index.invalidate();
+    }
+    state.find_replace.document_edited();
+    true
+}
+
+fn apply_table_edit(state: &mut WindowState, table_id: BlockId, op: TableOp) -> bool {
+    apply_undoable_edit(state, EditCommand::Table { table_id, op })
+}
+
+/// Runs `edit` on a copy of the table and, when it reports a change, swaps the copy in as
+/// one undo step. Suits edits like merges and styles whose inverse is the whole table.
+fn edit_table_copy(
+    state: &mut WindowState,
+    table_id: BlockId,
+    edit: impl FnOnce(&mut crate::document::model::Table) -> bool,
+) -> bool {
+    let Some(mut table) = active_table_ref(state, table_id).cloned() else {
+        return false;
+    };
+    if !edit(&mut table) {
+        return false;
+    }
+    apply_table_edit(state, table_id, TableOp::Replace(Box::new(table)))
+}
+
+/// Deletes a whole table as one undo step: a top-level table as a block, a nested one as an
+/// edit of the table that contains it.
+fn delete_table_with_undo(state: &mut WindowState, table_id: BlockId) -> bool {
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let command = match table_parent(&tab.document, table_id) {
+        Some((parent_id, cell)) => {
+            let Some(mut parent) = find_table(&tab.document, parent_id).cloned() else {
+                return false;
+            };
+            let Some(cell) = parent
+                .rows
+                .get_mut(cell.row)
+                .and_then(|row| row.cells.get_mut(cell.col))
+            else {
+                return false;
+            };
+            if !delete_table(&mut cell.blocks, table_id) {
+                return false;
+            }
+            EditCommand::Table {
+                table_id: parent_id,
+                op: TableOp::Replace(Box::new(parent)),
+            }
+        }
+        None => EditCommand::DeleteBlock { block_id: table_id },
+    };
+    apply_undoable_edit(state, command)
+}
+
+/// Undoes (or redoes) the last recorded edit in the active tab.
+fn undo_active_tab(state: &mut WindowState, redo: bool) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let Some(engine) = state.edit_engines.get_mut(&tab.id) else {
+        return false;
+    };
+    let applied = if redo {
+        engine.redo(&mut tab.document)
+    } else {
+        engine.undo(&mut tab.document)
+    };
+    if !applied {
+        return false;
+    }
+    tab.dirty = true;
+    tab.canvas.mark_dirty_full();
+    if let Some(index) = state.search_indexes.get_mut(&tab.id) {
+        index.invalidate();
+    }
+    state.find_replace.document_edited();
+    if let Some(table_id) = state.selected_table
+        && find_table(&tab.document, table_id).is_none()
+    {
+        state.selected_table = None;
+        state.table_selection_mode = None;
+        state.table_selection_range = None;
+    }
+    true
+}
+
 fn insert_image_from_path(
     state: &mut WindowState,
     path: &Path,
@@ -1325,6 +3634,9 @@ fn insert_loaded_image(
     source_path: Option<PathBuf>,
     alt_text: String,
 ) -> std::result::Result<BlockId, String> {
+    if let Some(Err(message)) = state.tabs.active_tab().map(|tab| tab.edit_guard()) {
+        return Err(message.to_string());
+    }
     let inserted = {
         let Some(tab) = state.tabs.active_tab_mut() else {
             return Err("no active tab".to_string());
@@ -1341,6 +3653,7 @@ fn insert_loaded_image(
         );
         tab.cursor.primary.block_id = block_id;
         tab.cursor.primary.offset = 0;
+        tab.document.mark_edited();
         tab.dirty = true;
         block_id
     };
@@ -1386,54 +3699,37 @@ fn collect_canvas_image_overlays(
     _selected_image: Option<BlockId>,
     image_cache: &mut ImageDecodeCache,
 ) -> Vec<CanvasImageOverlay> {
-    let page_rect = tab
-        .canvas
-        .page_rects(&tab.document)
-        .first()
-        .copied()
-        .unwrap_or(UiRect {
-            x: 0.0,
-            y: 0.0,
-            width: tab.canvas.viewport.width.max(1.0),
-            height: tab.canvas.viewport.height.max(1.0),
-        });
-
-    let content_left = page_rect.x + 46.0;
-    let content_right = page_rect.x + page_rect.width - 46.0;
-    let max_width = (content_right - content_left).max(72.0);
-    let mut cursor_y = page_rect.y + 86.0;
-    let bottom_limit = page_rect.y + page_rect.height - 50.0;
-
+    let viewport = tab.canvas.viewport;
     let mut overlays = Vec::new();
     let mut visible_hashes = Vec::new();
 
-    for block in &tab.document.content {
+    for (index, block) in tab.document.content.iter().enumerate() {
         let Block::Image(image) = block else {
             continue;
         };
-        let zoom = tab.canvas.zoom.max(0.25);
-        let width = (image.width * zoom * 0.72).clamp(56.0, max_width);
-        let mut height = (image.height * zoom * 0.72).clamp(42.0, page_rect.height * 0.5);
-
-        if image.width > 0.0 && image.height > 0.0 {
-            let ratio = (image.height / image.width).max(0.08);
-            height = (width * ratio).clamp(42.0, page_rect.height * 0.5);
-        }
-
-        if cursor_y + height > bottom_limit {
-            break;
+        // The image sits where pagination flowed it, scaled down to fit the text column.
+        let Some(column) = tab.canvas.block_rect(&tab.document, index) else {
+            continue;
+        };
+        if column.y > viewport.height || column.y + column.height < 0.0 {
+            continue;
         }
-
+        let natural_width = if image.width > 0.0 {
+            image.width
+        } else {
+            image.original_width as f32
+        };
+        let width = (natural_width * tab.canvas.zoom).min(column.width).max(1.0);
         let x = match image.alignment {
-            ImageAlignment::Left | ImageAlignment::Inline | ImageAlignment::Float => content_left,
-            ImageAlignment::Center => content_left + (max_width - width) * 0.5,
-            ImageAlignment::Right => content_right - width,
+            ImageAlignment::Left | ImageAlignment::Inline | ImageAlignment::Float => column.x,
+            ImageAlignment::Center => column.x + (column.width - width) * 0.5,
+            ImageAlignment::Right => column.x + column.width - width,
         };
         let rect = UiRect {
             x,
-            y: cursor_y,
+            y: column.y,
             width,
-            height,
+            height: column.height,
         };
 
         let scale = if image.original_width > 0 {
@@ -1456,76 +3752,471 @@ fn collect_canvas_image_overlays(
             interpolation,
             alt_text: image.alt_text.clone(),
         });
-        cursor_y += height + 16.0;
 
         if overlays.len() >= 12 {
             break;
         }
     }
-
-    image_cache.mark_visible_hashes(visible_hashes.as_slice());
-    overlays
+
+    image_cache.mark_visible_hashes(visible_hashes.as_slice());
+    overlays
+}
+
+fn collect_canvas_table_overlays(tab: &crate::ui::tabs::TabState) -> Vec<CanvasTableOverlay> {
+    let viewport = tab.canvas.viewport;
+    let mut overlays = Vec::new();
+
+    for (index, block) in tab.document.content.iter().enumerate() {
+        let Block::Table(table) = block else {
+            continue;
+        };
+        let Some(column) = tab.canvas.block_rect(&tab.document, index) else {
+            continue;
+        };
+        if column.y > viewport.height || column.y + column.height < 0.0 {
+            continue;
+        }
+        let (left, top) = (column.x, column.y);
+        let max_width = column.width.max(140.0);
+        let rows = table.rows.len().max(1);
+        let cols = table.column_widths.len().max(1);
+        let gutter_w = 18.0;
+        let header_h = 18.0;
+        let cell_h = 24.0;
+        let cell_w = ((max_width - gutter_w) / cols as f32).max(28.0);
+        let visible = visible_row_range(table, tab.canvas.scroll.y.max(0.0), tab.canvas.viewport.height, cell_h);
+        let visible_rows = (visible.1.saturating_sub(visible.0)).max(1);
+        let total_h = header_h + visible_rows as f32 * cell_h;
+        // Wide tables keep the page width and scroll horizontally inside it.
+        let total_w = (gutter_w + cell_w * cols as f32).min(max_width);
+        let view = tab
+            .canvas
+            .table_views
+            .get(&table.id)
+            .copied()
+            .unwrap_or_default();
+
+        overlays.push(CanvasTableOverlay {
+            table_id: table.id,
+            rect: UiRect {
+                x: left,
+                y: top,
+                width: total_w,
+                height: total_h,
+            },
+            rows,
+            cols,
+            cell_w,
+            cell_h,
+            header_h,
+            gutter_w,
+            scroll_x: view
+                .scroll_x
+                .clamp(0.0, (gutter_w + cell_w * cols as f32 - total_w).max(0.0)),
+            // The overlay rect already moves with the page; rows are measured from its top.
+            scroll_y: 0.0,
+            freeze_first_column: view.freeze_first_column,
+            freeze_header_row: view.freeze_header_row,
+            repeated_header_rows: tab
+                .canvas
+                .block_layout
+                .table_continuations()
+                .iter()
+                .filter(|c| c.table_id == table.id && c.repeated_header_row.is_some())
+                .map(|c| c.first_row)
+                .collect(),
+            row_fills: row_fills(table),
+        });
+        push_nested_table_overlays(&mut overlays, table, 1);
+
+        if overlays.len() >= 8 {
+            break;
+        }
+    }
+
+    overlays
+}
+
+fn row_fills(table: &crate::document::model::Table) -> Vec<Option<crate::ui::Color>> {
+    (0..table.rows.len())
+        .map(|row| table.row_fill(row))
+        .collect()
+}
+
+/// Adds overlays for tables nested in the cells of `table`, each drawn inside its cell and
+/// pushed after the parent so clicks land on the innermost table.
+fn push_nested_table_overlays(
+    overlays: &mut Vec<CanvasTableOverlay>,
+    table: &crate::document::model::Table,
+    depth: usize,
+) {
+    if depth > MAX_TABLE_NESTING {
+        return;
+    }
+    let Some(parent) = overlays.last().cloned() else {
+        return;
+    };
+    let viewport = parent.viewport();
+    for (row, table_row) in table.rows.iter().enumerate().take(parent.rows) {
+        for (col, cell) in table_row.cells.iter().enumerate().take(parent.cols) {
+            let Some(inner) = nested_table(cell) else {
+                continue;
+            };
+            let x = viewport.column_x(col) + 2.0;
+            let y = viewport.row_y(row) + 2.0;
+            let width = parent.cell_w - 4.0;
+            let height = parent.cell_h - 4.0;
+            // Skip cells scrolled out of the parent or under its frozen panes.
+            let min_x = if col == 0 {
+                viewport.gutter_w
+            } else {
+                viewport.gutter_w + viewport.frozen_width()
+            };
+            let min_y = if row == 0 {
+                viewport.header_h
+            } else {
+                viewport.header_h + viewport.frozen_height()
+            };
+            if width < 4.0
+                || height < 4.0
+                || x < min_x
+                || y < min_y
+                || x + width > parent.rect.width
+                || y + height > parent.rect.height
+            {
+                continue;
+            }
+            let rows = inner.rows.len().max(1);
+            let cols = inner.column_widths.len().max(1);
+            overlays.push(CanvasTableOverlay {
+                table_id: inner.id,
+                rect: UiRect {
+                    x: parent.rect.x + x,
+                    y: parent.rect.y + y,
+                    width,
+                    height,
+                },
+                rows,
+                cols,
+                cell_w: width / cols as f32,
+                cell_h: height / rows as f32,
+                header_h: 0.0,
+                gutter_w: 0.0,
+                repeated_header_rows: Vec::new(),
+                row_fills: row_fills(inner),
+                scroll_x: 0.0,
+                scroll_y: 0.0,
+                freeze_first_column: false,
+                freeze_header_row: false,
+            });
+            push_nested_table_overlays(overlays, inner, depth + 1);
+        }
+    }
+}
+
+fn canvas_contains(state: &WindowState, point: UiPoint) -> bool {
+    let origin = canvas_origin(state);
+    state.tabs.active_tab().is_some_and(|tab| {
+        contains_rect(
+            UiRect {
+                x: origin.x,
+                y: origin.y,
+                width: tab.canvas.viewport.width,
+                height: tab.canvas.viewport.height,
+            },
+            point,
+        )
+    })
+}
+
+/// Starts dragging the horizontal scrollbar when `point` is on it; a click on the
+/// track away from the thumb jumps the thumb there first.
+fn begin_horizontal_scrollbar_drag(state: &mut WindowState, point: UiPoint) -> bool {
+    if !canvas_contains(state, point) {
+        return false;
+    }
+    let origin = canvas_origin(state);
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let local_x = point.x - origin.x;
+    if point.y - origin.y < tab.canvas.viewport.height - HSCROLL_HIT_HEIGHT {
+        return false;
+    }
+    let content_width = tab.canvas.content_size(&tab.document).width;
+    let Some(thumb) = tab.canvas.horizontal_thumb_rect(content_width) else {
+        return false;
+    };
+    let grab = if local_x >= thumb.x && local_x <= thumb.x + thumb.width {
+        local_x - thumb.x
+    } else {
+        thumb.width / 2.0
+    };
+    state.hscroll_drag = Some(grab);
+    update_horizontal_scrollbar_drag(state, point);
+    true
+}
+
+fn update_horizontal_scrollbar_drag(state: &mut WindowState, point: UiPoint) {
+    let Some(grab) = state.hscroll_drag else {
+        return;
+    };
+    let origin = canvas_origin(state);
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        let content_width = tab.canvas.content_size(&tab.document).width;
+        // The thumb is drawn 2px in from its track offset.
+        tab.canvas
+            .drag_horizontal_thumb(content_width, point.x - origin.x - grab - 2.0);
+        tab.canvas.clamp_scroll(&tab.document);
+    }
+}
+
+fn update_pan(state: &mut WindowState, point: UiPoint) {
+    let Some(anchor) = state.pan_anchor.replace(point) else {
+        return;
+    };
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.canvas.pan_by(point.x - anchor.x, point.y - anchor.y);
+        tab.canvas.clamp_scroll(&tab.document);
+    }
+}
+
+fn canvas_text_hit(state: &WindowState, point: UiPoint) -> Option<CanvasTextHit> {
+    let (line, utf16) = state
+        .renderer
+        .as_ref()?
+        .preview_position_at(point.x, point.y)?;
+    let tab = state.tabs.active_tab()?;
+    let preview = collect_preview_lines(&tab.document, &tab.folded, 40);
+    let preview_line = preview.get(line)?;
+    let mut units = 0u32;
+    let offset = preview_line
+        .text
+        .chars()
+        .take_while(|ch| {
+            units += ch.len_utf16() as u32;
+            units <= utf16
+        })
+        .count();
+    Some(CanvasTextHit {
+        block_id: preview_line.block_id?,
+        offset,
+        line,
+        utf16,
+    })
+}
+
+/// Text of a top-level paragraph with a single run, the only blocks text edits address by offset.
+fn plain_paragraph_text(document: &DocumentModel, block_id: BlockId) -> Option<&str> {
+    document.content.iter().find_map(|block| match block {
+        Block::Paragraph(p) if p.id == block_id && p.runs.len() <= 1 => {
+            Some(p.runs.first().map_or("", |run| run.text.as_str()))
+        }
+        _ => None,
+    })
+}
+
+/// Selects the word under a double-click on the canvas.
+fn select_word_at(state: &mut WindowState, point: UiPoint) -> bool {
+    let Some(hit) = canvas_text_hit(state, point).filter(|_| canvas_contains(state, point)) else {
+        return false;
+    };
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let Some(text) = block_plain_text(&tab.document, hit.block_id) else {
+        return false;
+    };
+    let language = document_language(&tab.document);
+    tab.cursor
+        .select_word(hit.block_id, &text, hit.offset, language);
+    tab.cursor.selection.is_some()
+}
+
+/// Picks up the selection when the press lands inside it.
+fn begin_text_drag(state: &mut WindowState, point: UiPoint) -> bool {
+    let Some(hit) = canvas_text_hit(state, point) else {
+        return false;
+    };
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let Some(selection) = tab.cursor.selection.map(|selection| selection.normalized()) else {
+        return false;
+    };
+    if selection.start.block_id != hit.block_id
+        || selection.end.block_id != hit.block_id
+        || !(selection.start.offset..selection.end.offset).contains(&hit.offset)
+    {
+        return false;
+    }
+    let Some(text) = plain_paragraph_text(&tab.document, hit.block_id) else {
+        return false;
+    };
+    let start = byte_index_from_char_offset(text, selection.start.offset);
+    let end = byte_index_from_char_offset(text, selection.end.offset);
+    let at = |offset| CursorPosition {
+        block_id: hit.block_id,
+        offset,
+    };
+    let selection = CursorSelection {
+        start: at(start),
+        end: at(end),
+    };
+    state.text_drag = Some(TextDrag {
+        session: TextDragSession::new(selection, text[start..end].to_string(), false),
+        target: None,
+    });
+    true
+}
+
+fn update_text_drag(state: &mut WindowState, point: UiPoint) {
+    let hit = canvas_text_hit(state, point).filter(|hit| {
+        state
+            .tabs
+            .active_tab()
+            .is_some_and(|tab| plain_paragraph_text(&tab.document, hit.block_id).is_some())
+    });
+    if let Some(drag) = &mut state.text_drag {
+        drag.target = hit;
+    }
+}
+
+/// Drops dragged text where the pointer left it: moved, or copied with Ctrl held.
+fn finish_text_drag(state: &mut WindowState, mut drag: TextDrag, copy: bool) {
+    let Some(target) = drag.target else {
+        return;
+    };
+    let Some(offset) = state.tabs.active_tab().and_then(|tab| {
+        plain_paragraph_text(&tab.document, target.block_id)
+            .map(|text| byte_index_from_char_offset(text, target.offset))
+    }) else {
+        return;
+    };
+    let source = drag.session.selection;
+    if copy
+        && source.start.block_id == target.block_id
+        && (source.start.offset..=source.end.offset).contains(&offset)
+    {
+        return;
+    }
+    drag.session.copy_mode = copy;
+    drag.session.update_insertion_indicator(CursorPosition {
+        block_id: target.block_id,
+        offset,
+    });
+    let commands = drag.session.build_drop_commands();
+    if commands.is_empty() {
+        return;
+    }
+    // One undo step puts a moved selection back where it came from.
+    if !apply_undoable_edit(state, EditCommand::Batch(commands.clone())) {
+        return;
+    }
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.document.mark_edited();
+        let text = plain_paragraph_text(&tab.document, target.block_id).unwrap_or_default();
+        let Some(landed) = dropped_text_range(&commands, text, &drag.session.selected_text) else {
+            return;
+        };
+        let at = |offset| CursorPosition {
+            block_id: target.block_id,
+            offset,
+        };
+        tab.cursor.primary = at(landed.end);
+        tab.cursor.selection = Some(CursorSelection {
+            start: at(landed.start),
+            end: at(landed.end),
+        });
+    }
+    state.app_state.status_text = if copy { "Text copied" } else { "Text moved" }.to_string();
+}
+
+/// Opens dropped documents in tabs or inserts dropped images, whichever the files call for.
+fn open_dropped_files(state: &mut WindowState, payload: DropPayload) {
+    state.dropped_files = payload.files.clone();
+
+    state.app_state.status_text = match payload.action {
+        DropAction::OpenFilesInTabs => {
+            for path in &payload.files {
+                state.jump_list.add_recent_file(path.clone());
+                let _ = begin_background_load(state, path.clone(), true);
+            }
+            format!("Drop to open: {} file(s)", payload.files.len())
+        }
+        DropAction::InsertImage => {
+            let (inserted, failed) = insert_images_from_paths(state, &payload.files);
+            if inserted == 0 {
+                format!("Drop image insert failed ({failed} file(s))")
+            } else if failed == 0 {
+                format!("Inserted {} dropped image(s)", inserted)
+            } else {
+                format!("Inserted {} dropped image(s), {} failed", inserted, failed)
+            }
+        }
+        DropAction::Ignore => "Unsupported dropped content".to_string(),
+    };
+}
+
+fn handle_external_drop(state: &mut WindowState, dropped: ExternalDrop) {
+    let text = match dropped.content {
+        DropContent::Files(files) => {
+            let action = classify_drop(&files);
+            open_dropped_files(state, DropPayload { files, action });
+            return;
+        }
+        DropContent::Html(html) => html_to_plain_text(&html),
+        DropContent::Text(text) => text,
+    };
+    if text.is_empty() {
+        state.app_state.status_text = "Unsupported dropped content".to_string();
+        return;
+    }
+    if insert_dropped_text(state, dropped.point, &text) {
+        state.app_state.status_text = "Inserted dropped text".to_string();
+    }
 }
 
-fn collect_canvas_table_overlays(tab: &crate::ui::tabs::TabState) -> Vec<CanvasTableOverlay> {
-    let page_rect = tab
-        .canvas
-        .page_rects(&tab.document)
-        .first()
-        .copied()
-        .unwrap_or(UiRect {
-            x: 0.0,
-            y: 0.0,
-            width: tab.canvas.viewport.width.max(1.0),
-            height: tab.canvas.viewport.height.max(1.0),
+/// Inserts text dropped from another application where it landed and selects it. Drops
+/// outside plain paragraph text go to the cursor instead.
+fn insert_dropped_text(state: &mut WindowState, point: UiPoint, text: &str) -> bool {
+    let text_view = state
+        .tabs
+        .active_tab()
+        .is_some_and(|tab| tab.text_view.is_some());
+    let target = canvas_text_hit(state, point)
+        .filter(|_| !text_view && canvas_contains(state, point))
+        .and_then(|hit| {
+            let tab = state.tabs.active_tab()?;
+            let block_text = plain_paragraph_text(&tab.document, hit.block_id)?;
+            Some((hit, byte_index_from_char_offset(block_text, hit.offset)))
         });
+    let Some((hit, offset)) = target else {
+        return insert_text_at_cursor(state, text);
+    };
 
-    let left = page_rect.x + 46.0;
-    let mut top = page_rect.y + 430.0;
-    let max_width = (page_rect.width - 92.0).max(140.0);
-    let mut overlays = Vec::new();
-
-    for block in &tab.document.content {
-        let Block::Table(table) = block else {
-            continue;
+    let command = EditCommand::InsertText {
+        block_id: hit.block_id,
+        offset,
+        text: text.to_string(),
+    };
+    if !apply_undoable_edit(state, command) {
+        return false;
+    }
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.document.mark_edited();
+        let at = |offset| CursorPosition {
+            block_id: hit.block_id,
+            offset,
         };
-        let rows = table.rows.len().max(1);
-        let cols = table.column_widths.len().max(1);
-        let gutter_w = 18.0;
-        let header_h = 18.0;
-        let cell_h = 24.0;
-        let cell_w = ((max_width - gutter_w) / cols as f32).max(28.0);
-        let visible = visible_row_range(table, tab.canvas.scroll.y.max(0.0), tab.canvas.viewport.height, cell_h);
-        let visible_rows = (visible.1.saturating_sub(visible.0)).max(1);
-        let total_h = header_h + visible_rows as f32 * cell_h;
-        let total_w = gutter_w + cell_w * cols as f32;
-        if top + total_h > page_rect.y + page_rect.height - 24.0 {
-            break;
-        }
-
-        overlays.push(CanvasTableOverlay {
-            table_id: table.id,
-            rect: UiRect {
-                x: left,
-                y: top,
-                width: total_w,
-                height: total_h,
-            },
-            rows,
-            cols,
-            cell_w,
-            cell_h,
-            header_h,
-            gutter_w,
+        let end = hit.offset + text.chars().count();
+        tab.cursor.primary = at(end);
+        tab.cursor.selection = Some(CursorSelection {
+            start: at(hit.offset),
+            end: at(end),
         });
-        top += total_h + 18.0;
-
-        if overlays.len() >= 8 {
-            break;
-        }
     }
-
-    overlays
+    true
 }
 
 fn begin_table_interaction(state: &mut WindowState, point: UiPoint) -> bool {
@@ -1547,10 +4238,8 @@ fn begin_table_interaction(state: &mut WindowState, point: UiPoint) -> bool {
 
     let local_x = local.x - overlay.rect.x;
     let local_y = local.y - overlay.rect.y;
-    let rel_col = ((local_x - overlay.gutter_w) / overlay.cell_w).floor().max(0.0) as usize;
-    let rel_row = ((local_y - overlay.header_h) / overlay.cell_h).floor().max(0.0) as usize;
-    let col = rel_col.min(overlay.cols.saturating_sub(1));
-    let row = rel_row.min(overlay.rows.saturating_sub(1));
+    let viewport = overlay.viewport();
+    let CellPos { row, col } = viewport.cell_at(local_x, local_y);
 
     state.selected_table = Some(overlay.table_id);
     state.selected_image = None;
@@ -1593,7 +4282,7 @@ fn begin_table_interaction(state: &mut WindowState, point: UiPoint) -> bool {
 
     // Column/row border drag handles.
     let near_col_border = if local_x > overlay.gutter_w {
-        let x = local_x - overlay.gutter_w;
+        let x = viewport.content_x(local_x);
         let frac = (x / overlay.cell_w).fract();
         frac < 0.08 || frac > 0.92
     } else {
@@ -1601,7 +4290,7 @@ fn begin_table_interaction(state: &mut WindowState, point: UiPoint) -> bool {
     };
     if near_col_border {
         if let Some(table) = active_table_ref(state, overlay.table_id) {
-            let border_idx = ((local_x - overlay.gutter_w) / overlay.cell_w).round().max(0.0) as usize;
+            let border_idx = (viewport.content_x(local_x) / overlay.cell_w).round() as usize;
             let col_idx = border_idx.min(overlay.cols.saturating_sub(1));
             let start_value = table.column_widths.get(col_idx).copied().unwrap_or(120.0);
             state.table_resize = Some(TableResizeState {
@@ -1614,7 +4303,7 @@ fn begin_table_interaction(state: &mut WindowState, point: UiPoint) -> bool {
         }
     } else if local_x <= overlay.gutter_w && local_y > overlay.header_h {
         if let Some(table) = active_table_ref(state, overlay.table_id) {
-            let border_idx = ((local_y - overlay.header_h) / overlay.cell_h).round().max(0.0) as usize;
+            let border_idx = (viewport.content_y(local_y) / overlay.cell_h).round() as usize;
             let row_idx = border_idx.min(overlay.rows.saturating_sub(1));
             let start_value = table.row_heights.get(row_idx).copied().unwrap_or(28.0);
             state.table_resize = Some(TableResizeState {
@@ -1633,7 +4322,164 @@ fn begin_table_interaction(state: &mut WindowState, point: UiPoint) -> bool {
     true
 }
 
+/// Scrolls the wide table under `point` sideways; false when there is none to scroll.
+fn scroll_table_at(state: &mut WindowState, point: UiPoint, delta: f32) -> bool {
+    let origin = canvas_origin(state);
+    let local = UiPoint {
+        x: point.x - origin.x,
+        y: point.y - origin.y,
+    };
+    let Some(overlay) = state
+        .canvas_table_overlays
+        .iter()
+        .rev()
+        .find(|overlay| contains_rect(overlay.rect, local))
+        .cloned()
+    else {
+        return false;
+    };
+    let max_scroll = overlay.viewport().max_scroll_x(overlay.rect.width);
+    if max_scroll <= 0.0 {
+        return false;
+    }
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let view = tab.canvas.table_views.entry(overlay.table_id).or_default();
+    view.scroll_x = (overlay.scroll_x - delta * 48.0).clamp(0.0, max_scroll);
+    tab.canvas.mark_dirty_full();
+    true
+}
+
+/// Palette toggle for the selected table's frozen first column or header row.
+fn toggle_table_freeze(state: &mut WindowState, first_column: bool) {
+    let Some(table_id) = state.selected_table else {
+        state.app_state.status_text = "Select a table to freeze its panes".to_string();
+        return;
+    };
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return;
+    };
+    let view = tab.canvas.table_views.entry(table_id).or_default();
+    let (frozen, part) = if first_column {
+        view.freeze_first_column = !view.freeze_first_column;
+        (view.freeze_first_column, "First column")
+    } else {
+        view.freeze_header_row = !view.freeze_header_row;
+        (view.freeze_header_row, "Header row")
+    };
+    tab.canvas.mark_dirty_full();
+    state.app_state.status_text = format!("{part} {}", if frozen { "frozen" } else { "unfrozen" });
+}
+
+/// Replaces the selected table with one paragraph per row, cells separated by `delimiter`.
+fn convert_table_to_text(state: &mut WindowState, delimiter: TextDelimiter) {
+    let Some(table_id) = state.selected_table else {
+        state.app_state.status_text = "Select a table to convert to text".to_string();
+        return;
+    };
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    let Some(command) = table_to_text_command(&tab.document, table_id, delimiter) else {
+        return;
+    };
+    let first_row = match &command {
+        EditCommand::ReplaceBlocks { blocks, .. } => match blocks.first() {
+            Some(Block::Paragraph(paragraph)) => Some(paragraph.id),
+            _ => None,
+        },
+        _ => None,
+    };
+    if !apply_undoable_edit(state, command) {
+        return;
+    }
+    if let Some(tab) = state.tabs.active_tab_mut()
+        && let Some(first_row) = first_row
+    {
+        tab.cursor.primary.block_id = first_row;
+        tab.cursor.primary.offset = 0;
+    }
+    state.selected_table = None;
+    state.table_selection_mode = None;
+    state.table_selection_range = None;
+    state.app_state.status_text =
+        format!("Converted table to text separated by {}", delimiter.label());
+}
+
+/// Builds a table from the selected paragraphs, or the cursor paragraph when nothing is
+/// selected, splitting lines on the delimiter they use.
+fn convert_text_to_table(state: &mut WindowState) {
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    let (first, last) = match tab.cursor.selection {
+        Some(selection) => {
+            let selection = selection.normalized();
+            (selection.start.block_id, selection.end.block_id)
+        }
+        None => (tab.cursor.primary.block_id, tab.cursor.primary.block_id),
+    };
+    let Some((command, delimiter)) = text_to_table_command(&tab.document, first, last) else {
+        state.app_state.status_text = "Select paragraphs of delimited text to convert".to_string();
+        return;
+    };
+    let table_id = match &command {
+        EditCommand::ReplaceBlocks { blocks, .. } => match blocks.first() {
+            Some(Block::Table(table)) => Some(table.id),
+            _ => None,
+        },
+        _ => None,
+    };
+    if !apply_undoable_edit(state, command) {
+        return;
+    }
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.cursor.clear_selection();
+    }
+    state.selected_table = table_id;
+    state.table_selection_mode = None;
+    state.table_selection_range = None;
+    state.app_state.status_text =
+        format!("Converted text split on {} to a table", delimiter.label());
+}
+
+/// Quotes the selected blocks, or the cursor's block, or lifts them out of the quote they
+/// are already in.
+fn toggle_block_quote(state: &mut WindowState) {
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    let (first, last) = match tab.cursor.selection {
+        Some(selection) => {
+            let selection = selection.normalized();
+            (selection.start.block_id, selection.end.block_id)
+        }
+        None => (tab.cursor.primary.block_id, tab.cursor.primary.block_id),
+    };
+    let Some(command) = toggle_block_quote_command(&tab.document, first, last) else {
+        return;
+    };
+    // Wrapping adds a quote with a fresh id; unwrapping only hands back existing blocks.
+    let new_id = tab.document.next_block_id();
+    let wrapped = matches!(
+        &command,
+        EditCommand::ReplaceBlocks { blocks, .. }
+            if matches!(blocks.as_slice(), [Block::BlockQuote(quote)] if quote.id == new_id)
+    );
+    if apply_undoable_edit(state, command) {
+        state.app_state.status_text = if wrapped {
+            "Wrapped in a block quote".to_string()
+        } else {
+            "Removed block quote".to_string()
+        };
+    }
+}
+
 fn update_table_resize(state: &mut WindowState, point: UiPoint) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let Some(resize) = state.table_resize.clone() else {
         return false;
     };
@@ -1654,7 +4500,7 @@ fn update_table_resize(state: &mut WindowState, point: UiPoint) -> bool {
                 changed = resize_table_row(table, row, resize.start_value + dy);
             }
             if changed {
-                tab.document.dirty = true;
+                tab.document.mark_edited();
                 tab.dirty = true;
             }
         }
@@ -1662,6 +4508,46 @@ fn update_table_resize(state: &mut WindowState, point: UiPoint) -> bool {
     changed
 }
 
+/// Records a finished drag-resize for undo: the live drag edits the table directly, so put
+/// the starting size back and replay the final one through the edit engine.
+fn commit_table_resize(state: &mut WindowState, resize: &TableResizeState) {
+    let Some(table) = active_table_ref(state, resize.table_id) else {
+        return;
+    };
+    let op = if let Some(col) = resize.col {
+        let Some(width) = table.column_widths.get(col).copied() else {
+            return;
+        };
+        TableOp::ResizeColumn { col, width }
+    } else if let Some(row) = resize.row {
+        let Some(height) = table.row_heights.get(row).copied() else {
+            return;
+        };
+        TableOp::ResizeRow { row, height }
+    } else {
+        return;
+    };
+    let restore = match op {
+        TableOp::ResizeColumn { col, width } if width != resize.start_value => {
+            TableOp::ResizeColumn {
+                col,
+                width: resize.start_value,
+            }
+        }
+        TableOp::ResizeRow { row, height } if height != resize.start_value => TableOp::ResizeRow {
+            row,
+            height: resize.start_value,
+        },
+        _ => return,
+    };
+    if let Some(tab) = state.tabs.active_tab_mut()
+        && let Some(table) = find_table_mut(&mut tab.document, resize.table_id)
+    {
+        let _ = apply_table_op(table, &restore);
+    }
+    let _ = apply_table_edit(state, resize.table_id, op);
+}
+
 fn selected_table_cell(state: &WindowState) -> Option<CellPos> {
     match state.table_selection_mode {
         Some(TableSelectionMode::Cell(cell)) => Some(cell),
@@ -1678,38 +4564,25 @@ fn navigate_table_cell(state: &mut WindowState, backwards: bool) -> bool {
     let Some(current) = selected_table_cell(state) else {
         return false;
     };
-    let Some(table) = active_table_ref(state, table_id) else {
+    let Some(tab) = state.tabs.active_tab() else {
         return false;
     };
-    let rows = table.rows.len().max(1);
-    let cols = table.column_widths.len().max(1);
-    let mut row = current.row.min(rows.saturating_sub(1));
-    let mut col = current.col.min(cols.saturating_sub(1));
-
-    if backwards {
-        if col > 0 {
-            col -= 1;
-        } else if row > 0 {
-            row -= 1;
-            col = cols.saturating_sub(1);
-        }
-    } else if col + 1 < cols {
-        col += 1;
-    } else if row + 1 < rows {
-        row += 1;
-        col = 0;
-    } else {
-        if let Some(tab) = state.tabs.active_tab_mut() {
-            if let Some(table_mut) = find_table_mut(&mut tab.document, table_id) {
-                insert_row_below(table_mut, rows.saturating_sub(1));
-                tab.document.dirty = true;
-                tab.dirty = true;
+    let (table_id, row, col) = match step_table_cell(&tab.document, table_id, current, backwards) {
+        Some(CellStep::Cell(target, CellPos { row, col })) => (target, row, col),
+        Some(CellStep::AppendRow(table_id)) => {
+            let Some(table) = find_table(&tab.document, table_id) else {
+                return false;
+            };
+            let rows = table.rows.len();
+            if !apply_table_edit(state, table_id, TableOp::InsertRow { at: rows }) {
+                return false;
             }
+            (table_id, rows, 0)
         }
-        row = rows;
-        col = 0;
-    }
+        None => return false,
+    };
 
+    state.selected_table = Some(table_id);
     state.table_selection_mode = Some(TableSelectionMode::Cell(CellPos { row, col }));
     state.table_selection_range = Some(TableSelection {
         start: CellPos { row, col },
@@ -1774,6 +4647,9 @@ fn begin_image_interaction(state: &mut WindowState, point: UiPoint) -> bool {
 }
 
 fn update_image_drag(state: &mut WindowState, point: UiPoint, shift_down: bool) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let Some(drag) = state.image_drag.clone() else {
         return false;
     };
@@ -1829,7 +4705,7 @@ fn update_image_drag(state: &mut WindowState, point: UiPoint, shift_down: bool)
 
     if changed {
         if let Some(tab) = state.tabs.active_tab_mut() {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         state.app_state.status_text = if let Some(image) = active_image_ref(state, drag.block_id) {
@@ -1846,6 +4722,9 @@ fn update_image_drag(state: &mut WindowState, point: UiPoint, shift_down: bool)
 }
 
 fn delete_selected_image(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let Some(selected) = state.selected_image else {
         return false;
     };
@@ -1853,6 +4732,7 @@ fn delete_selected_image(state: &mut WindowState) -> bool {
     if let Some(tab) = state.tabs.active_tab_mut() {
         removed = tab.document.remove_image_block(selected);
         if removed {
+            tab.document.mark_edited();
             tab.dirty = true;
             tab.cursor.primary.offset = 0;
         }
@@ -1867,13 +4747,16 @@ fn delete_selected_image(state: &mut WindowState) -> bool {
 }
 
 fn align_selected_image(state: &mut WindowState, alignment: ImageAlignment) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let Some(selected) = state.selected_image else {
         return false;
     };
     if let Some(tab) = state.tabs.active_tab_mut() {
         if let Some(image) = tab.document.find_image_block_mut(selected) {
             image.alignment = alignment;
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
             return true;
         }
@@ -1882,6 +4765,9 @@ fn align_selected_image(state: &mut WindowState, alignment: ImageAlignment) -> b
 }
 
 fn toggle_selected_image_border(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let Some(selected) = state.selected_image else {
         return false;
     };
@@ -1896,7 +4782,7 @@ fn toggle_selected_image_border(state: &mut WindowState) -> bool {
                     color: crate::ui::Color::rgb(0.35, 0.54, 0.92),
                 })
             };
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
             return true;
         }
@@ -1940,6 +4826,32 @@ fn sync_sidebar_search_results(state: &mut WindowState) {
         .set_search_results(state.find_replace.query.clone(), items);
 }
 
+/// Remembers the editor selection for "match in selection only" as the find bar opens.
+fn capture_find_selection(state: &mut WindowState) {
+    state.find_replace.selection = state.tabs.active_tab().and_then(|tab| tab.cursor.selection);
+    state.find_replace.invalidate_cache();
+}
+
+/// Stores where the window is so the next launch opens it in the same place.
+fn remember_window_placement(state: &mut WindowState, hwnd: HWND) {
+    let previous = state.settings_dialog.settings().appearance.window;
+    if let Some(placement) = current_placement(hwnd, previous)
+        && Some(placement) != previous
+    {
+        state
+            .settings_dialog
+            .apply_change(|settings| settings.appearance.window = Some(placement));
+    }
+}
+
+/// Stores the find bar toggles in settings so the next session starts with them.
+fn save_find_options(state: &mut WindowState) {
+    let find = state.find_replace.settings();
+    state
+        .settings_dialog
+        .apply_change(|settings| settings.editor.find = find);
+}
+
 fn refresh_find_results(state: &mut WindowState) -> bool {
     let mut changed = false;
     if let Some(tab) = state.tabs.active_tab_mut() {
@@ -2000,9 +4912,16 @@ fn navigate_find_result(state: &mut WindowState, backwards: bool) -> bool {
 }
 
 fn replace_current_match(state: &mut WindowState) -> usize {
+    if edit_blocked_by_read_only(state) {
+        return 0;
+    }
     let mut count = 0;
     if let Some(tab) = state.tabs.active_tab_mut() {
         count = replace_current(&mut tab.document, &mut state.find_replace);
+        if count > 0 {
+            tab.document.mark_edited();
+            tab.dirty = true;
+        }
     }
     if count > 0 {
         sync_sidebar_search_results(state);
@@ -2011,9 +4930,16 @@ fn replace_current_match(state: &mut WindowState) -> usize {
 }
 
 fn replace_all_matches(state: &mut WindowState) -> usize {
+    if edit_blocked_by_read_only(state) {
+        return 0;
+    }
     let mut count = 0;
     if let Some(tab) = state.tabs.active_tab_mut() {
         count = replace_all(&mut tab.document, &mut state.find_replace);
+        if count > 0 {
+            tab.document.mark_edited();
+            tab.dirty = true;
+        }
     }
     if count > 0 {
         sync_sidebar_search_results(state);
@@ -2021,6 +4947,86 @@ fn replace_all_matches(state: &mut WindowState) -> usize {
     count
 }
 
+/// Replaces the current find query in every document tab after the user confirms the total.
+fn replace_in_all_open_tabs(state: &mut WindowState, hwnd: HWND) -> usize {
+    let query = state.find_replace.query.clone();
+    if query.is_empty() {
+        state.find_replace.open_replace();
+        state.find_focus = FindFieldFocus::Query;
+        state.focus_region = FocusRegion::Document;
+        state.app_state.status_text = "Set a Find query and replacement, then replace in all tabs".to_string();
+        return 0;
+    }
+
+    let preview = count_matches_in_documents(
+        state
+            .tabs
+            .tabs
+            .iter()
+            .filter(|tab| tab.kind == TabKind::Document && !tab.read_only)
+            .map(|tab| &tab.document),
+        query.as_str(),
+        state.find_replace.options,
+    );
+    if preview.total() == 0 {
+        state.app_state.status_text = format!("Replace in all tabs: no matches for '{query}'");
+        return 0;
+    }
+
+    let prompt = format!(
+        "Replace {} occurrence(s) of '{}' with '{}' in {} tab(s)?",
+        preview.total(),
+        query,
+        state.find_replace.replacement,
+        preview.documents_with_matches()
+    );
+    let prompt_wide = to_wide_null(prompt.as_str());
+    let choice = unsafe {
+        MessageBoxW(
+            Some(hwnd),
+            PCWSTR(prompt_wide.as_ptr()),
+            w!("Doco"),
+            MB_YESNO | MB_ICONQUESTION,
+        )
+    };
+    if choice != IDYES {
+        state.app_state.status_text = "Replace in all tabs cancelled".to_string();
+        return 0;
+    }
+
+    let replaced = replace_all_in_documents(
+        state
+            .tabs
+            .tabs
+            .iter_mut()
+            .filter(|tab| tab.kind == TabKind::Document && !tab.read_only)
+            .map(|tab| &mut tab.document),
+        &mut state.find_replace,
+    );
+    for (tab, count) in state
+        .tabs
+        .tabs
+        .iter_mut()
+        .filter(|tab| tab.kind == TabKind::Document && !tab.read_only)
+        .zip(replaced.per_document.iter())
+    {
+        if *count > 0 {
+            tab.document.mark_edited();
+            tab.dirty = true;
+        }
+    }
+
+    state.search_indexes.clear();
+    state.find_replace.document_edited();
+    refresh_find_results(state);
+    state.app_state.status_text = format!(
+        "Replaced {} occurrence(s) in {} tab(s)",
+        replaced.total(),
+        replaced.documents_with_matches()
+    );
+    replaced.total()
+}
+
 fn remove_last_char(text: &mut String) {
     let _ = text.pop();
 }
@@ -2087,30 +5093,11 @@ fn default_paragraph_with_style(id: BlockId, style: &RunStyle, text: String) ->
         spacing: ParagraphSpacing::default(),
         indent: Indent::default(),
         style_id: None,
+        direction: Default::default(),
+        frame: None,
     })
 }
 
-fn ensure_single_run(runs: &mut Vec<Run>, default_style: &RunStyle) {
-    if runs.is_empty() {
-        runs.push(Run {
-            text: String::new(),
-            style: default_style.clone(),
-        });
-        return;
-    }
-    if runs.len() == 1 {
-        return;
-    }
-
-    let text = runs.iter().map(|r| r.text.as_str()).collect::<String>();
-    let style = runs
-        .first()
-        .map(|r| r.style.clone())
-        .unwrap_or_else(|| default_style.clone());
-    runs.clear();
-    runs.push(Run { text, style });
-}
-
 fn run_style_from_toolbar(format: &ToolbarFormatState) -> RunStyle {
     RunStyle {
         font_family: if format.font_family.trim().is_empty() {
@@ -2118,7 +5105,7 @@ fn run_style_from_toolbar(format: &ToolbarFormatState) -> RunStyle {
         } else {
             Some(format.font_family.clone())
         },
-        font_size: Some(format.font_size.max(1.0)),
+        font_size: (format.font_size > 0.0).then(|| clamp_font_size(format.font_size)),
         bold: format.bold.is_on(),
         italic: format.italic.is_on(),
         underline: format.underline.is_on(),
@@ -2127,6 +5114,7 @@ fn run_style_from_toolbar(format: &ToolbarFormatState) -> RunStyle {
         background: format.highlight_color,
         superscript: format.superscript.is_on(),
         subscript: format.subscript.is_on(),
+        ..RunStyle::default()
     }
 }
 
@@ -2136,6 +5124,7 @@ fn ensure_editable_cursor_block(tab: &mut crate::ui::tabs::TabState, default_sty
         tab.document
             .content
             .push(default_paragraph_with_style(id, default_style, String::new()));
+        tab.document.revision = Revision::next();
         tab.cursor.primary.block_id = id;
         tab.cursor.primary.offset = 0;
         return 0;
@@ -2153,6 +5142,7 @@ fn ensure_editable_cursor_block(tab: &mut crate::ui::tabs::TabState, default_sty
             insert_at,
             default_paragraph_with_style(id, default_style, String::new()),
         );
+        tab.document.revision = Revision::next();
         tab.cursor.primary.block_id = id;
         tab.cursor.primary.offset = 0;
         return insert_at;
@@ -2179,6 +5169,7 @@ fn ensure_editable_cursor_block(tab: &mut crate::ui::tabs::TabState, default_sty
     tab.document
         .content
         .push(default_paragraph_with_style(id, default_style, String::new()));
+    tab.document.revision = Revision::next();
     tab.cursor.primary.block_id = id;
     tab.cursor.primary.offset = 0;
     insert_at
@@ -2193,8 +5184,12 @@ fn collect_text_block_lengths(document: &DocumentModel) -> Vec<(BlockId, usize)>
 }
 
 fn active_block_plain_text(tab: &crate::ui::tabs::TabState) -> Option<String> {
-    let idx = find_block_index_by_id(&tab.document, tab.cursor.primary.block_id)?;
-    match &tab.document.content[idx] {
+    block_plain_text(&tab.document, tab.cursor.primary.block_id)
+}
+
+fn block_plain_text(document: &DocumentModel, block_id: BlockId) -> Option<String> {
+    let idx = find_block_index_by_id(document, block_id)?;
+    match &document.content[idx] {
         Block::Paragraph(p) => Some(p.runs.iter().map(|r| r.text.as_str()).collect()),
         Block::Heading(h) => Some(h.runs.iter().map(|r| r.text.as_str()).collect()),
         Block::CodeBlock(c) => Some(c.code.clone()),
@@ -2209,10 +5204,78 @@ fn copy_active_block_to_clipboard(state: &WindowState) -> bool {
     let Some(text) = active_block_plain_text(tab) else {
         return false;
     };
-    set_plain_text(text.as_str()).is_ok()
+    set_plain_text(text.as_str()).is_ok()
+}
+
+fn copy_selected_image_to_clipboard(state: &WindowState) -> bool {
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let Some(image) = state
+        .selected_image
+        .and_then(|block_id| active_image_ref(state, block_id))
+    else {
+        return false;
+    };
+    let Some(data) = resolve_image_data(image, &tab.document) else {
+        return false;
+    };
+    let Ok(decoded) = image::load_from_memory(&data.bytes) else {
+        return false;
+    };
+    copy_image_to_clipboard(&decoded.to_rgba8()).is_ok()
+}
+
+/// Copies the selected table whole, with its widths, heights and styles; `false` when no
+/// table is selected.
+fn copy_selected_table_to_clipboard(state: &WindowState) -> bool {
+    let Some(table) = state
+        .selected_table
+        .and_then(|table_id| active_table_ref(state, table_id))
+    else {
+        return false;
+    };
+    copy_table_to_clipboard(table).is_ok()
+}
+
+/// Pastes a table copied by `copy_selected_table_to_clipboard` after the cursor block as one
+/// undo step, renumbered so it doesn't share ids with the original, and selects it.
+fn paste_table_from_clipboard(state: &mut WindowState) -> Option<BlockId> {
+    let Ok(Some(copied)) = read_clipboard_table() else {
+        return None;
+    };
+    let (at_index, table) = {
+        // A streamed text view takes the copied table as its plain text instead.
+        let tab = state
+            .tabs
+            .active_tab()
+            .filter(|tab| tab.text_view.is_none())?;
+        (
+            table_insert_index_for_cursor(tab),
+            table_for_paste(&tab.document, copied),
+        )
+    };
+    let id = table.id;
+    let whole = TableSelection::whole(&table);
+    let block = Block::Table(table);
+    if !apply_undoable_edit(state, EditCommand::RestoreBlock { at_index, block }) {
+        return None;
+    }
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.cursor.primary.block_id = id;
+        tab.cursor.primary.offset = 0;
+    }
+    state.selected_table = Some(id);
+    state.table_selection_mode = Some(TableSelectionMode::Table);
+    state.table_selection_range = Some(whole);
+    sync_sidebar_with_active_tab(state);
+    Some(id)
 }
 
 fn cut_active_block_to_clipboard(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     if !copy_active_block_to_clipboard(state) {
         return false;
     }
@@ -2243,7 +5306,7 @@ fn cut_active_block_to_clipboard(state: &mut WindowState) -> bool {
 
     if changed {
         tab.cursor.primary.offset = 0;
-        tab.document.dirty = true;
+        tab.document.mark_edited();
         tab.dirty = true;
     }
     changed
@@ -2256,10 +5319,37 @@ fn paste_text_from_clipboard_at_cursor(state: &mut WindowState) -> bool {
     }
 }
 
+/// Middle-click paste: moves the cursor to the text under `point`, when there is some, and
+/// pastes clipboard text there.
+fn paste_text_at_point(state: &mut WindowState, point: UiPoint) -> bool {
+    let text_view = state
+        .tabs
+        .active_tab()
+        .is_some_and(|tab| tab.text_view.is_some());
+    if !text_view
+        && let Some(hit) = canvas_text_hit(state, point)
+        && let Some(tab) = state.tabs.active_tab_mut()
+    {
+        tab.cursor.primary = CursorPosition {
+            block_id: hit.block_id,
+            offset: hit.offset,
+        };
+        tab.cursor.selection = None;
+    }
+    paste_text_from_clipboard_at_cursor(state)
+}
+
 fn insert_text_at_cursor(state: &mut WindowState, text: &str) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     if text.is_empty() {
         return false;
     }
+    // A streamed text view holds the file's text; the document behind it is only a stand-in.
+    if insert_into_text_view(state, text) {
+        return true;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     let mut changed = false;
 
@@ -2301,7 +5391,7 @@ fn insert_text_at_cursor(state: &mut WindowState, text: &str) -> bool {
         }
 
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
     }
@@ -2309,7 +5399,66 @@ fn insert_text_at_cursor(state: &mut WindowState, text: &str) -> bool {
     changed
 }
 
+/// Replaces a snippet abbreviation that ends at the cursor with its text, through
+/// `ReplaceText` so the expansion is a single edit.
+fn expand_snippet_at_cursor(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
+    let snippets = &state.app_state.settings.editor.snippets;
+    if snippets.is_empty() {
+        return false;
+    }
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let cursor = tab.cursor.primary;
+    let Some(Block::Paragraph(paragraph)) = tab
+        .document
+        .content
+        .iter()
+        .find(|block| matches!(block, Block::Paragraph(p) if p.id == cursor.block_id))
+    else {
+        return false;
+    };
+    // `ReplaceText` edits the first run; formatted paragraphs are left alone.
+    let [run] = paragraph.runs.as_slice() else {
+        return false;
+    };
+    let end = byte_index_from_char_offset(run.text.as_str(), cursor.offset);
+    let before = &run.text[..end];
+    let Some(snippet) = match_abbreviation(before, snippets) else {
+        return false;
+    };
+    let start = end - snippet.abbreviation.len();
+    let start_offset = before[..start].chars().count();
+    let command = EditCommand::ReplaceText {
+        block_id: cursor.block_id,
+        start,
+        end,
+        text: snippet.text.clone(),
+    };
+    let offset = start_offset + snippet.text.chars().count();
+    // Through the edit engine, so the expansion is one undo step.
+    if !apply_undoable_edit(state, command) {
+        return false;
+    }
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.cursor.primary.offset = offset;
+    }
+    true
+}
+
+fn insert_date_time(state: &mut WindowState) -> bool {
+    let format = state.app_state.settings.editor.date_time_format;
+    let text = format_date_time(format, &Local::now().naive_local(), &LocaleFormats::system());
+    insert_text_at_cursor(state, &text)
+}
+
 fn delete_backward_at_cursor(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
@@ -2368,7 +5517,7 @@ fn delete_backward_at_cursor(state: &mut WindowState) -> bool {
         }
 
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         return changed;
@@ -2377,6 +5526,9 @@ fn delete_backward_at_cursor(state: &mut WindowState) -> bool {
 }
 
 fn delete_forward_at_cursor(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
@@ -2426,7 +5578,7 @@ fn delete_forward_at_cursor(state: &mut WindowState) -> bool {
         }
 
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         return changed;
@@ -2435,6 +5587,9 @@ fn delete_forward_at_cursor(state: &mut WindowState) -> bool {
 }
 
 fn split_block_or_insert_newline(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
@@ -2463,7 +5618,7 @@ fn split_block_or_insert_newline(state: &mut WindowState) -> bool {
                 let at = byte_index_from_char_offset(c.code.as_str(), offset);
                 c.code.insert(at, '\n');
                 tab.cursor.primary.offset = offset + 1;
-                tab.document.dirty = true;
+                tab.document.mark_edited();
                 tab.dirty = true;
                 return true;
             }
@@ -2475,7 +5630,7 @@ fn split_block_or_insert_newline(state: &mut WindowState) -> bool {
             tab.document.content.insert(insert_at, block);
             tab.cursor.primary.block_id = new_id;
             tab.cursor.primary.offset = 0;
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
             return true;
         }
@@ -2483,7 +5638,7 @@ fn split_block_or_insert_newline(state: &mut WindowState) -> bool {
     false
 }
 
-fn move_cursor_in_text_blocks(state: &mut WindowState, movement: Movement) -> bool {
+fn move_cursor_in_text_blocks(state: &mut WindowState, mut movement: Movement) -> bool {
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let _ = ensure_editable_cursor_block(tab, &default_style);
@@ -2492,6 +5647,28 @@ fn move_cursor_in_text_blocks(state: &mut WindowState, movement: Movement) -> bo
             return false;
         }
         let before = tab.cursor.primary;
+        if matches!(movement, Movement::CtrlLeft | Movement::CtrlRight)
+            && let Some(text) = active_block_plain_text(tab)
+        {
+            let offset = before.offset.min(text.chars().count());
+            let language = document_language(&tab.document);
+            let target = if movement == Movement::CtrlLeft {
+                previous_word_start(&text, offset, language)
+            } else {
+                next_word_end(&text, offset, language)
+            };
+            if target != before.offset {
+                tab.cursor.primary.offset = target;
+                tab.cursor.clear_selection();
+                return true;
+            }
+            // Already at the block's edge, so step into the neighbouring block.
+            movement = if movement == Movement::CtrlLeft {
+                Movement::Left
+            } else {
+                Movement::Right
+            };
+        }
         tab.cursor
             .move_across_blocks(movement, blocks.as_slice(), 1, false);
         return tab.cursor.primary != before;
@@ -2499,7 +5676,78 @@ fn move_cursor_in_text_blocks(state: &mut WindowState, movement: Movement) -> bo
     false
 }
 
+/// While a selection drag holds the pointer past a canvas edge, extends the selection to the
+/// text showing at that edge as the canvas scrolls.
+fn extend_selection_to_view_edge(state: &mut WindowState) {
+    let origin = canvas_origin(state);
+    let Some(tab) = state.tabs.active_tab() else {
+        return;
+    };
+    let speed = tab.canvas.edge_scroll;
+    let height = tab.canvas.viewport.height;
+    let Some(renderer) = state.renderer.as_ref().filter(|_| speed != 0.0) else {
+        return;
+    };
+    // The edge itself can fall in a page margin or between lines, so step in from it to
+    // the first line of text.
+    let (edge_y, step) = if speed < 0.0 {
+        (origin.y + 1.0, 4.0)
+    } else {
+        (origin.y + height - 1.0, -4.0)
+    };
+    let x = origin.x + tab.canvas.viewport.width * 0.5;
+    let Some(point) = (0..(height / 8.0) as usize)
+        .map(|step_index| UiPoint {
+            x,
+            y: edge_y + step * step_index as f32,
+        })
+        .find(|point| renderer.preview_position_at(point.x, point.y).is_some())
+    else {
+        return;
+    };
+    let Some(hit) = canvas_text_hit(state, point) else {
+        return;
+    };
+    if let Some(tab) = state.tabs.active_tab_mut() {
+        tab.cursor.extend_to(CursorPosition {
+            block_id: hit.block_id,
+            offset: hit.offset,
+        });
+    }
+}
+
+/// Page Up/Down and Ctrl+Home/End: scrolls the canvas and brings the caret along.
+fn scroll_and_move_cursor(state: &mut WindowState, movement: Movement) -> bool {
+    let default_style = run_style_from_toolbar(&state.toolbar.format_state);
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let content_height = tab.canvas.content_size(&tab.document).height.max(1.0);
+    match movement {
+        Movement::PageUp => tab.canvas.scroll_pages(-1.0, &tab.document),
+        Movement::PageDown => tab.canvas.scroll_pages(1.0, &tab.document),
+        Movement::CtrlHome => tab.canvas.scroll_to_y(0.0, &tab.document),
+        Movement::CtrlEnd => tab.canvas.scroll_to_y(content_height, &tab.document),
+        _ => return move_cursor_in_text_blocks(state, movement),
+    }
+
+    let _ = ensure_editable_cursor_block(tab, &default_style);
+    let blocks = collect_text_block_lengths(&tab.document);
+    if !blocks.is_empty() {
+        // Blocks have no individual positions yet, so page by the share of them a viewport spans.
+        let per_page = (blocks.len() as f32 * tab.canvas.page_scroll_delta() / content_height)
+            .ceil()
+            .max(1.0) as usize;
+        tab.cursor
+            .move_across_blocks(movement, blocks.as_slice(), per_page, false);
+    }
+    true
+}
+
 fn cycle_font_family(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let families = ["Segoe UI", "Calibri", "Cambria", "Consolas"];
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
@@ -2537,7 +5785,7 @@ fn cycle_font_family(state: &mut WindowState) -> bool {
             _ => {}
         }
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         return changed;
@@ -2546,6 +5794,9 @@ fn cycle_font_family(state: &mut WindowState) -> bool {
 }
 
 fn cycle_font_size(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let sizes = [10.0_f32, 11.0, 12.0, 14.0, 16.0, 18.0, 20.0, 24.0];
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
@@ -2577,7 +5828,7 @@ fn cycle_font_size(state: &mut WindowState) -> bool {
             _ => {}
         }
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         return changed;
@@ -2586,6 +5837,9 @@ fn cycle_font_size(state: &mut WindowState) -> bool {
 }
 
 fn cycle_text_color(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let colors = [
         None,
         Some(crate::ui::Color::rgb(0.0, 0.0, 0.0)),
@@ -2615,7 +5869,7 @@ fn cycle_text_color(state: &mut WindowState) -> bool {
             _ => {}
         }
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         return changed;
@@ -2624,6 +5878,9 @@ fn cycle_text_color(state: &mut WindowState) -> bool {
 }
 
 fn cycle_heading_style(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
@@ -2635,7 +5892,7 @@ fn cycle_heading_style(state: &mut WindowState) -> bool {
                 _ => Some("Heading1".to_string()),
             };
             p.style_id = next;
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
             return true;
         }
@@ -2644,6 +5901,9 @@ fn cycle_heading_style(state: &mut WindowState) -> bool {
 }
 
 fn cycle_list_style(state: &mut WindowState) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
@@ -2655,7 +5915,7 @@ fn cycle_list_style(state: &mut WindowState) -> bool {
                 _ => Some("ListBullet".to_string()),
             };
             p.style_id = next;
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
             return true;
         }
@@ -2664,12 +5924,15 @@ fn cycle_list_style(state: &mut WindowState) -> bool {
 }
 
 fn set_paragraph_alignment(state: &mut WindowState, alignment: ParagraphAlignment) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
         if let Block::Paragraph(p) = &mut tab.document.content[idx] {
             p.alignment = alignment;
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
             return true;
         }
@@ -2677,7 +5940,34 @@ fn set_paragraph_alignment(state: &mut WindowState, alignment: ParagraphAlignmen
     false
 }
 
+/// Sets the reading direction of the paragraph at the cursor as one undo step.
+fn set_paragraph_direction(state: &mut WindowState, direction: TextDirection) -> bool {
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let block_id = tab.cursor.primary.block_id;
+    apply_undoable_edit(state, set_direction(block_id, direction))
+}
+
+/// Frames the cursor's paragraph with `preset`, or removes its frame for `None`.
+fn set_paragraph_frame_preset(
+    state: &mut WindowState,
+    preset: Option<ParagraphFramePreset>,
+) -> bool {
+    let Some(tab) = state.tabs.active_tab() else {
+        return false;
+    };
+    let block_id = tab.cursor.primary.block_id;
+    apply_undoable_edit(
+        state,
+        set_paragraph_frame(block_id, preset.map(ParagraphFramePreset::frame)),
+    )
+}
+
 fn toggle_inline_style(state: &mut WindowState, action: ToolbarAction) -> bool {
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
     let default_style = run_style_from_toolbar(&state.toolbar.format_state);
     if let Some(tab) = state.tabs.active_tab_mut() {
         let idx = ensure_editable_cursor_block(tab, &default_style);
@@ -2716,7 +6006,7 @@ fn toggle_inline_style(state: &mut WindowState, action: ToolbarAction) -> bool {
         }
 
         if changed {
-            tab.document.dirty = true;
+            tab.document.mark_edited();
             tab.dirty = true;
         }
         return changed;
@@ -2724,6 +6014,146 @@ fn toggle_inline_style(state: &mut WindowState, action: ToolbarAction) -> bool {
     false
 }
 
+fn selection_byte_range(
+    selection: Option<CursorSelection>,
+    block_id: BlockId,
+    runs: &[Run],
+) -> Option<FormatSelection> {
+    let selection = selection?.normalized();
+    if selection.start.block_id != block_id || selection.end.block_id != block_id {
+        return None;
+    }
+    let text = runs.iter().map(|r| r.text.as_str()).collect::<String>();
+    let start = byte_index_from_char_offset(text.as_str(), selection.start.offset);
+    let end = byte_index_from_char_offset(text.as_str(), selection.end.offset);
+    if start < end {
+        Some(FormatSelection::new(start, end))
+    } else {
+        None
+    }
+}
+
+/// Formats the selection inside the cursor paragraph through `FormatRun`, or
+/// restyles the whole cursor block when nothing is selected.
+fn format_cursor_selection<F>(state: &mut WindowState, build: F) -> bool
+where
+    F: FnOnce(BlockId, Option<FormatSelection>, &[Run], &mut RunStyle) -> Option<EditCommand>,
+{
+    if edit_blocked_by_read_only(state) {
+        return false;
+    }
+    let default_style = run_style_from_toolbar(&state.toolbar.format_state);
+    let Some(tab) = state.tabs.active_tab_mut() else {
+        return false;
+    };
+    let idx = ensure_editable_cursor_block(tab, &default_style);
+    let (block_id, runs, selection) = match &tab.document.content[idx] {
+        Block::Paragraph(p) => (
+            p.id,
+            p.runs.clone(),
+            selection_byte_range(tab.cursor.selection, p.id, &p.runs),
+        ),
+        Block::Heading(h) => (h.id, h.runs.clone(), None),
+        _ => return false,
+    };
+
+    let mut pending = runs
+        .first()
+        .map(|r| r.style.clone())
+        .unwrap_or_else(|| default_style.clone());
+    let changed = match build(block_id, selection, &runs, &mut pending) {
+        Some(command) => apply_to_document(&mut tab.document, &command).is_some(),
+        None => match &mut tab.document.content[idx] {
+            Block::Paragraph(p) => {
+                ensure_single_run(&mut p.runs, &default_style);
+                p.runs[0].style = pending;
+                true
+            }
+            Block::Heading(h) => {
+                ensure_single_run(&mut h.runs, &default_style);
+                h.runs[0].style = pending;
+                true
+            }
+            _ => false,
+        },
+    };
+
+    if changed {
+        tab.document.mark_edited();
+        tab.dirty = true;
+    }
+    changed
+}
+
+fn toggle_script_style(state: &mut WindowState, superscript: bool) -> bool {
+    format_cursor_selection(state, |block_id, selection, runs, pending| {
+        if superscript {
+            apply_or_toggle_superscript(block_id, selection, runs, pending)
+        } else {
+            apply_or_toggle_subscript(block_id, selection, runs, pending)
+        }
+    })
+}
+
+fn apply_highlight(state: &mut WindowState, choice: HighlightChoice) -> bool {
+    let color = choice.color();
+    format_cursor_selection(state, |block_id, selection, _runs, pending| {
+        apply_or_set_highlight_color(block_id, selection, color, pending)
+    })
+}
+
+fn apply_text_color(state: &mut WindowState, color: crate::ui::Color) -> bool {
+    let changed = format_cursor_selection(state, |block_id, selection, _runs, pending| {
+        apply_or_set_text_color(block_id, selection, color, pending)
+    });
+    if changed {
+        state.toolbar.remember_color(color);
+    }
+    changed
+}
+
+fn apply_dropdown_selection(state: &mut WindowState, selection: DropdownSelection) -> bool {
+    match selection {
+        DropdownSelection::TextColor(color) => {
+            let ok = apply_text_color(state, color);
+            state.app_state.status_text = if ok {
+                "Text color changed".to_string()
+            } else {
+                "Text color unavailable".to_string()
+            };
+            sync_toolbar_format_from_cursor(state);
+            ok
+        }
+        DropdownSelection::FontFamily(family) => {
+            let ok = format_cursor_selection(state, |block_id, selection, _runs, pending| {
+                apply_or_set_font_family(block_id, selection, family.as_str(), pending)
+            });
+            if ok {
+                state.toolbar.remember_font(family.as_str());
+            }
+            state.app_state.status_text = if ok {
+                format!("Font: {family}")
+            } else {
+                "Font unavailable".to_string()
+            };
+            sync_toolbar_format_from_cursor(state);
+            ok
+        }
+        DropdownSelection::FontSize(size) => {
+            let ok = format_cursor_selection(state, |block_id, selection, _runs, pending| {
+                apply_or_set_font_size(block_id, selection, size, pending)
+            });
+            state.app_state.status_text = if ok {
+                format!("Font size: {}", clamp_font_size(size))
+            } else {
+                "Font size unavailable".to_string()
+            };
+            sync_toolbar_format_from_cursor(state);
+            ok
+        }
+    }
+}
+
 fn heading_state_for_block(block: &Block) -> HeadingState {
     match block {
         Block::Heading(h) => match h.level {
@@ -2782,11 +6212,12 @@ fn sync_toolbar_format_from_cursor(state: &mut WindowState) {
     if let Some(tab) = state.tabs.active_tab() {
         if let Some(idx) = find_block_index_by_id(&tab.document, tab.cursor.primary.block_id) {
             let block = &tab.document.content[idx];
-            let first_style = match block {
-                Block::Paragraph(p) => p.runs.first().map(|r| r.style.clone()),
-                Block::Heading(h) => h.runs.first().map(|r| r.style.clone()),
-                _ => None,
+            let runs: &[Run] = match block {
+                Block::Paragraph(p) => &p.runs,
+                Block::Heading(h) => &h.runs,
+                _ => &[],
             };
+            let first_style = runs.first().map(|r| r.style.clone());
 
             if let Some(style) = first_style {
                 format.bold = if style.bold {
@@ -2819,11 +6250,13 @@ fn sync_toolbar_format_from_cursor(state: &mut WindowState) {
                 } else {
                     ToggleState::Off
                 };
-                format.font_family = style
-                    .font_family
-                    .clone()
-                    .unwrap_or_else(|| "Segoe UI".to_string());
-                format.font_size = style.font_size.unwrap_or(12.0);
+                // Font family and size follow the caret or selection; a mixed range leaves them blank.
+                let text = runs.iter().map(|r| r.text.as_str()).collect::<String>();
+                let caret = byte_index_from_char_offset(text.as_str(), tab.cursor.primary.offset);
+                let range = selection_byte_range(tab.cursor.selection, tab.cursor.primary.block_id, runs)
+                    .unwrap_or(FormatSelection::new(caret, caret));
+                format.font_family = common_font_family(runs, range, "Segoe UI").unwrap_or_default();
+                format.font_size = common_font_size(runs, range, 12.0).unwrap_or(0.0);
                 format.text_color = style.color;
                 format.highlight_color = style.background;
             }
@@ -2861,6 +6294,12 @@ fn apply_toolbar_action(state: &mut WindowState, hwnd: HWND, action: ToolbarActi
             ok
         }
         ToolbarAction::Copy => {
+            if state.table_selection_mode == Some(TableSelectionMode::Table)
+                && copy_selected_table_to_clipboard(state)
+            {
+                state.app_state.status_text = "Table copied".to_string();
+                return true;
+            }
             let ok = copy_active_block_to_clipboard(state);
             state.app_state.status_text = if ok {
                 "Copied".to_string()
@@ -2870,7 +6309,10 @@ fn apply_toolbar_action(state: &mut WindowState, hwnd: HWND, action: ToolbarActi
             ok
         }
         ToolbarAction::Paste => {
-            if paste_text_from_clipboard_at_cursor(state) {
+            if paste_table_from_clipboard(state).is_some() {
+                state.app_state.status_text = "Pasted table".to_string();
+                true
+            } else if paste_text_from_clipboard_at_cursor(state) {
                 state.app_state.status_text = "Pasted text".to_string();
                 true
             } else if let Ok(id) = insert_image_from_clipboard(state) {
@@ -2981,7 +6423,7 @@ fn apply_toolbar_action(state: &mut WindowState, hwnd: HWND, action: ToolbarActi
             ok
         }
         ToolbarAction::InsertImage => {
-            if let Some(path) = pick_image_file(hwnd) {
+            if let Some(path) = pick_image_via_dialog(state, hwnd) {
                 match insert_image_from_path(state, &path) {
                     Ok(id) => {
                         state.app_state.status_text = format!(
@@ -3018,11 +6460,19 @@ fn apply_toolbar_action(state: &mut WindowState, hwnd: HWND, action: ToolbarActi
             true
         }
         ToolbarAction::Undo => {
-            state.app_state.status_text = "Undo is not available yet".to_string();
+            state.app_state.status_text = if undo_active_tab(state, false) {
+                "Undo".to_string()
+            } else {
+                "Nothing to undo".to_string()
+            };
             true
         }
         ToolbarAction::Redo => {
-            state.app_state.status_text = "Redo is not available yet".to_string();
+            state.app_state.status_text = if undo_active_tab(state, true) {
+                "Redo".to_string()
+            } else {
+                "Nothing to redo".to_string()
+            };
             true
         }
         ToolbarAction::More => {
@@ -3154,7 +6604,7 @@ fn canvas_viewport_size(state: &WindowState, width: f32, height: f32) -> (f32, f
         0.0
     };
     let sidebar_w = if state.app_state.show_sidebar {
-        state.app_state.sidebar_width.clamp(200.0, 400.0)
+        clamp_sidebar_width(state.app_state.sidebar_width)
     } else {
         0.0
     };
@@ -3188,7 +6638,7 @@ fn relayout_shell(state: &mut WindowState, width: f32, height: f32) {
         0.0
     };
     let sidebar_w = if state.app_state.show_sidebar {
-        state.app_state.sidebar_width.clamp(200.0, 400.0)
+        clamp_sidebar_width(state.app_state.sidebar_width)
     } else {
         0.0
     };
@@ -3269,6 +6719,61 @@ fn relayout_shell(state: &mut WindowState, width: f32, height: f32) {
         },
         state.dpi,
     );
+    state.toolbar_customizer.layout(
+        UiRect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        },
+        state.dpi,
+    );
+    state.goto_symbol.layout(
+        UiRect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        },
+        state.dpi,
+    );
+    state.licenses_view.layout(
+        UiRect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        },
+        state.dpi,
+    );
+    state.shortcut_editor.layout(
+        UiRect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        },
+        state.dpi,
+    );
+    state.breadcrumb.set_visible(true);
+    state.breadcrumb.layout(
+        UiRect {
+            x: sidebar_w,
+            y: tab_h + toolbar_h,
+            width: (width - sidebar_w).max(0.0),
+            height: BREADCRUMB_HEIGHT * ui_scale,
+        },
+        state.dpi,
+    );
+    state.document_properties.layout(
+        UiRect {
+            x: 0.0,
+            y: 0.0,
+            width,
+            height,
+        },
+        state.dpi,
+    );
 
     let (canvas_w, canvas_h) = canvas_viewport_size(state, width, height);
     if let Some(tab) = state.tabs.active_tab_mut() {
@@ -3278,22 +6783,36 @@ fn relayout_shell(state: &mut WindowState, width: f32, height: f32) {
 }
 
 fn sync_theme_from_settings(state: &mut WindowState) -> bool {
-    let previous_name = state.theme.name.clone();
-    let previous_is_dark = state.theme.is_dark;
-    let next = state
-        .theme_manager
-        .apply_preference(&state.app_state.settings.appearance.theme);
-    let changed = previous_name != next.name || previous_is_dark != next.is_dark;
+    if state.theme_watcher.as_ref().is_some_and(ThemeWatcher::poll) {
+        let reloaded = state.theme_manager.reload();
+        if !reloaded.is_empty() {
+            state.app_state.status_text = format!("Reloaded theme: {}", reloaded.join(", "));
+        }
+    }
+    let next = theme_for_accessibility(
+        state
+            .theme_manager
+            .apply_preference(&state.app_state.settings.appearance.theme),
+        state.accessibility.high_contrast,
+        high_contrast_scheme_is_dark(),
+    );
+    // Compare whole themes so an edited theme file repaints with its new colors.
+    let changed = state.theme != next;
     state.theme = next;
+    state.toolbar.set_theme_palette(state.theme.is_dark);
     changed
 }
 
-fn sidebar_panel_from_preference(pref: SidebarDefaultPanel) -> SidebarPanel {
-    match pref {
-        SidebarDefaultPanel::Files => SidebarPanel::Files,
-        SidebarDefaultPanel::Outline => SidebarPanel::Outline,
-        SidebarDefaultPanel::Bookmarks => SidebarPanel::Bookmarks,
-    }
+/// Stores the sidebar's width and panel so the next launch opens it the same way.
+fn remember_sidebar_layout(state: &mut WindowState) {
+    let width = state.app_state.sidebar_width;
+    let panel = state.sidebar.active_panel.remembered();
+    state.settings_dialog.apply_change(|settings| {
+        settings.appearance.sidebar_width = width;
+        if panel.is_some() {
+            settings.appearance.sidebar_last_panel = panel;
+        }
+    });
 }
 
 fn set_settings_visible(state: &mut WindowState, visible: bool) {
@@ -3301,6 +6820,20 @@ fn set_settings_visible(state: &mut WindowState, visible: bool) {
     state.settings_dialog.set_visible(visible);
 }
 
+fn canvas_background_settings(state: &WindowState) -> BackgroundSettings {
+    let performance = &state.app_state.settings.performance;
+    let mut settings = from_canvas_preference(
+        &state.app_state.settings.appearance.canvas_background,
+        performance.animated_backgrounds && !state.accessibility.reduce_motion,
+        performance.background_pattern_quality,
+    );
+    if state.accessibility.high_contrast {
+        // High contrast colors the page too.
+        settings.page = None;
+    }
+    settings
+}
+
 fn apply_accessibility_preferences(state: &mut WindowState) {
     let reduce_motion =
         state.accessibility.reduce_motion || !state.app_state.settings.performance.animated_backgrounds;
@@ -3309,13 +6842,21 @@ fn apply_accessibility_preferences(state: &mut WindowState) {
     state.toolbar.set_reduce_motion(reduce_motion);
     state.tabs.reduce_motion = reduce_motion;
     state.toast.reduce_motion = reduce_motion;
-    if let Some(tab) = state.tabs.active_tab_mut() {
+    for tab in &mut state.tabs.tabs {
         tab.canvas.set_reduce_motion(reduce_motion);
     }
 }
 
 fn sync_runtime_from_settings(state: &mut WindowState, hwnd: HWND) {
-    let settings = state.settings_dialog.settings().clone();
+    apply_about_action(state);
+    let shortcuts = &state.settings_dialog.settings().keyboard_shortcuts;
+    if state.shortcut_dispatcher.update(shortcuts) {
+        state.command_palette.set_key_bindings(shortcuts);
+    }
+    if std::mem::take(&mut state.settings_dialog.pending_shortcut_editor) {
+        open_shortcut_editor(state);
+    }
+    let settings = effective_settings(state);
 
     let prev_show_toolbar = state.app_state.show_toolbar;
     let prev_show_sidebar = state.app_state.show_sidebar;
@@ -3323,33 +6864,24 @@ fn sync_runtime_from_settings(state: &mut WindowState, hwnd: HWND) {
     let prev_show_tabs = state.app_state.show_tabs;
     let prev_ui_scale = state.app_state.settings.appearance.ui_scale.as_factor();
     let prev_sidebar_panel = state.sidebar.active_panel;
+    let prev_hardware_acceleration = state.app_state.settings.performance.hardware_acceleration;
 
     state.app_state.settings = settings;
+    apply_editing_settings(state);
     state.app_state.show_toolbar = state.app_state.settings.appearance.show_toolbar;
     state.app_state.show_sidebar = state.app_state.settings.appearance.show_sidebar;
     state.app_state.show_statusbar = state.app_state.settings.appearance.show_status_bar;
     state.app_state.show_tabs = state.app_state.settings.appearance.show_tab_bar;
     apply_accessibility_preferences(state);
+    state
+        .toolbar
+        .set_layout(state.app_state.settings.appearance.toolbar_layout.as_deref());
 
-    let preferred_panel =
-        sidebar_panel_from_preference(state.app_state.settings.appearance.sidebar_default_panel);
+    let preferred_panel = SidebarPanel::restored(&state.app_state.settings.appearance);
     if state.sidebar.active_panel != SidebarPanel::SearchResults {
         state.sidebar.set_active_panel(preferred_panel);
     }
 
-    let autosave_seconds = state
-        .app_state
-        .settings
-        .files
-        .auto_save_interval
-        .as_seconds()
-        .unwrap_or(60 * 60 * 24 * 365 * 100)
-        .max(5);
-    let desired_interval = Duration::from_secs(autosave_seconds);
-    if state.app_state.autosave.interval != desired_interval {
-        state.app_state.autosave = crate::document::export::AutoSaveManager::new(autosave_seconds);
-    }
-
     let desired_image_cache_bytes = (state.app_state.settings.performance.max_image_cache_mb as usize)
         .max(32)
         * 1024
@@ -3383,76 +6915,165 @@ fn sync_runtime_from_settings(state: &mut WindowState, hwnd: HWND) {
             (client.bottom - client.top).max(0) as f32,
         );
     }
+
+    if prev_hardware_acceleration != state.app_state.settings.performance.hardware_acceleration
+        && state.renderer.is_some()
+    {
+        let mut client = RECT::default();
+        let _ = unsafe { GetClientRect(hwnd, &mut client) };
+        create_renderer(
+            state,
+            hwnd,
+            (client.right - client.left).max(1) as u32,
+            (client.bottom - client.top).max(1) as u32,
+        );
+        if let Some(renderer) = &state.renderer {
+            state.app_state.status_text = format!("Renderer: {}", renderer.driver().label());
+        }
+    }
 }
 
-fn collect_document_stats(document: &DocumentModel) -> (usize, usize) {
-    fn walk_block(block: &Block, words: &mut usize, chars: &mut usize) {
-        match block {
-            Block::Paragraph(p) => {
-                for run in &p.runs {
-                    *chars += run.text.chars().count();
-                    *words += run.text.split_whitespace().count();
+/// (Re)creates the renderer with the driver the performance settings ask for. The old
+/// renderer is dropped first because a window can only own one flip-model swap chain.
+fn create_renderer(state: &mut WindowState, hwnd: HWND, width: u32, height: u32) {
+    state.renderer = None;
+    let hardware_acceleration = state.app_state.settings.performance.hardware_acceleration;
+    match D2DRenderer::new(
+        hwnd,
+        width,
+        height,
+        state.dpi,
+        state.theme.clone(),
+        hardware_acceleration,
+    ) {
+        Ok(mut renderer) => {
+            renderer.set_debug_panel_visible(state.debug_panel_visible);
+            state.renderer = Some(renderer);
+            capture_system_info(state);
+        }
+        Err(error) => {
+            eprintln!("Renderer initialization failed: {error:?}");
+        }
+    }
+}
+
+/// One line of the canvas text preview and the block it was taken from, if any.
+struct PreviewLine {
+    block_id: Option<BlockId>,
+    text: String,
+    /// Byte ranges of footnote reference numbers in `text`.
+    footnote_marks: Vec<std::ops::Range<usize>>,
+    /// Byte ranges of highlighted runs, with the highlight color.
+    highlights: Vec<(std::ops::Range<usize>, crate::ui::Color)>,
+    /// Byte ranges raised or lowered as scripts: typeset math and runs formatted that way.
+    superscripts: Vec<std::ops::Range<usize>>,
+    subscripts: Vec<std::ops::Range<usize>>,
+    /// Byte ranges of wiki links, with the page each one names.
+    wiki_links: Vec<(std::ops::Range<usize>, String)>,
+    /// Byte ranges of cross-references, with the anchor each one names.
+    cross_references: Vec<(std::ops::Range<usize>, String)>,
+    /// Direction of the paragraph shown; lines of other blocks follow the document language.
+    direction: Option<TextDirection>,
+    frame: Option<ParagraphFrame>,
+    /// How many block quotes the line sits in.
+    quote_depth: usize,
+    /// The block's text as written when `text` shows it in capitals, as headings do. Find
+    /// matches index into this.
+    source: Option<String>,
+}
+
+impl PreviewLine {
+    fn new(block_id: Option<BlockId>, text: String) -> Self {
+        Self {
+            block_id,
+            text,
+            footnote_marks: Vec::new(),
+            highlights: Vec::new(),
+            superscripts: Vec::new(),
+            subscripts: Vec::new(),
+            wiki_links: Vec::new(),
+            cross_references: Vec::new(),
+            direction: None,
+            frame: None,
+            quote_depth: 0,
+            source: None,
+        }
+    }
+
+    fn from_runs(block_id: BlockId, runs: &[Run]) -> Self {
+        let mut line = Self::new(Some(block_id), String::new());
+        for run in runs {
+            let start = line.text.len();
+            if run.style.math.is_some() {
+                let math = layout_math(&run.text);
+                line.text.push_str(&math.text);
+                for (range, script) in math.scripts {
+                    let range = start + range.start..start + range.end;
+                    match script {
+                        MathScript::Superscript => line.superscripts.push(range),
+                        MathScript::Subscript => line.subscripts.push(range),
+                    }
                 }
+                continue;
             }
-            Block::Heading(h) => {
-                for run in &h.runs {
-                    *chars += run.text.chars().count();
-                    *words += run.text.split_whitespace().count();
-                }
+            line.text.push_str(run.text.as_str());
+            if let Some(color) = run.style.background {
+                line.highlights.push((start..line.text.len(), color));
             }
-            Block::CodeBlock(c) => {
-                *chars += c.code.chars().count();
-                *words += c.code.split_whitespace().count();
+            if run.style.superscript {
+                line.superscripts.push(start..line.text.len());
+            } else if run.style.subscript {
+                line.subscripts.push(start..line.text.len());
             }
-            Block::List(list) => {
-                for item in &list.items {
-                    for nested in &item.content {
-                        walk_block(nested, words, chars);
-                    }
-                }
+            if run.style.footnote.is_some() {
+                line.footnote_marks.push(start..line.text.len());
             }
-            Block::Table(table) => {
-                for row in &table.rows {
-                    for cell in &row.cells {
-                        for nested in &cell.blocks {
-                            walk_block(nested, words, chars);
-                        }
-                    }
-                }
+            if let Some(page) = &run.style.wiki_link {
+                line.wiki_links.push((start..line.text.len(), page.clone()));
             }
-            Block::BlockQuote(q) => {
-                for nested in &q.blocks {
-                    walk_block(nested, words, chars);
-                }
+            if let Some(reference) = &run.style.cross_reference {
+                line.cross_references
+                    .push((start..line.text.len(), reference.anchor.clone()));
             }
-            Block::Image(_) | Block::PageBreak | Block::HorizontalRule => {}
         }
-    }
-
-    let mut words = 0;
-    let mut chars = 0;
-    for block in &document.content {
-        walk_block(block, &mut words, &mut chars);
-    }
-    (words, chars)
-}
-
-fn collect_preview_lines(document: &DocumentModel, max_lines: usize) -> Vec<String> {
-    fn push_block_lines(block: &Block, out: &mut Vec<String>, max_lines: usize) {
+        line
+    }
+}
+
+/// One preview line per block, up to `max_lines`. A callout in `folded` shows only its
+/// header.
+fn collect_preview_lines(
+    document: &DocumentModel,
+    folded: &[BlockId],
+    max_lines: usize,
+) -> Vec<PreviewLine> {
+    fn push_block_lines(
+        block: &Block,
+        folded: &[BlockId],
+        captions: &[CaptionNumber],
+        out: &mut Vec<PreviewLine>,
+        max_lines: usize,
+    ) {
         if out.len() >= max_lines {
             return;
         }
+        // Tables are captioned above, figures below.
+        let caption = numbered_caption(captions, block);
         match block {
             Block::Paragraph(p) => {
-                let text = p.runs.iter().map(|r| r.text.as_str()).collect::<String>();
-                if !text.trim().is_empty() {
-                    out.push(text);
+                let mut line = PreviewLine::from_runs(p.id, &p.runs);
+                line.direction = Some(p.direction);
+                line.frame = p.frame.clone();
+                if !line.text.trim().is_empty() {
+                    out.push(line);
                 }
             }
             Block::Heading(h) => {
                 let text = h.runs.iter().map(|r| r.text.as_str()).collect::<String>();
                 if !text.trim().is_empty() {
-                    out.push(text.to_uppercase());
+                    let mut line = PreviewLine::new(Some(h.id), text.to_uppercase());
+                    line.source = Some(text);
+                    out.push(line);
                 }
             }
             Block::CodeBlock(c) => {
@@ -3461,7 +7082,7 @@ fn collect_preview_lines(document: &DocumentModel, max_lines: usize) -> Vec<Stri
                 } else {
                     &c.code
                 };
-                out.push(line.lines().next().unwrap_or("code block").to_string());
+                out.push(PreviewLine::new(Some(c.id), line.lines().next().unwrap_or("code block").to_string()));
             }
             Block::List(list) => {
                 for item in &list.items {
@@ -3469,111 +7090,156 @@ fn collect_preview_lines(document: &DocumentModel, max_lines: usize) -> Vec<Stri
                         break;
                     }
                     for nested in &item.content {
-                        push_block_lines(nested, out, max_lines);
+                        push_block_lines(nested, folded, captions, out, max_lines);
                     }
                 }
             }
             Block::Table(table) => {
-                out.push(format!("Table: {} rows", table.rows.len()));
+                out.extend(caption.map(|caption| PreviewLine::new(None, caption)));
+                out.push(PreviewLine::new(None, format!("Table: {} rows", table.rows.len())));
             }
             Block::BlockQuote(q) => {
-                for nested in &q.blocks {
-                    if out.len() >= max_lines {
-                        break;
+                let first = out.len();
+                let collapsed = q.callout.is_some() && folded.contains(&q.id);
+                if let Some(kind) = q.callout {
+                    // Clicking the header folds the body away.
+                    let arrow = if collapsed { '\u{25B8}' } else { '\u{25BE}' };
+                    let mut header =
+                        PreviewLine::new(Some(q.id), format!("{arrow} {}", kind.label()));
+                    header.frame = Some(kind.frame());
+                    out.push(header);
+                }
+                let body = out.len();
+                if !collapsed {
+                    for nested in &q.blocks {
+                        if out.len() >= max_lines {
+                            break;
+                        }
+                        push_block_lines(nested, folded, captions, out, max_lines);
+                    }
+                }
+                if let Some(kind) = q.callout {
+                    let shading = ParagraphFrame {
+                        icon: None,
+                        border: None,
+                        ..kind.frame()
+                    };
+                    for line in &mut out[body..] {
+                        line.frame.get_or_insert_with(|| shading.clone());
                     }
-                    push_block_lines(nested, out, max_lines);
                 }
+                for line in &mut out[first..] {
+                    line.quote_depth += 1;
+                }
+            }
+            Block::Image(_) => {
+                out.push(PreviewLine::new(None, "[Image]".to_string()));
+                out.extend(caption.map(|caption| PreviewLine::new(None, caption)));
             }
-            Block::Image(_) => out.push("[Image]".to_string()),
-            Block::PageBreak => out.push(String::new()),
-            Block::HorizontalRule => out.push("----".to_string()),
+            Block::PageBreak => out.push(PreviewLine::new(None, String::new())),
+            Block::HorizontalRule => out.push(PreviewLine::new(None, "----".to_string())),
         }
     }
 
+    let captions = number_captions(&document.content);
     let mut out = Vec::new();
     for block in &document.content {
-        push_block_lines(block, &mut out, max_lines);
+        push_block_lines(block, folded, &captions, &mut out, max_lines);
         if out.len() >= max_lines {
             break;
         }
     }
 
+    // The footnotes region closes the document, after a short rule like a printed page.
+    if !document.footnotes.is_empty() {
+        out.push(PreviewLine::new(None, "\u{2500}".repeat(12)));
+        for (index, note) in document.footnotes.iter().enumerate() {
+            let text = note.runs.iter().map(|r| r.text.as_str()).collect::<String>();
+            out.push(PreviewLine::new(None, format!("{}. {}", index + 1, text.replace('\n', " "))));
+        }
+    }
+
     if out.is_empty() {
-        out.push("Start typing here...".to_string());
+        out.push(PreviewLine::new(None, "Start typing here...".to_string()));
     }
 
     out
 }
 
-fn append_block_text(block: &Block, out: &mut String) {
-    match block {
-        Block::Paragraph(p) => {
-            for run in &p.runs {
-                out.push_str(run.text.as_str());
-            }
-            out.push('\n');
-        }
-        Block::Heading(h) => {
-            for run in &h.runs {
-                out.push_str(run.text.as_str());
-            }
-            out.push('\n');
-        }
-        Block::CodeBlock(c) => {
-            out.push_str(c.code.as_str());
-            out.push('\n');
-        }
-        Block::List(list) => {
-            for item in &list.items {
-                for nested in &item.content {
-                    append_block_text(nested, out);
-                }
-            }
-        }
-        Block::Table(table) => {
-            for row in &table.rows {
-                for cell in &row.cells {
-                    for nested in &cell.blocks {
-                        append_block_text(nested, out);
-                    }
-                    out.push(' ');
-                }
-                out.push('\n');
-            }
-        }
-        Block::BlockQuote(quote) => {
-            for nested in &quote.blocks {
-                append_block_text(nested, out);
-            }
-        }
-        Block::Image(_) | Block::PageBreak | Block::HorizontalRule => {}
-    }
+/// The byte ranges `ranges` picks from each line, as UTF-16 ranges of the joined canvas
+/// preview text.
+fn canvas_text_spans(
+    lines: &[PreviewLine],
+    ranges: impl Fn(&PreviewLine) -> Vec<std::ops::Range<usize>>,
+) -> Vec<crate::render::d2d::CanvasTextSpan> {
+    let untagged = |line: &PreviewLine| {
+        ranges(line)
+            .into_iter()
+            .map(|range| (range, ()))
+            .collect::<Vec<_>>()
+    };
+    canvas_tagged_spans(lines, untagged)
+        .into_iter()
+        .map(|(span, ())| span)
+        .collect()
 }
 
-fn collect_document_plain_text(document: &DocumentModel) -> String {
-    let mut out = String::new();
-    for block in &document.content {
-        append_block_text(block, &mut out);
+/// [`canvas_text_spans`] for ranges that carry something along, such as a color.
+fn canvas_tagged_spans<T>(
+    lines: &[PreviewLine],
+    ranges: impl Fn(&PreviewLine) -> Vec<(std::ops::Range<usize>, T)>,
+) -> Vec<(crate::render::d2d::CanvasTextSpan, T)> {
+    let mut out = Vec::new();
+    let mut line_start = 0u32;
+    for line in lines {
+        for (mark, tag) in ranges(line) {
+            let (Some(before), Some(marked)) = (line.text.get(..mark.start), line.text.get(mark.clone())) else {
+                continue;
+            };
+            let span = crate::render::d2d::CanvasTextSpan {
+                start: line_start + before.encode_utf16().count() as u32,
+                len: marked.encode_utf16().count() as u32,
+            };
+            out.push((span, tag));
+        }
+        line_start += line.text.encode_utf16().count() as u32 + 1;
     }
     out
 }
 
-fn block_id_for_search(block: &Block) -> BlockId {
-    match block {
-        Block::Paragraph(p) => p.id,
-        Block::Heading(h) => h.id,
-        Block::CodeBlock(c) => c.id,
-        Block::List(list) => list
-            .items
-            .first()
-            .and_then(|item| item.content.first())
-            .map(block_id_for_search)
-            .unwrap_or(BlockId(0)),
-        Block::Image(image) => image.id,
-        Block::Table(table) => table.id,
-        Block::BlockQuote(quote) => quote.id,
-        Block::PageBreak | Block::HorizontalRule => BlockId(0),
+/// Maps visible find matches onto UTF-16 ranges of the joined canvas preview text.
+fn canvas_find_highlights(
+    lines: &[PreviewLine],
+    find: &FindReplaceState,
+    visible_ids: &[BlockId],
+) -> Vec<crate::render::d2d::CanvasFindHighlight> {
+    let mut line_starts = Vec::with_capacity(lines.len());
+    let mut offset = 0u32;
+    for line in lines {
+        line_starts.push(offset);
+        offset += line.text.encode_utf16().count() as u32 + 1;
     }
+
+    matches_in_visible_blocks(&find.results, visible_ids)
+        .into_iter()
+        .filter_map(|(idx, m)| {
+            let line_idx = lines.iter().position(|line| line.block_id == Some(m.block_id))?;
+            let line = &lines[line_idx];
+            let text = line.source.as_deref().unwrap_or(&line.text);
+            let before = text.get(..m.start)?;
+            let matched = text.get(m.start..m.end)?;
+            let shown_len = |part: &str| match line.source {
+                // Capitals can be longer than the letters written, as "ß" becomes "SS".
+                Some(_) => part.to_uppercase().encode_utf16().count() as u32,
+                None => part.encode_utf16().count() as u32,
+            };
+            Some(crate::render::d2d::CanvasFindHighlight {
+                start: line_starts[line_idx] + shown_len(before),
+                len: shown_len(matched),
+                current: idx == find.current_index,
+            })
+        })
+        .collect()
 }
 
 fn find_in_all_open_tabs(state: &mut WindowState, query: &str) -> (usize, usize) {
@@ -3583,59 +7249,33 @@ fn find_in_all_open_tabs(state: &mut WindowState, query: &str) -> (usize, usize)
         return (0, 0);
     }
 
+    let options = state.find_replace.options;
     let mut tabs_with_matches = 0usize;
     let mut total_matches = 0usize;
     let mut sidebar_results = Vec::new();
 
     for tab in &state.tabs.tabs {
-        if tab.kind == TabKind::Welcome {
+        if tab.kind != TabKind::Document {
             continue;
         }
 
-        let text = collect_document_plain_text(&tab.document);
-        if text.is_empty() {
+        let index = state.search_indexes.entry(tab.id).or_default();
+        index.sync(&tab.document);
+        let matches = index.search(needle, options);
+        if matches.is_empty() {
             continue;
         }
 
-        let mut offset = 0usize;
-        let mut tab_matches = 0usize;
-        while let Some(rel) = text[offset..].find(needle) {
-            let absolute = offset + rel;
-            tab_matches += 1;
-            total_matches += 1;
-
-            if sidebar_results.len() < 120 {
-                let chars_before = text[..absolute].chars().count();
-                let snippet_start = chars_before.saturating_sub(22);
-                let snippet = text
-                    .chars()
-                    .skip(snippet_start)
-                    .take(90)
-                    .collect::<String>()
-                    .replace('\n', " ");
-                let block_id = tab
-                    .document
-                    .content
-                    .first()
-                    .map(block_id_for_search)
-                    .unwrap_or(BlockId(0));
-                sidebar_results.push(SearchResultItem {
-                    block_id,
-                    line_or_page: 1,
-                    snippet: format!("{}: {}", tab.title, snippet.trim()),
-                    start: absolute,
-                    end: absolute + needle.len(),
-                });
-            }
-
-            offset = absolute + needle.len().max(1);
-            if offset >= text.len() {
-                break;
-            }
-        }
-
-        if tab_matches > 0 {
-            tabs_with_matches += 1;
+        tabs_with_matches += 1;
+        total_matches += matches.len();
+        for m in matches.into_iter().take(120 - sidebar_results.len()) {
+            sidebar_results.push(SearchResultItem {
+                block_id: m.block_id,
+                line_or_page: m.line_or_page,
+                snippet: format!("{}: {}", tab.title, m.snippet),
+                start: m.start,
+                end: m.end,
+            });
         }
     }
 
@@ -3661,6 +7301,7 @@ fn tab_icon_label(tab: &crate::ui::tabs::TabState) -> &'static str {
         Some("docx") => "[DOCX]",
         Some("pdf") => "[PDF]",
         Some("md") | Some("markdown") => "[MD]",
+        Some("epub") => "[EPUB]",
         Some("txt") => "[TXT]",
         _ => "[DOC]",
     }
@@ -3706,9 +7347,32 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
     let mut file_format = "DOCX".to_string();
     let mut line = 1usize;
     let mut column = 1usize;
+    let active_read_only = state.tabs.active_tab().is_some_and(|tab| tab.read_only);
+    let save_indicator = state
+        .tabs
+        .active_tab()
+        .filter(|tab| tab.kind == TabKind::Document)
+        .map(|tab| SaveIndicator::new(tab.has_unsaved_changes()));
+    let language = state
+        .tabs
+        .active_tab_mut()
+        .map_or(DEFAULT_LOCALE, |tab| tab.language())
+        .to_string();
 
     let mut canvas_page_rects = Vec::new();
     let mut canvas_preview_lines = Vec::new();
+    let mut canvas_rtl_lines = Vec::new();
+    let mut canvas_line_frames = Vec::new();
+    let mut canvas_quote_depths = Vec::new();
+    let mut canvas_columns = 1usize;
+    let mut canvas_column_gap = 0.0f32;
+    let mut canvas_find_highlights_out = Vec::new();
+    let mut canvas_footnote_marks_out = Vec::new();
+    let mut canvas_text_highlights = Vec::new();
+    let mut canvas_superscripts = Vec::new();
+    let mut canvas_subscripts = Vec::new();
+    let mut canvas_links = Vec::new();
+    let mut canvas_broken_links = Vec::new();
     let mut canvas_show_margin_guides = false;
     let mut canvas_cursor_visible = true;
     let mut canvas_scrollbar_visible = false;
@@ -3722,26 +7386,37 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
     let mut canvas_images = Vec::new();
     let mut canvas_tables = Vec::new();
     let mut current_block = None;
+    let mut breadcrumb_path = Vec::new();
     let mut active_is_welcome = false;
     let selected_image_id = state.selected_image;
+    let link_root = wiki_root(state);
 
     {
         let (tabs, image_cache) = (&mut state.tabs, &mut state.image_cache);
+        let wiki_pages_cache = &mut state.wiki_pages;
         if let Some(tab) = tabs.active_tab_mut() {
             active_is_welcome = tab.kind == TabKind::Welcome;
-            (word_count, character_count) = collect_document_stats(&tab.document);
+            let stats = collect_document_stats(&tab.document.content);
+            (word_count, character_count) = (stats.words, stats.characters);
             let visible_indices = tab.canvas.cull_and_cache_visible_pages(&tab.document);
             let all_page_rects = tab.canvas.page_rects(&tab.document);
             let first_visible_index = visible_indices.first().copied();
 
-            for page_index_visible in visible_indices {
+            for &page_index_visible in &visible_indices {
                 if let Some(rect) = all_page_rects.get(page_index_visible).copied() {
                     canvas_page_rects.push(rect);
                 }
             }
 
             page_count = all_page_rects.len().max(1);
-            page_index = first_visible_index.map(|idx| idx + 1).unwrap_or(1);
+            // The page holding the cursor while it's on screen, otherwise the topmost one shown.
+            let cursor_page = find_block_index_by_id(&tab.document, tab.cursor.primary.block_id)
+                .and_then(|index| tab.canvas.block_layout.block_page(index))
+                .filter(|page| visible_indices.contains(page));
+            page_index = cursor_page
+                .or(first_visible_index)
+                .map(|idx| idx + 1)
+                .unwrap_or(1);
 
             canvas_show_margin_guides = tab.canvas.show_margin_guides;
             canvas_cursor_visible = tab.canvas.cursor.visible;
@@ -3765,18 +7440,135 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
             column = tab.cursor.primary.offset.saturating_add(1);
             line = 1;
             current_block = Some(tab.cursor.primary.block_id);
-            canvas_preview_lines = collect_preview_lines(&tab.document, 40);
+            breadcrumb_path = heading_path(&tab.document.content, tab.cursor.primary.block_id);
+            let preview = collect_preview_lines(&tab.document, &tab.folded, 40);
+            if state.find_replace.find_visible && !state.find_replace.results.is_empty() {
+                let visible_ids = collect_visible_block_ids_for_search(tab);
+                canvas_find_highlights_out =
+                    canvas_find_highlights(&preview, &state.find_replace, &visible_ids);
+            }
+            canvas_footnote_marks_out =
+                canvas_text_spans(&preview, |line| line.footnote_marks.clone());
+            canvas_text_highlights = canvas_tagged_spans(&preview, |line| line.highlights.clone())
+                .into_iter()
+                .map(|(span, color)| CanvasTextHighlight { span, color })
+                .collect();
+            canvas_superscripts = canvas_text_spans(&preview, |line| line.superscripts.clone());
+            canvas_subscripts = canvas_text_spans(&preview, |line| line.subscripts.clone());
+            if preview.iter().any(|line| !line.wiki_links.is_empty())
+                && let Some(root) = link_root
+            {
+                if wiki_pages_cache
+                    .as_ref()
+                    .is_none_or(|(cached, _)| *cached != root)
+                {
+                    *wiki_pages_cache = Some((root.clone(), wiki_pages(&root)));
+                }
+                let pages = wiki_pages_cache
+                    .as_ref()
+                    .map_or(&[][..], |(_, pages)| pages);
+                let links = |resolved: bool| {
+                    move |line: &PreviewLine| {
+                        line.wiki_links
+                            .iter()
+                            .filter(|(_, page)| find_wiki_page(pages, page).is_some() == resolved)
+                            .map(|(range, _)| range.clone())
+                            .collect()
+                    }
+                };
+                canvas_links = canvas_text_spans(&preview, links(true));
+                canvas_broken_links = canvas_text_spans(&preview, links(false));
+            }
+            // Cross-references draw like wiki links, broken once their anchor is gone.
+            if preview.iter().any(|line| !line.cross_references.is_empty()) {
+                let references = |resolved: bool| {
+                    move |line: &PreviewLine| {
+                        line.cross_references
+                            .iter()
+                            .filter(|(_, anchor)| {
+                                resolve_cross_reference(&tab.document, anchor).is_some() == resolved
+                            })
+                            .map(|(range, _)| range.clone())
+                            .collect()
+                    }
+                };
+                canvas_links.extend(canvas_text_spans(&preview, references(true)));
+                canvas_broken_links.extend(canvas_text_spans(&preview, references(false)));
+            }
+            let language_rtl = is_rtl_language(&language);
+            canvas_rtl_lines = preview
+                .iter()
+                .map(|line| match line.direction {
+                    Some(direction) => direction == TextDirection::Rtl,
+                    None => language_rtl,
+                })
+                .collect();
+            canvas_quote_depths = preview.iter().map(|line| line.quote_depth).collect();
+            let columns = tab.document.metadata.columns;
+            (canvas_columns, canvas_column_gap) =
+                (columns.count(), columns.spacing * tab.canvas.zoom);
+            canvas_line_frames = preview
+                .iter()
+                .map(|line| {
+                    line.frame
+                        .as_ref()
+                        .map(|frame| crate::render::d2d::CanvasLineFrame {
+                            border: frame.border.map(|border| (border.color, border.width)),
+                            shading: frame.shading,
+                            icon: frame.icon.clone(),
+                        })
+                })
+                .collect();
+            canvas_preview_lines = preview.into_iter().map(|line| line.text).collect();
             canvas_images = collect_canvas_image_overlays(tab, selected_image_id, image_cache);
             canvas_tables = collect_canvas_table_overlays(tab);
         }
     }
+    let mut text_view_caret = None;
+    if let Some(view) = state
+        .tabs
+        .active_tab()
+        .and_then(|tab| tab.text_view.as_ref())
+    {
+        // Streamed text has no blocks; only the lines on screen are read from the rope.
+        canvas_preview_lines = view.visible_lines(TEXT_VIEW_ROWS);
+        canvas_rtl_lines = vec![false; canvas_preview_lines.len()];
+        canvas_line_frames.clear();
+        canvas_quote_depths.clear();
+        let (cursor_line, cursor_column) = view.cursor;
+        (line, column) = (cursor_line + 1, cursor_column + 1);
+        character_count = view.document.char_count();
+        text_view_caret = cursor_line
+            .checked_sub(view.top_line)
+            .and_then(|row| Some((row, canvas_preview_lines.get(row)?)))
+            .map(|(row, text)| {
+                let utf16 = text
+                    .chars()
+                    .take(cursor_column)
+                    .map(char::len_utf16)
+                    .sum::<usize>();
+                (row, utf16 as u32)
+            });
+        canvas_cursor_visible = false;
+    }
     if active_is_welcome {
         canvas_preview_lines = welcome_preview_lines(state);
+        canvas_rtl_lines.clear();
+        canvas_line_frames.clear();
+        canvas_quote_depths.clear();
+        canvas_find_highlights_out.clear();
+        canvas_footnote_marks_out.clear();
+        canvas_text_highlights.clear();
+        canvas_superscripts.clear();
+        canvas_subscripts.clear();
+        canvas_links.clear();
+        canvas_broken_links.clear();
         canvas_cursor_visible = false;
         canvas_images.clear();
         canvas_tables.clear();
     }
     state.sidebar.set_current_outline_block(current_block);
+    state.breadcrumb.set_path(&breadcrumb_path);
     state.canvas_image_overlays = canvas_images.clone();
     state.canvas_table_overlays = canvas_tables.clone();
     if let Some(renderer) = &mut state.renderer {
@@ -3793,6 +7585,9 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         column,
         zoom_percent,
         file_format: file_format.clone(),
+        read_only: active_read_only,
+        language: language.clone(),
+        save_indicator,
         ..StatusBarInfo::default()
     });
 
@@ -3820,6 +7615,7 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
     let command_palette_query = state.command_palette.query.clone();
     let command_palette_selected = state.command_palette.selected;
     let command_palette_results = state.command_palette.result_labels(8);
+    let canvas_background = canvas_background_settings(state);
     let toast_entries = state
         .toast
         .entries
@@ -3842,6 +7638,7 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         .collect::<Vec<_>>();
     let settings_rows = state.settings_dialog.setting_rows();
     let settings_selected_row = state.settings_dialog.selected_setting_row();
+    let settings_focus = state.settings_dialog.focus();
     let settings_conflicts = state.settings_dialog.has_conflicting_shortcuts();
     let settings_save_error = state
         .settings_dialog
@@ -3856,6 +7653,8 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
     let find_case_sensitive = state.find_replace.options.case_sensitive;
     let find_whole_word = state.find_replace.options.whole_word;
     let find_regex = state.find_replace.options.regex;
+    let find_in_selection = state.find_replace.in_selection;
+    let find_regex_error = state.find_replace.regex_error().is_some();
     let find_total = state.find_replace.results.len();
     let find_current = if find_total == 0 {
         0
@@ -3962,6 +7761,12 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         tab_transition_offset: state.tabs.transition_slide_offset(),
         tab_has_overflow_left: state.tabs.overflow_offset > 0,
         tab_has_overflow_right: state.tabs.overflow_offset + state.tabs.tab_rects.len() < state.tabs.tabs.len(),
+        tab_count: state.tabs.tabs.len(),
+        region_focus_ring: FocusRing {
+            visible: state.focus_visible,
+            bounds: focus_ring_bounds(state).unwrap_or_default(),
+            thickness: 2.0,
+        },
         toolbar_buttons: state
             .toolbar
             .buttons
@@ -3983,6 +7788,122 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         toolbar_dropdown_open: state.toolbar.dropdown.open.is_some(),
         toolbar_dropdown_opacity: state.toolbar.dropdown.opacity,
         toolbar_dropdown_scale: state.toolbar.dropdown.scale,
+        toolbar_dropdown_rect: state.toolbar.dropdown_panel_rect().unwrap_or_default(),
+        toolbar_dropdown_swatches: state
+            .toolbar
+            .dropdown_swatches()
+            .into_iter()
+            .map(|(rect, color)| crate::render::d2d::ToolbarSwatchShellItem {
+                rect,
+                color,
+                selected: state.toolbar.format_state.text_color == Some(color),
+            })
+            .collect(),
+        toolbar_dropdown_input: state.toolbar.dropdown_input_text(),
+        toolbar_dropdown_rows: state
+            .toolbar
+            .dropdown_rows()
+            .into_iter()
+            .map(|(rect, label)| {
+                let format = &state.toolbar.format_state;
+                let selected = label.eq_ignore_ascii_case(&format.font_family)
+                    || (state.toolbar.dropdown.open == Some(ToolbarDropdownKind::FontSize)
+                        && label.parse::<f32>().ok() == Some(format.font_size));
+                crate::render::d2d::ToolbarDropdownRowShellItem { rect, label, selected }
+            })
+            .collect(),
+        document_properties_visible: state.document_properties.is_open(),
+        document_properties_panel: state.document_properties.panel_rect(),
+        document_properties_fields: state.document_properties.fields(),
+        zoom_popup_visible: state.zoom_popup.is_open(),
+        zoom_popup_panel: state.zoom_popup.bounds(),
+        zoom_popup_buttons: state
+            .zoom_popup
+            .button_items()
+            .into_iter()
+            .map(|(rect, label, selected)| crate::render::d2d::ToolbarDropdownRowShellItem {
+                rect,
+                label,
+                selected,
+            })
+            .collect(),
+        zoom_popup_track: state.zoom_popup.slider_track(),
+        zoom_popup_slider: state.zoom_popup.slider_position(),
+        stats_popup_visible: state.stats_popup.is_open(),
+        stats_popup_panel: state.stats_popup.bounds(),
+        stats_popup_cells: state
+            .stats_popup
+            .cells()
+            .into_iter()
+            .map(|(rect, text, muted)| crate::render::d2d::StatsCellShellItem { rect, text, muted })
+            .collect(),
+        toolbar_customizer_visible: state.toolbar_customizer.is_open(),
+        toolbar_customizer_panel: state.toolbar_customizer.panel_rect(),
+        toolbar_customizer_rows: state
+            .toolbar_customizer
+            .visible_rows()
+            .into_iter()
+            .map(|(rect, label, selected)| crate::render::d2d::ToolbarDropdownRowShellItem {
+                rect,
+                label,
+                selected,
+            })
+            .collect(),
+        breadcrumb_crumbs: if state.breadcrumb.is_shown() {
+            state
+                .breadcrumb
+                .crumb_rects()
+                .into_iter()
+                .map(|(rect, crumb)| crate::render::d2d::BreadcrumbShellItem {
+                    rect,
+                    label: crumb.label.clone(),
+                })
+                .collect()
+        } else {
+            Vec::new()
+        },
+        breadcrumb_bar: state.breadcrumb.bounds(),
+        goto_symbol_visible: state.goto_symbol.is_open(),
+        goto_symbol_panel: state.goto_symbol.panel_rect(),
+        goto_symbol_query: state.goto_symbol.query().to_string(),
+        goto_symbol_rows: state
+            .goto_symbol
+            .visible_rows()
+            .into_iter()
+            .map(|(rect, label, selected)| crate::render::d2d::ToolbarDropdownRowShellItem {
+                rect,
+                label,
+                selected,
+            })
+            .collect(),
+        licenses_visible: state.licenses_view.is_open(),
+        licenses_panel: state.licenses_view.panel_rect(),
+        licenses_query: state.licenses_view.query().to_string(),
+        licenses_rows: state
+            .licenses_view
+            .visible_rows()
+            .into_iter()
+            .map(|(rect, label, selected)| crate::render::d2d::ToolbarDropdownRowShellItem {
+                rect,
+                label,
+                selected,
+            })
+            .collect(),
+        licenses_summary: state.licenses_view.summary(),
+        shortcut_editor_visible: state.shortcut_editor.is_open(),
+        shortcut_editor_panel: state.shortcut_editor.panel_rect(),
+        shortcut_editor_title: state.shortcut_editor.title(),
+        shortcut_editor_rows: state
+            .shortcut_editor
+            .visible_rows()
+            .into_iter()
+            .map(|(rect, label, selected)| crate::render::d2d::ToolbarDropdownRowShellItem {
+                rect,
+                label,
+                selected,
+            })
+            .collect(),
+        shortcut_editor_hint: state.shortcut_editor.hint(),
         active_sidebar_panel: active_sidebar_panel.to_string(),
         sidebar_summary,
         sidebar_rows,
@@ -3998,6 +7919,7 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         settings_categories,
         settings_rows,
         settings_selected_row,
+        settings_focus,
         settings_conflicts,
         settings_save_error,
         table_picker_visible: state.table_picker_visible,
@@ -4014,6 +7936,8 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         find_case_sensitive,
         find_whole_word,
         find_regex,
+        find_in_selection,
+        find_regex_error,
         find_preview,
         find_current,
         find_total,
@@ -4022,13 +7946,31 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
         goto_input: state.goto_input.clone(),
         status_left: state.statusbar.left_text(),
         status_right: state.statusbar.right_text(),
-        canvas_background: from_canvas_preference(
-            &state.app_state.settings.appearance.canvas_background,
-        ),
+        canvas_page: page_colors(&state.theme, &canvas_background),
+        canvas_background,
         canvas_page_rects,
         canvas_preview_lines,
+        canvas_rtl_lines,
+        canvas_line_frames,
+        canvas_quote_depths,
+        canvas_columns,
+        canvas_column_gap,
+        canvas_language: language,
+        canvas_find_highlights: canvas_find_highlights_out,
+        canvas_footnote_marks: canvas_footnote_marks_out,
+        canvas_text_highlights,
+        canvas_superscripts,
+        canvas_subscripts,
+        canvas_links,
+        canvas_broken_links,
         canvas_show_margin_guides,
         canvas_cursor_visible,
+        canvas_drop_caret: state
+            .text_drag
+            .as_ref()
+            .and_then(|drag| drag.target)
+            .map(|target| (target.line, target.utf16))
+            .or(text_view_caret),
         canvas_scrollbar_visible,
         canvas_scrollbar_alpha,
         canvas_viewport_width,
@@ -4103,6 +8045,12 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
                     cell_h: overlay.cell_h,
                     header_h: overlay.header_h,
                     gutter_w: overlay.gutter_w,
+                    repeated_header_rows: overlay.repeated_header_rows.clone(),
+                    row_fills: overlay.row_fills.clone(),
+                    scroll_x: overlay.scroll_x,
+                    scroll_y: overlay.scroll_y,
+                    freeze_first_column: overlay.freeze_first_column,
+                    freeze_header_row: overlay.freeze_header_row,
                     selected,
                     selection_mode: mode,
                     selection_start_row: start_row,
@@ -4120,6 +8068,168 @@ fn build_shell_render_state(state: &mut WindowState) -> ShellRenderState {
     }
 }
 
+/// Runs what a click or Enter on a status bar segment asks for; returns the status text.
+fn apply_status_action(state: &mut WindowState, hwnd: HWND, action: StatusAction) -> String {
+    match action {
+        StatusAction::OpenZoomPopup => {
+            open_zoom_popup(state);
+            "Zoom control opened".to_string()
+        }
+        StatusAction::OpenStatsPopup => open_stats_popup(state),
+        StatusAction::ChangeEncoding => "Encoding picker requested".to_string(),
+        StatusAction::ChangeLanguage => show_language_menu(state, hwnd),
+        StatusAction::EditAnyway => set_active_tab_read_only(state, false),
+    }
+}
+
+/// Moves keyboard focus to the next shown shell region, or the previous one for Shift+F6.
+fn cycle_focus_region(state: &mut WindowState, backwards: bool) {
+    let app = &state.app_state;
+    state.focus_region = state.focus_region.cycle(backwards, |region| match region {
+        FocusRegion::Document => true,
+        FocusRegion::Sidebar => app.show_sidebar,
+        FocusRegion::Toolbar => app.show_toolbar,
+        FocusRegion::Tabs => app.show_tabs,
+        FocusRegion::StatusBar => app.show_statusbar,
+    });
+    state.focus_item = match state.focus_region {
+        FocusRegion::Toolbar => step_toolbar_focus(&state.toolbar, None, true),
+        _ => 0,
+    };
+    state.focus_visible = true;
+    state.app_state.status_text = format!(
+        "Focus: {} (arrows to move, Enter to choose, Esc to return)",
+        state.focus_region.label()
+    );
+}
+
+/// The next toolbar button keyboard focus can land on after `from`, or before it when not
+/// `forward`; separators and disabled buttons are passed over. Stays put at either end.
+fn step_toolbar_focus(toolbar: &Toolbar, from: Option<usize>, forward: bool) -> usize {
+    let focusable = |index: &usize| {
+        toolbar.button_rect(*index).is_some()
+            && toolbar
+                .buttons
+                .get(*index)
+                .is_some_and(|button| button.enabled && button.kind != ToolbarButtonType::Separator)
+    };
+    let count = toolbar.buttons.len();
+    let next = match (from, forward) {
+        (None, _) => (0..count).find(focusable),
+        (Some(from), true) => (from + 1..count).find(focusable),
+        (Some(from), false) => (0..from).rev().find(focusable),
+    };
+    next.or(from).unwrap_or(0)
+}
+
+/// Arrow keys and Enter for the shell region holding keyboard focus; Esc hands focus back
+/// to the document. False for keys the region leaves to the usual handling.
+fn handle_focused_region_key(state: &mut WindowState, hwnd: HWND, vk: u32) -> bool {
+    let forward = vk == 0x27;
+    match (state.focus_region, vk) {
+        (FocusRegion::Toolbar, 0x1B) if state.toolbar.dropdown.open.is_some() => {
+            state.toolbar.dropdown.close();
+        }
+        (_, 0x1B) => {
+            state.focus_region = FocusRegion::Document;
+            state.focus_visible = false;
+            state.app_state.status_text = "Focus: Document".to_string();
+        }
+        (FocusRegion::Sidebar, 0x25 | 0x27) => {
+            let panels = SidebarPanel::all();
+            let current = panels
+                .iter()
+                .position(|panel| *panel == state.sidebar.active_panel)
+                .unwrap_or(0);
+            let step = if forward { 1 } else { panels.len() - 1 };
+            let panel = panels[(current + step) % panels.len()];
+            state.sidebar.set_active_panel(panel);
+            remember_sidebar_layout(state);
+            state.app_state.status_text = format!("Sidebar panel: {}", panel.title());
+        }
+        (FocusRegion::Sidebar, 0x26 | 0x28 | 0x0D) => {
+            let _ = state.sidebar.handle_input(&UiInputEvent::KeyDown(vk));
+            let _ = apply_pending_sidebar_intents(state);
+        }
+        (FocusRegion::Toolbar, 0x25 | 0x27) => {
+            state.focus_item = step_toolbar_focus(&state.toolbar, Some(state.focus_item), forward);
+            if let Some(button) = state.toolbar.buttons.get(state.focus_item) {
+                state.app_state.status_text = button.tooltip.clone();
+            }
+        }
+        (FocusRegion::Toolbar, 0x0D) => {
+            let Some(rect) = state.toolbar.button_rect(state.focus_item) else {
+                return true;
+            };
+            let center = UiPoint {
+                x: rect.x + rect.width * 0.5,
+                y: rect.y + rect.height * 0.5,
+            };
+            if let Some(intent) = state.toolbar.invoke_with_point(center) {
+                state.toolbar.pending_intent = None;
+                let _ = apply_toolbar_intent(state, hwnd, intent);
+            }
+        }
+        (FocusRegion::Tabs, 0x25 | 0x27) => {
+            let count = state.tabs.tabs.len();
+            if count > 0 {
+                let step = if forward { 1 } else { count - 1 };
+                state.tabs.set_active((state.tabs.active + step) % count);
+                sync_sidebar_with_active_tab(state);
+                if let Some(tab) = state.tabs.active_tab() {
+                    state.app_state.status_text = format!("Switched to {}", tab.title);
+                }
+            }
+        }
+        (FocusRegion::Tabs, 0x0D) => {
+            state.app_state.status_text = show_tab_list_menu(state, hwnd);
+        }
+        (FocusRegion::StatusBar, 0x25 | 0x27) => {
+            let last = state.statusbar.focus_segments().len().saturating_sub(1);
+            state.focus_item = if forward {
+                (state.focus_item + 1).min(last)
+            } else {
+                state.focus_item.saturating_sub(1).min(last)
+            };
+        }
+        (FocusRegion::StatusBar, 0x0D) => {
+            let segments = state.statusbar.focus_segments();
+            if let Some((_, action)) = segments.get(state.focus_item) {
+                state.app_state.status_text = apply_status_action(state, hwnd, *action);
+            }
+        }
+        _ => return false,
+    }
+    true
+}
+
+/// What the focus ring outlines in the region holding keyboard focus.
+fn focus_ring_bounds(state: &WindowState) -> Option<UiRect> {
+    match state.focus_region {
+        FocusRegion::Document => {
+            let origin = canvas_origin(state);
+            state.tabs.active_tab().map(|tab| UiRect {
+                x: origin.x,
+                y: origin.y,
+                width: tab.canvas.viewport.width,
+                height: tab.canvas.viewport.height,
+            })
+        }
+        FocusRegion::Sidebar => Some(state.sidebar.bounds()),
+        FocusRegion::Toolbar => state.toolbar.button_rect(state.focus_item),
+        FocusRegion::Tabs => state
+            .tabs
+            .active
+            .checked_sub(state.tabs.overflow_offset)
+            .and_then(|index| state.tabs.tab_rects.get(index).copied()),
+        FocusRegion::StatusBar => state
+            .statusbar
+            .focus_segments()
+            .get(state.focus_item)
+            .map(|(rect, _)| *rect),
+    }
+}
+
 fn toolbar_action_text(action: ToolbarAction) -> &'static str {
     match action {
         ToolbarAction::FileMenu => "File menu",
@@ -4149,12 +8259,51 @@ fn toolbar_action_text(action: ToolbarAction) -> &'static str {
     }
 }
 
+/// Requests the next animation frame, paced to the display refresh so idle animation
+/// loops don't render faster than the monitor can show. Input invalidates directly.
+fn schedule_animation_frame(state: &mut WindowState, hwnd: HWND) {
+    match state.compositor.next_frame_delay(Instant::now()) {
+        None => {
+            let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+        }
+        Some(delay) => {
+            if !state.compositor.timer_armed {
+                let millis = delay.as_millis().clamp(1, u32::MAX as u128) as u32;
+                let _ = unsafe { SetTimer(Some(hwnd), FRAME_TIMER_ID, millis, None) };
+                state.compositor.timer_armed = true;
+            }
+        }
+    }
+}
+
+fn is_input_message(message: u32) -> bool {
+    matches!(
+        message,
+        WM_KEYDOWN
+            | WM_CHAR
+            | WM_MOUSEWHEEL
+            | WM_MOUSEMOVE
+            | WM_LBUTTONDOWN
+            | WM_LBUTTONUP
+            | WM_LBUTTONDBLCLK
+            | WM_MBUTTONDOWN
+            | WM_MBUTTONUP
+            | WM_RBUTTONUP
+    )
+}
+
 unsafe extern "system" fn window_proc(
     hwnd: HWND,
     message: u32,
     wparam: WPARAM,
     lparam: LPARAM,
 ) -> LRESULT {
+    if is_input_message(message)
+        && let Some(state) = unsafe { state_from_hwnd(hwnd) }
+    {
+        state.last_input = Instant::now();
+    }
+
     match message {
         WM_NCCREATE => {
             let create_struct = unsafe { &*(lparam.0 as *const CREATESTRUCTW) };
@@ -4172,6 +8321,11 @@ unsafe extern "system" fn window_proc(
             let _ = unsafe { GetClientRect(hwnd, &mut client) };
 
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                // Once registered, file drops come through the drop target as well;
+                // WM_DROPFILES only sees them if registration failed.
+                if let Err(error) = state.drop_targets.register(hwnd, WM_EXTERNAL_DROP) {
+                    eprintln!("Drop target registration failed: {error:?}");
+                }
                 state.accessibility = query_accessibility_preferences();
                 let _ = sync_theme_from_settings(state);
                 apply_accessibility_preferences(state);
@@ -4179,11 +8333,14 @@ unsafe extern "system" fn window_proc(
                 let width = (client.right - client.left).max(1) as u32;
                 let height = (client.bottom - client.top).max(1) as u32;
 
-                match D2DRenderer::new(hwnd, width, height, state.dpi, state.theme.clone()) {
-                    Ok(renderer) => state.renderer = Some(renderer),
-                    Err(error) => {
-                        eprintln!("Renderer initialization failed: {error:?}");
-                    }
+                create_renderer(state, hwnd, width, height);
+                if let Some(hz) = query_refresh_rate() {
+                    state.compositor.set_refresh_rate(hz);
+                }
+                match crate::render::dwrite::system_font_families() {
+                    Ok(fonts) if !fonts.is_empty() => state.toolbar.set_font_catalog(fonts),
+                    Ok(_) => {}
+                    Err(error) => eprintln!("Font enumeration failed: {error:?}"),
                 }
                 relayout_shell(state, width as f32, height as f32);
 
@@ -4196,21 +8353,83 @@ unsafe extern "system" fn window_proc(
                     opened_any = true;
                 }
 
-                let recovered = restore_recovery_tabs(state);
+                let recovered = if state.primary_window {
+                    restore_recovery_tabs(state)
+                } else {
+                    0
+                };
                 if recovered > 0 {
                     state.app_state.status_text =
                         format!("Recovered {} unsaved document(s)", recovered);
                     opened_any = true;
                 }
 
+                if state.startup_task.is_some() {
+                    // Run after creation finishes so the file picker has a window to own it.
+                    let _ = unsafe {
+                        PostMessageW(Some(hwnd), WM_RUN_STARTUP_TASK, WPARAM(0), LPARAM(0))
+                    };
+                    opened_any = true;
+                }
+
                 if !opened_any {
                     state.app_state.status_text = "Welcome to Doco".to_string();
                 }
-
+
+                if state.primary_window {
+                    let about = &state.settings_dialog.settings().about;
+                    // A release found before this build was installed isn't news anymore.
+                    if about
+                        .latest_version
+                        .as_deref()
+                        .is_some_and(|latest| !is_newer(latest, env!("CARGO_PKG_VERSION")))
+                    {
+                        state.settings_dialog.apply_change(|settings| {
+                            settings.about.latest_version = None;
+                            settings.about.latest_release_url = None;
+                        });
+                    }
+                    if state
+                        .settings_dialog
+                        .settings()
+                        .about
+                        .check_updates_on_startup
+                    {
+                        start_update_check(state, false);
+                    }
+                }
+
+                sync_sidebar_with_active_tab(state);
+            }
+            emit_startup_marker("window_create", create_begin.elapsed().as_secs_f64() * 1000.0);
+
+            LRESULT(0)
+        }
+        WM_RUN_STARTUP_TASK => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                match state.startup_task.take() {
+                    Some(JumpListTask::NewDocument) => {
+                        state.tabs.new_blank_tab();
+                        state.app_state.status_text = "New document".to_string();
+                    }
+                    Some(JumpListTask::OpenFile) => {
+                        let _ = open_file_via_picker(state, hwnd, true);
+                    }
+                    None => {}
+                }
+                sync_sidebar_with_active_tab(state);
+                let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+            }
+            LRESULT(0)
+        }
+        WM_EXTERNAL_DROP => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                for dropped in state.drop_targets.take_drops() {
+                    handle_external_drop(state, dropped);
+                }
                 sync_sidebar_with_active_tab(state);
+                let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
             }
-            emit_startup_marker("window_create", create_begin.elapsed().as_secs_f64() * 1000.0);
-
             LRESULT(0)
         }
         WM_SIZE => {
@@ -4221,10 +8440,28 @@ unsafe extern "system" fn window_proc(
                     let _ = renderer.resize(width, height);
                 }
                 relayout_shell(state, width as f32, height as f32);
+                // Dragging a border ends in WM_EXITSIZEMOVE; maximizing and restoring don't.
+                let was_maximized = state
+                    .settings_dialog
+                    .settings()
+                    .appearance
+                    .window
+                    .is_some_and(|saved| saved.maximized);
+                if wparam.0 as u32 == SIZE_MAXIMIZED
+                    || (wparam.0 as u32 == SIZE_RESTORED && was_maximized)
+                {
+                    remember_window_placement(state, hwnd);
+                }
             }
 
             LRESULT(0)
         }
+        WM_EXITSIZEMOVE => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                remember_window_placement(state, hwnd);
+            }
+            LRESULT(0)
+        }
         WM_DPICHANGED => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 state.dpi = (wparam.0 as u32 & 0xFFFF) as f32;
@@ -4248,6 +8485,10 @@ unsafe extern "system" fn window_proc(
                 if let Some(renderer) = &mut state.renderer {
                     renderer.set_dpi(state.dpi);
                 }
+                // A DPI change usually means the window moved to another monitor.
+                if let Some(hz) = query_refresh_rate() {
+                    state.compositor.set_refresh_rate(hz);
+                }
 
                 let mut client = RECT::default();
                 let _ = unsafe { GetClientRect(hwnd, &mut client) };
@@ -4301,17 +8542,40 @@ unsafe extern "system" fn window_proc(
                         .set_visible(state.app_state.show_settings);
                 }
                 state.settings_dialog.tick();
+                if state.shortcut_dispatcher.expire(Instant::now()) {
+                    state.app_state.status_text = "Shortcut chord cancelled".to_string();
+                }
                 sync_runtime_from_settings(state, hwnd);
                 state.app_state.show_settings = state.settings_dialog.is_open();
+                if let Some(tab) = state.tabs.active_tab()
+                    && tab.kind == TabKind::Document
+                {
+                    state
+                        .canvas_accessibility
+                        .sync(&tab.title, tab.id, &tab.document, tab.cursor.primary);
+                }
                 let mut needs_next_frame = false;
-                needs_next_frame |= tabs_animating;
-                needs_next_frame |= !state.toast.entries.is_empty();
+                let mut repaint_reasons = Vec::new();
+                if tabs_animating {
+                    needs_next_frame = true;
+                    repaint_reasons.push("tabs");
+                }
+                if !state.toast.entries.is_empty() {
+                    needs_next_frame = true;
+                    repaint_reasons.push("toast");
+                }
+                if state.shortcut_dispatcher.pending_prefix().is_some() {
+                    // Keep ticking so an abandoned chord times out without another key.
+                    needs_next_frame = true;
+                    repaint_reasons.push("chord");
+                }
                 if state.command_palette.is_open()
                     && !state.accessibility.reduce_motion
                     && (state.command_palette.opacity() < 0.999
                         || state.command_palette.slide_offset().abs() > 0.05)
                 {
                     needs_next_frame = true;
+                    repaint_reasons.push("palette");
                 }
                 if state.toolbar.dropdown.open.is_some()
                     && !state.accessibility.reduce_motion
@@ -4319,11 +8583,18 @@ unsafe extern "system" fn window_proc(
                         || (1.0 - state.toolbar.dropdown.scale).abs() > 0.01)
                 {
                     needs_next_frame = true;
+                    repaint_reasons.push("dropdown");
                 }
                 if let Some(tab) = state.tabs.active_tab_mut() {
-                    needs_next_frame |= tab.canvas.update(dt);
+                    if tab.canvas.update(dt) {
+                        needs_next_frame = true;
+                        repaint_reasons.push("canvas");
+                    }
                     tab.canvas.clamp_scroll(&tab.document);
-                    if let Ok(Some(path)) = state.app_state.autosave.tick(&tab.document) {
+                    // Recovery snapshots hold blocks, which a streamed text file has none of.
+                    if tab.text_view.is_none()
+                        && let Ok(Some(path)) = state.app_state.autosave.tick(&tab.document)
+                    {
                         state.app_state.status_text =
                             format!("Auto-saved recovery snapshot: {}", path.display());
                         state
@@ -4335,22 +8606,38 @@ unsafe extern "system" fn window_proc(
                         );
                     }
                 }
+                if state.selection_drag {
+                    extend_selection_to_view_edge(state);
+                }
                 if state.find_replace.should_live_update(now) {
                     let refreshed = refresh_find_results(state);
-                    needs_next_frame |=
-                        refreshed || state.find_replace.has_pending_background_search();
+                    if refreshed || state.find_replace.has_pending_background_search() {
+                        needs_next_frame = true;
+                        repaint_reasons.push("find");
+                    }
+                }
+                if !state.document_loads.is_empty() {
+                    let _ = process_document_loads(state);
+                    needs_next_frame = true;
+                    repaint_reasons.push("loading");
+                }
+                if state.update_check.is_some() {
+                    process_update_check(state);
+                    needs_next_frame = true;
+                    repaint_reasons.push("updates");
                 }
                 if state.find_replace.has_pending_background_search() {
                     let chunk_changed = process_find_background_search(state, 256);
                     needs_next_frame = true;
+                    repaint_reasons.push("search");
                     if chunk_changed {
                         state.app_state.status_text = state.find_replace.result_count_text.clone();
                     }
                 }
-                let background =
-                    from_canvas_preference(&state.app_state.settings.appearance.canvas_background);
+                let background = canvas_background_settings(state);
                 if matches!(background.kind, BackgroundKind::AnimatedGradient { .. }) {
                     needs_next_frame = true;
+                    repaint_reasons.push("background");
                 }
 
                 if !state.startup_files.is_empty() {
@@ -4358,6 +8645,7 @@ unsafe extern "system" fn window_proc(
                     if process_startup_file_queue(state) {
                         sync_sidebar_with_active_tab(state);
                         needs_next_frame = true;
+                        repaint_reasons.push("startup");
                         emit_startup_marker(
                             "startup_file_open",
                             startup_chunk_begin.elapsed().as_secs_f64() * 1000.0,
@@ -4366,12 +8654,18 @@ unsafe extern "system" fn window_proc(
                 }
 
                 let shell = build_shell_render_state(state);
+                let frame_start = state.compositor.begin_frame();
                 if let Some(renderer) = &mut state.renderer {
+                    renderer.update_frame_context(
+                        now.saturating_duration_since(state.last_input),
+                        repaint_reasons,
+                    );
                     let _ = renderer.render(&shell);
                 }
+                let _ = state.compositor.end_frame(frame_start);
 
                 if needs_next_frame {
-                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    schedule_animation_frame(state, hwnd);
                 }
             }
 
@@ -4383,6 +8677,12 @@ unsafe extern "system" fn window_proc(
                 let ctrl_down = unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0;
                 let shift_down = unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0;
                 let delta = ((wparam.0 >> 16) as i16 as f32) / 120.0;
+                let scroll = state.app_state.settings.editor.scroll;
+                let wheel = WheelBehavior {
+                    scroll_speed: scroll.speed,
+                    natural: scroll.natural,
+                    zoom_sensitivity: scroll.zoom_sensitivity,
+                };
 
                 let mut client = RECT::default();
                 let _ = unsafe { GetClientRect(hwnd, &mut client) };
@@ -4394,6 +8694,62 @@ unsafe extern "system" fn window_proc(
                     y: canvas_h * 0.5,
                 };
 
+                if state.toolbar.dropdown_accepts_text() {
+                    let mut screen = POINT {
+                        x: (lparam.0 as u32 & 0xFFFF) as i16 as i32,
+                        y: ((lparam.0 as u32 >> 16) & 0xFFFF) as i16 as i32,
+                    };
+                    let _ = unsafe { ScreenToClient(hwnd, &mut screen) };
+                    let event = UiInputEvent::MouseWheel {
+                        delta,
+                        position: UiPoint {
+                            x: screen.x as f32,
+                            y: screen.y as f32,
+                        },
+                    };
+                    if state.toolbar.handle_input(&event) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if state.toolbar_customizer.is_open() {
+                    let event = UiInputEvent::MouseWheel {
+                        delta,
+                        position: cursor_in_canvas,
+                    };
+                    let _ = state.toolbar_customizer.handle_input(&event);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.goto_symbol.is_open() {
+                    let event = UiInputEvent::MouseWheel {
+                        delta,
+                        position: cursor_in_canvas,
+                    };
+                    let _ = state.goto_symbol.handle_input(&event);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.licenses_view.is_open() {
+                    let event = UiInputEvent::MouseWheel {
+                        delta,
+                        position: cursor_in_canvas,
+                    };
+                    let _ = state.licenses_view.handle_input(&event);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.shortcut_editor.is_open() {
+                    let event = UiInputEvent::MouseWheel {
+                        delta,
+                        position: cursor_in_canvas,
+                    };
+                    let _ = state.shortcut_editor.handle_input(&event);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
                 if state.settings_dialog.is_open() && !state.command_palette.is_open() {
                     let event = UiInputEvent::MouseWheel {
                         delta,
@@ -4405,8 +8761,42 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
+                let mut screen = POINT {
+                    x: (lparam.0 as u32 & 0xFFFF) as i16 as i32,
+                    y: ((lparam.0 as u32 >> 16) & 0xFFFF) as i16 as i32,
+                };
+                let _ = unsafe { ScreenToClient(hwnd, &mut screen) };
+                let wheel_point = UiPoint {
+                    x: screen.x as f32,
+                    y: screen.y as f32,
+                };
+                if state.app_state.show_tabs && state.tabs.wheel_scrolls_tabs(wheel_point) {
+                    if state.tabs.scroll_overflow_by_wheel(delta) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    }
+                    return LRESULT(0);
+                }
+
+                if shift_down && !ctrl_down && scroll_table_at(state, wheel_point, delta) {
+                    state.app_state.status_text = "Table scrolled".to_string();
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if !ctrl_down
+                    && let Some(view) = state
+                        .tabs
+                        .active_tab_mut()
+                        .and_then(|tab| tab.text_view.as_mut())
+                {
+                    view.scroll_by(wheel.scroll_delta(delta).round() as isize);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
                 if let Some(tab) = state.tabs.active_tab_mut() {
                     tab.canvas.set_viewport(canvas_w, canvas_h);
+                    tab.canvas.wheel = wheel;
                     if ctrl_down {
                         tab.canvas.handle_mouse_wheel(delta, true, cursor_in_canvas);
                         state.app_state.status_text =
@@ -4427,13 +8817,83 @@ unsafe extern "system" fn window_proc(
             }
             unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
         }
+        WM_SYSKEYDOWN => {
+            // Alt combinations come here instead of WM_KEYDOWN. Bound ones and keys being
+            // recorded as a binding run as usual; the rest, like Alt+F4, keep the defaults.
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                let modifiers = KeyModifiers {
+                    ctrl: unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0,
+                    shift: unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0,
+                    alt: unsafe { GetKeyState(VK_MENU.0 as i32) } < 0,
+                };
+                let vk = wparam.0 as u32;
+                if state.shortcut_editor.is_capturing()
+                    || state.shortcut_dispatcher.claims(vk, modifiers)
+                {
+                    state.sys_key_handled = true;
+                    return unsafe { window_proc(hwnd, WM_KEYDOWN, wparam, lparam) };
+                }
+            }
+            unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+        }
+        WM_SYSCHAR => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) }
+                && std::mem::take(&mut state.sys_key_handled)
+            {
+                return LRESULT(0);
+            }
+            unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+        }
         WM_KEYDOWN => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let ctrl_down = unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0;
                 let shift_down = unsafe { GetKeyState(VK_SHIFT.0 as i32) } < 0;
+                let alt_down = unsafe { GetKeyState(VK_MENU.0 as i32) } < 0;
                 let vk = wparam.0 as u32;
 
-                if ctrl_down && shift_down && vk == 0x44 {
+                // Ahead of the global shortcuts so that any keys can be recorded as a binding.
+                if state.shortcut_editor.is_open() {
+                    if state.shortcut_editor.is_capturing() {
+                        let _ = state
+                            .shortcut_editor
+                            .capture_key(vk, ctrl_down, shift_down, alt_down);
+                    } else {
+                        let _ = state
+                            .shortcut_editor
+                            .handle_input(&UiInputEvent::KeyDown(vk));
+                    }
+                    apply_shortcut_edits(state, hwnd);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                let modifiers = KeyModifiers {
+                    ctrl: ctrl_down,
+                    shift: shift_down,
+                    alt: alt_down,
+                };
+                let command = match state
+                    .shortcut_dispatcher
+                    .press(vk, modifiers, Instant::now())
+                {
+                    KeyDispatch::Command(command) => Some(command),
+                    KeyDispatch::Pending(prefix) => {
+                        state.app_state.status_text =
+                            format!("({prefix}) was pressed. Waiting for the second key...");
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    KeyDispatch::Cancelled { prefix, key } => {
+                        state.app_state.status_text =
+                            format!("({prefix}, {key}) is not a shortcut");
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    KeyDispatch::Unbound => None,
+                };
+                let command = command.as_deref();
+
+                if command == Some("view.debug_panel") {
                     state.debug_panel_visible = !state.debug_panel_visible;
                     if let Some(renderer) = &mut state.renderer {
                         renderer.set_debug_panel_visible(state.debug_panel_visible);
@@ -4448,7 +8908,17 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if (ctrl_down && shift_down && vk == 0x50) || vk == 0x70 {
+                if state.toolbar.dropdown_accepts_text()
+                    && state.toolbar.handle_input(&UiInputEvent::KeyDown(vk))
+                {
+                    if let Some(selection) = state.toolbar.pending_selection.take() {
+                        let _ = apply_dropdown_selection(state, selection);
+                    }
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if command == Some("view.command_palette") || (command.is_none() && vk == 0x70) {
                     state.command_palette.open();
                     state
                         .command_palette
@@ -4490,13 +8960,19 @@ unsafe extern "system" fn window_proc(
                         } else if handled && state.app_state.status_text == "Find" {
                             state.find_replace.open_find();
                             state.find_focus = FindFieldFocus::Query;
+                            state.focus_region = FocusRegion::Document;
+                            capture_find_selection(state);
                             refresh_find_results(state);
                         } else if handled && state.app_state.status_text == "Replace" {
                             state.find_replace.open_replace();
                             state.find_focus = FindFieldFocus::Replacement;
+                            state.focus_region = FocusRegion::Document;
+                            capture_find_selection(state);
                             refresh_find_results(state);
+                        } else if handled && state.app_state.status_text == "Replace in all tabs" {
+                            let _ = replace_in_all_open_tabs(state, hwnd);
                         } else if handled && state.app_state.status_text == "Insert image" {
-                            if let Some(path) = pick_image_file(hwnd) {
+                            if let Some(path) = pick_image_via_dialog(state, hwnd) {
                                 match insert_image_from_path(state, &path) {
                                     Ok(_) => {
                                         state.app_state.status_text = format!(
@@ -4514,6 +8990,111 @@ unsafe extern "system" fn window_proc(
                             } else {
                                 state.app_state.status_text = "Insert image cancelled".to_string();
                             }
+                        } else if handled
+                            && state.app_state.status_text == "Restore original images"
+                        {
+                            state.app_state.status_text = restore_original_images(state);
+                        } else if handled && state.app_state.status_text == "Save image as" {
+                            state.app_state.status_text = save_selected_image_as(state, hwnd);
+                        } else if handled && state.app_state.status_text == "Customize toolbar" {
+                            open_toolbar_customizer(state);
+                        } else if handled && state.app_state.status_text == "Keyboard shortcuts" {
+                            open_shortcut_editor(state);
+                        } else if handled && state.app_state.status_text == "Go to heading" {
+                            open_goto_symbol(state);
+                        } else if handled && state.app_state.status_text == "Document properties" {
+                            open_document_properties(state);
+                        } else if handled && state.app_state.status_text == "Go to footnote" {
+                            toggle_footnote_jump(state);
+                        } else if handled && state.app_state.status_text == "Edit anyway" {
+                            state.app_state.status_text = set_active_tab_read_only(state, false);
+                        } else if handled && state.app_state.status_text == "Toggle read-only" {
+                            let read_only =
+                                state.tabs.active_tab().is_some_and(|tab| tab.read_only);
+                            state.app_state.status_text =
+                                set_active_tab_read_only(state, !read_only);
+                        } else if handled && state.app_state.status_text == "Copy file path" {
+                            let active = state.tabs.active;
+                            apply_tab_file_action(state, active, ContextAction::CopyFilePath);
+                        } else if handled && state.app_state.status_text == "Copy filename" {
+                            let active = state.tabs.active;
+                            apply_tab_file_action(state, active, ContextAction::CopyFileName);
+                        } else if handled && state.app_state.status_text == "Open containing folder"
+                        {
+                            let active = state.tabs.active;
+                            apply_tab_file_action(state, active, ContextAction::ShowInExplorer);
+                        } else if handled && state.app_state.status_text == "Follow link" {
+                            follow_link_at_cursor(state);
+                        } else if handled
+                            && state.app_state.status_text == "Insert table of contents"
+                        {
+                            write_table_of_contents(state, false);
+                        } else if handled
+                            && state.app_state.status_text == "Update table of contents"
+                        {
+                            write_table_of_contents(state, true);
+                        } else if handled
+                            && let Some(index) = [
+                                "Insert list of figures",
+                                "Update list of figures",
+                                "Insert list of tables",
+                                "Update list of tables",
+                            ]
+                            .iter()
+                            .position(|label| state.app_state.status_text == *label)
+                        {
+                            let kind = if index < 2 {
+                                CaptionKind::Figure
+                            } else {
+                                CaptionKind::Table
+                            };
+                            write_caption_list(state, kind, index % 2 == 1);
+                        } else if handled && state.app_state.status_text == "Add anchor" {
+                            add_anchor_at_cursor(state);
+                        } else if handled && state.app_state.status_text == "Toggle caption" {
+                            toggle_caption(state);
+                        } else if handled
+                            && let Some(index) = ["One column", "Two columns", "Three columns"]
+                                .iter()
+                                .position(|label| state.app_state.status_text == *label)
+                        {
+                            state.app_state.status_text = set_page_columns(state, index as u8 + 1);
+                        } else if handled && state.app_state.status_text == "Insert cross-reference"
+                        {
+                            open_cross_reference_picker(state, CrossReferenceDisplay::Text);
+                        } else if handled
+                            && state.app_state.status_text == "Insert cross-reference number"
+                        {
+                            open_cross_reference_picker(state, CrossReferenceDisplay::Number);
+                        } else if handled && state.app_state.status_text == "Next bookmark" {
+                            cycle_bookmark(state, true);
+                        } else if handled && state.app_state.status_text == "Previous bookmark" {
+                            cycle_bookmark(state, false);
+                        } else if handled && state.app_state.status_text == "Freeze table first column" {
+                            toggle_table_freeze(state, true);
+                        } else if handled && state.app_state.status_text == "Freeze table header row" {
+                            toggle_table_freeze(state, false);
+                        } else if handled
+                            && let Some(delimiter) =
+                                TextDelimiter::ALL.into_iter().find(|delimiter| {
+                                    state.app_state.status_text
+                                        == format!("Table to text: {}", delimiter.label())
+                                })
+                        {
+                            convert_table_to_text(state, delimiter);
+                            sync_sidebar_with_active_tab(state);
+                        } else if handled && state.app_state.status_text == "Text to table" {
+                            convert_text_to_table(state);
+                            sync_sidebar_with_active_tab(state);
+                        } else if handled && state.app_state.status_text == "Block quote" {
+                            toggle_block_quote(state);
+                        } else if handled && state.app_state.status_text == "Insert date/time" {
+                            if insert_date_time(state) {
+                                sync_sidebar_with_active_tab(state);
+                                state.app_state.status_text = "Inserted date/time".to_string();
+                            } else {
+                                state.app_state.status_text = "Nothing to insert the date into".to_string();
+                            }
                         } else if handled && state.app_state.status_text == "Insert table" {
                             open_table_picker(state);
                             state.app_state.status_text = "Insert table (picker)".to_string();
@@ -4524,11 +9105,58 @@ unsafe extern "system" fn window_proc(
                             let _ = save_active_document(state, hwnd, false);
                         } else if handled && state.app_state.status_text == "Save As" {
                             let _ = save_active_document(state, hwnd, true);
+                        } else if handled && state.app_state.status_text == "Save all" {
+                            save_all_documents(state, hwnd);
                         } else if handled && state.app_state.status_text == "Export PDF" {
                             let _ = export_active_document(state, hwnd, "pdf");
                         } else if handled && state.app_state.status_text == "Close tab" {
                             let active_index = state.tabs.active;
                             let _ = close_tab_with_prompt(state, hwnd, active_index);
+                        } else if handled && state.app_state.status_text == "Duplicate tab" {
+                            let active_index = state.tabs.active;
+                            duplicate_tab(state, active_index);
+                        } else if handled && state.app_state.status_text == "Close all tabs" {
+                            close_tabs(state, hwnd, BulkClose::All);
+                        } else if handled && state.app_state.status_text == "Close saved tabs" {
+                            close_tabs(state, hwnd, BulkClose::Saved);
+                        } else if handled
+                            && (state.app_state.status_text == "Superscript"
+                                || state.app_state.status_text == "Subscript")
+                        {
+                            let superscript = state.app_state.status_text == "Superscript";
+                            if toggle_script_style(state, superscript) {
+                                sync_toolbar_format_from_cursor(state);
+                                state.app_state.status_text =
+                                    format!("{} toggled", state.app_state.status_text);
+                            }
+                        } else if handled
+                            && let Some(choice) = state
+                                .app_state
+                                .status_text
+                                .strip_prefix("Highlight: ")
+                                .and_then(HighlightChoice::from_label)
+                        {
+                            if apply_highlight(state, choice) {
+                                sync_toolbar_format_from_cursor(state);
+                                state.app_state.status_text = match choice {
+                                    HighlightChoice::None => "Highlight removed".to_string(),
+                                    _ => format!("Highlight set to {}", choice.label()),
+                                };
+                            }
+                        } else if handled
+                            && let Some(label) = state
+                                .app_state
+                                .status_text
+                                .strip_prefix("Paragraph frame: ")
+                                .map(str::to_string)
+                        {
+                            let preset = ParagraphFramePreset::from_label(&label);
+                            if set_paragraph_frame_preset(state, preset) {
+                                state.app_state.status_text = match preset {
+                                    Some(preset) => format!("{} frame applied", preset.label()),
+                                    None => "Paragraph frame removed".to_string(),
+                                };
+                            }
                         }
                     }
                     if handled {
@@ -4542,6 +9170,84 @@ unsafe extern "system" fn window_proc(
                     sync_runtime_from_settings(state, hwnd);
                 }
 
+                if state.document_properties.is_open() && !state.command_palette.is_open() {
+                    // Shift+Tab walks the fields backwards, like Up.
+                    let key = if vk == 0x09 && shift_down { 0x26 } else { vk };
+                    let _ = state
+                        .document_properties
+                        .handle_input(&UiInputEvent::KeyDown(key));
+                    apply_document_properties(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if state.app_state.show_sidebar
+                    && state.sidebar.wants_bookmark_keys()
+                    && !state.command_palette.is_open()
+                {
+                    let handled = state.sidebar.handle_input(&UiInputEvent::KeyDown(vk));
+                    if handled || apply_pending_sidebar_intents(state) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if state.zoom_popup.is_open() && !state.command_palette.is_open() {
+                    if state.zoom_popup.handle_input(&UiInputEvent::KeyDown(vk)) {
+                        apply_zoom_popup_action(state);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    // Any other key dismisses the popup and is handled as usual.
+                    state.zoom_popup.close();
+                }
+
+                if state.stats_popup.is_open() && !state.command_palette.is_open() {
+                    let consumed = state.stats_popup.handle_input(&UiInputEvent::KeyDown(vk));
+                    state.stats_popup.close();
+                    if consumed {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if state.goto_symbol.is_open() && !state.command_palette.is_open() {
+                    let _ = state.goto_symbol.handle_input(&UiInputEvent::KeyDown(vk));
+                    apply_goto_symbol(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if state.licenses_view.is_open() && !state.command_palette.is_open() {
+                    let _ = state.licenses_view.handle_input(&UiInputEvent::KeyDown(vk));
+                    open_license_repository(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if command == Some("document.goto_heading") {
+                    open_goto_symbol(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if state.toolbar_customizer.is_open() && !state.command_palette.is_open() {
+                    if ctrl_down && (vk == 0x26 || vk == 0x28) {
+                        let _ = state
+                            .toolbar_customizer
+                            .move_selected(if vk == 0x26 { -1 } else { 1 });
+                    } else if ctrl_down && !shift_down && vk == 0x52 {
+                        state.toolbar_customizer.reset_to_default();
+                    } else {
+                        let _ = state
+                            .toolbar_customizer
+                            .handle_input(&UiInputEvent::KeyDown(vk));
+                    }
+                    apply_toolbar_customization(state, hwnd);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
                 if state.settings_dialog.is_open() && !state.command_palette.is_open() {
                     if ctrl_down
                         && !shift_down
@@ -4556,6 +9262,12 @@ unsafe extern "system" fn window_proc(
                         return LRESULT(0);
                     }
 
+                    if vk == 0x09 && shift_down {
+                        state.settings_dialog.move_focus(true);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+
                     let event = UiInputEvent::KeyDown(vk);
                     let handled_settings = state.settings_dialog.handle_input(&event);
                     if handled_settings {
@@ -4645,17 +9357,50 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
-                if ctrl_down && vk == 0x53 {
-                    if shift_down {
-                        let _ = save_active_document(state, hwnd, true);
-                    } else {
-                        let _ = save_active_document(state, hwnd, false);
+                if command.is_none()
+                    && vk == 0x75
+                    && !ctrl_down
+                    && !alt_down
+                    && !state.command_palette.is_open()
+                {
+                    cycle_focus_region(state, shift_down);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.focus_region != FocusRegion::Document
+                    && !state.command_palette.is_open()
+                    && !state.find_replace.find_visible
+                    && !state.goto_visible
+                    && !state.goto_symbol.is_open()
+                {
+                    // Keys that aren't shortcuts stay with the focused region rather than
+                    // editing the document behind it.
+                    let shortcut =
+                        command.is_some() || ctrl_down || alt_down || (0x70..=0x87).contains(&vk);
+                    if handle_focused_region_key(state, hwnd, vk) || !shortcut {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if matches!(
+                    command,
+                    Some("file.save" | "file.save_as" | "file.save_all")
+                ) {
+                    match command {
+                        Some("file.save_all") => save_all_documents(state, hwnd),
+                        Some("file.save_as") => {
+                            let _ = save_active_document(state, hwnd, true);
+                        }
+                        _ => {
+                            let _ = save_active_document(state, hwnd, false);
+                        }
                     }
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x4F {
+                if command == Some("file.open") {
                     let _ = open_file_via_picker(state, hwnd, true);
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
@@ -4670,13 +9415,27 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down
-                    && !shift_down
-                    && vk == 0x43
+                if command == Some("edit.copy")
                     && !state.find_replace.find_visible
                     && !state.command_palette.is_open()
                     && !state.goto_visible
                 {
+                    if state.selected_image.is_some() {
+                        state.app_state.status_text = if copy_selected_image_to_clipboard(state) {
+                            "Image copied".to_string()
+                        } else {
+                            "Couldn't copy the image".to_string()
+                        };
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    if state.table_selection_mode == Some(TableSelectionMode::Table)
+                        && copy_selected_table_to_clipboard(state)
+                    {
+                        state.app_state.status_text = "Table copied".to_string();
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
                     if copy_active_block_to_clipboard(state) {
                         state.app_state.status_text = "Copied".to_string();
                         let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
@@ -4684,9 +9443,25 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
-                if ctrl_down
-                    && !shift_down
-                    && vk == 0x58
+                let redo_alternate = command.is_none() && ctrl_down && shift_down && vk == 0x5A;
+                if (matches!(command, Some("edit.undo" | "edit.redo")) || redo_alternate)
+                    && !state.find_replace.find_visible
+                    && !state.command_palette.is_open()
+                    && !state.goto_visible
+                {
+                    let redo = command != Some("edit.undo");
+                    state.app_state.status_text = match (undo_active_tab(state, redo), redo) {
+                        (true, false) => "Undo".to_string(),
+                        (true, true) => "Redo".to_string(),
+                        (false, false) => "Nothing to undo".to_string(),
+                        (false, true) => "Nothing to redo".to_string(),
+                    };
+                    sync_sidebar_with_active_tab(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if command == Some("edit.cut")
                     && !state.find_replace.find_visible
                     && !state.command_palette.is_open()
                     && !state.goto_visible
@@ -4700,13 +9475,16 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
-                if ctrl_down
-                    && !shift_down
-                    && vk == 0x56
+                if command == Some("edit.paste")
                     && !state.find_replace.find_visible
                     && !state.command_palette.is_open()
                     && !state.goto_visible
                 {
+                    if paste_table_from_clipboard(state).is_some() {
+                        state.app_state.status_text = "Pasted table".to_string();
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
                     match insert_image_from_clipboard(state) {
                         Ok(id) => {
                             state.app_state.status_text = format!("Pasted image {}", id.0);
@@ -4726,10 +9504,11 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
-                if ctrl_down && shift_down && vk == 0x46 {
+                if command == Some("edit.find_in_tabs") {
                     if state.find_replace.query.trim().is_empty() {
                         state.find_replace.open_find();
                         state.find_focus = FindFieldFocus::Query;
+                        state.focus_region = FocusRegion::Document;
                         state.app_state.status_text =
                             "Set a Find query, then press Ctrl+Shift+F to search all tabs".to_string();
                     } else {
@@ -4745,27 +9524,32 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x46 {
+                if command == Some("edit.find") {
                     state.find_replace.open_find();
                     state.find_focus = FindFieldFocus::Query;
+                    state.focus_region = FocusRegion::Document;
+                    capture_find_selection(state);
                     refresh_find_results(state);
                     state.app_state.status_text = "Find".to_string();
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x48 {
+                if command == Some("edit.replace") {
                     state.find_replace.open_replace();
                     state.find_focus = FindFieldFocus::Replacement;
+                    state.focus_region = FocusRegion::Document;
+                    capture_find_selection(state);
                     refresh_find_results(state);
                     state.app_state.status_text = "Replace".to_string();
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x47 {
+                if command == Some("document.goto_page") {
                     state.goto_visible = true;
                     state.goto_input.clear();
+                    state.focus_region = FocusRegion::Document;
                     state.app_state.status_text = "Go to line/page".to_string();
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
@@ -4800,6 +9584,18 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
+                if command.is_none() && vk == 0x71 && !ctrl_down {
+                    cycle_bookmark(state, !shift_down);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if command == Some("document.follow_link") && !state.find_replace.find_visible {
+                    follow_link_at_cursor(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
                 if vk == 0x72 {
                     if navigate_find_result(state, shift_down) {
                         let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
@@ -4863,6 +9659,7 @@ unsafe extern "system" fn window_proc(
                         state.find_replace.pending_live_update = true;
                         state.find_replace.last_input_at = Instant::now()
                             - std::time::Duration::from_millis(state.find_replace.debounce_ms);
+                        save_find_options(state);
                         handled_find = true;
                     }
                     if ctrl_down && shift_down && vk == 0x57 {
@@ -4872,6 +9669,7 @@ unsafe extern "system" fn window_proc(
                         state.find_replace.pending_live_update = true;
                         state.find_replace.last_input_at = Instant::now()
                             - std::time::Duration::from_millis(state.find_replace.debounce_ms);
+                        save_find_options(state);
                         handled_find = true;
                     }
                     if ctrl_down && shift_down && vk == 0x52 {
@@ -4880,6 +9678,26 @@ unsafe extern "system" fn window_proc(
                         state.find_replace.pending_live_update = true;
                         state.find_replace.last_input_at = Instant::now()
                             - std::time::Duration::from_millis(state.find_replace.debounce_ms);
+                        save_find_options(state);
+                        handled_find = true;
+                    }
+                    if ctrl_down && shift_down && vk == 0x4C {
+                        state.find_replace.in_selection = !state.find_replace.in_selection;
+                        state.find_replace.invalidate_cache();
+                        state.find_replace.pending_live_update = true;
+                        state.find_replace.last_input_at = Instant::now()
+                            - std::time::Duration::from_millis(state.find_replace.debounce_ms);
+                        save_find_options(state);
+                        state.app_state.status_text = match (
+                            state.find_replace.in_selection,
+                            state.find_replace.selection.is_some(),
+                        ) {
+                            (false, _) => "Find in whole document".to_string(),
+                            (true, true) => "Find in selection only".to_string(),
+                            (true, false) => {
+                                "Find in selection only (select text, then reopen Find)".to_string()
+                            }
+                        };
                         handled_find = true;
                     }
 
@@ -4900,7 +9718,7 @@ unsafe extern "system" fn window_proc(
                     }
 
                     if ctrl_down && !shift_down && vk == 0x52 {
-                        if let Some(path) = pick_image_file(hwnd) {
+                        if let Some(path) = pick_image_via_dialog(state, hwnd) {
                             if let Some(selected) = state.selected_image {
                                 if delete_selected_image(state) {
                                     if let Ok(inserted) = insert_image_from_path(state, &path) {
@@ -4951,7 +9769,21 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
-                if ctrl_down && !shift_down && vk == 0x50 {
+                if matches!(command, Some("format.subscript" | "format.superscript")) {
+                    let superscript = command == Some("format.superscript");
+                    if toggle_script_style(state, superscript) {
+                        sync_toolbar_format_from_cursor(state);
+                        state.app_state.status_text = if superscript {
+                            "Superscript toggled".to_string()
+                        } else {
+                            "Subscript toggled".to_string()
+                        };
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if command == Some("file.print") {
                     state.print_state.request_print_dialog();
                     if let Some(result) = open_print_dialog(hwnd) {
                         state.print_state.page_range = result.page_range;
@@ -4970,7 +9802,14 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0xBC {
+                if command == Some("view.keyboard_shortcuts") {
+                    open_shortcut_editor(state);
+                    state.app_state.status_text = "Keyboard shortcuts".to_string();
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
+                if command == Some("view.settings") {
                     let visible = !state.settings_dialog.is_open();
                     set_settings_visible(state, visible);
                     state.app_state.status_text = if visible {
@@ -4985,7 +9824,7 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && shift_down && vk == 0x42 {
+                if command == Some("document.add_bookmark") {
                     if let Some(tab) = state.tabs.active_tab() {
                         let block_id = tab.cursor.primary.block_id;
                         let snippet = block_snippet(&tab.document, block_id);
@@ -4996,7 +9835,7 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
-                if ctrl_down && !shift_down && vk == 0x42 {
+                if command == Some("view.toggle_sidebar") {
                     state.app_state.show_sidebar = !state.app_state.show_sidebar;
                     let show_sidebar = state.app_state.show_sidebar;
                     state.app_state.settings.appearance.show_sidebar = show_sidebar;
@@ -5024,7 +9863,7 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && shift_down && vk == 0x54 {
+                if command == Some("view.toggle_toolbar") {
                     state.app_state.show_toolbar = !state.app_state.show_toolbar;
                     let show_toolbar = state.app_state.show_toolbar;
                     state.app_state.settings.appearance.show_toolbar = show_toolbar;
@@ -5047,7 +9886,7 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x4C {
+                if command == Some("view.toggle_statusbar") {
                     state.app_state.show_statusbar = !state.app_state.show_statusbar;
                     let show_status_bar = state.app_state.show_statusbar;
                     state.app_state.settings.appearance.show_status_bar = show_status_bar;
@@ -5070,7 +9909,7 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x54 {
+                if command == Some("file.new_tab") {
                     let index = open_new_blank_tab(state);
                     let title = state
                         .tabs
@@ -5084,7 +9923,7 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x4E {
+                if command == Some("file.new") {
                     let index = open_new_blank_tab(state);
                     let title = state
                         .tabs
@@ -5098,15 +9937,15 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && vk == 0x57 {
+                if command == Some("file.close_tab") {
                     let active_index = state.tabs.active;
                     let _ = close_tab_with_prompt(state, hwnd, active_index);
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
 
-                if ctrl_down && vk == 0x09 {
-                    if shift_down {
+                if matches!(command, Some("view.next_tab" | "view.previous_tab")) {
+                    if command == Some("view.previous_tab") {
                         state.tabs.switch_prev();
                     } else {
                         state.tabs.switch_next();
@@ -5122,7 +9961,7 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
-                if ctrl_down && !shift_down && (0x31..=0x39).contains(&vk) {
+                if command.is_none() && ctrl_down && !shift_down && (0x31..=0x39).contains(&vk) {
                     let tab_number = (vk - 0x30) as usize;
                     state.tabs.switch_to_number(tab_number);
                     sync_sidebar_with_active_tab(state);
@@ -5137,12 +9976,90 @@ unsafe extern "system" fn window_proc(
                     return LRESULT(0);
                 }
 
+                if command.is_none()
+                    && ctrl_down
+                    && !shift_down
+                    && matches!(vk, 0x25 | 0x27)
+                    && !state.find_replace.find_visible
+                    && !state.command_palette.is_open()
+                    && !state.goto_visible
+                    && !state.table_picker_visible
+                    && !active_tab_is_loading(state)
+                {
+                    let movement = if vk == 0x25 {
+                        Movement::CtrlLeft
+                    } else {
+                        Movement::CtrlRight
+                    };
+                    if move_cursor_in_text_blocks(state, movement) {
+                        sync_sidebar_with_active_tab(state);
+                        sync_toolbar_format_from_cursor(state);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if command.is_none()
+                    && ctrl_down
+                    && shift_down
+                    && matches!(vk, 0x25 | 0x27)
+                    && !state.find_replace.find_visible
+                    && !state.command_palette.is_open()
+                    && !state.goto_visible
+                    && !state.table_picker_visible
+                    && !active_tab_is_loading(state)
+                {
+                    let (direction, label) = if vk == 0x27 {
+                        (TextDirection::Rtl, "Right-to-left paragraph")
+                    } else {
+                        (TextDirection::Ltr, "Left-to-right paragraph")
+                    };
+                    if set_paragraph_direction(state, direction) {
+                        state.app_state.status_text = label.to_string();
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
+                if command.is_none()
+                    && ctrl_down
+                    && !shift_down
+                    && matches!(vk, 0x23 | 0x24)
+                    && !state.find_replace.find_visible
+                    && !state.command_palette.is_open()
+                    && !state.goto_visible
+                    && !state.table_picker_visible
+                    && !active_tab_is_loading(state)
+                {
+                    let (movement, label) = if vk == 0x24 {
+                        (Movement::CtrlHome, "Start of document")
+                    } else {
+                        (Movement::CtrlEnd, "End of document")
+                    };
+                    if scroll_and_move_cursor(state, movement) {
+                        state.app_state.status_text = label.to_string();
+                        sync_sidebar_with_active_tab(state);
+                        sync_toolbar_format_from_cursor(state);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                }
+
                 if !ctrl_down
                     && !state.find_replace.find_visible
                     && !state.command_palette.is_open()
                     && !state.goto_visible
                     && !state.table_picker_visible
+                    && !active_tab_is_loading(state)
                 {
+                    if matches!(vk, 0x08 | 0x0D | 0x2E | 0x09) && edit_blocked_by_read_only(state) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    if apply_text_view_key(state, vk) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
                     let handled_text = match vk {
                         0x08 => delete_backward_at_cursor(state),
                         0x0D => split_block_or_insert_newline(state),
@@ -5154,8 +10071,8 @@ unsafe extern "system" fn window_proc(
                         0x28 => move_cursor_in_text_blocks(state, Movement::Down),
                         0x24 => move_cursor_in_text_blocks(state, Movement::Home),
                         0x23 => move_cursor_in_text_blocks(state, Movement::End),
-                        0x21 => move_cursor_in_text_blocks(state, Movement::PageUp),
-                        0x22 => move_cursor_in_text_blocks(state, Movement::PageDown),
+                        0x21 => scroll_and_move_cursor(state, Movement::PageUp),
+                        0x22 => scroll_and_move_cursor(state, Movement::PageDown),
                         _ => false,
                     };
 
@@ -5181,6 +10098,49 @@ unsafe extern "system" fn window_proc(
         WM_CHAR => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let code = wparam.0 as u32;
+                if state.toolbar.dropdown_accepts_text()
+                    && let Some(ch) = char::from_u32(code)
+                    && state.toolbar.handle_input(&UiInputEvent::Char(ch))
+                {
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.toolbar_customizer.is_open() && !state.command_palette.is_open() {
+                    // The customize dialog is keyboard-driven through WM_KEYDOWN only.
+                    return LRESULT(0);
+                }
+                if state.goto_symbol.is_open() && !state.command_palette.is_open() {
+                    if let Some(ch) = char::from_u32(code) {
+                        let _ = state.goto_symbol.handle_input(&UiInputEvent::Char(ch));
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    }
+                    return LRESULT(0);
+                }
+                if state.licenses_view.is_open() && !state.command_palette.is_open() {
+                    if let Some(ch) = char::from_u32(code) {
+                        let _ = state.licenses_view.handle_input(&UiInputEvent::Char(ch));
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    }
+                    return LRESULT(0);
+                }
+                if state.shortcut_editor.is_open() {
+                    // Keys are recorded from WM_KEYDOWN; the characters they type are dropped.
+                    return LRESULT(0);
+                }
+                if state.document_properties.is_open() && !state.command_palette.is_open() {
+                    if let Some(ch) = char::from_u32(code) {
+                        let _ = state.document_properties.handle_input(&UiInputEvent::Char(ch));
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    }
+                    return LRESULT(0);
+                }
+                if state.sidebar.is_renaming_bookmark() && !state.command_palette.is_open() {
+                    if let Some(ch) = char::from_u32(code) {
+                        let _ = state.sidebar.handle_input(&UiInputEvent::Char(ch));
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    }
+                    return LRESULT(0);
+                }
                 if state.settings_dialog.is_open() && !state.command_palette.is_open() {
                     if let Some(ch) = char::from_u32(code) {
                         let event = UiInputEvent::Char(ch);
@@ -5194,6 +10154,16 @@ unsafe extern "system" fn window_proc(
                     }
                     return LRESULT(0);
                 }
+                if state.focus_region != FocusRegion::Document
+                    && !state.command_palette.is_open()
+                    && !state.table_picker_visible
+                    && !state.find_replace.find_visible
+                    && !state.goto_visible
+                    && !state.goto_symbol.is_open()
+                {
+                    // Keyboard focus is on the shell; the text it types isn't for the document.
+                    return LRESULT(0);
+                }
 
                 if state.table_picker_visible {
                     if let Some(ch) = char::from_u32(code) {
@@ -5267,10 +10237,22 @@ unsafe extern "system" fn window_proc(
                     && !state.goto_visible
                     && !state.table_picker_visible
                     && !state.settings_dialog.is_open()
+                    && !active_tab_is_loading(state)
                     && let Some(ch) = char::from_u32(code)
                     && !ch.is_control()
                 {
+                    if edit_blocked_by_read_only(state) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
                     let mut buf = [0u8; 4];
+                    if insert_into_text_view(state, ch.encode_utf8(&mut buf)) {
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    if is_expansion_trigger(ch) {
+                        let _ = expand_snippet_at_cursor(state);
+                    }
                     let text = ch.encode_utf8(&mut buf);
                     if insert_text_at_cursor(state, text) {
                         sync_sidebar_with_active_tab(state);
@@ -5285,36 +10267,7 @@ unsafe extern "system" fn window_proc(
         WM_DROPFILES => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let payload = unsafe { extract_drop_payload(HDROP(wparam.0 as *mut c_void)) };
-                state.dropped_files = payload.files.clone();
-
-                state.app_state.status_text = match payload.action {
-                    DropAction::OpenFilesInTabs => {
-                        for path in &payload.files {
-                            state.jump_list.add_recent_file(path.clone());
-                            let title = path
-                                .file_name()
-                                .and_then(|v| v.to_str())
-                                .unwrap_or("Document")
-                                .to_string();
-                            let document = load_document_for_path(path.as_path());
-                            state
-                                .tabs
-                                .open_document_tab(title, Some(path.clone()), document);
-                        }
-                        format!("Drop to open: {} file(s)", payload.files.len())
-                    }
-                    DropAction::InsertImage => {
-                        let (inserted, failed) = insert_images_from_paths(state, &payload.files);
-                        if inserted == 0 {
-                            format!("Drop image insert failed ({failed} file(s))")
-                        } else if failed == 0 {
-                            format!("Inserted {} dropped image(s)", inserted)
-                        } else {
-                            format!("Inserted {} dropped image(s), {} failed", inserted, failed)
-                        }
-                    }
-                    DropAction::Ignore => "Unsupported dropped content".to_string(),
-                };
+                open_dropped_files(state, payload);
                 sync_sidebar_with_active_tab(state);
 
                 let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
@@ -5327,6 +10280,11 @@ unsafe extern "system" fn window_proc(
                 if state.settings_dialog.is_open() && !state.command_palette.is_open() {
                     return LRESULT(0);
                 }
+                if state.zoom_popup.handle_input(&UiInputEvent::MouseMove(point)) {
+                    apply_zoom_popup_action(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
                 if state.table_picker_visible {
                     if update_table_picker_hover(state, point) {
                         let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
@@ -5335,7 +10293,7 @@ unsafe extern "system" fn window_proc(
                 }
                 if state.sidebar_resizing {
                     let next_width =
-                        (point.x + state.sidebar_resize_grab_offset).clamp(200.0, 400.0);
+                        clamp_sidebar_width(point.x + state.sidebar_resize_grab_offset);
                     if (state.app_state.sidebar_width - next_width).abs() > f32::EPSILON {
                         state.app_state.sidebar_width = next_width;
                         state.app_state.status_text = format!("Sidebar width: {:.0}px", next_width);
@@ -5350,6 +10308,33 @@ unsafe extern "system" fn window_proc(
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
+                if state.hscroll_drag.is_some() {
+                    update_horizontal_scrollbar_drag(state, point);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.pan_anchor.is_some() {
+                    update_pan(state, point);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.text_drag.is_some() {
+                    update_text_drag(state, point);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.selection_drag {
+                    let origin = canvas_origin(state);
+                    if let Some(tab) = state.tabs.active_tab_mut() {
+                        let speed =
+                            edge_scroll_speed(point.y - origin.y, tab.canvas.viewport.height);
+                        if speed != tab.canvas.edge_scroll {
+                            tab.canvas.edge_scroll = speed;
+                            let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        }
+                    }
+                    return LRESULT(0);
+                }
                 if state.table_resize.is_some() && update_table_resize(state, point) {
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
@@ -5383,6 +10368,63 @@ unsafe extern "system" fn window_proc(
         WM_LBUTTONDOWN => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let point = point_from_lparam(lparam);
+                state.focus_region = FocusRegion::Document;
+                state.focus_visible = false;
+                if state.toolbar_customizer.is_open() {
+                    let _ = state
+                        .toolbar_customizer
+                        .handle_input(&UiInputEvent::MouseDown(point));
+                    apply_toolbar_customization(state, hwnd);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.goto_symbol.is_open() {
+                    let _ = state
+                        .goto_symbol
+                        .handle_input(&UiInputEvent::MouseDown(point));
+                    apply_goto_symbol(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.licenses_view.is_open() {
+                    let _ = state
+                        .licenses_view
+                        .handle_input(&UiInputEvent::MouseDown(point));
+                    open_license_repository(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.shortcut_editor.is_open() {
+                    let _ = state
+                        .shortcut_editor
+                        .handle_input(&UiInputEvent::MouseDown(point));
+                    apply_shortcut_edits(state, hwnd);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.document_properties.is_open() {
+                    let _ = state
+                        .document_properties
+                        .handle_input(&UiInputEvent::MouseDown(point));
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.zoom_popup.is_open() {
+                    let _ = state.zoom_popup.handle_input(&UiInputEvent::MouseDown(point));
+                    apply_zoom_popup_action(state);
+                    if state.zoom_popup.is_dragging() {
+                        let _ = unsafe { SetCapture(hwnd) };
+                    }
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if state.stats_popup.is_open() {
+                    let _ = state
+                        .stats_popup
+                        .handle_input(&UiInputEvent::MouseDown(point));
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
                 if state.table_picker_visible {
                     if handle_table_picker_click(state, point) {
                         let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
@@ -5424,6 +10466,17 @@ unsafe extern "system" fn window_proc(
                         let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                         return LRESULT(0);
                     }
+                    // The chevron, or Ctrl with either arrow, lists every tab to jump to.
+                    let ctrl_down = unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0;
+                    if state.tabs.tab_list_hit_test(point)
+                        || (ctrl_down
+                            && (state.tabs.overflow_left_hit_test(point)
+                                || state.tabs.overflow_right_hit_test(point)))
+                    {
+                        state.app_state.status_text = show_tab_list_menu(state, hwnd);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
                     if state.tabs.overflow_left_hit_test(point) && state.tabs.scroll_overflow_left() {
                         let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                         return LRESULT(0);
@@ -5433,6 +10486,11 @@ unsafe extern "system" fn window_proc(
                         return LRESULT(0);
                     }
                 }
+                if begin_horizontal_scrollbar_drag(state, point) {
+                    let _ = unsafe { SetCapture(hwnd) };
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
                 if sidebar_splitter_hit_test(state, point) {
                     state.sidebar_resizing = true;
                     state.sidebar.resizing = true;
@@ -5449,6 +10507,10 @@ unsafe extern "system" fn window_proc(
                 if state.app_state.show_tabs {
                     let previous_active = state.tabs.active;
                     handled |= state.tabs.handle_input(&event);
+                    if state.tabs.is_dragging_tab() {
+                        // Keep receiving the mouse so a drop outside the window can tear the tab off.
+                        let _ = unsafe { SetCapture(hwnd) };
+                    }
                     if state.tabs.active != previous_active {
                         sync_sidebar_with_active_tab(state);
                         if let Some(tab) = state.tabs.active_tab() {
@@ -5459,6 +10521,11 @@ unsafe extern "system" fn window_proc(
 
                 if state.app_state.show_toolbar {
                     handled |= state.toolbar.handle_input(&event);
+                    if let Some(selection) = state.toolbar.pending_selection.take() {
+                        apply_dropdown_selection(state, selection);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
                 }
 
                 if state.app_state.show_sidebar {
@@ -5466,6 +10533,7 @@ unsafe extern "system" fn window_proc(
                     handled |= state.sidebar.handle_input(&event);
                     handled |= apply_pending_sidebar_intents(state);
                     if before != state.sidebar.active_panel {
+                        remember_sidebar_layout(state);
                         state.app_state.status_text = format!(
                             "Sidebar panel: {}",
                             match state.sidebar.active_panel {
@@ -5478,17 +10546,31 @@ unsafe extern "system" fn window_proc(
                     }
                 }
 
+                if !handled && state.breadcrumb.handle_input(&event) {
+                    if let Some(block_id) = state.breadcrumb.pending_jump.take() {
+                        jump_to_heading(state, block_id);
+                    }
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+
                 if state.app_state.show_statusbar {
                     handled |= state.statusbar.handle_input(&event);
                     if let Some(action) = state.statusbar.pending_action.take() {
-                        state.app_state.status_text = match action {
-                            StatusAction::OpenZoomPopup => "Zoom control requested".to_string(),
-                            StatusAction::ChangeEncoding => "Encoding picker requested".to_string(),
-                        };
+                        state.app_state.status_text = apply_status_action(state, hwnd, action);
                         handled = true;
                     }
                 }
 
+                let ctrl_down = unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0;
+                if !handled && ctrl_down && follow_link_at_point(state, point) {
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+                if !handled && toggle_callout_at_point(state, point) {
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
                 if begin_image_interaction(state, point) {
                     handled = true;
                 }
@@ -5498,6 +10580,17 @@ unsafe extern "system" fn window_proc(
                     }
                     handled = true;
                 }
+                if !handled && begin_text_drag(state, point) {
+                    let _ = unsafe { SetCapture(hwnd) };
+                    state.app_state.status_text =
+                        "Drag to move the selection; hold Ctrl to copy".to_string();
+                    handled = true;
+                }
+                if !handled && canvas_contains(state, point) {
+                    // Captured so dragging past the canvas edges keeps extending the selection.
+                    state.selection_drag = true;
+                    let _ = unsafe { SetCapture(hwnd) };
+                }
 
                 if handled {
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
@@ -5506,13 +10599,67 @@ unsafe extern "system" fn window_proc(
             }
             unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
         }
+        WM_ACTIVATE => {
+            if (wparam.0 & 0xFFFF) as u32 != WA_INACTIVE
+                && let Some(state) = unsafe { state_from_hwnd(hwnd) }
+            {
+                // Notes may have been added or renamed while the window was in the background.
+                state.wiki_pages = None;
+                if check_external_changes(state, hwnd) {
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                }
+            }
+            unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+        }
         WM_MBUTTONDOWN => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                let point = point_from_lparam(lparam);
+                let tab = state
+                    .tabs
+                    .tab_hit_test(point)
+                    .filter(|_| state.app_state.show_tabs);
+                let paste_enabled = state.app_state.settings.editor.middle_click_paste;
+                match middle_click_target(tab, canvas_contains(state, point), paste_enabled) {
+                    MiddleClick::CloseTab(index) => {
+                        let _ = close_tab_with_prompt(state, hwnd, index);
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    MiddleClick::Paste => {
+                        if paste_text_at_point(state, point) {
+                            state.app_state.status_text = "Pasted".to_string();
+                            sync_sidebar_with_active_tab(state);
+                            sync_toolbar_format_from_cursor(state);
+                        }
+                        let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                        return LRESULT(0);
+                    }
+                    MiddleClick::Pan => {
+                        state.pan_anchor = Some(point);
+                        let _ = unsafe { SetCapture(hwnd) };
+                        return LRESULT(0);
+                    }
+                    MiddleClick::Ignore => {}
+                }
+            }
+            unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+        }
+        WM_MBUTTONUP => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) }
+                && state.pan_anchor.take().is_some()
+            {
+                let _ = unsafe { ReleaseCapture() };
+                return LRESULT(0);
+            }
+            unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+        }
+        WM_RBUTTONUP => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let point = point_from_lparam(lparam);
                 if state.app_state.show_tabs
                     && let Some(index) = state.tabs.tab_hit_test(point)
                 {
-                    let _ = close_tab_with_prompt(state, hwnd, index);
+                    show_tab_context_menu(state, hwnd, index, point);
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
@@ -5523,11 +10670,19 @@ unsafe extern "system" fn window_proc(
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let point = point_from_lparam(lparam);
                 let mut handled = false;
+                if state.zoom_popup.handle_input(&UiInputEvent::MouseUp(point)) {
+                    let _ = unsafe { ReleaseCapture() };
+                    return LRESULT(0);
+                }
                 if state.app_state.show_tabs {
                     let tab_event = UiInputEvent::MouseUp(point);
                     if state.tabs.handle_input(&tab_event) {
+                        let _ = unsafe { ReleaseCapture() };
                         handled = true;
                     }
+                    if let Some(index) = state.tabs.take_pending_detach() {
+                        move_dragged_tab(state, hwnd, index, point);
+                    }
                 }
                 if state.sidebar_resizing {
                     state.sidebar_resizing = false;
@@ -5537,16 +10692,41 @@ unsafe extern "system" fn window_proc(
                         "Sidebar width set to {:.0}px",
                         state.app_state.sidebar_width
                     );
+                    remember_sidebar_layout(state);
+                    handled = true;
+                }
+                if state.hscroll_drag.take().is_some() {
+                    let _ = unsafe { ReleaseCapture() };
+                    handled = true;
+                }
+                if let Some(drag) = state.text_drag.take() {
+                    let _ = unsafe { ReleaseCapture() };
+                    let copy = unsafe { GetKeyState(VK_CONTROL.0 as i32) } < 0;
+                    finish_text_drag(state, drag, copy);
+                    sync_sidebar_with_active_tab(state);
+                    handled = true;
+                }
+                if state.selection_drag {
+                    state.selection_drag = false;
+                    if let Some(tab) = state.tabs.active_tab_mut() {
+                        tab.canvas.edge_scroll = 0.0;
+                    }
+                    let _ = unsafe { ReleaseCapture() };
                     handled = true;
                 }
-                if state.table_resize.take().is_some() {
+                if let Some(resize) = state.table_resize.take() {
                     let _ = unsafe { ReleaseCapture() };
+                    commit_table_resize(state, &resize);
                     state.app_state.status_text = "Table resized".to_string();
                     handled = true;
                 }
                 if state.image_drag.take().is_some() {
                     handled = true;
                 }
+                if state.app_state.show_sidebar {
+                    handled |= state.sidebar.handle_input(&UiInputEvent::MouseUp(point));
+                    handled |= apply_pending_sidebar_intents(state);
+                }
                 if state.app_state.show_toolbar {
                     let toolbar_event = UiInputEvent::MouseUp(point);
                     handled |= state.toolbar.handle_input(&toolbar_event);
@@ -5565,6 +10745,12 @@ unsafe extern "system" fn window_proc(
         WM_LBUTTONDBLCLK => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
                 let point = point_from_lparam(lparam);
+                if state.app_state.show_sidebar && state.sidebar.begin_rename_bookmark_at(point) {
+                    state.app_state.status_text =
+                        "Rename bookmark: type a name, Enter to keep, Esc to cancel".to_string();
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
                 if state.app_state.show_tabs && state.tabs.is_empty_tab_bar_space(point) {
                     let index = open_new_blank_tab(state);
                     let title = state
@@ -5598,14 +10784,42 @@ unsafe extern "system" fn window_proc(
                     let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
                     return LRESULT(0);
                 }
+                if select_word_at(state, point) {
+                    sync_toolbar_format_from_cursor(state);
+                    let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+                    return LRESULT(0);
+                }
+            }
+            unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+        }
+        WM_TIMER if wparam.0 == FRAME_TIMER_ID => {
+            let _ = unsafe { KillTimer(Some(hwnd), FRAME_TIMER_ID) };
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                state.compositor.timer_armed = false;
+            }
+            let _ = unsafe { InvalidateRect(Some(hwnd), None, false) };
+            LRESULT(0)
+        }
+        WM_GETOBJECT => {
+            if let Some(state) = unsafe { state_from_hwnd(hwnd) }
+                && let Some(result) = state.canvas_accessibility.handle_get_object(hwnd, wparam, lparam)
+            {
+                return result;
             }
             unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
         }
         WM_DESTROY => {
             if let Some(state) = unsafe { state_from_hwnd(hwnd) } {
+                remember_window_placement(state, hwnd);
                 state.settings_dialog.force_flush();
+                let open_tabs = (0..state.tabs.tabs.len()).collect::<Vec<_>>();
+                remember_document_marks(state, &open_tabs);
+                state.canvas_accessibility.disconnect(hwnd);
+                state.drop_targets.revoke(hwnd);
+            }
+            if OPEN_WINDOWS.fetch_sub(1, Ordering::SeqCst) <= 1 {
+                unsafe { PostQuitMessage(0) };
             }
-            unsafe { PostQuitMessage(0) };
             LRESULT(0)
         }
         WM_NCDESTROY => {
@@ -0,0 +1,197 @@
+use std::mem::size_of;
+
+use windows::{
+    Win32::{
+        Foundation::{HWND, LPARAM, RECT},
+        Graphics::Gdi::{EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO},
+        UI::WindowsAndMessaging::{
+            GetWindowPlacement, GetWindowRect, IsIconic, IsZoomed, MONITORINFOF_PRIMARY,
+            WINDOWPLACEMENT,
+        },
+    },
+    core::BOOL,
+};
+
+use crate::settings::schema::WindowPlacement;
+
+/// Smallest size a saved placement restores to, so a damaged entry can't shrink the window
+/// out of reach.
+const MIN_WIDTH: i32 = 480;
+const MIN_HEIGHT: i32 = 360;
+
+/// Where a window saved at `saved` should open, given the work areas of the connected
+/// monitors with the primary first. It stays on the monitor it overlaps most, shrunk and
+/// moved until it fits; when it overlaps none, as after its monitor was disconnected, it is
+/// centered on the primary monitor instead.
+pub fn clamp_to_monitors(saved: RECT, monitors: &[RECT]) -> RECT {
+    let saved = RECT {
+        right: saved.left + (saved.right - saved.left).max(MIN_WIDTH),
+        bottom: saved.top + (saved.bottom - saved.top).max(MIN_HEIGHT),
+        ..saved
+    };
+    let overlap = |area: &RECT| {
+        let width = (saved.right.min(area.right) - saved.left.max(area.left)).max(0);
+        let height = (saved.bottom.min(area.bottom) - saved.top.max(area.top)).max(0);
+        i64::from(width) * i64::from(height)
+    };
+    let best = monitors
+        .iter()
+        .filter(|area| overlap(area) > 0)
+        .max_by_key(|area| overlap(area));
+    let (area, centered) = match (best, monitors.first()) {
+        (Some(area), _) => (area, false),
+        (None, Some(primary)) => (primary, true),
+        (None, None) => return saved,
+    };
+    let width = (saved.right - saved.left).min(area.right - area.left);
+    let height = (saved.bottom - saved.top).min(area.bottom - area.top);
+    let (left, top) = if centered {
+        (
+            area.left + (area.right - area.left - width) / 2,
+            area.top + (area.bottom - area.top - height) / 2,
+        )
+    } else {
+        (
+            saved.left.clamp(area.left, area.right - width),
+            saved.top.clamp(area.top, area.bottom - height),
+        )
+    };
+    RECT {
+        left,
+        top,
+        right: left + width,
+        bottom: top + height,
+    }
+}
+
+/// The rect `placement` describes.
+pub fn placement_rect(placement: &WindowPlacement) -> RECT {
+    RECT {
+        left: placement.x,
+        top: placement.y,
+        right: placement.x + placement.width,
+        bottom: placement.y + placement.height,
+    }
+}
+
+/// Work areas of the connected monitors, the primary monitor first.
+pub fn monitor_work_areas() -> Vec<RECT> {
+    unsafe extern "system" fn collect(
+        monitor: HMONITOR,
+        _hdc: HDC,
+        _clip: *mut RECT,
+        data: LPARAM,
+    ) -> BOOL {
+        let areas = unsafe { &mut *(data.0 as *mut Vec<RECT>) };
+        let mut info = MONITORINFO {
+            cbSize: size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if unsafe { GetMonitorInfoW(monitor, &mut info) }.as_bool() {
+            if info.dwFlags & MONITORINFOF_PRIMARY != 0 {
+                areas.insert(0, info.rcWork);
+            } else {
+                areas.push(info.rcWork);
+            }
+        }
+        true.into()
+    }
+
+    let mut areas = Vec::<RECT>::new();
+    let _ = unsafe {
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(collect),
+            LPARAM(&mut areas as *mut Vec<RECT> as isize),
+        )
+    };
+    areas
+}
+
+/// Where `hwnd` is now, for saving. A maximized window keeps the restored rect from
+/// `previous` so it comes back to the same place when unmaximized; `None` while minimized.
+pub fn current_placement(hwnd: HWND, previous: Option<WindowPlacement>) -> Option<WindowPlacement> {
+    if unsafe { IsIconic(hwnd) }.as_bool() {
+        return None;
+    }
+    let maximized = unsafe { IsZoomed(hwnd) }.as_bool();
+    let rect = match previous {
+        Some(previous) if maximized => placement_rect(&previous),
+        _ if maximized => {
+            let mut placement = WINDOWPLACEMENT {
+                length: size_of::<WINDOWPLACEMENT>() as u32,
+                ..Default::default()
+            };
+            unsafe { GetWindowPlacement(hwnd, &mut placement) }.ok()?;
+            placement.rcNormalPosition
+        }
+        _ => {
+            let mut rect = RECT::default();
+            unsafe { GetWindowRect(hwnd, &mut rect) }.ok()?;
+            rect
+        }
+    };
+    Some(WindowPlacement {
+        x: rect.left,
+        y: rect.top,
+        width: rect.right - rect.left,
+        height: rect.bottom - rect.top,
+        maximized,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: i32, top: i32, right: i32, bottom: i32) -> RECT {
+        RECT {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    #[test]
+    fn saved_rects_are_kept_on_a_visible_monitor() {
+        let primary = rect(0, 0, 1920, 1040);
+        let right = rect(1920, 0, 3840, 1040);
+        let monitors = [primary, right];
+
+        // A rect that fits stays where it was, on whichever monitor it was on.
+        let inside = rect(2000, 100, 3200, 900);
+        assert_eq!(clamp_to_monitors(inside, &monitors), inside);
+
+        // Hanging off the bottom right, it moves back in without resizing.
+        assert_eq!(
+            clamp_to_monitors(rect(3400, 800, 4600, 1600), &monitors),
+            rect(2640, 240, 3840, 1040)
+        );
+
+        // Straddling two monitors, it goes to the one holding most of it.
+        assert_eq!(
+            clamp_to_monitors(rect(1700, 100, 2900, 900), &monitors),
+            rect(1920, 100, 3120, 900)
+        );
+
+        // Larger than the monitor, it shrinks to the work area.
+        assert_eq!(
+            clamp_to_monitors(rect(-50, -50, 2000, 1200), &[primary]),
+            primary
+        );
+
+        // Its monitor was disconnected: center it on the primary monitor.
+        assert_eq!(
+            clamp_to_monitors(rect(4000, 200, 5200, 1000), &[primary]),
+            rect(360, 120, 1560, 920)
+        );
+
+        // A damaged, empty rect still opens at a usable size.
+        assert_eq!(
+            clamp_to_monitors(rect(100, 100, 100, 100), &[primary]),
+            rect(100, 100, 580, 460)
+        );
+    }
+}
@@ -0,0 +1,300 @@
+use std::mem::size_of;
+
+use windows::{
+    Win32::{
+        Foundation::ERROR_SUCCESS,
+        System::{
+            Registry::{HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RRF_RT_REG_SZ, RegGetValueW},
+            SystemInformation::{GlobalMemoryStatusEx, MEMORYSTATUSEX},
+        },
+    },
+    core::{PCWSTR, w},
+};
+
+use crate::render::{
+    d2d::{AdapterInfo, RenderDriver},
+    perf::query_process_working_set_bytes,
+};
+
+/// First build number of Windows 11, which still reports itself as "Windows 10".
+const WINDOWS_11_FIRST_BUILD: u32 = 22_000;
+const UNKNOWN: &str = "Unknown";
+
+/// The Windows release as `CurrentVersion` in the registry describes it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OsVersion {
+    pub product_name: String,
+    /// Feature update name, such as "23H2".
+    pub display_version: Option<String>,
+    pub build: Option<u32>,
+    /// Update build revision, the part after the dot in "22631.4317".
+    pub revision: Option<u32>,
+}
+
+impl OsVersion {
+    pub fn query() -> Option<Self> {
+        let key = w!("SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion");
+        Some(Self {
+            product_name: read_registry_string(key, w!("ProductName"))?,
+            display_version: read_registry_string(key, w!("DisplayVersion")),
+            build: read_registry_string(key, w!("CurrentBuildNumber"))
+                .and_then(|build| build.trim().parse().ok()),
+            revision: read_registry_dword(key, w!("UBR")),
+        })
+    }
+
+    pub fn label(&self) -> String {
+        let mut label = match self.build {
+            Some(build) if build >= WINDOWS_11_FIRST_BUILD => {
+                self.product_name.replacen("Windows 10", "Windows 11", 1)
+            }
+            _ => self.product_name.clone(),
+        };
+        if let Some(display) = &self.display_version {
+            label = format!("{label} {display}");
+        }
+        match (self.build, self.revision) {
+            (Some(build), Some(revision)) => format!("{label} (build {build}.{revision})"),
+            (Some(build), None) => format!("{label} (build {build})"),
+            _ => label,
+        }
+    }
+}
+
+/// The raw facts a snapshot is built from; `SystemProbe::query` reads them from this
+/// machine. Anything that couldn't be read is `None` and reports as unknown.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SystemProbe {
+    pub os: Option<OsVersion>,
+    pub adapter: Option<AdapterInfo>,
+    /// `None` while no renderer is running.
+    pub driver: Option<RenderDriver>,
+    pub working_set_bytes: Option<u64>,
+    pub total_memory_bytes: Option<u64>,
+}
+
+impl SystemProbe {
+    /// Reads the OS and memory figures; the GPU facts come from the running renderer.
+    pub fn query(adapter: Option<AdapterInfo>, driver: Option<RenderDriver>) -> Self {
+        Self {
+            os: OsVersion::query(),
+            adapter,
+            driver,
+            working_set_bytes: query_process_working_set_bytes(),
+            total_memory_bytes: query_total_memory_bytes(),
+        }
+    }
+}
+
+/// What Settings › About shows and copies for bug reports, one labelled line per fact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemInfo {
+    pub app_version: String,
+    pub os: String,
+    pub gpu: String,
+    pub gpu_driver: String,
+    pub renderer: String,
+    pub memory: String,
+}
+
+impl SystemInfo {
+    pub fn assemble(app_version: &str, probe: &SystemProbe) -> Self {
+        let os = probe
+            .os
+            .as_ref()
+            .map_or_else(|| UNKNOWN.to_string(), OsVersion::label);
+        let gpu = match &probe.adapter {
+            Some(adapter) if adapter.dedicated_video_memory > 0 => format!(
+                "{} ({} VRAM)",
+                adapter.description,
+                format_bytes(adapter.dedicated_video_memory)
+            ),
+            Some(adapter) => adapter.description.clone(),
+            None => UNKNOWN.to_string(),
+        };
+        let gpu_driver = probe
+            .adapter
+            .as_ref()
+            .and_then(|adapter| adapter.driver_version)
+            .map_or_else(|| UNKNOWN.to_string(), format_driver_version);
+        let renderer = probe.driver.map_or_else(
+            || "Not running".to_string(),
+            |driver| driver.label().to_string(),
+        );
+        let memory = match (probe.working_set_bytes, probe.total_memory_bytes) {
+            (Some(used), Some(total)) => {
+                format!(
+                    "{} in use of {} installed",
+                    format_bytes(used),
+                    format_bytes(total)
+                )
+            }
+            (Some(used), None) => format!("{} in use", format_bytes(used)),
+            (None, Some(total)) => format!("{} installed", format_bytes(total)),
+            (None, None) => UNKNOWN.to_string(),
+        };
+        Self {
+            app_version: app_version.to_string(),
+            os,
+            gpu,
+            gpu_driver,
+            renderer,
+            memory,
+        }
+    }
+
+    /// Plain text for pasting into an issue.
+    pub fn report(&self) -> String {
+        format!(
+            "Doco {}\nOS: {}\nGPU: {}\nGPU driver: {}\nRenderer: {}\nMemory: {}",
+            self.app_version, self.os, self.gpu, self.gpu_driver, self.renderer, self.memory
+        )
+    }
+}
+
+/// Formats a packed driver version as Windows shows it, e.g. "31.0.15.5222".
+fn format_driver_version(version: u64) -> String {
+    format!(
+        "{}.{}.{}.{}",
+        version >> 48,
+        (version >> 32) & 0xFFFF,
+        (version >> 16) & 0xFFFF,
+        version & 0xFFFF
+    )
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const MB: u64 = 1024 * 1024;
+    const GB: u64 = 1024 * MB;
+    if bytes >= GB {
+        format!("{:.1} GB", bytes as f64 / GB as f64)
+    } else {
+        format!("{} MB", bytes.div_ceil(MB))
+    }
+}
+
+fn query_total_memory_bytes() -> Option<u64> {
+    let mut status = MEMORYSTATUSEX {
+        dwLength: size_of::<MEMORYSTATUSEX>() as u32,
+        ..Default::default()
+    };
+    unsafe { GlobalMemoryStatusEx(&mut status) }
+        .ok()
+        .map(|()| status.ullTotalPhys)
+}
+
+fn read_registry_string(key: PCWSTR, value: PCWSTR) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let mut size = size_of::<[u16; 256]>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr().cast()),
+            Some(&mut size),
+        )
+    };
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+    let len = buffer.iter().position(|&unit| unit == 0).unwrap_or(0);
+    let text = String::from_utf16_lossy(&buffer[..len]);
+    (!text.trim().is_empty()).then_some(text)
+}
+
+fn read_registry_dword(key: PCWSTR, value: PCWSTR) -> Option<u32> {
+    let mut data: u32 = 0;
+    let mut size = size_of::<u32>() as u32;
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some((&mut data as *mut u32).cast()),
+            Some(&mut size),
+        )
+    };
+    (status == ERROR_SUCCESS).then_some(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn probe() -> SystemProbe {
+        SystemProbe {
+            os: Some(OsVersion {
+                product_name: "Windows 10 Pro".to_string(),
+                display_version: Some("23H2".to_string()),
+                build: Some(22631),
+                revision: Some(4317),
+            }),
+            adapter: Some(AdapterInfo {
+                description: "NVIDIA GeForce RTX 3060".to_string(),
+                vendor_id: 0x10DE,
+                dedicated_video_memory: 12 * 1024 * 1024 * 1024,
+                driver_version: Some((31 << 48) | (15 << 16) | 5222),
+            }),
+            driver: Some(RenderDriver::Hardware),
+            working_set_bytes: Some(180 * 1024 * 1024 + 1),
+            total_memory_bytes: Some(16 * 1024 * 1024 * 1024),
+        }
+    }
+
+    #[test]
+    fn snapshot_names_each_probed_fact() {
+        let info = SystemInfo::assemble("1.4.0", &probe());
+        // Windows 11 still calls itself Windows 10 in ProductName.
+        assert_eq!(info.os, "Windows 11 Pro 23H2 (build 22631.4317)");
+        assert_eq!(info.gpu, "NVIDIA GeForce RTX 3060 (12.0 GB VRAM)");
+        assert_eq!(info.gpu_driver, "31.0.15.5222");
+        assert_eq!(info.renderer, "Hardware (D3D11)");
+        assert_eq!(info.memory, "181 MB in use of 16.0 GB installed");
+        assert_eq!(
+            info.report(),
+            "Doco 1.4.0\n\
+             OS: Windows 11 Pro 23H2 (build 22631.4317)\n\
+             GPU: NVIDIA GeForce RTX 3060 (12.0 GB VRAM)\n\
+             GPU driver: 31.0.15.5222\n\
+             Renderer: Hardware (D3D11)\n\
+             Memory: 181 MB in use of 16.0 GB installed"
+        );
+    }
+
+    #[test]
+    fn snapshot_reports_missing_facts_as_unknown() {
+        let mut partial = probe();
+        partial.os = Some(OsVersion {
+            product_name: "Windows 10 Home".to_string(),
+            display_version: None,
+            build: Some(19045),
+            revision: None,
+        });
+        partial.adapter = Some(AdapterInfo {
+            description: "Microsoft Basic Render Driver".to_string(),
+            vendor_id: 0x1414,
+            dedicated_video_memory: 0,
+            driver_version: None,
+        });
+        partial.driver = Some(RenderDriver::Warp);
+        partial.total_memory_bytes = None;
+        let info = SystemInfo::assemble("1.4.0", &partial);
+        assert_eq!(info.os, "Windows 10 Home (build 19045)");
+        assert_eq!(info.gpu, "Microsoft Basic Render Driver");
+        assert_eq!(info.gpu_driver, UNKNOWN);
+        assert_eq!(info.renderer, "WARP (software)");
+        assert_eq!(info.memory, "181 MB in use");
+
+        let info = SystemInfo::assemble("1.4.0", &SystemProbe::default());
+        assert_eq!(info.os, UNKNOWN);
+        assert_eq!(info.gpu, UNKNOWN);
+        assert_eq!(info.gpu_driver, UNKNOWN);
+        assert_eq!(info.renderer, "Not running");
+        assert_eq!(info.memory, UNKNOWN);
+    }
+}
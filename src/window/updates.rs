@@ -0,0 +1,389 @@
+use std::{
+    cmp::Ordering,
+    io,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+};
+
+use serde::Deserialize;
+use windows::{
+    Win32::Networking::WinHttp::{
+        INTERNET_DEFAULT_HTTPS_PORT, WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY, WINHTTP_FLAG_SECURE,
+        WINHTTP_QUERY_FLAG_NUMBER, WINHTTP_QUERY_STATUS_CODE, WinHttpCloseHandle, WinHttpConnect,
+        WinHttpOpen, WinHttpOpenRequest, WinHttpQueryHeaders, WinHttpReadData,
+        WinHttpReceiveResponse, WinHttpSendRequest, WinHttpSetTimeouts,
+    },
+    core::{HSTRING, PCWSTR, w},
+};
+
+/// Manifests larger than this aren't a version manifest; stop reading.
+const MAX_MANIFEST_BYTES: usize = 256 * 1024;
+const TIMEOUT_MS: i32 = 10_000;
+
+/// A semantic version: `major.minor.patch`, an optional `-pre.release` and ignored
+/// `+build` metadata. A leading `v`, as release tags carry, is accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<String>,
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix(['v', 'V']).unwrap_or(text);
+        let text = text.split_once('+').map_or(text, |(version, _)| version);
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (text, None),
+        };
+
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        let (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return None;
+        };
+        let pre = match pre {
+            Some(pre) => {
+                let ids = pre.split('.').map(str::to_string).collect::<Vec<_>>();
+                if ids.iter().any(|id| id.is_empty()) {
+                    return None;
+                }
+                ids
+            }
+            None => Vec::new(),
+        };
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                // A pre-release comes before the release it leads up to.
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_pre_release(&self.pre, &other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Numeric identifiers compare as numbers and sort before alphanumeric ones; when one list
+/// is a prefix of the other, the shorter comes first.
+fn compare_pre_release(a: &[String], b: &[String]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let order = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// The latest release as the manifest describes it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateManifest {
+    pub version: String,
+    pub url: String,
+}
+
+/// A manifest in either shape: Doco's own `version`/`url`, or GitHub's release API, which
+/// names them `tag_name`/`html_url` and also sends `url` as the release's API address.
+#[derive(Deserialize)]
+struct ManifestFields {
+    version: Option<String>,
+    url: Option<String>,
+    tag_name: Option<String>,
+    html_url: Option<String>,
+}
+
+/// Reads a manifest, rejecting one without a usable version or with a release link that
+/// isn't HTTPS.
+pub fn parse_manifest(json: &str) -> Option<UpdateManifest> {
+    let fields = serde_json::from_str::<ManifestFields>(json).ok()?;
+    let manifest = UpdateManifest {
+        version: fields.tag_name.or(fields.version)?,
+        url: fields.html_url.or(fields.url)?,
+    };
+    Version::parse(&manifest.version)?;
+    manifest
+        .url
+        .to_ascii_lowercase()
+        .starts_with("https://")
+        .then_some(manifest)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UpdateOutcome {
+    UpToDate,
+    Available {
+        version: String,
+        url: String,
+    },
+    /// Offline, a server error or an unreadable manifest.
+    Failed,
+}
+
+/// Compares the manifest's release against `current`, the version this build reports.
+pub fn evaluate_manifest(current: &str, manifest: &UpdateManifest) -> UpdateOutcome {
+    let (Some(current), Some(latest)) =
+        (Version::parse(current), Version::parse(&manifest.version))
+    else {
+        return UpdateOutcome::Failed;
+    };
+    if latest > current {
+        UpdateOutcome::Available {
+            version: manifest.version.trim_start_matches(['v', 'V']).to_string(),
+            url: manifest.url.clone(),
+        }
+    } else {
+        UpdateOutcome::UpToDate
+    }
+}
+
+/// Whether `latest`, as remembered from an earlier check, is newer than `current`.
+pub fn is_newer(latest: &str, current: &str) -> bool {
+    match (Version::parse(latest), Version::parse(current)) {
+        (Some(latest), Some(current)) => latest > current,
+        _ => false,
+    }
+}
+
+/// A manifest fetch running on a worker thread. `manual` checks report every outcome;
+/// the startup check only speaks up when there is an update.
+#[derive(Debug)]
+pub struct UpdateCheck {
+    pub manual: bool,
+    rx: Receiver<UpdateOutcome>,
+}
+
+impl UpdateCheck {
+    pub fn spawn(manifest_url: String, current_version: String, manual: bool) -> Self {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let outcome = match fetch_https(&manifest_url) {
+                Ok(body) => match parse_manifest(&body) {
+                    Some(manifest) => evaluate_manifest(&current_version, &manifest),
+                    None => UpdateOutcome::Failed,
+                },
+                Err(_) => UpdateOutcome::Failed,
+            };
+            let _ = tx.send(outcome);
+        });
+        Self { manual, rx }
+    }
+
+    /// The outcome once the worker is done; `None` while it is still fetching.
+    pub fn poll(&self) -> Option<UpdateOutcome> {
+        match self.rx.try_recv() {
+            Ok(outcome) => Some(outcome),
+            Err(TryRecvError::Empty) => None,
+            Err(TryRecvError::Disconnected) => Some(UpdateOutcome::Failed),
+        }
+    }
+}
+
+/// Closes a WinHTTP handle when dropped.
+struct HttpHandle(*mut core::ffi::c_void);
+
+impl HttpHandle {
+    fn new(raw: *mut core::ffi::c_void) -> io::Result<Self> {
+        if raw.is_null() {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Self(raw))
+        }
+    }
+}
+
+impl Drop for HttpHandle {
+    fn drop(&mut self) {
+        let _ = unsafe { WinHttpCloseHandle(self.0) };
+    }
+}
+
+/// GETs `url` over HTTPS and returns the body as text; anything but a 200 is an error.
+fn fetch_https(url: &str) -> io::Result<String> {
+    let rest = url
+        .strip_prefix("https://")
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "update URL must be HTTPS"))?;
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let path = if path.is_empty() { "/" } else { path };
+
+    let agent = HSTRING::from(format!("Doco/{}", env!("CARGO_PKG_VERSION")));
+    let session = HttpHandle::new(unsafe {
+        WinHttpOpen(
+            &agent,
+            WINHTTP_ACCESS_TYPE_AUTOMATIC_PROXY,
+            PCWSTR::null(),
+            PCWSTR::null(),
+            0,
+        )
+    })?;
+    unsafe { WinHttpSetTimeouts(session.0, TIMEOUT_MS, TIMEOUT_MS, TIMEOUT_MS, TIMEOUT_MS) }
+        .map_err(io::Error::other)?;
+    let connection = HttpHandle::new(unsafe {
+        WinHttpConnect(
+            session.0,
+            &HSTRING::from(host),
+            INTERNET_DEFAULT_HTTPS_PORT,
+            0,
+        )
+    })?;
+    let request = HttpHandle::new(unsafe {
+        WinHttpOpenRequest(
+            connection.0,
+            w!("GET"),
+            &HSTRING::from(path),
+            PCWSTR::null(),
+            PCWSTR::null(),
+            std::ptr::null(),
+            WINHTTP_FLAG_SECURE,
+        )
+    })?;
+
+    unsafe {
+        WinHttpSendRequest(request.0, None, None, 0, 0, 0).map_err(io::Error::other)?;
+        WinHttpReceiveResponse(request.0, std::ptr::null_mut()).map_err(io::Error::other)?;
+    }
+
+    let mut status = 0u32;
+    let mut status_len = size_of::<u32>() as u32;
+    unsafe {
+        WinHttpQueryHeaders(
+            request.0,
+            WINHTTP_QUERY_STATUS_CODE | WINHTTP_QUERY_FLAG_NUMBER,
+            PCWSTR::null(),
+            Some(&mut status as *mut u32 as *mut core::ffi::c_void),
+            &mut status_len,
+            std::ptr::null_mut(),
+        )
+    }
+    .map_err(io::Error::other)?;
+    if status != 200 {
+        return Err(io::Error::other(format!("update server answered {status}")));
+    }
+
+    let mut body = Vec::new();
+    let mut chunk = [0u8; 8192];
+    loop {
+        let mut read = 0u32;
+        unsafe {
+            WinHttpReadData(
+                request.0,
+                chunk.as_mut_ptr() as *mut core::ffi::c_void,
+                chunk.len() as u32,
+                &mut read,
+            )
+        }
+        .map_err(io::Error::other)?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read as usize]);
+        if body.len() > MAX_MANIFEST_BYTES {
+            return Err(io::Error::other("update manifest too large"));
+        }
+    }
+    String::from_utf8(body).map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(text: &str) -> Version {
+        Version::parse(text).expect(text)
+    }
+
+    #[test]
+    fn versions_order_by_semver_precedence() {
+        assert_eq!(
+            v("v1.2.3"),
+            Version {
+                major: 1,
+                minor: 2,
+                patch: 3,
+                pre: Vec::new(),
+            }
+        );
+        assert!(v("0.10.0") > v("0.9.12"));
+        assert!(v("1.0.0") > v("1.0.0-rc.1"));
+        assert!(v("1.0.0-rc.2") > v("1.0.0-rc.1"));
+        assert!(v("1.0.0-rc.10") > v("1.0.0-rc.9"));
+        assert!(v("1.0.0-beta") > v("1.0.0-alpha.1"));
+        assert!(v("1.0.0-alpha.1") > v("1.0.0-alpha"));
+        assert!(v("1.0.0-alpha.beta") > v("1.0.0-alpha.1"));
+        assert_eq!(v("1.0.0+build.5").cmp(&v("1.0.0")), Ordering::Equal);
+
+        for bad in ["", "1.2", "1.2.3.4", "1.x.3", "1.2.3-", "1.2.3-rc..1"] {
+            assert_eq!(Version::parse(bad), None, "{bad:?}");
+        }
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(!is_newer("0.1.0", "0.1.0"));
+        assert!(!is_newer("garbage", "0.1.0"));
+    }
+
+    #[test]
+    fn manifests_parse_in_either_shape_and_compare_to_the_build() {
+        let plain = parse_manifest(
+            r#"{"version": "0.3.0", "url": "https://example.com/doco/0.3.0", "notes": "Fixes"}"#,
+        )
+        .expect("plain manifest");
+        assert_eq!(
+            evaluate_manifest("0.2.1", &plain),
+            UpdateOutcome::Available {
+                version: "0.3.0".to_string(),
+                url: "https://example.com/doco/0.3.0".to_string(),
+            }
+        );
+        assert_eq!(evaluate_manifest("0.3.0", &plain), UpdateOutcome::UpToDate);
+        assert_eq!(evaluate_manifest("1.0.0", &plain), UpdateOutcome::UpToDate);
+
+        let release = parse_manifest(
+            r#"{"url": "https://api.github.com/repos/o/r/releases/1", "tag_name": "v0.4.0-beta.1", "html_url": "https://github.com/o/r/releases/tag/v0.4.0-beta.1", "draft": false}"#,
+        )
+        .expect("release manifest");
+        assert_eq!(release.version, "v0.4.0-beta.1");
+        assert_eq!(
+            release.url,
+            "https://github.com/o/r/releases/tag/v0.4.0-beta.1"
+        );
+        assert!(matches!(
+            evaluate_manifest("0.3.0", &release),
+            UpdateOutcome::Available { ref version, .. } if version == "0.4.0-beta.1"
+        ));
+
+        assert_eq!(parse_manifest("not json"), None);
+        assert_eq!(
+            parse_manifest(r#"{"version": "soon", "url": "https://x"}"#),
+            None
+        );
+        assert_eq!(
+            parse_manifest(r#"{"version": "1.0.0", "url": "http://insecure.example"}"#),
+            None
+        );
+    }
+}